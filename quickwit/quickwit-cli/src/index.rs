@@ -42,7 +42,7 @@ use quickwit_indexing::models::IndexingStatistics;
 use quickwit_indexing::IndexingPipeline;
 use quickwit_metastore::{IndexMetadata, Split, SplitState};
 use quickwit_proto::search::{CountHits, SortField, SortOrder};
-use quickwit_rest_client::models::IngestSource;
+use quickwit_rest_client::models::{IngestFormat, IngestSource};
 use quickwit_rest_client::rest_client::{CommitType, IngestEvent};
 use quickwit_search::SearchResponseRest;
 use quickwit_serve::{ListSplitsQueryParams, SearchRequestQueryString, SortBy};
@@ -75,8 +75,22 @@ pub fn build_index_command() -> Command {
                 ])
             )
         .subcommand(
-            Command::new("clear")
+            Command::new("update")
                 .display_order(2)
+                .about("Updates an index from an index config file.")
+                .long_about("Updates the doc mapping, the search settings, the retention policy, and the indexing settings of an index from an index config file. The index URI of an index cannot be modified and the doc mapping only accepts backward compatible changes.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index")
+                        .display_order(1)
+                        .required(true),
+                    arg!(--"index-config" <INDEX_CONFIG> "Location of the index config file.")
+                        .display_order(2)
+                        .required(true),
+                ])
+            )
+        .subcommand(
+            Command::new("clear")
+                .display_order(3)
                 .alias("clr")
                 .about("Clears an index: deletes all splits and resets checkpoint.")
                 .long_about("Deletes all its splits and resets its checkpoint. This operation is destructive and cannot be undone, proceed with caution.")
@@ -88,7 +102,7 @@ pub fn build_index_command() -> Command {
             )
         .subcommand(
             Command::new("delete")
-                .display_order(3)
+                .display_order(4)
                 .alias("del")
                 .about("Deletes an index.")
                 .long_about("Deletes an index. This operation is destructive and cannot be undone, proceed with caution.")
@@ -102,7 +116,7 @@ pub fn build_index_command() -> Command {
             )
         .subcommand(
             Command::new("describe")
-                .display_order(4)
+                .display_order(5)
                 .about("Displays descriptive statistics of an index.")
                 .long_about("Displays descriptive statistics of an index. Displayed statistics are: number of published splits, number of documents, splits min/max timestamps, size of splits.")
                 .args(&[
@@ -113,20 +127,23 @@ pub fn build_index_command() -> Command {
         .subcommand(
             Command::new("list")
                 .alias("ls")
-                .display_order(5)
+                .display_order(6)
                 .about("List indexes.")
             )
         .subcommand(
             Command::new("ingest")
-                .display_order(6)
-                .about("Ingest NDJSON documents with the ingest API.")
-                .long_about("Reads NDJSON documents from a file or streamed from stdin and sends them into ingest API.")
+                .display_order(7)
+                .about("Ingest NDJSON, CSV or TSV documents with the ingest API.")
+                .long_about("Reads NDJSON, CSV or TSV documents from a file or streamed from stdin and sends them into ingest API. CSV and TSV input must start with a header row.")
                 .args(&[
                     arg!(--index <INDEX> "ID of the target index")
                         .display_order(1)
                         .required(true),
                     arg!(--"input-path" <INPUT_PATH> "Location of the input file.")
                         .required(false),
+                    arg!(--"input-format" <INPUT_FORMAT> "Format of the input data: `json`, `csv`, or `tsv`.")
+                        .default_value("json")
+                        .required(false),
                     arg!(--"batch-size-limit" <BATCH_SIZE_LIMIT> "Size limit of each submitted document batch.")
                         .required(false),
                     Arg::new("wait")
@@ -155,7 +172,7 @@ pub fn build_index_command() -> Command {
             )
         .subcommand(
             Command::new("search")
-                .display_order(7)
+                .display_order(8)
                 .about("Searches an index.")
                 .args(&[
                     arg!(--index <INDEX> "ID of the target index")
@@ -204,6 +221,13 @@ pub struct CreateIndexArgs {
     pub assume_yes: bool,
 }
 
+#[derive(Debug, Eq, PartialEq)]
+pub struct UpdateIndexArgs {
+    pub client_args: ClientArgs,
+    pub index_id: String,
+    pub index_config_uri: Uri,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub struct DescribeIndexArgs {
     pub client_args: ClientArgs,
@@ -215,6 +239,7 @@ pub struct IngestDocsArgs {
     pub client_args: ClientArgs,
     pub index_id: String,
     pub input_path_opt: Option<PathBuf>,
+    pub input_format: IngestFormat,
     pub batch_size_limit_opt: Option<ByteSize>,
     pub commit_type: CommitType,
 }
@@ -256,6 +281,7 @@ pub enum IndexCliCommand {
     Ingest(IngestDocsArgs),
     List(ListIndexesArgs),
     Search(SearchIndexArgs),
+    Update(UpdateIndexArgs),
 }
 
 impl IndexCliCommand {
@@ -278,6 +304,7 @@ impl IndexCliCommand {
             "ingest" => Self::parse_ingest_args(submatches),
             "list" => Self::parse_list_args(submatches),
             "search" => Self::parse_search_args(submatches),
+            "update" => Self::parse_update_args(submatches),
             _ => bail!("unknown index subcommand `{subcommand}`"),
         }
     }
@@ -312,6 +339,23 @@ impl IndexCliCommand {
         }))
     }
 
+    fn parse_update_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
+        let client_args = ClientArgs::parse(&mut matches)?;
+        let index_id = matches
+            .remove_one::<String>("index")
+            .expect("`index` should be a required arg.");
+        let index_config_uri = matches
+            .remove_one::<String>("index-config")
+            .map(|uri| Uri::from_str(&uri))
+            .expect("`index-config` should be a required arg.")?;
+
+        Ok(Self::Update(UpdateIndexArgs {
+            client_args,
+            index_id,
+            index_config_uri,
+        }))
+    }
+
     fn parse_describe_args(mut matches: ArgMatches) -> anyhow::Result<Self> {
         let client_args = ClientArgs::parse(&mut matches)?;
         let index_id = matches
@@ -340,7 +384,10 @@ impl IndexCliCommand {
         } else {
             None
         };
-
+        let input_format = matches
+            .remove_one::<String>("input-format")
+            .map(|input_format| IngestFormat::from_str(&input_format))
+            .expect("`input-format` should have a default value.")?;
         let batch_size_limit_opt = matches
             .remove_one::<String>("batch-size-limit")
             .map(|limit| limit.parse::<ByteSize>())
@@ -361,6 +408,7 @@ impl IndexCliCommand {
             client_args,
             index_id,
             input_path_opt,
+            input_format,
             batch_size_limit_opt,
             commit_type,
         }))
@@ -438,6 +486,7 @@ impl IndexCliCommand {
             Self::Ingest(args) => ingest_docs_cli(args).await,
             Self::List(args) => list_index_cli(args).await,
             Self::Search(args) => search_index_cli(args).await,
+            Self::Update(args) => update_index_cli(args).await,
         }
     }
 }
@@ -489,6 +538,24 @@ pub async fn create_index_cli(args: CreateIndexArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn update_index_cli(args: UpdateIndexArgs) -> anyhow::Result<()> {
+    debug!(args=?args, "update-index");
+    println!("❯ Updating index...");
+    let storage_resolver = StorageResolver::unconfigured();
+    let file_content = load_file(&storage_resolver, &args.index_config_uri).await?;
+    let index_config_str: String = std::str::from_utf8(&file_content)
+        .with_context(|| format!("Invalid utf8: `{}`", args.index_config_uri))?
+        .to_string();
+    let config_format = ConfigFormat::sniff_from_uri(&args.index_config_uri)?;
+    let qw_client = args.client_args.client();
+    qw_client
+        .indexes()
+        .update(&args.index_id, &index_config_str, config_format)
+        .await?;
+    println!("{} Index successfully updated.", "✔".color(GREEN_COLOR));
+    Ok(())
+}
+
 pub async fn list_index_cli(args: ListIndexesArgs) -> anyhow::Result<()> {
     debug!(args=?args, "list-index");
     let qw_client = args.client_args.client();
@@ -960,6 +1027,7 @@ pub async fn ingest_docs_cli(args: IngestDocsArgs) -> anyhow::Result<()> {
         .ingest(
             &args.index_id,
             ingest_source,
+            args.input_format,
             batch_size_limit_opt,
             Some(&update_progress_bar),
             args.commit_type,
@@ -93,7 +93,7 @@ mod tests {
     use quickwit_cli::cli::{build_cli, CliCommand};
     use quickwit_cli::index::{
         ClearIndexArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, IndexCliCommand,
-        IngestDocsArgs, SearchIndexArgs,
+        IngestDocsArgs, SearchIndexArgs, UpdateIndexArgs,
     };
     use quickwit_cli::split::{DescribeSplitArgs, SplitCliCommand};
     use quickwit_cli::tool::{
@@ -103,7 +103,7 @@ mod tests {
     use quickwit_cli::ClientArgs;
     use quickwit_common::uri::Uri;
     use quickwit_config::SourceInputFormat;
-    use quickwit_rest_client::models::Timeout;
+    use quickwit_rest_client::models::{IngestFormat, Timeout};
     use quickwit_rest_client::rest_client::CommitType;
     use reqwest::Url;
 
@@ -178,6 +178,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_update_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from([
+            "index",
+            "update",
+            "--index",
+            "wikipedia",
+            "--index-config",
+            "index-conf.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(matches)?;
+        let expected_index_config_uri = Uri::from_str(&format!(
+            "file://{}/index-conf.yaml",
+            std::env::current_dir().unwrap().display()
+        ))
+        .unwrap();
+        let expected_cmd = CliCommand::Index(IndexCliCommand::Update(UpdateIndexArgs {
+            client_args: ClientArgs::default(),
+            index_id: "wikipedia".to_string(),
+            index_config_uri: expected_index_config_uri,
+        }));
+        assert_eq!(command, expected_cmd);
+        Ok(())
+    }
+
     #[test]
     fn test_parse_ingest_v2_args() {
         let app = build_cli().no_binary_name(true);
@@ -192,6 +218,7 @@ mod tests {
                     client_args,
                     index_id,
                     input_path_opt: None,
+                    input_format: IngestFormat::Json,
                     batch_size_limit_opt: None,
                     commit_type: CommitType::Auto,
                 })) if &index_id == "wikipedia"
@@ -223,6 +250,7 @@ mod tests {
                     client_args,
                     index_id,
                     input_path_opt: None,
+                    input_format: IngestFormat::Json,
                     batch_size_limit_opt: None,
                     commit_type: CommitType::Auto,
                 })) if &index_id == "wikipedia"
@@ -241,6 +269,8 @@ mod tests {
             "wikipedia",
             "--batch-size-limit",
             "8MB",
+            "--input-format",
+            "csv",
             "--force",
         ])?;
         let command = CliCommand::parse_cli_args(matches)?;
@@ -251,6 +281,7 @@ mod tests {
                     client_args,
                     index_id,
                     input_path_opt: None,
+                    input_format: IngestFormat::Csv,
                     batch_size_limit_opt: Some(batch_size_limit),
                     commit_type: CommitType::Force,
                 })) if &index_id == "wikipedia"
@@ -280,6 +311,7 @@ mod tests {
                     client_args,
                     index_id,
                     input_path_opt: None,
+                    input_format: IngestFormat::Json,
                     batch_size_limit_opt: Some(batch_size_limit),
                     commit_type: CommitType::WaitFor,
                 })) if &index_id == "wikipedia"
@@ -310,6 +342,7 @@ mod tests {
                     client_args,
                     index_id,
                     input_path_opt: None,
+                    input_format: IngestFormat::Json,
                     batch_size_limit_opt: None,
                     commit_type: CommitType::Auto,
                 })) if &index_id == "wikipedia"
@@ -341,6 +374,7 @@ mod tests {
                     client_args,
                     index_id,
                     input_path_opt: None,
+                    input_format: IngestFormat::Json,
                     batch_size_limit_opt: None,
                     commit_type: CommitType::WaitFor,
                 })) if &index_id == "wikipedia"
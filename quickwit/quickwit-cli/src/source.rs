@@ -747,6 +747,9 @@ mod tests {
             source_params: SourceParams::file("path/to/file"),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         }];
         let expected_source = vec![SourceRow {
             source_id: "foo-source".to_string(),
@@ -808,6 +811,9 @@ mod tests {
                 source_params: SourceParams::stdin(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             },
             SourceConfig {
                 source_id: "bar-source".to_string(),
@@ -817,6 +823,9 @@ mod tests {
                 source_params: SourceParams::stdin(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             },
         ];
         let expected_sources = [
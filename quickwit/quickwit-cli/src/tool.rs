@@ -82,7 +82,7 @@ pub fn build_tool_command() -> Command {
                         .required(true),
                     arg!(--"input-path" <INPUT_PATH> "Location of the input file.")
                         .required(false),
-                    arg!(--"input-format" <INPUT_FORMAT> "Format of the input data.")
+                    arg!(--"input-format" <INPUT_FORMAT> "Format of the input data: `json`, `plain`, `csv`, or `tsv`.")
                         .default_value("json")
                         .required(false),
                     arg!(--overwrite "Overwrites pre-existing index.")
@@ -428,6 +428,9 @@ pub async fn local_ingest_docs_cli(args: LocalIngestDocsArgs) -> anyhow::Result<
         source_params,
         transform_config,
         input_format: args.input_format,
+        dead_letter_config: None,
+        multiline_config: None,
+        processors: Vec::new(),
     };
     run_index_checklist(
         &mut metastore,
@@ -549,6 +552,8 @@ pub async fn local_search_cli(args: LocalSearchArgs) -> anyhow::Result<()> {
         max_hits: args.max_hits as u64,
         search_fields: args.search_fields,
         snippet_fields: args.snippet_fields,
+        source_includes: None,
+        source_excludes: None,
         start_timestamp: args.start_timestamp,
         end_timestamp: args.end_timestamp,
         aggs,
@@ -614,6 +619,9 @@ pub async fn merge_cli(args: MergeArgs) -> anyhow::Result<()> {
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             },
             pipeline_uid: PipelineUid::from_u128(0u128),
         })
@@ -952,6 +960,7 @@ async fn create_empty_cluster(config: &NodeConfig) -> anyhow::Result<Cluster> {
         config.gossip_interval,
         FailureDetectorConfig::default(),
         &ChannelTransport::default(),
+        None,
     )
     .await?;
 
@@ -24,7 +24,7 @@ use chitchat::{ChitchatId, NodeState};
 use quickwit_common::sorted_iter::{KeyDiff, SortedByKeyIterator};
 use quickwit_common::tower::{make_channel, warmup_channel};
 use quickwit_proto::types::NodeId;
-use tonic::transport::Channel;
+use tonic::transport::{Channel, ClientTlsConfig};
 use tracing::{info, warn};
 
 use crate::member::NodeStateExt;
@@ -45,6 +45,7 @@ pub(crate) async fn compute_cluster_change_events(
     previous_nodes: &mut BTreeMap<NodeId, ClusterNode>,
     previous_node_states: &BTreeMap<ChitchatId, NodeState>,
     new_node_states: &BTreeMap<ChitchatId, NodeState>,
+    tls_config_opt: Option<&ClientTlsConfig>,
 ) -> Vec<ClusterChange> {
     let mut cluster_events = Vec::new();
 
@@ -61,6 +62,7 @@ pub(crate) async fn compute_cluster_change_events(
                     chitchat_id,
                     node_state,
                     previous_nodes,
+                    tls_config_opt,
                 )
                 .await;
 
@@ -109,6 +111,7 @@ async fn compute_cluster_change_events_on_added(
     new_chitchat_id: &ChitchatId,
     new_node_state: &NodeState,
     previous_nodes: &mut BTreeMap<NodeId, ClusterNode>,
+    tls_config_opt: Option<&ClientTlsConfig>,
 ) -> Vec<ClusterChange> {
     let is_self_node = self_chitchat_id == new_chitchat_id;
     let new_node_id: NodeId = new_chitchat_id.node_id.clone().into();
@@ -148,8 +151,14 @@ async fn compute_cluster_change_events_on_added(
             new_chitchat_id.node_id
         );
     }
-    let Some(new_node) =
-        try_new_node(cluster_id, new_chitchat_id, new_node_state, is_self_node).await
+    let Some(new_node) = try_new_node(
+        cluster_id,
+        new_chitchat_id,
+        new_node_state,
+        is_self_node,
+        tls_config_opt,
+    )
+    .await
     else {
         return events;
     };
@@ -276,10 +285,11 @@ async fn try_new_node(
     chitchat_id: &ChitchatId,
     node_state: &NodeState,
     is_self_node: bool,
+    tls_config_opt: Option<&ClientTlsConfig>,
 ) -> Option<ClusterNode> {
     match node_state.grpc_advertise_addr() {
         Ok(socket_addr) => {
-            let channel = make_channel(socket_addr).await;
+            let channel = make_channel(socket_addr, tls_config_opt.cloned()).await;
             try_new_node_with_channel(cluster_id, chitchat_id, node_state, channel, is_self_node)
         }
         Err(error) => {
@@ -393,6 +403,7 @@ mod tests {
                 &new_chitchat_id,
                 &new_node_state,
                 &mut previous_nodes,
+                None,
             )
             .await;
             assert!(events.is_empty());
@@ -415,6 +426,7 @@ mod tests {
                 &new_chitchat_id,
                 &new_node_state,
                 &mut previous_nodes,
+                None,
             )
             .await;
             assert!(events.is_empty());
@@ -443,6 +455,7 @@ mod tests {
                 &new_chitchat_id,
                 &new_node_state,
                 &mut previous_nodes,
+                None,
             )
             .await;
 
@@ -465,6 +478,7 @@ mod tests {
                 &rejoined_chitchat_id,
                 &new_node_state,
                 &mut previous_nodes,
+                None,
             )
             .await;
             assert_eq!(events.len(), 2);
@@ -493,6 +507,7 @@ mod tests {
                 &new_chitchat_id,
                 &new_node_state,
                 &mut previous_nodes,
+                None,
             )
             .await;
             assert!(events.is_empty());
@@ -517,6 +532,7 @@ mod tests {
                 &new_chitchat_id,
                 &new_node_state,
                 &mut previous_nodes,
+                None,
             )
             .await;
             assert_eq!(events.len(), 1);
@@ -822,6 +838,7 @@ mod tests {
                 &mut previous_nodes,
                 &previous_node_states,
                 &new_node_states,
+                None,
             )
             .await;
             assert!(events.is_empty());
@@ -851,6 +868,7 @@ mod tests {
                 &mut previous_nodes,
                 &previous_node_states,
                 &new_node_states,
+                None,
             )
             .await;
             assert!(events.is_empty());
@@ -868,6 +886,7 @@ mod tests {
                 &mut previous_nodes,
                 &previous_node_states,
                 &new_node_states,
+                None,
             )
             .await;
             assert_eq!(events.len(), 1);
@@ -882,6 +901,7 @@ mod tests {
                 &mut previous_nodes,
                 &new_node_states,
                 &new_node_states,
+                None,
             )
             .await;
             assert_eq!(events.len(), 0);
@@ -914,6 +934,7 @@ mod tests {
                 &mut previous_nodes,
                 &previous_node_states,
                 &new_node_states,
+                None,
             )
             .await;
             assert_eq!(events.len(), 1);
@@ -933,6 +954,7 @@ mod tests {
                 &mut previous_nodes,
                 &previous_node_states,
                 &new_node_states,
+                None,
             )
             .await;
             assert_eq!(events.len(), 1);
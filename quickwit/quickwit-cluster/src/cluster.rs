@@ -39,6 +39,7 @@ use tokio::sync::{mpsc, watch, Mutex, RwLock};
 use tokio::time::timeout;
 use tokio_stream::wrappers::{UnboundedReceiverStream, WatchStream};
 use tokio_stream::StreamExt;
+use tonic::transport::ClientTlsConfig;
 use tracing::{info, warn};
 
 use crate::change::{compute_cluster_change_events, ClusterChange};
@@ -114,6 +115,7 @@ impl Cluster {
         gossip_interval: Duration,
         failure_detector_config: FailureDetectorConfig,
         transport: &dyn Transport,
+        client_grpc_tls_config_opt: Option<ClientTlsConfig>,
     ) -> anyhow::Result<Self> {
         info!(
             cluster_id=%cluster_id,
@@ -178,7 +180,7 @@ impl Cluster {
             gossip_interval,
             inner: Arc::new(RwLock::new(inner)),
         };
-        spawn_ready_nodes_change_stream_task(cluster.clone()).await;
+        spawn_ready_nodes_change_stream_task(cluster.clone(), client_grpc_tls_config_opt).await;
         Ok(cluster)
     }
 
@@ -500,7 +502,10 @@ fn chitchat_kv_to_indexing_task(key: &str, value: &str) -> Option<IndexingTask>
     })
 }
 
-async fn spawn_ready_nodes_change_stream_task(cluster: Cluster) {
+async fn spawn_ready_nodes_change_stream_task(
+    cluster: Cluster,
+    client_grpc_tls_config_opt: Option<ClientTlsConfig>,
+) {
     let cluster_guard = cluster.inner.read().await;
     let cluster_id = cluster_guard.cluster_id.clone();
     let self_chitchat_id = cluster_guard.self_chitchat_id.clone();
@@ -526,6 +531,7 @@ async fn spawn_ready_nodes_change_stream_task(cluster: Cluster) {
                 previous_live_nodes,
                 &previous_live_node_states,
                 &new_live_node_states,
+                client_grpc_tls_config_opt.as_ref(),
             )
             .await;
             if !events.is_empty() {
@@ -642,6 +648,7 @@ pub async fn create_cluster_for_test_with_id(
         Duration::from_millis(25),
         failure_detector_config,
         transport,
+        None,
     )
     .await?;
     cluster.set_self_node_readiness(self_node_readiness).await;
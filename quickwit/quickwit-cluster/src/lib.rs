@@ -26,7 +26,9 @@ pub mod metrics;
 mod node;
 
 use std::net::SocketAddr;
+use std::path::Path;
 
+use anyhow::Context;
 use async_trait::async_trait;
 pub use chitchat::transport::ChannelTransport;
 use chitchat::transport::{Socket, Transport, UdpTransport};
@@ -34,10 +36,11 @@ use chitchat::ChitchatMessage;
 pub use chitchat::{FailureDetectorConfig, KeyChangeEvent, ListenerHandle};
 use quickwit_common::metrics::IntCounter;
 use quickwit_config::service::QuickwitService;
-use quickwit_config::NodeConfig;
+use quickwit_config::{NodeConfig, TlsConfig};
 use quickwit_proto::indexing::CpuCapacity;
 use quickwit_proto::types::NodeId;
 use time::OffsetDateTime;
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity};
 
 pub use crate::change::ClusterChange;
 #[cfg(any(test, feature = "testsuite"))]
@@ -127,6 +130,12 @@ pub async fn start_cluster_service(node_config: &NodeConfig) -> anyhow::Result<C
         indexing_tasks,
         indexing_cpu_capacity,
     };
+    let client_grpc_tls_config_opt = node_config
+        .grpc_config
+        .tls
+        .as_ref()
+        .map(make_client_grpc_tls_config)
+        .transpose()?;
     let cluster = Cluster::join(
         cluster_id,
         self_node,
@@ -135,6 +144,7 @@ pub async fn start_cluster_service(node_config: &NodeConfig) -> anyhow::Result<C
         node_config.gossip_interval,
         FailureDetectorConfig::default(),
         &CountingUdpTransport,
+        client_grpc_tls_config_opt,
     )
     .await?;
     if node_config
@@ -147,3 +157,31 @@ pub async fn start_cluster_service(node_config: &NodeConfig) -> anyhow::Result<C
     }
     Ok(cluster)
 }
+
+/// Builds the TLS config used by the node to connect to the gRPC servers of the other nodes of
+/// the cluster. The node presents its own certificate so that the other nodes can require mutual
+/// TLS.
+fn make_client_grpc_tls_config(tls_config: &TlsConfig) -> anyhow::Result<ClientTlsConfig> {
+    let cert_pem = read_pem_file(&tls_config.cert_path)?;
+    let key_pem = read_pem_file(&tls_config.key_path)?;
+    let mut client_tls_config =
+        ClientTlsConfig::new().identity(Identity::from_pem(cert_pem, key_pem));
+
+    if let Some(ca_path) = &tls_config.ca_path {
+        let ca_pem = read_pem_file(ca_path)?;
+        client_tls_config = client_tls_config.ca_certificate(Certificate::from_pem(ca_pem));
+    }
+    if let Some(expected_server_name) = &tls_config.expected_server_name {
+        client_tls_config = client_tls_config.domain_name(expected_server_name);
+    }
+    // Building an endpoint parses the certificates and the key, so invalid PEM files are reported
+    // at startup rather than when connecting to the other nodes.
+    Endpoint::from_static("https://127.0.0.1")
+        .tls_config(client_tls_config.clone())
+        .context("failed to build gRPC client TLS config")?;
+    Ok(client_tls_config)
+}
+
+fn read_pem_file(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("failed to read PEM file `{}`", path.display()))
+}
@@ -0,0 +1,432 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decompression of files and request bodies.
+//!
+//! The codec is selected either from a file extension or from the value of a `Content-Encoding`
+//! HTTP header.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use bytes::Bytes;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use thiserror::Error;
+use tokio::io::AsyncRead;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+
+/// Size of the chunks sent by the decompression task to the async reader.
+const DECOMPRESSED_CHUNK_NUM_BYTES: usize = 64 * 1024;
+
+/// Stream identifier chunk opening a snappy framed stream.
+const SNAPPY_STREAM_IDENTIFIER: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CompressionCodec {
+    Gzip,
+    Zstd,
+    Bzip2,
+    /// LZ4 frame format, as produced by the `lz4` CLI.
+    Lz4,
+    /// Snappy framed format, or a single raw snappy block.
+    Snappy,
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "unsupported content encoding `{0}`, supported encodings are `gzip`, `zstd`, `bzip2`, `lz4` \
+     and `snappy`"
+)]
+pub struct UnsupportedEncoding(pub String);
+
+#[derive(Debug, Error)]
+pub enum DecompressionError {
+    #[error("decompressed content exceeds the limit of {max_num_bytes} bytes")]
+    TooLarge { max_num_bytes: usize },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl CompressionCodec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Bzip2 => "bzip2",
+            Self::Lz4 => "lz4",
+            Self::Snappy => "snappy",
+        }
+    }
+
+    /// Returns the codec associated with the extension of `path`, if any.
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension().and_then(OsStr::to_str)?;
+
+        match extension.to_ascii_lowercase().as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "bz2" => Some(Self::Bzip2),
+            "lz4" => Some(Self::Lz4),
+            "sz" | "snappy" => Some(Self::Snappy),
+            _ => None,
+        }
+    }
+
+    /// Parses the value of a `Content-Encoding` header. Returns `None` for the `identity`
+    /// encoding.
+    pub fn from_content_encoding(
+        content_encoding: &str,
+    ) -> Result<Option<Self>, UnsupportedEncoding> {
+        let content_encoding = content_encoding.trim();
+
+        match content_encoding.to_ascii_lowercase().as_str() {
+            "" | "identity" => Ok(None),
+            "gzip" | "x-gzip" => Ok(Some(Self::Gzip)),
+            "zstd" => Ok(Some(Self::Zstd)),
+            "bzip2" | "x-bzip2" => Ok(Some(Self::Bzip2)),
+            "lz4" | "x-lz4" => Ok(Some(Self::Lz4)),
+            "snappy" | "x-snappy" => Ok(Some(Self::Snappy)),
+            _ => Err(UnsupportedEncoding(content_encoding.to_string())),
+        }
+    }
+
+    /// Wraps `reader` into a blocking reader yielding its decompressed content. Concatenated
+    /// gzip members, bzip2 streams and zstd frames are decompressed one after the other.
+    pub fn decoder<'a, R: Read + 'a>(self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        let decoder: Box<dyn Read + 'a> = match self {
+            Self::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            Self::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+            Self::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(reader)),
+            Self::Snappy => snappy_decoder(reader)?,
+        };
+        Ok(decoder)
+    }
+
+    /// Decompresses `compressed` in the calling thread. Decompression stops and fails as soon as
+    /// the decompressed content exceeds `max_num_bytes`.
+    pub fn decompress(
+        self,
+        compressed: &[u8],
+        max_num_bytes: usize,
+    ) -> Result<Vec<u8>, DecompressionError> {
+        // Raw snappy blocks are decompressed in one go, so their length, stored in their header,
+        // is checked beforehand.
+        if self == Self::Snappy && !compressed.starts_with(SNAPPY_STREAM_IDENTIFIER) {
+            let num_bytes = snap::raw::decompress_len(compressed)
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            if num_bytes > max_num_bytes {
+                return Err(DecompressionError::TooLarge { max_num_bytes });
+            }
+        }
+        let mut decompressed = Vec::new();
+        self.decoder(compressed)?
+            .take(max_num_bytes as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+
+        if decompressed.len() > max_num_bytes {
+            return Err(DecompressionError::TooLarge { max_num_bytes });
+        }
+        Ok(decompressed)
+    }
+}
+
+impl fmt::Display for CompressionCodec {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.as_str())
+    }
+}
+
+/// Snappy payloads come in two flavors: the framed format, used for files and streams, and raw
+/// blocks, used for instance by Prometheus remote write. The framed format is detected from its
+/// stream identifier. Raw blocks cannot be decompressed incrementally, so they are read entirely
+/// in memory.
+fn snappy_decoder<'a, R: Read + 'a>(mut reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    let mut header = Vec::with_capacity(SNAPPY_STREAM_IDENTIFIER.len());
+    (&mut reader)
+        .take(SNAPPY_STREAM_IDENTIFIER.len() as u64)
+        .read_to_end(&mut header)?;
+
+    if header == SNAPPY_STREAM_IDENTIFIER {
+        let reader = Cursor::new(header).chain(reader);
+        return Ok(Box::new(snap::read::FrameDecoder::new(reader)));
+    }
+    let mut compressed = header;
+    reader.read_to_end(&mut compressed)?;
+
+    let decompressed = snap::raw::Decoder::new()
+        .decompress_vec(&compressed)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    Ok(Box::new(Cursor::new(decompressed)))
+}
+
+/// Decompresses `compressed` on tokio's blocking thread pool, failing if the decompressed content
+/// exceeds `max_num_bytes`.
+pub async fn decompress_bytes(
+    codec: CompressionCodec,
+    compressed: Bytes,
+    max_num_bytes: usize,
+) -> Result<Bytes, DecompressionError> {
+    tokio::task::spawn_blocking(move || {
+        codec
+            .decompress(&compressed, max_num_bytes)
+            .map(Bytes::from)
+    })
+    .await
+    .map_err(|join_error| io::Error::new(io::ErrorKind::Other, join_error))?
+}
+
+/// Wraps `reader` into an async reader yielding its decompressed content.
+///
+/// Decompression runs in a task of tokio's blocking thread pool that lives until the content is
+/// fully decompressed or the returned reader is dropped. Decompression errors are surfaced to the
+/// returned reader.
+pub fn decompress_reader<R>(
+    codec: CompressionCodec,
+    reader: R,
+) -> Box<dyn AsyncRead + Send + Unpin>
+where
+    R: AsyncRead + Send + Unpin + 'static,
+{
+    let (chunk_tx, chunk_rx) = mpsc::channel::<io::Result<Bytes>>(2);
+    let sync_reader = SyncIoBridge::new(reader);
+
+    tokio::task::spawn_blocking(move || {
+        let mut decoder = match codec.decoder(sync_reader) {
+            Ok(decoder) => decoder,
+            Err(error) => {
+                let _ = chunk_tx.blocking_send(Err(error));
+                return;
+            }
+        };
+        loop {
+            let mut chunk = vec![0u8; DECOMPRESSED_CHUNK_NUM_BYTES];
+
+            match decoder.read(&mut chunk) {
+                Ok(0) => return,
+                Ok(num_bytes) => {
+                    chunk.truncate(num_bytes);
+
+                    if chunk_tx.blocking_send(Ok(Bytes::from(chunk))).is_err() {
+                        // The reader was dropped.
+                        return;
+                    }
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) => {
+                    let _ = chunk_tx.blocking_send(Err(error));
+                    return;
+                }
+            }
+        }
+    });
+    Box::new(StreamReader::new(ReceiverStream::new(chunk_rx)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    const PAYLOAD: &[u8] = b"{\"body\": \"foo\"}\n{\"body\": \"bar\"}\n";
+
+    fn compress(codec: CompressionCodec, payload: &[u8]) -> Vec<u8> {
+        match codec {
+            CompressionCodec::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(payload).unwrap();
+                encoder.finish().unwrap()
+            }
+            CompressionCodec::Zstd => zstd::encode_all(payload, 0).unwrap(),
+            CompressionCodec::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+                encoder.write_all(payload).unwrap();
+                encoder.finish().unwrap()
+            }
+            CompressionCodec::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(payload).unwrap();
+                encoder.finish().unwrap()
+            }
+            CompressionCodec::Snappy => {
+                let mut encoder = snap::write::FrameEncoder::new(Vec::new());
+                encoder.write_all(payload).unwrap();
+                encoder.into_inner().unwrap()
+            }
+        }
+    }
+
+    const CODECS: [CompressionCodec; 5] = [
+        CompressionCodec::Gzip,
+        CompressionCodec::Zstd,
+        CompressionCodec::Bzip2,
+        CompressionCodec::Lz4,
+        CompressionCodec::Snappy,
+    ];
+
+    #[test]
+    fn test_compression_codec_from_extension() {
+        assert_eq!(
+            CompressionCodec::from_extension(Path::new("logs/a.json.gz")),
+            Some(CompressionCodec::Gzip)
+        );
+        assert_eq!(
+            CompressionCodec::from_extension(Path::new("logs/a.json.zst")),
+            Some(CompressionCodec::Zstd)
+        );
+        assert_eq!(
+            CompressionCodec::from_extension(Path::new("logs/a.json.bz2")),
+            Some(CompressionCodec::Bzip2)
+        );
+        assert_eq!(
+            CompressionCodec::from_extension(Path::new("logs/a.json.LZ4")),
+            Some(CompressionCodec::Lz4)
+        );
+        assert_eq!(
+            CompressionCodec::from_extension(Path::new("logs/a.json.sz")),
+            Some(CompressionCodec::Snappy)
+        );
+        assert_eq!(
+            CompressionCodec::from_extension(Path::new("logs/a.json")),
+            None
+        );
+        assert_eq!(CompressionCodec::from_extension(Path::new("logs")), None);
+    }
+
+    #[test]
+    fn test_compression_codec_from_content_encoding() {
+        assert_eq!(
+            CompressionCodec::from_content_encoding("identity").unwrap(),
+            None
+        );
+        assert_eq!(
+            CompressionCodec::from_content_encoding("x-gzip").unwrap(),
+            Some(CompressionCodec::Gzip)
+        );
+        for codec in CODECS {
+            assert_eq!(
+                CompressionCodec::from_content_encoding(codec.as_str()).unwrap(),
+                Some(codec)
+            );
+        }
+        let error = CompressionCodec::from_content_encoding("br").unwrap_err();
+        assert_eq!(error.0, "br");
+    }
+
+    #[test]
+    fn test_compression_codec_decompress() {
+        for codec in CODECS {
+            let compressed = compress(codec, PAYLOAD);
+            let decompressed = codec.decompress(&compressed, PAYLOAD.len()).unwrap();
+            assert_eq!(decompressed, PAYLOAD, "codec: {codec}");
+        }
+        CompressionCodec::Zstd
+            .decompress(PAYLOAD, 1024)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn test_compression_codec_decompress_concatenated_gzip_members() {
+        let mut compressed = compress(CompressionCodec::Gzip, b"foo\n");
+        compressed.extend(compress(CompressionCodec::Gzip, b"bar\n"));
+
+        let decompressed = CompressionCodec::Gzip
+            .decompress(&compressed, 1024)
+            .unwrap();
+        assert_eq!(decompressed, b"foo\nbar\n");
+    }
+
+    #[test]
+    fn test_compression_codec_decompress_raw_snappy() {
+        let compressed = snap::raw::Encoder::new().compress_vec(PAYLOAD).unwrap();
+        let decompressed = CompressionCodec::Snappy
+            .decompress(&compressed, PAYLOAD.len())
+            .unwrap();
+        assert_eq!(decompressed, PAYLOAD);
+    }
+
+    #[test]
+    fn test_compression_codec_decompress_rejects_content_exceeding_limit() {
+        let payload = PAYLOAD.repeat(1_000);
+
+        for codec in CODECS {
+            let compressed = compress(codec, &payload);
+            let error = codec
+                .decompress(&compressed, payload.len() - 1)
+                .unwrap_err();
+            assert!(
+                matches!(error, DecompressionError::TooLarge { .. }),
+                "codec: {codec}"
+            );
+        }
+        let compressed = snap::raw::Encoder::new().compress_vec(&payload).unwrap();
+        let error = CompressionCodec::Snappy
+            .decompress(&compressed, payload.len() - 1)
+            .unwrap_err();
+        assert!(matches!(error, DecompressionError::TooLarge { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_decompress_bytes() {
+        let compressed = Bytes::from(compress(CompressionCodec::Lz4, PAYLOAD));
+        let decompressed = decompress_bytes(CompressionCodec::Lz4, compressed.clone(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(decompressed, PAYLOAD);
+
+        let error = decompress_bytes(CompressionCodec::Lz4, compressed, PAYLOAD.len() - 1)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DecompressionError::TooLarge { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_decompress_reader() {
+        let payload = PAYLOAD.repeat(10_000);
+
+        for codec in CODECS {
+            let compressed = compress(codec, &payload);
+            let mut reader = decompress_reader(codec, Cursor::new(compressed));
+
+            let mut decompressed = Vec::new();
+            reader.read_to_end(&mut decompressed).await.unwrap();
+            assert_eq!(decompressed, payload, "codec: {codec}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decompress_reader_surfaces_errors() {
+        let mut compressed = compress(CompressionCodec::Zstd, PAYLOAD);
+        compressed.truncate(compressed.len() / 2);
+        let mut reader = decompress_reader(CompressionCodec::Zstd, Cursor::new(compressed));
+
+        let mut decompressed = Vec::new();
+        reader.read_to_end(&mut decompressed).await.unwrap_err();
+    }
+}
@@ -22,6 +22,7 @@
 mod coolid;
 
 pub mod binary_heap;
+pub mod compression;
 pub mod fs;
 pub mod io;
 mod kill_switch;
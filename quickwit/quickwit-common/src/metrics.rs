@@ -79,6 +79,11 @@ impl<const N: usize> IntGaugeVec<N> {
     pub fn with_label_values(&self, label_values: [&str; N]) -> IntGauge {
         self.underlying.with_label_values(&label_values)
     }
+
+    /// Removes the gauge associated with the label values, if any.
+    pub fn remove_label_values(&self, label_values: [&str; N]) {
+        let _ = self.underlying.remove_label_values(&label_values);
+    }
 }
 
 pub fn new_counter(name: &str, description: &str, namespace: &str) -> IntCounter {
@@ -31,7 +31,7 @@ use futures::{Stream, StreamExt};
 use http::Uri;
 use tokio::sync::{mpsc, watch};
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tonic::transport::{Channel, Endpoint};
+use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
 use tower::balance::p2c::Balance;
 use tower::buffer::Buffer;
 use tower::discover::Change as TowerChange;
@@ -184,20 +184,34 @@ where K: Hash + Eq + Clone + Send + Sync + 'static
     }
 }
 
-/// Creates a channel from a socket address.
+/// Creates a channel from a socket address. The channel uses TLS if a TLS config is provided.
 ///
 /// The function is marked as `async` because it requires an executor (`connect_lazy`).
-pub async fn make_channel(socket_addr: SocketAddr) -> Channel {
+pub async fn make_channel(
+    socket_addr: SocketAddr,
+    tls_config_opt: Option<ClientTlsConfig>,
+) -> Channel {
+    let scheme = if tls_config_opt.is_some() {
+        "https"
+    } else {
+        "http"
+    };
     let uri = Uri::builder()
-        .scheme("http")
+        .scheme(scheme)
         .authority(socket_addr.to_string())
         .path_and_query("/")
         .build()
         .expect("The provided arguments should be valid.");
-    Endpoint::from(uri)
+    let mut endpoint = Endpoint::from(uri)
         .connect_timeout(Duration::from_secs(5))
-        .timeout(Duration::from_secs(30))
-        .connect_lazy()
+        .timeout(Duration::from_secs(30));
+
+    if let Some(tls_config) = tls_config_opt {
+        endpoint = endpoint
+            .tls_config(tls_config)
+            .expect("The TLS config should have been validated at startup.");
+    }
+    endpoint.connect_lazy()
 }
 
 /// Forces a channel to initiate the underlying HTTP connection. Calling this function only makes
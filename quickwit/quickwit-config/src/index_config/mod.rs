@@ -25,7 +25,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{ensure, Context};
+use anyhow::{bail, ensure, Context};
 use bytesize::ByteSize;
 use chrono::Utc;
 use cron::Schedule;
@@ -37,6 +37,7 @@ use quickwit_doc_mapper::{
 };
 use quickwit_proto::types::IndexId;
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 pub use serialize::load_index_config_from_user_config;
 use tracing::warn;
 
@@ -562,6 +563,106 @@ pub(super) fn validate_index_config(
     Ok(())
 }
 
+/// Checks that `new_doc_mapping` is a backward compatible evolution of `current_doc_mapping`, i.e.
+/// that splits built with the current doc mapping can be searched alongside splits built with the
+/// new one.
+///
+/// The accepted changes are:
+/// - adding fields, including in object fields;
+/// - making an existing field fast or stored;
+/// - updating field descriptions;
+/// - adding tokenizers;
+/// - updating `store_source` and `max_num_partitions`.
+pub fn validate_doc_mapping_update(
+    current_doc_mapping: &DocMapping,
+    new_doc_mapping: &DocMapping,
+) -> anyhow::Result<()> {
+    ensure!(
+        current_doc_mapping.timestamp_field == new_doc_mapping.timestamp_field,
+        "updating the timestamp field is not allowed"
+    );
+    ensure!(
+        current_doc_mapping.tag_fields == new_doc_mapping.tag_fields,
+        "updating the tag fields is not allowed"
+    );
+    ensure!(
+        current_doc_mapping.partition_key == new_doc_mapping.partition_key,
+        "updating the partition key is not allowed"
+    );
+    ensure!(
+        current_doc_mapping.mode == new_doc_mapping.mode,
+        "updating the doc mapping mode is not allowed"
+    );
+    ensure!(
+        current_doc_mapping.index_field_presence == new_doc_mapping.index_field_presence,
+        "updating `index_field_presence` is not allowed"
+    );
+    for tokenizer in &current_doc_mapping.tokenizers {
+        ensure!(
+            new_doc_mapping.tokenizers.contains(tokenizer),
+            "updating or removing tokenizer `{}` is not allowed",
+            tokenizer.name
+        );
+    }
+    let current_field_mappings = serde_json::to_value(&current_doc_mapping.field_mappings)?;
+    let new_field_mappings = serde_json::to_value(&new_doc_mapping.field_mappings)?;
+    validate_field_mappings_update("", &current_field_mappings, &new_field_mappings)
+}
+
+/// Compares the serialized field mappings of two doc mappings. Field mappings are compared in
+/// their JSON form so that the check does not need to be kept in sync with every field type.
+fn validate_field_mappings_update(
+    field_path_prefix: &str,
+    current_field_mappings: &JsonValue,
+    new_field_mappings: &JsonValue,
+) -> anyhow::Result<()> {
+    let (Some(current_field_mappings), Some(new_field_mappings)) = (
+        current_field_mappings.as_array(),
+        new_field_mappings.as_array(),
+    ) else {
+        bail!("field mappings should be serialized as JSON arrays");
+    };
+    for current_field_mapping in current_field_mappings {
+        let field_name = current_field_mapping["name"].as_str().unwrap_or_default();
+        let field_path = format!("{field_path_prefix}{field_name}");
+        let new_field_mapping = new_field_mappings
+            .iter()
+            .find(|new_field_mapping| new_field_mapping["name"] == current_field_mapping["name"])
+            .with_context(|| format!("removing field `{field_path}` is not allowed"))?;
+        let (Some(current_params), Some(new_params)) = (
+            current_field_mapping.as_object(),
+            new_field_mapping.as_object(),
+        ) else {
+            bail!("field mappings should be serialized as JSON objects");
+        };
+        let param_keys: BTreeSet<&String> =
+            current_params.keys().chain(new_params.keys()).collect();
+
+        for param_key in param_keys {
+            let current_value = current_params.get(param_key).unwrap_or(&JsonValue::Null);
+            let new_value = new_params.get(param_key).unwrap_or(&JsonValue::Null);
+
+            match param_key.as_str() {
+                "name" | "description" => {}
+                "field_mappings" => validate_field_mappings_update(
+                    &format!("{field_path}."),
+                    current_value,
+                    new_value,
+                )?,
+                // Fields can be made fast or stored: older splits simply do not have the
+                // corresponding data.
+                "fast" | "stored"
+                    if current_value.is_null() || *current_value == JsonValue::Bool(false) => {}
+                _ => ensure!(
+                    current_value == new_value,
+                    "updating parameter `{param_key}` of field `{field_path}` is not allowed"
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -944,4 +1045,125 @@ mod tests {
         schedule_test_helper_fn("monthly");
         schedule_test_helper_fn("* * * ? * ?");
     }
+
+    #[test]
+    fn test_validate_doc_mapping_update() {
+        let current_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: timestamp
+                type: datetime
+                fast: true
+              - name: body
+                type: text
+                stored: false
+              - name: attributes
+                type: object
+                field_mappings:
+                  - name: service
+                    type: text
+                    tokenizer: raw
+            timestamp_field: timestamp
+            "#,
+        )
+        .unwrap();
+        validate_doc_mapping_update(&current_doc_mapping, &current_doc_mapping).unwrap();
+
+        let new_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: timestamp
+                type: datetime
+                fast: true
+              - name: body
+                type: text
+                stored: true
+                fast: true
+                description: Log message
+              - name: severity
+                type: text
+                tokenizer: raw
+              - name: attributes
+                type: object
+                field_mappings:
+                  - name: service
+                    type: text
+                    tokenizer: raw
+                    fast: true
+                  - name: host
+                    type: text
+                    tokenizer: raw
+            timestamp_field: timestamp
+            store_source: true
+            "#,
+        )
+        .unwrap();
+        validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping).unwrap();
+
+        // Removing a field is not allowed.
+        let mut new_doc_mapping = current_doc_mapping.clone();
+        new_doc_mapping.field_mappings.remove(1);
+        let error = validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "removing field `body` is not allowed");
+
+        // Changing the tokenizer of a nested field is not allowed.
+        let new_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: timestamp
+                type: datetime
+                fast: true
+              - name: body
+                type: text
+                stored: false
+              - name: attributes
+                type: object
+                field_mappings:
+                  - name: service
+                    type: text
+                    tokenizer: default
+            timestamp_field: timestamp
+            "#,
+        )
+        .unwrap();
+        let error = validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "updating parameter `tokenizer` of field `attributes.service` is not allowed"
+        );
+
+        // Making a field no longer fast is not allowed.
+        let new_doc_mapping: DocMapping = serde_yaml::from_str(
+            r#"
+            field_mappings:
+              - name: timestamp
+                type: datetime
+                fast: false
+              - name: body
+                type: text
+                stored: false
+              - name: attributes
+                type: object
+                field_mappings:
+                  - name: service
+                    type: text
+                    tokenizer: raw
+            timestamp_field: timestamp
+            "#,
+        )
+        .unwrap();
+        validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping).unwrap_err();
+
+        // Changing the timestamp field is not allowed.
+        let mut new_doc_mapping = current_doc_mapping.clone();
+        new_doc_mapping.timestamp_field = None;
+        let error = validate_doc_mapping_update(&current_doc_mapping, &new_doc_mapping)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "updating the timestamp field is not allowed");
+    }
 }
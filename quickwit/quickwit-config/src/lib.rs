@@ -46,17 +46,23 @@ pub use cluster_config::ClusterConfig;
 // See #2048
 use index_config::serialize::{IndexConfigV0_7, VersionedIndexConfig};
 pub use index_config::{
-    build_doc_mapper, load_index_config_from_user_config, DocMapping, IndexConfig,
-    IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
+    build_doc_mapper, load_index_config_from_user_config, validate_doc_mapping_update, DocMapping,
+    IndexConfig, IndexingResources, IndexingSettings, RetentionPolicy, SearchSettings,
 };
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
 pub use source_config::{
-    load_source_config_from_user_config, FileSourceParams, GcpPubSubSourceParams,
-    KafkaSourceParams, KinesisSourceParams, PulsarSourceAuth, PulsarSourceParams, RegionOrEndpoint,
-    SourceConfig, SourceInputFormat, SourceParams, TransformConfig, VecSourceParams,
-    VoidSourceParams, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
+    load_source_config_from_user_config, AmqpSourceParams, AvroInputFormatParams, CsvColumnType,
+    CsvInputFormatParams, DateProcessorConfig, DeadLetterConfig, DissectPattern,
+    DissectProcessorConfig, DropIfProcessorConfig, FileSourceParams, GcpPubSubSourceParams,
+    GrokPattern, GrokProcessorConfig, IndexDeadLetterParams, KafkaDeadLetterParams,
+    KafkaSourceParams, KinesisSourceParams, KvProcessorConfig, MqttSourceParams, MultilineConfig,
+    ProcessorConfig, ProtobufInputFormatParams, PulsarSourceAuth, PulsarSourceParams,
+    RegionOrEndpoint, RemoveProcessorConfig, RenameProcessorConfig, SourceConfig,
+    SourceInputFormat, SourceParams, StorageDeadLetterParams, StoragePrefixCompression,
+    StoragePrefixSourceParams, SyslogFormat, SyslogProtocol, SyslogSourceParams, TransformConfig,
+    VecSourceParams, VoidSourceParams, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
 };
 use tracing::warn;
 
@@ -69,8 +75,9 @@ pub use crate::metastore_config::{
     MetastoreBackend, MetastoreConfig, MetastoreConfigs, PostgresMetastoreConfig,
 };
 pub use crate::node_config::{
-    enable_ingest_v2, IndexerConfig, IngestApiConfig, JaegerConfig, NodeConfig, SearcherConfig,
-    SplitCacheLimits, DEFAULT_QW_CONFIG_PATH,
+    enable_ingest_v2, ApiKeyConfig, IndexerConfig, IngestApiConfig, JaegerConfig, NodeConfig,
+    PermissionConfig, RestAuthConfig, Role, SearcherConfig, SplitCacheLimits, TlsConfig,
+    UserConfig, DEFAULT_QW_CONFIG_PATH,
 };
 use crate::source_config::serialize::{SourceConfigV0_7, VersionedSourceConfig};
 pub use crate::storage_config::{
@@ -93,13 +100,37 @@ pub use crate::storage_config::{
     VersionedIndexTemplate,
     IndexTemplateV0_7,
     SourceInputFormat,
+    CsvInputFormatParams,
+    CsvColumnType,
+    AvroInputFormatParams,
+    ProtobufInputFormatParams,
+    DeadLetterConfig,
+    IndexDeadLetterParams,
+    StorageDeadLetterParams,
+    KafkaDeadLetterParams,
+    MultilineConfig,
+    ProcessorConfig,
+    GrokProcessorConfig,
+    DissectProcessorConfig,
+    KvProcessorConfig,
+    RenameProcessorConfig,
+    RemoveProcessorConfig,
+    DateProcessorConfig,
+    DropIfProcessorConfig,
     SourceParams,
+    AmqpSourceParams,
     FileSourceParams,
     GcpPubSubSourceParams,
     KafkaSourceParams,
     KinesisSourceParams,
+    MqttSourceParams,
     PulsarSourceParams,
     PulsarSourceAuth,
+    StoragePrefixSourceParams,
+    StoragePrefixCompression,
+    SyslogSourceParams,
+    SyslogProtocol,
+    SyslogFormat,
     RegionOrEndpoint,
     ConstWriteAmplificationMergePolicyConfig,
     StableLogMergePolicyConfig,
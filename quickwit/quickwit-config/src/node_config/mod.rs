@@ -20,11 +20,11 @@
 mod serialize;
 
 use std::collections::{HashMap, HashSet};
-use std::env;
 use std::net::SocketAddr;
 use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
+use std::{env, fmt};
 
 use anyhow::{bail, ensure};
 use bytesize::ByteSize;
@@ -39,7 +39,7 @@ use tracing::warn;
 use crate::node_config::serialize::load_node_config_with_env;
 use crate::service::QuickwitService;
 use crate::storage_config::StorageConfigs;
-use crate::{ConfigFormat, MetastoreConfigs};
+use crate::{validate_index_id_pattern, ConfigFormat, MetastoreConfigs};
 
 pub const DEFAULT_QW_CONFIG_PATH: &str = "config/quickwit.yaml";
 
@@ -50,6 +50,205 @@ pub struct RestConfig {
     pub cors_allow_origins: Vec<String>,
     #[serde(with = "http_serde::header_map")]
     pub extra_headers: HeaderMap,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub auth: Option<RestAuthConfig>,
+}
+
+impl RestConfig {
+    pub fn redact(&mut self) {
+        if let Some(auth_config) = self.auth.as_mut() {
+            auth_config.redact();
+        }
+    }
+}
+
+/// Authentication and authorization settings of the REST API. When set, every request must carry
+/// either an API key (`Authorization: Bearer <key>`) or basic auth credentials, and the
+/// permissions attached to these credentials must allow the request.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestAuthConfig {
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
+    pub users: Vec<UserConfig>,
+}
+
+impl RestAuthConfig {
+    pub fn redact(&mut self) {
+        for api_key_config in &mut self.api_keys {
+            api_key_config.key = "***redacted***".to_string();
+        }
+        for user_config in &mut self.users {
+            user_config.password = "***redacted***".to_string();
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.api_keys.is_empty() || !self.users.is_empty(),
+            "REST authentication (`rest.auth`) requires at least one API key or user"
+        );
+        let mut api_key_names = HashSet::new();
+
+        for api_key_config in &self.api_keys {
+            ensure!(
+                api_key_names.insert(&api_key_config.name),
+                "API key name `{}` is not unique",
+                api_key_config.name
+            );
+            ensure!(
+                !api_key_config.key.is_empty(),
+                "API key `{}` is empty",
+                api_key_config.name
+            );
+            for permission_config in &api_key_config.permissions {
+                permission_config.validate()?;
+            }
+        }
+        let mut usernames = HashSet::new();
+
+        for user_config in &self.users {
+            ensure!(
+                !user_config.username.is_empty() && !user_config.username.contains(':'),
+                "username `{}` must be non-empty and cannot contain `:`",
+                user_config.username
+            );
+            ensure!(
+                usernames.insert(&user_config.username),
+                "username `{}` is not unique",
+                user_config.username
+            );
+            ensure!(
+                !user_config.password.is_empty(),
+                "password of user `{}` is empty",
+                user_config.username
+            );
+            for permission_config in &user_config.permissions {
+                permission_config.validate()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Static API key, sent by clients as a bearer token.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKeyConfig {
+    /// Name of the API key, used to identify it in logs.
+    pub name: String,
+    pub key: String,
+    #[serde(default)]
+    pub permissions: Vec<PermissionConfig>,
+}
+
+impl fmt::Debug for ApiKeyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyConfig")
+            .field("name", &self.name)
+            .field("key", &"***redacted***")
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+/// User authenticated with basic auth.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserConfig {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub permissions: Vec<PermissionConfig>,
+}
+
+impl fmt::Debug for UserConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserConfig")
+            .field("username", &self.username)
+            .field("password", &"***redacted***")
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
+
+/// Grants roles on the indexes matching the index ID patterns.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionConfig {
+    pub index_patterns: Vec<String>,
+    pub roles: Vec<Role>,
+}
+
+impl PermissionConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.index_patterns.is_empty(),
+            "permission must have at least one index pattern"
+        );
+        for index_pattern in &self.index_patterns {
+            validate_index_id_pattern(index_pattern, false)?;
+        }
+        ensure!(
+            !self.roles.is_empty(),
+            "permission must have at least one role"
+        );
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Search and read the documents and metadata of the indexes.
+    Read,
+    /// Ingest documents into the indexes.
+    Ingest,
+    /// Create, update, and delete the indexes and their sources. Includes the other roles.
+    Admin,
+}
+
+impl Role {
+    /// Returns whether this role grants the permissions of `other`.
+    pub fn includes(self, other: Role) -> bool {
+        self == Role::Admin || self == other
+    }
+}
+
+/// TLS settings of the REST or gRPC listener. For gRPC, the same certificate and key are also
+/// presented by the node when it connects to the other nodes of the cluster.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// Path to the PEM-encoded certificate chain of the node.
+    pub cert_path: PathBuf,
+    /// Path to the PEM-encoded private key of the node.
+    pub key_path: PathBuf,
+    /// Path to the PEM-encoded CA certificate(s) used to verify the certificates of the clients
+    /// and, for gRPC, of the other nodes.
+    #[serde(default)]
+    pub ca_path: Option<PathBuf>,
+    /// Requires clients to present a certificate signed by the CA (mutual TLS).
+    #[serde(default)]
+    pub validate_client: bool,
+    /// Name expected in the certificates of the other nodes when connecting to them over gRPC.
+    /// Defaults to the IP address of their gRPC advertise address.
+    #[serde(default)]
+    pub expected_server_name: Option<String>,
+}
+
+impl TlsConfig {
+    fn validate(&self, config_key: &str) -> anyhow::Result<()> {
+        ensure!(
+            !self.validate_client || self.ca_path.is_some(),
+            "client certificate validation (`{config_key}.tls.validate_client`) requires a CA \
+             certificate (`{config_key}.tls.ca_path`)"
+        );
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -57,6 +256,8 @@ pub struct RestConfig {
 pub struct GrpcConfig {
     #[serde(default = "GrpcConfig::default_max_message_size")]
     pub max_message_size: ByteSize,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
 }
 
 impl GrpcConfig {
@@ -70,6 +271,14 @@ impl GrpcConfig {
             "max gRPC message size (`grpc.max_message_size`) must be at least 1MB, got `{}`",
             self.max_message_size
         );
+        if let Some(tls_config) = &self.tls {
+            tls_config.validate("grpc")?;
+            ensure!(
+                tls_config.ca_path.is_some(),
+                "gRPC TLS requires a CA certificate (`grpc.tls.ca_path`) to verify the \
+                 certificates of the other nodes"
+            );
+        }
         Ok(())
     }
 }
@@ -78,6 +287,7 @@ impl Default for GrpcConfig {
     fn default() -> Self {
         Self {
             max_message_size: Self::default_max_message_size(),
+            tls: None,
         }
     }
 }
@@ -470,6 +680,7 @@ impl NodeConfig {
     pub fn redact(&mut self) {
         self.metastore_configs.redact();
         self.metastore_uri.redact();
+        self.rest_config.redact();
         self.storage_configs.redact();
     }
 
@@ -597,12 +808,83 @@ mod tests {
     fn test_grpc_config_validate() {
         let grpc_config = GrpcConfig {
             max_message_size: ByteSize::mb(1),
+            tls: None,
         };
         assert!(grpc_config.validate().is_ok());
 
         let grpc_config = GrpcConfig {
             max_message_size: ByteSize::kb(1),
+            tls: None,
         };
         assert!(grpc_config.validate().is_err());
     }
+
+    #[test]
+    fn test_grpc_config_tls_serialization() {
+        let grpc_config: GrpcConfig = serde_yaml::from_str(
+            r#"
+                tls:
+                  cert_path: /etc/quickwit/node.crt
+                  key_path: /etc/quickwit/node.key
+                  ca_path: /etc/quickwit/ca.crt
+                  validate_client: true
+            "#,
+        )
+        .unwrap();
+        let tls_config = grpc_config.tls.unwrap();
+        assert_eq!(
+            tls_config.cert_path,
+            PathBuf::from("/etc/quickwit/node.crt")
+        );
+        assert_eq!(tls_config.key_path, PathBuf::from("/etc/quickwit/node.key"));
+        assert_eq!(
+            tls_config.ca_path.unwrap(),
+            PathBuf::from("/etc/quickwit/ca.crt")
+        );
+        assert!(tls_config.validate_client);
+        assert!(tls_config.expected_server_name.is_none());
+    }
+
+    #[test]
+    fn test_grpc_config_tls_validate() {
+        let tls_config = TlsConfig {
+            cert_path: PathBuf::from("node.crt"),
+            key_path: PathBuf::from("node.key"),
+            ca_path: Some(PathBuf::from("ca.crt")),
+            validate_client: true,
+            expected_server_name: None,
+        };
+        let grpc_config = GrpcConfig {
+            max_message_size: ByteSize::mb(1),
+            tls: Some(tls_config.clone()),
+        };
+        assert!(grpc_config.validate().is_ok());
+
+        let grpc_config = GrpcConfig {
+            max_message_size: ByteSize::mb(1),
+            tls: Some(TlsConfig {
+                ca_path: None,
+                validate_client: false,
+                ..tls_config.clone()
+            }),
+        };
+        assert_eq!(
+            grpc_config.validate().unwrap_err().to_string(),
+            "gRPC TLS requires a CA certificate (`grpc.tls.ca_path`) to verify the certificates \
+             of the other nodes"
+        );
+
+        let grpc_config = GrpcConfig {
+            max_message_size: ByteSize::mb(1),
+            tls: Some(TlsConfig {
+                ca_path: None,
+                ..tls_config
+            }),
+        };
+        assert_eq!(
+            grpc_config.validate().unwrap_err().to_string(),
+            "client certificate validation (`grpc.tls.validate_client`) requires a CA certificate \
+             (`grpc.tls.ca_path`)"
+        );
+    }
 }
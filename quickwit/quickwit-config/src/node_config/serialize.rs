@@ -30,7 +30,7 @@ use quickwit_common::uri::Uri;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
-use super::{GrpcConfig, RestConfig};
+use super::{GrpcConfig, RestAuthConfig, RestConfig, TlsConfig};
 use crate::config_value::ConfigValue;
 use crate::qw_env_vars::*;
 use crate::service::QuickwitService;
@@ -386,6 +386,10 @@ struct RestConfigBuilder {
     #[serde(with = "http_serde::header_map")]
     #[serde(default)]
     pub extra_headers: HeaderMap,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub auth: Option<RestAuthConfig>,
 }
 
 impl RestConfigBuilder {
@@ -400,10 +404,18 @@ impl RestConfigBuilder {
             listen_port_from_config_or_default,
         )
         .resolve(env_vars)?;
+        if let Some(tls_config) = &self.tls {
+            tls_config.validate("rest")?;
+        }
+        if let Some(auth_config) = &self.auth {
+            auth_config.validate()?;
+        }
         let rest_config = RestConfig {
             listen_addr: SocketAddr::new(listen_ip, listen_port),
             cors_allow_origins: self.cors_allow_origins,
             extra_headers: self.extra_headers,
+            tls: self.tls,
+            auth: self.auth,
         };
         Ok(rest_config)
     }
@@ -441,6 +453,8 @@ pub fn node_config_for_test() -> NodeConfig {
         listen_addr: rest_listen_addr,
         cors_allow_origins: Vec::new(),
         extra_headers: HeaderMap::new(),
+        tls: None,
+        auth: None,
     };
     NodeConfig {
         cluster_id: default_cluster_id().unwrap(),
@@ -471,7 +485,7 @@ mod tests {
     use std::env;
     use std::net::Ipv4Addr;
     use std::num::{NonZeroU64, NonZeroUsize};
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use bytesize::ByteSize;
     use itertools::Itertools;
@@ -1217,6 +1231,158 @@ mod tests {
         .expect_err("Config should not allow empty origins.");
     }
 
+    #[tokio::test]
+    async fn test_rest_config_tls() {
+        let rest_config_yaml = r#"
+            version: 0.7
+            rest:
+              tls:
+                cert_path: /etc/quickwit/node.crt
+                key_path: /etc/quickwit/node.key
+        "#;
+        let config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let tls_config = config.rest_config.tls.unwrap();
+        assert_eq!(
+            tls_config.cert_path,
+            PathBuf::from("/etc/quickwit/node.crt")
+        );
+        assert_eq!(tls_config.key_path, PathBuf::from("/etc/quickwit/node.key"));
+        assert!(tls_config.ca_path.is_none());
+        assert!(!tls_config.validate_client);
+
+        let rest_config_yaml = r#"
+            version: 0.7
+            rest:
+              tls:
+                cert_path: /etc/quickwit/node.crt
+                key_path: /etc/quickwit/node.key
+                validate_client: true
+        "#;
+        let error_message = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(error_message.contains("`rest.tls.ca_path`"));
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_auth() {
+        let rest_config_yaml = r#"
+            version: 0.7
+            rest:
+              auth:
+                api_keys:
+                  - name: log-shipper
+                    key: my-secret-key
+                    permissions:
+                      - index_patterns: [logs-*]
+                        roles: [ingest]
+                users:
+                  - username: alice
+                    password: my-password
+                    permissions:
+                      - index_patterns: ["*"]
+                        roles: [read, admin]
+        "#;
+        let mut config = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let auth_config = config.rest_config.auth.as_ref().unwrap();
+        assert_eq!(auth_config.api_keys.len(), 1);
+        assert_eq!(auth_config.api_keys[0].name, "log-shipper");
+        assert_eq!(auth_config.api_keys[0].key, "my-secret-key");
+        assert_eq!(
+            auth_config.api_keys[0].permissions[0].index_patterns,
+            ["logs-*"]
+        );
+        assert_eq!(
+            auth_config.api_keys[0].permissions[0].roles,
+            [crate::Role::Ingest]
+        );
+        assert_eq!(auth_config.users[0].username, "alice");
+        assert_eq!(
+            auth_config.users[0].permissions[0].roles,
+            [crate::Role::Read, crate::Role::Admin]
+        );
+        assert!(!format!("{config:?}").contains("my-secret-key"));
+
+        config.redact();
+        let auth_config = config.rest_config.auth.as_ref().unwrap();
+        assert_eq!(auth_config.api_keys[0].key, "***redacted***");
+        assert_eq!(auth_config.users[0].password, "***redacted***");
+    }
+
+    #[tokio::test]
+    async fn test_rest_config_auth_validation() {
+        let rest_config_yaml = r#"
+            version: 0.7
+            rest:
+              auth:
+                users:
+                  - username: alice
+                    password: my-password
+                  - username: alice
+                    password: my-other-password
+        "#;
+        let error_message = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert_eq!(error_message, "username `alice` is not unique");
+
+        let rest_config_yaml = r#"
+            version: 0.7
+            rest:
+              auth:
+                api_keys:
+                  - name: log-shipper
+                    key: my-secret-key
+                    permissions:
+                      - index_patterns: [logs-*]
+                        roles: []
+        "#;
+        let error_message = load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert_eq!(error_message, "permission must have at least one role");
+
+        let rest_config_yaml = r#"
+            version: 0.7
+            rest:
+              auth: {}
+        "#;
+        load_node_config_with_env(
+            ConfigFormat::Yaml,
+            rest_config_yaml.as_bytes(),
+            &Default::default(),
+        )
+        .await
+        .unwrap_err();
+    }
+
     #[tokio::test]
     async fn test_node_config_validates_ingest_config() {
         let ingest_config = IngestApiConfig {
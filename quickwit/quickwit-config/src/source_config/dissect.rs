@@ -0,0 +1,149 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::bail;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::DissectProcessorConfig;
+
+static KEY_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"%\{([^}]*)\}").expect("regular expression should compile"));
+
+/// A key of a dissect pattern along with the delimiter that follows it.
+#[derive(Debug)]
+struct DissectKey {
+    /// Name of the field receiving the value, `None` for skipped keys (`%{}` or `%{?name}`).
+    field_opt: Option<String>,
+    /// Whether repeated occurrences of the delimiter following the value are skipped (`->`).
+    skip_right_padding: bool,
+    delimiter: String,
+}
+
+/// A parsed dissect pattern: the literal parts of the pattern delimit the values of the keys.
+/// Unlike grok, dissect does not use regular expressions.
+#[derive(Debug)]
+pub struct DissectPattern {
+    prefix: String,
+    keys: Vec<DissectKey>,
+}
+
+impl DissectProcessorConfig {
+    /// Parses the dissect pattern of the processor.
+    pub fn parse_pattern(&self) -> anyhow::Result<DissectPattern> {
+        DissectPattern::parse(&self.pattern)
+    }
+}
+
+impl DissectPattern {
+    pub fn parse(pattern: &str) -> anyhow::Result<Self> {
+        let mut prefix = String::new();
+        let mut keys: Vec<DissectKey> = Vec::new();
+        let mut last_end = 0;
+
+        for key_captures in KEY_REGEX.captures_iter(pattern) {
+            let key_match = key_captures.get(0).expect("group 0 should always match");
+            let literal = &pattern[last_end..key_match.start()];
+            last_end = key_match.end();
+
+            if let Some(previous_key) = keys.last_mut() {
+                if literal.is_empty() {
+                    bail!("dissect pattern `{pattern}` has consecutive keys without a delimiter");
+                }
+                previous_key.delimiter = literal.to_string();
+            } else {
+                prefix = literal.to_string();
+            }
+            let mut key = &key_captures[1];
+            let skip_right_padding = if let Some(stripped_key) = key.strip_suffix("->") {
+                key = stripped_key;
+                true
+            } else {
+                false
+            };
+            let field_opt = if key.is_empty() || key.starts_with('?') {
+                None
+            } else if key.starts_with(['+', '*', '&']) {
+                bail!("dissect pattern `{pattern}` uses an unsupported modifier in key `{key}`");
+            } else {
+                Some(key.to_string())
+            };
+            keys.push(DissectKey {
+                field_opt,
+                skip_right_padding,
+                delimiter: String::new(),
+            });
+        }
+        let Some(last_key) = keys.last_mut() else {
+            bail!("dissect pattern `{pattern}` must contain at least one key");
+        };
+        last_key.delimiter = pattern[last_end..].to_string();
+
+        Ok(Self { prefix, keys })
+    }
+
+    /// Returns the values of the keys extracted from `text`, or `None` if the pattern does not
+    /// match.
+    pub fn dissect(&self, text: &str) -> Option<Vec<(&str, String)>> {
+        let mut remaining = text.strip_prefix(self.prefix.as_str())?;
+        let mut fields = Vec::with_capacity(self.keys.len());
+
+        for (key_idx, key) in self.keys.iter().enumerate() {
+            let value = if key_idx == self.keys.len() - 1 {
+                // The last key receives the rest of the text, minus the trailing literal.
+                let value = remaining.strip_suffix(key.delimiter.as_str())?;
+                remaining = "";
+                value
+            } else {
+                let (value, rest) = remaining.split_once(key.delimiter.as_str())?;
+                remaining = rest;
+
+                if key.skip_right_padding {
+                    while let Some(rest) = remaining.strip_prefix(key.delimiter.as_str()) {
+                        remaining = rest;
+                    }
+                }
+                value
+            };
+            if let Some(field) = &key.field_opt {
+                fields.push((field.as_str(), value.to_string()));
+            }
+        }
+        Some(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dissect_pattern_parse_errors() {
+        let error = DissectPattern::parse("no keys").err().unwrap();
+        assert!(error.to_string().contains("must contain at least one key"));
+
+        let error = DissectPattern::parse("%{a}%{b}").err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("consecutive keys without a delimiter"));
+
+        let error = DissectPattern::parse("%{+a} %{b}").err().unwrap();
+        assert!(error.to_string().contains("unsupported modifier"));
+    }
+}
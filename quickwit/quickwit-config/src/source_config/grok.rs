@@ -0,0 +1,322 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Context};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value as JsonValue;
+
+use super::GrokProcessorConfig;
+
+/// Maximum nesting depth of pattern references, which guards against recursive definitions.
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// Built-in pattern library, adapted from the Logstash core patterns to the syntax of the `regex`
+/// crate, which does not support look-around assertions.
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("USERNAME", r#"[a-zA-Z0-9._-]+"#),
+    ("USER", r#"%{USERNAME}"#),
+    (
+        "EMAILLOCALPART",
+        r#"[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+)*"#,
+    ),
+    ("EMAILADDRESS", r#"%{EMAILLOCALPART}@%{HOSTNAME}"#),
+    ("INT", r#"[+-]?[0-9]+"#),
+    ("BASE10NUM", r#"[+-]?(?:[0-9]+(?:\.[0-9]+)?|\.[0-9]+)"#),
+    ("NUMBER", r#"%{BASE10NUM}"#),
+    ("BASE16NUM", r#"[+-]?(?:0x)?[0-9A-Fa-f]+"#),
+    ("POSINT", r#"\b[1-9][0-9]*\b"#),
+    ("NONNEGINT", r#"\b[0-9]+\b"#),
+    ("WORD", r#"\b\w+\b"#),
+    ("NOTSPACE", r#"\S+"#),
+    ("SPACE", r#"\s*"#),
+    ("DATA", r#".*?"#),
+    ("GREEDYDATA", r#".*"#),
+    (
+        "QUOTEDSTRING",
+        r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|`(?:[^`\\]|\\.)*`"#,
+    ),
+    ("QS", r#"%{QUOTEDSTRING}"#),
+    (
+        "UUID",
+        r#"[A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}"#,
+    ),
+    ("CISCOMAC", r#"(?:[A-Fa-f0-9]{4}\.){2}[A-Fa-f0-9]{4}"#),
+    ("WINDOWSMAC", r#"(?:[A-Fa-f0-9]{2}-){5}[A-Fa-f0-9]{2}"#),
+    ("COMMONMAC", r#"(?:[A-Fa-f0-9]{2}:){5}[A-Fa-f0-9]{2}"#),
+    ("MAC", r#"%{CISCOMAC}|%{WINDOWSMAC}|%{COMMONMAC}"#),
+    (
+        "IPV4",
+        r#"(?:(?:25[0-5]|2[0-4][0-9]|1[0-9]{2}|[1-9]?[0-9])\.){3}(?:25[0-5]|2[0-4][0-9]|1[0-9]{2}|[1-9]?[0-9])"#,
+    ),
+    (
+        "IPV6",
+        r#"(?:[0-9A-Fa-f]{1,4}:){7}[0-9A-Fa-f]{1,4}|(?:[0-9A-Fa-f]{1,4}:){1,7}:(?:[0-9A-Fa-f]{1,4}(?::[0-9A-Fa-f]{1,4})*)?|::(?:[0-9A-Fa-f]{1,4}(?::[0-9A-Fa-f]{1,4})*)?"#,
+    ),
+    ("IP", r#"%{IPV6}|%{IPV4}"#),
+    (
+        "HOSTNAME",
+        r#"\b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*\.?"#,
+    ),
+    ("IPORHOST", r#"%{IP}|%{HOSTNAME}"#),
+    ("HOSTPORT", r#"%{IPORHOST}:%{POSINT}"#),
+    ("UNIXPATH", r#"(?:/[\w_%!$@:.,+~-]*)+"#),
+    ("WINPATH", r#"(?:[A-Za-z]+:|\\)(?:\\[^\\?*]*)+"#),
+    ("PATH", r#"%{UNIXPATH}|%{WINPATH}"#),
+    ("URIPROTO", r#"[A-Za-z][A-Za-z0-9+.-]+"#),
+    ("URIHOST", r#"%{IPORHOST}(?::%{POSINT})?"#),
+    ("URIPATH", r#"(?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_-]*)+"#),
+    ("URIQUERY", r#"[A-Za-z0-9$.+!*'|(){},~@#%&/=:;_?\[\]<>-]*"#),
+    ("URIPARAM", r#"\?%{URIQUERY}"#),
+    ("URIPATHPARAM", r#"%{URIPATH}(?:%{URIPARAM})?"#),
+    (
+        "URI",
+        r#"%{URIPROTO}://(?:%{USER}(?::[^@]*)?@)?(?:%{URIHOST})?(?:%{URIPATHPARAM})?"#,
+    ),
+    (
+        "MONTH",
+        r#"\b(?:[Jj]an(?:uary)?|[Ff]eb(?:ruary)?|[Mm]ar(?:ch)?|[Aa]pr(?:il)?|[Mm]ay|[Jj]un(?:e)?|[Jj]ul(?:y)?|[Aa]ug(?:ust)?|[Ss]ep(?:tember)?|[Oo]ct(?:ober)?|[Nn]ov(?:ember)?|[Dd]ec(?:ember)?)\b"#,
+    ),
+    ("MONTHNUM", r#"0?[1-9]|1[0-2]"#),
+    ("MONTHNUM2", r#"0[1-9]|1[0-2]"#),
+    ("MONTHDAY", r#"0[1-9]|[12][0-9]|3[01]|[1-9]"#),
+    (
+        "DAY",
+        r#"\b(?:Mon(?:day)?|Tue(?:sday)?|Wed(?:nesday)?|Thu(?:rsday)?|Fri(?:day)?|Sat(?:urday)?|Sun(?:day)?)\b"#,
+    ),
+    ("YEAR", r#"(?:[0-9]{2}){1,2}"#),
+    ("HOUR", r#"2[0123]|[01]?[0-9]"#),
+    ("MINUTE", r#"[0-5][0-9]"#),
+    ("SECOND", r#"(?:[0-5]?[0-9]|60)(?:[:.,][0-9]+)?"#),
+    ("TIME", r#"%{HOUR}:%{MINUTE}(?::%{SECOND})?"#),
+    ("DATE_US", r#"%{MONTHNUM}[/-]%{MONTHDAY}[/-]%{YEAR}"#),
+    ("DATE_EU", r#"%{MONTHDAY}[./-]%{MONTHNUM}[./-]%{YEAR}"#),
+    ("DATE", r#"%{DATE_US}|%{DATE_EU}"#),
+    ("DATESTAMP", r#"%{DATE}[- ]%{TIME}"#),
+    ("TZ", r#"[APMCE][SD]T|UTC"#),
+    ("ISO8601_TIMEZONE", r#"Z|[+-]%{HOUR}(?::?%{MINUTE})"#),
+    ("ISO8601_SECOND", r#"%{SECOND}"#),
+    (
+        "TIMESTAMP_ISO8601",
+        r#"%{YEAR}-%{MONTHNUM}-%{MONTHDAY}[T ]%{HOUR}:?%{MINUTE}(?::?%{SECOND})?%{ISO8601_TIMEZONE}?"#,
+    ),
+    ("HTTPDATE", r#"%{MONTHDAY}/%{MONTH}/%{YEAR}:%{TIME} %{INT}"#),
+    ("SYSLOGTIMESTAMP", r#"%{MONTH} +%{MONTHDAY} %{TIME}"#),
+    ("PROG", r#"[\x21-\x5a\x5c\x5e-\x7e]+"#),
+    ("SYSLOGPROG", r#"%{PROG:program}(?:\[%{POSINT:pid}\])?"#),
+    ("SYSLOGHOST", r#"%{IPORHOST}"#),
+    (
+        "SYSLOGFACILITY",
+        r#"<%{NONNEGINT:facility}.%{NONNEGINT:priority}>"#,
+    ),
+    (
+        "SYSLOGBASE",
+        r#"%{SYSLOGTIMESTAMP:timestamp} (?:%{SYSLOGFACILITY} )?%{SYSLOGHOST:logsource} %{SYSLOGPROG}:"#,
+    ),
+    (
+        "LOGLEVEL",
+        r#"[Aa]lert|ALERT|[Tt]race|TRACE|[Dd]ebug|DEBUG|[Nn]otice|NOTICE|[Ii]nfo(?:rmation)?|INFO(?:RMATION)?|[Ww]arn(?:ing)?|WARN(?:ING)?|[Ee]rr(?:or)?|ERR(?:OR)?|[Cc]rit(?:ical)?|CRIT(?:ICAL)?|[Ff]atal|FATAL|[Ss]evere|SEVERE|[Ee]merg(?:ency)?|EMERG(?:ENCY)?"#,
+    ),
+    ("HTTPDUSER", r#"%{EMAILADDRESS}|%{USER}"#),
+    (
+        "COMMONAPACHELOG",
+        r#"%{IPORHOST:clientip} %{HTTPDUSER:ident} %{USER:auth} \[%{HTTPDATE:timestamp}\] "(?:%{WORD:verb} %{NOTSPACE:request}(?: HTTP/%{NUMBER:httpversion})?|%{DATA:rawrequest})" %{NUMBER:response} (?:%{NUMBER:bytes}|-)"#,
+    ),
+    (
+        "COMBINEDAPACHELOG",
+        r#"%{COMMONAPACHELOG} %{QS:referrer} %{QS:agent}"#,
+    ),
+];
+
+static BUILTIN_PATTERNS_MAP: Lazy<HashMap<&'static str, &'static str>> =
+    Lazy::new(|| BUILTIN_PATTERNS.iter().copied().collect());
+
+/// Matches a pattern reference: `%{NAME}`, `%{NAME:field}`, or `%{NAME:field:type}`.
+static PATTERN_REFERENCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"%\{(?P<name>[A-Za-z0-9_]+)(?::(?P<field>[^:}]+))?(?::(?P<type>[A-Za-z]+))?\}")
+        .expect("regular expression should compile")
+});
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GrokValueType {
+    String,
+    Int,
+    Float,
+}
+
+impl GrokProcessorConfig {
+    /// Compiles the grok patterns of the processor.
+    pub fn compile_patterns(&self) -> anyhow::Result<Vec<GrokPattern>> {
+        self.patterns
+            .iter()
+            .map(|pattern| GrokPattern::compile(pattern, &self.pattern_definitions))
+            .collect()
+    }
+}
+
+impl GrokValueType {
+    fn parse(value_type_str: &str) -> anyhow::Result<Self> {
+        match value_type_str {
+            "int" => Ok(Self::Int),
+            "float" => Ok(Self::Float),
+            _ => bail!("unknown grok type `{value_type_str}`: expected `int` or `float`"),
+        }
+    }
+
+    fn convert(&self, value: &str) -> Option<JsonValue> {
+        match self {
+            Self::String => Some(JsonValue::String(value.to_string())),
+            Self::Int => value.parse::<i64>().ok().map(JsonValue::from),
+            Self::Float => value
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(JsonValue::Number),
+        }
+    }
+}
+
+/// Semantic of a pattern reference: the value it matches is stored in `field`.
+#[derive(Debug)]
+struct GrokCapture {
+    group_name: String,
+    field: String,
+    value_type: GrokValueType,
+}
+
+/// A grok pattern compiled into a regular expression. Its semantics are mapped to named capture
+/// groups because field names may contain characters forbidden in group names, such as dots.
+#[derive(Debug)]
+pub struct GrokPattern {
+    regex: Regex,
+    captures: Vec<GrokCapture>,
+}
+
+impl GrokPattern {
+    pub fn compile(
+        pattern: &str,
+        pattern_definitions: &BTreeMap<String, String>,
+    ) -> anyhow::Result<Self> {
+        let mut captures = Vec::new();
+        let regex_str = expand_pattern(pattern, pattern_definitions, &mut captures, 0)?;
+        let regex = Regex::new(&regex_str)
+            .with_context(|| format!("failed to compile grok pattern `{pattern}`"))?;
+        Ok(Self { regex, captures })
+    }
+
+    /// Returns the fields extracted from `text`, or `None` if the pattern does not match.
+    pub fn extract(&self, text: &str) -> Option<Result<Vec<(&str, JsonValue)>, String>> {
+        let regex_captures = self.regex.captures(text)?;
+        let mut fields = Vec::with_capacity(self.captures.len());
+
+        for capture in &self.captures {
+            // Pattern references nested in an alternative that did not match are skipped.
+            let Some(value) = regex_captures.name(&capture.group_name) else {
+                continue;
+            };
+            let Some(json_value) = capture.value_type.convert(value.as_str()) else {
+                return Some(Err(format!(
+                    "failed to convert value `{}` of field `{}` to `{:?}`",
+                    value.as_str(),
+                    capture.field,
+                    capture.value_type
+                )));
+            };
+            fields.push((capture.field.as_str(), json_value));
+        }
+        Some(Ok(fields))
+    }
+}
+
+/// Replaces the pattern references of `pattern` with the regular expression of the referenced
+/// patterns, recursively.
+fn expand_pattern(
+    pattern: &str,
+    pattern_definitions: &BTreeMap<String, String>,
+    captures: &mut Vec<GrokCapture>,
+    depth: usize,
+) -> anyhow::Result<String> {
+    if depth > MAX_EXPANSION_DEPTH {
+        bail!("grok pattern references are nested too deeply, the definitions may be recursive");
+    }
+    let mut regex_str = String::with_capacity(pattern.len());
+    let mut last_end = 0;
+
+    for reference in PATTERN_REFERENCE_REGEX.captures_iter(pattern) {
+        let reference_match = reference.get(0).expect("group 0 should always match");
+        regex_str.push_str(&pattern[last_end..reference_match.start()]);
+        last_end = reference_match.end();
+
+        let name = &reference["name"];
+        let definition = pattern_definitions
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| BUILTIN_PATTERNS_MAP.get(name).copied())
+            .with_context(|| format!("unknown grok pattern `{name}`"))?;
+
+        let Some(field) = reference.name("field") else {
+            let expanded = expand_pattern(definition, pattern_definitions, captures, depth + 1)?;
+            regex_str.push_str(&format!("(?:{expanded})"));
+            continue;
+        };
+        let value_type = reference
+            .name("type")
+            .map(|value_type| GrokValueType::parse(value_type.as_str()))
+            .transpose()?
+            .unwrap_or(GrokValueType::String);
+        let group_name = format!("grok{}", captures.len());
+        captures.push(GrokCapture {
+            group_name: group_name.clone(),
+            field: field.as_str().to_string(),
+            value_type,
+        });
+        let expanded = expand_pattern(definition, pattern_definitions, captures, depth + 1)?;
+        regex_str.push_str(&format!("(?P<{group_name}>{expanded})"));
+    }
+    regex_str.push_str(&pattern[last_end..]);
+    Ok(regex_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_patterns_compile() {
+        for (name, _) in BUILTIN_PATTERNS {
+            GrokPattern::compile(&format!("%{{{name}:value}}"), &BTreeMap::new())
+                .unwrap_or_else(|error| panic!("pattern `{name}` should compile: {error}"));
+        }
+    }
+
+    #[test]
+    fn test_grok_pattern_compile_errors() {
+        let error = GrokPattern::compile("%{UNKNOWN:field}", &BTreeMap::new()).unwrap_err();
+        assert_eq!(error.to_string(), "unknown grok pattern `UNKNOWN`");
+
+        let error = GrokPattern::compile("%{INT:field:bool}", &BTreeMap::new()).unwrap_err();
+        assert!(error.to_string().contains("unknown grok type `bool`"));
+
+        let pattern_definitions = BTreeMap::from([("LOOP".to_string(), "a%{LOOP}".to_string())]);
+        let error = GrokPattern::compile("%{LOOP}", &pattern_definitions).unwrap_err();
+        assert!(error.to_string().contains("nested too deeply"));
+    }
+}
@@ -19,11 +19,16 @@
 
 use std::num::NonZeroUsize;
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use serde::{Deserialize, Serialize};
 
-use super::{TransformConfig, RESERVED_SOURCE_IDS};
-use crate::{validate_identifier, ConfigFormat, SourceConfig, SourceInputFormat, SourceParams};
+use super::{
+    DeadLetterConfig, MultilineConfig, ProcessorConfig, TransformConfig, RESERVED_SOURCE_IDS,
+};
+use crate::{
+    validate_identifier, AvroInputFormatParams, ConfigFormat, KafkaSourceParams,
+    ProtobufInputFormatParams, SourceConfig, SourceInputFormat, SourceParams,
+};
 
 type SourceConfigForSerialization = SourceConfigV0_7;
 
@@ -92,22 +97,121 @@ impl SourceConfigForSerialization {
             SourceParams::Kafka(_) | SourceParams::Kinesis(_) | SourceParams::Pulsar(_) => {
                 // TODO consider any validation opportunity
             }
+            SourceParams::Amqp(amqp_params) => {
+                if amqp_params.queue.is_empty() {
+                    bail!(
+                        "source `{}` of type `amqp` must contain a queue name",
+                        self.source_id
+                    )
+                }
+            }
+            SourceParams::Mqtt(mqtt_params) => {
+                if mqtt_params.topics.is_empty()
+                    || mqtt_params.topics.iter().any(|topic| topic.is_empty())
+                {
+                    bail!(
+                        "source `{}` of type `mqtt` must contain at least one topic, and topics \
+                         must not be empty",
+                        self.source_id
+                    )
+                }
+                if mqtt_params.password.is_some() && mqtt_params.username.is_none() {
+                    bail!(
+                        "source `{}` of type `mqtt` defines a password but no username",
+                        self.source_id
+                    )
+                }
+            }
             SourceParams::GcpPubSub(_)
             | SourceParams::Ingest
             | SourceParams::IngestApi
             | SourceParams::IngestCli
             | SourceParams::Vec(_)
             | SourceParams::Void(_) => {}
+            SourceParams::StoragePrefix(storage_prefix_params) => {
+                if let Some(glob) = &storage_prefix_params.glob {
+                    glob::Pattern::new(glob).with_context(|| {
+                        format!(
+                            "source `{}` of type `storage_prefix` has an invalid glob pattern \
+                             `{glob}`",
+                            self.source_id
+                        )
+                    })?;
+                }
+            }
+            SourceParams::Syslog(_) => {
+                if self.input_format != SourceInputFormat::Json {
+                    bail!(
+                        "source `{}` of type `syslog` only supports the `json` input format: \
+                         syslog messages are parsed into JSON objects by the source",
+                        self.source_id
+                    )
+                }
+            }
         }
         match &self.source_params {
-            SourceParams::GcpPubSub(_) | SourceParams::Kafka(_) => {}
+            SourceParams::Amqp(_)
+            | SourceParams::GcpPubSub(_)
+            | SourceParams::Kafka(_)
+            | SourceParams::StoragePrefix(_) => {}
             _ => {
                 if self.desired_num_pipelines > 1 || self.max_num_pipelines_per_indexer > 1 {
-                    bail!("Quickwit currently supports multiple pipelines only for GCP PubSub or Kafka sources. open an issue https://github.com/quickwit-oss/quickwit/issues if you need the feature for other source types");
+                    bail!("Quickwit currently supports multiple pipelines only for AMQP, GCP PubSub, Kafka or storage prefix sources. open an issue https://github.com/quickwit-oss/quickwit/issues if you need the feature for other source types");
                 }
             }
         }
 
+        match &self.input_format {
+            SourceInputFormat::Csv(csv_params) => {
+                csv_params.validate()?;
+                // The header row is read from the beginning of the file, which the file source
+                // reads again when it resumes from a checkpoint. Other sources have no way to
+                // tell the header apart from a data row.
+                if csv_params.has_header() && !matches!(self.source_params, SourceParams::File(_)) {
+                    bail!(
+                        "source `{}`: CSV header rows are only supported for file sources, other \
+                         sources must define the CSV `columns` and set `has_header` to false",
+                        self.source_id
+                    );
+                }
+            }
+            SourceInputFormat::Avro(AvroInputFormatParams { schema: None })
+            | SourceInputFormat::Protobuf(ProtobufInputFormatParams { schema: None, .. }) => {
+                let SourceParams::Kafka(kafka_params) = &self.source_params else {
+                    bail!(
+                        "Avro and Protobuf input formats require an inline `schema` for sources \
+                         other than Kafka"
+                    );
+                };
+                if kafka_params.schema_registry_url.is_none() {
+                    bail!(
+                        "Avro and Protobuf input formats require either an inline `schema` or a \
+                         schema registry (`params.schema_registry_url`)"
+                    );
+                }
+            }
+            SourceInputFormat::Protobuf(ProtobufInputFormatParams {
+                message_name: Some(message_name),
+                ..
+            }) if message_name.is_empty() => {
+                bail!("Protobuf message name must not be empty");
+            }
+            _ => {}
+        }
+        if let SourceParams::Kafka(KafkaSourceParams {
+            schema_registry_url: Some(schema_registry_url),
+            ..
+        }) = &self.source_params
+        {
+            if !schema_registry_url.starts_with("http://")
+                && !schema_registry_url.starts_with("https://")
+            {
+                bail!(
+                    "schema registry URL `{schema_registry_url}` must start with `http://` or \
+                     `https://`"
+                );
+            }
+        }
         if let Some(transform_config) = &self.transform {
             if matches!(
                 self.input_format,
@@ -117,6 +221,34 @@ impl SourceConfigForSerialization {
             }
             transform_config.validate_vrl_script()?;
         }
+        if let Some(dead_letter_config) = &self.dead_letter {
+            dead_letter_config.validate()?;
+        }
+        if let Some(multiline_config) = &self.multiline {
+            if self.input_format != SourceInputFormat::PlainText {
+                bail!("multiline assembly is only supported for the `plain_text` input format");
+            }
+            if !matches!(
+                self.source_params,
+                SourceParams::File(_) | SourceParams::Kafka(_) | SourceParams::IngestApi
+            ) {
+                bail!(
+                    "multiline assembly is only supported for file, Kafka, and ingest API sources"
+                );
+            }
+            multiline_config.validate()?;
+        }
+        if !self.processors.is_empty()
+            && matches!(
+                self.input_format,
+                SourceInputFormat::OtlpTraceJson | SourceInputFormat::OtlpTraceProtobuf
+            )
+        {
+            bail!("processors are not supported for OTLP input formats");
+        }
+        for processor_config in &self.processors {
+            processor_config.validate()?;
+        }
 
         Ok(SourceConfig {
             source_id: self.source_id,
@@ -126,6 +258,9 @@ impl SourceConfigForSerialization {
             source_params: self.source_params,
             transform_config: self.transform,
             input_format: self.input_format,
+            dead_letter_config: self.dead_letter,
+            multiline_config: self.multiline,
+            processors: self.processors,
         })
     }
 }
@@ -140,6 +275,9 @@ impl From<SourceConfig> for SourceConfigV0_7 {
             source_params: source_config.source_params,
             transform: source_config.transform_config,
             input_format: source_config.input_format,
+            dead_letter: source_config.dead_letter_config,
+            multiline: source_config.multiline_config,
+            processors: source_config.processors,
         }
     }
 }
@@ -197,4 +335,14 @@ pub struct SourceConfigV0_7 {
     // Denotes the input data format.
     #[serde(default)]
     pub input_format: SourceInputFormat,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter: Option<DeadLetterConfig>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiline: Option<MultilineConfig>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub processors: Vec<ProcessorConfig>,
 }
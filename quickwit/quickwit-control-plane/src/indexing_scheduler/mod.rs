@@ -160,11 +160,14 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
                     },
                 });
             }
-            SourceType::Kafka
+            SourceType::Amqp
+            | SourceType::Kafka
             | SourceType::Kinesis
             | SourceType::GcpPubsub
+            | SourceType::Mqtt
             | SourceType::Nats
-            | SourceType::Pulsar => {
+            | SourceType::Pulsar
+            | SourceType::Syslog => {
                 sources.push(SourceToSchedule {
                     source_uid,
                     source_type: SourceToScheduleType::NonSharded {
@@ -175,6 +178,17 @@ fn get_sources_to_schedule(model: &ControlPlaneModel) -> Vec<SourceToSchedule> {
                     },
                 });
             }
+            SourceType::StoragePrefix => {
+                sources.push(SourceToSchedule {
+                    source_uid,
+                    source_type: SourceToScheduleType::NonShardedWithSlots {
+                        num_pipelines: source_config.desired_num_pipelines.get() as u32,
+                        // FIXME
+                        load_per_pipeline: NonZeroU32::new(PIPELINE_FULL_CAPACITY.cpu_millis())
+                            .unwrap(),
+                    },
+                });
+            }
         }
     }
     sources
@@ -660,6 +674,7 @@ mod tests {
             client_log_level: None,
             client_params: serde_json::json!({}),
             enable_backfill_mode: false,
+            schema_registry_url: None,
         };
         let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
         let index_uid = index_metadata.index_uid.clone();
@@ -675,6 +690,9 @@ mod tests {
                     source_params: SourceParams::Kafka(kafka_source_params.clone()),
                     transform_config: None,
                     input_format: Default::default(),
+                    dead_letter_config: None,
+                    multiline_config: None,
+                    processors: Vec::new(),
                 },
             )
             .unwrap();
@@ -689,6 +707,9 @@ mod tests {
                     source_params: SourceParams::Kafka(kafka_source_params.clone()),
                     transform_config: None,
                     input_format: Default::default(),
+                    dead_letter_config: None,
+                    multiline_config: None,
+                    processors: Vec::new(),
                 },
             )
             .unwrap();
@@ -704,6 +725,9 @@ mod tests {
                     source_params: SourceParams::IngestApi,
                     transform_config: None,
                     input_format: Default::default(),
+                    dead_letter_config: None,
+                    multiline_config: None,
+                    processors: Vec::new(),
                 },
             )
             .unwrap();
@@ -719,6 +743,9 @@ mod tests {
                     source_params: SourceParams::Ingest,
                     transform_config: None,
                     input_format: Default::default(),
+                    dead_letter_config: None,
+                    multiline_config: None,
+                    processors: Vec::new(),
                 },
             )
             .unwrap();
@@ -735,6 +762,9 @@ mod tests {
                     source_params: SourceParams::Ingest,
                     transform_config: None,
                     input_format: Default::default(),
+                    dead_letter_config: None,
+                    multiline_config: None,
+                    processors: Vec::new(),
                 },
             )
             .unwrap();
@@ -750,6 +780,9 @@ mod tests {
                     source_params: SourceParams::IngestCli,
                     transform_config: None,
                     input_format: Default::default(),
+                    dead_letter_config: None,
+                    multiline_config: None,
+                    processors: Vec::new(),
                 },
             )
             .unwrap();
@@ -840,6 +873,7 @@ mod tests {
                 "bootstrap.servers": "localhost:9092",
             }),
             enable_backfill_mode: true,
+            schema_registry_url: None,
         })
     }
 
@@ -856,6 +890,9 @@ mod tests {
               source_params: kafka_source_params_for_test(),
               transform_config: None,
               input_format: SourceInputFormat::Json,
+              dead_letter_config: None,
+              multiline_config: None,
+              processors: Vec::new(),
           })
       }
     }
@@ -69,6 +69,10 @@ fn populate_problem(
         SourceToScheduleType::NonSharded {
             num_pipelines,
             load_per_pipeline,
+        }
+        | SourceToScheduleType::NonShardedWithSlots {
+            num_pipelines,
+            load_per_pipeline,
         } => {
             let source_ord = problem.add_source(*num_pipelines, *load_per_pipeline);
             Some(source_ord)
@@ -137,7 +141,8 @@ fn convert_physical_plan_to_solution(
                             indexer_assignment
                                 .add_shards(source_ord, indexing_task.shard_ids.len() as u32);
                         }
-                        SourceToScheduleType::NonSharded { .. } => {
+                        SourceToScheduleType::NonSharded { .. }
+                        | SourceToScheduleType::NonShardedWithSlots { .. } => {
                             // For non-sharded sources like Kafka, one pipeline = one shard in the
                             // solutions
                             indexer_assignment.add_shards(source_ord, 1);
@@ -168,6 +173,12 @@ pub enum SourceToScheduleType {
         num_pipelines: u32,
         load_per_pipeline: NonZeroU32,
     },
+    // Non-sharded source whose pipelines split the work among `num_pipelines` slots. Each slot is
+    // assigned to exactly one pipeline, as shard ID `0..num_pipelines` of its indexing task.
+    NonShardedWithSlots {
+        num_pipelines: u32,
+        load_per_pipeline: NonZeroU32,
+    },
     // deprecated
     IngestV1,
 }
@@ -191,9 +202,9 @@ fn compute_max_num_shards_per_pipeline(source_type: &SourceToScheduleType) -> No
                     NonZeroU32::MIN // also colloquially known as `1`
                 })
         }
-        SourceToScheduleType::IngestV1 | SourceToScheduleType::NonSharded { .. } => {
-            NonZeroU32::new(1u32).unwrap()
-        }
+        SourceToScheduleType::IngestV1
+        | SourceToScheduleType::NonSharded { .. }
+        | SourceToScheduleType::NonShardedWithSlots { .. } => NonZeroU32::new(1u32).unwrap(),
     }
 }
 
@@ -253,8 +264,10 @@ fn convert_scheduling_solution_to_physical_plan_single_node_single_source(
             }
             new_tasks
         }
-        SourceToScheduleType::NonSharded { .. } => {
+        SourceToScheduleType::NonSharded { .. }
+        | SourceToScheduleType::NonShardedWithSlots { .. } => {
             // For non-sharded pipelines, we just need `num_shards` is a number of pipelines.
+            // The slots of non-sharded sources with slots are assigned in postprocessing.
             let mut indexing_tasks: Vec<IndexingTask> = previous_tasks
                 .iter()
                 .take(remaining_num_shards_to_schedule_on_node as usize)
@@ -425,11 +438,70 @@ fn convert_scheduling_solution_to_physical_plan(
         }
     }
 
+    // Non-sharded sources with slots need their slots to be spread over their pipelines.
+    for source in sources {
+        if let SourceToScheduleType::NonShardedWithSlots { num_pipelines, .. } = &source.source_type
+        {
+            assign_slots_to_pipelines(&source.source_uid, *num_pipelines, &mut new_physical_plan);
+        }
+    }
+
     new_physical_plan.normalize();
 
     new_physical_plan
 }
 
+/// Spreads the slots `0..num_slots` of a non-sharded source over its pipelines. Pipelines keep
+/// the slots they were previously assigned as long as the slots remain evenly spread.
+fn assign_slots_to_pipelines(
+    source_uid: &SourceUid,
+    num_slots: u32,
+    physical_plan: &mut PhysicalIndexingPlan,
+) {
+    let mut source_tasks: Vec<&mut IndexingTask> = physical_plan
+        .indexing_tasks_per_indexer_mut()
+        .values_mut()
+        .flatten()
+        .filter(|task| {
+            task.index_uid() == &source_uid.index_uid && task.source_id == source_uid.source_id
+        })
+        .collect();
+    if source_tasks.is_empty() {
+        return;
+    }
+    let max_num_slots_per_task =
+        quickwit_common::div_ceil_u32(num_slots, source_tasks.len() as u32) as usize;
+    let mut assigned_slots: FnvHashSet<ShardId> = FnvHashSet::default();
+
+    for task in &mut source_tasks {
+        let mut num_task_slots = 0;
+        task.shard_ids.retain(|slot| {
+            let is_valid_slot = slot
+                .as_u64()
+                .map_or(false, |slot_ord| slot_ord < num_slots as u64);
+            if is_valid_slot
+                && num_task_slots < max_num_slots_per_task
+                && assigned_slots.insert(slot.clone())
+            {
+                num_task_slots += 1;
+                true
+            } else {
+                false
+            }
+        });
+    }
+    for slot in (0..num_slots as u64).map(ShardId::from) {
+        if assigned_slots.contains(&slot) {
+            continue;
+        }
+        let task = source_tasks
+            .iter_mut()
+            .min_by_key(|task| task.shard_ids.len())
+            .expect("source should have at least one task");
+        task.shard_ids.push(slot);
+    }
+}
+
 // Checks that's the physical solution indeed matches the scheduling solution.
 fn assert_post_condition_physical_plan_match_solution(
     physical_plan: &PhysicalIndexingPlan,
@@ -596,7 +668,7 @@ mod tests {
     use quickwit_proto::types::{IndexUid, PipelineUid, ShardId, SourceUid};
 
     use super::{
-        build_physical_indexing_plan,
+        assign_slots_to_pipelines, build_physical_indexing_plan,
         convert_scheduling_solution_to_physical_plan_single_node_single_source, SourceToSchedule,
         SourceToScheduleType,
     };
@@ -612,6 +684,98 @@ mod tests {
         }
     }
 
+    fn slots_per_pipeline(
+        plan: &PhysicalIndexingPlan,
+        source_uid: &SourceUid,
+    ) -> Vec<(PipelineUid, Vec<ShardId>)> {
+        let mut slots_per_pipeline: Vec<(PipelineUid, Vec<ShardId>)> = plan
+            .indexing_tasks_per_indexer()
+            .values()
+            .flatten()
+            .filter(|task| task.source_id == source_uid.source_id)
+            .map(|task| (task.pipeline_uid(), task.shard_ids.clone()))
+            .collect();
+        slots_per_pipeline.sort();
+        slots_per_pipeline
+    }
+
+    #[test]
+    fn test_build_physical_plan_with_slots() {
+        let source_uid = source_id();
+        let sources = [SourceToSchedule {
+            source_uid: source_uid.clone(),
+            source_type: SourceToScheduleType::NonShardedWithSlots {
+                num_pipelines: 3,
+                load_per_pipeline: NonZeroU32::new(3_200).unwrap(),
+            },
+        }];
+        let mut indexer_id_to_cpu_capacities = FnvHashMap::default();
+        indexer_id_to_cpu_capacities.insert("indexer1".to_string(), mcpu(4_000));
+        indexer_id_to_cpu_capacities.insert("indexer2".to_string(), mcpu(8_000));
+        let indexing_plan =
+            build_physical_indexing_plan(&sources, &indexer_id_to_cpu_capacities, None);
+
+        let pipeline_slots = slots_per_pipeline(&indexing_plan, &source_uid);
+        assert_eq!(pipeline_slots.len(), 3);
+
+        let mut slots: Vec<ShardId> = pipeline_slots
+            .iter()
+            .flat_map(|(_, slots)| slots.clone())
+            .collect();
+        slots.sort();
+        assert_eq!(
+            slots,
+            [ShardId::from(0), ShardId::from(1), ShardId::from(2)]
+        );
+
+        // The pipelines keep their slots when the plan is rebuilt.
+        let new_indexing_plan = build_physical_indexing_plan(
+            &sources,
+            &indexer_id_to_cpu_capacities,
+            Some(&indexing_plan),
+        );
+        assert_eq!(
+            slots_per_pipeline(&new_indexing_plan, &source_uid),
+            pipeline_slots
+        );
+    }
+
+    #[test]
+    fn test_assign_slots_to_pipelines() {
+        let source_uid = source_id();
+        let pipeline_uid_1 = PipelineUid::from_u128(1u128);
+        let pipeline_uid_2 = PipelineUid::from_u128(2u128);
+        let mut indexing_plan = PhysicalIndexingPlan::with_indexer_ids(&["indexer1".to_string()]);
+        // The first pipeline was previously alone and was assigned all the slots, plus a slot
+        // that no longer exists.
+        indexing_plan.add_indexing_task(
+            "indexer1",
+            IndexingTask {
+                index_uid: Some(source_uid.index_uid.clone()),
+                source_id: source_uid.source_id.clone(),
+                pipeline_uid: Some(pipeline_uid_1),
+                shard_ids: (0..4).map(ShardId::from).collect(),
+            },
+        );
+        indexing_plan.add_indexing_task(
+            "indexer1",
+            IndexingTask {
+                index_uid: Some(source_uid.index_uid.clone()),
+                source_id: source_uid.source_id.clone(),
+                pipeline_uid: Some(pipeline_uid_2),
+                shard_ids: Vec::new(),
+            },
+        );
+        assign_slots_to_pipelines(&source_uid, 3, &mut indexing_plan);
+        assert_eq!(
+            slots_per_pipeline(&indexing_plan, &source_uid),
+            [
+                (pipeline_uid_1, vec![ShardId::from(0), ShardId::from(1)]),
+                (pipeline_uid_2, vec![ShardId::from(2)]),
+            ]
+        );
+    }
+
     #[test]
     fn test_build_physical_plan() {
         let indexer1 = "indexer1".to_string();
@@ -62,9 +62,13 @@ fn index_metadata_for_test(
             "bootstrap.servers": "localhost:9092",
             }),
             enable_backfill_mode: true,
+            schema_registry_url: None,
         }),
         transform_config: None,
         input_format: SourceInputFormat::Json,
+        dead_letter_config: None,
+        multiline_config: None,
+        processors: Vec::new(),
     };
     index_metadata
         .sources
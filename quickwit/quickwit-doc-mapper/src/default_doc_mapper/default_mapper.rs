@@ -611,6 +611,7 @@ impl DocMapper for DefaultDocMapper {
         split_schema: Schema,
         query_ast: &QueryAst,
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
         build_query(
             query_ast,
@@ -618,6 +619,7 @@ impl DocMapper for DefaultDocMapper {
             self.tokenizer_manager(),
             &self.default_search_field_names[..],
             with_validation,
+            split_id_opt,
         )
     }
 
@@ -1637,7 +1639,7 @@ mod tests {
             .parse_user_query(doc_mapper.default_search_fields())
             .map_err(|err| err.to_string())?;
         let (query, _) = doc_mapper
-            .query(doc_mapper.schema(), &query_ast, true)
+            .query(doc_mapper.schema(), &query_ast, true, None)
             .map_err(|err| err.to_string())?;
         Ok(format!("{query:?}"))
     }
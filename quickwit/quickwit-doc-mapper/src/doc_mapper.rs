@@ -102,11 +102,13 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     ///
     /// Considering schema evolution, splits within an index can have different schema
     /// over time. So `split_schema` is the schema of the split the query is targeting.
+    /// `split_id_opt` is the ID of that split: `ids` queries match no documents without it.
     fn query(
         &self,
         split_schema: Schema,
         query_ast: &QueryAst,
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError>;
 
     /// Returns the timestamp field name.
@@ -370,7 +372,7 @@ mod tests {
         }
         .parse_user_query(&[])
         .unwrap();
-        let (query, _) = doc_mapper.query(schema, &query_ast, true).unwrap();
+        let (query, _) = doc_mapper.query(schema, &query_ast, true, None).unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"TermQuery(Term(field=2, type=Json, path=toto.titi, type=Str, "hello"))"#
@@ -389,7 +391,7 @@ mod tests {
         let query_ast = query_ast_from_user_text("toto.titi:hello", None)
             .parse_user_query(doc_mapper.default_search_fields())
             .unwrap();
-        let (query, _) = doc_mapper.query(schema, &query_ast, true).unwrap();
+        let (query, _) = doc_mapper.query(schema, &query_ast, true, None).unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"TermQuery(Term(field=1, type=Json, path=toto.titi, type=Str, "hello"))"#
@@ -408,7 +410,7 @@ mod tests {
         let query_ast = query_ast_from_user_text("toto:5", None)
             .parse_user_query(&[])
             .unwrap();
-        let (query, _) = doc_mapper.query(schema, &query_ast, true).unwrap();
+        let (query, _) = doc_mapper.query(schema, &query_ast, true, None).unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"BooleanQuery { subqueries: [(Should, TermQuery(Term(field=1, type=Json, path=toto, type=I64, 5))), (Should, TermQuery(Term(field=1, type=Json, path=toto, type=Str, "5")))] }"#
@@ -608,7 +610,7 @@ mod tests {
             field: "multilang".to_string(),
             value: "JPN:す".to_string(),
         });
-        let (query, _) = doc_mapper.query(schema, &query_ast, false).unwrap();
+        let (query, _) = doc_mapper.query(schema, &query_ast, false, None).unwrap();
         assert_eq!(
             format!("{query:?}"),
             r#"TermQuery(Term(field=2, type=Str, "JPN:す"))"#
@@ -22,8 +22,8 @@ use std::convert::Infallible;
 use std::ops::Bound;
 
 use quickwit_query::query_ast::{
-    FieldPresenceQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, QueryAstVisitor, RangeQuery,
-    TermSetQuery, WildcardQuery,
+    FieldPresenceQuery, FullTextQuery, FuzzyQuery, PhrasePrefixQuery, PrefixQuery, QueryAst,
+    QueryAstVisitor, RangeQuery, RegexQuery, TermSetQuery, WildcardQuery,
 };
 use quickwit_query::tokenizers::TokenizerManager;
 use quickwit_query::{find_field_or_hit_dynamic, InvalidQuery};
@@ -69,13 +69,29 @@ impl<'a> QueryAstVisitor<'a> for ExistsQueryFields {
 }
 
 /// Build a `Query` with field resolution & forbidding range clauses.
+///
+/// Without validation, the query is built against the schema of a split, and the queries on the
+/// fields missing from that schema, such as the fields added by a doc mapping update after the
+/// split was created, match no documents. `split_id_opt` is the ID of that split, used to resolve
+/// the document ids of `ids` queries.
 pub(crate) fn build_query(
     query_ast: &QueryAst,
     schema: Schema,
     tokenizer_manager: &TokenizerManager,
     search_fields: &[String],
     with_validation: bool,
+    split_id_opt: Option<&str>,
 ) -> Result<(Box<dyn Query>, WarmupInfo), QueryParserError> {
+    let split_query_ast;
+    let query_ast = if with_validation {
+        query_ast
+    } else {
+        let mut query_ast = query_ast.clone();
+        query_ast.match_none_on_missing_fields(&schema);
+        split_query_ast = query_ast;
+        &split_query_ast
+    };
+
     let mut range_query_fields = RangeQueryFields::default();
     // This cannot fail. The error type is Infallible.
     let _: Result<(), Infallible> = range_query_fields.visit(query_ast);
@@ -98,9 +114,10 @@ pub(crate) fn build_query(
         tokenizer_manager,
         search_fields,
         with_validation,
+        split_id_opt,
     )?;
 
-    let term_set_query_fields = extract_term_set_query_fields(query_ast, &schema)?;
+    let term_dict_fields = extract_term_dict_fields(query_ast, &schema)?;
     let term_ranges_grouped_by_field =
         extract_prefix_term_ranges(query_ast, &schema, tokenizer_manager)?;
 
@@ -115,7 +132,7 @@ pub(crate) fn build_query(
     });
 
     let warmup_info = WarmupInfo {
-        term_dict_fields: term_set_query_fields,
+        term_dict_fields,
         terms_grouped_by_field,
         term_ranges_grouped_by_field,
         fast_field_names,
@@ -132,21 +149,33 @@ fn is_fast_field(schema: &Schema, field_name: &str) -> bool {
     false
 }
 
-struct ExtractTermSetFields<'a> {
+/// Extracts the fields whose term dictionary needs to be entirely warmed up: the fields targeted
+/// by term set queries, and by the queries running an automaton over the term dictionary (regex,
+/// fuzzy, and wildcard queries that are not simple prefix queries).
+struct ExtractTermDictFields<'a> {
     term_dict_fields_to_warm_up: HashSet<Field>,
     schema: &'a Schema,
 }
 
-impl<'a> ExtractTermSetFields<'a> {
+impl<'a> ExtractTermDictFields<'a> {
     fn new(schema: &'a Schema) -> Self {
-        ExtractTermSetFields {
+        ExtractTermDictFields {
             term_dict_fields_to_warm_up: HashSet::new(),
             schema,
         }
     }
+
+    /// Adds the field targeted by an automaton query, if it exists. If it does not, the query
+    /// matches no documents and there is nothing to warm up.
+    fn add_automaton_query_field(&mut self, field_name: &str) {
+        if let Ok((field, _field_entry, _path)) = find_field_or_hit_dynamic(field_name, self.schema)
+        {
+            self.term_dict_fields_to_warm_up.insert(field);
+        }
+    }
 }
 
-impl<'a, 'b> QueryAstVisitor<'a> for ExtractTermSetFields<'b> {
+impl<'a, 'b> QueryAstVisitor<'a> for ExtractTermDictFields<'b> {
     type Err = anyhow::Error;
 
     fn visit_term_set(&mut self, term_set_query: &'a TermSetQuery) -> anyhow::Result<()> {
@@ -160,13 +189,30 @@ impl<'a, 'b> QueryAstVisitor<'a> for ExtractTermSetFields<'b> {
         }
         Ok(())
     }
+
+    fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> anyhow::Result<()> {
+        if !wildcard_query.is_prefix_pattern() {
+            self.add_automaton_query_field(&wildcard_query.field);
+        }
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, regex_query: &'a RegexQuery) -> anyhow::Result<()> {
+        self.add_automaton_query_field(&regex_query.field);
+        Ok(())
+    }
+
+    fn visit_fuzzy(&mut self, fuzzy_query: &'a FuzzyQuery) -> anyhow::Result<()> {
+        self.add_automaton_query_field(&fuzzy_query.field);
+        Ok(())
+    }
 }
 
-fn extract_term_set_query_fields(
+fn extract_term_dict_fields(
     query_ast: &QueryAst,
     schema: &Schema,
 ) -> anyhow::Result<HashSet<Field>> {
-    let mut visitor = ExtractTermSetFields::new(schema);
+    let mut visitor = ExtractTermDictFields::new(schema);
     visitor.visit(query_ast)?;
     Ok(visitor.term_dict_fields_to_warm_up)
 }
@@ -252,10 +298,21 @@ impl<'a, 'b: 'a> QueryAstVisitor<'a> for ExtractPrefixTermRanges<'b> {
     }
 
     fn visit_wildcard(&mut self, wildcard_query: &'a WildcardQuery) -> Result<(), Self::Err> {
+        if !wildcard_query.is_prefix_pattern() {
+            // The term dictionary of the field is entirely warmed up, see
+            // `ExtractTermDictFields`.
+            return Ok(());
+        }
         let (_, term) = wildcard_query.extract_prefix_term(self.schema, self.tokenizer_manager)?;
         self.add_prefix_term(term, u32::MAX, false);
         Ok(())
     }
+
+    fn visit_prefix(&mut self, prefix_query: &'a PrefixQuery) -> Result<(), Self::Err> {
+        let (_, term) = prefix_query.extract_prefix_term(self.schema, self.tokenizer_manager)?;
+        self.add_prefix_term(term, u32::MAX, false);
+        Ok(())
+    }
 }
 
 fn extract_prefix_term_ranges(
@@ -272,7 +329,9 @@ fn extract_prefix_term_ranges(
 mod test {
     use quickwit_datetime::{parse_date_time_str, DateTimeInputFormat};
     use quickwit_query::create_default_quickwit_tokenizer_manager;
-    use quickwit_query::query_ast::query_ast_from_user_text;
+    use quickwit_query::query_ast::{
+        query_ast_from_user_text, PrefixQuery, QueryAst, RegexQuery, WildcardQuery,
+    };
     use tantivy::columnar::MonotonicallyMappableToU64;
     use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
     use tantivy::{DateOptions, DateTime, DateTimePrecision};
@@ -342,6 +401,7 @@ mod test {
             &create_default_quickwit_tokenizer_manager(),
             &[],
             true,
+            None,
         );
         query_result
             .map(|query| format!("{:?}", query))
@@ -662,6 +722,7 @@ mod test {
             &create_default_quickwit_tokenizer_manager(),
             &[],
             true,
+            None,
         )
         .unwrap();
         assert_eq!(warmup_info.term_dict_fields.len(), 1);
@@ -675,8 +736,65 @@ mod test {
             &create_default_quickwit_tokenizer_manager(),
             &[],
             true,
+            None,
         )
         .unwrap();
         assert!(warmup_info.term_dict_fields.is_empty());
     }
+
+    #[test]
+    fn test_build_query_warmup_info_term_level_queries() {
+        let desc_field = tantivy::schema::Field::from_field_id(1);
+        let prefix_wildcard_query: QueryAst = WildcardQuery {
+            field: "desc".to_string(),
+            value: "hel*".to_string(),
+        }
+        .into();
+        let wildcard_query: QueryAst = WildcardQuery {
+            field: "desc".to_string(),
+            value: "h*o".to_string(),
+        }
+        .into();
+        let prefix_query: QueryAst = PrefixQuery {
+            field: "desc".to_string(),
+            value: "hel".to_string(),
+        }
+        .into();
+        let regex_query: QueryAst = RegexQuery {
+            field: "desc".to_string(),
+            regex: "h.*o".to_string(),
+        }
+        .into();
+
+        for query_ast in [&prefix_wildcard_query, &prefix_query] {
+            let (_, warmup_info) = build_query(
+                query_ast,
+                make_schema(true),
+                &create_default_quickwit_tokenizer_manager(),
+                &[],
+                true,
+                None,
+            )
+            .unwrap();
+            assert!(warmup_info.term_dict_fields.is_empty());
+            assert_eq!(warmup_info.term_ranges_grouped_by_field.len(), 1);
+            assert!(warmup_info
+                .term_ranges_grouped_by_field
+                .contains_key(&desc_field));
+        }
+        for query_ast in [&wildcard_query, &regex_query] {
+            let (_, warmup_info) = build_query(
+                query_ast,
+                make_schema(true),
+                &create_default_quickwit_tokenizer_manager(),
+                &[],
+                true,
+                None,
+            )
+            .unwrap();
+            assert_eq!(warmup_info.term_dict_fields.len(), 1);
+            assert!(warmup_info.term_dict_fields.contains(&desc_field));
+            assert!(warmup_info.term_ranges_grouped_by_field.is_empty());
+        }
+    }
 }
@@ -81,14 +81,17 @@ pub struct SplitRemovalInfo {
 ///   collected.
 /// * `deletion_grace_period` -  Threshold period after which a marked as deleted split can be
 ///   safely deleted.
+/// * `protected_split_ids` - Splits pinned by a point in time, which must not be deleted.
 /// * `dry_run` - Should this only return a list of affected files without performing deletion.
 /// * `progress` - For reporting progress (useful when called from within a quickwit actor).
+#[allow(clippy::too_many_arguments)]
 pub async fn run_garbage_collect(
     index_uid: IndexUid,
     storage: Arc<dyn Storage>,
     mut metastore: MetastoreServiceClient,
     staged_grace_period: Duration,
     deletion_grace_period: Duration,
+    protected_split_ids: HashSet<SplitId>,
     dry_run: bool,
     progress_opt: Option<&Progress>,
 ) -> anyhow::Result<SplitRemovalInfo> {
@@ -125,6 +128,7 @@ pub async fn run_garbage_collect(
 
         let candidate_entries: Vec<SplitInfo> = splits_marked_for_deletion
             .into_iter()
+            .filter(|split| !protected_split_ids.contains(&split.split_id))
             .map(|split| split.as_split_info())
             .collect();
         return Ok(SplitRemovalInfo {
@@ -156,6 +160,7 @@ pub async fn run_garbage_collect(
     let deleted_splits = delete_splits_marked_for_deletion(
         index_uid,
         updated_before_timestamp,
+        &protected_split_ids,
         storage,
         metastore,
         progress_opt,
@@ -167,25 +172,34 @@ pub async fn run_garbage_collect(
 #[instrument(skip(storage, metastore, progress_opt))]
 /// Removes any splits marked for deletion which haven't been
 /// updated after `updated_before_timestamp` in batches of 1000 splits.
+/// Splits listed in `protected_split_ids` are skipped.
 ///
 /// The aim of this is to spread the load out across a longer period
 /// rather than short, heavy bursts on the metastore and storage system itself.
 async fn delete_splits_marked_for_deletion(
     index_uid: IndexUid,
     updated_before_timestamp: i64,
+    protected_split_ids: &HashSet<SplitId>,
     storage: Arc<dyn Storage>,
     mut metastore: MetastoreServiceClient,
     progress_opt: Option<&Progress>,
 ) -> SplitRemovalInfo {
     let mut removed_splits = Vec::new();
     let mut failed_splits = Vec::new();
+    // Protected splits are never deleted, so they show up again in the following batches. We skip
+    // over them to make progress.
+    let mut num_protected_splits = 0;
 
     loop {
-        let query = ListSplitsQuery::for_index(index_uid.clone())
+        let mut query = ListSplitsQuery::for_index(index_uid.clone())
             .with_split_state(SplitState::MarkedForDeletion)
             .with_update_timestamp_lte(updated_before_timestamp)
             .with_limit(DELETE_SPLITS_BATCH_SIZE);
 
+        if num_protected_splits > 0 {
+            query = query.with_offset(num_protected_splits);
+        }
+
         let list_splits_request = match ListSplitsRequest::try_from_list_splits_query(&query) {
             Ok(request) => request,
             Err(error) => {
@@ -213,11 +227,25 @@ async fn delete_splits_marked_for_deletion(
                 }
             };
 
-        let num_splits_to_delete = splits_metadata_to_delete.len();
+        let num_splits_listed = splits_metadata_to_delete.len();
 
-        if num_splits_to_delete == 0 {
+        if num_splits_listed == 0 {
             break;
         }
+        let (protected_splits, splits_metadata_to_delete): (
+            Vec<SplitMetadata>,
+            Vec<SplitMetadata>,
+        ) = splits_metadata_to_delete
+            .into_iter()
+            .partition(|split| protected_split_ids.contains(&split.split_id));
+        num_protected_splits += protected_splits.len();
+
+        if splits_metadata_to_delete.is_empty() {
+            if num_splits_listed < DELETE_SPLITS_BATCH_SIZE {
+                break;
+            }
+            continue;
+        }
         let delete_splits_result = delete_splits_from_storage_and_metastore(
             index_uid.clone(),
             storage.clone(),
@@ -235,7 +263,7 @@ async fn delete_splits_marked_for_deletion(
                 break;
             }
         }
-        if num_splits_to_delete < DELETE_SPLITS_BATCH_SIZE {
+        if num_splits_listed < DELETE_SPLITS_BATCH_SIZE {
             break;
         }
     }
@@ -416,6 +444,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(30),
+            HashSet::new(),
             false,
             None,
         )
@@ -444,6 +473,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(0),
             Duration::from_secs(30),
+            HashSet::new(),
             false,
             None,
         )
@@ -522,6 +552,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(30),
+            HashSet::new(),
             false,
             None,
         )
@@ -550,6 +581,7 @@ mod tests {
             metastore.clone(),
             Duration::from_secs(30),
             Duration::from_secs(0),
+            HashSet::new(),
             false,
             None,
         )
@@ -571,6 +603,93 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_run_gc_skips_protected_splits() {
+        let storage = storage_for_test();
+        let mut metastore = metastore_for_test();
+
+        let index_id = "test-run-gc--index";
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+
+        let split_ids = ["test-run-gc--split-1", "test-run-gc--split-2"];
+        let splits_metadata = split_ids.iter().map(|split_id| SplitMetadata {
+            split_id: split_id.to_string(),
+            index_uid: index_uid.clone(),
+            ..Default::default()
+        });
+        let stage_splits_request =
+            StageSplitsRequest::try_from_splits_metadata(index_uid.clone(), splits_metadata)
+                .unwrap();
+        metastore.stage_splits(stage_splits_request).await.unwrap();
+        let mark_splits_for_deletion_request = MarkSplitsForDeletionRequest::new(
+            index_uid.clone(),
+            split_ids
+                .iter()
+                .map(|split_id| split_id.to_string())
+                .collect(),
+        );
+        metastore
+            .mark_splits_for_deletion(mark_splits_for_deletion_request)
+            .await
+            .unwrap();
+
+        let protected_split_ids = HashSet::from_iter([split_ids[0].to_string()]);
+
+        let split_removal_info = run_garbage_collect(
+            index_uid.clone(),
+            storage.clone(),
+            metastore.clone(),
+            Duration::from_secs(30),
+            Duration::from_secs(0),
+            protected_split_ids.clone(),
+            true,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(split_removal_info.removed_split_entries.len(), 1);
+        assert_eq!(
+            split_removal_info.removed_split_entries[0].split_id,
+            split_ids[1]
+        );
+
+        let split_removal_info = run_garbage_collect(
+            index_uid.clone(),
+            storage.clone(),
+            metastore.clone(),
+            Duration::from_secs(30),
+            Duration::from_secs(0),
+            protected_split_ids,
+            false,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(split_removal_info.removed_split_entries.len(), 1);
+        assert!(split_removal_info.failed_splits.is_empty());
+
+        let query = ListSplitsQuery::for_index(index_uid);
+        let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query).unwrap();
+        let splits = metastore
+            .list_splits(list_splits_request)
+            .await
+            .unwrap()
+            .collect_splits()
+            .await
+            .unwrap();
+        assert_eq!(splits.len(), 1);
+        assert_eq!(splits[0].split_id(), split_ids[0]);
+    }
+
     #[tokio::test]
     async fn test_run_gc_deletes_splits_with_no_split() {
         // Test that we make only 2 calls to the metastore.
@@ -586,6 +705,7 @@ mod tests {
             MetastoreServiceClient::from(metastore),
             Duration::from_secs(30),
             Duration::from_secs(30),
+            HashSet::new(),
             false,
             None,
         )
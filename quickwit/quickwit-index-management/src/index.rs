@@ -21,17 +21,19 @@ use std::path::Path;
 use std::time::Duration;
 
 use quickwit_common::fs::{empty_dir, get_cache_directory_path};
-use quickwit_config::{validate_identifier, IndexConfig, SourceConfig};
+use quickwit_config::{
+    validate_doc_mapping_update, validate_identifier, IndexConfig, SourceConfig,
+};
 use quickwit_indexing::check_source_connectivity;
 use quickwit_metastore::{
     AddSourceRequestExt, CreateIndexResponseExt, IndexMetadata, IndexMetadataResponseExt,
     ListSplitsQuery, ListSplitsRequestExt, MetastoreServiceStreamSplitsExt, SplitInfo,
-    SplitMetadata, SplitState,
+    SplitMetadata, SplitState, UpdateIndexRequestExt,
 };
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, EntityKind,
     IndexMetadataRequest, ListSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError,
-    MetastoreService, MetastoreServiceClient, ResetSourceCheckpointRequest,
+    MetastoreService, MetastoreServiceClient, ResetSourceCheckpointRequest, UpdateIndexRequest,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_proto::{ServiceError, ServiceErrorCode};
@@ -139,6 +141,61 @@ impl IndexService {
         Ok(index_metadata)
     }
 
+    /// Updates the doc mapping, the search settings, the retention policy, and the indexing
+    /// settings of the index identified by `index_id` from `index_config`. The index URI of an
+    /// existing index cannot be modified, and the doc mapping only accepts backward compatible
+    /// changes (see [`validate_doc_mapping_update`]).
+    pub async fn update_index(
+        &mut self,
+        index_id: &str,
+        index_config: IndexConfig,
+    ) -> Result<IndexMetadata, IndexServiceError> {
+        if index_config.index_id != index_id {
+            return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+                "index ID `{}` in the index config does not match the index ID `{index_id}` of \
+                 the updated index",
+                index_config.index_id
+            )));
+        }
+        let index_metadata_request = IndexMetadataRequest::for_index_id(index_id.to_string());
+        let current_index_metadata = self
+            .metastore
+            .index_metadata(index_metadata_request)
+            .await?
+            .deserialize_index_metadata()?;
+
+        if current_index_metadata.index_uri() != &index_config.index_uri {
+            return Err(IndexServiceError::InvalidConfig(anyhow::anyhow!(
+                "index URI of index `{index_id}` cannot be updated from `{}` to `{}`",
+                current_index_metadata.index_uri(),
+                index_config.index_uri
+            )));
+        }
+        validate_doc_mapping_update(
+            &current_index_metadata.index_config.doc_mapping,
+            &index_config.doc_mapping,
+        )
+        .map_err(|error| {
+            IndexServiceError::InvalidConfig(error.context(format!(
+                "doc mapping of index `{index_id}` cannot be updated"
+            )))
+        })?;
+        let update_index_request = UpdateIndexRequest::try_from_updates(
+            current_index_metadata.index_uid,
+            &index_config.doc_mapping,
+            &index_config.search_settings,
+            &index_config.retention_policy_opt,
+            &index_config.indexing_settings,
+        )?;
+        let index_metadata = self
+            .metastore
+            .update_index(update_index_request)
+            .await?
+            .deserialize_index_metadata()?;
+        info!(index_id, "index successfully updated");
+        Ok(index_metadata)
+    }
+
     /// Deletes the index specified with `index_id`.
     /// This is equivalent to running `rm -rf <index path>` for a local index or
     /// `aws s3 rm --recursive <index path>` for a remote Amazon S3 index.
@@ -234,6 +291,7 @@ impl IndexService {
             .await?
             .deserialize_index_metadata()?;
         let index_uid = index_metadata.index_uid.clone();
+        let protected_split_ids = index_metadata.point_in_time_split_ids();
         let index_config = index_metadata.into_index_config();
         let storage = self
             .storage_resolver
@@ -248,6 +306,7 @@ impl IndexService {
             // deletion_grace_period of zero, so that a cli call directly deletes splits after
             // marking to be deleted.
             Duration::ZERO,
+            protected_split_ids,
             dry_run,
             None,
         )
@@ -406,7 +465,9 @@ pub async fn validate_storage_uri(
 mod tests {
 
     use quickwit_common::uri::Uri;
-    use quickwit_config::{IndexConfig, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID};
+    use quickwit_config::{
+        IndexConfig, RetentionPolicy, CLI_SOURCE_ID, INGEST_API_SOURCE_ID, INGEST_V2_SOURCE_ID,
+    };
     use quickwit_metastore::{
         metastore_for_test, MetastoreServiceExt, SplitMetadata, StageSplitsRequestExt,
     };
@@ -460,6 +521,90 @@ mod tests {
         assert!(index_metadata_0.index_uid != index_metadata_1.index_uid);
     }
 
+    #[tokio::test]
+    async fn test_update_index() {
+        let mut metastore = metastore_for_test();
+        let storage_resolver = StorageResolver::for_test();
+        let mut index_service = IndexService::new(metastore.clone(), storage_resolver);
+        let index_id = "test-index";
+        let index_uri = "ram://indexes/test-index";
+        let index_config = IndexConfig::for_test(index_id, index_uri);
+        let index_uid = index_service
+            .create_index(index_config.clone(), false)
+            .await
+            .unwrap()
+            .index_uid;
+
+        let mut new_index_config = index_config.clone();
+        new_index_config.search_settings.default_search_fields = vec!["owner".to_string()];
+        new_index_config.indexing_settings.commit_timeout_secs = 5;
+        new_index_config.retention_policy_opt = Some(RetentionPolicy {
+            retention_period: "42 days".to_string(),
+            evaluation_schedule: "hourly".to_string(),
+        });
+        let index_metadata = index_service
+            .update_index(index_id, new_index_config.clone())
+            .await
+            .unwrap();
+        assert_eq!(index_metadata.index_uid, index_uid);
+        assert_eq!(index_metadata.index_config, new_index_config);
+        assert_eq!(index_metadata.doc_mapping_version, 0);
+
+        new_index_config.doc_mapping.store_source = false;
+        new_index_config.doc_mapping.field_mappings.push(
+            serde_json::from_str(r#"{"name": "severity", "type": "text", "tokenizer": "raw"}"#)
+                .unwrap(),
+        );
+        let index_metadata = index_service
+            .update_index(index_id, new_index_config.clone())
+            .await
+            .unwrap();
+        assert_eq!(index_metadata.index_config, new_index_config);
+        assert_eq!(index_metadata.doc_mapping_version, 1);
+
+        let index_metadata = metastore
+            .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+            .await
+            .unwrap()
+            .deserialize_index_metadata()
+            .unwrap();
+        assert_eq!(index_metadata.index_config, new_index_config);
+
+        let error = index_service
+            .update_index("test-index-foo", new_index_config.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidConfig(_)));
+
+        let mut invalid_index_config = new_index_config.clone();
+        invalid_index_config.index_uri = Uri::for_test("ram://indexes/test-index-foo");
+        let error = index_service
+            .update_index(index_id, invalid_index_config)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidConfig(_)));
+
+        let mut invalid_index_config = new_index_config.clone();
+        invalid_index_config.doc_mapping.field_mappings.remove(0);
+        let error = index_service
+            .update_index(index_id, invalid_index_config)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IndexServiceError::InvalidConfig(_)));
+
+        let error = index_service
+            .update_index(
+                "test-index-bar",
+                IndexConfig::for_test("test-index-bar", index_uri),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            IndexServiceError::Metastore(MetastoreError::NotFound(EntityKind::Index { .. }))
+        ));
+    }
+
     #[tokio::test]
     async fn test_delete_index() {
         let mut metastore = metastore_for_test();
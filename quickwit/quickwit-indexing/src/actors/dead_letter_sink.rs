@@ -0,0 +1,413 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use quickwit_actors::Mailbox;
+use quickwit_config::DeadLetterConfig;
+use quickwit_ingest::{CommitType, DocBatchBuilder, IngestApiService, IngestRequest};
+use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+use quickwit_proto::types::Position;
+use quickwit_storage::{Storage, StorageResolver};
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use ulid::Ulid;
+
+/// A document rejected by the doc processor, along with the reason of the rejection and its
+/// position in the source.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct DeadLetterRecord {
+    /// Time at which the document was rejected, formatted as RFC 3339.
+    pub timestamp: String,
+    pub index_id: String,
+    pub source_id: String,
+    /// One of `parsing_error`, `processor_error`, `transform_error`, `otlp_trace_parsing_error`,
+    /// or `doc_mapper_error`.
+    pub error_kind: &'static str,
+    pub error_message: String,
+    /// The original payload, as a UTF-8 string if possible, base64-encoded otherwise.
+    pub payload: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_encoding: Option<&'static str>,
+    pub source_position: DeadLetterSourcePosition,
+}
+
+/// Sources only track positions per batch, so the position of a rejected document is described by
+/// the checkpoint delta of its batch and its index within that batch.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct DeadLetterSourcePosition {
+    pub partitions: Vec<DeadLetterPartitionDelta>,
+    pub doc_index: usize,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct DeadLetterPartitionDelta {
+    pub partition_id: String,
+    pub from: Position,
+    pub to: Position,
+}
+
+/// A document rejected while processing a batch, before it is turned into a
+/// [`DeadLetterRecord`].
+#[derive(Debug)]
+pub(super) struct RejectedDoc {
+    pub payload: Bytes,
+    pub doc_index: usize,
+    pub error_kind: &'static str,
+    pub error_message: String,
+}
+
+impl DeadLetterRecord {
+    pub(super) fn build_batch(
+        index_id: &str,
+        source_id: &str,
+        checkpoint_delta: &SourceCheckpointDelta,
+        rejected_docs: Vec<RejectedDoc>,
+    ) -> Vec<DeadLetterRecord> {
+        let timestamp = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .expect("RFC 3339 formatting of the current time should not fail");
+        let partitions: Vec<DeadLetterPartitionDelta> = checkpoint_delta
+            .iter()
+            .map(|(partition_id, partition_delta)| DeadLetterPartitionDelta {
+                partition_id: partition_id.0.to_string(),
+                from: partition_delta.from,
+                to: partition_delta.to,
+            })
+            .collect();
+        rejected_docs
+            .into_iter()
+            .map(|rejected_doc| {
+                let (payload, payload_encoding) = match std::str::from_utf8(&rejected_doc.payload) {
+                    Ok(payload_str) => (payload_str.to_string(), None),
+                    Err(_) => (
+                        BASE64_STANDARD.encode(&rejected_doc.payload),
+                        Some("base64"),
+                    ),
+                };
+                DeadLetterRecord {
+                    timestamp: timestamp.clone(),
+                    index_id: index_id.to_string(),
+                    source_id: source_id.to_string(),
+                    error_kind: rejected_doc.error_kind,
+                    error_message: rejected_doc.error_message,
+                    payload,
+                    payload_encoding,
+                    source_position: DeadLetterSourcePosition {
+                        partitions: partitions.clone(),
+                        doc_index: rejected_doc.doc_index,
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+/// Destination of the documents rejected by the doc processor.
+///
+/// Sending dead-letter records is best effort: the doc processor logs failures and moves on, so
+/// that an unavailable dead-letter destination never stalls indexing.
+#[async_trait]
+pub trait DeadLetterSink: Send + Sync + 'static {
+    async fn send(&self, records: Vec<DeadLetterRecord>) -> anyhow::Result<()>;
+}
+
+/// Builds the dead-letter sink described by `dead_letter_config`.
+pub(super) async fn build_dead_letter_sink(
+    dead_letter_config: &DeadLetterConfig,
+    index_id: &str,
+    source_id: &str,
+    queues_dir_path: &Path,
+    storage_resolver: &StorageResolver,
+) -> anyhow::Result<Arc<dyn DeadLetterSink>> {
+    match dead_letter_config {
+        DeadLetterConfig::Index(index_params) => {
+            if index_params.index_id == index_id {
+                anyhow::bail!(
+                    "dead-letter index of source `{source_id}` must be different from index \
+                     `{index_id}`"
+                );
+            }
+            let ingest_api_service = quickwit_ingest::get_ingest_api_service(queues_dir_path)
+                .await
+                .context("dead-letter index requires the ingest API service")?;
+            let index_sink = IndexDeadLetterSink {
+                index_id: index_params.index_id.clone(),
+                ingest_api_service,
+            };
+            Ok(Arc::new(index_sink))
+        }
+        DeadLetterConfig::Storage(storage_params) => {
+            let storage = storage_resolver.resolve(&storage_params.uri).await?;
+            let storage_sink = StorageDeadLetterSink {
+                storage,
+                prefix: Path::new(index_id).join(source_id),
+            };
+            Ok(Arc::new(storage_sink))
+        }
+        #[cfg(feature = "kafka")]
+        DeadLetterConfig::Kafka(kafka_params) => {
+            let kafka_sink = KafkaDeadLetterSink::try_new(
+                kafka_params.topic.clone(),
+                kafka_params.client_params.clone(),
+            )?;
+            Ok(Arc::new(kafka_sink))
+        }
+        #[cfg(not(feature = "kafka"))]
+        DeadLetterConfig::Kafka(_) => {
+            anyhow::bail!(
+                "Quickwit was compiled without the `kafka` feature: Kafka dead-letter topics are \
+                 not supported"
+            )
+        }
+    }
+}
+
+/// Ingests dead-letter records into another index via the ingest API.
+struct IndexDeadLetterSink {
+    index_id: String,
+    ingest_api_service: Mailbox<IngestApiService>,
+}
+
+#[async_trait]
+impl DeadLetterSink for IndexDeadLetterSink {
+    async fn send(&self, records: Vec<DeadLetterRecord>) -> anyhow::Result<()> {
+        let mut doc_batch_builder = DocBatchBuilder::new(self.index_id.clone()).json_writer();
+        for record in &records {
+            doc_batch_builder.ingest_doc(record)?;
+        }
+        let ingest_request = IngestRequest {
+            doc_batches: vec![doc_batch_builder.build()],
+            commit: CommitType::Auto.into(),
+        };
+        self.ingest_api_service
+            .ask_for_res(ingest_request)
+            .await
+            .map_err(|error| {
+                anyhow!(
+                    "failed to ingest dead-letter records into index `{}`: {error}",
+                    self.index_id
+                )
+            })?;
+        Ok(())
+    }
+}
+
+/// Writes each batch of dead-letter records as an NDJSON file named
+/// `<index_id>/<source_id>/<ulid>.ndjson` under the configured storage URI.
+struct StorageDeadLetterSink {
+    storage: Arc<dyn Storage>,
+    prefix: PathBuf,
+}
+
+#[async_trait]
+impl DeadLetterSink for StorageDeadLetterSink {
+    async fn send(&self, records: Vec<DeadLetterRecord>) -> anyhow::Result<()> {
+        let mut payload = Vec::new();
+        for record in &records {
+            serde_json::to_writer(&mut payload, record)?;
+            payload.push(b'\n');
+        }
+        let file_path = self.prefix.join(format!("{}.ndjson", Ulid::new()));
+        self.storage
+            .put(&file_path, Box::new(payload))
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to write dead-letter file `{}` to `{}`",
+                    file_path.display(),
+                    self.storage.uri()
+                )
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "kafka")]
+use kafka_dead_letter_sink::KafkaDeadLetterSink;
+
+#[cfg(feature = "kafka")]
+mod kafka_dead_letter_sink {
+    use std::time::Duration;
+
+    use futures::future::try_join_all;
+    use rdkafka::producer::{FutureProducer, FutureRecord};
+    use serde_json::Value as JsonValue;
+
+    use super::*;
+    use crate::source::parse_client_params;
+
+    const KAFKA_QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Produces dead-letter records to a Kafka topic, keyed by source ID.
+    pub(super) struct KafkaDeadLetterSink {
+        topic: String,
+        producer: FutureProducer,
+    }
+
+    impl KafkaDeadLetterSink {
+        pub fn try_new(topic: String, client_params: JsonValue) -> anyhow::Result<Self> {
+            let client_params = if client_params.is_null() {
+                JsonValue::Object(Default::default())
+            } else {
+                client_params
+            };
+            let producer: FutureProducer = parse_client_params(client_params)?
+                .create()
+                .context("failed to create Kafka dead-letter producer")?;
+            Ok(Self { topic, producer })
+        }
+    }
+
+    #[async_trait]
+    impl DeadLetterSink for KafkaDeadLetterSink {
+        async fn send(&self, records: Vec<DeadLetterRecord>) -> anyhow::Result<()> {
+            let payloads: Vec<Vec<u8>> = records
+                .iter()
+                .map(serde_json::to_vec)
+                .collect::<serde_json::Result<_>>()?;
+            let delivery_futures = records.iter().zip(&payloads).map(|(record, payload)| {
+                let kafka_record = FutureRecord::to(&self.topic)
+                    .key(record.source_id.as_str())
+                    .payload(payload);
+                self.producer.send(kafka_record, KAFKA_QUEUE_TIMEOUT)
+            });
+            try_join_all(delivery_futures).await.map_err(|(error, _)| {
+                anyhow!(
+                    "failed to produce dead-letter records to topic `{}`: {error}",
+                    self.topic
+                )
+            })?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::IndexDeadLetterParams;
+    use quickwit_storage::RamStorage;
+
+    use super::*;
+
+    fn rejected_doc(payload: &'static [u8], doc_index: usize) -> RejectedDoc {
+        RejectedDoc {
+            payload: Bytes::from_static(payload),
+            doc_index,
+            error_kind: "parsing_error",
+            error_message: "doc parsing error: expected value at line 1 column 1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_dead_letter_record_build_batch() {
+        let checkpoint_delta = SourceCheckpointDelta::from_range(0..3);
+        let rejected_docs = vec![rejected_doc(b"not json", 0), rejected_doc(b"\xff\xfe", 2)];
+        let records = DeadLetterRecord::build_batch(
+            "test-index",
+            "test-source",
+            &checkpoint_delta,
+            rejected_docs,
+        );
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].index_id, "test-index");
+        assert_eq!(records[0].source_id, "test-source");
+        assert_eq!(records[0].error_kind, "parsing_error");
+        assert_eq!(records[0].payload, "not json");
+        assert!(records[0].payload_encoding.is_none());
+        assert_eq!(records[0].source_position.doc_index, 0);
+        assert_eq!(records[0].source_position.partitions.len(), 1);
+        assert_eq!(
+            records[0].source_position.partitions[0].from,
+            Position::Beginning
+        );
+        assert_eq!(
+            records[0].source_position.partitions[0].to,
+            Position::offset(2u64)
+        );
+        assert!(records[0].timestamp.ends_with('Z'));
+
+        assert_eq!(records[1].payload, "//4=");
+        assert_eq!(records[1].payload_encoding, Some("base64"));
+        assert_eq!(records[1].source_position.doc_index, 2);
+
+        let record_json = serde_json::to_value(&records[0]).unwrap();
+        assert!(record_json.get("payload_encoding").is_none());
+        assert_eq!(record_json["source_position"]["doc_index"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_storage_dead_letter_sink() {
+        let ram_storage = Arc::new(RamStorage::default());
+        let storage_sink = StorageDeadLetterSink {
+            storage: ram_storage.clone(),
+            prefix: Path::new("test-index").join("test-source"),
+        };
+        let checkpoint_delta = SourceCheckpointDelta::from_range(0..2);
+        let records = DeadLetterRecord::build_batch(
+            "test-index",
+            "test-source",
+            &checkpoint_delta,
+            vec![rejected_doc(b"foo", 0), rejected_doc(b"bar", 1)],
+        );
+        storage_sink.send(records).await.unwrap();
+
+        let files = ram_storage.list_files().await;
+        assert_eq!(files.len(), 1);
+        assert!(files[0].starts_with("test-index/test-source"));
+        assert_eq!(files[0].extension().unwrap(), "ndjson");
+
+        let file_content = ram_storage.get_all(&files[0]).await.unwrap();
+        let lines: Vec<serde_json::Value> = file_content
+            .as_slice()
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["payload"], "foo");
+        assert_eq!(lines[1]["payload"], "bar");
+        assert_eq!(lines[1]["source_position"]["doc_index"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_build_dead_letter_sink_rejects_own_index() {
+        let dead_letter_config = DeadLetterConfig::Index(IndexDeadLetterParams {
+            index_id: "test-index".to_string(),
+        });
+        let error = build_dead_letter_sink(
+            &dead_letter_config,
+            "test-index",
+            "test-source",
+            Path::new("./queues"),
+            &StorageResolver::for_test(),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("must be different"));
+    }
+}
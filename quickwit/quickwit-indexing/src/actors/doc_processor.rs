@@ -24,10 +24,11 @@ use std::sync::Arc;
 use anyhow::{bail, Context};
 use async_trait::async_trait;
 use bytes::Bytes;
+use csv::{ReaderBuilder, StringRecord};
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::rate_limited_tracing::rate_limited_warn;
 use quickwit_common::runtimes::RuntimeType;
-use quickwit_config::{SourceInputFormat, TransformConfig};
+use quickwit_config::{CsvColumnType, CsvInputFormatParams, SourceInputFormat, TransformConfig};
 use quickwit_doc_mapper::{DocMapper, DocParsingError, JsonObject};
 use quickwit_opentelemetry::otlp::{
     parse_otlp_spans_json, parse_otlp_spans_protobuf, JsonSpanIterator, OtlpTraceError,
@@ -38,7 +39,11 @@ use tantivy::schema::{Field, Value};
 use tantivy::{DateTime, TantivyDocument};
 use thiserror::Error;
 use tokio::runtime::Handle;
+use tracing::warn;
 
+use super::dead_letter_sink::{DeadLetterRecord, DeadLetterSink, RejectedDoc};
+use super::processors::{ProcessorChain, ProcessorError};
+use super::record_decoder::RecordDecoder;
 #[cfg(feature = "vrl")]
 use super::vrl_processing::*;
 use crate::actors::Indexer;
@@ -78,6 +83,171 @@ impl JsonDoc {
         let json_value = serde_json::to_value(vrl_doc.vrl_value)?;
         Self::try_from_json_value(json_value, vrl_doc.num_bytes)
     }
+
+    #[cfg(feature = "vrl")]
+    fn try_into_vrl_doc(self) -> Result<VrlDoc, DocProcessorError> {
+        let vrl_value = serde_json::from_value::<VrlValue>(JsonValue::Object(self.json_obj))?;
+        Ok(VrlDoc::new(vrl_value, self.num_bytes))
+    }
+}
+
+/// Converts CSV records into JSON objects.
+///
+/// Each raw doc may hold one or several records. When the input format expects a header row, the
+/// first record processed by the pipeline is consumed as the header. Header rows are only
+/// supported for file sources, which emit the header row first even when they resume from a
+/// checkpoint.
+struct CsvDocParser {
+    params: CsvInputFormatParams,
+    columns: Vec<String>,
+    expects_header: bool,
+}
+
+impl CsvDocParser {
+    fn new(params: CsvInputFormatParams) -> Self {
+        Self {
+            columns: params.columns.clone(),
+            expects_header: params.has_header(),
+            params,
+        }
+    }
+
+    fn parse_records(&mut self, raw_doc: &[u8]) -> Vec<Result<JsonDoc, DocProcessorError>> {
+        // The delimiter and quote characters are validated to be ASCII when the source config
+        // is loaded.
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.params.delimiter as u8)
+            .quote(self.params.quote as u8)
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(raw_doc);
+        let mut record = StringRecord::new();
+        let mut record_start = 0;
+        let mut json_doc_results = Vec::new();
+
+        loop {
+            match reader.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => {
+                    let parsing_error =
+                        DocProcessorError::Parsing(format!("invalid CSV record: {error}"));
+                    json_doc_results.push(Err(parsing_error));
+                    break;
+                }
+            }
+            let record_end = reader.position().byte() as usize;
+            let num_bytes = record_end - record_start;
+            record_start = record_end;
+
+            if self.expects_header {
+                self.expects_header = false;
+
+                if self.columns.is_empty() {
+                    self.columns = record.iter().map(|column| column.to_string()).collect();
+                }
+                continue;
+            }
+            json_doc_results.push(self.record_to_json_doc(&record, num_bytes));
+        }
+        json_doc_results
+    }
+
+    fn record_to_json_doc(
+        &self,
+        record: &StringRecord,
+        num_bytes: usize,
+    ) -> Result<JsonDoc, DocProcessorError> {
+        if record.len() > self.columns.len() {
+            return Err(DocProcessorError::Parsing(format!(
+                "CSV record has {} fields but only {} columns are defined",
+                record.len(),
+                self.columns.len()
+            )));
+        }
+        let mut json_obj = JsonObject::with_capacity(record.len());
+
+        // Records with fewer fields than columns are accepted: the trailing columns are missing.
+        for (column, field) in self.columns.iter().zip(record.iter()) {
+            let column_type = self
+                .params
+                .column_types
+                .get(column)
+                .copied()
+                .unwrap_or(CsvColumnType::String);
+
+            if let Some(json_value) = coerce_csv_field(column, field, column_type)? {
+                json_obj.insert(column.clone(), json_value);
+            }
+        }
+        Ok(JsonDoc::new(json_obj, num_bytes))
+    }
+}
+
+/// Parsers of the input formats that hold state (CSV header) or resources (Avro and Protobuf
+/// schemas).
+struct InputParsers {
+    csv_parser_opt: Option<CsvDocParser>,
+    record_decoder_opt: Option<RecordDecoder>,
+}
+
+impl InputParsers {
+    fn try_new(
+        input_format: &SourceInputFormat,
+        schema_registry_url_opt: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let csv_parser_opt = match input_format {
+            SourceInputFormat::Csv(csv_params) => Some(CsvDocParser::new(csv_params.clone())),
+            _ => None,
+        };
+        let record_decoder_opt = RecordDecoder::try_new(input_format, schema_registry_url_opt)?;
+        Ok(Self {
+            csv_parser_opt,
+            record_decoder_opt,
+        })
+    }
+}
+
+/// Coerces a CSV field to the type hinted for its column. Empty fields of non-string columns are
+/// treated as missing values.
+fn coerce_csv_field(
+    column: &str,
+    field: &str,
+    column_type: CsvColumnType,
+) -> Result<Option<JsonValue>, DocProcessorError> {
+    let field = match column_type {
+        CsvColumnType::String => return Ok(Some(JsonValue::String(field.to_string()))),
+        _ => field.trim(),
+    };
+    if field.is_empty() {
+        return Ok(None);
+    }
+    let json_value_opt = match column_type {
+        CsvColumnType::I64 => field.parse::<i64>().ok().map(JsonValue::from),
+        CsvColumnType::U64 => field.parse::<u64>().ok().map(JsonValue::from),
+        CsvColumnType::F64 => field
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(JsonValue::Number),
+        CsvColumnType::Bool => {
+            if field.eq_ignore_ascii_case("true") {
+                Some(JsonValue::Bool(true))
+            } else if field.eq_ignore_ascii_case("false") {
+                Some(JsonValue::Bool(false))
+            } else {
+                None
+            }
+        }
+        CsvColumnType::String => None,
+    };
+    match json_value_opt {
+        Some(json_value) => Ok(Some(json_value)),
+        None => Err(DocProcessorError::Parsing(format!(
+            "failed to coerce value `{field}` of CSV column `{column}` to `{}`",
+            column_type.as_str()
+        ))),
+    }
 }
 
 #[derive(Error, Debug)]
@@ -88,17 +258,39 @@ pub enum DocProcessorError {
     OltpTraceParsing(OtlpTraceError),
     #[error("doc parsing error: {0}")]
     Parsing(String),
+    #[error("{0}")]
+    Processor(ProcessorError),
     #[cfg(feature = "vrl")]
     #[error("VRL transform error: {0}")]
     Transform(VrlTerminate),
 }
 
+impl DocProcessorError {
+    /// Returns the label identifying the kind of error in metrics and dead-letter records.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DocProcessorError::DocMapperParsing(_) => "doc_mapper_error",
+            DocProcessorError::OltpTraceParsing(_) => "otlp_trace_parsing_error",
+            DocProcessorError::Parsing(_) => "parsing_error",
+            DocProcessorError::Processor(_) => "processor_error",
+            #[cfg(feature = "vrl")]
+            DocProcessorError::Transform(_) => "transform_error",
+        }
+    }
+}
+
 impl From<OtlpTraceError> for DocProcessorError {
     fn from(error: OtlpTraceError) -> Self {
         DocProcessorError::OltpTraceParsing(error)
     }
 }
 
+impl From<ProcessorError> for DocProcessorError {
+    fn from(error: ProcessorError) -> Self {
+        DocProcessorError::Processor(error)
+    }
+}
+
 impl From<DocParsingError> for DocProcessorError {
     fn from(error: DocParsingError) -> Self {
         DocProcessorError::DocMapperParsing(error)
@@ -119,7 +311,7 @@ impl From<FromUtf8Error> for DocProcessorError {
 
 #[cfg(feature = "vrl")]
 fn try_into_vrl_doc(
-    input_format: SourceInputFormat,
+    input_format: &SourceInputFormat,
     raw_doc: Bytes,
     num_bytes: usize,
 ) -> Result<VrlDoc, DocProcessorError> {
@@ -135,13 +327,17 @@ fn try_into_vrl_doc(
         SourceInputFormat::OtlpTraceJson | SourceInputFormat::OtlpTraceProtobuf => {
             panic!("OTP log or trace data does not support VRL transforms")
         }
+        SourceInputFormat::Csv(_) | SourceInputFormat::Avro(_) | SourceInputFormat::Protobuf(_) => {
+            panic!("CSV, Avro, and Protobuf records should be converted to JSON objects first")
+        }
     };
     let vrl_doc = VrlDoc::new(vrl_value, num_bytes);
     Ok(vrl_doc)
 }
 
 fn try_into_json_docs(
-    input_format: SourceInputFormat,
+    input_format: &SourceInputFormat,
+    input_parsers: &mut InputParsers,
     raw_doc: Bytes,
     num_bytes: usize,
 ) -> JsonDocIterator {
@@ -168,19 +364,53 @@ fn try_into_json_docs(
             });
             JsonDocIterator::from(json_doc_result)
         }
+        SourceInputFormat::Csv(_) => {
+            let csv_parser = input_parsers
+                .csv_parser_opt
+                .as_mut()
+                .expect("the CSV parser should be initialized for the CSV input format");
+            JsonDocIterator::from(csv_parser.parse_records(&raw_doc))
+        }
+        SourceInputFormat::Avro(_) | SourceInputFormat::Protobuf(_) => {
+            let record_decoder = input_parsers.record_decoder_opt.as_ref().expect(
+                "the record decoder should be initialized for the Avro and Protobuf input formats",
+            );
+            let json_doc_result = record_decoder
+                .decode(&raw_doc)
+                .and_then(|json_value| JsonDoc::try_from_json_value(json_value, num_bytes));
+            JsonDocIterator::from(json_doc_result)
+        }
     }
 }
 
 #[cfg(feature = "vrl")]
 fn parse_raw_doc(
-    input_format: SourceInputFormat,
+    input_format: &SourceInputFormat,
+    input_parsers: &mut InputParsers,
     raw_doc: Bytes,
     num_bytes: usize,
     vrl_program_opt: Option<&mut VrlProgram>,
 ) -> JsonDocIterator {
     let Some(vrl_program) = vrl_program_opt else {
-        return try_into_json_docs(input_format, raw_doc, num_bytes);
+        return try_into_json_docs(input_format, input_parsers, raw_doc, num_bytes);
     };
+    if matches!(
+        input_format,
+        SourceInputFormat::Csv(_) | SourceInputFormat::Avro(_) | SourceInputFormat::Protobuf(_)
+    ) {
+        // Records are converted to JSON objects first. A raw doc may hold several CSV records,
+        // which are transformed one by one.
+        let json_doc_results: Vec<Result<JsonDoc, DocProcessorError>> =
+            try_into_json_docs(input_format, input_parsers, raw_doc, num_bytes)
+                .map(|json_doc_result| {
+                    json_doc_result
+                        .and_then(JsonDoc::try_into_vrl_doc)
+                        .and_then(|vrl_doc| vrl_program.transform_doc(vrl_doc))
+                        .and_then(JsonDoc::try_from_vrl_doc)
+                })
+                .collect();
+        return JsonDocIterator::from(json_doc_results);
+    }
     let json_doc_result = try_into_vrl_doc(input_format, raw_doc, num_bytes)
         .and_then(|vrl_doc| vrl_program.transform_doc(vrl_doc))
         .and_then(JsonDoc::try_from_vrl_doc);
@@ -190,16 +420,18 @@ fn parse_raw_doc(
 
 #[cfg(not(feature = "vrl"))]
 fn parse_raw_doc(
-    input_format: SourceInputFormat,
+    input_format: &SourceInputFormat,
+    input_parsers: &mut InputParsers,
     raw_doc: Bytes,
     num_bytes: usize,
     _vrl_program_opt: Option<&mut VrlProgram>,
 ) -> JsonDocIterator {
-    try_into_json_docs(input_format, raw_doc, num_bytes)
+    try_into_json_docs(input_format, input_parsers, raw_doc, num_bytes)
 }
 
 enum JsonDocIterator {
     One(Option<Result<JsonDoc, DocProcessorError>>),
+    Many(std::vec::IntoIter<Result<JsonDoc, DocProcessorError>>),
     Spans(JsonSpanIterator),
 }
 
@@ -209,6 +441,7 @@ impl Iterator for JsonDocIterator {
     fn next(&mut self) -> Option<Self::Item> {
         match self {
             Self::One(opt) => opt.take(),
+            Self::Many(json_doc_results) => json_doc_results.next(),
             Self::Spans(spans) => spans
                 .next()
                 .map(|(json_value, num_bytes)| JsonDoc::try_from_json_value(json_value, num_bytes)),
@@ -227,6 +460,12 @@ where E: Into<DocProcessorError>
     }
 }
 
+impl From<Vec<Result<JsonDoc, DocProcessorError>>> for JsonDocIterator {
+    fn from(json_doc_results: Vec<Result<JsonDoc, DocProcessorError>>) -> Self {
+        Self::Many(json_doc_results.into_iter())
+    }
+}
+
 impl From<Result<JsonSpanIterator, OtlpTraceError>> for JsonDocIterator {
     fn from(result: Result<JsonSpanIterator, OtlpTraceError>) -> Self {
         match result {
@@ -241,14 +480,18 @@ pub struct DocProcessorCounters {
     index_id: String,
     source_id: String,
     /// Overall number of documents received, partitioned
-    /// into 4 categories:
+    /// into 6 categories:
     /// - number of docs that could not be parsed.
     /// - number of docs that could not be transformed.
     /// - number of docs for which the doc mapper returnd an error.
+    /// - number of docs for which a processor returned an error.
+    /// - number of docs dropped by a `drop_if` processor.
     /// - number of valid docs.
     pub num_doc_parsing_errors: AtomicU64,
     pub num_transform_errors: AtomicU64,
     pub num_oltp_trace_errors: AtomicU64,
+    pub num_processor_errors: AtomicU64,
+    pub num_dropped_docs: AtomicU64,
     pub num_valid_docs: AtomicU64,
 
     /// Number of bytes that went through the indexer
@@ -266,6 +509,8 @@ impl DocProcessorCounters {
             num_doc_parsing_errors: Default::default(),
             num_transform_errors: Default::default(),
             num_oltp_trace_errors: Default::default(),
+            num_processor_errors: Default::default(),
+            num_dropped_docs: Default::default(),
             num_valid_docs: Default::default(),
             num_bytes_total: Default::default(),
         }
@@ -277,6 +522,8 @@ impl DocProcessorCounters {
             + self.num_doc_parsing_errors.load(Ordering::Relaxed)
             + self.num_oltp_trace_errors.load(Ordering::Relaxed)
             + self.num_transform_errors.load(Ordering::Relaxed)
+            + self.num_processor_errors.load(Ordering::Relaxed)
+            + self.num_dropped_docs.load(Ordering::Relaxed)
     }
 
     /// Returns the overall number of docs that were sent to the indexer but were invalid.
@@ -286,6 +533,7 @@ impl DocProcessorCounters {
         self.num_doc_parsing_errors.load(Ordering::Relaxed)
             + self.num_oltp_trace_errors.load(Ordering::Relaxed)
             + self.num_transform_errors.load(Ordering::Relaxed)
+            + self.num_processor_errors.load(Ordering::Relaxed)
     }
 
     pub fn record_valid(&self, num_bytes: u64) {
@@ -302,26 +550,43 @@ impl DocProcessorCounters {
             .inc_by(num_bytes);
     }
 
+    /// Records a doc intentionally dropped by a `drop_if` processor.
+    pub fn record_dropped(&self, num_bytes: u64) {
+        self.num_dropped_docs.fetch_add(1, Ordering::Relaxed);
+        self.num_bytes_total.fetch_add(num_bytes, Ordering::Relaxed);
+
+        crate::metrics::INDEXER_METRICS
+            .processed_docs_total
+            .with_label_values([&self.index_id, "dropped"])
+            .inc();
+        crate::metrics::INDEXER_METRICS
+            .processed_bytes
+            .with_label_values([&self.index_id, "dropped"])
+            .inc_by(num_bytes);
+    }
+
     pub fn record_error(&self, error: DocProcessorError, num_bytes: u64) {
-        let label = match error {
-            DocProcessorError::DocMapperParsing(_) => {
+        let label = error.kind();
+        match error {
+            DocProcessorError::DocMapperParsing(_) | DocProcessorError::Parsing(_) => {
                 self.num_doc_parsing_errors.fetch_add(1, Ordering::Relaxed);
-                "doc_mapper_error"
             }
             DocProcessorError::OltpTraceParsing(_) => {
                 self.num_oltp_trace_errors.fetch_add(1, Ordering::Relaxed);
-                "otlp_trace_parsing_error"
             }
-            DocProcessorError::Parsing(_) => {
-                self.num_doc_parsing_errors.fetch_add(1, Ordering::Relaxed);
-                "parsing_error"
+            DocProcessorError::Processor(processor_error) => {
+                self.num_processor_errors.fetch_add(1, Ordering::Relaxed);
+
+                crate::metrics::INDEXER_METRICS
+                    .processor_errors_total
+                    .with_label_values([&self.index_id, &processor_error.processor_id])
+                    .inc();
             }
             #[cfg(feature = "vrl")]
             DocProcessorError::Transform(_) => {
                 self.num_transform_errors.fetch_add(1, Ordering::Relaxed);
-                "transform_error"
             }
-        };
+        }
         crate::metrics::INDEXER_METRICS
             .processed_docs_total
             .with_label_values([&self.index_id, label])
@@ -345,6 +610,9 @@ pub struct DocProcessor {
     #[cfg(feature = "vrl")]
     transform_opt: Option<VrlProgram>,
     input_format: SourceInputFormat,
+    input_parsers: InputParsers,
+    processor_chain_opt: Option<ProcessorChain>,
+    dead_letter_sink_opt: Option<Arc<dyn DeadLetterSink>>,
 }
 
 impl DocProcessor {
@@ -355,11 +623,14 @@ impl DocProcessor {
         indexer_mailbox: Mailbox<Indexer>,
         transform_config_opt: Option<TransformConfig>,
         input_format: SourceInputFormat,
+        schema_registry_url_opt: Option<String>,
     ) -> anyhow::Result<Self> {
         let timestamp_field_opt = extract_timestamp_field(&*doc_mapper)?;
         if cfg!(not(feature = "vrl")) && transform_config_opt.is_some() {
             bail!("VRL is not enabled. please recompile with the `vrl` feature")
         }
+        let input_parsers =
+            InputParsers::try_new(&input_format, schema_registry_url_opt.as_deref())?;
         let doc_processor = Self {
             doc_mapper,
             indexer_mailbox,
@@ -371,10 +642,29 @@ impl DocProcessor {
                 .map(VrlProgram::try_from_transform_config)
                 .transpose()?,
             input_format,
+            input_parsers,
+            processor_chain_opt: None,
+            dead_letter_sink_opt: None,
         };
         Ok(doc_processor)
     }
 
+    /// Sends the documents rejected by this doc processor to `dead_letter_sink` instead of
+    /// dropping them.
+    pub fn with_dead_letter_sink(mut self, dead_letter_sink: Arc<dyn DeadLetterSink>) -> Self {
+        self.dead_letter_sink_opt = Some(dead_letter_sink);
+        self
+    }
+
+    /// Applies `processor_chain` to the documents, after the VRL transform if any, and before doc
+    /// mapping.
+    pub(crate) fn with_processor_chain(mut self, processor_chain: ProcessorChain) -> Self {
+        if !processor_chain.is_empty() {
+            self.processor_chain_opt = Some(processor_chain);
+        }
+        self
+    }
+
     // Extract a timestamp from a tantivy document.
     //
     // If the timestamp is set up in the docmapper and the timestamp is missing,
@@ -395,23 +685,41 @@ impl DocProcessor {
         Ok(Some(timestamp))
     }
 
-    fn process_raw_doc(&mut self, raw_doc: Bytes, processed_docs: &mut Vec<ProcessedDoc>) {
+    fn process_raw_doc(
+        &mut self,
+        raw_doc: Bytes,
+        doc_index: usize,
+        processed_docs: &mut Vec<ProcessedDoc>,
+        rejected_docs: &mut Vec<RejectedDoc>,
+    ) {
         let num_bytes = raw_doc.len();
+        // `Bytes` clones are cheap, but we only keep the payload around when it may be needed.
+        let payload_opt = self.dead_letter_sink_opt.as_ref().map(|_| raw_doc.clone());
 
         #[cfg(feature = "vrl")]
         let transform_opt = self.transform_opt.as_mut();
         #[cfg(not(feature = "vrl"))]
         let transform_opt: Option<&mut VrlProgram> = None;
 
-        for json_doc_result in parse_raw_doc(self.input_format, raw_doc, num_bytes, transform_opt) {
+        let json_doc_results = parse_raw_doc(
+            &self.input_format,
+            &mut self.input_parsers,
+            raw_doc,
+            num_bytes,
+            transform_opt,
+        );
+        for json_doc_result in json_doc_results {
             let processed_doc_result =
                 json_doc_result.and_then(|json_doc| self.process_json_doc(json_doc));
 
             match processed_doc_result {
-                Ok(processed_doc) => {
+                Ok(Some(processed_doc)) => {
                     self.counters.record_valid(processed_doc.num_bytes as u64);
                     processed_docs.push(processed_doc);
                 }
+                Ok(None) => {
+                    self.counters.record_dropped(num_bytes as u64);
+                }
                 Err(error) => {
                     rate_limited_warn!(
                         limit_per_min = 5,
@@ -420,23 +728,44 @@ impl DocProcessor {
                         "{}",
                         error
                     );
+                    if let Some(payload) = &payload_opt {
+                        rejected_docs.push(RejectedDoc {
+                            payload: payload.clone(),
+                            doc_index,
+                            error_kind: error.kind(),
+                            error_message: error.to_string(),
+                        });
+                    }
                     self.counters.record_error(error, num_bytes as u64);
                 }
             }
         }
     }
 
-    fn process_json_doc(&self, json_doc: JsonDoc) -> Result<ProcessedDoc, DocProcessorError> {
-        let num_bytes = json_doc.num_bytes;
+    /// Returns `None` if the document was dropped by a `drop_if` processor.
+    fn process_json_doc(
+        &self,
+        json_doc: JsonDoc,
+    ) -> Result<Option<ProcessedDoc>, DocProcessorError> {
+        let JsonDoc {
+            mut json_obj,
+            num_bytes,
+        } = json_doc;
 
-        let (partition, doc) = self.doc_mapper.doc_from_json_obj(json_doc.json_obj)?;
+        if let Some(processor_chain) = &self.processor_chain_opt {
+            if !processor_chain.process(&mut json_obj)? {
+                return Ok(None);
+            }
+        }
+        let (partition, doc) = self.doc_mapper.doc_from_json_obj(json_obj)?;
         let timestamp_opt = self.extract_timestamp(&doc)?;
-        Ok(ProcessedDoc {
+        let processed_doc = ProcessedDoc {
             doc,
             timestamp_opt,
             partition,
             num_bytes,
-        })
+        };
+        Ok(Some(processed_doc))
     }
 }
 
@@ -505,12 +834,39 @@ impl Handler<RawDocBatch> for DocProcessor {
         if self.publish_lock.is_dead() {
             return Ok(());
         }
+        if let Some(record_decoder) = &mut self.input_parsers.record_decoder_opt {
+            ctx.protect_future(record_decoder.fetch_missing_schemas(&raw_doc_batch.docs))
+                .await?;
+        }
         let mut processed_docs: Vec<ProcessedDoc> = Vec::with_capacity(raw_doc_batch.docs.len());
-        for raw_doc in raw_doc_batch.docs {
+        let mut rejected_docs: Vec<RejectedDoc> = Vec::new();
+        for (doc_index, raw_doc) in raw_doc_batch.docs.into_iter().enumerate() {
             let _protected_zone_guard = ctx.protect_zone();
-            self.process_raw_doc(raw_doc, &mut processed_docs);
+            self.process_raw_doc(raw_doc, doc_index, &mut processed_docs, &mut rejected_docs);
             ctx.record_progress();
         }
+        if let Some(dead_letter_sink) = &self.dead_letter_sink_opt {
+            if !rejected_docs.is_empty() {
+                let dead_letter_records = DeadLetterRecord::build_batch(
+                    &self.counters.index_id,
+                    &self.counters.source_id,
+                    &raw_doc_batch.checkpoint_delta,
+                    rejected_docs,
+                );
+                let num_records = dead_letter_records.len();
+                if let Err(error) = ctx
+                    .protect_future(dead_letter_sink.send(dead_letter_records))
+                    .await
+                {
+                    warn!(
+                        index_id = self.counters.index_id,
+                        source_id = self.counters.source_id,
+                        "failed to send {num_records} rejected document(s) to dead-letter sink: \
+                         {error:#}"
+                    );
+                }
+            }
+        }
         let processed_doc_batch = ProcessedDocBatch {
             docs: processed_docs,
             checkpoint_delta: raw_doc_batch.checkpoint_delta,
@@ -566,6 +922,7 @@ mod tests {
     use quickwit_opentelemetry::otlp::OtlpGrpcTracesService;
     use quickwit_proto::opentelemetry::proto::collector::trace::v1::ExportTraceServiceRequest;
     use quickwit_proto::opentelemetry::proto::trace::v1::{ResourceSpans, ScopeSpans, Span};
+    use quickwit_proto::types::Position;
     use serde_json::Value as JsonValue;
     use tantivy::schema::NamedFieldDocument;
     use tantivy::Document;
@@ -587,6 +944,7 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -674,6 +1032,7 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -730,6 +1089,7 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -762,6 +1122,7 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::Json,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -808,6 +1169,7 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::OtlpTraceJson,
+            None,
         )
         .unwrap();
 
@@ -888,6 +1250,7 @@ mod tests {
             indexer_mailbox,
             None,
             SourceInputFormat::OtlpTraceProtobuf,
+            None,
         )
         .unwrap();
 
@@ -953,6 +1316,283 @@ mod tests {
         assert!(matches!(exit_status, ActorExitStatus::Success));
         universe.assert_quit().await;
     }
+
+    #[test]
+    fn test_csv_doc_parser_header_row() {
+        let mut csv_params = CsvInputFormatParams::default();
+        csv_params
+            .column_types
+            .insert("status".to_string(), CsvColumnType::U64);
+        csv_params
+            .column_types
+            .insert("cached".to_string(), CsvColumnType::Bool);
+        let mut csv_parser = CsvDocParser::new(csv_params);
+
+        let json_docs = csv_parser.parse_records(b"host,status,cached,path");
+        assert!(json_docs.is_empty());
+
+        let json_docs: Vec<JsonDoc> = csv_parser
+            .parse_records(b"cdn-1,200,TRUE,\"/index.html?a=1,b=2\"\ncdn-2, 404 ,,")
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(json_docs.len(), 2);
+        assert_eq!(
+            JsonValue::Object(json_docs[0].json_obj.clone()),
+            serde_json::json!({
+                "host": "cdn-1",
+                "status": 200,
+                "cached": true,
+                "path": "/index.html?a=1,b=2",
+            })
+        );
+        assert_eq!(json_docs[0].num_bytes, 37);
+        assert_eq!(
+            JsonValue::Object(json_docs[1].json_obj.clone()),
+            serde_json::json!({
+                "host": "cdn-2",
+                "status": 404,
+                "path": "",
+            })
+        );
+        // Only the first record is a header row: records identical to the header are documents.
+        let json_docs = csv_parser.parse_records(b"host,status,cached,path");
+        let DocProcessorError::Parsing(error) = json_docs[0].as_ref().err().unwrap() else {
+            panic!("expected parsing error");
+        };
+        assert_eq!(
+            error,
+            "failed to coerce value `status` of CSV column `status` to `u64`"
+        );
+    }
+
+    #[test]
+    fn test_csv_doc_parser_explicit_columns() {
+        let mut csv_params = CsvInputFormatParams::tsv()
+            .with_columns(vec!["timestamp".to_string(), "amount".to_string()], false);
+        csv_params
+            .column_types
+            .insert("timestamp".to_string(), CsvColumnType::I64);
+        csv_params
+            .column_types
+            .insert("amount".to_string(), CsvColumnType::F64);
+        let mut csv_parser = CsvDocParser::new(csv_params);
+
+        let json_docs = csv_parser.parse_records(b"1628837062\t12.5");
+        assert_eq!(json_docs.len(), 1);
+        assert_eq!(
+            JsonValue::Object(json_docs[0].as_ref().unwrap().json_obj.clone()),
+            serde_json::json!({"timestamp": 1628837062, "amount": 12.5})
+        );
+        let json_docs = csv_parser.parse_records(b"1628837062\tfree");
+        let DocProcessorError::Parsing(error) = json_docs[0].as_ref().err().unwrap() else {
+            panic!("expected parsing error");
+        };
+        assert_eq!(
+            error,
+            "failed to coerce value `free` of CSV column `amount` to `f64`"
+        );
+        let json_docs = csv_parser.parse_records(b"1628837062\t12.5\tEUR");
+        let DocProcessorError::Parsing(error) = json_docs[0].as_ref().err().unwrap() else {
+            panic!("expected parsing error");
+        };
+        assert_eq!(
+            error,
+            "CSV record has 3 fields but only 2 columns are defined"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_csv_input() {
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let mut csv_params = CsvInputFormatParams::tsv();
+        csv_params
+            .column_types
+            .insert("timestamp".to_string(), CsvColumnType::I64);
+        csv_params
+            .column_types
+            .insert("response_time".to_string(), CsvColumnType::F64);
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper.clone(),
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Csv(csv_params),
+            None,
+        )
+        .unwrap();
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    "body\ttimestamp\tresponse_date\tresponse_time\tresponse_payload",
+                    "happy\t1628837062\t2021-12-19T16:39:59+00:00\t2\tYWJj",
+                    "happy2\t1628837062\t2021-12-19T16:40:57+00:00\tslow\tYWJj",
+                ],
+                0..3,
+            ))
+            .await
+            .unwrap();
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_doc_parsing_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 1);
+
+        let batch = indexer_inbox.drain_for_test_typed::<ProcessedDocBatch>();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].docs.len(), 1);
+
+        let schema = doc_mapper.schema();
+        let NamedFieldDocument(named_field_doc_map) = batch[0].docs[0].doc.to_named_doc(&schema);
+        let doc_json = JsonValue::Object(doc_mapper.doc_to_json(named_field_doc_map).unwrap());
+        assert_eq!(doc_json["_source"]["timestamp"], 1628837062);
+        assert_eq!(doc_json["_source"]["response_time"], 2.0);
+        universe.assert_quit().await;
+    }
+
+    #[derive(Default)]
+    struct DeadLetterSinkForTest {
+        records: std::sync::Mutex<Vec<DeadLetterRecord>>,
+    }
+
+    #[async_trait]
+    impl DeadLetterSink for DeadLetterSinkForTest {
+        async fn send(&self, records: Vec<DeadLetterRecord>) -> anyhow::Result<()> {
+            self.records.lock().unwrap().extend(records);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_dead_letter_sink() {
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let dead_letter_sink = Arc::new(DeadLetterSinkForTest::default());
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper,
+            indexer_mailbox,
+            None,
+            SourceInputFormat::Json,
+            None,
+        )
+        .unwrap()
+        .with_dead_letter_sink(dead_letter_sink.clone());
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    r#"{"body": "happy", "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#, // missing timestamp
+                    r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:59+00:00", "response_time": 2, "response_payload": "YWJj"}"#, // ok
+                    "{", // invalid json
+                ],
+                0..3,
+            ))
+            .await
+            .unwrap();
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_doc_parsing_errors.load(Ordering::Relaxed), 2);
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 1);
+
+        let batch = indexer_inbox.drain_for_test_typed::<ProcessedDocBatch>();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].docs.len(), 1);
+
+        let records = dead_letter_sink.records.lock().unwrap().clone();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].index_id, "my-index");
+        assert_eq!(records[0].source_id, "my-source");
+        assert_eq!(records[0].error_kind, "doc_mapper_error");
+        assert!(records[0]
+            .payload
+            .starts_with(r#"{"body": "happy", "response_date""#));
+        assert_eq!(records[0].source_position.doc_index, 0);
+
+        assert_eq!(records[1].error_kind, "parsing_error");
+        assert_eq!(records[1].payload, "{");
+        assert_eq!(records[1].source_position.doc_index, 2);
+        assert_eq!(
+            records[1].source_position.partitions[0].to,
+            Position::offset(2u64)
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_doc_processor_processor_chain() {
+        let universe = Universe::with_accelerated_time();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let doc_mapper = Arc::new(default_doc_mapper_for_test());
+        let processor_configs = serde_json::from_value(serde_json::json!([
+            {
+                "type": "grok",
+                "field": "plain_text",
+                "patterns": ["^%{NUMBER:timestamp:int} %{GREEDYDATA:body}$"]
+            },
+            {"type": "remove", "fields": ["plain_text"]},
+            {"type": "drop_if", "field": "body", "equals": "healthcheck"},
+        ]))
+        .unwrap();
+        let processor_chain = ProcessorChain::try_new(&processor_configs).unwrap();
+        let doc_processor = DocProcessor::try_new(
+            "my-index".to_string(),
+            "my-source".to_string(),
+            doc_mapper.clone(),
+            indexer_mailbox,
+            None,
+            SourceInputFormat::PlainText,
+            None,
+        )
+        .unwrap()
+        .with_processor_chain(processor_chain);
+        let (doc_processor_mailbox, doc_processor_handle) =
+            universe.spawn_builder().spawn(doc_processor);
+        doc_processor_mailbox
+            .send_message(RawDocBatch::for_test(
+                &[
+                    "1628837062 happy",
+                    "1628837063 healthcheck",
+                    "not a log line",
+                ],
+                0..3,
+            ))
+            .await
+            .unwrap();
+        let counters = doc_processor_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(counters.num_valid_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_dropped_docs.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_processor_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(counters.num_processed_docs(), 3);
+        assert_eq!(counters.num_invalid_docs(), 1);
+
+        let batch = indexer_inbox.drain_for_test_typed::<ProcessedDocBatch>();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].docs.len(), 1);
+
+        let schema = doc_mapper.schema();
+        let NamedFieldDocument(named_field_doc_map) = batch[0].docs[0].doc.to_named_doc(&schema);
+        let doc_json = JsonValue::Object(doc_mapper.doc_to_json(named_field_doc_map).unwrap());
+        assert_eq!(doc_json["_source"]["body"], "happy");
+        assert_eq!(doc_json["_source"]["timestamp"], 1628837062);
+        assert!(doc_json["_source"].get("plain_text").is_none());
+        universe.assert_quit().await;
+    }
 }
 
 #[cfg(feature = "vrl")]
@@ -981,6 +1621,7 @@ mod tests_vrl {
             indexer_mailbox,
             Some(transform_config),
             SourceInputFormat::Json,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
@@ -1071,6 +1712,7 @@ mod tests_vrl {
             indexer_mailbox,
             Some(transform_config),
             SourceInputFormat::PlainText,
+            None,
         )
         .unwrap();
         let (doc_processor_mailbox, doc_processor_handle) =
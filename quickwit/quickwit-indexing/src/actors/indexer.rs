@@ -93,6 +93,7 @@ pub struct IndexerCounters {
 
 struct IndexerState {
     pipeline_id: IndexingPipelineId,
+    doc_mapping_version: u64,
     metastore: MetastoreServiceClient,
     indexing_directory: TempDirectory,
     indexing_settings: IndexingSettings,
@@ -130,6 +131,7 @@ impl IndexerState {
         let indexed_split = IndexedSplitBuilder::new_in_dir(
             self.pipeline_id.clone(),
             partition_id,
+            self.doc_mapping_version,
             last_delete_opstamp,
             self.indexing_directory.clone(),
             index_builder,
@@ -519,6 +521,7 @@ impl Indexer {
     pub fn new(
         pipeline_id: IndexingPipelineId,
         doc_mapper: Arc<dyn DocMapper>,
+        doc_mapping_version: u64,
         metastore: MetastoreServiceClient,
         indexing_directory: TempDirectory,
         indexing_settings: IndexingSettings,
@@ -539,6 +542,7 @@ impl Indexer {
         Self {
             indexer_state: IndexerState {
                 pipeline_id,
+                doc_mapping_version,
                 metastore: metastore.clone(),
                 indexing_directory,
                 indexing_settings,
@@ -762,6 +766,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -898,6 +903,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -974,6 +980,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1056,6 +1063,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1143,6 +1151,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1226,6 +1235,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1321,6 +1331,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1392,6 +1403,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1464,6 +1476,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1528,6 +1541,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(metastore),
             indexing_directory,
             indexing_settings,
@@ -1596,6 +1610,7 @@ mod tests {
         let indexer = Indexer::new(
             pipeline_id,
             doc_mapper,
+            0,
             MetastoreServiceClient::from(mock_metastore),
             indexing_directory,
             indexing_settings,
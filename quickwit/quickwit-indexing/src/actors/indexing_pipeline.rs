@@ -30,7 +30,7 @@ use quickwit_actors::{
 use quickwit_common::pubsub::EventBroker;
 use quickwit_common::temp_dir::TempDirectory;
 use quickwit_common::KillSwitch;
-use quickwit_config::{IndexingSettings, SourceConfig};
+use quickwit_config::{IndexingSettings, SourceConfig, SourceParams};
 use quickwit_doc_mapper::DocMapper;
 use quickwit_ingest::IngesterPool;
 use quickwit_metastore::IndexMetadataResponseExt;
@@ -44,12 +44,13 @@ use tokio::sync::Semaphore;
 use tracing::{debug, error, info, instrument};
 
 use super::MergePlanner;
+use crate::actors::dead_letter_sink::build_dead_letter_sink;
 use crate::actors::doc_processor::DocProcessor;
 use crate::actors::index_serializer::IndexSerializer;
 use crate::actors::publisher::PublisherType;
 use crate::actors::sequencer::Sequencer;
 use crate::actors::uploader::UploaderType;
-use crate::actors::{Indexer, Packager, Publisher, Uploader};
+use crate::actors::{Indexer, Packager, ProcessorChain, Publisher, Uploader};
 use crate::merge_policy::MergePolicy;
 use crate::models::IndexingStatistics;
 use crate::source::{
@@ -394,6 +395,7 @@ impl IndexingPipeline {
         let indexer = Indexer::new(
             self.params.pipeline_id.clone(),
             self.params.doc_mapper.clone(),
+            self.params.doc_mapping_version,
             self.params.metastore.clone(),
             self.params.indexing_directory.clone(),
             self.params.indexing_settings.clone(),
@@ -410,14 +412,37 @@ impl IndexingPipeline {
             .set_kill_switch(self.kill_switch.clone())
             .spawn(indexer);
 
+        let schema_registry_url_opt = match &self.params.source_config.source_params {
+            SourceParams::Kafka(kafka_params) => kafka_params.schema_registry_url.clone(),
+            _ => None,
+        };
         let doc_processor = DocProcessor::try_new(
             index_id.to_string(),
             source_id.to_string(),
             self.params.doc_mapper.clone(),
             indexer_mailbox,
             self.params.source_config.transform_config.clone(),
-            self.params.source_config.input_format,
-        )?;
+            self.params.source_config.input_format.clone(),
+            schema_registry_url_opt,
+        )?
+        .with_processor_chain(ProcessorChain::try_new(
+            &self.params.source_config.processors,
+        )?);
+        let doc_processor =
+            if let Some(dead_letter_config) = &self.params.source_config.dead_letter_config {
+                let dead_letter_sink = ctx
+                    .protect_future(build_dead_letter_sink(
+                        dead_letter_config,
+                        index_id,
+                        source_id,
+                        &self.params.queues_dir_path,
+                        &self.params.source_storage_resolver,
+                    ))
+                    .await?;
+                doc_processor.with_dead_letter_sink(dead_letter_sink)
+            } else {
+                doc_processor
+            };
         let (doc_processor_mailbox, doc_processor_handle) = ctx
             .spawn_actor()
             .set_backpressure_micros_counter(
@@ -582,6 +607,7 @@ pub struct IndexingPipelineParams {
 
     // Indexing-related parameters
     pub doc_mapper: Arc<dyn DocMapper>,
+    pub doc_mapping_version: u64,
     pub indexing_directory: TempDirectory,
     pub indexing_settings: IndexingSettings,
     pub split_store: IndexingSplitStore,
@@ -706,6 +732,9 @@ mod tests {
             source_params: SourceParams::file(PathBuf::from(test_file)),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store_for_test(storage.clone());
@@ -714,6 +743,7 @@ mod tests {
         let pipeline_params = IndexingPipelineParams {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
+            doc_mapping_version: 0,
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
@@ -814,6 +844,9 @@ mod tests {
             source_params: SourceParams::file(PathBuf::from(test_file)),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store_for_test(storage.clone());
@@ -821,6 +854,7 @@ mod tests {
         let pipeline_params = IndexingPipelineParams {
             pipeline_id,
             doc_mapper: Arc::new(default_doc_mapper_for_test()),
+            doc_mapping_version: 0,
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
@@ -891,6 +925,9 @@ mod tests {
             source_params: SourceParams::Void(VoidSourceParams),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = MetastoreServiceClient::from(mock_metastore);
         let storage = Arc::new(RamStorage::default());
@@ -914,6 +951,7 @@ mod tests {
         let indexing_pipeline_params = IndexingPipelineParams {
             pipeline_id,
             doc_mapper,
+            doc_mapping_version: 0,
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
@@ -1010,6 +1048,9 @@ mod tests {
             source_params: SourceParams::file(PathBuf::from(test_file)),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let storage = Arc::new(RamStorage::default());
         let split_store = IndexingSplitStore::create_without_local_store_for_test(storage.clone());
@@ -1035,6 +1076,7 @@ mod tests {
         let pipeline_params = IndexingPipelineParams {
             pipeline_id,
             doc_mapper: Arc::new(broken_mapper),
+            doc_mapping_version: 0,
             source_config,
             source_storage_resolver: StorageResolver::for_test(),
             indexing_directory: TempDirectory::for_test(),
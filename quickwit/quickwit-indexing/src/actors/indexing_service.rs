@@ -17,10 +17,11 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::{Debug, Display, Formatter};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -50,7 +51,7 @@ use quickwit_proto::indexing::{
 use quickwit_proto::metastore::{
     IndexMetadataRequest, ListIndexesMetadataRequest, MetastoreService, MetastoreServiceClient,
 };
-use quickwit_proto::types::{IndexId, IndexUid, PipelineUid};
+use quickwit_proto::types::{IndexId, IndexUid, PipelineUid, ShardId};
 use quickwit_storage::StorageResolver;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Semaphore;
@@ -66,6 +67,14 @@ use crate::{IndexingPipeline, IndexingPipelineParams, IndexingSplitStore, Indexi
 /// Name of the indexing directory, usually located at `<data_dir_path>/indexing`.
 pub const INDEXING_DIR_NAME: &str = "indexing";
 
+/// Interval between two checks of the index configs of the running pipelines against the
+/// metastore. Pipelines whose index config has been updated are respawned with the new config.
+const REFRESH_INDEX_CONFIGS_INTERVAL: Duration = if cfg!(any(test, feature = "testsuite")) {
+    Duration::from_millis(500)
+} else {
+    Duration::from_secs(30)
+};
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct IndexingServiceCounters {
     pub num_running_pipelines: usize,
@@ -106,6 +115,8 @@ struct PipelineHandle {
     mailbox: Mailbox<IndexingPipeline>,
     handle: ActorHandle<IndexingPipeline>,
     indexing_pipeline_id: IndexingPipelineId,
+    // Index config the pipeline was spawned with, used to detect index updates.
+    index_config: IndexConfig,
 }
 
 /// The indexing service is (single) actor service running on indexer and in charge
@@ -251,9 +262,16 @@ impl IndexingService {
             node_id: self.node_id.clone(),
             pipeline_uid,
         };
+        let doc_mapping_version = index_metadata.doc_mapping_version;
         let index_config = index_metadata.into_index_config();
-        self.spawn_pipeline_inner(ctx, pipeline_id.clone(), index_config, source_config)
-            .await?;
+        self.spawn_pipeline_inner(
+            ctx,
+            pipeline_id.clone(),
+            index_config,
+            doc_mapping_version,
+            source_config,
+        )
+        .await?;
         Ok(pipeline_id)
     }
 
@@ -262,6 +280,7 @@ impl IndexingService {
         ctx: &ActorContext<Self>,
         pipeline_id: IndexingPipelineId,
         index_config: IndexConfig,
+        doc_mapping_version: u64,
         source_config: SourceConfig,
     ) -> Result<(), IndexingError> {
         if self
@@ -323,6 +342,7 @@ impl IndexingService {
             storage,
             // Indexing-related parameters
             doc_mapper,
+            doc_mapping_version,
             indexing_directory,
             indexing_settings: index_config.indexing_settings.clone(),
             split_store,
@@ -347,6 +367,7 @@ impl IndexingService {
             mailbox: pipeline_mailbox,
             handle: pipeline_handle,
             indexing_pipeline_id: pipeline_id.clone(),
+            index_config,
         };
         self.indexing_pipelines
             .insert(pipeline_id.pipeline_uid, pipeline_handle);
@@ -595,6 +616,7 @@ impl IndexingService {
                             ctx,
                             new_pipeline_id.clone(),
                             index_metadata.index_config.clone(),
+                            index_metadata.doc_mapping_version,
                             source_config.clone(),
                         )
                         .await
@@ -658,6 +680,126 @@ impl IndexingService {
         }
     }
 
+    /// Respawns the running pipelines whose doc mapping or indexing settings have been updated in
+    /// the metastore, so that they pick up the new doc mapper, indexing settings, and merge policy.
+    /// The merge pipelines of the updated indexes are respawned as well. Updates of the other
+    /// parts of the index config, such as the search settings or the retention policy, do not
+    /// affect indexing and leave the pipelines untouched.
+    async fn refresh_index_configs(&mut self, ctx: &ActorContext<Self>) -> anyhow::Result<()> {
+        if self.indexing_pipelines.is_empty() {
+            return Ok(());
+        }
+        let index_id_patterns: Vec<String> = self
+            .indexing_pipelines
+            .values()
+            .map(|pipeline_handle| &pipeline_handle.indexing_pipeline_id.index_uid.index_id)
+            .unique()
+            .cloned()
+            .collect();
+        let list_indexes_metadata_request = ListIndexesMetadataRequest { index_id_patterns };
+        let indexes_metadata = {
+            let _protect_guard = ctx.protect_zone();
+            self.metastore
+                .clone()
+                .list_indexes_metadata(list_indexes_metadata_request)
+                .await?
+                .deserialize_indexes_metadata()?
+        };
+        let indexes_metadata_by_index_uid: HashMap<IndexUid, IndexMetadata> = indexes_metadata
+            .into_iter()
+            .map(|index_metadata| (index_metadata.index_uid.clone(), index_metadata))
+            .collect();
+
+        let pipeline_uids_to_respawn: Vec<PipelineUid> = self
+            .indexing_pipelines
+            .iter()
+            .filter(|(_, pipeline_handle)| {
+                let index_uid = &pipeline_handle.indexing_pipeline_id.index_uid;
+                indexes_metadata_by_index_uid
+                    .get(index_uid)
+                    .is_some_and(|index_metadata| {
+                        let new_index_config = &index_metadata.index_config;
+                        let index_config = &pipeline_handle.index_config;
+                        new_index_config.doc_mapping != index_config.doc_mapping
+                            || new_index_config.indexing_settings != index_config.indexing_settings
+                    })
+            })
+            .map(|(pipeline_uid, _)| *pipeline_uid)
+            .collect();
+
+        if pipeline_uids_to_respawn.is_empty() {
+            return Ok(());
+        }
+        // All the pipelines and merge pipelines of an updated index must be shut down before
+        // respawning any of them: otherwise, a new pipeline could attach itself to a merge pipeline
+        // that is about to be killed.
+        let mut pipelines_to_respawn: Vec<(IndexingPipelineId, BTreeSet<ShardId>)> =
+            Vec::with_capacity(pipeline_uids_to_respawn.len());
+
+        for pipeline_uid in pipeline_uids_to_respawn {
+            let Some(pipeline_handle) = self.indexing_pipelines.remove(&pipeline_uid) else {
+                continue;
+            };
+            self.counters.num_running_pipelines -= 1;
+
+            let pipeline_id = pipeline_handle.indexing_pipeline_id;
+            let shard_ids = pipeline_handle.handle.last_observation().shard_ids.clone();
+            info!(
+                index_id=%pipeline_id.index_uid.index_id,
+                source_id=%pipeline_id.source_id,
+                pipeline_uid=%pipeline_id.pipeline_uid,
+                "index config was updated, respawning indexing pipeline"
+            );
+            pipeline_handle.handle.kill().await;
+
+            let merge_pipeline_id = MergePipelineId::from(&pipeline_id);
+
+            if let Some(merge_pipeline_handle) =
+                self.merge_pipeline_handles.remove(&merge_pipeline_id)
+            {
+                self.counters.num_running_merge_pipelines -= 1;
+                merge_pipeline_handle.handle.kill().await;
+            }
+            pipelines_to_respawn.push((pipeline_id, shard_ids));
+        }
+        for (pipeline_id, shard_ids) in pipelines_to_respawn {
+            let index_metadata = &indexes_metadata_by_index_uid[&pipeline_id.index_uid];
+
+            let Some(source_config) = index_metadata.sources.get(&pipeline_id.source_id) else {
+                warn!(
+                    pipeline_id=?pipeline_id,
+                    "failed to respawn pipeline: source does not exist"
+                );
+                continue;
+            };
+            if let Err(error) = self
+                .spawn_pipeline_inner(
+                    ctx,
+                    pipeline_id.clone(),
+                    index_metadata.index_config.clone(),
+                    index_metadata.doc_mapping_version,
+                    source_config.clone(),
+                )
+                .await
+            {
+                error!(pipeline_id=?pipeline_id, err=?error, "failed to respawn pipeline");
+                continue;
+            }
+            if shard_ids.is_empty() {
+                continue;
+            }
+            let pipeline_handle = &self.indexing_pipelines[&pipeline_id.pipeline_uid];
+            let message = AssignShards(Assignment { shard_ids });
+
+            if let Err(error) = pipeline_handle.mailbox.send_message(message).await {
+                error!(error=%error, "failed to assign shards to indexing pipeline");
+            }
+        }
+        self.update_cluster_running_indexing_tasks_in_chitchat()
+            .await;
+        Ok(())
+    }
+
     async fn update_cluster_running_indexing_tasks_in_chitchat(&self) {
         let mut indexing_tasks: Vec<IndexingTask> = self
             .indexing_pipelines
@@ -793,6 +935,26 @@ impl Handler<SuperviseLoop> for IndexingService {
     }
 }
 
+#[derive(Debug)]
+struct RefreshIndexConfigsLoop;
+
+#[async_trait]
+impl Handler<RefreshIndexConfigsLoop> for IndexingService {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _message: RefreshIndexConfigsLoop,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if let Err(error) = self.refresh_index_configs(ctx).await {
+            warn!(error=?error, "failed to refresh index configs of running pipelines");
+        }
+        ctx.schedule_self_msg(REFRESH_INDEX_CONFIGS_INTERVAL, RefreshIndexConfigsLoop);
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Actor for IndexingService {
     type ObservableState = IndexingServiceCounters;
@@ -803,6 +965,7 @@ impl Actor for IndexingService {
 
     async fn initialize(&mut self, ctx: &ActorContext<Self>) -> Result<(), ActorExitStatus> {
         self.run_ingest_api_queues_gc().await?;
+        ctx.schedule_self_msg(REFRESH_INDEX_CONFIGS_INTERVAL, RefreshIndexConfigsLoop);
         self.handle(SuperviseLoop, ctx).await
     }
 }
@@ -873,12 +1036,12 @@ mod tests {
     use quickwit_ingest::{init_ingest_api, CreateQueueIfNotExistsRequest};
     use quickwit_metastore::{
         metastore_for_test, AddSourceRequestExt, CreateIndexRequestExt,
-        ListIndexesMetadataResponseExt,
+        ListIndexesMetadataResponseExt, UpdateIndexRequestExt,
     };
     use quickwit_proto::indexing::IndexingTask;
     use quickwit_proto::metastore::{
         AddSourceRequest, CreateIndexRequest, DeleteIndexRequest, IndexMetadataResponse,
-        ListIndexesMetadataResponse,
+        ListIndexesMetadataResponse, UpdateIndexRequest,
     };
 
     use super::*;
@@ -962,6 +1125,9 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let spawn_pipeline_msg = SpawnPipeline {
             index_id: index_id.clone(),
@@ -1019,6 +1185,114 @@ mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_indexing_service_respawns_pipelines_on_index_update() {
+        quickwit_common::setup_logging_for_tests();
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["indexer"], &transport, true)
+            .await
+            .unwrap();
+        let mut metastore = metastore_for_test();
+
+        let index_id = append_random_suffix("test-indexing-service-index-update");
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        let index_uid: IndexUid = metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone();
+        let source_config = SourceConfig {
+            source_id: "test-indexing-service--source".to_string(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::void(),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
+        };
+        let add_source_request =
+            AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config).unwrap();
+        metastore.add_source(add_source_request).await.unwrap();
+
+        let universe = Universe::with_accelerated_time();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (indexing_service, indexing_service_handle) =
+            spawn_indexing_service_for_test(temp_dir.path(), &universe, metastore.clone(), cluster)
+                .await;
+        indexing_service
+            .ask_for_res(SpawnPipeline {
+                index_id: index_id.clone(),
+                source_config,
+                pipeline_uid: PipelineUid::default(),
+            })
+            .await
+            .unwrap();
+        let indexing_pipeline_mailbox = universe.get_one::<IndexingPipeline>().unwrap();
+        let merge_pipeline_mailbox = universe.get_one::<MergePipeline>().unwrap();
+
+        // Without any update, the pipelines are left untouched.
+        universe.sleep(REFRESH_INDEX_CONFIGS_INTERVAL * 2).await;
+        assert!(!indexing_pipeline_mailbox.is_disconnected());
+        assert!(!merge_pipeline_mailbox.is_disconnected());
+
+        // Updating the search settings does not affect indexing.
+        let mut search_settings = index_config.search_settings.clone();
+        search_settings.default_search_fields = vec!["body".to_string()];
+        let update_index_request = UpdateIndexRequest::try_from_updates(
+            index_uid.clone(),
+            &index_config.doc_mapping,
+            &search_settings,
+            &index_config.retention_policy_opt,
+            &index_config.indexing_settings,
+        )
+        .unwrap();
+        metastore.update_index(update_index_request).await.unwrap();
+
+        universe.sleep(REFRESH_INDEX_CONFIGS_INTERVAL * 2).await;
+        assert!(!indexing_pipeline_mailbox.is_disconnected());
+        assert!(!merge_pipeline_mailbox.is_disconnected());
+
+        let mut indexing_settings = index_config.indexing_settings.clone();
+        indexing_settings.commit_timeout_secs = 5;
+        let update_index_request = UpdateIndexRequest::try_from_updates(
+            index_uid,
+            &index_config.doc_mapping,
+            &search_settings,
+            &index_config.retention_policy_opt,
+            &indexing_settings,
+        )
+        .unwrap();
+        metastore.update_index(update_index_request).await.unwrap();
+
+        universe.sleep(REFRESH_INDEX_CONFIGS_INTERVAL * 2).await;
+        assert!(indexing_pipeline_mailbox.is_disconnected());
+        assert!(merge_pipeline_mailbox.is_disconnected());
+
+        let new_indexing_pipeline_mailbox = universe.get_one::<IndexingPipeline>().unwrap();
+        assert_ne!(
+            new_indexing_pipeline_mailbox.actor_instance_id(),
+            indexing_pipeline_mailbox.actor_instance_id()
+        );
+        let new_merge_pipeline_mailbox = universe.get_one::<MergePipeline>().unwrap();
+        assert_ne!(
+            new_merge_pipeline_mailbox.actor_instance_id(),
+            merge_pipeline_mailbox.actor_instance_id()
+        );
+        let observation = indexing_service_handle.observe().await;
+        assert_eq!(observation.num_running_pipelines, 1);
+        assert_eq!(observation.num_running_merge_pipelines, 1);
+        assert_eq!(observation.num_failed_pipelines, 0);
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_indexing_service_supervise_pipelines() {
         quickwit_common::setup_logging_for_tests();
@@ -1054,6 +1328,9 @@ mod tests {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         indexing_service
             .ask_for_res(SpawnPipeline {
@@ -1121,6 +1398,9 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let add_source_request =
             AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config_1).unwrap();
@@ -1162,6 +1442,7 @@ mod tests {
             client_log_level: None,
             client_params: serde_json::Value::Null,
             enable_backfill_mode: false,
+            schema_registry_url: None,
         };
         let source_config_2 = SourceConfig {
             source_id: "test-indexing-service--source-2".to_string(),
@@ -1171,6 +1452,9 @@ mod tests {
             source_params: SourceParams::Kafka(kafka_params),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let add_source_request_2 =
             AddSourceRequest::try_from_source_config(index_uid.clone(), &source_config_2).unwrap();
@@ -1328,6 +1612,9 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let create_index_request =
             CreateIndexRequest::try_from_index_config(&index_config).unwrap();
@@ -1458,6 +1745,9 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         index_metadata
             .sources
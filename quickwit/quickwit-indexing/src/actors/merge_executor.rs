@@ -249,9 +249,15 @@ pub fn merge_split_attrs(
         .map(|split| split.delete_opstamp)
         .min()
         .unwrap_or(0);
+    // The merge planner only merges splits sharing the same doc mapping version.
+    let doc_mapping_version = splits
+        .first()
+        .map(|split| split.doc_mapping_version)
+        .unwrap_or(0);
     SplitAttrs {
         split_id: merge_split_id,
         partition_id,
+        doc_mapping_version,
         pipeline_id: pipeline_id.clone(),
         replaced_split_ids,
         time_range,
@@ -307,6 +313,7 @@ impl MergeExecutor {
                 split_directories,
                 Vec::new(),
                 None,
+                None,
                 merge_scratch_directory.path(),
                 ctx,
             )
@@ -372,6 +379,7 @@ impl MergeExecutor {
                 union_index_meta,
                 split_directories,
                 delete_tasks,
+                Some(split.split_id()),
                 Some(self.doc_mapper.clone()),
                 merge_scratch_directory.path(),
                 ctx,
@@ -439,6 +447,7 @@ impl MergeExecutor {
             split_attrs: SplitAttrs {
                 split_id: merge_split_id,
                 partition_id: split.partition_id,
+                doc_mapping_version: split.doc_mapping_version,
                 pipeline_id: index_pipeline_id,
                 replaced_split_ids: vec![split.split_id.clone()],
                 time_range,
@@ -454,11 +463,16 @@ impl MergeExecutor {
         Ok(Some(indexed_split))
     }
 
+    /// Merges the split directories, applying the delete tasks if any. `deleted_split_id_opt` is
+    /// the ID of the split the delete tasks are applied to, which resolves the document ids of the
+    /// `ids` queries.
+    #[allow(clippy::too_many_arguments)]
     async fn merge_split_directories(
         &self,
         union_index_meta: IndexMeta,
         split_directories: Vec<Box<dyn Directory>>,
         delete_tasks: Vec<DeleteTask>,
+        deleted_split_id_opt: Option<&str>,
         doc_mapper_opt: Option<Arc<dyn DocMapper>>,
         output_path: &Path,
         ctx: &ActorContext<MergeExecutor>,
@@ -509,8 +523,12 @@ impl MergeExecutor {
                     "Delete all documents matched by query `{:?}`",
                     parsed_query_ast
                 );
-                let (query, _) =
-                    doc_mapper.query(union_index.schema(), &parsed_query_ast, false)?;
+                let (query, _) = doc_mapper.query(
+                    union_index.schema(),
+                    &parsed_query_ast,
+                    false,
+                    deleted_split_id_opt,
+                )?;
                 index_writer.delete_query(query)?;
             }
             debug!("commit-delete-operations");
@@ -37,11 +37,28 @@ use crate::merge_policy::MergeOperation;
 use crate::models::NewSplits;
 use crate::MergePolicy;
 
+/// Splits can only be merged with splits of the same merge partition, i.e. splits sharing the same
+/// partition ID and built with the same doc mapping version.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+struct MergePartition {
+    partition_id: u64,
+    doc_mapping_version: u64,
+}
+
+impl MergePartition {
+    fn from_split_metadata(split_metadata: &SplitMetadata) -> Self {
+        MergePartition {
+            partition_id: split_metadata.partition_id,
+            doc_mapping_version: split_metadata.doc_mapping_version,
+        }
+    }
+}
+
 /// The merge planner decides when to start a merge task.
 pub struct MergePlanner {
     /// A young split is a split that has not reached maturity
     /// yet and can be candidate to merge operations.
-    partitioned_young_splits: HashMap<u64, Vec<SplitMetadata>>,
+    partitioned_young_splits: HashMap<MergePartition, Vec<SplitMetadata>>,
 
     /// This set contains all of the split ids that we "acknowledged".
     /// The point of this set is to rapidly dismiss redundant `NewSplit` message.
@@ -228,7 +245,7 @@ impl MergePlanner {
     fn record_split(&mut self, new_split: SplitMetadata) {
         let splits_for_partition: &mut Vec<SplitMetadata> = self
             .partitioned_young_splits
-            .entry(new_split.partition_id)
+            .entry(MergePartition::from_split_metadata(&new_split))
             .or_default();
         splits_for_partition.push(new_split);
     }
@@ -444,6 +461,76 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_merge_planner_does_not_merge_splits_with_different_doc_mapping_versions(
+    ) -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let (merge_split_downloader_mailbox, merge_split_downloader_inbox) =
+            universe.create_test_mailbox();
+        let pipeline_id = IndexingPipelineId {
+            index_uid: index_uid.clone(),
+            source_id: "test-source".to_string(),
+            node_id: "test-node".to_string(),
+            pipeline_uid: PipelineUid::default(),
+        };
+        let merge_policy = Arc::new(StableLogMergePolicy::new(
+            StableLogMergePolicyConfig {
+                min_level_num_docs: 10_000,
+                merge_factor: 3,
+                max_merge_factor: 3,
+                maturation_period: Duration::from_secs(3600),
+            },
+            50_000,
+        ));
+        let merge_planner = MergePlanner::new(
+            pipeline_id,
+            Vec::new(),
+            merge_policy,
+            merge_split_downloader_mailbox,
+            universe.get_or_spawn_one(),
+        );
+        let (merge_planner_mailbox, merge_planner_handle) =
+            universe.spawn_builder().spawn(merge_planner);
+
+        let split_metadata_for_test_with_version = |split_id: &str, doc_mapping_version: u64| {
+            let mut split_metadata = split_metadata_for_test(&index_uid, split_id, 0, 1000, 0);
+            split_metadata.doc_mapping_version = doc_mapping_version;
+            split_metadata
+        };
+        let message = NewSplits {
+            new_splits: vec![
+                split_metadata_for_test_with_version("1", 0),
+                split_metadata_for_test_with_version("2", 0),
+                split_metadata_for_test_with_version("3", 1),
+                split_metadata_for_test_with_version("4", 1),
+            ],
+        };
+        merge_planner_mailbox.send_message(message).await?;
+        merge_planner_handle.process_pending_and_observe().await;
+        let merge_tasks = merge_split_downloader_inbox.drain_for_test_typed::<MergeTask>();
+        assert!(merge_tasks.is_empty());
+
+        let message = NewSplits {
+            new_splits: vec![split_metadata_for_test_with_version("5", 1)],
+        };
+        merge_planner_mailbox.send_message(message).await?;
+        merge_planner_handle.process_pending_and_observe().await;
+        let merge_tasks = merge_split_downloader_inbox.drain_for_test_typed::<MergeTask>();
+        assert_eq!(merge_tasks.len(), 1);
+
+        let merged_split_ids: Vec<&str> = merge_tasks[0]
+            .splits
+            .iter()
+            .map(|split| split.split_id())
+            .sorted()
+            .collect();
+        assert_eq!(merged_split_ids, ["3", "4", "5"]);
+
+        universe.assert_quit().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_merge_planner_spawns_merge_over_existing_splits_on_startup() -> anyhow::Result<()>
     {
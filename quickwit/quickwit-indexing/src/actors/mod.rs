@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+mod dead_letter_sink;
 mod doc_processor;
 mod index_serializer;
 mod indexer;
@@ -28,12 +29,17 @@ mod merge_planner;
 mod merge_scheduler_service;
 mod merge_split_downloader;
 mod packager;
+mod processors;
 mod publisher;
+mod record_decoder;
 mod sequencer;
 mod uploader;
 #[cfg(feature = "vrl")]
 mod vrl_processing;
 
+pub use dead_letter_sink::{
+    DeadLetterPartitionDelta, DeadLetterRecord, DeadLetterSink, DeadLetterSourcePosition,
+};
 pub use doc_processor::{DocProcessor, DocProcessorCounters};
 pub use index_serializer::IndexSerializer;
 pub use indexer::{Indexer, IndexerCounters};
@@ -47,6 +53,7 @@ pub use merge_planner::MergePlanner;
 pub use merge_scheduler_service::{schedule_merge, MergePermit, MergeSchedulerService};
 pub use merge_split_downloader::MergeSplitDownloader;
 pub use packager::Packager;
+pub(crate) use processors::ProcessorChain;
 pub use publisher::{Publisher, PublisherCounters, PublisherType};
 pub use quickwit_proto::indexing::IndexingError;
 pub use sequencer::Sequencer;
@@ -521,6 +521,7 @@ mod tests {
             split_attrs: SplitAttrs {
                 split_id: "test-split".to_string(),
                 partition_id: 17u64,
+                doc_mapping_version: 0,
                 pipeline_id,
                 num_docs,
                 uncompressed_docs_size_in_bytes: num_docs * 15,
@@ -0,0 +1,118 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_config::{DissectPattern, DissectProcessorConfig};
+use quickwit_doc_mapper::JsonObject;
+use serde_json::Value as JsonValue;
+
+use super::{get_string_field, insert_field};
+
+/// Splits a string field into fields according to a dissect pattern.
+pub(super) struct DissectProcessor {
+    field: String,
+    pattern: DissectPattern,
+    ignore_missing: bool,
+}
+
+impl DissectProcessor {
+    pub fn try_new(dissect_config: &DissectProcessorConfig) -> anyhow::Result<Self> {
+        let pattern = dissect_config.parse_pattern()?;
+        Ok(Self {
+            field: dissect_config.field.clone(),
+            pattern,
+            ignore_missing: dissect_config.ignore_missing,
+        })
+    }
+
+    pub fn process(&self, json_obj: &mut JsonObject) -> Result<(), String> {
+        let Some(text) = get_string_field(json_obj, &self.field, self.ignore_missing)? else {
+            return Ok(());
+        };
+        let Some(fields) = self.pattern.dissect(text) else {
+            return Err(format!(
+                "field `{}` does not match the dissect pattern",
+                self.field
+            ));
+        };
+        for (field, value) in fields {
+            insert_field(json_obj, field, JsonValue::String(value))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn dissect_processor(pattern: &str) -> anyhow::Result<DissectProcessor> {
+        let dissect_config = DissectProcessorConfig {
+            field: "message".to_string(),
+            pattern: pattern.to_string(),
+            ignore_missing: true,
+        };
+        DissectProcessor::try_new(&dissect_config)
+    }
+
+    fn process(dissect_processor: &DissectProcessor, message: &str) -> Result<JsonValue, String> {
+        let mut json_obj = json!({"message": message}).as_object().unwrap().clone();
+        dissect_processor.process(&mut json_obj)?;
+        Ok(JsonValue::Object(json_obj))
+    }
+
+    #[test]
+    fn test_dissect_processor() {
+        let dissect_processor = dissect_processor(
+            r#"%{client.ip} %{?ident} %{} [%{timestamp}] "%{request}" %{status}"#,
+        )
+        .unwrap();
+        let json_doc = process(
+            &dissect_processor,
+            r#"1.2.3.4 - - [30/Apr/1998:22:00:52 +0000] "GET /index.html HTTP/1.0" 200"#,
+        )
+        .unwrap();
+        assert_eq!(
+            json_doc,
+            json!({
+                "message": r#"1.2.3.4 - - [30/Apr/1998:22:00:52 +0000] "GET /index.html HTTP/1.0" 200"#,
+                "client": {"ip": "1.2.3.4"},
+                "timestamp": "30/Apr/1998:22:00:52 +0000",
+                "request": "GET /index.html HTTP/1.0",
+                "status": "200",
+            })
+        );
+        let error = process(&dissect_processor, "1.2.3.4 - -").unwrap_err();
+        assert_eq!(error, "field `message` does not match the dissect pattern");
+
+        let mut json_obj = JsonObject::new();
+        dissect_processor.process(&mut json_obj).unwrap();
+        assert!(json_obj.is_empty());
+    }
+
+    #[test]
+    fn test_dissect_processor_right_padding() {
+        let dissect_processor = dissect_processor("[%{level}] %{module->} %{body}").unwrap();
+        let json_doc = process(&dissect_processor, "[INFO] server    started").unwrap();
+        assert_eq!(json_doc["module"], "server");
+        assert_eq!(json_doc["level"], "INFO");
+        assert_eq!(json_doc["body"], "started");
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_config::{GrokPattern, GrokProcessorConfig};
+use quickwit_doc_mapper::JsonObject;
+
+use super::{get_string_field, insert_field};
+
+/// Extracts fields from a string field with the first grok pattern that matches it.
+pub(super) struct GrokProcessor {
+    field: String,
+    patterns: Vec<GrokPattern>,
+    ignore_missing: bool,
+}
+
+impl GrokProcessor {
+    pub fn try_new(grok_config: &GrokProcessorConfig) -> anyhow::Result<Self> {
+        let patterns = grok_config.compile_patterns()?;
+        Ok(Self {
+            field: grok_config.field.clone(),
+            patterns,
+            ignore_missing: grok_config.ignore_missing,
+        })
+    }
+
+    pub fn process(&self, json_obj: &mut JsonObject) -> Result<(), String> {
+        let Some(text) = get_string_field(json_obj, &self.field, self.ignore_missing)? else {
+            return Ok(());
+        };
+        let Some(fields_result) = self
+            .patterns
+            .iter()
+            .find_map(|pattern| pattern.extract(text))
+        else {
+            return Err(format!(
+                "field `{}` does not match any grok pattern",
+                self.field
+            ));
+        };
+        for (field, json_value) in fields_result? {
+            insert_field(json_obj, field, json_value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::{json, Value as JsonValue};
+
+    use super::*;
+
+    fn grok_processor(patterns: &[&str]) -> GrokProcessor {
+        let grok_config = GrokProcessorConfig {
+            field: "message".to_string(),
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            pattern_definitions: BTreeMap::from([("STATUS".to_string(), "OK|KO".to_string())]),
+            ignore_missing: false,
+        };
+        GrokProcessor::try_new(&grok_config).unwrap()
+    }
+
+    fn process(grok_processor: &GrokProcessor, message: &str) -> Result<JsonValue, String> {
+        let mut json_obj = json!({"message": message}).as_object().unwrap().clone();
+        grok_processor.process(&mut json_obj)?;
+        Ok(JsonValue::Object(json_obj))
+    }
+
+    #[test]
+    fn test_grok_processor() {
+        let grok_processor = grok_processor(&["%{IP:client.ip} %{WORD:http.method} \
+                                               %{URIPATHPARAM:http.path} \
+                                               %{NUMBER:duration:float} %{INT:status:int} \
+                                               %{STATUS:outcome}"]);
+        let json_doc = process(
+            &grok_processor,
+            "55.3.244.1 GET /index.html?page=2 15.824 200 OK",
+        )
+        .unwrap();
+        assert_eq!(
+            json_doc,
+            json!({
+                "message": "55.3.244.1 GET /index.html?page=2 15.824 200 OK",
+                "client": {"ip": "55.3.244.1"},
+                "http": {"method": "GET", "path": "/index.html?page=2"},
+                "duration": 15.824,
+                "status": 200,
+                "outcome": "OK",
+            })
+        );
+    }
+
+    #[test]
+    fn test_grok_processor_tries_patterns_in_order() {
+        let grok_processor = grok_processor(&[
+            "^%{TIMESTAMP_ISO8601:timestamp} %{LOGLEVEL:level} %{GREEDYDATA:body}$",
+            "^%{GREEDYDATA:body}$",
+        ]);
+        let json_doc = process(&grok_processor, "2024-01-02T03:04:05Z WARN disk is full").unwrap();
+        assert_eq!(json_doc["timestamp"], "2024-01-02T03:04:05Z");
+        assert_eq!(json_doc["level"], "WARN");
+        assert_eq!(json_doc["body"], "disk is full");
+
+        let json_doc = process(&grok_processor, "disk is full").unwrap();
+        assert!(json_doc.get("level").is_none());
+        assert_eq!(json_doc["body"], "disk is full");
+    }
+
+    #[test]
+    fn test_grok_processor_combined_apache_log() {
+        let grok_processor = grok_processor(&["%{COMBINEDAPACHELOG}"]);
+        let json_doc = process(
+            &grok_processor,
+            r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326 "http://www.example.com/start.html" "Mozilla/4.08""#,
+        )
+        .unwrap();
+        assert_eq!(json_doc["clientip"], "127.0.0.1");
+        assert_eq!(json_doc["auth"], "frank");
+        assert_eq!(json_doc["timestamp"], "10/Oct/2000:13:55:36 -0700");
+        assert_eq!(json_doc["verb"], "GET");
+        assert_eq!(json_doc["request"], "/apache_pb.gif");
+        assert_eq!(json_doc["httpversion"], "1.0");
+        assert_eq!(json_doc["response"], "200");
+        assert_eq!(json_doc["bytes"], "2326");
+        assert_eq!(json_doc["agent"], r#""Mozilla/4.08""#);
+        assert!(json_doc.get("rawrequest").is_none());
+    }
+
+    #[test]
+    fn test_grok_processor_errors() {
+        let grok_processor = grok_processor(&["^%{INT:status:int}$"]);
+        let error = process(&grok_processor, "not a number").unwrap_err();
+        assert_eq!(error, "field `message` does not match any grok pattern");
+
+        let error = process(&grok_processor, "99999999999999999999").unwrap_err();
+        assert!(error.contains("failed to convert value"));
+
+        let mut json_obj = JsonObject::new();
+        let error = grok_processor.process(&mut json_obj).unwrap_err();
+        assert_eq!(error, "field `message` is missing");
+    }
+}
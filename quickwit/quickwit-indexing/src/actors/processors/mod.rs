@@ -0,0 +1,490 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Declarative processors applied to the documents before doc mapping, as an alternative to VRL
+//! transforms for the usual log parsing tasks.
+
+mod dissect;
+mod grok;
+
+use std::str::FromStr;
+
+use quickwit_config::{
+    DateProcessorConfig, DropIfProcessorConfig, KvProcessorConfig, ProcessorConfig,
+    RemoveProcessorConfig, RenameProcessorConfig,
+};
+use quickwit_datetime::{
+    parse_date_time_str, parse_timestamp_float, parse_timestamp_int, DateTimeInputFormat,
+    DateTimeOutputFormat,
+};
+use quickwit_doc_mapper::JsonObject;
+use regex::Regex;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+use self::dissect::DissectProcessor;
+use self::grok::GrokProcessor;
+
+#[derive(Debug, Error)]
+#[error("`{processor_id}` processor error: {message}")]
+pub(crate) struct ProcessorError {
+    /// Identifies the processor that failed in logs and metrics, for instance `grok[0]`.
+    pub processor_id: String,
+    pub message: String,
+}
+
+/// Ordered list of processors applied to each document.
+#[derive(Default)]
+pub(crate) struct ProcessorChain {
+    processors: Vec<(String, Processor)>,
+}
+
+impl ProcessorChain {
+    pub fn try_new(processor_configs: &[ProcessorConfig]) -> anyhow::Result<Self> {
+        let mut processors = Vec::with_capacity(processor_configs.len());
+
+        for (processor_idx, processor_config) in processor_configs.iter().enumerate() {
+            let processor_id = format!("{}[{processor_idx}]", processor_config.processor_type());
+            let processor = Processor::try_new(processor_config).map_err(|error| {
+                anyhow::anyhow!("failed to build `{processor_id}` processor: {error}")
+            })?;
+            processors.push((processor_id, processor));
+        }
+        Ok(Self { processors })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// Applies the processors to the document, in order. Returns `false` if the document must be
+    /// dropped.
+    pub fn process(&self, json_obj: &mut JsonObject) -> Result<bool, ProcessorError> {
+        for (processor_id, processor) in &self.processors {
+            match processor.process(json_obj) {
+                Ok(true) => {}
+                Ok(false) => return Ok(false),
+                Err(message) => {
+                    return Err(ProcessorError {
+                        processor_id: processor_id.clone(),
+                        message,
+                    })
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+enum Processor {
+    Grok(GrokProcessor),
+    Dissect(DissectProcessor),
+    Kv(KvProcessorConfig),
+    Rename(RenameProcessorConfig),
+    Remove(RemoveProcessorConfig),
+    Date(DateProcessor),
+    DropIf(DropIfProcessor),
+}
+
+impl Processor {
+    fn try_new(processor_config: &ProcessorConfig) -> anyhow::Result<Self> {
+        let processor = match processor_config {
+            ProcessorConfig::Grok(grok_config) => Self::Grok(GrokProcessor::try_new(grok_config)?),
+            ProcessorConfig::Dissect(dissect_config) => {
+                Self::Dissect(DissectProcessor::try_new(dissect_config)?)
+            }
+            ProcessorConfig::Kv(kv_config) => Self::Kv(kv_config.clone()),
+            ProcessorConfig::Rename(rename_config) => Self::Rename(rename_config.clone()),
+            ProcessorConfig::Remove(remove_config) => Self::Remove(remove_config.clone()),
+            ProcessorConfig::Date(date_config) => Self::Date(DateProcessor::try_new(date_config)?),
+            ProcessorConfig::DropIf(drop_if_config) => {
+                Self::DropIf(DropIfProcessor::try_new(drop_if_config)?)
+            }
+        };
+        Ok(processor)
+    }
+
+    fn process(&self, json_obj: &mut JsonObject) -> Result<bool, String> {
+        match self {
+            Self::Grok(grok_processor) => grok_processor.process(json_obj)?,
+            Self::Dissect(dissect_processor) => dissect_processor.process(json_obj)?,
+            Self::Kv(kv_config) => process_kv(kv_config, json_obj)?,
+            Self::Rename(rename_config) => process_rename(rename_config, json_obj)?,
+            Self::Remove(remove_config) => process_remove(remove_config, json_obj)?,
+            Self::Date(date_processor) => date_processor.process(json_obj)?,
+            Self::DropIf(drop_if_processor) => return Ok(!drop_if_processor.matches(json_obj)),
+        }
+        Ok(true)
+    }
+}
+
+fn process_kv(kv_config: &KvProcessorConfig, json_obj: &mut JsonObject) -> Result<(), String> {
+    let Some(text) = get_string_field(json_obj, &kv_config.field, kv_config.ignore_missing)? else {
+        return Ok(());
+    };
+    let pairs: Vec<(String, JsonValue)> = text
+        .split(kv_config.field_split.as_str())
+        .filter_map(|pair| pair.split_once(kv_config.value_split.as_str()))
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| {
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            let field = match &kv_config.target_field {
+                Some(target_field) => format!("{target_field}.{key}"),
+                None => key.to_string(),
+            };
+            (field, JsonValue::String(value.to_string()))
+        })
+        .collect();
+    for (field, json_value) in pairs {
+        insert_field(json_obj, &field, json_value)?;
+    }
+    Ok(())
+}
+
+fn process_rename(
+    rename_config: &RenameProcessorConfig,
+    json_obj: &mut JsonObject,
+) -> Result<(), String> {
+    let Some(json_value) = remove_field(json_obj, &rename_config.field) else {
+        return missing_field(&rename_config.field, rename_config.ignore_missing);
+    };
+    insert_field(json_obj, &rename_config.target_field, json_value)
+}
+
+fn process_remove(
+    remove_config: &RemoveProcessorConfig,
+    json_obj: &mut JsonObject,
+) -> Result<(), String> {
+    for field in &remove_config.fields {
+        if remove_field(json_obj, field).is_none() {
+            missing_field(field, remove_config.ignore_missing)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses a date from a string or a numeric timestamp and stores it as an RFC 3339 string, which
+/// datetime fields accept by default.
+struct DateProcessor {
+    field: String,
+    target_field: String,
+    formats: Vec<DateTimeInputFormat>,
+    ignore_missing: bool,
+}
+
+impl DateProcessor {
+    fn try_new(date_config: &DateProcessorConfig) -> anyhow::Result<Self> {
+        let formats = date_config
+            .formats
+            .iter()
+            .map(|format| DateTimeInputFormat::from_str(format).map_err(anyhow::Error::msg))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self {
+            field: date_config.field.clone(),
+            target_field: date_config
+                .target_field
+                .clone()
+                .unwrap_or_else(|| date_config.field.clone()),
+            formats,
+            ignore_missing: date_config.ignore_missing,
+        })
+    }
+
+    fn process(&self, json_obj: &mut JsonObject) -> Result<(), String> {
+        let date_time = match get_field(json_obj, &self.field) {
+            Some(JsonValue::String(date_time_str)) => {
+                parse_date_time_str(date_time_str, &self.formats)?
+            }
+            Some(JsonValue::Number(number)) => {
+                if let Some(timestamp) = number.as_i64() {
+                    parse_timestamp_int(timestamp, &self.formats)?
+                } else if let Some(timestamp) = number.as_f64() {
+                    parse_timestamp_float(timestamp, &self.formats)?
+                } else {
+                    return Err(format!("field `{}` is not a valid timestamp", self.field));
+                }
+            }
+            Some(_) => {
+                return Err(format!(
+                    "field `{}` is neither a string nor a number",
+                    self.field
+                ))
+            }
+            None => return missing_field(&self.field, self.ignore_missing),
+        };
+        let json_value = DateTimeOutputFormat::Rfc3339.format_to_json(date_time)?;
+        insert_field(json_obj, &self.target_field, json_value)
+    }
+}
+
+struct DropIfProcessor {
+    field: String,
+    equals_opt: Option<JsonValue>,
+    matches_opt: Option<Regex>,
+}
+
+impl DropIfProcessor {
+    fn try_new(drop_if_config: &DropIfProcessorConfig) -> anyhow::Result<Self> {
+        let matches_opt = drop_if_config
+            .matches
+            .as_deref()
+            .map(Regex::new)
+            .transpose()?;
+        Ok(Self {
+            field: drop_if_config.field.clone(),
+            equals_opt: drop_if_config.equals.clone(),
+            matches_opt,
+        })
+    }
+
+    fn matches(&self, json_obj: &JsonObject) -> bool {
+        let Some(json_value) = get_field(json_obj, &self.field) else {
+            return false;
+        };
+        if let Some(expected_value) = &self.equals_opt {
+            return json_value == expected_value;
+        }
+        if let Some(regex) = &self.matches_opt {
+            return json_value
+                .as_str()
+                .map(|text| regex.is_match(text))
+                .unwrap_or(false);
+        }
+        true
+    }
+}
+
+fn missing_field(field: &str, ignore_missing: bool) -> Result<(), String> {
+    if ignore_missing {
+        Ok(())
+    } else {
+        Err(format!("field `{field}` is missing"))
+    }
+}
+
+/// Returns the value of a field designated by a dot-separated path.
+fn get_field<'a>(json_obj: &'a JsonObject, field: &str) -> Option<&'a JsonValue> {
+    let mut path = field.split('.');
+    let mut json_value = json_obj.get(path.next()?)?;
+
+    for key in path {
+        json_value = json_value.as_object()?.get(key)?;
+    }
+    Some(json_value)
+}
+
+/// Returns the value of a string field, or `None` if the field is missing and `ignore_missing` is
+/// set.
+fn get_string_field<'a>(
+    json_obj: &'a JsonObject,
+    field: &str,
+    ignore_missing: bool,
+) -> Result<Option<&'a str>, String> {
+    match get_field(json_obj, field) {
+        Some(JsonValue::String(text)) => Ok(Some(text)),
+        Some(_) => Err(format!("field `{field}` is not a string")),
+        None => missing_field(field, ignore_missing).map(|_| None),
+    }
+}
+
+/// Sets the value of a field designated by a dot-separated path, creating the intermediate
+/// objects as needed.
+fn insert_field(
+    json_obj: &mut JsonObject,
+    field: &str,
+    json_value: JsonValue,
+) -> Result<(), String> {
+    let (parent_path_opt, key) = match field.rsplit_once('.') {
+        Some((parent_path, key)) => (Some(parent_path), key),
+        None => (None, field),
+    };
+    let mut parent_obj = json_obj;
+
+    for parent_key in parent_path_opt.into_iter().flat_map(|path| path.split('.')) {
+        parent_obj = parent_obj
+            .entry(parent_key)
+            .or_insert_with(|| JsonValue::Object(JsonObject::new()))
+            .as_object_mut()
+            .ok_or_else(|| {
+                format!("cannot set field `{field}`: `{parent_key}` is not an object")
+            })?;
+    }
+    parent_obj.insert(key.to_string(), json_value);
+    Ok(())
+}
+
+/// Removes a field designated by a dot-separated path and returns its value.
+fn remove_field(json_obj: &mut JsonObject, field: &str) -> Option<JsonValue> {
+    let Some((parent_path, key)) = field.rsplit_once('.') else {
+        return json_obj.remove(field);
+    };
+    let mut parent_obj = json_obj;
+
+    for parent_key in parent_path.split('.') {
+        parent_obj = parent_obj.get_mut(parent_key)?.as_object_mut()?;
+    }
+    parent_obj.remove(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn process(
+        processor_configs_json: JsonValue,
+        json_doc: JsonValue,
+    ) -> Result<Option<JsonValue>, ProcessorError> {
+        let processor_configs: Vec<ProcessorConfig> =
+            serde_json::from_value(processor_configs_json).unwrap();
+        let processor_chain = ProcessorChain::try_new(&processor_configs).unwrap();
+        let mut json_obj = json_doc.as_object().unwrap().clone();
+        let keep = processor_chain.process(&mut json_obj)?;
+        Ok(keep.then_some(JsonValue::Object(json_obj)))
+    }
+
+    #[test]
+    fn test_processor_chain() {
+        let processor_configs_json = json!([
+            {
+                "type": "grok",
+                "field": "message",
+                "patterns": ["^%{TIMESTAMP_ISO8601:timestamp} %{LOGLEVEL:level} %{GREEDYDATA:kv}$"]
+            },
+            {"type": "kv", "field": "kv", "target_field": "attributes"},
+            {"type": "rename", "field": "attributes.user", "target_field": "user.name"},
+            {"type": "remove", "fields": ["message", "kv"]},
+            {"type": "date", "field": "timestamp", "formats": ["%Y-%m-%d %H:%M:%S"]},
+            {"type": "drop_if", "field": "level", "equals": "DEBUG"},
+        ]);
+        let json_doc = process(
+            processor_configs_json.clone(),
+            json!({"message": r#"2024-03-01 10:00:00 INFO user=alice status="ok""#}),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            json_doc,
+            json!({
+                "timestamp": "2024-03-01T10:00:00Z",
+                "level": "INFO",
+                "attributes": {"status": "ok"},
+                "user": {"name": "alice"},
+            })
+        );
+        let json_doc_opt = process(
+            processor_configs_json.clone(),
+            json!({"message": "2024-03-01 10:00:00 DEBUG user=bob"}),
+        )
+        .unwrap();
+        assert!(json_doc_opt.is_none());
+
+        let error = process(
+            processor_configs_json,
+            json!({"message": "2024-03-01 10:00:00 INFO status=ok"}),
+        )
+        .unwrap_err();
+        assert_eq!(error.processor_id, "rename[2]");
+        assert_eq!(error.message, "field `attributes.user` is missing");
+    }
+
+    #[test]
+    fn test_date_processor() {
+        let processor_configs_json = json!([{
+            "type": "date",
+            "field": "ts",
+            "target_field": "timestamp",
+            "formats": ["rfc3339", "unix_timestamp"],
+            "ignore_missing": true,
+        }]);
+        let json_doc = process(processor_configs_json.clone(), json!({"ts": 1700000000}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(json_doc["timestamp"], "2023-11-14T22:13:20Z");
+
+        let json_doc = process(
+            processor_configs_json.clone(),
+            json!({"ts": "2023-11-14T23:13:20+01:00"}),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(json_doc["timestamp"], "2023-11-14T22:13:20Z");
+
+        let json_doc = process(processor_configs_json.clone(), json!({}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(json_doc, json!({}));
+
+        let error = process(processor_configs_json, json!({"ts": "yesterday"})).unwrap_err();
+        assert_eq!(error.processor_id, "date[0]");
+        assert!(error
+            .message
+            .contains("failed to parse datetime `yesterday`"));
+    }
+
+    #[test]
+    fn test_drop_if_processor() {
+        let processor_configs_json = json!([{
+            "type": "drop_if",
+            "field": "http.path",
+            "matches": "^/health",
+        }]);
+        let json_doc_opt = process(
+            processor_configs_json.clone(),
+            json!({"http": {"path": "/healthz"}}),
+        )
+        .unwrap();
+        assert!(json_doc_opt.is_none());
+
+        let json_doc_opt =
+            process(processor_configs_json, json!({"http": {"path": "/search"}})).unwrap();
+        assert!(json_doc_opt.is_some());
+
+        let processor_configs_json = json!([{"type": "drop_if", "field": "debug"}]);
+        let json_doc_opt =
+            process(processor_configs_json.clone(), json!({"debug": false})).unwrap();
+        assert!(json_doc_opt.is_none());
+
+        let json_doc_opt = process(processor_configs_json, json!({"body": "hello"})).unwrap();
+        assert!(json_doc_opt.is_some());
+    }
+
+    #[test]
+    fn test_insert_and_remove_field() {
+        let mut json_obj = JsonObject::new();
+        insert_field(&mut json_obj, "a.b.c", json!(1)).unwrap();
+        insert_field(&mut json_obj, "a.d", json!(2)).unwrap();
+        assert_eq!(
+            JsonValue::Object(json_obj.clone()),
+            json!({"a": {"b": {"c": 1}, "d": 2}})
+        );
+
+        let error = insert_field(&mut json_obj, "a.d.e", json!(3)).unwrap_err();
+        assert_eq!(error, "cannot set field `a.d.e`: `d` is not an object");
+
+        assert_eq!(remove_field(&mut json_obj, "a.b.c"), Some(json!(1)));
+        assert_eq!(remove_field(&mut json_obj, "a.b.c"), None);
+        assert_eq!(remove_field(&mut json_obj, "a.d.e"), None);
+        assert_eq!(JsonValue::Object(json_obj), json!({"a": {"b": {}, "d": 2}}));
+    }
+}
@@ -0,0 +1,647 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of Avro and Protobuf records into JSON objects.
+//!
+//! Schemas are either provided inline in the source config, or fetched from a
+//! Confluent-compatible schema registry, in which case records are expected to be encoded with the
+//! Confluent wire format: a magic byte, followed by the schema ID as a big-endian `u32`, followed
+//! (for Protobuf only) by the message indexes, and finally the encoded record.
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context};
+use apache_avro::Schema as AvroSchema;
+use bytes::Bytes;
+use prost_reflect::{DynamicMessage, FileDescriptor, MessageDescriptor, SerializeOptions};
+use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+use protox::Compiler;
+use quickwit_config::{AvroInputFormatParams, ProtobufInputFormatParams, SourceInputFormat};
+use reqwest::header::ACCEPT;
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use tracing::info;
+
+use super::doc_processor::DocProcessorError;
+
+const CONFLUENT_MAGIC_BYTE: u8 = 0;
+
+const CONFLUENT_HEADER_LEN: usize = 5;
+
+const SCHEMA_REGISTRY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Name given to the file holding an inline or registered Protobuf schema.
+const PROTO_FILE_NAME: &str = "schema.proto";
+
+/// Decodes the records of the `avro` and `protobuf` input formats into JSON values.
+pub(super) struct RecordDecoder {
+    format: RecordFormat,
+    schema_registry_opt: Option<SchemaRegistryClient>,
+    // Schemas fetched from the registry, keyed by schema ID. Schemas that could not be found or
+    // parsed are cached as well so that the registry is not queried for every record.
+    registered_schemas: HashMap<u32, Result<RegisteredSchema, String>>,
+}
+
+enum RecordFormat {
+    InlineAvro(AvroSchema),
+    InlineProtobuf(MessageDescriptor),
+    RegisteredAvro,
+    RegisteredProtobuf,
+}
+
+enum RegisteredSchema {
+    Avro(AvroSchema),
+    Protobuf(FileDescriptor),
+}
+
+impl RecordDecoder {
+    /// Creates a record decoder for the `avro` and `protobuf` input formats. Returns `None` for
+    /// the other input formats.
+    pub fn try_new(
+        input_format: &SourceInputFormat,
+        schema_registry_url_opt: Option<&str>,
+    ) -> anyhow::Result<Option<Self>> {
+        let format = match input_format {
+            SourceInputFormat::Avro(AvroInputFormatParams {
+                schema: Some(schema),
+            }) => {
+                let avro_schema =
+                    AvroSchema::parse_str(schema).context("failed to parse inline Avro schema")?;
+                RecordFormat::InlineAvro(avro_schema)
+            }
+            SourceInputFormat::Avro(_) => RecordFormat::RegisteredAvro,
+            SourceInputFormat::Protobuf(ProtobufInputFormatParams {
+                schema: Some(schema),
+                message_name,
+            }) => {
+                let file_descriptor = compile_proto_schema(schema)
+                    .context("failed to compile inline Protobuf schema")?;
+                let message_descriptor = match message_name {
+                    Some(message_name) => file_descriptor
+                        .parent_pool()
+                        .get_message_by_name(message_name)
+                        .with_context(|| {
+                            format!("message `{message_name}` is not defined in Protobuf schema")
+                        })?,
+                    None => file_descriptor
+                        .messages()
+                        .next()
+                        .context("Protobuf schema does not define any message")?,
+                };
+                RecordFormat::InlineProtobuf(message_descriptor)
+            }
+            SourceInputFormat::Protobuf(_) => RecordFormat::RegisteredProtobuf,
+            _ => return Ok(None),
+        };
+        let schema_registry_opt = match format {
+            RecordFormat::RegisteredAvro | RecordFormat::RegisteredProtobuf => {
+                let schema_registry_url = schema_registry_url_opt.context(
+                    "Avro and Protobuf input formats require either an inline schema or a schema \
+                     registry",
+                )?;
+                Some(SchemaRegistryClient::try_new(schema_registry_url)?)
+            }
+            RecordFormat::InlineAvro(_) | RecordFormat::InlineProtobuf(_) => None,
+        };
+        let record_decoder = Self {
+            format,
+            schema_registry_opt,
+            registered_schemas: HashMap::new(),
+        };
+        Ok(Some(record_decoder))
+    }
+
+    /// Fetches from the schema registry the schemas referenced by the records that are not cached
+    /// yet. Errors are only returned when the registry cannot be reached.
+    pub async fn fetch_missing_schemas(&mut self, raw_docs: &[Bytes]) -> anyhow::Result<()> {
+        let Some(schema_registry) = &self.schema_registry_opt else {
+            return Ok(());
+        };
+        let missing_schema_ids: BTreeSet<u32> = raw_docs
+            .iter()
+            .filter_map(|raw_doc| parse_confluent_header(raw_doc).ok())
+            .map(|(schema_id, _)| schema_id)
+            .filter(|schema_id| !self.registered_schemas.contains_key(schema_id))
+            .collect();
+
+        for schema_id in missing_schema_ids {
+            let registered_schema_result = match schema_registry.fetch_schema(schema_id).await? {
+                Some(schema_response) => parse_registered_schema(&self.format, schema_response)
+                    .map_err(|error| format!("invalid schema `{schema_id}`: {error:#}")),
+                None => Err(format!("schema `{schema_id}` not found in schema registry")),
+            };
+            info!(schema_id=%schema_id, "fetched schema from schema registry");
+            self.registered_schemas
+                .insert(schema_id, registered_schema_result);
+        }
+        Ok(())
+    }
+
+    pub fn decode(&self, record: &[u8]) -> Result<JsonValue, DocProcessorError> {
+        match &self.format {
+            RecordFormat::InlineAvro(avro_schema) => decode_avro(avro_schema, record),
+            RecordFormat::InlineProtobuf(message_descriptor) => {
+                decode_protobuf(message_descriptor.clone(), record)
+            }
+            RecordFormat::RegisteredAvro | RecordFormat::RegisteredProtobuf => {
+                let (schema_id, payload) = parse_confluent_header(record)?;
+                let registered_schema = self
+                    .registered_schemas
+                    .get(&schema_id)
+                    .ok_or_else(|| {
+                        DocProcessorError::Parsing(format!("schema `{schema_id}` is not resolved"))
+                    })?
+                    .as_ref()
+                    .map_err(|error| DocProcessorError::Parsing(error.clone()))?;
+
+                match registered_schema {
+                    RegisteredSchema::Avro(avro_schema) => decode_avro(avro_schema, payload),
+                    RegisteredSchema::Protobuf(file_descriptor) => {
+                        let (message_descriptor, payload) =
+                            resolve_protobuf_message(file_descriptor, payload)?;
+                        decode_protobuf(message_descriptor, payload)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Splits a record encoded with the Confluent wire format into its schema ID and payload.
+fn parse_confluent_header(record: &[u8]) -> Result<(u32, &[u8]), DocProcessorError> {
+    if record.len() < CONFLUENT_HEADER_LEN || record[0] != CONFLUENT_MAGIC_BYTE {
+        return Err(DocProcessorError::Parsing(
+            "record is not encoded with the Confluent wire format".to_string(),
+        ));
+    }
+    let schema_id_bytes: [u8; 4] = record[1..CONFLUENT_HEADER_LEN]
+        .try_into()
+        .expect("slice should be 4 bytes long");
+    let schema_id = u32::from_be_bytes(schema_id_bytes);
+    Ok((schema_id, &record[CONFLUENT_HEADER_LEN..]))
+}
+
+fn parse_registered_schema(
+    format: &RecordFormat,
+    schema_response: SchemaResponse,
+) -> anyhow::Result<RegisteredSchema> {
+    if !schema_response.references.is_empty() {
+        bail!("schema references are not supported");
+    }
+    let registered_schema = match (format, schema_response.schema_type) {
+        (RecordFormat::RegisteredAvro, SchemaType::Avro) => {
+            let avro_schema = AvroSchema::parse_str(&schema_response.schema)?;
+            RegisteredSchema::Avro(avro_schema)
+        }
+        (RecordFormat::RegisteredProtobuf, SchemaType::Protobuf) => {
+            let file_descriptor = compile_proto_schema(&schema_response.schema)?;
+            RegisteredSchema::Protobuf(file_descriptor)
+        }
+        (_, schema_type) => bail!("unexpected schema type `{schema_type:?}`"),
+    };
+    Ok(registered_schema)
+}
+
+fn decode_avro(
+    avro_schema: &AvroSchema,
+    mut payload: &[u8],
+) -> Result<JsonValue, DocProcessorError> {
+    let avro_value =
+        apache_avro::from_avro_datum(avro_schema, &mut payload, None).map_err(|error| {
+            DocProcessorError::Parsing(format!("failed to decode Avro record: {error}"))
+        })?;
+    JsonValue::try_from(avro_value).map_err(|error| {
+        DocProcessorError::Parsing(format!("failed to convert Avro record to JSON: {error}"))
+    })
+}
+
+fn decode_protobuf(
+    message_descriptor: MessageDescriptor,
+    payload: &[u8],
+) -> Result<JsonValue, DocProcessorError> {
+    let message = DynamicMessage::decode(message_descriptor, payload).map_err(|error| {
+        DocProcessorError::Parsing(format!("failed to decode Protobuf record: {error}"))
+    })?;
+    // Field names are kept as is, and 64-bit integers are emitted as JSON numbers rather than
+    // strings so that they can be indexed in numeric fields.
+    let serialize_options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .stringify_64_bit_integers(false)
+        .skip_default_fields(false);
+    let json_value =
+        message.serialize_with_options(serde_json::value::Serializer, &serialize_options)?;
+    Ok(json_value)
+}
+
+/// Resolves the message type of a record from the message indexes that follow the Confluent
+/// header. The indexes designate the path of the message type in the tree of message types
+/// defined in the schema.
+fn resolve_protobuf_message<'a>(
+    file_descriptor: &FileDescriptor,
+    mut payload: &'a [u8],
+) -> Result<(MessageDescriptor, &'a [u8]), DocProcessorError> {
+    let num_indexes = read_zigzag_varint(&mut payload)?;
+
+    // A single `0` is used as a shorthand for the path `[0]`, i.e. the first message type.
+    let message_indexes = if num_indexes == 0 {
+        vec![0]
+    } else {
+        (0..num_indexes)
+            .map(|_| read_zigzag_varint(&mut payload))
+            .collect::<Result<Vec<usize>, _>>()?
+    };
+    let unknown_message_error = || {
+        DocProcessorError::Parsing(format!(
+            "message indexes {message_indexes:?} do not match any message type of the schema"
+        ))
+    };
+    let mut message_descriptor = file_descriptor
+        .messages()
+        .nth(message_indexes[0])
+        .ok_or_else(unknown_message_error)?;
+
+    for message_index in &message_indexes[1..] {
+        message_descriptor = message_descriptor
+            .child_messages()
+            .nth(*message_index)
+            .ok_or_else(unknown_message_error)?;
+    }
+    Ok((message_descriptor, payload))
+}
+
+fn read_zigzag_varint(payload: &mut &[u8]) -> Result<usize, DocProcessorError> {
+    let varint = prost::encoding::decode_varint(payload).map_err(|error| {
+        DocProcessorError::Parsing(format!("invalid Protobuf message indexes: {error}"))
+    })?;
+    let value = ((varint >> 1) as i64) ^ -((varint & 1) as i64);
+    usize::try_from(value).map_err(|_| {
+        DocProcessorError::Parsing(format!("invalid Protobuf message index `{value}`"))
+    })
+}
+
+/// Compiles a Protobuf schema. Imports of the well-known types (`google/protobuf/*.proto`) are
+/// supported.
+fn compile_proto_schema(schema: &str) -> anyhow::Result<FileDescriptor> {
+    let mut file_resolver = ChainFileResolver::new();
+    file_resolver.add(InlineFileResolver {
+        schema: schema.to_string(),
+    });
+    file_resolver.add(GoogleFileResolver::new());
+
+    let mut compiler = Compiler::with_file_resolver(file_resolver);
+    compiler
+        .open_file(PROTO_FILE_NAME)
+        .map_err(|error| anyhow!("{error}"))?;
+    let file_descriptor = compiler
+        .descriptor_pool()
+        .get_file_by_name(PROTO_FILE_NAME)
+        .expect("compiled file should be in the descriptor pool");
+    Ok(file_descriptor)
+}
+
+struct InlineFileResolver {
+    schema: String,
+}
+
+impl FileResolver for InlineFileResolver {
+    fn open_file(&self, name: &str) -> Result<File, protox::Error> {
+        if name == PROTO_FILE_NAME {
+            File::from_source(name, &self.schema)
+        } else {
+            Err(protox::Error::file_not_found(name))
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum SchemaType {
+    #[default]
+    Avro,
+    Protobuf,
+    Json,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemaResponse {
+    schema: String,
+    // The registry omits the schema type for Avro schemas.
+    #[serde(default)]
+    schema_type: SchemaType,
+    #[serde(default)]
+    references: Vec<JsonValue>,
+}
+
+/// Minimal client for the Confluent schema registry REST API.
+struct SchemaRegistryClient {
+    http_client: reqwest::Client,
+    base_url: Url,
+    credentials_opt: Option<(String, Option<String>)>,
+}
+
+impl SchemaRegistryClient {
+    /// Creates a client for the registry at `url`. Credentials for basic authentication can be
+    /// passed in the URL (`https://<username>:<password>@registry.example.com`).
+    fn try_new(url: &str) -> anyhow::Result<Self> {
+        let mut base_url =
+            Url::parse(url).with_context(|| format!("invalid schema registry URL `{url}`"))?;
+
+        let credentials_opt = if base_url.username().is_empty() {
+            None
+        } else {
+            let username = base_url.username().to_string();
+            let password_opt = base_url.password().map(|password| password.to_string());
+            let _ = base_url.set_username("");
+            let _ = base_url.set_password(None);
+            Some((username, password_opt))
+        };
+        // Makes sure that joining relative paths preserves the path of the registry, if any.
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+        let http_client = reqwest::Client::builder()
+            .timeout(SCHEMA_REGISTRY_TIMEOUT)
+            .build()
+            .context("failed to create schema registry client")?;
+        Ok(Self {
+            http_client,
+            base_url,
+            credentials_opt,
+        })
+    }
+
+    /// Fetches the schema with ID `schema_id`. Returns `None` if the registry does not know the
+    /// schema.
+    async fn fetch_schema(&self, schema_id: u32) -> anyhow::Result<Option<SchemaResponse>> {
+        let url = self.base_url.join(&format!("schemas/ids/{schema_id}"))?;
+        let mut request = self
+            .http_client
+            .get(url)
+            .header(ACCEPT, "application/vnd.schemaregistry.v1+json");
+
+        if let Some((username, password_opt)) = &self.credentials_opt {
+            request = request.basic_auth(username, password_opt.as_ref());
+        }
+        let response = request
+            .send()
+            .await
+            .context("failed to reach schema registry")?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let schema_response = response
+            .error_for_status()
+            .with_context(|| format!("failed to fetch schema `{schema_id}` from schema registry"))?
+            .json()
+            .await?;
+        Ok(Some(schema_response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::types::Record as AvroRecord;
+    use prost::Message;
+    use prost_reflect::Value as ProtobufValue;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    const AVRO_SCHEMA: &str = r#"{
+        "type": "record",
+        "name": "Order",
+        "fields": [
+            {"name": "id", "type": "string"},
+            {"name": "amount", "type": "double"},
+            {"name": "coupon", "type": ["null", "string"], "default": null}
+        ]
+    }"#;
+
+    const PROTO_SCHEMA: &str = r#"
+        syntax = "proto3";
+
+        package shop;
+
+        message Customer {
+            string name = 1;
+        }
+
+        message Order {
+            string id = 1;
+            int64 quantity = 2;
+
+            message Line {
+                string sku = 1;
+            }
+        }
+    "#;
+
+    fn encode_avro_order(avro_schema: &AvroSchema) -> Vec<u8> {
+        let mut avro_record = AvroRecord::new(avro_schema).unwrap();
+        avro_record.put("id", "order-1");
+        avro_record.put("amount", 12.5);
+        avro_record.put(
+            "coupon",
+            apache_avro::types::Value::Union(1, Box::new("SUMMER".into())),
+        );
+        apache_avro::to_avro_datum(avro_schema, avro_record).unwrap()
+    }
+
+    fn encode_protobuf_order(file_descriptor: &FileDescriptor) -> Vec<u8> {
+        let message_descriptor = file_descriptor
+            .parent_pool()
+            .get_message_by_name("shop.Order")
+            .unwrap();
+        let mut message = DynamicMessage::new(message_descriptor);
+        message.set_field_by_name("id", ProtobufValue::String("order-1".to_string()));
+        message.set_field_by_name("quantity", ProtobufValue::I64(3));
+        message.encode_to_vec()
+    }
+
+    fn confluent_record(schema_id: u32, message_indexes: &[u8], payload: &[u8]) -> Bytes {
+        let mut record = vec![CONFLUENT_MAGIC_BYTE];
+        record.extend_from_slice(&schema_id.to_be_bytes());
+        record.extend_from_slice(message_indexes);
+        record.extend_from_slice(payload);
+        Bytes::from(record)
+    }
+
+    #[test]
+    fn test_record_decoder_ignores_other_formats() {
+        assert!(RecordDecoder::try_new(&SourceInputFormat::Json, None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_record_decoder_inline_avro() {
+        let input_format = SourceInputFormat::Avro(AvroInputFormatParams {
+            schema: Some(AVRO_SCHEMA.to_string()),
+        });
+        let record_decoder = RecordDecoder::try_new(&input_format, None)
+            .unwrap()
+            .unwrap();
+        let avro_schema = AvroSchema::parse_str(AVRO_SCHEMA).unwrap();
+        let record = encode_avro_order(&avro_schema);
+
+        let json_value = record_decoder.decode(&record).unwrap();
+        assert_eq!(
+            json_value,
+            json!({"id": "order-1", "amount": 12.5, "coupon": "SUMMER"})
+        );
+        let error = record_decoder.decode(b"\xff").unwrap_err();
+        assert!(error.to_string().contains("failed to decode Avro record"));
+    }
+
+    #[test]
+    fn test_record_decoder_inline_protobuf() {
+        let input_format = SourceInputFormat::Protobuf(ProtobufInputFormatParams {
+            schema: Some(PROTO_SCHEMA.to_string()),
+            message_name: Some("shop.Order".to_string()),
+        });
+        let record_decoder = RecordDecoder::try_new(&input_format, None)
+            .unwrap()
+            .unwrap();
+        let file_descriptor = compile_proto_schema(PROTO_SCHEMA).unwrap();
+        let record = encode_protobuf_order(&file_descriptor);
+
+        let json_value = record_decoder.decode(&record).unwrap();
+        assert_eq!(json_value, json!({"id": "order-1", "quantity": 3}));
+
+        let input_format = SourceInputFormat::Protobuf(ProtobufInputFormatParams {
+            schema: Some(PROTO_SCHEMA.to_string()),
+            message_name: Some("shop.Invoice".to_string()),
+        });
+        let error = RecordDecoder::try_new(&input_format, None).err().unwrap();
+        assert!(error.to_string().contains("`shop.Invoice` is not defined"));
+    }
+
+    #[test]
+    fn test_resolve_protobuf_message() {
+        let file_descriptor = compile_proto_schema(PROTO_SCHEMA).unwrap();
+
+        let (message_descriptor, payload) =
+            resolve_protobuf_message(&file_descriptor, b"\x00payload").unwrap();
+        assert_eq!(message_descriptor.full_name(), "shop.Customer");
+        assert_eq!(payload, b"payload");
+
+        // Zigzag-encoded `[1, 0]`.
+        let (message_descriptor, payload) =
+            resolve_protobuf_message(&file_descriptor, b"\x04\x02\x00payload").unwrap();
+        assert_eq!(message_descriptor.full_name(), "shop.Order.Line");
+        assert_eq!(payload, b"payload");
+
+        resolve_protobuf_message(&file_descriptor, b"\x02\x04payload").unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_record_decoder_schema_registry() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/registry/schemas/ids/1"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({ "schema": AVRO_SCHEMA })),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/registry/schemas/ids/2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "schema": PROTO_SCHEMA,
+                "schemaType": "PROTOBUF"
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/registry/schemas/ids/3"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let schema_registry_url = format!("{}/registry", mock_server.uri());
+        let input_format = SourceInputFormat::Avro(AvroInputFormatParams::default());
+        let mut record_decoder = RecordDecoder::try_new(&input_format, Some(&schema_registry_url))
+            .unwrap()
+            .unwrap();
+        let avro_schema = AvroSchema::parse_str(AVRO_SCHEMA).unwrap();
+        let avro_payload = encode_avro_order(&avro_schema);
+        let raw_docs = vec![
+            confluent_record(1, &[], &avro_payload),
+            confluent_record(1, &[], &avro_payload),
+            confluent_record(2, &[], b""),
+            confluent_record(3, &[], b""),
+            Bytes::from_static(b"{}"),
+        ];
+        record_decoder
+            .fetch_missing_schemas(&raw_docs)
+            .await
+            .unwrap();
+        // Schemas are cached.
+        record_decoder
+            .fetch_missing_schemas(&raw_docs)
+            .await
+            .unwrap();
+
+        let json_value = record_decoder.decode(&raw_docs[0]).unwrap();
+        assert_eq!(
+            json_value,
+            json!({"id": "order-1", "amount": 12.5, "coupon": "SUMMER"})
+        );
+        let error = record_decoder.decode(&raw_docs[2]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("invalid schema `2`: unexpected schema type `Protobuf`"));
+
+        let error = record_decoder.decode(&raw_docs[3]).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("schema `3` not found in schema registry"));
+
+        let error = record_decoder.decode(&raw_docs[4]).unwrap_err();
+        assert!(error.to_string().contains("Confluent wire format"));
+
+        // Records with a registered Protobuf schema.
+        let input_format = SourceInputFormat::Protobuf(ProtobufInputFormatParams::default());
+        let mut record_decoder = RecordDecoder::try_new(&input_format, Some(&schema_registry_url))
+            .unwrap()
+            .unwrap();
+        let file_descriptor = compile_proto_schema(PROTO_SCHEMA).unwrap();
+        let raw_docs = vec![confluent_record(
+            2,
+            b"\x02\x02",
+            &encode_protobuf_order(&file_descriptor),
+        )];
+        record_decoder
+            .fetch_missing_schemas(&raw_docs)
+            .await
+            .unwrap();
+        let json_value = record_decoder.decode(&raw_docs[0]).unwrap();
+        assert_eq!(json_value, json!({"id": "order-1", "quantity": 3}));
+    }
+}
@@ -552,6 +552,7 @@ mod tests {
                 vec![PackagedSplit {
                     split_attrs: SplitAttrs {
                         partition_id: 3u64,
+                        doc_mapping_version: 0,
                         pipeline_id,
                         time_range: Some(
                             DateTime::from_timestamp_secs(1_628_203_589)
@@ -662,6 +663,7 @@ mod tests {
             split_attrs: SplitAttrs {
                 split_id: "test-split-1".to_string(),
                 partition_id: 3u64,
+                doc_mapping_version: 0,
                 pipeline_id: pipeline_id.clone(),
                 num_docs: 10,
                 uncompressed_docs_size_in_bytes: 1_000,
@@ -686,6 +688,7 @@ mod tests {
             split_attrs: SplitAttrs {
                 split_id: "test-split-2".to_string(),
                 partition_id: 3u64,
+                doc_mapping_version: 0,
                 pipeline_id,
                 num_docs: 10,
                 uncompressed_docs_size_in_bytes: 1_000,
@@ -811,6 +814,7 @@ mod tests {
                 vec![PackagedSplit {
                     split_attrs: SplitAttrs {
                         partition_id: 3u64,
+                        doc_mapping_version: 0,
                         pipeline_id,
                         time_range: None,
                         uncompressed_docs_size_in_bytes: 1_000,
@@ -987,6 +991,7 @@ mod tests {
                 vec![PackagedSplit {
                     split_attrs: SplitAttrs {
                         partition_id: 3u64,
+                        doc_mapping_version: 0,
                         pipeline_id,
                         time_range: Some(
                             DateTime::from_timestamp_secs(1_628_203_589)
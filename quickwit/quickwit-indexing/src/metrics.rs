@@ -25,8 +25,10 @@ use quickwit_common::metrics::{
 pub struct IndexerMetrics {
     pub processed_docs_total: IntCounterVec<2>,
     pub processed_bytes: IntCounterVec<2>,
+    pub processor_errors_total: IntCounterVec<2>,
     pub backpressure_micros: IntCounterVec<1>,
     pub available_concurrent_upload_permits: IntGaugeVec<1>,
+    pub kafka_consumer_lag: IntGaugeVec<3>,
     pub ongoing_merge_operations: IntGauge,
     pub pending_merge_operations: IntGauge,
     pub pending_merge_bytes: IntGauge,
@@ -49,6 +51,13 @@ impl Default for IndexerMetrics {
                 "quickwit_indexing",
                 ["index", "docs_processed_status"],
             ),
+            processor_errors_total: new_counter_vec(
+                "processor_errors_total",
+                "Number of docs rejected by a source processor, by index and processor (for \
+                 instance `grok[0]`)",
+                "quickwit_indexing",
+                ["index", "processor"],
+            ),
             backpressure_micros: new_counter_vec(
                 "backpressure_micros",
                 "Amount of time spent in backpressure (in micros). This time only includes the \
@@ -62,6 +71,13 @@ impl Default for IndexerMetrics {
                 "quickwit_indexing",
                 ["component"],
             ),
+            kafka_consumer_lag: new_gauge_vec(
+                "kafka_consumer_lag",
+                "Number of messages between the high watermark of a Kafka partition and the \
+                 position of the source consuming it, by index, source and partition",
+                "quickwit_indexing",
+                ["index", "source", "partition"],
+            ),
             ongoing_merge_operations: new_gauge(
                 "ongoing_merge_operations",
                 "Number of ongoing merge operations",
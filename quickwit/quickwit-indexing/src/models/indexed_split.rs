@@ -80,6 +80,7 @@ impl IndexedSplitBuilder {
     pub fn new_in_dir(
         pipeline_id: IndexingPipelineId,
         partition_id: u64,
+        doc_mapping_version: u64,
         last_delete_opstamp: u64,
         scratch_directory: TempDirectory,
         index_builder: IndexBuilder,
@@ -103,6 +104,7 @@ impl IndexedSplitBuilder {
             split_attrs: SplitAttrs {
                 pipeline_id,
                 partition_id,
+                doc_mapping_version,
                 split_id,
                 num_docs: 0,
                 replaced_split_ids: Vec::new(),
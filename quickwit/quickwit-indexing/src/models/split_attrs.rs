@@ -44,6 +44,10 @@ pub struct SplitAttrs {
     /// does not hurt correctness however.
     pub partition_id: u64,
 
+    /// Version of the doc mapping the split was built with. Splits with different doc mapping
+    /// versions can have different schemas and must not be merged together.
+    pub doc_mapping_version: u64,
+
     pub pipeline_id: IndexingPipelineId,
 
     /// Number of valid documents in the split.
@@ -70,6 +74,7 @@ impl fmt::Debug for SplitAttrs {
         f.debug_struct("SplitAttrs")
             .field("split_id", &self.split_id)
             .field("partition_id", &self.partition_id)
+            .field("doc_mapping_version", &self.doc_mapping_version)
             .field("replaced_split_ids", &self.replaced_split_ids)
             .field("time_range", &self.time_range)
             .field(
@@ -109,5 +114,6 @@ pub fn create_split_metadata(
         footer_offsets,
         delete_opstamp: split_attrs.delete_opstamp,
         num_merge_ops: split_attrs.num_merge_ops,
+        doc_mapping_version: split_attrs.doc_mapping_version,
     }
 }
@@ -17,19 +17,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::ffi::OsStr;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use std::{fmt, io};
 
 use anyhow::Context;
-use async_compression::tokio::bufread::GzipDecoder;
 use async_trait::async_trait;
 use bytes::Bytes;
 use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::compression::{decompress_reader, CompressionCodec};
 use quickwit_common::uri::Uri;
-use quickwit_config::FileSourceParams;
+use quickwit_config::{FileSourceParams, SourceInputFormat};
 use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
 use quickwit_proto::types::Position;
 use serde::Serialize;
@@ -38,6 +37,7 @@ use tracing::info;
 
 use crate::actors::DocProcessor;
 use crate::models::RawDocBatch;
+use crate::source::multiline::{MultilineAssembler, MultilineEvent};
 use crate::source::{Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
 
 /// Number of bytes after which a new batch is cut.
@@ -55,6 +55,10 @@ pub struct FileSource {
     params: FileSourceParams,
     counters: FileSourceCounters,
     reader: FileSourceReader,
+    multiline_assembler_opt: Option<MultilineAssembler>,
+    // Header row of a CSV file, re-emitted before the first line read when the source resumes
+    // from a checkpoint.
+    csv_header_opt: Option<Bytes>,
 }
 
 impl fmt::Debug for FileSource {
@@ -71,9 +75,20 @@ impl Source for FileSource {
         ctx: &SourceContext,
     ) -> Result<Duration, ActorExitStatus> {
         // We collect batches of documents before sending them to the indexer.
-        let limit_num_bytes = self.counters.previous_offset + BATCH_NUM_BYTES_LIMIT;
+        let limit_num_bytes = self.counters.current_offset + BATCH_NUM_BYTES_LIMIT;
         let mut reached_eof = false;
         let mut doc_batch = RawDocBatch::default();
+        // Offset up to which the lines have been added to the batch. With multiline assembly, the
+        // lines of the pending event are not part of the batch and must not be checkpointed.
+        let mut batch_offset = self.counters.previous_offset;
+
+        if let Some(event) = self
+            .multiline_assembler_opt
+            .as_mut()
+            .and_then(MultilineAssembler::flush_if_expired)
+        {
+            add_multiline_event(&mut doc_batch, &mut batch_offset, event);
+        }
         while self.counters.current_offset < limit_num_bytes {
             let mut doc_line = String::new();
             // guard the zone in case of slow read, such as reading from someone
@@ -86,11 +101,36 @@ impl Source for FileSource {
                 reached_eof = true;
                 break;
             }
-            doc_batch.docs.push(Bytes::from(doc_line));
+            let line_offset = self.counters.current_offset;
             self.counters.current_offset += num_bytes as u64;
             self.counters.num_lines_processed += 1;
+
+            if let Some(multiline_assembler) = &mut self.multiline_assembler_opt {
+                if let Some(event) = multiline_assembler.push_line(
+                    doc_line.as_bytes(),
+                    Position::offset(line_offset),
+                    Position::offset(self.counters.current_offset),
+                ) {
+                    add_multiline_event(&mut doc_batch, &mut batch_offset, event);
+                }
+            } else {
+                doc_batch.docs.push(Bytes::from(doc_line));
+                batch_offset = self.counters.current_offset;
+            }
+        }
+        if reached_eof {
+            if let Some(event) = self
+                .multiline_assembler_opt
+                .as_mut()
+                .and_then(MultilineAssembler::flush)
+            {
+                add_multiline_event(&mut doc_batch, &mut batch_offset, event);
+            }
         }
         if !doc_batch.docs.is_empty() {
+            if let Some(csv_header) = self.csv_header_opt.take() {
+                doc_batch.docs.insert(0, csv_header);
+            }
             if let Some(filepath) = &self.params.filepath {
                 let filepath_str = filepath
                     .to_str()
@@ -102,11 +142,11 @@ impl Source for FileSource {
                     .record_partition_delta(
                         partition_id,
                         Position::offset(self.counters.previous_offset),
-                        Position::offset(self.counters.current_offset),
+                        Position::offset(batch_offset),
                     )
                     .unwrap();
             }
-            self.counters.previous_offset = self.counters.current_offset;
+            self.counters.previous_offset = batch_offset;
             ctx.send_message(doc_processor_mailbox, doc_batch).await?;
         }
         if reached_eof {
@@ -126,6 +166,14 @@ impl Source for FileSource {
     }
 }
 
+fn add_multiline_event(doc_batch: &mut RawDocBatch, batch_offset: &mut u64, event: MultilineEvent) {
+    doc_batch.docs.push(event.doc);
+    *batch_offset = event
+        .to_position
+        .as_u64()
+        .expect("file offset should be stored as u64");
+}
+
 pub struct FileSourceFactory;
 
 #[async_trait]
@@ -140,6 +188,7 @@ impl TypedSourceFactory for FileSourceFactory {
         checkpoint: SourceCheckpoint,
     ) -> anyhow::Result<FileSource> {
         let mut offset = 0;
+        let mut csv_header_opt = None;
         let reader: FileSourceReader = if let Some(filepath) = &params.filepath {
             let partition_id = PartitionId::from(filepath.to_string_lossy().to_string());
             offset = checkpoint
@@ -153,11 +202,26 @@ impl TypedSourceFactory for FileSourceFactory {
             let (dir_uri, file_name) = dir_and_filename(filepath)?;
             let storage = ctx.storage_resolver.resolve(&dir_uri).await?;
             let file_size = storage.file_num_bytes(file_name).await?.try_into().unwrap();
-            // If it's a gzip file, we can't seek to a specific offset, we need to start from the
-            // beginning of the file, decompress and skip the first `offset` bytes.
-            if filepath.extension() == Some(OsStr::new("gz")) {
+            let compression_codec_opt = CompressionCodec::from_extension(filepath);
+
+            // The doc processor expects the CSV header row to be the first record of the
+            // pipeline, so it is read again when resuming from a checkpoint.
+            if offset > 0 && has_csv_header(&ctx.source_config.input_format) {
+                let mut stream = storage.get_slice_stream(file_name, 0..file_size).await?;
+                if let Some(codec) = compression_codec_opt {
+                    stream = decompress_reader(codec, stream);
+                }
+                let mut csv_header = Vec::new();
+                BufReader::new(stream)
+                    .read_until(b'\n', &mut csv_header)
+                    .await?;
+                csv_header_opt = Some(Bytes::from(csv_header));
+            }
+            // If it's a compressed file, we can't seek to a specific offset, we need to start from
+            // the beginning of the file, decompress and skip the first `offset` bytes.
+            if let Some(codec) = compression_codec_opt {
                 let stream = storage.get_slice_stream(file_name, 0..file_size).await?;
-                FileSourceReader::new(Box::new(GzipDecoder::new(BufReader::new(stream))), offset)
+                FileSourceReader::new(decompress_reader(codec, stream), offset)
             } else {
                 let stream = storage
                     .get_slice_stream(file_name, offset..file_size)
@@ -168,6 +232,12 @@ impl TypedSourceFactory for FileSourceFactory {
             // We cannot use the checkpoint.
             FileSourceReader::new(Box::new(tokio::io::stdin()), 0)
         };
+        let multiline_assembler_opt = ctx
+            .source_config
+            .multiline_config
+            .as_ref()
+            .map(MultilineAssembler::try_new)
+            .transpose()?;
         let file_source = FileSource {
             source_id: ctx.source_id().to_string(),
             counters: FileSourceCounters {
@@ -177,11 +247,17 @@ impl TypedSourceFactory for FileSourceFactory {
             },
             reader,
             params,
+            multiline_assembler_opt,
+            csv_header_opt,
         };
         Ok(file_source)
     }
 }
 
+fn has_csv_header(input_format: &SourceInputFormat) -> bool {
+    matches!(input_format, SourceInputFormat::Csv(csv_params) if csv_params.has_header())
+}
+
 struct FileSourceReader {
     reader: BufReader<Box<dyn AsyncRead + Send + Unpin>>,
     num_bytes_to_skip: usize,
@@ -195,7 +271,7 @@ impl FileSourceReader {
         }
     }
 
-    // This function is only called for GZIP file.
+    // This function is only called for compressed files.
     // Because they cannot be seeked into, we have to scan them to the right initial position.
     async fn skip(&mut self) -> io::Result<()> {
         // Allocate once a 64kb buffer.
@@ -238,9 +314,11 @@ mod tests {
     use std::num::NonZeroUsize;
     use std::path::PathBuf;
 
-    use async_compression::tokio::write::GzipEncoder;
+    use async_compression::tokio::write::{GzipEncoder, ZstdEncoder};
     use quickwit_actors::{Command, Universe};
-    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{
+        CsvInputFormatParams, MultilineConfig, SourceConfig, SourceInputFormat, SourceParams,
+    };
     use quickwit_metastore::checkpoint::{SourceCheckpoint, SourceCheckpointDelta};
     use quickwit_metastore::metastore_for_test;
     use quickwit_proto::types::IndexUid;
@@ -270,6 +348,9 @@ mod tests {
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let file_source = FileSourceFactory::typed_create_source(
@@ -353,6 +434,9 @@ mod tests {
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let source = FileSourceFactory::typed_create_source(
@@ -462,6 +546,9 @@ mod tests {
             source_params: SourceParams::File(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let source = FileSourceFactory::typed_create_source(
@@ -496,6 +583,249 @@ mod tests {
         assert!(&indexer_messages[0].docs[0].starts_with(b"2\n"));
     }
 
+    #[tokio::test]
+    async fn test_file_source_resume_from_checkpoint_with_csv_header() {
+        aux_test_file_source_resume_from_checkpoint_with_csv_header(false).await;
+        aux_test_file_source_resume_from_checkpoint_with_csv_header(true).await;
+    }
+
+    async fn aux_test_file_source_resume_from_checkpoint_with_csv_header(gzip: bool) {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let documents_bytes = b"host,status\ncdn-1,200\ncdn-2,404\n";
+        let mut temp_file: tempfile::NamedTempFile = if gzip {
+            tempfile::Builder::new().suffix(".gz").tempfile().unwrap()
+        } else {
+            tempfile::NamedTempFile::new().unwrap()
+        };
+        let temp_file_path = temp_file.path().canonicalize().unwrap();
+        if gzip {
+            let gzipped_documents = gzip_bytes(documents_bytes).await;
+            temp_file.write_all(&gzipped_documents).unwrap();
+        } else {
+            temp_file.write_all(documents_bytes).unwrap();
+        }
+        temp_file.flush().unwrap();
+
+        let params = FileSourceParams::file(&temp_file_path);
+        let mut checkpoint = SourceCheckpoint::default();
+        let partition_id = PartitionId::from(temp_file_path.to_string_lossy().to_string());
+        let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id.clone(),
+            Position::offset(0u64),
+            Position::offset(22u64),
+        )
+        .unwrap();
+        checkpoint.try_apply_delta(checkpoint_delta).unwrap();
+
+        let source_config = SourceConfig {
+            source_id: "test-file-source".to_string(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::File(params.clone()),
+            transform_config: None,
+            input_format: SourceInputFormat::Csv(CsvInputFormatParams::default()),
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
+        };
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                IndexUid::new_with_random_ulid("test-index"),
+                source_config,
+                metastore,
+                PathBuf::from("./queues"),
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, _counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+
+        let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(indexer_messages.len(), 1);
+        assert_eq!(
+            indexer_messages[0].docs,
+            [
+                Bytes::from_static(b"host,status\n"),
+                Bytes::from_static(b"cdn-2,404\n")
+            ]
+        );
+        // The header row is not checkpointed.
+        let expected_checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::offset(22u64),
+            Position::offset(32u64),
+        )
+        .unwrap();
+        assert_eq!(
+            indexer_messages[0].checkpoint_delta,
+            expected_checkpoint_delta
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_file_source_zstd() {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let mut documents_bytes = Vec::new();
+        for i in 0..100 {
+            documents_bytes
+                .write_all(format!("{i}\n").as_bytes())
+                .unwrap();
+        }
+        let mut zstd_documents = Vec::new();
+        let mut encoder = ZstdEncoder::new(&mut zstd_documents);
+        tokio::io::AsyncWriteExt::write_all(&mut encoder, &documents_bytes)
+            .await
+            .unwrap();
+        tokio::io::AsyncWriteExt::shutdown(&mut encoder)
+            .await
+            .unwrap();
+
+        let mut temp_file = tempfile::Builder::new().suffix(".zst").tempfile().unwrap();
+        temp_file.write_all(&zstd_documents).unwrap();
+        temp_file.flush().unwrap();
+
+        let params = FileSourceParams::file(temp_file.path());
+        let source_config = SourceConfig {
+            source_id: "test-file-source".to_string(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::File(params.clone()),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
+        };
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                IndexUid::new_with_random_ulid("test-index"),
+                source_config,
+                metastore,
+                PathBuf::from("./queues"),
+            ),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 290u64,
+                "current_offset": 290u64,
+                "num_lines_processed": 100u64
+            })
+        );
+        let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert!(&indexer_messages[0].docs[0].starts_with(b"0\n"));
+    }
+
+    #[tokio::test]
+    async fn test_file_source_multiline() {
+        let universe = Universe::with_accelerated_time();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let mut documents_bytes = Vec::new();
+        for i in 0..3 {
+            documents_bytes
+                .write_all(format!("event {i}\n  at line {i}\n  at line {}\n", i + 1).as_bytes())
+                .unwrap();
+        }
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file.write_all(&documents_bytes).unwrap();
+        temp_file.flush().unwrap();
+
+        let temp_file_path = temp_file.path().canonicalize().unwrap();
+        let params = FileSourceParams::file(&temp_file_path);
+        let source_config = SourceConfig {
+            source_id: "test-file-source".to_string(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::File(params.clone()),
+            transform_config: None,
+            input_format: SourceInputFormat::PlainText,
+            dead_letter_config: None,
+            multiline_config: Some(MultilineConfig::for_test("^event")),
+            processors: Vec::new(),
+        };
+        let metastore = metastore_for_test();
+        let source = FileSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                IndexUid::new_with_random_ulid("test-index"),
+                source_config,
+                metastore,
+                PathBuf::from("./queues"),
+            ),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap();
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            doc_processor_mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_builder().spawn(file_source_actor);
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+
+        let num_bytes = documents_bytes.len() as u64;
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": num_bytes,
+                "current_offset": num_bytes,
+                "num_lines_processed": 9u64
+            })
+        );
+        let indexer_messages: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(indexer_messages.len(), 1);
+        assert_eq!(
+            indexer_messages[0].docs,
+            [
+                "event 0\n  at line 0\n  at line 1",
+                "event 1\n  at line 1\n  at line 2",
+                "event 2\n  at line 2\n  at line 3",
+            ]
+        );
+        let partition_id = PartitionId::from(temp_file_path.to_string_lossy().to_string());
+        let expected_checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::offset(0u64),
+            Position::offset(num_bytes),
+        )
+        .unwrap();
+        assert_eq!(
+            indexer_messages[0].checkpoint_delta,
+            expected_checkpoint_delta
+        );
+    }
+
     async fn gzip_bytes(bytes: &[u8]) -> Vec<u8> {
         let mut gzip_documents = Vec::new();
         let mut encoder = GzipEncoder::new(&mut gzip_documents);
@@ -325,6 +325,9 @@ mod gcp_pubsub_emulator_tests {
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         }
     }
 
@@ -35,6 +35,7 @@ use serde_json::Value as JsonValue;
 use super::{Source, SourceActor, SourceContext, TypedSourceFactory};
 use crate::actors::DocProcessor;
 use crate::models::RawDocBatch;
+use crate::source::multiline::{MultilineAssembler, MultilineEvent};
 use crate::source::SourceRuntimeArgs;
 
 /// Wait time for SourceActor before pooling for new documents.
@@ -47,7 +48,8 @@ pub struct IngestApiSourceCounters {
     /// Maintains the value of where we stopped in queue from
     /// a previous call on `emit_batch` and allows
     /// setting the lower-bound of the checkpoint delta.
-    /// It has the same value as `current_offset` at the end of emit_batch.
+    /// It has the same value as `current_offset` at the end of emit_batch, unless
+    /// the docs of a pending multiline event have been fetched.
     pub previous_offset: Option<u64>,
     /// Maintains the value of where we are in queue and allows
     /// setting the upper-bound of the checkpoint delta.
@@ -61,6 +63,7 @@ pub struct IngestApiSource {
     partition_id: PartitionId,
     ingest_api_service: Mailbox<IngestApiService>,
     counters: IngestApiSourceCounters,
+    multiline_assembler_opt: Option<MultilineAssembler>,
 }
 
 impl fmt::Debug for IngestApiSource {
@@ -89,6 +92,12 @@ impl IngestApiSource {
             .position_for_partition(&partition_id)
             .map(|position| position.as_u64().expect("offset should be stored as u64"));
         let current_offset = previous_offset;
+        let multiline_assembler_opt = runtime_args
+            .source_config
+            .multiline_config
+            .as_ref()
+            .map(MultilineAssembler::try_new)
+            .transpose()?;
         let ingest_api_source = IngestApiSource {
             runtime_args,
             source_id,
@@ -99,6 +108,7 @@ impl IngestApiSource {
                 current_offset,
                 num_docs_processed: 0,
             },
+            multiline_assembler_opt,
         };
         Ok(ingest_api_source)
     }
@@ -117,13 +127,44 @@ impl IngestApiSource {
         Ok(())
     }
 
-    fn update_counters(&mut self, current_offset: u64, num_docs: u64) {
-        self.counters.num_docs_processed += num_docs;
-        self.counters.current_offset = Some(current_offset);
-        self.counters.previous_offset = Some(current_offset);
+    /// Records the checkpoint delta of the batch, which covers the queue up to `batch_offset`
+    /// included, and sends the batch to the doc processor.
+    async fn send_batch(
+        &mut self,
+        mut raw_doc_batch: RawDocBatch,
+        batch_offset: u64,
+        batch_sink: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<(), ActorExitStatus> {
+        let partition_id = self.partition_id.clone();
+        raw_doc_batch
+            .checkpoint_delta
+            .record_partition_delta(
+                partition_id,
+                self.counters
+                    .previous_offset
+                    .map(Position::offset)
+                    .unwrap_or_default(),
+                Position::offset(batch_offset),
+            )
+            .map_err(anyhow::Error::from)?;
+
+        self.counters.num_docs_processed += raw_doc_batch.docs.len() as u64;
+        self.counters.previous_offset = Some(batch_offset);
+        ctx.send_message(batch_sink, raw_doc_batch).await?;
+        Ok(())
     }
 }
 
+fn add_multiline_event(
+    raw_doc_batch: &mut RawDocBatch,
+    batch_offset_opt: &mut Option<u64>,
+    event: MultilineEvent,
+) {
+    raw_doc_batch.docs.push(event.doc);
+    *batch_offset_opt = event.to_position.as_u64();
+}
+
 #[async_trait]
 impl Source for IngestApiSource {
     async fn initialize(
@@ -163,33 +204,74 @@ impl Source for IngestApiSource {
         let (first_position, doc_batch) = if let Some(first_position) = first_position_opt {
             (first_position, doc_batch_opt.unwrap())
         } else {
+            if let Some(event) = self
+                .multiline_assembler_opt
+                .as_mut()
+                .and_then(MultilineAssembler::flush_if_expired)
+            {
+                let batch_offset = event
+                    .to_position
+                    .as_u64()
+                    .expect("offset should be stored as u64");
+                let mut raw_doc_batch = RawDocBatch::with_capacity(1);
+                raw_doc_batch.docs.push(event.doc);
+                self.send_batch(raw_doc_batch, batch_offset, batch_sink, ctx)
+                    .await?;
+            }
             return Ok(INGEST_API_POLLING_COOL_DOWN);
         };
 
         // TODO use a timestamp (in the raw doc batch) given by at ingest time to be more accurate.
         let mut raw_doc_batch = RawDocBatch::with_capacity(doc_batch.num_docs());
-        for doc in doc_batch.iter() {
-            match doc {
-                DocCommand::Ingest { payload } => raw_doc_batch.docs.push(payload),
-                DocCommand::Commit => raw_doc_batch.force_commit = true,
+        // Position up to which the docs have been added to the batch. With multiline assembly, the
+        // docs of the pending event are not part of the batch and must not be checkpointed.
+        let mut batch_offset_opt = self.counters.previous_offset;
+        let mut previous_position = self
+            .counters
+            .current_offset
+            .map(Position::offset)
+            .unwrap_or_default();
+
+        for (doc_idx, doc) in doc_batch.iter().enumerate() {
+            let current_offset = first_position + doc_idx as u64;
+            let current_position = Position::offset(current_offset);
+
+            match (doc, &mut self.multiline_assembler_opt) {
+                (DocCommand::Ingest { payload }, Some(multiline_assembler)) => {
+                    if let Some(event) = multiline_assembler.push_line(
+                        &payload,
+                        previous_position,
+                        current_position.clone(),
+                    ) {
+                        add_multiline_event(&mut raw_doc_batch, &mut batch_offset_opt, event);
+                    }
+                }
+                (DocCommand::Ingest { payload }, None) => {
+                    raw_doc_batch.docs.push(payload);
+                    batch_offset_opt = Some(current_offset);
+                }
+                (DocCommand::Commit, multiline_assembler_opt) => {
+                    if let Some(event) = multiline_assembler_opt
+                        .as_mut()
+                        .and_then(MultilineAssembler::flush)
+                    {
+                        add_multiline_event(&mut raw_doc_batch, &mut batch_offset_opt, event);
+                    }
+                    raw_doc_batch.force_commit = true;
+                    batch_offset_opt = Some(current_offset);
+                }
             }
+            previous_position = current_position;
         }
         let current_offset = first_position + doc_batch.num_docs() as u64 - 1;
-        let partition_id = self.partition_id.clone();
-        raw_doc_batch
-            .checkpoint_delta
-            .record_partition_delta(
-                partition_id,
-                self.counters
-                    .previous_offset
-                    .map(Position::offset)
-                    .unwrap_or_default(),
-                Position::offset(current_offset),
-            )
-            .map_err(anyhow::Error::from)?;
+        self.counters.current_offset = Some(current_offset);
 
-        self.update_counters(current_offset, raw_doc_batch.docs.len() as u64);
-        ctx.send_message(batch_sink, raw_doc_batch).await?;
+        if let Some(batch_offset) = batch_offset_opt {
+            if batch_offset_opt != self.counters.previous_offset {
+                self.send_batch(raw_doc_batch, batch_offset, batch_sink, ctx)
+                    .await?;
+            }
+        }
         Ok(Duration::default())
     }
 
@@ -242,7 +324,8 @@ mod tests {
     use quickwit_actors::Universe;
     use quickwit_common::rand::append_random_suffix;
     use quickwit_config::{
-        IngestApiConfig, SourceConfig, SourceInputFormat, SourceParams, INGEST_API_SOURCE_ID,
+        IngestApiConfig, MultilineConfig, SourceConfig, SourceInputFormat, SourceParams,
+        INGEST_API_SOURCE_ID,
     };
     use quickwit_ingest::{init_ingest_api, CommitType, DocBatchBuilder, IngestRequest};
     use quickwit_metastore::checkpoint::{SourceCheckpoint, SourceCheckpointDelta};
@@ -286,6 +369,9 @@ mod tests {
             source_params: SourceParams::IngestApi,
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         }
     }
 
@@ -511,6 +597,136 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_source_multiline() -> anyhow::Result<()> {
+        let universe = Universe::with_accelerated_time();
+        let metastore = metastore_for_test();
+        let index_id = append_random_suffix("test-ingest-api-source");
+        let index_uid = IndexUid::new_with_random_ulid(&index_id);
+        let temp_dir = tempfile::tempdir()?;
+        let queues_dir_path = temp_dir.path();
+        let ingest_api_service =
+            init_ingest_api(&universe, queues_dir_path, &IngestApiConfig::default()).await?;
+
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let mut source_config = make_source_config();
+        source_config.input_format = SourceInputFormat::PlainText;
+        source_config.multiline_config = Some(MultilineConfig::for_test("^(ERROR|INFO)"));
+        let ctx = SourceRuntimeArgs::for_test(
+            index_uid.clone(),
+            source_config.clone(),
+            metastore.clone(),
+            queues_dir_path.to_path_buf(),
+        );
+        let ingest_api_source = IngestApiSource::try_new(ctx, SourceCheckpoint::default()).await?;
+        let ingest_api_source_actor = SourceActor {
+            source: Box::new(ingest_api_source),
+            doc_processor_mailbox,
+        };
+        let (_ingest_api_source_mailbox, ingest_api_source_handle) =
+            universe.spawn_builder().spawn(ingest_api_source_actor);
+
+        let mut doc_batch_builder = DocBatchBuilder::new(index_id.clone());
+        for line in ["ERROR boom", "  at a", "  at b", "INFO ok"] {
+            doc_batch_builder.ingest_doc(line.as_bytes());
+        }
+        let ingest_req = IngestRequest {
+            doc_batches: vec![doc_batch_builder.build()],
+            commit: CommitType::Auto.into(),
+        };
+        ingest_api_service
+            .ask_for_res(ingest_req)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+        universe.sleep(Duration::from_secs(2)).await;
+        let counters = ingest_api_source_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        // The last event is still pending, so it is not checkpointed: `previous_offset` stays at
+        // the last doc of the last event sent while `current_offset` moves to the last doc
+        // fetched.
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 2u64,
+                "current_offset": 3u64,
+                "num_docs_processed": 1u64
+            })
+        );
+        let doc_batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(doc_batches.len(), 1);
+        assert_eq!(doc_batches[0].docs, ["ERROR boom\n  at a\n  at b"]);
+
+        let partition_id: PartitionId = ingest_api_service.ask(GetPartitionId).await?.into();
+        let expected_checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id.clone(),
+            Position::Beginning,
+            Position::offset(2u64),
+        )?;
+        assert_eq!(doc_batches[0].checkpoint_delta, expected_checkpoint_delta);
+        ingest_api_source_handle.quit().await;
+
+        // A source restarting from the published checkpoint fetches the docs of the pending event
+        // again. Its checkpoint delta starts after the last event sent.
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint.try_apply_delta(expected_checkpoint_delta)?;
+
+        let mut doc_batch_builder = DocBatchBuilder::new(index_id.clone());
+        doc_batch_builder.ingest_doc(b"ERROR again");
+        let ingest_req = IngestRequest {
+            doc_batches: vec![doc_batch_builder.build()],
+            commit: CommitType::Auto.into(),
+        };
+        ingest_api_service
+            .ask_for_res(ingest_req)
+            .await
+            .map_err(|err| anyhow::anyhow!(err.to_string()))?;
+
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let ctx = SourceRuntimeArgs::for_test(
+            index_uid,
+            source_config,
+            metastore,
+            queues_dir_path.to_path_buf(),
+        );
+        let ingest_api_source = IngestApiSource::try_new(ctx, checkpoint).await?;
+        let ingest_api_source_actor = SourceActor {
+            source: Box::new(ingest_api_source),
+            doc_processor_mailbox,
+        };
+        let (_ingest_api_source_mailbox, ingest_api_source_handle) =
+            universe.spawn_builder().spawn(ingest_api_source_actor);
+
+        universe.sleep(Duration::from_secs(2)).await;
+        let counters = ingest_api_source_handle
+            .process_pending_and_observe()
+            .await
+            .state;
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 3u64,
+                "current_offset": 4u64,
+                "num_docs_processed": 1u64
+            })
+        );
+        let doc_batches: Vec<RawDocBatch> = doc_processor_inbox.drain_for_test_typed();
+        assert_eq!(doc_batches.len(), 1);
+        assert_eq!(doc_batches[0].docs, ["INFO ok"]);
+
+        let expected_checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::offset(2u64),
+            Position::offset(3u64),
+        )?;
+        assert_eq!(doc_batches[0].checkpoint_delta, expected_checkpoint_delta);
+
+        ingest_api_source_handle.quit().await;
+        universe.assert_quit().await;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_ingest_api_source_with_force_commit() -> anyhow::Result<()> {
         let universe = Universe::with_accelerated_time();
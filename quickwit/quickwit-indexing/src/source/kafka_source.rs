@@ -17,9 +17,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, bail, Context};
@@ -39,6 +39,7 @@ use rdkafka::consumer::{
 };
 use rdkafka::error::KafkaError;
 use rdkafka::message::BorrowedMessage;
+use rdkafka::statistics::Statistics;
 use rdkafka::util::Timeout;
 use rdkafka::{ClientContext, Message, Offset, TopicPartitionList};
 use serde_json::{json, Value as JsonValue};
@@ -48,7 +49,9 @@ use tokio::time;
 use tracing::{debug, info, warn};
 
 use crate::actors::DocProcessor;
+use crate::metrics::INDEXER_METRICS;
 use crate::models::{NewPublishLock, PublishLock};
+use crate::source::multiline::{MultilineAssembler, MultilineEvent};
 use crate::source::{
     BatchBuilder, Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory,
     BATCH_NUM_BYTES_LIMIT, EMIT_BATCHES_TIMEOUT,
@@ -81,12 +84,25 @@ enum KafkaEvent {
         assignment_tx: oneshot::Sender<Vec<(i32, Offset)>>,
     },
     RevokePartitions {
-        ack_tx: oneshot::Sender<()>,
+        partitions: Vec<i32>,
+        seek_tx: oneshot::Sender<Vec<(i32, Offset)>>,
     },
     PartitionEOF(i32),
     Error(anyhow::Error),
 }
 
+/// Outcome of [`RdKafkaContext::send_event`].
+#[derive(Debug, Eq, PartialEq)]
+enum SendEventOutcome {
+    Sent,
+    /// The event was held in the backlog. The partition of the message, if it was not paused
+    /// yet, must be paused.
+    Held {
+        partition_to_pause_opt: Option<i32>,
+    },
+    SourceDropped,
+}
+
 #[derive(Debug)]
 struct KafkaMessage {
     doc_opt: Option<Bytes>,
@@ -107,11 +123,143 @@ impl From<BorrowedMessage<'_>> for KafkaMessage {
 }
 
 struct RdKafkaContext {
+    index_id: String,
+    source_id: String,
     topic: String,
     events_tx: mpsc::Sender<KafkaEvent>,
+    /// Events that could not be sent to the source because the events channel was full. The
+    /// partitions of the messages held in the backlog are paused until it is drained.
+    backlog: Mutex<VecDeque<KafkaEvent>>,
+    paused_partitions: Mutex<HashSet<i32>>,
+    /// Positions to which the partitions retained after a revocation must be rewound. The seeks
+    /// are performed by the poll loop once the rebalance callback has returned.
+    pending_seeks: Mutex<Vec<(i32, Offset)>>,
 }
 
-impl ClientContext for RdKafkaContext {}
+impl RdKafkaContext {
+    /// Sends the event to the source without blocking. The event is held in the backlog instead
+    /// if the events channel is full or if events are already waiting in the backlog.
+    fn send_event(&self, event: KafkaEvent) -> SendEventOutcome {
+        let mut backlog = self.backlog.lock().unwrap();
+
+        let event = if backlog.is_empty() {
+            match self.events_tx.try_send(event) {
+                Ok(()) => return SendEventOutcome::Sent,
+                Err(mpsc::error::TrySendError::Full(event)) => event,
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    return SendEventOutcome::SourceDropped;
+                }
+            }
+        } else {
+            event
+        };
+        let mut partition_to_pause_opt = None;
+
+        if let KafkaEvent::Message(message) = &event {
+            if self
+                .paused_partitions
+                .lock()
+                .unwrap()
+                .insert(message.partition)
+            {
+                partition_to_pause_opt = Some(message.partition);
+            }
+        }
+        backlog.push_back(event);
+        SendEventOutcome::Held {
+            partition_to_pause_opt,
+        }
+    }
+
+    /// Sends the events of the backlog to the source without blocking. Returns `None` if events
+    /// are still waiting in the backlog afterwards, and the paused partitions, which must be
+    /// resumed, otherwise.
+    fn try_flush_backlog(&self) -> Option<Vec<i32>> {
+        let mut backlog = self.backlog.lock().unwrap();
+
+        while let Some(event) = backlog.pop_front() {
+            match self.events_tx.try_send(event) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(event)) => {
+                    backlog.push_front(event);
+                    return None;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    backlog.clear();
+                }
+            }
+        }
+        let partitions_to_resume = self
+            .paused_partitions
+            .lock()
+            .unwrap()
+            .drain()
+            .sorted()
+            .collect();
+        Some(partitions_to_resume)
+    }
+
+    /// Sends the events of the backlog to the source, waiting for room in the events channel if
+    /// necessary. Rebalance events must not overtake the messages of the backlog.
+    fn flush_backlog(&self) {
+        let mut backlog = self.backlog.lock().unwrap();
+
+        for event in backlog.drain(..) {
+            if self.events_tx.blocking_send(event).is_err() {
+                debug!("failed to flush backlog: the source was dropped");
+                break;
+            }
+        }
+    }
+
+    fn take_pending_seeks(&self) -> Vec<(i32, Offset)> {
+        std::mem::take(&mut *self.pending_seeks.lock().unwrap())
+    }
+
+    fn record_consumer_lags(&self, partition_lags: impl IntoIterator<Item = (i32, i64)>) {
+        for (partition, consumer_lag) in partition_lags {
+            // librdkafka reports an internal partition `-1` and a lag of `-1` when the lag is
+            // unknown.
+            if partition < 0 || consumer_lag < 0 {
+                continue;
+            }
+            INDEXER_METRICS
+                .kafka_consumer_lag
+                .with_label_values([&self.index_id, &self.source_id, &partition.to_string()])
+                .set(consumer_lag);
+        }
+    }
+
+    /// Stops reporting the consumer lag of partitions that are no longer assigned to the source.
+    fn remove_consumer_lags(&self, partitions: &[i32]) {
+        for partition in partitions {
+            INDEXER_METRICS.kafka_consumer_lag.remove_label_values([
+                &self.index_id,
+                &self.source_id,
+                &partition.to_string(),
+            ]);
+        }
+    }
+}
+
+impl ClientContext for RdKafkaContext {
+    /// Records the consumer lag of the partitions assigned to the source, i.e. the number of
+    /// messages between their high watermark and the position of the consumer. Statistics are
+    /// emitted every `statistics.interval.ms`.
+    fn stats(&self, statistics: Statistics) {
+        let Some(topic_statistics) = statistics.topics.get(&self.topic) else {
+            return;
+        };
+        let partition_lags =
+            topic_statistics
+                .partitions
+                .iter()
+                .map(|(partition, partition_statistics)| {
+                    (*partition, partition_statistics.consumer_lag)
+                });
+        self.record_consumer_lags(partition_lags);
+    }
+}
 
 macro_rules! return_if_err {
     ($expression:expr, $lit: literal) => {
@@ -132,6 +280,11 @@ macro_rules! return_if_err {
 /// - Broker waits for ALL the consumers to ack the revoke notification (synchronization barrier).
 /// - Consumers receive new partition assignmennts.
 ///
+/// With the eager protocol, all the partitions are revoked and reassigned on every rebalance.
+/// With the cooperative protocol (`partition.assignment.strategy: cooperative-sticky`), only the
+/// partitions changing owner are revoked or assigned: the notifications carry incremental
+/// changes.
+///
 /// The API of the rebalance callback is better explained in the docs of `librdkafka`:
 /// <https://docs.confluent.io/2.0.0/clients/librdkafka/classRdKafka_1_1RebalanceCb.html>
 impl ConsumerContext for RdKafkaContext {
@@ -140,18 +293,33 @@ impl ConsumerContext for RdKafkaContext {
             let partitions = collect_partitions(tpl, &self.topic);
             debug!(partitions=?partitions, "revoke partitions");
 
-            let (ack_tx, ack_rx) = oneshot::channel();
+            self.flush_backlog();
+            self.remove_consumer_lags(&partitions);
+
+            // Revoked partitions lose their paused state.
+            let mut paused_partitions = self.paused_partitions.lock().unwrap();
+            for partition in &partitions {
+                paused_partitions.remove(partition);
+            }
+            drop(paused_partitions);
+
+            let (seek_tx, seek_rx) = oneshot::channel();
             return_if_err!(
-                self.events_tx
-                    .blocking_send(KafkaEvent::RevokePartitions { ack_tx }),
+                self.events_tx.blocking_send(KafkaEvent::RevokePartitions {
+                    partitions,
+                    seek_tx,
+                }),
                 "failed to send revoke message to source"
             );
-            return_if_err!(ack_rx.recv(), "failed to receive revoke ack from source");
+            let seeks = return_if_err!(seek_rx.recv(), "failed to receive revoke ack from source");
+            self.pending_seeks.lock().unwrap().extend(seeks);
         }
         if let Rebalance::Assign(tpl) = rebalance {
             let partitions = collect_partitions(tpl, &self.topic);
             debug!(partitions=?partitions, "assign partitions");
 
+            self.flush_backlog();
+
             let (assignment_tx, assignment_rx) = oneshot::channel();
             return_if_err!(
                 self.events_tx.blocking_send(KafkaEvent::AssignPartitions {
@@ -198,8 +366,11 @@ pub struct KafkaSourceState {
     pub assigned_partitions: HashMap<i32, PartitionId>,
     /// Offset for each partition of the last message received.
     pub current_positions: HashMap<i32, Position>,
-    /// Number of inactive partitions, i.e., that have reached EOF.
-    pub num_inactive_partitions: usize,
+    /// Position for each partition from which the source started consuming after the partition
+    /// was assigned.
+    pub start_positions: HashMap<i32, Position>,
+    /// Inactive partitions, i.e., that have reached EOF.
+    pub inactive_partitions: HashSet<i32>,
     /// Number of bytes processed by the source.
     pub num_bytes_processed: u64,
     /// Number of messages processed by the source (including invalid messages).
@@ -221,6 +392,8 @@ pub struct KafkaSource {
     truncate_tx: watch::Sender<SourceCheckpoint>,
     poll_loop_jh: JoinHandle<()>,
     publish_lock: PublishLock,
+    /// Multiline assembler for each assigned partition, if multiline assembly is enabled.
+    multiline_assemblers: HashMap<i32, MultilineAssembler>,
 }
 
 impl fmt::Debug for KafkaSource {
@@ -286,6 +459,7 @@ impl KafkaSource {
             truncate_tx,
             poll_loop_jh,
             publish_lock,
+            multiline_assemblers: HashMap::new(),
         })
     }
 
@@ -302,9 +476,7 @@ impl KafkaSource {
             ..
         } = message;
 
-        if let Some(doc) = doc_opt {
-            batch.add_doc(doc);
-        } else {
+        if doc_opt.is_none() {
             self.state.num_invalid_messages += 1;
         }
         self.state.num_bytes_processed += payload_len;
@@ -324,11 +496,43 @@ impl KafkaSource {
             })?
             .clone();
         let current_position = Position::offset(offset);
-        let previous_position = self
+        let previous_position = match self
             .state
             .current_positions
             .insert(partition, current_position.clone())
-            .unwrap_or_else(|| previous_position_for_offset(offset));
+        {
+            Some(previous_position) => previous_position,
+            None => {
+                let start_position = previous_position_for_offset(offset);
+                self.state
+                    .start_positions
+                    .insert(partition, start_position.clone());
+                start_position
+            }
+        };
+        if let Some(multiline_assembler) = self.multiline_assemblers.get_mut(&partition) {
+            // Only the positions of the complete events are recorded in the checkpoint delta: the
+            // lines of the pending event are consumed again after a restart.
+            let Some(doc) = doc_opt else {
+                if let Some(event) = multiline_assembler.flush() {
+                    add_multiline_event(batch, partition_id.clone(), event)?;
+                }
+                batch
+                    .checkpoint_delta
+                    .record_partition_delta(partition_id, previous_position, current_position)
+                    .context("failed to record partition delta")?;
+                return Ok(());
+            };
+            if let Some(event) =
+                multiline_assembler.push_line(&doc, previous_position, current_position)
+            {
+                add_multiline_event(batch, partition_id, event)?;
+            }
+            return Ok(());
+        }
+        if let Some(doc) = doc_opt {
+            batch.add_doc(doc);
+        }
         batch
             .checkpoint_delta
             .record_partition_delta(partition_id, previous_position, current_position)
@@ -336,12 +540,18 @@ impl KafkaSource {
         Ok(())
     }
 
-    async fn process_assign_partitions(
-        &mut self,
-        ctx: &SourceContext,
-        partitions: &[i32],
-        assignment_tx: oneshot::Sender<Vec<(i32, Offset)>>,
-    ) -> anyhow::Result<()> {
+    /// Adds the multiline events pending for longer than the flush timeout to the batch.
+    fn flush_expired_multiline_events(&mut self, batch: &mut BatchBuilder) -> anyhow::Result<()> {
+        for (partition, multiline_assembler) in self.multiline_assemblers.iter_mut() {
+            if let Some(event) = multiline_assembler.flush_if_expired() {
+                let partition_id = self.state.assigned_partitions[partition].clone();
+                add_multiline_event(batch, partition_id, event)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch_checkpoint(&self, ctx: &SourceContext) -> anyhow::Result<SourceCheckpoint> {
         let index_metadata_request =
             IndexMetadataRequest::for_index_uid(self.ctx.index_uid().clone());
         let index_metadata = ctx
@@ -358,10 +568,19 @@ impl KafkaSource {
             .source_checkpoint(self.ctx.source_id())
             .cloned()
             .unwrap_or_default();
+        Ok(checkpoint)
+    }
 
-        self.state.assigned_partitions.clear();
-        self.state.current_positions.clear();
-        self.state.num_inactive_partitions = 0;
+    /// Adds the partitions to the assignment of the source. With the eager rebalance protocol, the
+    /// previous assignment has been revoked beforehand, whereas with the cooperative protocol, the
+    /// partitions are added to the ones the source keeps consuming.
+    async fn process_assign_partitions(
+        &mut self,
+        ctx: &SourceContext,
+        partitions: &[i32],
+        assignment_tx: oneshot::Sender<Vec<(i32, Offset)>>,
+    ) -> anyhow::Result<()> {
+        let checkpoint = self.fetch_checkpoint(ctx).await?;
 
         let mut next_offsets: Vec<(i32, Offset)> = Vec::with_capacity(partitions.len());
 
@@ -371,27 +590,26 @@ impl KafkaSource {
             self.state
                 .assigned_partitions
                 .insert(partition, partition_id.clone());
+            self.state.current_positions.remove(&partition);
+            self.state.start_positions.remove(&partition);
+            self.state.inactive_partitions.remove(&partition);
 
+            if let Some(multiline_config) = &self.ctx.source_config.multiline_config {
+                self.multiline_assemblers
+                    .insert(partition, MultilineAssembler::try_new(multiline_config)?);
+            }
             let Some(current_position) = checkpoint.position_for_partition(&partition_id).cloned()
             else {
                 continue;
             };
-            let next_offset = match &current_position {
-                Position::Beginning => Offset::Beginning,
-                Position::Offset(offset) => {
-                    let offset = offset
-                        .as_i64()
-                        .expect("Kafka offset should be stored as i64");
-                    Offset::Offset(offset + 1)
-                }
-                Position::Eof(_) => {
-                    panic!("position of a Kafka partition should never be EOF")
-                }
-            };
+            next_offsets.push((partition, next_offset_for_position(&current_position)));
+
             self.state
                 .current_positions
+                .insert(partition, current_position.clone());
+            self.state
+                .start_positions
                 .insert(partition, current_position);
-            next_offsets.push((partition, next_offset));
         }
         info!(
             index_id=%self.ctx.index_id(),
@@ -407,16 +625,55 @@ impl KafkaSource {
         Ok(())
     }
 
+    /// Removes the partitions from the assignment of the source.
+    ///
+    /// The splits in flight may contain messages from the revoked partitions, which the new
+    /// owners of the partitions are about to consume again from the last published checkpoint.
+    /// Those splits are discarded by killing the publish lock. The partitions retained by the
+    /// source, if any (cooperative rebalance protocol), are then rewound to their last published
+    /// position so that the messages of the discarded splits are consumed again: the seeks
+    /// returned to the consumer context are performed once the rebalance callback has returned.
     async fn process_revoke_partitions(
         &mut self,
         ctx: &SourceContext,
         doc_processor_mailbox: &Mailbox<DocProcessor>,
         batch: &mut BatchBuilder,
-        ack_tx: oneshot::Sender<()>,
+        partitions: &[i32],
+        seek_tx: oneshot::Sender<Vec<(i32, Offset)>>,
     ) -> anyhow::Result<()> {
         ctx.protect_future(self.publish_lock.kill()).await;
-        ack_tx
-            .send(())
+
+        for partition in partitions {
+            self.state.assigned_partitions.remove(partition);
+            self.state.current_positions.remove(partition);
+            self.state.start_positions.remove(partition);
+            self.state.inactive_partitions.remove(partition);
+            self.multiline_assemblers.remove(partition);
+        }
+        // The lines of the pending multiline events of the retained partitions are consumed again
+        // after the partitions are rewound.
+        for multiline_assembler in self.multiline_assemblers.values_mut() {
+            multiline_assembler.reset();
+        }
+        let seeks = if self.state.current_positions.is_empty() {
+            Vec::new()
+        } else {
+            // Once the publish lock is dead, no split can be published anymore, so the checkpoint
+            // is up-to-date.
+            let checkpoint = self.fetch_checkpoint(ctx).await?;
+            self.rewind_partitions(&checkpoint)
+        };
+        info!(
+            index_id=%self.ctx.index_id(),
+            source_id=%self.ctx.source_id(),
+            topic=%self.topic,
+            group_id=%self.group_id,
+            partitions=?partitions,
+            retained_partitions=?self.state.assigned_partitions.keys().sorted().collect::<Vec<_>>(),
+            "Partitions revoked after rebalance.",
+        );
+        seek_tx
+            .send(seeks)
             .context("Kafka consumer context was dropped")?;
 
         batch.clear();
@@ -430,22 +687,61 @@ impl KafkaSource {
         Ok(())
     }
 
-    fn process_partition_eof(&mut self, partition: i32) {
-        self.state.num_inactive_partitions += 1;
+    /// Resets the current position of the partitions from which messages have been consumed to
+    /// their position in the checkpoint, or to their start position if they have no published
+    /// position yet. Returns the offsets the consumer must seek to.
+    fn rewind_partitions(&mut self, checkpoint: &SourceCheckpoint) -> Vec<(i32, Offset)> {
+        let mut seeks = Vec::with_capacity(self.state.current_positions.len());
+
+        for (&partition, current_position) in self.state.current_positions.iter_mut() {
+            let partition_id = &self.state.assigned_partitions[&partition];
+            let Some(rewind_position) = checkpoint
+                .position_for_partition(partition_id)
+                .or_else(|| self.state.start_positions.get(&partition))
+                .cloned()
+            else {
+                continue;
+            };
+            if *current_position == rewind_position {
+                continue;
+            }
+            seeks.push((partition, next_offset_for_position(&rewind_position)));
+            *current_position = rewind_position;
+        }
+        seeks.sort_by_key(|(partition, _)| *partition);
+        seeks
+    }
+
+    fn process_partition_eof(
+        &mut self,
+        partition: i32,
+        batch: &mut BatchBuilder,
+    ) -> anyhow::Result<()> {
+        self.state.inactive_partitions.insert(partition);
+
+        if let Some(event) = self
+            .multiline_assemblers
+            .get_mut(&partition)
+            .and_then(MultilineAssembler::flush)
+        {
+            let partition_id = self.state.assigned_partitions[&partition].clone();
+            add_multiline_event(batch, partition_id, event)?;
+        }
 
         info!(
             topic=%self.topic,
             partition=%partition,
-            num_inactive_partitions=?self.state.num_inactive_partitions,
+            num_inactive_partitions=?self.state.inactive_partitions.len(),
             "reached end of partition"
         );
+        Ok(())
     }
 
     fn should_exit(&self) -> bool {
         self.backfill_mode_enabled
             // This check ensures that we don't shutdown the source before the first partition assignment.
-            && self.state.num_inactive_partitions > 0
-            && self.state.num_inactive_partitions == self.state.assigned_partitions.len()
+            && !self.state.inactive_partitions.is_empty()
+            && self.state.inactive_partitions.len() == self.state.assigned_partitions.len()
     }
 
     fn truncate(&self, checkpoint: SourceCheckpoint) -> anyhow::Result<()> {
@@ -486,8 +782,8 @@ impl Source for KafkaSource {
                     match event {
                         KafkaEvent::Message(message) => self.process_message(message, &mut batch).await?,
                         KafkaEvent::AssignPartitions { partitions, assignment_tx} => self.process_assign_partitions(ctx, &partitions, assignment_tx).await?,
-                        KafkaEvent::RevokePartitions { ack_tx } => self.process_revoke_partitions(ctx, doc_processor_mailbox, &mut batch, ack_tx).await?,
-                        KafkaEvent::PartitionEOF(partition) => self.process_partition_eof(partition),
+                        KafkaEvent::RevokePartitions { partitions, seek_tx } => self.process_revoke_partitions(ctx, doc_processor_mailbox, &mut batch, &partitions, seek_tx).await?,
+                        KafkaEvent::PartitionEOF(partition) => self.process_partition_eof(partition, &mut batch)?,
                         KafkaEvent::Error(error) => Err(ActorExitStatus::from(error))?,
                     }
                     if batch.num_bytes >= BATCH_NUM_BYTES_LIMIT {
@@ -500,6 +796,8 @@ impl Source for KafkaSource {
             }
             ctx.record_progress();
         }
+        self.flush_expired_multiline_events(&mut batch)?;
+
         if !batch.checkpoint_delta.is_empty() {
             debug!(
                 num_docs=%batch.docs.len(),
@@ -555,7 +853,7 @@ impl Source for KafkaSource {
             "topic": self.topic,
             "assigned_partitions": assigned_partitions,
             "current_positions": current_positions,
-            "num_inactive_partitions": self.state.num_inactive_partitions,
+            "num_inactive_partitions": self.state.inactive_partitions.len(),
             "num_bytes_processed": self.state.num_bytes_processed,
             "num_messages_processed": self.state.num_messages_processed,
             "num_invalid_messages": self.state.num_invalid_messages,
@@ -587,21 +885,68 @@ fn spawn_consumer_poll_loop(
             let _ = events_tx.blocking_send(KafkaEvent::Error(anyhow!(error)));
             return;
         }
+        let context = consumer.context().clone();
+
         while !events_tx.is_closed() {
-            if let Some(message_res) = consumer.poll(Some(Duration::from_secs(1))) {
-                let event = match message_res {
-                    Ok(message) => KafkaEvent::Message(message.into()),
-                    Err(KafkaError::PartitionEOF(partition)) => KafkaEvent::PartitionEOF(partition),
-                    Err(error) => KafkaEvent::Error(anyhow!(error)),
+            // When the doc processor experiences backpressure, the source stops draining the
+            // events channel, which eventually becomes full. Instead of blocking the poll loop,
+            // which would get the consumer kicked out of the group once `max.poll.interval.ms`
+            // elapses, the events are held in a backlog and the partitions they belong to are
+            // paused. The partitions are resumed as soon as the backlog is drained.
+            let partitions_to_resume_opt = context.try_flush_backlog();
+            let backlog_drained = partitions_to_resume_opt.is_some();
+
+            if let Some(partitions_to_resume) = partitions_to_resume_opt {
+                if !partitions_to_resume.is_empty() {
+                    let tpl = topic_partition_list(&topic, partitions_to_resume);
+                    if let Err(error) = consumer.resume(&tpl) {
+                        warn!(error=?error, "failed to resume partitions");
+                    }
+                }
+            }
+            let poll_timeout = if backlog_drained {
+                Duration::from_secs(1)
+            } else {
+                Duration::from_millis(100)
+            };
+            let message_res_opt = consumer.poll(poll_timeout);
+
+            // The seeks requested by the source while revoking partitions must be performed before
+            // sending any new message for the rewound partitions. A message returned by the poll
+            // that triggered the rebalance was fetched before the seek: it is dropped and will be
+            // consumed again.
+            let rewound_partitions =
+                match seek_partitions(&consumer, &topic, context.take_pending_seeks()) {
+                    Ok(rewound_partitions) => rewound_partitions,
+                    Err(error) => {
+                        let _ = events_tx.blocking_send(KafkaEvent::Error(error));
+                        break;
+                    }
                 };
-                // When the source experiences backpressure, this channel becomes full and the
-                // consumer might not call `poll()` for a duration that exceeds
-                // `max.poll.interval.ms`. When that happens the consumer is kicked out of the group
-                // and the source fails. This should not happen in practice with a
-                // sufficiently large value for `max.poll.interval.ms`. The default value is 5
-                // minutes.
-                if events_tx.blocking_send(event).is_err() {
-                    break;
+            let event_opt = message_res_opt.and_then(|message_res| match message_res {
+                Ok(message) if rewound_partitions.contains(&message.partition()) => None,
+                Ok(message) => Some(KafkaEvent::Message(message.into())),
+                Err(KafkaError::PartitionEOF(partition)) => {
+                    Some(KafkaEvent::PartitionEOF(partition))
+                }
+                Err(error) => Some(KafkaEvent::Error(anyhow!(error))),
+            });
+            if let Some(event) = event_opt {
+                match context.send_event(event) {
+                    SendEventOutcome::Sent => {}
+                    SendEventOutcome::Held {
+                        partition_to_pause_opt: Some(partition),
+                    } => {
+                        let tpl = topic_partition_list(&topic, [partition]);
+
+                        if let Err(error) = consumer.pause(&tpl) {
+                            warn!(error=?error, partition=%partition, "failed to pause partition");
+                        }
+                    }
+                    SendEventOutcome::Held {
+                        partition_to_pause_opt: None,
+                    } => {}
+                    SendEventOutcome::SourceDropped => break,
                 }
             }
             if let Ok(true) = truncate_rx.has_changed() {
@@ -633,6 +978,55 @@ fn spawn_consumer_poll_loop(
     })
 }
 
+fn topic_partition_list(
+    topic: &str,
+    partitions: impl IntoIterator<Item = i32>,
+) -> TopicPartitionList {
+    let mut tpl = TopicPartitionList::new();
+    for partition in partitions {
+        tpl.add_partition(topic, partition);
+    }
+    tpl
+}
+
+/// Moves the position of the consumer for the given partitions and returns them.
+///
+/// Failing to seek a partition is an error: the source would otherwise record a checkpoint delta
+/// skipping the messages that should have been consumed again.
+fn seek_partitions(
+    consumer: &RdKafkaConsumer,
+    topic: &str,
+    seeks: Vec<(i32, Offset)>,
+) -> anyhow::Result<HashSet<i32>> {
+    let mut rewound_partitions = HashSet::with_capacity(seeks.len());
+
+    for (partition, offset) in seeks {
+        consumer
+            .seek(topic, partition, offset, Duration::from_secs(5))
+            .with_context(|| {
+                format!("failed to seek partition `{partition}` to offset `{offset:?}`")
+            })?;
+        rewound_partitions.insert(partition);
+    }
+    Ok(rewound_partitions)
+}
+
+/// Returns the offset of the message following the position.
+fn next_offset_for_position(position: &Position) -> Offset {
+    match position {
+        Position::Beginning => Offset::Beginning,
+        Position::Offset(offset) => {
+            let offset = offset
+                .as_i64()
+                .expect("Kafka offset should be stored as i64");
+            Offset::Offset(offset + 1)
+        }
+        Position::Eof(_) => {
+            panic!("position of a Kafka partition should never be EOF")
+        }
+    }
+}
+
 /// Returns the preceding `Position` for the offset.
 fn previous_position_for_offset(offset: i64) -> Position {
     if offset == 0 {
@@ -688,6 +1082,10 @@ fn create_consumer(
 
     let mut client_config = parse_client_params(params.client_params)?;
 
+    // Statistics are required to report the consumer lag of the partitions.
+    if client_config.get("statistics.interval.ms").is_none() {
+        client_config.set("statistics.interval.ms", "10000");
+    }
     let log_level = parse_client_log_level(params.client_log_level)?;
     let consumer: RdKafkaConsumer = client_config
         .set("enable.auto.commit", "false") // We manage offsets ourselves: we always want to set this value to `false`.
@@ -698,8 +1096,13 @@ fn create_consumer(
         .set("group.id", &group_id)
         .set_log_level(log_level)
         .create_with_context(RdKafkaContext {
+            index_id: index_uid.index_id.to_string(),
+            source_id: source_id.to_string(),
             topic: params.topic,
             events_tx,
+            backlog: Mutex::default(),
+            paused_partitions: Mutex::default(),
+            pending_seeks: Mutex::default(),
         })
         .context("failed to create Kafka consumer")?;
 
@@ -726,7 +1129,7 @@ fn parse_client_log_level(client_log_level: Option<String>) -> anyhow::Result<RD
     Ok(log_level)
 }
 
-fn parse_client_params(client_params: JsonValue) -> anyhow::Result<ClientConfig> {
+pub(crate) fn parse_client_params(client_params: JsonValue) -> anyhow::Result<ClientConfig> {
     let params = if let JsonValue::Object(params) = client_params {
         params
     } else {
@@ -751,6 +1154,19 @@ fn parse_client_params(client_params: JsonValue) -> anyhow::Result<ClientConfig>
 }
 
 /// Returns the message payload as a `Bytes` object if it exists and is not empty.
+fn add_multiline_event(
+    batch: &mut BatchBuilder,
+    partition_id: PartitionId,
+    event: MultilineEvent,
+) -> anyhow::Result<()> {
+    batch.add_doc(event.doc);
+    batch
+        .checkpoint_delta
+        .record_partition_delta(partition_id, event.from_position, event.to_position)
+        .context("failed to record partition delta")?;
+    Ok(())
+}
+
 fn message_payload_to_doc(message: &BorrowedMessage) -> Option<Bytes> {
     match message.payload() {
         Some(payload) if !payload.is_empty() => {
@@ -775,6 +1191,153 @@ fn message_payload_to_doc(message: &BorrowedMessage) -> Option<Bytes> {
     None
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rdkafka_context_for_test(
+        source_id: &str,
+        events_tx: mpsc::Sender<KafkaEvent>,
+    ) -> RdKafkaContext {
+        RdKafkaContext {
+            index_id: "test-index".to_string(),
+            source_id: source_id.to_string(),
+            topic: "test-topic".to_string(),
+            events_tx,
+            backlog: Mutex::default(),
+            paused_partitions: Mutex::default(),
+            pending_seeks: Mutex::default(),
+        }
+    }
+
+    fn message_event(partition: i32, offset: i64) -> KafkaEvent {
+        KafkaEvent::Message(KafkaMessage {
+            doc_opt: Some(Bytes::from_static(b"{}")),
+            payload_len: 2,
+            partition,
+            offset,
+        })
+    }
+
+    fn received_message(events_rx: &mut mpsc::Receiver<KafkaEvent>) -> (i32, i64) {
+        match events_rx.try_recv().unwrap() {
+            KafkaEvent::Message(message) => (message.partition, message.offset),
+            event => panic!("expected message, got `{event:?}`"),
+        }
+    }
+
+    #[test]
+    fn test_rdkafka_context_pauses_partitions_under_backpressure() {
+        let (events_tx, mut events_rx) = mpsc::channel(1);
+        let context = rdkafka_context_for_test("test-source-backpressure", events_tx);
+
+        assert_eq!(
+            context.send_event(message_event(0, 0)),
+            SendEventOutcome::Sent
+        );
+        // The events channel is full: the messages are held and their partitions paused once.
+        assert_eq!(
+            context.send_event(message_event(0, 1)),
+            SendEventOutcome::Held {
+                partition_to_pause_opt: Some(0)
+            }
+        );
+        assert_eq!(
+            context.send_event(message_event(1, 0)),
+            SendEventOutcome::Held {
+                partition_to_pause_opt: Some(1)
+            }
+        );
+        assert_eq!(
+            context.send_event(message_event(0, 2)),
+            SendEventOutcome::Held {
+                partition_to_pause_opt: None
+            }
+        );
+        assert!(context.try_flush_backlog().is_none());
+
+        assert_eq!(received_message(&mut events_rx), (0, 0));
+        // The backlog is not drained yet, so the partitions remain paused.
+        assert!(context.try_flush_backlog().is_none());
+        // Events keep being held while the backlog is not empty, even if the channel has room.
+        assert_eq!(received_message(&mut events_rx), (0, 1));
+        assert_eq!(
+            context.send_event(message_event(1, 1)),
+            SendEventOutcome::Held {
+                partition_to_pause_opt: None
+            }
+        );
+        let mut received_messages = Vec::new();
+        let partitions_to_resume = loop {
+            if let Some(partitions_to_resume) = context.try_flush_backlog() {
+                break partitions_to_resume;
+            }
+            received_messages.push(received_message(&mut events_rx));
+        };
+        assert_eq!(partitions_to_resume, [0, 1]);
+        received_messages.push(received_message(&mut events_rx));
+        assert_eq!(received_messages, [(1, 0), (0, 2), (1, 1)]);
+
+        // Once resumed, the partitions are paused again on the next backpressure episode.
+        assert_eq!(context.try_flush_backlog(), Some(Vec::new()));
+        assert_eq!(
+            context.send_event(message_event(0, 3)),
+            SendEventOutcome::Sent
+        );
+        assert_eq!(
+            context.send_event(message_event(0, 4)),
+            SendEventOutcome::Held {
+                partition_to_pause_opt: Some(0)
+            }
+        );
+        drop(events_rx);
+        assert_eq!(context.try_flush_backlog(), Some(vec![0]));
+        assert_eq!(
+            context.send_event(message_event(0, 5)),
+            SendEventOutcome::SourceDropped
+        );
+    }
+
+    #[test]
+    fn test_rdkafka_context_records_consumer_lag() {
+        let (events_tx, _events_rx) = mpsc::channel(1);
+        let source_id = "test-source-consumer-lag";
+        let context = rdkafka_context_for_test(source_id, events_tx);
+
+        let reported_consumer_lags = || -> Vec<String> {
+            quickwit_common::metrics::metrics_text_payload()
+                .lines()
+                .filter(|line| {
+                    line.starts_with("quickwit_indexing_kafka_consumer_lag{")
+                        && line.contains(&format!("source=\"{source_id}\""))
+                })
+                .map(|line| {
+                    let (labels, consumer_lag) = line.rsplit_once(' ').unwrap();
+                    let partition = labels
+                        .split_once("partition=\"")
+                        .unwrap()
+                        .1
+                        .split_once('"')
+                        .unwrap()
+                        .0;
+                    format!("{partition}:{consumer_lag}")
+                })
+                .sorted()
+                .collect()
+        };
+        // Unknown partitions and lags are ignored.
+        context.record_consumer_lags([(0, 42), (1, 7), (2, -1), (-1, 3)]);
+        assert_eq!(reported_consumer_lags(), ["0:42", "1:7"]);
+
+        context.record_consumer_lags([(0, 12)]);
+        assert_eq!(reported_consumer_lags(), ["0:12", "1:7"]);
+
+        // The lag of revoked partitions is no longer reported.
+        context.remove_consumer_lags(&[0, 2]);
+        assert_eq!(reported_consumer_lags(), ["1:7"]);
+    }
+}
+
 #[cfg(all(test, feature = "kafka-broker-tests"))]
 mod kafka_broker_tests {
     use std::num::NonZeroUsize;
@@ -782,7 +1345,9 @@ mod kafka_broker_tests {
 
     use quickwit_actors::{ActorContext, Universe};
     use quickwit_common::rand::append_random_suffix;
-    use quickwit_config::{IndexConfig, SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_config::{
+        IndexConfig, MultilineConfig, SourceConfig, SourceInputFormat, SourceParams,
+    };
     use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
     use quickwit_metastore::{
         metastore_for_test, CreateIndexRequestExt, SplitMetadata, StageSplitsRequestExt,
@@ -912,9 +1477,13 @@ mod kafka_broker_tests {
                     "bootstrap.servers": "localhost:9092",
                 }),
                 enable_backfill_mode: true,
+                schema_registry_url: None,
             }),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         (source_id, source_config)
     }
@@ -1122,6 +1691,91 @@ mod kafka_broker_tests {
             .unwrap_err();
     }
 
+    #[tokio::test]
+    async fn test_kafka_source_process_message_multiline() {
+        let admin_client = create_admin_client();
+        let topic = append_random_suffix("test-kafka-source--process-message-multiline--topic");
+        create_topic(&admin_client, &topic, 1).await.unwrap();
+
+        let metastore = metastore_for_test();
+        let index_id = append_random_suffix("test-kafka-source--process-message-multiline--index");
+        let index_uid = IndexUid::new_with_random_ulid(&index_id);
+        let (_source_id, mut source_config) = get_source_config(&topic, "earliest");
+        source_config.input_format = SourceInputFormat::PlainText;
+        source_config.multiline_config = Some(MultilineConfig::for_test("^(ERROR|INFO)"));
+
+        let SourceParams::Kafka(params) = source_config.clone().source_params else {
+            panic!(
+                "Expected Kafka source params, got {:?}.",
+                source_config.source_params
+            );
+        };
+        let multiline_config = source_config.multiline_config.clone().unwrap();
+        let ctx = SourceRuntimeArgs::for_test(
+            index_uid,
+            source_config,
+            metastore,
+            PathBuf::from("./queues"),
+        );
+        let ignored_checkpoint = SourceCheckpoint::default();
+        let mut kafka_source = KafkaSource::try_new(ctx, params, ignored_checkpoint)
+            .await
+            .unwrap();
+
+        let partition_id_1 = PartitionId::from(1u64);
+        kafka_source.state.assigned_partitions = HashMap::from_iter([(1, partition_id_1.clone())]);
+        kafka_source.multiline_assemblers =
+            HashMap::from_iter([(1, MultilineAssembler::try_new(&multiline_config).unwrap())]);
+
+        let mut batch = BatchBuilder::default();
+
+        for (offset, line) in ["ERROR boom", "\tat a", "INFO ok"].into_iter().enumerate() {
+            let message = KafkaMessage {
+                doc_opt: Some(Bytes::from_static(line.as_bytes())),
+                payload_len: line.len() as u64,
+                partition: 1,
+                offset: offset as i64,
+            };
+            kafka_source
+                .process_message(message, &mut batch)
+                .await
+                .unwrap();
+        }
+        assert_eq!(batch.docs.len(), 1);
+        assert_eq!(batch.docs[0], "ERROR boom\n\tat a");
+
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(
+                partition_id_1.clone(),
+                Position::Beginning,
+                Position::offset(1u64),
+            )
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+
+        // An invalid message flushes the pending event.
+        let message = KafkaMessage {
+            doc_opt: None,
+            payload_len: 7,
+            partition: 1,
+            offset: 3,
+        };
+        kafka_source
+            .process_message(message, &mut batch)
+            .await
+            .unwrap();
+
+        assert_eq!(batch.docs.len(), 2);
+        assert_eq!(batch.docs[1], "INFO ok");
+
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::default();
+        expected_checkpoint_delta
+            .record_partition_delta(partition_id_1, Position::Beginning, Position::offset(3u64))
+            .unwrap();
+        assert_eq!(batch.checkpoint_delta, expected_checkpoint_delta);
+    }
+
     #[tokio::test]
     async fn test_kafka_source_process_assign_partitions() {
         let admin_client = create_admin_client();
@@ -1150,7 +1804,7 @@ mod kafka_broker_tests {
         let mut kafka_source = KafkaSource::try_new(ctx, params, ignored_checkpoint)
             .await
             .unwrap();
-        kafka_source.state.num_inactive_partitions = 1;
+        kafka_source.state.inactive_partitions = HashSet::from_iter([1]);
 
         let universe = Universe::with_accelerated_time();
         let (source_mailbox, _source_inbox) = universe.create_test_mailbox();
@@ -1164,7 +1818,7 @@ mod kafka_broker_tests {
             .await
             .unwrap();
 
-        assert_eq!(kafka_source.state.num_inactive_partitions, 0);
+        assert!(kafka_source.state.inactive_partitions.is_empty());
 
         let expected_assigned_partitions =
             HashMap::from_iter([(1, PartitionId::from(1u64)), (2, PartitionId::from(2u64))]);
@@ -1179,7 +1833,27 @@ mod kafka_broker_tests {
         );
 
         let assignment = assignment_rx.await.unwrap();
-        assert_eq!(assignment, &[(2, Offset::Offset(43))])
+        assert_eq!(assignment, &[(2, Offset::Offset(43))]);
+
+        // With the cooperative protocol, partitions are assigned incrementally.
+        let (assignment_tx, assignment_rx) = oneshot::channel();
+
+        kafka_source
+            .process_assign_partitions(&ctx, &[3], assignment_tx)
+            .await
+            .unwrap();
+
+        let expected_assigned_partitions = HashMap::from_iter([
+            (1, PartitionId::from(1u64)),
+            (2, PartitionId::from(2u64)),
+            (3, PartitionId::from(3u64)),
+        ]);
+        assert_eq!(
+            kafka_source.state.assigned_partitions,
+            expected_assigned_partitions
+        );
+        let assignment = assignment_rx.await.unwrap();
+        assert!(assignment.is_empty());
     }
 
     #[tokio::test]
@@ -1215,21 +1889,27 @@ mod kafka_broker_tests {
         let (observable_state_tx, _observable_state_rx) = watch::channel(json!({}));
         let ctx: ActorContext<SourceActor> =
             ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
-        let (ack_tx, ack_rx) = oneshot::channel();
+        let (seek_tx, seek_rx) = oneshot::channel();
 
         let mut batch = BatchBuilder::default();
         batch.add_doc(Bytes::from_static(b"test-doc"));
 
+        kafka_source.state.assigned_partitions = HashMap::from_iter([(0, PartitionId::from(0u64))]);
+        kafka_source.state.current_positions = HashMap::from_iter([(0, Position::offset(7u64))]);
+
         let publish_lock = kafka_source.publish_lock.clone();
         assert!(publish_lock.is_alive());
         assert_eq!(kafka_source.state.num_rebalances, 0);
 
         kafka_source
-            .process_revoke_partitions(&ctx, &indexer_mailbox, &mut batch, ack_tx)
+            .process_revoke_partitions(&ctx, &indexer_mailbox, &mut batch, &[0], seek_tx)
             .await
             .unwrap();
 
-        ack_rx.await.unwrap();
+        let seeks = seek_rx.await.unwrap();
+        assert!(seeks.is_empty());
+        assert!(kafka_source.state.assigned_partitions.is_empty());
+        assert!(kafka_source.state.current_positions.is_empty());
         assert!(batch.docs.is_empty());
         assert!(publish_lock.is_dead());
 
@@ -1240,6 +1920,99 @@ mod kafka_broker_tests {
         assert!(indexer_messages[0].0.is_alive());
     }
 
+    #[tokio::test]
+    async fn test_kafka_source_process_revoke_partitions_cooperative() {
+        let admin_client = create_admin_client();
+        let topic =
+            append_random_suffix("test-kafka-source--process-revoke-partitions-cooperative--topic");
+        create_topic(&admin_client, &topic, 4).await.unwrap();
+
+        let metastore = metastore_for_test();
+        let index_id =
+            append_random_suffix("test-kafka-source--process-revoke-partitions-cooperative--index");
+        let (source_id, source_config) = get_source_config(&topic, "earliest");
+
+        let index_uid = setup_index(metastore.clone(), &index_id, &source_id, &[(2, -1, 42)]).await;
+
+        let SourceParams::Kafka(params) = source_config.clone().source_params else {
+            panic!(
+                "Expected Kafka source params, got {:?}.",
+                source_config.source_params
+            );
+        };
+        let ctx = SourceRuntimeArgs::for_test(
+            index_uid,
+            source_config,
+            metastore,
+            PathBuf::from("./queues"),
+        );
+        let ignored_checkpoint = SourceCheckpoint::default();
+        let mut kafka_source = KafkaSource::try_new(ctx, params, ignored_checkpoint)
+            .await
+            .unwrap();
+
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox();
+        let (indexer_mailbox, indexer_inbox) = universe.create_test_mailbox();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(json!({}));
+        let ctx: ActorContext<SourceActor> =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        kafka_source.state.assigned_partitions = HashMap::from_iter(
+            (0..4).map(|partition| (partition, PartitionId::from(partition as u64))),
+        );
+        // Partition 0 has been assigned but not consumed yet.
+        // Partition 1 is revoked.
+        // Partition 2 has been consumed past its published position.
+        // Partition 3 has been consumed but has no published position yet.
+        kafka_source.state.current_positions = HashMap::from_iter([
+            (1, Position::offset(12u64)),
+            (2, Position::offset(50u64)),
+            (3, Position::offset(20u64)),
+        ]);
+        kafka_source.state.start_positions = HashMap::from_iter([
+            (1, Position::Beginning),
+            (2, Position::offset(42u64)),
+            (3, Position::offset(9u64)),
+        ]);
+        let mut batch = BatchBuilder::default();
+        batch.add_doc(Bytes::from_static(b"test-doc"));
+
+        let publish_lock = kafka_source.publish_lock.clone();
+        let (seek_tx, seek_rx) = oneshot::channel();
+
+        kafka_source
+            .process_revoke_partitions(&ctx, &indexer_mailbox, &mut batch, &[1], seek_tx)
+            .await
+            .unwrap();
+
+        let seeks = seek_rx.await.unwrap();
+        assert_eq!(seeks, &[(2, Offset::Offset(43)), (3, Offset::Offset(10))]);
+
+        let expected_assigned_partitions = HashMap::from_iter([
+            (0, PartitionId::from(0u64)),
+            (2, PartitionId::from(2u64)),
+            (3, PartitionId::from(3u64)),
+        ]);
+        assert_eq!(
+            kafka_source.state.assigned_partitions,
+            expected_assigned_partitions
+        );
+        let expected_current_positions =
+            HashMap::from_iter([(2, Position::offset(42u64)), (3, Position::offset(9u64))]);
+        assert_eq!(
+            kafka_source.state.current_positions,
+            expected_current_positions
+        );
+        assert!(batch.docs.is_empty());
+        assert!(publish_lock.is_dead());
+        assert_eq!(kafka_source.state.num_rebalances, 1);
+
+        let indexer_messages: Vec<NewPublishLock> = indexer_inbox.drain_for_test_typed();
+        assert_eq!(indexer_messages.len(), 1);
+        assert!(indexer_messages[0].0.is_alive());
+    }
+
     #[tokio::test]
     async fn test_kafka_source_process_partition_eof() {
         let admin_client = create_admin_client();
@@ -1271,8 +2044,10 @@ mod kafka_broker_tests {
 
         assert!(!kafka_source.should_exit());
 
-        kafka_source.process_partition_eof(1);
-        assert_eq!(kafka_source.state.num_inactive_partitions, 1);
+        kafka_source
+            .process_partition_eof(1, &mut BatchBuilder::default())
+            .unwrap();
+        assert_eq!(kafka_source.state.inactive_partitions.len(), 1);
         assert!(kafka_source.should_exit());
 
         kafka_source.backfill_mode_enabled = false;
@@ -1619,6 +2394,7 @@ mod kafka_broker_tests {
             client_log_level: None,
             client_params: json!({ "bootstrap.servers": bootstrap_servers }),
             enable_backfill_mode: true,
+            schema_registry_url: None,
         })
         .await
         .unwrap();
@@ -1630,6 +2406,7 @@ mod kafka_broker_tests {
             client_log_level: None,
             client_params: json!({ "bootstrap.servers": bootstrap_servers }),
             enable_backfill_mode: true,
+            schema_registry_url: None,
         })
         .await
         .unwrap_err();
@@ -1642,6 +2419,7 @@ mod kafka_broker_tests {
                 "bootstrap.servers": "192.0.2.10:9092"
             }),
             enable_backfill_mode: true,
+            schema_registry_url: None,
         })
         .await
         .unwrap_err();
@@ -57,6 +57,8 @@
 //!   that file.
 //! - the kafka source: the partition id is a kafka topic partition id, and the position is a kafka
 //!   offset.
+#[cfg(feature = "amqp")]
+mod amqp_source;
 mod file_source;
 #[cfg(feature = "gcp-pubsub")]
 mod gcp_pubsub_source;
@@ -66,9 +68,14 @@ mod ingest_api_source;
 mod kafka_source;
 #[cfg(feature = "kinesis")]
 mod kinesis;
+#[cfg(feature = "mqtt")]
+mod mqtt_source;
+mod multiline;
 #[cfg(feature = "pulsar")]
 mod pulsar_source;
 mod source_factory;
+mod storage_prefix_source;
+mod syslog_source;
 mod vec_source;
 mod void_source;
 
@@ -76,6 +83,8 @@ use std::collections::BTreeSet;
 use std::path::PathBuf;
 use std::time::Duration;
 
+#[cfg(feature = "amqp")]
+pub use amqp_source::{AmqpSource, AmqpSourceFactory};
 use async_trait::async_trait;
 use bytes::Bytes;
 use bytesize::ByteSize;
@@ -83,9 +92,13 @@ pub use file_source::{FileSource, FileSourceFactory};
 #[cfg(feature = "gcp-pubsub")]
 pub use gcp_pubsub_source::{GcpPubSubSource, GcpPubSubSourceFactory};
 #[cfg(feature = "kafka")]
+pub(crate) use kafka_source::parse_client_params;
+#[cfg(feature = "kafka")]
 pub use kafka_source::{KafkaSource, KafkaSourceFactory};
 #[cfg(feature = "kinesis")]
 pub use kinesis::kinesis_source::{KinesisSource, KinesisSourceFactory};
+#[cfg(feature = "mqtt")]
+pub use mqtt_source::{MqttSource, MqttSourceFactory};
 use once_cell::sync::OnceCell;
 #[cfg(feature = "pulsar")]
 pub use pulsar_source::{PulsarSource, PulsarSourceFactory};
@@ -101,13 +114,15 @@ use quickwit_proto::types::{IndexUid, PipelineUid, ShardId};
 use quickwit_storage::StorageResolver;
 use serde_json::Value as JsonValue;
 pub use source_factory::{SourceFactory, SourceLoader, TypedSourceFactory};
+pub use storage_prefix_source::{StoragePrefixSource, StoragePrefixSourceFactory};
+pub use syslog_source::{SyslogSource, SyslogSourceFactory};
 use tokio::runtime::Handle;
 use tracing::error;
 pub use vec_source::{VecSource, VecSourceFactory};
 pub use void_source::{VoidSource, VoidSourceFactory};
 
 use self::file_source::dir_and_filename;
-use crate::actors::DocProcessor;
+use crate::actors::{DocProcessor, ProcessorChain};
 use crate::models::RawDocBatch;
 use crate::source::ingest::IngestSourceFactory;
 use crate::source::ingest_api_source::IngestApiSourceFactory;
@@ -383,6 +398,8 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
     static SOURCE_LOADER: OnceCell<SourceLoader> = OnceCell::new();
     SOURCE_LOADER.get_or_init(|| {
         let mut source_factory = SourceLoader::default();
+        #[cfg(feature = "amqp")]
+        source_factory.add_source("amqp", AmqpSourceFactory);
         source_factory.add_source("file", FileSourceFactory);
         #[cfg(feature = "gcp-pubsub")]
         source_factory.add_source("gcp_pubsub", GcpPubSubSourceFactory);
@@ -392,8 +409,12 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
         source_factory.add_source("kafka", KafkaSourceFactory);
         #[cfg(feature = "kinesis")]
         source_factory.add_source("kinesis", KinesisSourceFactory);
+        #[cfg(feature = "mqtt")]
+        source_factory.add_source("mqtt", MqttSourceFactory);
         #[cfg(feature = "pulsar")]
         source_factory.add_source("pulsar", PulsarSourceFactory);
+        source_factory.add_source("storage_prefix", StoragePrefixSourceFactory);
+        source_factory.add_source("syslog", SyslogSourceFactory);
         source_factory.add_source("vec", VecSourceFactory);
         source_factory.add_source("void", VoidSourceFactory);
         source_factory
@@ -404,6 +425,10 @@ pub async fn check_source_connectivity(
     storage_resolver: &StorageResolver,
     source_config: &SourceConfig,
 ) -> anyhow::Result<()> {
+    // The source config only validates the structure of the processors: grok patterns and the
+    // like are compiled here, so that invalid processors are rejected when the source is created.
+    ProcessorChain::try_new(&source_config.processors)?;
+
     match &source_config.source_params {
         SourceParams::File(params) => {
             if let Some(filepath) = &params.filepath {
@@ -414,6 +439,17 @@ pub async fn check_source_connectivity(
             Ok(())
         }
         #[allow(unused_variables)]
+        SourceParams::Amqp(params) => {
+            #[cfg(not(feature = "amqp"))]
+            anyhow::bail!("Quickwit binary was not compiled with the `amqp` feature");
+
+            #[cfg(feature = "amqp")]
+            {
+                amqp_source::check_connectivity(params).await?;
+                Ok(())
+            }
+        }
+        #[allow(unused_variables)]
         SourceParams::Kafka(params) => {
             #[cfg(not(feature = "kafka"))]
             anyhow::bail!("Quickwit binary was not compiled with the `kafka` feature");
@@ -446,6 +482,17 @@ pub async fn check_source_connectivity(
                 Ok(())
             }
         }
+        SourceParams::Mqtt(_) => {
+            #[cfg(not(feature = "mqtt"))]
+            anyhow::bail!("Quickwit binary was not compiled with the `mqtt` feature");
+
+            #[cfg(feature = "mqtt")]
+            {
+                // The client connects lazily and retries forever, so there is nothing to check
+                // without subscribing.
+                Ok(())
+            }
+        }
         _ => Ok(()),
     }
 }
@@ -526,6 +573,9 @@ mod tests {
                 source_params: SourceParams::void(),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             };
             check_source_connectivity(&StorageResolver::for_test(), &source_config).await?;
         }
@@ -538,6 +588,9 @@ mod tests {
                 source_params: SourceParams::Vec(VecSourceParams::default()),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             };
             check_source_connectivity(&StorageResolver::for_test(), &source_config).await?;
         }
@@ -550,6 +603,9 @@ mod tests {
                 source_params: SourceParams::file("file-does-not-exist.json"),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             };
             assert!(
                 check_source_connectivity(&StorageResolver::for_test(), &source_config)
@@ -566,6 +622,9 @@ mod tests {
                 source_params: SourceParams::file("data/test_corpus.json"),
                 transform_config: None,
                 input_format: SourceInputFormat::Json,
+                dead_letter_config: None,
+                multiline_config: None,
+                processors: Vec::new(),
             };
             assert!(
                 check_source_connectivity(&StorageResolver::for_test(), &source_config)
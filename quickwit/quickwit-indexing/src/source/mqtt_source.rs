@@ -0,0 +1,499 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use async_trait::async_trait;
+use bytes::Bytes;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::rand::append_random_suffix;
+use quickwit_config::MqttSourceParams;
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_proto::types::Position;
+use rumqttc::{AsyncClient, ClientError, Event, EventLoop, MqttOptions, Packet, Publish, QoS};
+use serde_json::{json, Value as JsonValue};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
+use tracing::{debug, info, warn};
+
+use crate::actors::DocProcessor;
+use crate::source::{
+    BatchBuilder, Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory,
+    BATCH_NUM_BYTES_LIMIT, EMIT_BATCHES_TIMEOUT,
+};
+
+/// Capacity of the channel of requests (subscriptions, acknowledgements) sent to the event loop.
+const REQUEST_CHANNEL_CAPACITY: usize = 1_000;
+
+/// Capacity of the channel of messages forwarded by the event loop to the source.
+const MESSAGE_CHANNEL_CAPACITY: usize = 1_000;
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+pub struct MqttSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for MqttSourceFactory {
+    type Source = MqttSource;
+    type Params = MqttSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: MqttSourceParams,
+        _checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        MqttSource::try_new(ctx, params).await
+    }
+}
+
+#[derive(Default)]
+pub struct MqttSourceState {
+    /// Number of bytes processed by the source.
+    num_bytes_processed: u64,
+    /// Number of messages processed by the source (including invalid messages).
+    num_messages_processed: u64,
+    /// Number of invalid messages, i.e., that were empty.
+    num_invalid_messages: u64,
+    /// Number of messages acknowledged to the broker.
+    num_acked_messages: u64,
+}
+
+/// Subscribes to topics of an MQTT broker with QoS 1.
+///
+/// The session is persistent, and messages are acknowledged only once the split containing them
+/// is published: the broker redelivers the messages not acknowledged when the source reconnects.
+/// Packet identifiers are recycled, so messages are positioned by their sequence number within
+/// the source instance, and each instance records its checkpoint under a partition of its own.
+///
+/// Brokers cap the number of QoS 1 messages delivered but not acknowledged yet (for instance
+/// `max_inflight_messages` in Mosquitto). Since acknowledgements wait for the split to be
+/// published, the broker stops delivering messages once this cap is reached, until the next
+/// commit: the source ingests at most that many messages per `commit_timeout_secs`, so the cap
+/// must be raised on the broker according to the expected throughput.
+pub struct MqttSource {
+    ctx: Arc<SourceRuntimeArgs>,
+    params: MqttSourceParams,
+    client_id: String,
+    client: AsyncClient,
+    message_rx: mpsc::Receiver<Publish>,
+    event_loop_handle: JoinHandle<()>,
+    partition_id: PartitionId,
+    current_position: Position,
+    unacked_messages: UnackedMessages,
+    state: MqttSourceState,
+}
+
+impl MqttSource {
+    pub async fn try_new(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: MqttSourceParams,
+    ) -> anyhow::Result<Self> {
+        let client_id = params
+            .client_id
+            .clone()
+            .unwrap_or_else(|| format!("quickwit-{}-{}", ctx.index_uid(), ctx.source_id()));
+        info!(
+            index_id=%ctx.index_id(),
+            source_id=%ctx.source_id(),
+            host=%params.host,
+            port=%params.port,
+            topics=?params.topics,
+            client_id=%client_id,
+            "Starting MQTT source."
+        );
+        let mut mqtt_options = MqttOptions::new(&client_id, &params.host, params.port);
+        mqtt_options
+            .set_keep_alive(KEEP_ALIVE_INTERVAL)
+            .set_clean_session(false)
+            .set_manual_acks(true);
+
+        if let Some(username) = &params.username {
+            mqtt_options.set_credentials(username, params.password.clone().unwrap_or_default());
+        }
+        let (client, event_loop) = AsyncClient::new(mqtt_options, REQUEST_CHANNEL_CAPACITY);
+        let (message_tx, message_rx) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+        let event_loop_handle = tokio::spawn(run_event_loop(
+            event_loop,
+            client.clone(),
+            params.topics.clone(),
+            message_tx,
+        ));
+        let partition_id = PartitionId::from(append_random_suffix(&format!("mqtt-{client_id}")));
+
+        Ok(Self {
+            ctx,
+            params,
+            client_id,
+            client,
+            message_rx,
+            event_loop_handle,
+            partition_id,
+            current_position: Position::Beginning,
+            unacked_messages: UnackedMessages::default(),
+            state: MqttSourceState::default(),
+        })
+    }
+
+    fn process_message(
+        &mut self,
+        publish: Publish,
+        batch: &mut BatchBuilder,
+    ) -> anyhow::Result<()> {
+        self.state.num_messages_processed += 1;
+        self.state.num_bytes_processed += publish.payload.len() as u64;
+
+        let sequence_number = self.state.num_messages_processed;
+        let to_position = Position::offset(sequence_number);
+        let from_position = mem::replace(&mut self.current_position, to_position.clone());
+        batch
+            .checkpoint_delta
+            .record_partition_delta(self.partition_id.clone(), from_position, to_position)
+            .context("failed to record partition delta")?;
+
+        if publish.payload.is_empty() {
+            warn!(topic=%publish.topic, "message received from broker was empty");
+            self.state.num_invalid_messages += 1;
+        } else {
+            batch.add_doc(publish.payload.clone());
+        }
+        self.unacked_messages.push(sequence_number, publish);
+        Ok(())
+    }
+
+    fn flush_acks(&mut self) -> anyhow::Result<()> {
+        let num_acked_messages = self
+            .unacked_messages
+            .flush(&self.client)
+            .context("failed to acknowledge MQTT messages")?;
+        self.state.num_acked_messages += num_acked_messages as u64;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Source for MqttSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        // Sends the acknowledgements that could not be sent in `suggest_truncate`.
+        self.flush_acks()?;
+
+        let now = Instant::now();
+        let mut batch = BatchBuilder::default();
+        let deadline = time::sleep(EMIT_BATCHES_TIMEOUT);
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                publish_opt = self.message_rx.recv() => {
+                    let publish = publish_opt
+                        .ok_or_else(|| anyhow!("MQTT event loop exited unexpectedly"))?;
+                    self.process_message(publish, &mut batch)?;
+
+                    if batch.num_bytes >= BATCH_NUM_BYTES_LIMIT {
+                        break;
+                    }
+                }
+                _ = &mut deadline => {
+                    break;
+                }
+            }
+            ctx.record_progress();
+        }
+
+        if !batch.checkpoint_delta.is_empty() {
+            debug!(
+                num_docs=%batch.docs.len(),
+                num_bytes=%batch.num_bytes,
+                num_millis=%now.elapsed().as_millis(),
+                "Sending doc batch to indexer.");
+            let message = batch.build();
+            ctx.send_message(doc_processor_mailbox, message).await?;
+        }
+        Ok(Duration::default())
+    }
+
+    async fn suggest_truncate(
+        &mut self,
+        checkpoint: SourceCheckpoint,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let Some(sequence_number) = checkpoint
+            .position_for_partition(&self.partition_id)
+            .and_then(Position::as_u64)
+        else {
+            return Ok(());
+        };
+        self.unacked_messages.commit(sequence_number);
+        self.flush_acks()
+    }
+
+    async fn finalize(
+        &mut self,
+        _exit_status: &ActorExitStatus,
+        _ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        // The broker keeps the messages that were not acknowledged in the session.
+        let _ = self.client.try_disconnect();
+        self.event_loop_handle.abort();
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        format!("MqttSource{{source_id={}}}", self.ctx.source_id())
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        json!({
+            "index_id": self.ctx.index_id(),
+            "source_id": self.ctx.source_id(),
+            "topics": self.params.topics,
+            "client_id": self.client_id,
+            "partition_id": self.partition_id,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+            "num_acked_messages": self.state.num_acked_messages,
+            "num_unacked_messages": self.unacked_messages.len(),
+        })
+    }
+}
+
+/// Polls the MQTT event loop, which sends the requests of the client and reconnects to the broker
+/// on error, and forwards the messages received to the source.
+async fn run_event_loop(
+    mut event_loop: EventLoop,
+    client: AsyncClient,
+    topics: Vec<String>,
+    message_tx: mpsc::Sender<Publish>,
+) {
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Incoming(Packet::Publish(publish))) => {
+                if message_tx.send(publish).await.is_err() {
+                    // The source was dropped.
+                    return;
+                }
+            }
+            Ok(Event::Incoming(Packet::ConnAck(conn_ack))) => {
+                info!(
+                    session_present=%conn_ack.session_present,
+                    "Connected to MQTT broker."
+                );
+                // Subscriptions are stored in the session, so they only need to be (re)created
+                // when the broker did not keep it.
+                if !conn_ack.session_present {
+                    for topic in &topics {
+                        if let Err(error) = client.try_subscribe(topic, QoS::AtLeastOnce) {
+                            warn!(topic=%topic, error=%error, "failed to subscribe to MQTT topic");
+                        }
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(error) => {
+                // The next call to `poll` reconnects to the broker.
+                warn!(error=%error, "MQTT connection error");
+                time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Messages received but not acknowledged yet, in the order they were received. MQTT requires
+/// acknowledgements to be sent in that order.
+#[derive(Default)]
+struct UnackedMessages {
+    messages: VecDeque<(u64, Publish)>,
+    last_committed_sequence_number: u64,
+}
+
+impl UnackedMessages {
+    fn push(&mut self, sequence_number: u64, mut publish: Publish) {
+        // Only the packet identifier and the QoS are needed to acknowledge the message.
+        publish.payload = Bytes::new();
+        self.messages.push_back((sequence_number, publish));
+    }
+
+    fn commit(&mut self, sequence_number: u64) {
+        self.last_committed_sequence_number =
+            self.last_committed_sequence_number.max(sequence_number);
+    }
+
+    /// Acknowledges the committed messages, returning the number of messages acknowledged. The
+    /// acknowledgements that do not fit in the request channel of the client are left for the
+    /// next call.
+    fn flush(&mut self, client: &AsyncClient) -> Result<usize, ClientError> {
+        let mut num_acked_messages = 0;
+
+        while let Some((sequence_number, publish)) = self.messages.front() {
+            if *sequence_number > self.last_committed_sequence_number {
+                break;
+            }
+            match client.try_ack(publish) {
+                Ok(()) => {}
+                Err(ClientError::TryRequest(_)) => break,
+                Err(error) => return Err(error),
+            }
+            self.messages.pop_front();
+            num_acked_messages += 1;
+        }
+        Ok(num_acked_messages)
+    }
+
+    fn len(&self) -> usize {
+        self.messages.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use quickwit_actors::{ActorContext, Universe};
+    use quickwit_config::{SourceConfig, SourceParams};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use quickwit_metastore::metastore_for_test;
+    use quickwit_proto::types::IndexUid;
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::SourceActor;
+
+    fn publish(pkid: u16, payload: &str) -> Publish {
+        let mut publish = Publish::new("sensors/1", QoS::AtLeastOnce, payload);
+        publish.pkid = pkid;
+        publish
+    }
+
+    #[test]
+    fn test_unacked_messages_flush() {
+        let mqtt_options = MqttOptions::new("test-client", "localhost", 1883);
+        // The event loop is never polled, so the request channel fills up.
+        let (client, _event_loop) = AsyncClient::new(mqtt_options, 2);
+
+        let mut unacked_messages = UnackedMessages::default();
+        for sequence_number in 1..=4 {
+            unacked_messages.push(sequence_number, publish(sequence_number as u16, "{}"));
+        }
+        assert!(unacked_messages
+            .messages
+            .iter()
+            .all(|(_, publish)| publish.payload.is_empty()));
+
+        assert_eq!(unacked_messages.flush(&client).unwrap(), 0);
+        assert_eq!(unacked_messages.len(), 4);
+
+        unacked_messages.commit(3);
+        assert_eq!(unacked_messages.flush(&client).unwrap(), 2);
+        assert_eq!(unacked_messages.len(), 2);
+
+        unacked_messages.commit(1);
+        assert_eq!(unacked_messages.last_committed_sequence_number, 3);
+        assert_eq!(unacked_messages.flush(&client).unwrap(), 0);
+        assert_eq!(unacked_messages.messages.front().unwrap().0, 3);
+    }
+
+    #[tokio::test]
+    async fn test_mqtt_source_acks_messages_once_published() {
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+        let (doc_processor_mailbox, doc_processor_inbox) =
+            universe.create_test_mailbox::<DocProcessor>();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(JsonValue::Null);
+        let ctx: SourceContext =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        let params = MqttSourceParams {
+            host: "localhost".to_string(),
+            port: 1883,
+            topics: vec!["sensors/#".to_string()],
+            client_id: None,
+            username: None,
+            password: None,
+        };
+        let source_config =
+            SourceConfig::for_test("test-mqtt-source", SourceParams::Mqtt(params.clone()));
+        let runtime_args = SourceRuntimeArgs::for_test(
+            IndexUid::new_with_random_ulid("test-index"),
+            source_config,
+            metastore_for_test(),
+            PathBuf::from("./queues"),
+        );
+        // The event loop is never polled, so the acknowledgements stay in the request channel.
+        let mqtt_options = MqttOptions::new("test-client", "localhost", 1883);
+        let (client, _event_loop) = AsyncClient::new(mqtt_options, 10);
+        let (message_tx, message_rx) = mpsc::channel(10);
+        let partition_id = PartitionId::from("mqtt-test-client");
+
+        let mut source = MqttSource {
+            ctx: runtime_args,
+            params,
+            client_id: "test-client".to_string(),
+            client,
+            message_rx,
+            event_loop_handle: tokio::spawn(async {}),
+            partition_id: partition_id.clone(),
+            current_position: Position::Beginning,
+            unacked_messages: UnackedMessages::default(),
+            state: MqttSourceState::default(),
+        };
+        for pkid in 1..=3 {
+            message_tx.send(publish(pkid, "{}")).await.unwrap();
+        }
+        source
+            .emit_batches(&doc_processor_mailbox, &ctx)
+            .await
+            .unwrap();
+
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs.len(), 3);
+
+        // The messages are not acknowledged until the split containing them is published.
+        let observable_state = source.observable_state();
+        assert_eq!(observable_state["num_acked_messages"], 0);
+        assert_eq!(observable_state["num_unacked_messages"], 3);
+
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(SourceCheckpointDelta::from_partition_delta(
+                partition_id,
+                Position::Beginning,
+                Position::offset(2u64),
+            ))
+            .unwrap();
+        source.suggest_truncate(checkpoint, &ctx).await.unwrap();
+
+        let observable_state = source.observable_state();
+        assert_eq!(observable_state["num_acked_messages"], 2);
+        assert_eq!(observable_state["num_unacked_messages"], 1);
+        universe.assert_quit().await;
+    }
+}
@@ -0,0 +1,345 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use bytes::{Bytes, BytesMut};
+use quickwit_config::MultilineConfig;
+use quickwit_proto::types::Position;
+use regex::bytes::Regex;
+use tokio::time::Instant;
+
+/// A document assembled from one or several consecutive lines, along with the range of positions
+/// covered by its lines.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct MultilineEvent {
+    pub doc: Bytes,
+    /// Position preceding the first line of the event (exclusive).
+    pub from_position: Position,
+    /// Position of the last line of the event (inclusive).
+    pub to_position: Position,
+}
+
+struct PendingEvent {
+    buffer: BytesMut,
+    num_lines: usize,
+    from_position: Position,
+    to_position: Position,
+    started_at: Instant,
+}
+
+impl PendingEvent {
+    fn into_event(self) -> MultilineEvent {
+        MultilineEvent {
+            doc: self.buffer.freeze(),
+            from_position: self.from_position,
+            to_position: self.to_position,
+        }
+    }
+}
+
+/// Groups the consecutive lines of a partition into events according to a [`MultilineConfig`].
+///
+/// The last event is held until a line starting a new event is pushed, the event reaches its
+/// maximum size, or the flush timeout elapses. Sources must only checkpoint the positions of the
+/// events returned by the assembler: the lines of the pending event are consumed again after a
+/// restart.
+pub(crate) struct MultilineAssembler {
+    start_pattern_opt: Option<Regex>,
+    continuation_pattern_opt: Option<Regex>,
+    max_lines: usize,
+    max_bytes: usize,
+    flush_timeout: Duration,
+    pending_event_opt: Option<PendingEvent>,
+}
+
+impl MultilineAssembler {
+    pub fn try_new(multiline_config: &MultilineConfig) -> anyhow::Result<Self> {
+        let start_pattern_opt = multiline_config
+            .start_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()?;
+        let continuation_pattern_opt = multiline_config
+            .continuation_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()?;
+        Ok(Self {
+            start_pattern_opt,
+            continuation_pattern_opt,
+            max_lines: multiline_config.max_lines.get(),
+            max_bytes: multiline_config.max_bytes.as_u64() as usize,
+            flush_timeout: Duration::from_secs(multiline_config.flush_timeout_secs.get()),
+            pending_event_opt: None,
+        })
+    }
+
+    /// Pushes the line located between `from_position` (exclusive) and `to_position` (inclusive).
+    /// Returns the previous event if the line does not continue it.
+    pub fn push_line(
+        &mut self,
+        line: &[u8],
+        from_position: Position,
+        to_position: Position,
+    ) -> Option<MultilineEvent> {
+        let line = trim_line_terminator(line);
+        let starts_new_event = self.starts_new_event(line);
+
+        if let Some(pending_event) = &mut self.pending_event_opt {
+            if !starts_new_event
+                && pending_event.num_lines < self.max_lines
+                && pending_event.buffer.len() + 1 + line.len() <= self.max_bytes
+            {
+                pending_event.buffer.extend_from_slice(b"\n");
+                pending_event.buffer.extend_from_slice(line);
+                pending_event.num_lines += 1;
+                pending_event.to_position = to_position;
+                return None;
+            }
+        }
+        let new_pending_event = PendingEvent {
+            buffer: BytesMut::from(line),
+            num_lines: 1,
+            from_position,
+            to_position,
+            started_at: Instant::now(),
+        };
+        self.pending_event_opt
+            .replace(new_pending_event)
+            .map(PendingEvent::into_event)
+    }
+
+    /// Returns the pending event, if any.
+    pub fn flush(&mut self) -> Option<MultilineEvent> {
+        self.pending_event_opt.take().map(PendingEvent::into_event)
+    }
+
+    /// Discards the pending event, if any.
+    pub fn reset(&mut self) {
+        self.pending_event_opt = None;
+    }
+
+    /// Returns the pending event if it was started more than `flush_timeout` ago.
+    pub fn flush_if_expired(&mut self) -> Option<MultilineEvent> {
+        let pending_event = self.pending_event_opt.as_ref()?;
+
+        if pending_event.started_at.elapsed() < self.flush_timeout {
+            return None;
+        }
+        self.flush()
+    }
+
+    fn starts_new_event(&self, line: &[u8]) -> bool {
+        if let Some(start_pattern) = &self.start_pattern_opt {
+            if start_pattern.is_match(line) {
+                return true;
+            }
+        }
+        if let Some(continuation_pattern) = &self.continuation_pattern_opt {
+            return !continuation_pattern.is_match(line);
+        }
+        false
+    }
+}
+
+fn trim_line_terminator(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::{NonZeroU64, NonZeroUsize};
+
+    use bytesize::ByteSize;
+
+    use super::*;
+
+    /// Pushes the lines, located at consecutive offsets, and flushes the assembler at the end.
+    fn assemble(assembler: &mut MultilineAssembler, lines: &[&str]) -> Vec<MultilineEvent> {
+        let mut events = Vec::new();
+
+        for (offset, line) in lines.iter().enumerate() {
+            let from_position = if offset == 0 {
+                Position::Beginning
+            } else {
+                Position::offset(offset as u64 - 1)
+            };
+            let to_position = Position::offset(offset as u64);
+
+            if let Some(event) = assembler.push_line(line.as_bytes(), from_position, to_position) {
+                events.push(event);
+            }
+        }
+        events.extend(assembler.flush());
+        events
+    }
+
+    fn event(doc: &str, from_position: Position, to_offset: u64) -> MultilineEvent {
+        MultilineEvent {
+            doc: Bytes::from(doc.to_string()),
+            from_position,
+            to_position: Position::offset(to_offset),
+        }
+    }
+
+    #[test]
+    fn test_multiline_assembler_start_pattern() {
+        let multiline_config = MultilineConfig::for_test(r"^\d{4}-\d{2}-\d{2}");
+        let mut assembler = MultilineAssembler::try_new(&multiline_config).unwrap();
+
+        let events = assemble(
+            &mut assembler,
+            &[
+                "2024-01-01 ERROR boom\n",
+                "java.lang.RuntimeException: boom\n",
+                "\tat com.example.Main.main(Main.java:3)\n",
+                "2024-01-01 INFO recovered\r\n",
+                "2024-01-02 INFO done\n",
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                event(
+                    "2024-01-01 ERROR boom\njava.lang.RuntimeException: boom\n\tat \
+                     com.example.Main.main(Main.java:3)",
+                    Position::Beginning,
+                    2
+                ),
+                event("2024-01-01 INFO recovered", Position::offset(2u64), 3),
+                event("2024-01-02 INFO done", Position::offset(3u64), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiline_assembler_continuation_pattern() {
+        let multiline_config = MultilineConfig {
+            start_pattern: None,
+            continuation_pattern: Some(r"^(\s|Traceback|\w+Error:)".to_string()),
+            ..MultilineConfig::for_test("")
+        };
+        let mut assembler = MultilineAssembler::try_new(&multiline_config).unwrap();
+
+        let events = assemble(
+            &mut assembler,
+            &[
+                "request failed",
+                "Traceback (most recent call last):",
+                "  File \"main.py\", line 1, in <module>",
+                "ValueError: boom",
+                "request succeeded",
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                event(
+                    "request failed\nTraceback (most recent call last):\n  File \"main.py\", line \
+                     1, in <module>\nValueError: boom",
+                    Position::Beginning,
+                    3
+                ),
+                event("request succeeded", Position::offset(3u64), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiline_assembler_start_and_continuation_patterns() {
+        let multiline_config = MultilineConfig {
+            start_pattern: Some(r"^\[".to_string()),
+            continuation_pattern: Some(r"^\s".to_string()),
+            ..MultilineConfig::for_test("")
+        };
+        let mut assembler = MultilineAssembler::try_new(&multiline_config).unwrap();
+
+        let events = assemble(&mut assembler, &["[a]", " a", "b", "[c]", " c"]);
+        assert_eq!(
+            events,
+            [
+                event("[a]\n a", Position::Beginning, 1),
+                event("b", Position::offset(1u64), 2),
+                event("[c]\n c", Position::offset(2u64), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiline_assembler_limits() {
+        let multiline_config = MultilineConfig {
+            max_lines: NonZeroUsize::new(2).unwrap(),
+            ..MultilineConfig::for_test(r"^\S")
+        };
+        let mut assembler = MultilineAssembler::try_new(&multiline_config).unwrap();
+
+        let events = assemble(&mut assembler, &["a", " 1", " 2", " 3"]);
+        assert_eq!(
+            events,
+            [
+                event("a\n 1", Position::Beginning, 1),
+                event(" 2\n 3", Position::offset(1u64), 3),
+            ]
+        );
+
+        let multiline_config = MultilineConfig {
+            max_bytes: ByteSize::b(8),
+            ..MultilineConfig::for_test(r"^\S")
+        };
+        let mut assembler = MultilineAssembler::try_new(&multiline_config).unwrap();
+
+        let events = assemble(&mut assembler, &["abc", " de", " fg"]);
+        assert_eq!(
+            events,
+            [
+                event("abc\n de", Position::Beginning, 1),
+                event(" fg", Position::offset(1u64), 2),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_multiline_assembler_flush_if_expired() {
+        let multiline_config = MultilineConfig {
+            flush_timeout_secs: NonZeroU64::new(5).unwrap(),
+            ..MultilineConfig::for_test(r"^\S")
+        };
+        let mut assembler = MultilineAssembler::try_new(&multiline_config).unwrap();
+        assert!(assembler.flush_if_expired().is_none());
+
+        assert!(assembler
+            .push_line(b"a", Position::Beginning, Position::offset(0u64))
+            .is_none());
+        tokio::time::advance(Duration::from_secs(3)).await;
+
+        assert!(assembler
+            .push_line(b" 1", Position::offset(0u64), Position::offset(1u64))
+            .is_none());
+        assert!(assembler.flush_if_expired().is_none());
+
+        tokio::time::advance(Duration::from_secs(2)).await;
+
+        let event = assembler.flush_if_expired().unwrap();
+        assert_eq!(event.doc, "a\n 1");
+        assert_eq!(event.to_position, Position::offset(1u64));
+        assert!(assembler.flush().is_none());
+    }
+}
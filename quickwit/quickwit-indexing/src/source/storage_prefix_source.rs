@@ -0,0 +1,1175 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use bytes::Bytes;
+use fnv::FnvHasher;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::compression::{decompress_reader, CompressionCodec};
+use quickwit_config::{StoragePrefixCompression, StoragePrefixSourceParams};
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_metastore::IndexMetadataResponseExt;
+use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService};
+use quickwit_proto::types::{Position, ShardId};
+use quickwit_storage::{FileEntry, Storage, StorageErrorKind};
+use serde_json::{json, Value as JsonValue};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+use super::{BatchBuilder, BATCH_NUM_BYTES_LIMIT, EMIT_BATCHES_TIMEOUT};
+use crate::actors::DocProcessor;
+use crate::source::{Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
+
+/// Separates the number of slots of the source, the path of the last object read in a slot, and
+/// the number of bytes read from this object in slot positions. It sorts before the characters
+/// found in object paths, so positions sort like the paths of their objects.
+const SLOT_POSITION_SEPARATOR: char = '\t';
+
+/// Replaces the number of bytes read in the position of a slot whose last object has been fully
+/// read. It sorts after the digits of the offsets.
+const OBJECT_EOF_MARKER: &str = "~";
+
+/// Listings start after the lowest watermark of the assigned slots, except every
+/// `NUM_LISTINGS_PER_FULL_LISTING` listings, which list the whole prefix to detect the objects
+/// added before the watermark of their slot.
+const NUM_LISTINGS_PER_FULL_LISTING: u64 = 10;
+
+pub struct StoragePrefixSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for StoragePrefixSourceFactory {
+    type Source = StoragePrefixSource;
+    type Params = StoragePrefixSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: StoragePrefixSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        StoragePrefixSource::try_new(ctx, params, checkpoint).await
+    }
+}
+
+#[derive(Default)]
+struct StoragePrefixSourceState {
+    num_listings: u64,
+    num_objects_processed: u64,
+    num_bytes_processed: u64,
+    num_lines_processed: u64,
+    num_objects_skipped: u64,
+}
+
+/// The object currently read by the source.
+struct ObjectReader {
+    slot: u64,
+    path: String,
+    reader: BufReader<Box<dyn AsyncRead + Send + Unpin>>,
+    /// Number of (decompressed) bytes of the object read as of the last checkpoint delta emitted
+    /// for this object.
+    checkpointed_offset: u64,
+    /// Number of (decompressed) bytes of the object read so far.
+    current_offset: u64,
+}
+
+/// A source that continuously lists the objects stored under a storage prefix and indexes the
+/// new ones, one document per line.
+///
+/// Objects are spread over `desired_num_pipelines` slots by hashing their path, and the control
+/// plane assigns the slots to the pipelines of the source. The objects of a slot are read in the
+/// lexicographic order of their paths, so the checkpoint holds a single partition per slot, whose
+/// position is a watermark: the path of the last object read and the number of decompressed
+/// bytes read from it. Objects added with a path sorting before the watermark of their slot are
+/// never read: they are logged and counted as skipped.
+///
+/// Since the slot of an object depends on the number of slots, the slot positions also record it,
+/// and the source refuses to start if `desired_num_pipelines` no longer matches its checkpoint.
+pub struct StoragePrefixSource {
+    ctx: Arc<SourceRuntimeArgs>,
+    params: StoragePrefixSourceParams,
+    storage: Arc<dyn Storage>,
+    glob_opt: Option<glob::Pattern>,
+    num_slots: u64,
+    /// Positions of the slots assigned to this pipeline, including the checkpoint deltas emitted
+    /// but not published yet.
+    slot_positions: BTreeMap<u64, Position>,
+    /// Objects to read, in order.
+    pending_objects: VecDeque<FileEntry>,
+    /// Paths of the objects of the assigned slots returned by the listings since the last full
+    /// listing, used to detect the objects added before the watermark of their slot. `None` until
+    /// the assigned slots are listed.
+    listed_paths_opt: Option<HashSet<String>>,
+    num_listings_since_full_listing: u64,
+    current_object_opt: Option<ObjectReader>,
+    next_listing_at: Instant,
+    state: StoragePrefixSourceState,
+}
+
+impl fmt::Debug for StoragePrefixSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StoragePrefixSource")
+            .field("index_id", &self.ctx.index_id())
+            .field("source_id", &self.ctx.source_id())
+            .field("uri", &self.params.uri)
+            .finish()
+    }
+}
+
+impl StoragePrefixSource {
+    async fn try_new(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: StoragePrefixSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self> {
+        let storage = ctx.storage_resolver.resolve(&params.uri).await?;
+        let glob_opt = params
+            .glob
+            .as_deref()
+            .map(glob::Pattern::new)
+            .transpose()
+            .context("failed to parse glob pattern")?;
+        let num_slots = ctx.source_config.desired_num_pipelines.get() as u64;
+        check_checkpoint_num_slots(&checkpoint, num_slots)?;
+        let mut slot_positions = BTreeMap::new();
+
+        // A source with a single slot does not wait for the control plane to assign it.
+        if num_slots == 1 {
+            let position = checkpoint
+                .position_for_partition(&PartitionId::from(0u64))
+                .cloned()
+                .unwrap_or_default();
+            slot_positions.insert(0, position);
+        }
+        info!(
+            index_id=%ctx.index_id(),
+            source_id=%ctx.source_id(),
+            uri=%params.uri,
+            num_slots=%num_slots,
+            "Starting storage prefix source."
+        );
+        Ok(Self {
+            ctx,
+            params,
+            storage,
+            glob_opt,
+            num_slots,
+            slot_positions,
+            pending_objects: VecDeque::new(),
+            listed_paths_opt: None,
+            num_listings_since_full_listing: 0,
+            current_object_opt: None,
+            next_listing_at: Instant::now(),
+            state: StoragePrefixSourceState::default(),
+        })
+    }
+
+    fn matches_glob(&self, path: &Path) -> bool {
+        let Some(glob) = &self.glob_opt else {
+            return true;
+        };
+        let match_options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        glob.matches_path_with(path, match_options)
+    }
+
+    fn object_slot(&self, path: &str) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hasher.write(path.as_bytes());
+        hasher.finish() % self.num_slots
+    }
+
+    async fn fetch_published_checkpoint(
+        &self,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<SourceCheckpoint> {
+        let index_metadata_request =
+            IndexMetadataRequest::for_index_uid(self.ctx.index_uid().clone());
+        let index_metadata = ctx
+            .protect_future(
+                self.ctx
+                    .metastore
+                    .clone()
+                    .index_metadata(index_metadata_request),
+            )
+            .await?
+            .deserialize_index_metadata()?;
+        let published_checkpoint = index_metadata
+            .checkpoint
+            .source_checkpoint(self.ctx.source_id())
+            .cloned()
+            .unwrap_or_default();
+        Ok(published_checkpoint)
+    }
+
+    /// Returns the entry of the object stored at `path`, or `None` if it has been deleted.
+    async fn object_entry(
+        &self,
+        path: &str,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<Option<FileEntry>> {
+        match ctx
+            .protect_future(self.storage.file_num_bytes(Path::new(path)))
+            .await
+        {
+            Ok(num_bytes) => Ok(Some(FileEntry {
+                path: PathBuf::from(path),
+                num_bytes,
+            })),
+            Err(error) if error.kind() == StorageErrorKind::NotFound => {
+                warn!("object `{path}` was deleted before being fully read");
+                Ok(None)
+            }
+            Err(error) => {
+                Err(error).with_context(|| format!("failed to get size of object `{path}`"))
+            }
+        }
+    }
+
+    /// Lists the objects of the assigned slots that sort after the watermarks of their slot and
+    /// queues them, along with the objects that were being read.
+    async fn list_objects(&mut self, ctx: &SourceContext) -> anyhow::Result<()> {
+        self.next_listing_at =
+            Instant::now() + Duration::from_secs(self.params.poll_interval_secs.get());
+
+        if self.slot_positions.is_empty() {
+            return Ok(());
+        }
+        let mut pending_objects = Vec::new();
+        // Unless it is a full listing, the listing starts after the lowest watermark of the
+        // assigned slots. It excludes the objects at the watermarks, so the ones that were being
+        // read are fetched separately.
+        let mut start_after_opt: Option<String> = None;
+        let mut list_from_beginning = false;
+
+        for slot_position in self.slot_positions.values() {
+            let Some((path, offset_opt)) = parse_slot_position(slot_position, self.num_slots)?
+            else {
+                list_from_beginning = true;
+                continue;
+            };
+            if offset_opt.is_some() {
+                if let Some(file_entry) = self.object_entry(&path, ctx).await? {
+                    pending_objects.push(file_entry);
+                }
+            }
+            if start_after_opt
+                .as_ref()
+                .map_or(true, |start_after| path < *start_after)
+            {
+                start_after_opt = Some(path);
+            }
+        }
+        let is_full_listing = list_from_beginning
+            || self.listed_paths_opt.is_none()
+            || self.num_listings_since_full_listing + 1 >= NUM_LISTINGS_PER_FULL_LISTING;
+
+        if is_full_listing {
+            start_after_opt = None;
+        }
+        let file_entries = ctx
+            .protect_future(
+                self.storage.list_files_with_prefix(
+                    Path::new(""),
+                    start_after_opt.as_deref().map(Path::new),
+                ),
+            )
+            .await
+            .with_context(|| format!("failed to list objects under `{}`", self.params.uri))?;
+        self.state.num_listings += 1;
+
+        let previous_listed_paths_opt = if is_full_listing {
+            self.num_listings_since_full_listing = 0;
+            self.listed_paths_opt.take()
+        } else {
+            self.num_listings_since_full_listing += 1;
+            None
+        };
+        let mut listed_paths = HashSet::new();
+
+        for file_entry in file_entries {
+            if !self.matches_glob(&file_entry.path) {
+                continue;
+            }
+            let path = file_entry.path.to_string_lossy().into_owned();
+            let slot = self.object_slot(&path);
+            let Some(slot_position) = self.slot_positions.get(&slot) else {
+                continue;
+            };
+            match parse_slot_position(slot_position, self.num_slots)? {
+                Some((last_path, _)) if path <= last_path => {
+                    // The watermarks only move forward, so an object sorting before the watermark
+                    // of its slot that no listing returned since the previous full listing was
+                    // added too late.
+                    let is_new_object = previous_listed_paths_opt
+                        .as_ref()
+                        .map_or(false, |previous_listed_paths| {
+                            !previous_listed_paths.contains(&path)
+                        });
+                    if path < last_path && is_new_object {
+                        warn!(
+                            slot=%slot,
+                            "object `{path}` sorts before the last object read in its slot \
+                             `{last_path}` and will not be indexed"
+                        );
+                        self.state.num_objects_skipped += 1;
+                    }
+                }
+                _ => pending_objects.push(file_entry),
+            }
+            listed_paths.insert(path);
+        }
+        if is_full_listing {
+            self.listed_paths_opt = Some(listed_paths);
+        } else if let Some(previous_listed_paths) = &mut self.listed_paths_opt {
+            previous_listed_paths.extend(listed_paths);
+        }
+
+        // The paths are compared as strings, like the watermarks and the listing.
+        pending_objects
+            .sort_by_cached_key(|file_entry| file_entry.path.to_string_lossy().into_owned());
+        self.pending_objects = pending_objects.into();
+
+        debug!(
+            num_pending_objects=%self.pending_objects.len(),
+            "Listed objects under `{}`.", self.params.uri
+        );
+        Ok(())
+    }
+
+    /// Opens the next object to read, listing the prefix first if no object is pending and the
+    /// poll interval has elapsed. Returns `false` if there is no object to read.
+    async fn open_next_object(&mut self, ctx: &SourceContext) -> anyhow::Result<bool> {
+        if self.pending_objects.is_empty() {
+            if Instant::now() < self.next_listing_at {
+                return Ok(false);
+            }
+            self.list_objects(ctx).await?;
+        }
+        while let Some(file_entry) = self.pending_objects.pop_front() {
+            let path = file_entry.path.to_string_lossy().into_owned();
+            let slot = self.object_slot(&path);
+
+            let Some(slot_position) = self.slot_positions.get(&slot) else {
+                continue;
+            };
+            let offset = match parse_slot_position(slot_position, self.num_slots)? {
+                Some((last_path, Some(offset))) if last_path == path => offset,
+                _ => 0,
+            };
+            let codec_opt = object_compression(&file_entry.path, self.params.compression);
+            let reader = ctx
+                .protect_future(open_object(&*self.storage, &file_entry, codec_opt, offset))
+                .await
+                .with_context(|| format!("failed to open object `{path}`"))?;
+            self.current_object_opt = Some(ObjectReader {
+                slot,
+                path,
+                reader,
+                checkpointed_offset: offset,
+                current_offset: offset,
+            });
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+/// Returns the position of a slot of a source with `num_slots` slots whose last object read is
+/// `path`. `offset_opt` is the number of (decompressed) bytes read from the object, or `None` once
+/// it has been fully read.
+fn slot_position(num_slots: u64, path: &str, offset_opt: Option<u64>) -> Position {
+    let position_str = match offset_opt {
+        Some(offset) => format!(
+            "{num_slots}{SLOT_POSITION_SEPARATOR}{path}{SLOT_POSITION_SEPARATOR}{offset:0>20}"
+        ),
+        None => format!(
+            "{num_slots}{SLOT_POSITION_SEPARATOR}{path}{SLOT_POSITION_SEPARATOR}{OBJECT_EOF_MARKER}"
+        ),
+    };
+    Position::offset(position_str.as_str())
+}
+
+/// Parses the position of a slot into the path of the last object read and the number of bytes
+/// read from it, if it has not been fully read. Returns `None` if no object has been read yet, and
+/// an error if the position was recorded with a number of slots other than `num_slots`.
+fn parse_slot_position(
+    position: &Position,
+    num_slots: u64,
+) -> anyhow::Result<Option<(String, Option<u64>)>> {
+    let Position::Offset(offset) = position else {
+        return Ok(None);
+    };
+    let (position_num_slots, path, object_offset) = offset
+        .as_str()
+        .split_once(SLOT_POSITION_SEPARATOR)
+        .and_then(|(num_slots, path_and_offset)| {
+            let (path, object_offset) = path_and_offset.rsplit_once(SLOT_POSITION_SEPARATOR)?;
+            Some((num_slots.parse::<u64>().ok()?, path, object_offset))
+        })
+        .with_context(|| format!("invalid storage prefix slot position `{offset}`"))?;
+
+    if position_num_slots != num_slots {
+        bail!(
+            "storage prefix slot position `{offset}` was recorded with {position_num_slots} \
+             slots, but the source has {num_slots} slots: `desired_num_pipelines` cannot be \
+             changed without resetting the source checkpoint"
+        );
+    }
+
+    let offset_opt = if object_offset == OBJECT_EOF_MARKER {
+        None
+    } else {
+        let offset = object_offset
+            .parse()
+            .with_context(|| format!("invalid storage prefix slot position `{offset}`"))?;
+        Some(offset)
+    };
+    Ok(Some((path.to_string(), offset_opt)))
+}
+
+/// Checks that the checkpoint of the source was recorded with `num_slots` slots. Objects are
+/// assigned to slots by hashing their path, so the slot positions are meaningless once the number
+/// of slots changes.
+fn check_checkpoint_num_slots(checkpoint: &SourceCheckpoint, num_slots: u64) -> anyhow::Result<()> {
+    for (partition_id, position) in checkpoint.iter() {
+        if partition_id.as_u64().map_or(true, |slot| slot >= num_slots) {
+            bail!(
+                "storage prefix slot `{partition_id}` is out of range, the source has {num_slots} \
+                 slots: `desired_num_pipelines` cannot be changed without resetting the source \
+                 checkpoint"
+            );
+        }
+        parse_slot_position(&position, num_slots)?;
+    }
+    Ok(())
+}
+
+/// Moves the position of `slot` to `to_position`, recording the progress in the batch.
+fn record_slot_delta(
+    slot_positions: &mut BTreeMap<u64, Position>,
+    slot: u64,
+    to_position: Position,
+    batch: &mut BatchBuilder,
+) -> anyhow::Result<()> {
+    let slot_position = slot_positions
+        .get_mut(&slot)
+        .with_context(|| format!("slot {slot} is not assigned to this pipeline"))?;
+    let from_position = std::mem::replace(slot_position, to_position.clone());
+    batch
+        .checkpoint_delta
+        .record_partition_delta(PartitionId::from(slot), from_position, to_position)
+        .context("failed to record partition delta")?;
+    Ok(())
+}
+
+#[async_trait]
+impl Source for StoragePrefixSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let mut batch = BatchBuilder::default();
+        let deadline = Instant::now() + EMIT_BATCHES_TIMEOUT;
+
+        while batch.num_bytes < BATCH_NUM_BYTES_LIMIT && Instant::now() < deadline {
+            let Some(object_reader) = self.current_object_opt.as_mut() else {
+                if !self.open_next_object(ctx).await? {
+                    break;
+                }
+                continue;
+            };
+            let mut line = Vec::new();
+            let num_bytes = ctx
+                .protect_future(object_reader.reader.read_until(b'\n', &mut line))
+                .await
+                .map_err(anyhow::Error::from)?;
+
+            if num_bytes == 0 {
+                let to_position = slot_position(self.num_slots, &object_reader.path, None);
+                record_slot_delta(
+                    &mut self.slot_positions,
+                    object_reader.slot,
+                    to_position,
+                    &mut batch,
+                )?;
+                self.current_object_opt = None;
+                self.state.num_objects_processed += 1;
+                continue;
+            }
+            object_reader.current_offset += num_bytes as u64;
+            self.state.num_bytes_processed += num_bytes as u64;
+            self.state.num_lines_processed += 1;
+            batch.add_doc(Bytes::from(line));
+            ctx.record_progress();
+        }
+        if let Some(object_reader) = self.current_object_opt.as_mut() {
+            if object_reader.current_offset > object_reader.checkpointed_offset {
+                let to_position = slot_position(
+                    self.num_slots,
+                    &object_reader.path,
+                    Some(object_reader.current_offset),
+                );
+                record_slot_delta(
+                    &mut self.slot_positions,
+                    object_reader.slot,
+                    to_position,
+                    &mut batch,
+                )?;
+                object_reader.checkpointed_offset = object_reader.current_offset;
+            }
+        }
+        if !batch.checkpoint_delta.is_empty() {
+            debug!(
+                num_bytes=%batch.num_bytes,
+                num_docs=%batch.docs.len(),
+                "Sending doc batch to indexer."
+            );
+            ctx.send_message(doc_processor_mailbox, batch.build())
+                .await?;
+        }
+        if self.current_object_opt.is_none() && self.pending_objects.is_empty() {
+            return Ok(self
+                .next_listing_at
+                .saturating_duration_since(Instant::now()));
+        }
+        Ok(Duration::default())
+    }
+
+    /// The control plane assigns the slots of the source to its pipelines as shards whose IDs are
+    /// the slot numbers.
+    async fn assign_shards(
+        &mut self,
+        shard_ids: BTreeSet<ShardId>,
+        _doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> anyhow::Result<()> {
+        let mut slots = BTreeSet::new();
+
+        for shard_id in &shard_ids {
+            let slot = shard_id
+                .as_u64()
+                .filter(|slot| *slot < self.num_slots)
+                .with_context(|| format!("invalid storage prefix slot `{shard_id}`"))?;
+            slots.insert(slot);
+        }
+        if slots.iter().eq(self.slot_positions.keys()) {
+            return Ok(());
+        }
+        info!(slots=?slots, "Assigning storage prefix slots.");
+
+        self.slot_positions.retain(|slot, _| slots.contains(slot));
+
+        if let Some(object_reader) = &self.current_object_opt {
+            if !slots.contains(&object_reader.slot) {
+                self.current_object_opt = None;
+            }
+        }
+        let new_slots: Vec<u64> = slots
+            .into_iter()
+            .filter(|slot| !self.slot_positions.contains_key(slot))
+            .collect();
+
+        if !new_slots.is_empty() {
+            let published_checkpoint = self.fetch_published_checkpoint(ctx).await?;
+            check_checkpoint_num_slots(&published_checkpoint, self.num_slots)?;
+
+            for slot in new_slots {
+                let position = published_checkpoint
+                    .position_for_partition(&PartitionId::from(slot))
+                    .cloned()
+                    .unwrap_or_default();
+                self.slot_positions.insert(slot, position);
+            }
+        }
+        self.pending_objects.clear();
+        self.listed_paths_opt = None;
+        self.next_listing_at = Instant::now();
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        format!("StoragePrefixSource{{source_id={}}}", self.ctx.source_id())
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        let slots: Vec<u64> = self.slot_positions.keys().copied().collect();
+        json!({
+            "index_id": self.ctx.index_id(),
+            "source_id": self.ctx.source_id(),
+            "uri": self.params.uri.to_string(),
+            "slots": slots,
+            "num_pending_objects": self.pending_objects.len(),
+            "num_listings": self.state.num_listings,
+            "num_objects_processed": self.state.num_objects_processed,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_lines_processed": self.state.num_lines_processed,
+            "num_objects_skipped": self.state.num_objects_skipped,
+        })
+    }
+}
+
+fn object_compression(
+    path: &Path,
+    compression: StoragePrefixCompression,
+) -> Option<CompressionCodec> {
+    match compression {
+        StoragePrefixCompression::Auto => CompressionCodec::from_extension(path),
+        StoragePrefixCompression::None => None,
+        StoragePrefixCompression::Gzip => Some(CompressionCodec::Gzip),
+        StoragePrefixCompression::Zstd => Some(CompressionCodec::Zstd),
+        StoragePrefixCompression::Bzip2 => Some(CompressionCodec::Bzip2),
+        StoragePrefixCompression::Lz4 => Some(CompressionCodec::Lz4),
+        StoragePrefixCompression::Snappy => Some(CompressionCodec::Snappy),
+    }
+}
+
+/// Opens a stream on the object, decompressing it if needed, and positions it at `offset`.
+async fn open_object(
+    storage: &dyn Storage,
+    file_entry: &FileEntry,
+    codec_opt: Option<CompressionCodec>,
+    offset: u64,
+) -> anyhow::Result<BufReader<Box<dyn AsyncRead + Send + Unpin>>> {
+    let num_bytes = file_entry.num_bytes as usize;
+
+    let reader: Box<dyn AsyncRead + Send + Unpin> = match codec_opt {
+        None => {
+            let start = (offset as usize).min(num_bytes);
+            open_object_range(storage, &file_entry.path, start..num_bytes).await?
+        }
+        Some(codec) => {
+            let stream = open_object_range(storage, &file_entry.path, 0..num_bytes).await?;
+            let mut decoder = decompress_reader(codec, stream);
+            // Compressed objects cannot be seeked into, so we decompress and skip the bytes
+            // preceding the checkpointed offset.
+            let num_skipped_bytes =
+                tokio::io::copy(&mut (&mut decoder).take(offset), &mut tokio::io::sink()).await?;
+            if num_skipped_bytes < offset {
+                bail!(
+                    "decompressed object is shorter ({num_skipped_bytes} bytes) than its \
+                     checkpointed offset ({offset} bytes)"
+                );
+            }
+            decoder
+        }
+    };
+    Ok(BufReader::new(reader))
+}
+
+async fn open_object_range(
+    storage: &dyn Storage,
+    path: &Path,
+    range: std::ops::Range<usize>,
+) -> anyhow::Result<Box<dyn AsyncRead + Send + Unpin>> {
+    // Some object storages reject empty ranges.
+    if range.is_empty() {
+        return Ok(Box::new(tokio::io::empty()));
+    }
+    let stream = storage.get_slice_stream(path, range).await?;
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::{NonZeroU64, NonZeroUsize};
+
+    use async_compression::tokio::bufread::GzipEncoder;
+    use quickwit_actors::{ActorContext, Universe};
+    use quickwit_common::uri::Uri;
+    use quickwit_config::{IndexConfig, SourceConfig, SourceInputFormat, SourceParams};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use quickwit_metastore::{metastore_for_test, CreateIndexRequestExt};
+    use quickwit_proto::metastore::{CreateIndexRequest, MetastoreServiceClient};
+    use quickwit_proto::types::IndexUid;
+    use tokio::sync::watch;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::SourceActor;
+
+    async fn setup_index(mut metastore: MetastoreServiceClient, index_id: &str) -> IndexUid {
+        let index_uri = format!("ram:///indexes/{index_id}");
+        let index_config = IndexConfig::for_test(index_id, &index_uri);
+        let create_index_request =
+            CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+        metastore
+            .create_index(create_index_request)
+            .await
+            .unwrap()
+            .index_uid()
+            .clone()
+    }
+
+    fn make_source_config(
+        params: StoragePrefixSourceParams,
+        desired_num_pipelines: usize,
+    ) -> SourceConfig {
+        SourceConfig {
+            source_id: "test-storage-prefix-source".to_string(),
+            desired_num_pipelines: NonZeroUsize::new(desired_num_pipelines).unwrap(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::StoragePrefix(params),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
+        }
+    }
+
+    fn make_params(uri: &'static str, glob_opt: Option<&str>) -> StoragePrefixSourceParams {
+        StoragePrefixSourceParams {
+            uri: Uri::for_test(uri),
+            glob: glob_opt.map(ToString::to_string),
+            compression: StoragePrefixCompression::Auto,
+            poll_interval_secs: NonZeroU64::new(30).unwrap(),
+        }
+    }
+
+    async fn gzip(payload: &[u8]) -> Vec<u8> {
+        let mut compressed_payload = Vec::new();
+        GzipEncoder::new(payload)
+            .read_to_end(&mut compressed_payload)
+            .await
+            .unwrap();
+        compressed_payload
+    }
+
+    async fn create_source(
+        metastore: MetastoreServiceClient,
+        index_uid: IndexUid,
+        params: StoragePrefixSourceParams,
+        desired_num_pipelines: usize,
+        checkpoint: SourceCheckpoint,
+        objects: &[(&str, Vec<u8>)],
+    ) -> StoragePrefixSource {
+        let source_config = make_source_config(params.clone(), desired_num_pipelines);
+        let runtime_args = SourceRuntimeArgs::for_test(
+            index_uid,
+            source_config,
+            metastore,
+            PathBuf::from("./queues"),
+        );
+        let storage = runtime_args
+            .storage_resolver
+            .resolve(&params.uri)
+            .await
+            .unwrap();
+        for (path, payload) in objects {
+            storage
+                .put(Path::new(path), Box::new(payload.clone()))
+                .await
+                .unwrap();
+        }
+        StoragePrefixSourceFactory::typed_create_source(runtime_args, params, checkpoint)
+            .await
+            .unwrap()
+    }
+
+    fn partition_deltas(batch: &RawDocBatch) -> Vec<(PartitionId, Position, Position)> {
+        batch
+            .checkpoint_delta
+            .iter()
+            .map(|(partition_id, partition_delta)| {
+                (partition_id, partition_delta.from, partition_delta.to)
+            })
+            .collect()
+    }
+
+    fn pending_paths(source: &StoragePrefixSource) -> Vec<String> {
+        source
+            .pending_objects
+            .iter()
+            .map(|file_entry| file_entry.path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_object_compression() {
+        let auto = StoragePrefixCompression::Auto;
+        assert_eq!(
+            object_compression(Path::new("logs/a.ndjson.gz"), auto),
+            Some(CompressionCodec::Gzip)
+        );
+        assert_eq!(
+            object_compression(Path::new("logs/a.ndjson.zst"), auto),
+            Some(CompressionCodec::Zstd)
+        );
+        assert_eq!(
+            object_compression(Path::new("logs/a.ndjson.sz"), auto),
+            Some(CompressionCodec::Snappy)
+        );
+        assert_eq!(object_compression(Path::new("logs/a.ndjson"), auto), None);
+        assert_eq!(
+            object_compression(Path::new("logs/a.ndjson"), StoragePrefixCompression::Gzip),
+            Some(CompressionCodec::Gzip)
+        );
+        assert_eq!(
+            object_compression(
+                Path::new("logs/a.ndjson.gz"),
+                StoragePrefixCompression::None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_slot_position() {
+        assert_eq!(parse_slot_position(&Position::Beginning, 1).unwrap(), None);
+        assert_eq!(
+            parse_slot_position(&slot_position(1, "logs/a.ndjson", Some(42)), 1).unwrap(),
+            Some(("logs/a.ndjson".to_string(), Some(42)))
+        );
+        assert_eq!(
+            parse_slot_position(&slot_position(1, "logs/a.ndjson", None), 1).unwrap(),
+            Some(("logs/a.ndjson".to_string(), None))
+        );
+        assert_eq!(
+            parse_slot_position(&slot_position(2, "logs/a\tb.ndjson", None), 2).unwrap(),
+            Some(("logs/a\tb.ndjson".to_string(), None))
+        );
+        parse_slot_position(&Position::offset(42u64), 1).unwrap_err();
+
+        let error = parse_slot_position(&slot_position(2, "logs/a.ndjson", None), 3).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`desired_num_pipelines` cannot be changed"));
+
+        // Positions sort like the paths of their objects, then like the number of bytes read.
+        assert!(Position::Beginning < slot_position(1, "a", Some(0)));
+        assert!(slot_position(1, "a", Some(9)) < slot_position(1, "a", Some(10)));
+        assert!(slot_position(1, "a", Some(10)) < slot_position(1, "a", None));
+        assert!(slot_position(1, "a", None) < slot_position(1, "a-b", Some(0)));
+        assert!(slot_position(1, "a-b", None) < slot_position(1, "a/b", Some(0)));
+    }
+
+    #[test]
+    fn test_check_checkpoint_num_slots() {
+        let mut checkpoint = SourceCheckpoint::default();
+        check_checkpoint_num_slots(&checkpoint, 2).unwrap();
+
+        checkpoint
+            .try_apply_delta(SourceCheckpointDelta::from_partition_delta(
+                PartitionId::from(1u64),
+                Position::Beginning,
+                slot_position(2, "a.ndjson", None),
+            ))
+            .unwrap();
+        check_checkpoint_num_slots(&checkpoint, 2).unwrap();
+        check_checkpoint_num_slots(&checkpoint, 1).unwrap_err();
+        check_checkpoint_num_slots(&checkpoint, 3).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_storage_prefix_source() {
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+        let (doc_processor_mailbox, doc_processor_inbox) =
+            universe.create_test_mailbox::<DocProcessor>();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(JsonValue::Null);
+        let ctx: SourceContext =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        let metastore = metastore_for_test();
+        let index_uid = setup_index(metastore.clone(), "test-storage-prefix-source").await;
+        let params = make_params(
+            "ram:///test-storage-prefix-source/logs",
+            Some("**/*.ndjson*"),
+        );
+        let objects = [
+            ("a.ndjson", b"{\"n\": 1}\n{\"n\": 2}\n".to_vec()),
+            ("2024/b.ndjson.gz", gzip(b"{\"n\": 3}\n{\"n\": 4}\n").await),
+            (
+                "2024/c.ndjson.zst",
+                zstd::encode_all(&b"{\"n\": 5}\n"[..], 0).unwrap(),
+            ),
+            ("d.txt", b"ignored\n".to_vec()),
+        ];
+        let mut source = create_source(
+            metastore,
+            index_uid,
+            params.clone(),
+            1,
+            SourceCheckpoint::default(),
+            &objects,
+        )
+        .await;
+
+        let wait_for = source
+            .emit_batches(&doc_processor_mailbox, &ctx)
+            .await
+            .unwrap();
+        // All the objects have been read: the source waits for the next listing.
+        assert!(!wait_for.is_zero());
+
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].docs,
+            [
+                Bytes::from_static(b"{\"n\": 3}\n"),
+                Bytes::from_static(b"{\"n\": 4}\n"),
+                Bytes::from_static(b"{\"n\": 5}\n"),
+                Bytes::from_static(b"{\"n\": 1}\n"),
+                Bytes::from_static(b"{\"n\": 2}\n"),
+            ]
+        );
+        assert_eq!(
+            partition_deltas(&batches[0]),
+            [(
+                PartitionId::from(0u64),
+                Position::Beginning,
+                slot_position(1, "a.ndjson", None)
+            )]
+        );
+        let observable_state = source.observable_state();
+        assert_eq!(observable_state["slots"], json!([0]));
+        assert_eq!(observable_state["num_objects_processed"], 3);
+        assert_eq!(observable_state["num_lines_processed"], 5);
+        assert_eq!(observable_state["num_objects_skipped"], 0);
+
+        // The objects sorting after the watermark are read on the next listing, the ones sorting
+        // before are skipped.
+        let storage = source.storage.clone();
+        storage
+            .put(Path::new("e.ndjson"), Box::new(b"{\"n\": 6}".to_vec()))
+            .await
+            .unwrap();
+        storage
+            .put(Path::new("0.ndjson"), Box::new(b"{\"n\": 0}\n".to_vec()))
+            .await
+            .unwrap();
+        let wait_for = source
+            .emit_batches(&doc_processor_mailbox, &ctx)
+            .await
+            .unwrap();
+        assert!(!wait_for.is_zero());
+        assert!(doc_processor_inbox
+            .drain_for_test_typed::<RawDocBatch>()
+            .is_empty());
+
+        source.next_listing_at = Instant::now();
+        source
+            .emit_batches(&doc_processor_mailbox, &ctx)
+            .await
+            .unwrap();
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs, [Bytes::from_static(b"{\"n\": 6}")]);
+        assert_eq!(
+            partition_deltas(&batches[0]),
+            [(
+                PartitionId::from(0u64),
+                slot_position(1, "a.ndjson", None),
+                slot_position(1, "e.ndjson", None)
+            )]
+        );
+        assert_eq!(source.observable_state()["num_objects_skipped"], 0);
+
+        // The object added before the watermark is reported as skipped by the next full listing,
+        // once.
+        for _ in 0..NUM_LISTINGS_PER_FULL_LISTING {
+            source.list_objects(&ctx).await.unwrap();
+        }
+        assert!(source.pending_objects.is_empty());
+        assert_eq!(source.observable_state()["num_objects_skipped"], 1);
+
+        for _ in 0..NUM_LISTINGS_PER_FULL_LISTING {
+            source.list_objects(&ctx).await.unwrap();
+        }
+        assert_eq!(source.observable_state()["num_objects_skipped"], 1);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_storage_prefix_source_resumes_from_checkpoint() {
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+        let (doc_processor_mailbox, doc_processor_inbox) =
+            universe.create_test_mailbox::<DocProcessor>();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(JsonValue::Null);
+        let ctx: SourceContext =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        let metastore = metastore_for_test();
+        let index_uid = setup_index(metastore.clone(), "test-storage-prefix-source-resume").await;
+        let params = make_params("ram:///test-storage-prefix-source-resume/logs", None);
+        let objects = [
+            ("a.ndjson", b"{\"n\": 1}\n{\"n\": 2}\n".to_vec()),
+            ("b.ndjson.gz", gzip(b"{\"n\": 3}\n{\"n\": 4}\n").await),
+            ("c.ndjson", b"{\"n\": 5}\n".to_vec()),
+        ];
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(SourceCheckpointDelta::from_partition_delta(
+                PartitionId::from(0u64),
+                Position::Beginning,
+                slot_position(1, "b.ndjson.gz", Some(9)),
+            ))
+            .unwrap();
+        let mut source = create_source(metastore, index_uid, params, 1, checkpoint, &objects).await;
+
+        source
+            .emit_batches(&doc_processor_mailbox, &ctx)
+            .await
+            .unwrap();
+
+        let batches = doc_processor_inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].docs,
+            [
+                Bytes::from_static(b"{\"n\": 4}\n"),
+                Bytes::from_static(b"{\"n\": 5}\n")
+            ]
+        );
+        assert_eq!(
+            partition_deltas(&batches[0]),
+            [(
+                PartitionId::from(0u64),
+                slot_position(1, "b.ndjson.gz", Some(9)),
+                slot_position(1, "c.ndjson", None)
+            )]
+        );
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_storage_prefix_source_assign_shards() {
+        let universe = Universe::with_accelerated_time();
+        let (source_mailbox, _source_inbox) = universe.create_test_mailbox::<SourceActor>();
+        let (doc_processor_mailbox, _doc_processor_inbox) =
+            universe.create_test_mailbox::<DocProcessor>();
+        let (observable_state_tx, _observable_state_rx) = watch::channel(JsonValue::Null);
+        let ctx: SourceContext =
+            ActorContext::for_test(&universe, source_mailbox, observable_state_tx);
+
+        let metastore = metastore_for_test();
+        let index_uid = setup_index(metastore.clone(), "test-storage-prefix-source-assign").await;
+        let params = make_params("ram:///test-storage-prefix-source-assign/logs", None);
+        let objects: Vec<(String, Vec<u8>)> = (0..20)
+            .map(|object_idx| (format!("{object_idx:02}.ndjson"), b"{}\n".to_vec()))
+            .collect();
+        let objects: Vec<(&str, Vec<u8>)> = objects
+            .iter()
+            .map(|(path, payload)| (path.as_str(), payload.clone()))
+            .collect();
+        let mut source = create_source(
+            metastore,
+            index_uid,
+            params,
+            2,
+            SourceCheckpoint::default(),
+            &objects,
+        )
+        .await;
+
+        // The source does not read any object until the control plane assigns it slots.
+        source.list_objects(&ctx).await.unwrap();
+        assert!(source.pending_objects.is_empty());
+
+        let slot_paths = |slot: u64| -> Vec<String> {
+            objects
+                .iter()
+                .map(|(path, _)| path.to_string())
+                .filter(|path| source.object_slot(path) == slot)
+                .collect()
+        };
+        let slot_0_paths = slot_paths(0);
+        let slot_1_paths = slot_paths(1);
+        assert!(!slot_0_paths.is_empty());
+        assert!(!slot_1_paths.is_empty());
+
+        source
+            .assign_shards(
+                BTreeSet::from_iter([ShardId::from(0u64)]),
+                &doc_processor_mailbox,
+                &ctx,
+            )
+            .await
+            .unwrap();
+        source.list_objects(&ctx).await.unwrap();
+        assert_eq!(pending_paths(&source), slot_0_paths);
+
+        source
+            .assign_shards(
+                BTreeSet::from_iter([ShardId::from(1u64)]),
+                &doc_processor_mailbox,
+                &ctx,
+            )
+            .await
+            .unwrap();
+        assert!(source.pending_objects.is_empty());
+
+        source.list_objects(&ctx).await.unwrap();
+        assert_eq!(pending_paths(&source), slot_1_paths);
+        assert_eq!(source.observable_state()["slots"], json!([1]));
+
+        source
+            .assign_shards(
+                BTreeSet::from_iter([ShardId::from(2u64)]),
+                &doc_processor_mailbox,
+                &ctx,
+            )
+            .await
+            .unwrap_err();
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_storage_prefix_source_rejects_num_slots_change() {
+        let metastore = metastore_for_test();
+        let index_uid = setup_index(metastore.clone(), "test-storage-prefix-source-slots").await;
+        let params = make_params("ram:///test-storage-prefix-source-slots/logs", None);
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(SourceCheckpointDelta::from_partition_delta(
+                PartitionId::from(0u64),
+                Position::Beginning,
+                slot_position(2, "a.ndjson", None),
+            ))
+            .unwrap();
+        let runtime_args = SourceRuntimeArgs::for_test(
+            index_uid,
+            make_source_config(params.clone(), 1),
+            metastore,
+            PathBuf::from("./queues"),
+        );
+        let error =
+            StoragePrefixSourceFactory::typed_create_source(runtime_args, params, checkpoint)
+                .await
+                .err()
+                .unwrap();
+        assert!(error
+            .to_string()
+            .contains("`desired_num_pipelines` cannot be changed"));
+    }
+}
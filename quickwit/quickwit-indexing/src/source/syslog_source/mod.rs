@@ -0,0 +1,662 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+mod parser;
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::{Buf, Bytes, BytesMut};
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_common::rate_limited_tracing::rate_limited_warn;
+use quickwit_config::{SyslogProtocol, SyslogSourceParams};
+use quickwit_metastore::checkpoint::{PartitionId, SourceCheckpoint};
+use quickwit_proto::types::Position;
+use serde_json::{json, Value as JsonValue};
+use tokio::io::AsyncReadExt;
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time;
+use tracing::{debug, info, warn};
+
+use self::parser::parse_syslog_message;
+use super::{BatchBuilder, BATCH_NUM_BYTES_LIMIT, EMIT_BATCHES_TIMEOUT};
+use crate::actors::DocProcessor;
+use crate::source::{Source, SourceContext, SourceRuntimeArgs, TypedSourceFactory};
+
+/// Maximum size of a syslog message. Larger TCP frames close the connection, larger UDP datagrams
+/// are truncated by the socket.
+const MAX_MESSAGE_NUM_BYTES: usize = 64 * 1024;
+
+/// Number of messages buffered between the listener and the source.
+const MESSAGE_CHANNEL_CAPACITY: usize = 10_000;
+
+pub struct SyslogSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for SyslogSourceFactory {
+    type Source = SyslogSource;
+    type Params = SyslogSourceParams;
+
+    async fn typed_create_source(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: SyslogSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        SyslogSource::try_new(ctx, params, checkpoint).await
+    }
+}
+
+/// A message received by the listener, before parsing.
+struct SyslogMessage {
+    payload: Bytes,
+    peer_addr: SocketAddr,
+}
+
+#[derive(Default)]
+struct SyslogSourceState {
+    /// Number of bytes processed by the source.
+    num_bytes_processed: u64,
+    /// Number of messages processed by the source.
+    num_messages_processed: u64,
+    /// Number of messages that could not be parsed.
+    num_invalid_messages: u64,
+    /// Position of the last message processed.
+    current_position: Position,
+    /// Offset of the next message.
+    next_offset: u64,
+}
+
+/// A source that listens for syslog messages over TCP or UDP and parses them into JSON objects.
+///
+/// Syslog senders cannot replay messages, so the checkpoint only tracks the number of messages
+/// received by the listener. The partition ID is derived from the node ID and the listen address
+/// so that a pipeline restarted on the same node resumes from its previous position.
+pub struct SyslogSource {
+    ctx: Arc<SourceRuntimeArgs>,
+    params: SyslogSourceParams,
+    local_addr: SocketAddr,
+    partition_id: PartitionId,
+    message_rx: mpsc::Receiver<SyslogMessage>,
+    listener_handle: JoinHandle<()>,
+    state: SyslogSourceState,
+}
+
+impl fmt::Debug for SyslogSource {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("SyslogSource")
+            .field("index_id", &self.ctx.index_id())
+            .field("source_id", &self.ctx.source_id())
+            .field("local_addr", &self.local_addr)
+            .field("protocol", &self.params.protocol)
+            .finish()
+    }
+}
+
+impl Drop for SyslogSource {
+    fn drop(&mut self) {
+        // Stops the listener and, for TCP, the connection tasks it owns.
+        self.listener_handle.abort();
+    }
+}
+
+impl SyslogSource {
+    pub async fn try_new(
+        ctx: Arc<SourceRuntimeArgs>,
+        params: SyslogSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self> {
+        let (message_tx, message_rx) = mpsc::channel(MESSAGE_CHANNEL_CAPACITY);
+
+        let (local_addr, listener_handle) = match params.protocol {
+            SyslogProtocol::Tcp => {
+                let listener = TcpListener::bind(params.listen_address)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "failed to bind syslog TCP listener to `{}`",
+                            params.listen_address
+                        )
+                    })?;
+                let local_addr = listener.local_addr()?;
+                let listener_handle = tokio::spawn(accept_tcp_connections(listener, message_tx));
+                (local_addr, listener_handle)
+            }
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind(params.listen_address)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "failed to bind syslog UDP socket to `{}`",
+                            params.listen_address
+                        )
+                    })?;
+                let local_addr = socket.local_addr()?;
+                let listener_handle = tokio::spawn(receive_udp_datagrams(socket, message_tx));
+                (local_addr, listener_handle)
+            }
+        };
+        let partition_id =
+            PartitionId::from(format!("{}:{}", ctx.node_id(), params.listen_address));
+        let current_position = checkpoint
+            .position_for_partition(&partition_id)
+            .cloned()
+            .unwrap_or_default();
+        let next_offset = current_position
+            .as_u64()
+            .map(|offset| offset + 1)
+            .unwrap_or_default();
+        let state = SyslogSourceState {
+            current_position,
+            next_offset,
+            ..Default::default()
+        };
+        info!(
+            index_id=%ctx.index_id(),
+            source_id=%ctx.source_id(),
+            local_addr=%local_addr,
+            protocol=?params.protocol,
+            "Starting syslog source."
+        );
+        Ok(Self {
+            ctx,
+            params,
+            local_addr,
+            partition_id,
+            message_rx,
+            listener_handle,
+            state,
+        })
+    }
+
+    fn process_message(&mut self, message: SyslogMessage, batch: &mut BatchBuilder) {
+        self.state.num_messages_processed += 1;
+        self.state.num_bytes_processed += message.payload.len() as u64;
+        self.state.next_offset += 1;
+
+        match parse_syslog_message(&message.payload, self.params.format) {
+            Ok(mut json_obj) => {
+                json_obj.insert(
+                    "source_ip".to_string(),
+                    JsonValue::from(message.peer_addr.ip().to_string()),
+                );
+                let doc =
+                    serde_json::to_vec(&json_obj).expect("JSON object should be serializable");
+                batch.add_doc(Bytes::from(doc));
+            }
+            Err(error) => {
+                self.state.num_invalid_messages += 1;
+                rate_limited_warn!(
+                    limit_per_min = 10,
+                    index_id = self.ctx.index_id(),
+                    source_id = self.ctx.source_id(),
+                    peer_addr = %message.peer_addr,
+                    "failed to parse syslog message: {error:#}"
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Source for SyslogSource {
+    async fn emit_batches(
+        &mut self,
+        doc_processor_mailbox: &Mailbox<DocProcessor>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let mut batch = BatchBuilder::default();
+        let next_offset_before_batch = self.state.next_offset;
+        let deadline = time::sleep(EMIT_BATCHES_TIMEOUT);
+        tokio::pin!(deadline);
+
+        loop {
+            tokio::select! {
+                message_opt = self.message_rx.recv() => {
+                    let Some(message) = message_opt else {
+                        let error = anyhow::anyhow!(
+                            "syslog listener on `{}` exited unexpectedly", self.local_addr
+                        );
+                        return Err(ActorExitStatus::from(error));
+                    };
+                    self.process_message(message, &mut batch);
+
+                    if batch.num_bytes >= BATCH_NUM_BYTES_LIMIT {
+                        break;
+                    }
+                }
+                _ = &mut deadline => {
+                    break;
+                }
+            }
+            ctx.record_progress();
+        }
+        if self.state.next_offset > next_offset_before_batch {
+            let to_position = Position::offset(self.state.next_offset - 1);
+            let from_position =
+                std::mem::replace(&mut self.state.current_position, to_position.clone());
+            batch
+                .checkpoint_delta
+                .record_partition_delta(self.partition_id.clone(), from_position, to_position)
+                .context("failed to record partition delta")?;
+        }
+        if !batch.checkpoint_delta.is_empty() {
+            debug!(
+                num_bytes=%batch.num_bytes,
+                num_docs=%batch.docs.len(),
+                "Sending doc batch to indexer."
+            );
+            ctx.send_message(doc_processor_mailbox, batch.build())
+                .await?;
+        }
+        Ok(Duration::default())
+    }
+
+    fn name(&self) -> String {
+        format!("SyslogSource{{source_id={}}}", self.ctx.source_id())
+    }
+
+    fn observable_state(&self) -> JsonValue {
+        json!({
+            "index_id": self.ctx.index_id(),
+            "source_id": self.ctx.source_id(),
+            "local_addr": self.local_addr.to_string(),
+            "protocol": self.params.protocol,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+        })
+    }
+}
+
+async fn receive_udp_datagrams(socket: UdpSocket, message_tx: mpsc::Sender<SyslogMessage>) {
+    let mut buffer = vec![0u8; MAX_MESSAGE_NUM_BYTES];
+
+    loop {
+        let (num_bytes, peer_addr) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(error) => {
+                warn!(%error, "failed to receive syslog datagram");
+                continue;
+            }
+        };
+        let payload = trim_line_ending(&buffer[..num_bytes]);
+
+        if payload.is_empty() {
+            continue;
+        }
+        let message = SyslogMessage {
+            payload: Bytes::copy_from_slice(payload),
+            peer_addr,
+        };
+        if message_tx.send(message).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn accept_tcp_connections(listener: TcpListener, message_tx: mpsc::Sender<SyslogMessage>) {
+    // Dropping the join set, when the listener task is aborted, aborts the connection tasks.
+    let mut connection_tasks = JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accept_result = listener.accept() => {
+                match accept_result {
+                    Ok((stream, peer_addr)) => {
+                        debug!(%peer_addr, "accepted syslog TCP connection");
+                        connection_tasks.spawn(read_tcp_stream(stream, peer_addr, message_tx.clone()));
+                    }
+                    Err(error) => {
+                        warn!(%error, "failed to accept syslog TCP connection");
+                        time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+            Some(_) = connection_tasks.join_next(), if !connection_tasks.is_empty() => {}
+        }
+    }
+}
+
+async fn read_tcp_stream(
+    mut stream: TcpStream,
+    peer_addr: SocketAddr,
+    message_tx: mpsc::Sender<SyslogMessage>,
+) {
+    let mut buffer = BytesMut::with_capacity(8 * 1024);
+
+    loop {
+        loop {
+            match next_tcp_frame(&mut buffer) {
+                Ok(Some(payload)) => {
+                    let message = SyslogMessage { payload, peer_addr };
+                    if message_tx.send(message).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break,
+                Err(error) => {
+                    warn!(%peer_addr, "closing syslog TCP connection: {error}");
+                    return;
+                }
+            }
+        }
+        match stream.read_buf(&mut buffer).await {
+            Ok(0) => {
+                // The last message of a newline-delimited stream may lack a trailing newline.
+                let payload = trim_line_ending(&buffer);
+
+                if !payload.is_empty() && !payload[0].is_ascii_digit() {
+                    let message = SyslogMessage {
+                        payload: Bytes::copy_from_slice(payload),
+                        peer_addr,
+                    };
+                    let _ = message_tx.send(message).await;
+                }
+                return;
+            }
+            Ok(_) => {}
+            Err(error) => {
+                debug!(%peer_addr, %error, "failed to read from syslog TCP connection");
+                return;
+            }
+        }
+    }
+}
+
+/// Extracts the next frame from a TCP stream buffer. Frames use either octet counting
+/// (`<length> <message>`) or newline delimiters, as described in RFC 6587. Returns `None` if the
+/// buffer does not hold a complete frame yet.
+fn next_tcp_frame(buffer: &mut BytesMut) -> anyhow::Result<Option<Bytes>> {
+    loop {
+        let Some(first_byte) = buffer.first() else {
+            return Ok(None);
+        };
+        if first_byte.is_ascii_digit() {
+            // Octet counting.
+            let Some(space_idx) = buffer.iter().position(|byte| *byte == b' ') else {
+                if buffer.len() > 10 {
+                    anyhow::bail!("invalid syslog frame length");
+                }
+                return Ok(None);
+            };
+            let frame_len: usize = std::str::from_utf8(&buffer[..space_idx])
+                .ok()
+                .and_then(|frame_len_str| frame_len_str.parse().ok())
+                .context("invalid syslog frame length")?;
+
+            if frame_len > MAX_MESSAGE_NUM_BYTES {
+                anyhow::bail!("syslog frame of {frame_len} bytes exceeds the maximum message size");
+            }
+            if buffer.len() < space_idx + 1 + frame_len {
+                return Ok(None);
+            }
+            buffer.advance(space_idx + 1);
+            let mut frame = buffer.split_to(frame_len).freeze();
+            let payload_len = trim_line_ending(&frame).len();
+            frame.truncate(payload_len);
+            return Ok(Some(frame));
+        }
+        // Newline delimiters.
+        let Some(newline_idx) = buffer.iter().position(|byte| *byte == b'\n') else {
+            if buffer.len() > MAX_MESSAGE_NUM_BYTES {
+                anyhow::bail!("syslog message exceeds the maximum message size");
+            }
+            return Ok(None);
+        };
+        let line = buffer.split_to(newline_idx + 1);
+        let payload = trim_line_ending(&line);
+
+        // Skip empty lines.
+        if !payload.is_empty() {
+            return Ok(Some(Bytes::copy_from_slice(payload)));
+        }
+    }
+}
+
+fn trim_line_ending(payload: &[u8]) -> &[u8] {
+    let mut payload = payload;
+
+    while let [rest @ .., b'\n' | b'\r' | b'\0'] = payload {
+        payload = rest;
+    }
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    use quickwit_actors::Universe;
+    use quickwit_config::{SourceConfig, SourceInputFormat, SourceParams, SyslogFormat};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use quickwit_metastore::metastore_for_test;
+    use quickwit_proto::types::IndexUid;
+
+    use super::*;
+    use crate::models::RawDocBatch;
+    use crate::source::SourceActor;
+
+    #[test]
+    fn test_next_tcp_frame() {
+        let mut buffer = BytesMut::from(&b"<13>hello\n\r\n<13>world\r\n12 <13>octet"[..]);
+        assert_eq!(next_tcp_frame(&mut buffer).unwrap().unwrap(), "<13>hello");
+        assert_eq!(next_tcp_frame(&mut buffer).unwrap().unwrap(), "<13>world");
+        assert!(next_tcp_frame(&mut buffer).unwrap().is_none());
+
+        buffer.extend_from_slice(b" et7 <13>bye");
+        assert_eq!(
+            next_tcp_frame(&mut buffer).unwrap().unwrap(),
+            "<13>octet et"
+        );
+        assert_eq!(next_tcp_frame(&mut buffer).unwrap().unwrap(), "<13>bye");
+        assert!(next_tcp_frame(&mut buffer).unwrap().is_none());
+        assert!(buffer.is_empty());
+
+        let mut buffer = BytesMut::from(&b"12345678901234567890"[..]);
+        next_tcp_frame(&mut buffer).unwrap_err();
+
+        let mut buffer = BytesMut::from(&b"999999 <13>too long"[..]);
+        next_tcp_frame(&mut buffer).unwrap_err();
+    }
+
+    #[test]
+    fn test_trim_line_ending() {
+        assert_eq!(trim_line_ending(b"foo\r\n"), b"foo");
+        assert_eq!(trim_line_ending(b"foo\n\0"), b"foo");
+        assert_eq!(trim_line_ending(b"\n"), b"");
+        assert_eq!(trim_line_ending(b"foo"), b"foo");
+    }
+
+    fn make_source_config(params: SyslogSourceParams) -> SourceConfig {
+        SourceConfig {
+            source_id: "test-syslog-source".to_string(),
+            desired_num_pipelines: NonZeroUsize::new(1).unwrap(),
+            max_num_pipelines_per_indexer: NonZeroUsize::new(1).unwrap(),
+            enabled: true,
+            source_params: SourceParams::Syslog(params),
+            transform_config: None,
+            input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
+        }
+    }
+
+    async fn wait_for_batches(
+        doc_processor_inbox: &quickwit_actors::Inbox<DocProcessor>,
+        num_docs: usize,
+    ) -> Vec<RawDocBatch> {
+        let mut batches = Vec::new();
+        let mut num_received_docs = 0;
+
+        for _ in 0..100 {
+            for batch in doc_processor_inbox.drain_for_test_typed::<RawDocBatch>() {
+                num_received_docs += batch.docs.len();
+                batches.push(batch);
+            }
+            if num_received_docs >= num_docs {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        batches
+    }
+
+    #[tokio::test]
+    async fn test_syslog_source_udp() {
+        let universe = Universe::new();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let params = SyslogSourceParams {
+            listen_address: "127.0.0.1:0".parse().unwrap(),
+            protocol: SyslogProtocol::Udp,
+            format: SyslogFormat::Auto,
+        };
+        let source_config = make_source_config(params.clone());
+        let index_uid = IndexUid::new_with_random_ulid("test-index");
+        let partition_id = PartitionId::from("test-node:127.0.0.1:0");
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(SourceCheckpointDelta::from_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                Position::offset(41u64),
+            ))
+            .unwrap();
+        let syslog_source = SyslogSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                index_uid,
+                source_config,
+                metastore_for_test(),
+                PathBuf::from("./queues"),
+            ),
+            params,
+            checkpoint,
+        )
+        .await
+        .unwrap();
+        let local_addr = syslog_source.local_addr;
+
+        let syslog_source_actor = SourceActor {
+            source: Box::new(syslog_source),
+            doc_processor_mailbox,
+        };
+        let (_syslog_source_mailbox, syslog_source_handle) =
+            universe.spawn_builder().spawn(syslog_source_actor);
+
+        let client_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for message in [
+            "<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - 'su root' failed",
+            "not a syslog message",
+            "<13>Feb  5 17:32:18 host app[42]: hello\n",
+        ] {
+            client_socket
+                .send_to(message.as_bytes(), local_addr)
+                .await
+                .unwrap();
+        }
+        let batches = wait_for_batches(&doc_processor_inbox, 2).await;
+        let docs: Vec<JsonValue> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter())
+            .map(|doc| serde_json::from_slice(doc).unwrap())
+            .collect();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0]["hostname"], "mymachine.example.com");
+        assert_eq!(docs[0]["facility"], "auth");
+        assert_eq!(docs[0]["severity"], "crit");
+        assert_eq!(docs[0]["msg"], "'su root' failed");
+        assert_eq!(docs[0]["source_ip"], "127.0.0.1");
+        assert_eq!(docs[1]["app_name"], "app");
+        assert_eq!(docs[1]["proc_id"], "42");
+        assert_eq!(docs[1]["msg"], "hello");
+
+        let first_delta = batches[0].checkpoint_delta.iter().next().unwrap();
+        assert_eq!(first_delta.0, partition_id);
+        assert_eq!(first_delta.1.from, Position::offset(41u64));
+
+        let observable_state = syslog_source_handle.observe().await.state;
+        assert_eq!(observable_state["num_messages_processed"], 3);
+        assert_eq!(observable_state["num_invalid_messages"], 1);
+        universe.assert_quit().await;
+    }
+
+    #[tokio::test]
+    async fn test_syslog_source_tcp() {
+        let universe = Universe::new();
+        let (doc_processor_mailbox, doc_processor_inbox) = universe.create_test_mailbox();
+        let params = SyslogSourceParams {
+            listen_address: "127.0.0.1:0".parse().unwrap(),
+            protocol: SyslogProtocol::Tcp,
+            format: SyslogFormat::Rfc3164,
+        };
+        let source_config = make_source_config(params.clone());
+        let syslog_source = SyslogSourceFactory::typed_create_source(
+            SourceRuntimeArgs::for_test(
+                IndexUid::new_with_random_ulid("test-index"),
+                source_config,
+                metastore_for_test(),
+                PathBuf::from("./queues"),
+            ),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap();
+        let local_addr = syslog_source.local_addr;
+
+        let syslog_source_actor = SourceActor {
+            source: Box::new(syslog_source),
+            doc_processor_mailbox,
+        };
+        let (_syslog_source_mailbox, _syslog_source_handle) =
+            universe.spawn_builder().spawn(syslog_source_actor);
+
+        let mut client_stream = TcpStream::connect(local_addr).await.unwrap();
+        tokio::io::AsyncWriteExt::write_all(
+            &mut client_stream,
+            b"<13>app: first\n16 <13>app: second\n<13>app: third",
+        )
+        .await
+        .unwrap();
+        drop(client_stream);
+
+        let batches = wait_for_batches(&doc_processor_inbox, 3).await;
+        let messages: Vec<String> = batches
+            .iter()
+            .flat_map(|batch| batch.docs.iter())
+            .map(|doc| {
+                let doc_json: JsonValue = serde_json::from_slice(doc).unwrap();
+                doc_json["msg"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(messages, ["first", "second", "third"]);
+
+        let first_delta = batches[0].checkpoint_delta.iter().next().unwrap();
+        assert_eq!(first_delta.1.from, Position::Beginning);
+        universe.assert_quit().await;
+    }
+}
@@ -0,0 +1,441 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Parsers for RFC 5424 and RFC 3164 (BSD) syslog messages.
+
+use anyhow::{bail, Context};
+use quickwit_config::SyslogFormat;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use time::OffsetDateTime;
+
+const FACILITY_NAMES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITY_NAMES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+const NIL_VALUE: &str = "-";
+
+/// Parses a syslog message into a JSON object with the following fields, omitted when missing
+/// from the message: `facility`, `severity`, `version`, `timestamp`, `hostname`, `app_name`,
+/// `proc_id`, `msg_id`, `structured_data`, and `msg`.
+pub(super) fn parse_syslog_message(
+    message: &[u8],
+    format: SyslogFormat,
+) -> anyhow::Result<JsonMap<String, JsonValue>> {
+    let message = String::from_utf8_lossy(message);
+    let (priority, rest) = parse_priority(&message)?;
+
+    let mut json_obj = JsonMap::new();
+    json_obj.insert(
+        "facility".to_string(),
+        JsonValue::from(FACILITY_NAMES[(priority / 8) as usize]),
+    );
+    json_obj.insert(
+        "severity".to_string(),
+        JsonValue::from(SEVERITY_NAMES[(priority % 8) as usize]),
+    );
+    let is_rfc_5424 = match format {
+        SyslogFormat::Auto => looks_like_rfc_5424(rest),
+        SyslogFormat::Rfc5424 => true,
+        SyslogFormat::Rfc3164 => false,
+    };
+    if is_rfc_5424 {
+        parse_rfc_5424(rest, &mut json_obj)?;
+    } else {
+        parse_rfc_3164(rest, OffsetDateTime::now_utc(), &mut json_obj)?;
+    }
+    Ok(json_obj)
+}
+
+/// Parses the `<PRI>` header shared by both formats.
+fn parse_priority(message: &str) -> anyhow::Result<(u8, &str)> {
+    let Some(rest) = message.strip_prefix('<') else {
+        bail!("syslog message must start with a priority, e.g. `<34>`");
+    };
+    let end = rest
+        .find('>')
+        .filter(|end| (1..=3).contains(end))
+        .context("invalid syslog priority")?;
+    let priority: u8 = rest[..end]
+        .parse()
+        .ok()
+        .filter(|priority| *priority < 192)
+        .context("invalid syslog priority")?;
+    Ok((priority, &rest[end + 1..]))
+}
+
+/// RFC 5424 messages carry a version number right after the priority.
+fn looks_like_rfc_5424(rest: &str) -> bool {
+    let mut chars = rest.chars();
+    matches!(chars.next(), Some('1'..='9')) && chars.next() == Some(' ')
+}
+
+// <PRI>VERSION SP TIMESTAMP SP HOSTNAME SP APP-NAME SP PROCID SP MSGID SP STRUCTURED-DATA [SP MSG]
+fn parse_rfc_5424(rest: &str, json_obj: &mut JsonMap<String, JsonValue>) -> anyhow::Result<()> {
+    let mut rest = rest;
+    let version = next_header_field(&mut rest).context("missing syslog version")?;
+    let version: u8 = version.parse().context("invalid syslog version")?;
+    json_obj.insert("version".to_string(), JsonValue::from(version));
+
+    for field_name in ["timestamp", "hostname", "app_name", "proc_id", "msg_id"] {
+        let field_value = next_header_field(&mut rest)
+            .with_context(|| format!("missing syslog header field `{field_name}`"))?;
+        if field_value != NIL_VALUE {
+            json_obj.insert(field_name.to_string(), JsonValue::from(field_value));
+        }
+    }
+    let (structured_data_opt, rest) = parse_structured_data(rest)?;
+    if let Some(structured_data) = structured_data_opt {
+        json_obj.insert(
+            "structured_data".to_string(),
+            JsonValue::Object(structured_data),
+        );
+    }
+    let msg = rest.strip_prefix(' ').unwrap_or(rest);
+    let msg = msg.strip_prefix('\u{feff}').unwrap_or(msg);
+    if !msg.is_empty() {
+        json_obj.insert("msg".to_string(), JsonValue::from(msg));
+    }
+    Ok(())
+}
+
+fn next_header_field<'a>(rest: &mut &'a str) -> Option<&'a str> {
+    let (field, remaining) = match rest.find(' ') {
+        Some(end) => (&rest[..end], &rest[end + 1..]),
+        None => (*rest, ""),
+    };
+    if field.is_empty() {
+        return None;
+    }
+    *rest = remaining;
+    Some(field)
+}
+
+type StructuredData = JsonMap<String, JsonValue>;
+
+/// Parses the structured data of an RFC 5424 message: `-` or a sequence of
+/// `[SD-ID PARAM-NAME="PARAM-VALUE" ...]` elements. Returns the parsed elements, keyed by SD-ID,
+/// and the rest of the message.
+fn parse_structured_data(rest: &str) -> anyhow::Result<(Option<StructuredData>, &str)> {
+    if let Some(rest) = rest.strip_prefix(NIL_VALUE) {
+        return Ok((None, rest));
+    }
+    if !rest.starts_with('[') {
+        bail!("invalid syslog structured data");
+    }
+    let mut structured_data = StructuredData::new();
+    let mut rest = rest;
+
+    while let Some(element) = rest.strip_prefix('[') {
+        let sd_id_end = element
+            .find([' ', ']'])
+            .context("unterminated syslog structured data element")?;
+        let sd_id = &element[..sd_id_end];
+        if sd_id.is_empty() {
+            bail!("syslog structured data element must have an ID");
+        }
+        let mut params = JsonMap::new();
+        let mut element = &element[sd_id_end..];
+
+        loop {
+            if let Some(remaining) = element.strip_prefix(']') {
+                rest = remaining;
+                break;
+            }
+            element = element
+                .strip_prefix(' ')
+                .context("invalid syslog structured data parameter")?;
+            let name_end = element
+                .find('=')
+                .context("invalid syslog structured data parameter")?;
+            let param_name = &element[..name_end];
+            let (param_value, remaining) = parse_param_value(&element[name_end + 1..])?;
+            params.insert(param_name.to_string(), JsonValue::from(param_value));
+            element = remaining;
+        }
+        structured_data.insert(sd_id.to_string(), JsonValue::Object(params));
+    }
+    Ok((Some(structured_data), rest))
+}
+
+/// Parses a quoted parameter value, unescaping `\"`, `\\`, and `\]`.
+fn parse_param_value(rest: &str) -> anyhow::Result<(String, &str)> {
+    let rest = rest
+        .strip_prefix('"')
+        .context("syslog structured data parameter value must be quoted")?;
+    let mut param_value = String::new();
+    let mut chars = rest.char_indices();
+
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' => return Ok((param_value, &rest[idx + 1..])),
+            '\\' => match chars.next() {
+                Some((_, escaped @ ('"' | '\\' | ']'))) => param_value.push(escaped),
+                Some((_, other)) => {
+                    param_value.push('\\');
+                    param_value.push(other);
+                }
+                None => break,
+            },
+            _ => param_value.push(ch),
+        }
+    }
+    bail!("unterminated syslog structured data parameter value")
+}
+
+// <PRI>TIMESTAMP SP HOSTNAME SP TAG[PID]: MSG
+//
+// RFC 3164 describes observed practice rather than a strict format, so this parser is lenient:
+// the timestamp, hostname, and tag are all optional.
+fn parse_rfc_3164(
+    rest: &str,
+    now: OffsetDateTime,
+    json_obj: &mut JsonMap<String, JsonValue>,
+) -> anyhow::Result<()> {
+    let mut rest = rest;
+
+    if let Some(timestamp) = rest
+        .get(..15)
+        .and_then(|prefix| parse_bsd_timestamp(prefix, now))
+    {
+        json_obj.insert("timestamp".to_string(), JsonValue::from(timestamp));
+        rest = rest[15..].trim_start_matches(' ');
+
+        // The hostname follows the timestamp, unless the next token is already the tag.
+        if let Some(end) = rest.find(' ') {
+            let token = &rest[..end];
+            if !token.ends_with(':') && !token.contains('[') {
+                json_obj.insert("hostname".to_string(), JsonValue::from(token));
+                rest = &rest[end + 1..];
+            }
+        }
+    }
+    let tag_end = rest
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.' | '/')))
+        .unwrap_or(rest.len());
+
+    if tag_end > 0 && matches!(rest[tag_end..].chars().next(), Some('[' | ':')) {
+        json_obj.insert("app_name".to_string(), JsonValue::from(&rest[..tag_end]));
+        rest = &rest[tag_end..];
+
+        if let Some(pid_and_rest) = rest.strip_prefix('[') {
+            if let Some(pid_end) = pid_and_rest.find(']') {
+                json_obj.insert(
+                    "proc_id".to_string(),
+                    JsonValue::from(&pid_and_rest[..pid_end]),
+                );
+                rest = &pid_and_rest[pid_end + 1..];
+            }
+        }
+        rest = rest.strip_prefix(':').unwrap_or(rest);
+        rest = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    if !rest.is_empty() {
+        json_obj.insert("msg".to_string(), JsonValue::from(rest));
+    }
+    Ok(())
+}
+
+/// Parses a `Mmm dd hh:mm:ss` timestamp into an RFC 3339 timestamp. BSD timestamps carry neither
+/// the year nor the timezone: we assume UTC and the current year, or the previous year for
+/// timestamps that would otherwise be more than a month in the future.
+fn parse_bsd_timestamp(timestamp: &str, now: OffsetDateTime) -> Option<String> {
+    if !timestamp.is_ascii() {
+        return None;
+    }
+    let month = MONTH_NAMES
+        .iter()
+        .position(|month_name| timestamp.starts_with(month_name))? as u8
+        + 1;
+    if timestamp.as_bytes()[3] != b' ' {
+        return None;
+    }
+    let day: u8 = timestamp[4..6].trim_start().parse().ok()?;
+    let time = &timestamp[7..15];
+    let time_bytes = time.as_bytes();
+
+    if !(1..=31).contains(&day)
+        || time_bytes[2] != b':'
+        || time_bytes[5] != b':'
+        || !time
+            .bytes()
+            .enumerate()
+            .all(|(idx, byte)| idx == 2 || idx == 5 || byte.is_ascii_digit())
+    {
+        return None;
+    }
+    let current_month = now.month() as u8;
+    let year = if month > current_month + 1 {
+        now.year() - 1
+    } else {
+        now.year()
+    };
+    Some(format!("{year:04}-{month:02}-{day:02}T{time}Z"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_priority() {
+        assert_eq!(parse_priority("<34>1 ...").unwrap(), (34, "1 ..."));
+        assert_eq!(parse_priority("<0>Oct").unwrap(), (0, "Oct"));
+        assert_eq!(parse_priority("<191>").unwrap(), (191, ""));
+        parse_priority("34>1").unwrap_err();
+        parse_priority("<>1").unwrap_err();
+        parse_priority("<192>1").unwrap_err();
+        parse_priority("<1234>1").unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_rfc_5424_message() {
+        let message = br#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Application" eventID="1011"][examplePriority@32473 class="high \"quoted\""] An application event log entry..."#;
+        let json_obj = parse_syslog_message(message, SyslogFormat::Auto).unwrap();
+        assert_eq!(
+            JsonValue::Object(json_obj),
+            json!({
+                "facility": "local4",
+                "severity": "notice",
+                "version": 1,
+                "timestamp": "2003-10-11T22:14:15.003Z",
+                "hostname": "mymachine.example.com",
+                "app_name": "evntslog",
+                "msg_id": "ID47",
+                "structured_data": {
+                    "exampleSDID@32473": {
+                        "iut": "3",
+                        "eventSource": "Application",
+                        "eventID": "1011",
+                    },
+                    "examplePriority@32473": {
+                        "class": "high \"quoted\"",
+                    },
+                },
+                "msg": "An application event log entry...",
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rfc_5424_message_with_nil_values() {
+        let message = "<34>1 - - - - - -".as_bytes();
+        let json_obj = parse_syslog_message(message, SyslogFormat::Rfc5424).unwrap();
+        assert_eq!(
+            JsonValue::Object(json_obj),
+            json!({
+                "facility": "auth",
+                "severity": "crit",
+                "version": 1,
+            })
+        );
+        let message = "<34>1 - host app 1234 - - \u{feff}hello".as_bytes();
+        let json_obj = parse_syslog_message(message, SyslogFormat::Rfc5424).unwrap();
+        assert_eq!(json_obj["proc_id"], "1234");
+        assert_eq!(json_obj["msg"], "hello");
+
+        parse_syslog_message(b"<34>1 - host app", SyslogFormat::Rfc5424).unwrap_err();
+        parse_syslog_message(b"<34>1 - - - - - [id", SyslogFormat::Rfc5424).unwrap_err();
+        parse_syslog_message(b"<34>1 - - - - - [id a=b]", SyslogFormat::Rfc5424).unwrap_err();
+    }
+
+    #[test]
+    fn test_parse_rfc_3164_message() {
+        let now = datetime!(2024-03-01 12:00 UTC);
+        let mut json_obj = JsonMap::new();
+        parse_rfc_3164(
+            "Feb  5 17:32:18 mymachine su[123]: 'su root' failed for lonvick on /dev/pts/8",
+            now,
+            &mut json_obj,
+        )
+        .unwrap();
+        assert_eq!(
+            JsonValue::Object(json_obj),
+            json!({
+                "timestamp": "2024-02-05T17:32:18Z",
+                "hostname": "mymachine",
+                "app_name": "su",
+                "proc_id": "123",
+                "msg": "'su root' failed for lonvick on /dev/pts/8",
+            })
+        );
+        let mut json_obj = JsonMap::new();
+        parse_rfc_3164("Dec 31 23:59:59 sshd: bye", now, &mut json_obj).unwrap();
+        assert_eq!(
+            JsonValue::Object(json_obj),
+            json!({
+                "timestamp": "2023-12-31T23:59:59Z",
+                "app_name": "sshd",
+                "msg": "bye",
+            })
+        );
+        let mut json_obj = JsonMap::new();
+        parse_rfc_3164("Use the BFG!", now, &mut json_obj).unwrap();
+        assert_eq!(JsonValue::Object(json_obj), json!({"msg": "Use the BFG!"}));
+    }
+
+    #[test]
+    fn test_parse_syslog_message_format_detection() {
+        let json_obj =
+            parse_syslog_message(b"<13>Feb  5 17:32:18 host app: hello", SyslogFormat::Auto)
+                .unwrap();
+        assert_eq!(json_obj["facility"], "user");
+        assert_eq!(json_obj["severity"], "notice");
+        assert_eq!(json_obj["hostname"], "host");
+        assert!(json_obj.get("version").is_none());
+
+        let json_obj = parse_syslog_message(b"<13>1 hello", SyslogFormat::Rfc3164).unwrap();
+        assert_eq!(json_obj["msg"], "1 hello");
+
+        parse_syslog_message(b"hello", SyslogFormat::Auto).unwrap_err();
+    }
+}
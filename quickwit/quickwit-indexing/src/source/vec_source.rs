@@ -164,6 +164,9 @@ mod tests {
             source_params: SourceParams::Vec(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let vec_source = VecSourceFactory::typed_create_source(
@@ -225,6 +228,9 @@ mod tests {
             source_params: SourceParams::Vec(params.clone()),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let vec_source = VecSourceFactory::typed_create_source(
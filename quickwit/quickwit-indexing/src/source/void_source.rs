@@ -94,6 +94,9 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let ctx = SourceRuntimeArgs::for_test(
@@ -120,6 +123,9 @@ mod tests {
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         let metastore = metastore_for_test();
         let void_source = VoidSourceFactory::typed_create_source(
@@ -28,6 +28,8 @@ use serde::Serialize;
 
 #[derive(Debug, Clone, thiserror::Error, Serialize)]
 pub enum IngestServiceError {
+    #[error("bad request: {0}")]
+    BadRequest(String),
     #[error("data corruption: {0}")]
     Corruption(String),
     #[error("index `{index_id}` already exists")]
@@ -111,6 +113,7 @@ impl From<tonic::Status> for IngestServiceError {
 impl ServiceError for IngestServiceError {
     fn error_code(&self) -> ServiceErrorCode {
         match self {
+            IngestServiceError::BadRequest(_) => ServiceErrorCode::BadRequest,
             IngestServiceError::Corruption(_) => ServiceErrorCode::Internal,
             IngestServiceError::IndexAlreadyExists { .. } => ServiceErrorCode::BadRequest,
             IngestServiceError::IndexNotFound { .. } => ServiceErrorCode::NotFound,
@@ -136,6 +139,7 @@ impl From<CorruptedKey> for IngestServiceError {
 impl From<IngestServiceError> for tonic::Status {
     fn from(error: IngestServiceError) -> tonic::Status {
         let code = match &error {
+            IngestServiceError::BadRequest(_) => tonic::Code::InvalidArgument,
             IngestServiceError::Corruption { .. } => tonic::Code::DataLoss,
             IngestServiceError::IndexAlreadyExists { .. } => tonic::Code::AlreadyExists,
             IngestServiceError::IndexNotFound { .. } => tonic::Code::NotFound,
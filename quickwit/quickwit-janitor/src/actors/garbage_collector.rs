@@ -114,6 +114,7 @@ impl GarbageCollector {
                     return None;
                 }
             };
+            let protected_split_ids = index.point_in_time_split_ids();
             let index_uid = index.index_uid;
             let gc_res = run_garbage_collect(
                 index_uid.clone(),
@@ -121,6 +122,7 @@ impl GarbageCollector {
                 metastore,
                 STAGED_GRACE_PERIOD,
                 DELETION_GRACE_PERIOD,
+                protected_split_ids,
                 false,
                 Some(ctx.progress()),
             ).await;
@@ -332,6 +334,7 @@ mod tests {
             MetastoreServiceClient::from(mock_metastore),
             STAGED_GRACE_PERIOD,
             DELETION_GRACE_PERIOD,
+            HashSet::new(),
             false,
             None,
         )
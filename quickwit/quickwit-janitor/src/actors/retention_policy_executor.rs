@@ -54,6 +54,9 @@ struct Loop;
 #[derive(Debug)]
 struct Execute {
     index_uid: IndexUid,
+    /// The generation of the chain of executions this execution belongs to. Executions of a
+    /// superseded chain are discarded.
+    generation: u64,
 }
 
 /// An actor for scheduling retention policy execution on all indexes.
@@ -65,6 +68,11 @@ pub struct RetentionPolicyExecutor {
     /// This act as local cache that is periodically updated while taking into
     /// account deleted indexes, updated or removed retention policy on indexes.
     index_configs: HashMap<String, IndexConfig>,
+    /// A map of index_id to the generation of the current chain of executions of the index.
+    execution_generations: HashMap<String, u64>,
+    /// The generation of the last chain of executions started. It is never reused, so that the
+    /// executions of an index that was removed from the cache and added back are discarded.
+    last_execution_generation: u64,
     counters: RetentionPolicyExecutorCounters,
 }
 
@@ -73,10 +81,36 @@ impl RetentionPolicyExecutor {
         Self {
             metastore,
             index_configs: HashMap::new(),
+            execution_generations: HashMap::new(),
+            last_execution_generation: 0,
             counters: RetentionPolicyExecutorCounters::default(),
         }
     }
 
+    /// Starts a new chain of executions for the index, superseding the pending executions of the
+    /// previous chain, if any.
+    fn schedule_first_execution(
+        &mut self,
+        index_uid: IndexUid,
+        next_interval: Duration,
+        ctx: &ActorContext<Self>,
+    ) {
+        self.last_execution_generation += 1;
+        self.execution_generations
+            .insert(index_uid.index_id.clone(), self.last_execution_generation);
+        let message = Execute {
+            index_uid,
+            generation: self.last_execution_generation,
+        };
+        ctx.schedule_self_msg(next_interval, message);
+    }
+
+    /// Removes the index from the cache. Its pending executions will be discarded.
+    fn remove_index(&mut self, index_id: &str) {
+        self.index_configs.remove(index_id);
+        self.execution_generations.remove(index_id);
+    }
+
     /// Indexes refresh Loop handler logic.
     /// Should not return an error to prevent the actor from crashing.
     async fn handle_refresh_loop(&mut self, ctx: &ActorContext<Self>) {
@@ -106,7 +140,7 @@ impl RetentionPolicyExecutor {
         if !deleted_indexes.is_empty() {
             debug!(index_ids=%deleted_indexes.iter().join(", "), "deleting indexes from cache");
             for index_id in deleted_indexes {
-                self.index_configs.remove(&index_id);
+                self.remove_index(&index_id);
             }
         }
 
@@ -120,25 +154,48 @@ impl RetentionPolicyExecutor {
                     // Remove the index from the cache if it exist.
                     // In case where the retention policy was removed this index might have
                     // been inserted in the cache from a previous iteration.
-                    self.index_configs.remove(&index_config.index_id);
+                    self.remove_index(&index_config.index_id);
                     continue;
                 }
             };
 
             // Insert or update the index in the cache.
             if let Some(value) = self.index_configs.get_mut(&index_config.index_id) {
+                let schedule_changed = value
+                    .retention_policy_opt
+                    .as_ref()
+                    .map(|cached_policy| &cached_policy.evaluation_schedule)
+                    != Some(&retention_policy.evaluation_schedule);
                 // Update the cache index entry in case the retention policy was updated.
                 *value = index_config;
+
+                if !schedule_changed {
+                    continue;
+                }
+                // The evaluation schedule was updated: we start a new chain of executions following
+                // the new schedule, which supersedes the pending one.
+                let retention_policy = value
+                    .retention_policy_opt
+                    .as_ref()
+                    .expect("index config should have a retention policy");
+                let index_id = value.index_id.clone();
+
+                if let Ok(next_interval) = retention_policy.duration_until_next_evaluation() {
+                    info!(index_id=?index_id, scheduled_in=?next_interval, "retention-policy-reschedule-operation");
+                    self.schedule_first_execution(index_uid, next_interval, ctx);
+                } else {
+                    self.remove_index(&index_id);
+                    error!(index_id=%index_id, "Couldn't extract the index next schedule time.")
+                }
                 continue;
             }
 
             if let Ok(next_interval) = retention_policy.duration_until_next_evaluation() {
-                let message = Execute { index_uid };
                 info!(index_id=?index_config.index_id, scheduled_in=?next_interval, "retention-policy-schedule-operation");
                 // Inserts & schedule the index's first retention policy execution.
                 self.index_configs
                     .insert(index_config.index_id.clone(), index_config);
-                ctx.schedule_self_msg(next_interval, message);
+                self.schedule_first_execution(index_uid, next_interval, ctx);
             } else {
                 error!(index_id=%index_config.index_id, "Couldn't extract the index next schedule time.")
             }
@@ -191,9 +248,6 @@ impl Handler<Execute> for RetentionPolicyExecutor {
         message: Execute,
         ctx: &ActorContext<Self>,
     ) -> Result<(), quickwit_actors::ActorExitStatus> {
-        info!(index_id=%message.index_uid.index_id, "retention-policy-execute-operation");
-        self.counters.num_execution_passes += 1;
-
         let index_config = match self.index_configs.get(&message.index_uid.index_id) {
             Some(config) => config,
             None => {
@@ -201,12 +255,19 @@ impl Handler<Execute> for RetentionPolicyExecutor {
                 return Ok(());
             }
         };
-
+        if self.execution_generations.get(&message.index_uid.index_id) != Some(&message.generation)
+        {
+            debug!(index_id=%message.index_uid.index_id, "the retention policy execution was superseded");
+            return Ok(());
+        }
         let retention_policy = index_config
             .retention_policy_opt
             .as_ref()
             .expect("Expected index to have retention policy configure.");
 
+        info!(index_id=%message.index_uid.index_id, "retention-policy-execute-operation");
+        self.counters.num_execution_passes += 1;
+
         let execution_result = run_execute_retention_policy(
             message.index_uid.clone(),
             self.metastore.clone(),
@@ -228,7 +289,7 @@ impl Handler<Execute> for RetentionPolicyExecutor {
             // Since we have failed to schedule next execution for this index,
             // we remove it from the cache for it to be retried next time it gets
             // added back by the RetentionPolicyExecutor cache refresh loop.
-            self.index_configs.remove(&message.index_uid.index_id);
+            self.remove_index(&message.index_uid.index_id);
             error!(index_id=%message.index_uid.index_id, "couldn't extract the index next schedule interval");
         }
         Ok(())
@@ -442,6 +503,40 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_retention_executor_discards_outdated_executions() -> anyhow::Result<()> {
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        mock_metastore
+            .expect_list_indexes_metadata()
+            .times(..)
+            .returning(|_list_indexes_request| {
+                let indexes_metadata = make_indexes(&[("index-1", Some("1 hour"))]);
+                Ok(
+                    ListIndexesMetadataResponse::try_from_indexes_metadata(indexes_metadata)
+                        .unwrap(),
+                )
+            });
+        mock_metastore.expect_list_splits().never();
+
+        let retention_policy_executor =
+            RetentionPolicyExecutor::new(MetastoreServiceClient::from(mock_metastore));
+        let universe = Universe::with_accelerated_time();
+        let (mailbox, handle) = universe.spawn_builder().spawn(retention_policy_executor);
+
+        // Generations start at 1, so this execution does not belong to the current chain.
+        let execute = Execute {
+            index_uid: IndexUid::for_test("index-1", 0),
+            generation: 0,
+        };
+        mailbox.ask(execute).await?;
+
+        let counters = handle.process_pending_and_observe().await.state;
+        assert_eq!(counters.num_execution_passes, 0);
+        universe.assert_quit().await;
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_retention_policy_execution_calls_dependencies() -> anyhow::Result<()> {
         let mut mock_metastore = MetastoreServiceClient::mock();
@@ -82,6 +82,7 @@ async fn create_empty_cluster(config: &NodeConfig) -> anyhow::Result<Cluster> {
         config.gossip_interval,
         FailureDetectorConfig::default(),
         &ChannelTransport::default(),
+        None,
     )
     .await?;
     Ok(cluster)
@@ -138,6 +139,9 @@ pub async fn ingest(args: IngestArgs) -> anyhow::Result<IndexingStatistics> {
         source_params,
         transform_config,
         input_format: args.input_format,
+        dead_letter_config: None,
+        multiline_config: None,
+        processors: Vec::new(),
     };
 
     let checklist_result = run_index_checklist(
@@ -23,8 +23,8 @@ use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 use quickwit_proto::control_plane::{ControlPlaneService, ControlPlaneServiceClient};
 use quickwit_proto::metastore::{
-    AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, CreateIndexRequest,
-    CreateIndexResponse, CreateIndexTemplateRequest, DeleteIndexRequest,
+    AcquireShardsRequest, AcquireShardsResponse, AddSourceRequest, ClosePointInTimeRequest,
+    CreateIndexRequest, CreateIndexResponse, CreateIndexTemplateRequest, DeleteIndexRequest,
     DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest, DeleteShardsResponse,
     DeleteSourceRequest, DeleteSplitsRequest, DeleteTask, EmptyResponse,
     FindIndexTemplateMatchesRequest, FindIndexTemplateMatchesResponse, GetIndexTemplateRequest,
@@ -34,9 +34,9 @@ use quickwit_proto::metastore::{
     ListIndexesMetadataRequest, ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse,
     ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest,
     MetastoreResult, MetastoreService, MetastoreServiceClient, MetastoreServiceStream,
-    OpenShardsRequest, OpenShardsResponse, PublishSplitsRequest, ResetSourceCheckpointRequest,
-    StageSplitsRequest, ToggleSourceRequest, UpdateSplitsDeleteOpstampRequest,
-    UpdateSplitsDeleteOpstampResponse,
+    OpenPointInTimeRequest, OpenShardsRequest, OpenShardsResponse, PublishSplitsRequest,
+    ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest,
+    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
 };
 
 /// A [`MetastoreService`] implementation that proxies some requests to the control plane so it can
@@ -124,6 +124,13 @@ impl MetastoreService for ControlPlaneMetastore {
         self.metastore.index_metadata(request).await
     }
 
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        self.metastore.update_index(request).await
+    }
+
     async fn list_indexes_metadata(
         &mut self,
         request: ListIndexesMetadataRequest,
@@ -159,6 +166,20 @@ impl MetastoreService for ControlPlaneMetastore {
         self.metastore.list_stale_splits(request).await
     }
 
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.open_point_in_time(request).await
+    }
+
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        self.metastore.close_point_in_time(request).await
+    }
+
     async fn mark_splits_for_deletion(
         &mut self,
         request: MarkSplitsForDeletionRequest,
@@ -30,7 +30,10 @@ use std::ops::Bound;
 
 use itertools::Itertools;
 use quickwit_common::PrettySample;
-use quickwit_config::{SourceConfig, INGEST_V2_SOURCE_ID};
+use quickwit_config::{
+    DocMapping, IndexingSettings, RetentionPolicy, SearchSettings, SourceConfig,
+    INGEST_V2_SOURCE_ID,
+};
 use quickwit_proto::metastore::{
     AcquireShardsSubrequest, AcquireShardsSubresponse, DeleteQuery, DeleteShardsSubrequest,
     DeleteTask, EntityKind, ListShardsSubrequest, ListShardsSubresponse, MetastoreError,
@@ -486,6 +489,25 @@ impl FileBackedIndex {
         Ok(())
     }
 
+    /// Replaces the doc mapping, the search settings, the retention policy, and the indexing
+    /// settings of the index. Returns whether a mutation occurred.
+    pub(crate) fn update_index_settings(
+        &mut self,
+        doc_mapping: DocMapping,
+        search_settings: SearchSettings,
+        retention_policy_opt: Option<RetentionPolicy>,
+        indexing_settings: IndexingSettings,
+    ) -> MetastoreResult<bool> {
+        let doc_mapping_mutated = self.metadata.set_doc_mapping(doc_mapping)?;
+        let search_settings_mutated = self.metadata.set_search_settings(search_settings);
+        let retention_policy_mutated = self.metadata.set_retention_policy(retention_policy_opt);
+        let indexing_settings_mutated = self.metadata.set_indexing_settings(indexing_settings);
+        Ok(doc_mapping_mutated
+            || search_settings_mutated
+            || retention_policy_mutated
+            || indexing_settings_mutated)
+    }
+
     /// Enables or disables a source. Returns whether a mutation occurred.
     pub(crate) fn toggle_source(&mut self, source_id: &str, enable: bool) -> MetastoreResult<bool> {
         self.metadata.toggle_source(source_id, enable)
@@ -501,6 +523,29 @@ impl FileBackedIndex {
         Ok(self.metadata.checkpoint.reset_source(source_id))
     }
 
+    /// Opens a point in time pinning the splits currently published, or extends its expiration
+    /// timestamp if it already exists. Returns whether a mutation occurred.
+    pub(crate) fn open_point_in_time(
+        &mut self,
+        point_in_time_id: &str,
+        expiration_timestamp: i64,
+    ) -> MetastoreResult<bool> {
+        let split_ids: Vec<SplitId> = self
+            .splits
+            .values()
+            .filter(|split| split.split_state == SplitState::Published)
+            .map(|split| split.split_id().to_string())
+            .collect();
+        Ok(self
+            .metadata
+            .open_point_in_time(point_in_time_id, split_ids, expiration_timestamp))
+    }
+
+    /// Closes a point in time. Returns whether a mutation occurred.
+    pub(crate) fn close_point_in_time(&mut self, point_in_time_id: &str) -> MetastoreResult<bool> {
+        self.metadata.close_point_in_time(point_in_time_id)
+    }
+
     /// Creates [`DeleteTask`] from a [`DeleteQuery`].
     pub(crate) fn create_delete_task(
         &mut self,
@@ -44,20 +44,20 @@ use quickwit_common::ServiceStream;
 use quickwit_config::IndexTemplate;
 use quickwit_proto::metastore::{
     serde_utils, AcquireShardsRequest, AcquireShardsResponse, AcquireShardsSubrequest,
-    AddSourceRequest, CreateIndexRequest, CreateIndexResponse, CreateIndexTemplateRequest,
-    DeleteIndexRequest, DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest,
-    DeleteShardsResponse, DeleteShardsSubrequest, DeleteSourceRequest, DeleteSplitsRequest,
-    DeleteTask, EmptyResponse, EntityKind, FindIndexTemplateMatchesRequest,
+    AddSourceRequest, ClosePointInTimeRequest, CreateIndexRequest, CreateIndexResponse,
+    CreateIndexTemplateRequest, DeleteIndexRequest, DeleteIndexTemplatesRequest, DeleteQuery,
+    DeleteShardsRequest, DeleteShardsResponse, DeleteShardsSubrequest, DeleteSourceRequest,
+    DeleteSplitsRequest, DeleteTask, EmptyResponse, EntityKind, FindIndexTemplateMatchesRequest,
     FindIndexTemplateMatchesResponse, GetIndexTemplateRequest, GetIndexTemplateResponse,
     IndexMetadataRequest, IndexMetadataResponse, IndexTemplateMatch, LastDeleteOpstampRequest,
     LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
     ListIndexTemplatesRequest, ListIndexTemplatesResponse, ListIndexesMetadataRequest,
     ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse, ListSplitsRequest,
     ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError,
-    MetastoreResult, MetastoreService, MetastoreServiceStream, OpenShardsRequest,
-    OpenShardsResponse, OpenShardsSubrequest, PublishSplitsRequest, ResetSourceCheckpointRequest,
-    StageSplitsRequest, ToggleSourceRequest, UpdateSplitsDeleteOpstampRequest,
-    UpdateSplitsDeleteOpstampResponse,
+    MetastoreResult, MetastoreService, MetastoreServiceStream, OpenPointInTimeRequest,
+    OpenShardsRequest, OpenShardsResponse, OpenShardsSubrequest, PublishSplitsRequest,
+    ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest, UpdateIndexRequest,
+    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
 };
 use quickwit_proto::types::{IndexId, IndexUid};
 use quickwit_storage::Storage;
@@ -74,7 +74,8 @@ use self::store_operations::{delete_index, index_exists, load_index, put_index};
 use super::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadataResponseExt,
     ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
-    PublishSplitsRequestExt, StageSplitsRequestExt, STREAM_SPLITS_CHUNK_SIZE,
+    PublishSplitsRequestExt, StageSplitsRequestExt, UpdateIndexRequestExt,
+    STREAM_SPLITS_CHUNK_SIZE,
 };
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::{IndexMetadata, ListSplitsQuery, MetastoreServiceExt, Split, SplitState};
@@ -457,6 +458,36 @@ impl MetastoreService for FileBackedMetastore {
         Ok(response)
     }
 
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let doc_mapping = request.deserialize_doc_mapping()?;
+        let search_settings = request.deserialize_search_settings()?;
+        let retention_policy_opt = request.deserialize_retention_policy()?;
+        let indexing_settings = request.deserialize_indexing_settings()?;
+        let index_uid = request.index_uid();
+
+        let index_metadata = self
+            .mutate(index_uid, |index| {
+                let mutation_occurred = index.update_index_settings(
+                    doc_mapping,
+                    search_settings,
+                    retention_policy_opt,
+                    indexing_settings,
+                )?;
+                let index_metadata = index.metadata().clone();
+
+                if mutation_occurred {
+                    Ok(MutationOccurred::Yes(index_metadata))
+                } else {
+                    Ok(MutationOccurred::No(index_metadata))
+                }
+            })
+            .await?;
+        IndexMetadataResponse::try_from_index_metadata(&index_metadata)
+    }
+
     async fn delete_index(
         &mut self,
         request: DeleteIndexRequest,
@@ -698,6 +729,36 @@ impl MetastoreService for FileBackedMetastore {
         ListSplitsResponse::try_from_splits(splits)
     }
 
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid = request.index_uid();
+
+        self.mutate(index_uid, |index| {
+            index
+                .open_point_in_time(&request.point_in_time_id, request.expiration_timestamp)
+                .map(MutationOccurred::from)
+        })
+        .await?;
+        Ok(EmptyResponse {})
+    }
+
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid = request.index_uid();
+
+        self.mutate(index_uid, |index| {
+            index
+                .close_point_in_time(&request.point_in_time_id)
+                .map(MutationOccurred::from)
+        })
+        .await?;
+        Ok(EmptyResponse {})
+    }
+
     async fn index_metadata(
         &mut self,
         request: IndexMetadataRequest,
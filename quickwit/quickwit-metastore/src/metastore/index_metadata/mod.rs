@@ -20,12 +20,15 @@
 pub(crate) mod serialize;
 
 use std::collections::hash_map::Entry;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use quickwit_common::uri::Uri;
-use quickwit_config::{IndexConfig, SourceConfig, TestableForRegression};
+use quickwit_config::{
+    validate_doc_mapping_update, DocMapping, IndexConfig, IndexingSettings, RetentionPolicy,
+    SearchSettings, SourceConfig, TestableForRegression,
+};
 use quickwit_proto::metastore::{EntityKind, MetastoreError, MetastoreResult};
-use quickwit_proto::types::{IndexUid, Position, SourceId};
+use quickwit_proto::types::{IndexUid, Position, SourceId, SplitId};
 use serde::{Deserialize, Serialize};
 use serialize::VersionedIndexMetadata;
 use time::OffsetDateTime;
@@ -48,6 +51,29 @@ pub struct IndexMetadata {
     pub create_timestamp: i64,
     /// Sources
     pub sources: HashMap<SourceId, SourceConfig>,
+    /// Version of the index doc mapping. It is incremented every time the doc mapping is
+    /// updated and recorded in the metadata of the splits built with that doc mapping.
+    pub doc_mapping_version: u64,
+    /// Points in time opened on the index, keyed by point in time id.
+    pub points_in_time: BTreeMap<String, PointInTime>,
+}
+
+/// A point in time pins the set of splits that were published when it was opened, so that
+/// successive searches performed against it observe a consistent view of the index. The pinned
+/// splits are not garbage collected until the point in time expires or is closed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PointInTime {
+    /// IDs of the splits pinned by the point in time.
+    pub split_ids: Vec<SplitId>,
+    /// Timestamp in seconds after which the point in time expires.
+    pub expiration_timestamp: i64,
+}
+
+impl PointInTime {
+    /// Returns whether the point in time has expired at the given timestamp.
+    pub fn is_expired(&self, now_timestamp: i64) -> bool {
+        self.expiration_timestamp <= now_timestamp
+    }
 }
 
 impl IndexMetadata {
@@ -65,6 +91,8 @@ impl IndexMetadata {
             checkpoint: Default::default(),
             create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
             sources: HashMap::default(),
+            doc_mapping_version: 0,
+            points_in_time: BTreeMap::default(),
         }
     }
 
@@ -99,6 +127,47 @@ impl IndexMetadata {
         &self.index_config().index_uri
     }
 
+    /// Replaces the doc mapping in the index config, returning whether a mutation occurred. The
+    /// doc mapping version is incremented whenever the doc mapping changes. Returns an error if
+    /// the new doc mapping is not a backward compatible evolution of the current one.
+    pub fn set_doc_mapping(&mut self, doc_mapping: DocMapping) -> MetastoreResult<bool> {
+        if self.index_config.doc_mapping == doc_mapping {
+            return Ok(false);
+        }
+        validate_doc_mapping_update(&self.index_config.doc_mapping, &doc_mapping).map_err(
+            |error| MetastoreError::InvalidArgument {
+                message: format!(
+                    "doc mapping of index `{}` cannot be updated: {error:#}",
+                    self.index_id()
+                ),
+            },
+        )?;
+        self.index_config.doc_mapping = doc_mapping;
+        self.doc_mapping_version += 1;
+        Ok(true)
+    }
+
+    /// Replaces the search settings in the index config, returning whether a mutation occurred.
+    pub fn set_search_settings(&mut self, search_settings: SearchSettings) -> bool {
+        let mutation_occurred = self.index_config.search_settings != search_settings;
+        self.index_config.search_settings = search_settings;
+        mutation_occurred
+    }
+
+    /// Replaces the retention policy in the index config, returning whether a mutation occurred.
+    pub fn set_retention_policy(&mut self, retention_policy_opt: Option<RetentionPolicy>) -> bool {
+        let mutation_occurred = self.index_config.retention_policy_opt != retention_policy_opt;
+        self.index_config.retention_policy_opt = retention_policy_opt;
+        mutation_occurred
+    }
+
+    /// Replaces the indexing settings in the index config, returning whether a mutation occurred.
+    pub fn set_indexing_settings(&mut self, indexing_settings: IndexingSettings) -> bool {
+        let mutation_occurred = self.index_config.indexing_settings != indexing_settings;
+        self.index_config.indexing_settings = indexing_settings;
+        mutation_occurred
+    }
+
     /// Adds a source to the index. Returns an error if the source already exists.
     pub fn add_source(&mut self, source_config: SourceConfig) -> MetastoreResult<()> {
         match self.sources.entry(source_config.source_id.clone()) {
@@ -137,6 +206,72 @@ impl IndexMetadata {
         self.checkpoint.remove_source(source_id);
         Ok(true)
     }
+
+    /// Returns the point in time identified by `point_in_time_id` if it exists and has not
+    /// expired.
+    pub fn point_in_time(&self, point_in_time_id: &str) -> Option<&PointInTime> {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        self.points_in_time
+            .get(point_in_time_id)
+            .filter(|point_in_time| !point_in_time.is_expired(now_timestamp))
+    }
+
+    /// Returns the IDs of the splits pinned by the points in time that have not expired yet.
+    pub fn point_in_time_split_ids(&self) -> HashSet<SplitId> {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        self.points_in_time
+            .values()
+            .filter(|point_in_time| !point_in_time.is_expired(now_timestamp))
+            .flat_map(|point_in_time| point_in_time.split_ids.iter().cloned())
+            .collect()
+    }
+
+    /// Opens a point in time pinning `split_ids`. If the point in time already exists, its
+    /// expiration timestamp is extended instead and the pinned splits are left untouched. Expired
+    /// points in time are pruned along the way. Returns whether a mutation occurred.
+    pub(crate) fn open_point_in_time(
+        &mut self,
+        point_in_time_id: &str,
+        split_ids: Vec<SplitId>,
+        expiration_timestamp: i64,
+    ) -> bool {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let num_points_in_time_before = self.points_in_time.len();
+        self.points_in_time
+            .retain(|_, point_in_time| !point_in_time.is_expired(now_timestamp));
+        let mut mutation_occurred = self.points_in_time.len() != num_points_in_time_before;
+
+        match self.points_in_time.get_mut(point_in_time_id) {
+            Some(point_in_time) => {
+                if point_in_time.expiration_timestamp < expiration_timestamp {
+                    point_in_time.expiration_timestamp = expiration_timestamp;
+                    mutation_occurred = true;
+                }
+            }
+            None => {
+                let point_in_time = PointInTime {
+                    split_ids,
+                    expiration_timestamp,
+                };
+                self.points_in_time
+                    .insert(point_in_time_id.to_string(), point_in_time);
+                mutation_occurred = true;
+            }
+        }
+        mutation_occurred
+    }
+
+    /// Closes a point in time. Returns an error if the point in time does not exist or has
+    /// already expired.
+    pub(crate) fn close_point_in_time(&mut self, point_in_time_id: &str) -> MetastoreResult<bool> {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        match self.points_in_time.remove(point_in_time_id) {
+            Some(point_in_time) if !point_in_time.is_expired(now_timestamp) => Ok(true),
+            _ => Err(MetastoreError::NotFound(EntityKind::PointInTime {
+                point_in_time_id: point_in_time_id.to_string(),
+            })),
+        }
+    }
 }
 
 impl TestableForRegression for IndexMetadata {
@@ -159,6 +294,8 @@ impl TestableForRegression for IndexMetadata {
             checkpoint,
             create_timestamp: 1789,
             sources: Default::default(),
+            doc_mapping_version: 1,
+            points_in_time: Default::default(),
         };
         index_metadata
             .add_source(SourceConfig::sample_for_regression())
@@ -171,5 +308,7 @@ impl TestableForRegression for IndexMetadata {
         assert_eq!(self.checkpoint, other.checkpoint);
         assert_eq!(self.create_timestamp, other.create_timestamp);
         assert_eq!(self.sources, other.sources);
+        assert_eq!(self.doc_mapping_version, other.doc_mapping_version);
+        assert_eq!(self.points_in_time, other.points_in_time);
     }
 }
@@ -17,13 +17,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use quickwit_config::{IndexConfig, SourceConfig};
 use quickwit_proto::types::IndexUid;
 use serde::{self, Deserialize, Serialize};
 
 use crate::checkpoint::IndexCheckpoint;
+use crate::metastore::index_metadata::PointInTime;
 use crate::split_metadata::utc_now_timestamp;
 use crate::IndexMetadata;
 
@@ -65,6 +66,8 @@ impl From<IndexMetadata> for IndexMetadataV0_7 {
             checkpoint: index_metadata.checkpoint,
             create_timestamp: index_metadata.create_timestamp,
             sources,
+            doc_mapping_version: index_metadata.doc_mapping_version,
+            points_in_time: index_metadata.points_in_time,
         }
     }
 }
@@ -83,6 +86,11 @@ pub(crate) struct IndexMetadataV0_7 {
     pub create_timestamp: i64,
     #[schema(value_type = Vec<VersionedSourceConfig>)]
     pub sources: Vec<SourceConfig>,
+    #[serde(default)]
+    pub doc_mapping_version: u64,
+    #[schema(value_type = Object)]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub points_in_time: BTreeMap<String, PointInTime>,
 }
 
 impl TryFrom<IndexMetadataV0_7> for IndexMetadata {
@@ -106,6 +114,8 @@ impl TryFrom<IndexMetadataV0_7> for IndexMetadata {
             checkpoint: v0_6.checkpoint,
             create_timestamp: v0_6.create_timestamp,
             sources,
+            doc_mapping_version: v0_6.doc_mapping_version,
+            points_in_time: v0_6.points_in_time,
         })
     }
 }
@@ -28,17 +28,19 @@ use std::ops::{Bound, RangeInclusive};
 
 use async_trait::async_trait;
 use futures::TryStreamExt;
-pub use index_metadata::IndexMetadata;
+pub use index_metadata::{IndexMetadata, PointInTime};
 use itertools::Itertools;
 use once_cell::sync::Lazy;
 use quickwit_common::tower::PrometheusMetricsLayer;
-use quickwit_config::{IndexConfig, SourceConfig};
+use quickwit_config::{
+    DocMapping, IndexConfig, IndexingSettings, RetentionPolicy, SearchSettings, SourceConfig,
+};
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
 use quickwit_proto::metastore::{
     serde_utils, AddSourceRequest, CreateIndexRequest, CreateIndexResponse, DeleteTask,
     IndexMetadataRequest, IndexMetadataResponse, ListIndexesMetadataResponse, ListSplitsRequest,
     ListSplitsResponse, MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceClient,
-    MetastoreServiceStream, PublishSplitsRequest, StageSplitsRequest,
+    MetastoreServiceStream, PublishSplitsRequest, StageSplitsRequest, UpdateIndexRequest,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use time::OffsetDateTime;
@@ -156,6 +158,79 @@ impl CreateIndexRequestExt for CreateIndexRequest {
     }
 }
 
+/// Helper trait to build a [`UpdateIndexRequest`] and deserialize its payload.
+pub trait UpdateIndexRequestExt {
+    /// Creates a new [`UpdateIndexRequest`] from the different updated fields.
+    fn try_from_updates(
+        index_uid: impl Into<IndexUid>,
+        doc_mapping: &DocMapping,
+        search_settings: &SearchSettings,
+        retention_policy_opt: &Option<RetentionPolicy>,
+        indexing_settings: &IndexingSettings,
+    ) -> MetastoreResult<UpdateIndexRequest>;
+
+    /// Deserializes the `doc_mapping_json` field of an [`UpdateIndexRequest`] into a
+    /// [`DocMapping`] object.
+    fn deserialize_doc_mapping(&self) -> MetastoreResult<DocMapping>;
+
+    /// Deserializes the `search_settings_json` field of an [`UpdateIndexRequest`] into a
+    /// [`SearchSettings`] object.
+    fn deserialize_search_settings(&self) -> MetastoreResult<SearchSettings>;
+
+    /// Deserializes the `retention_policy_json` field of an [`UpdateIndexRequest`] into a
+    /// [`RetentionPolicy`] object.
+    fn deserialize_retention_policy(&self) -> MetastoreResult<Option<RetentionPolicy>>;
+
+    /// Deserializes the `indexing_settings_json` field of an [`UpdateIndexRequest`] into a
+    /// [`IndexingSettings`] object.
+    fn deserialize_indexing_settings(&self) -> MetastoreResult<IndexingSettings>;
+}
+
+impl UpdateIndexRequestExt for UpdateIndexRequest {
+    fn try_from_updates(
+        index_uid: impl Into<IndexUid>,
+        doc_mapping: &DocMapping,
+        search_settings: &SearchSettings,
+        retention_policy_opt: &Option<RetentionPolicy>,
+        indexing_settings: &IndexingSettings,
+    ) -> MetastoreResult<UpdateIndexRequest> {
+        let doc_mapping_json = serde_utils::to_json_str(doc_mapping)?;
+        let search_settings_json = serde_utils::to_json_str(search_settings)?;
+        let retention_policy_json = retention_policy_opt
+            .as_ref()
+            .map(serde_utils::to_json_str)
+            .transpose()?;
+        let indexing_settings_json = serde_utils::to_json_str(indexing_settings)?;
+        let update_request = UpdateIndexRequest {
+            index_uid: index_uid.into().into(),
+            search_settings_json,
+            retention_policy_json,
+            indexing_settings_json,
+            doc_mapping_json,
+        };
+        Ok(update_request)
+    }
+
+    fn deserialize_doc_mapping(&self) -> MetastoreResult<DocMapping> {
+        serde_utils::from_json_str(&self.doc_mapping_json)
+    }
+
+    fn deserialize_search_settings(&self) -> MetastoreResult<SearchSettings> {
+        serde_utils::from_json_str(&self.search_settings_json)
+    }
+
+    fn deserialize_retention_policy(&self) -> MetastoreResult<Option<RetentionPolicy>> {
+        self.retention_policy_json
+            .as_ref()
+            .map(|retention_policy_json| serde_utils::from_json_str(retention_policy_json))
+            .transpose()
+    }
+
+    fn deserialize_indexing_settings(&self) -> MetastoreResult<IndexingSettings> {
+        serde_utils::from_json_str(&self.indexing_settings_json)
+    }
+}
+
 /// Helper trait to deserialize the payload of a [`CreateIndexResponse`].
 pub trait CreateIndexResponseExt {
     /// Deserializes the `index_metadata_json` field of a [`CreateIndexResponse`] into an
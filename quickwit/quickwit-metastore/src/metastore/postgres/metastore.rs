@@ -31,22 +31,23 @@ use quickwit_config::{
 use quickwit_proto::ingest::{Shard, ShardState};
 use quickwit_proto::metastore::{
     serde_utils, AcquireShardsRequest, AcquireShardsResponse, AcquireShardsSubresponse,
-    AddSourceRequest, CreateIndexRequest, CreateIndexResponse, CreateIndexTemplateRequest,
-    DeleteIndexRequest, DeleteIndexTemplatesRequest, DeleteQuery, DeleteShardsRequest,
-    DeleteShardsResponse, DeleteSourceRequest, DeleteSplitsRequest, DeleteTask, EmptyResponse,
-    EntityKind, FindIndexTemplateMatchesRequest, FindIndexTemplateMatchesResponse,
-    GetIndexTemplateRequest, GetIndexTemplateResponse, IndexMetadataRequest, IndexMetadataResponse,
-    IndexTemplateMatch, LastDeleteOpstampRequest, LastDeleteOpstampResponse,
-    ListDeleteTasksRequest, ListDeleteTasksResponse, ListIndexTemplatesRequest,
-    ListIndexTemplatesResponse, ListIndexesMetadataRequest, ListIndexesMetadataResponse,
-    ListShardsRequest, ListShardsResponse, ListShardsSubresponse, ListSplitsRequest,
-    ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest, MetastoreError,
-    MetastoreResult, MetastoreService, MetastoreServiceStream, OpenShardsRequest,
-    OpenShardsResponse, OpenShardsSubrequest, OpenShardsSubresponse, PublishSplitsRequest,
-    ResetSourceCheckpointRequest, StageSplitsRequest, ToggleSourceRequest,
-    UpdateSplitsDeleteOpstampRequest, UpdateSplitsDeleteOpstampResponse,
+    AddSourceRequest, ClosePointInTimeRequest, CreateIndexRequest, CreateIndexResponse,
+    CreateIndexTemplateRequest, DeleteIndexRequest, DeleteIndexTemplatesRequest, DeleteQuery,
+    DeleteShardsRequest, DeleteShardsResponse, DeleteSourceRequest, DeleteSplitsRequest,
+    DeleteTask, EmptyResponse, EntityKind, FindIndexTemplateMatchesRequest,
+    FindIndexTemplateMatchesResponse, GetIndexTemplateRequest, GetIndexTemplateResponse,
+    IndexMetadataRequest, IndexMetadataResponse, IndexTemplateMatch, LastDeleteOpstampRequest,
+    LastDeleteOpstampResponse, ListDeleteTasksRequest, ListDeleteTasksResponse,
+    ListIndexTemplatesRequest, ListIndexTemplatesResponse, ListIndexesMetadataRequest,
+    ListIndexesMetadataResponse, ListShardsRequest, ListShardsResponse, ListShardsSubresponse,
+    ListSplitsRequest, ListSplitsResponse, ListStaleSplitsRequest, MarkSplitsForDeletionRequest,
+    MetastoreError, MetastoreResult, MetastoreService, MetastoreServiceStream,
+    OpenPointInTimeRequest, OpenShardsRequest, OpenShardsResponse, OpenShardsSubrequest,
+    OpenShardsSubresponse, PublishSplitsRequest, ResetSourceCheckpointRequest, StageSplitsRequest,
+    ToggleSourceRequest, UpdateIndexRequest, UpdateSplitsDeleteOpstampRequest,
+    UpdateSplitsDeleteOpstampResponse,
 };
-use quickwit_proto::types::{IndexId, IndexUid, Position, PublishToken, SourceId};
+use quickwit_proto::types::{IndexId, IndexUid, Position, PublishToken, SourceId, SplitId};
 use sea_query::{Asterisk, PostgresQueryBuilder, Query};
 use sea_query_binder::SqlxBinder;
 use sqlx::{Executor, Pool, Postgres, Transaction};
@@ -65,7 +66,7 @@ use crate::metastore::{PublishSplitsRequestExt, STREAM_SPLITS_CHUNK_SIZE};
 use crate::{
     AddSourceRequestExt, CreateIndexRequestExt, IndexMetadata, IndexMetadataResponseExt,
     ListIndexesMetadataResponseExt, ListSplitsRequestExt, ListSplitsResponseExt,
-    MetastoreServiceExt, Split, SplitState, StageSplitsRequestExt,
+    MetastoreServiceExt, Split, SplitState, StageSplitsRequestExt, UpdateIndexRequestExt,
 };
 
 /// PostgreSQL metastore implementation.
@@ -395,6 +396,38 @@ impl MetastoreService for PostgresqlMetastore {
         Ok(response)
     }
 
+    #[instrument(skip_all, fields(index_id=%request.index_uid()))]
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> MetastoreResult<IndexMetadataResponse> {
+        let doc_mapping = request.deserialize_doc_mapping()?;
+        let search_settings = request.deserialize_search_settings()?;
+        let retention_policy_opt = request.deserialize_retention_policy()?;
+        let indexing_settings = request.deserialize_indexing_settings()?;
+        let index_uid: IndexUid = request.index_uid().clone();
+
+        let index_metadata = run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid.clone(), |index_metadata| {
+                let doc_mapping_mutated = index_metadata.set_doc_mapping(doc_mapping)?;
+                let search_settings_mutated = index_metadata.set_search_settings(search_settings);
+                let retention_policy_mutated =
+                    index_metadata.set_retention_policy(retention_policy_opt);
+                let indexing_settings_mutated =
+                    index_metadata.set_indexing_settings(indexing_settings);
+                Ok::<_, MetastoreError>(
+                    doc_mapping_mutated
+                        || search_settings_mutated
+                        || retention_policy_mutated
+                        || indexing_settings_mutated,
+                )
+            })
+            .await?;
+            index_metadata(tx, &index_uid.index_id).await
+        })?;
+        IndexMetadataResponse::try_from_index_metadata(&index_metadata)
+    }
+
     #[instrument(skip_all, fields(index_id=%request.index_uid()))]
     async fn delete_index(
         &mut self,
@@ -1171,6 +1204,56 @@ impl MetastoreService for PostgresqlMetastore {
         Ok(response)
     }
 
+    #[instrument(skip(self))]
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid().clone();
+        run_with_tx!(self.connection_pool, tx, {
+            let published_split_ids: Vec<SplitId> = sqlx::query_scalar(
+                r#"
+                SELECT split_id
+                FROM splits
+                WHERE
+                    index_uid = $1
+                    AND split_state = $2
+                "#,
+            )
+            .bind(index_uid.to_string())
+            .bind(SplitState::Published.as_str())
+            .fetch_all(tx.as_mut())
+            .await?;
+
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                Ok::<_, MetastoreError>(index_metadata.open_point_in_time(
+                    &request.point_in_time_id,
+                    published_split_ids,
+                    request.expiration_timestamp,
+                ))
+            })
+            .await?;
+            Ok(())
+        })?;
+        Ok(EmptyResponse {})
+    }
+
+    #[instrument(skip(self))]
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> MetastoreResult<EmptyResponse> {
+        let index_uid: IndexUid = request.index_uid().clone();
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_uid, |index_metadata| {
+                index_metadata.close_point_in_time(&request.point_in_time_id)
+            })
+            .await?;
+            Ok(())
+        })?;
+        Ok(EmptyResponse {})
+    }
+
     async fn open_shards(
         &mut self,
         request: OpenShardsRequest,
@@ -91,6 +91,10 @@ pub(crate) struct SplitMetadataV0_7 {
 
     #[serde(default)]
     num_merge_ops: usize,
+
+    /// Version of the index doc mapping this split was built with.
+    #[serde(default)]
+    doc_mapping_version: u64,
 }
 
 impl From<SplitMetadataV0_7> for SplitMetadata {
@@ -126,6 +130,7 @@ impl From<SplitMetadataV0_7> for SplitMetadata {
             tags: v6.tags,
             footer_offsets: v6.footer_offsets,
             num_merge_ops: v6.num_merge_ops,
+            doc_mapping_version: v6.doc_mapping_version,
         }
     }
 }
@@ -147,6 +152,7 @@ impl From<SplitMetadata> for SplitMetadataV0_7 {
             tags: split.tags,
             footer_offsets: split.footer_offsets,
             num_merge_ops: split.num_merge_ops,
+            doc_mapping_version: split.doc_mapping_version,
         }
     }
 }
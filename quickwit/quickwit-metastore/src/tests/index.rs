@@ -20,24 +20,34 @@
 // Index API tests
 //
 //  - create_index
+//  - update_index
 //  - index_exists
 //  - index_metadata
 //  - list_indexes
 //  - delete_index
+//  - open_point_in_time
+//  - close_point_in_time
+
+use std::collections::HashSet;
 
 use quickwit_common::rand::append_random_suffix;
-use quickwit_config::{IndexConfig, SourceConfig, CLI_SOURCE_ID, INGEST_V2_SOURCE_ID};
+use quickwit_config::{
+    IndexConfig, IndexingSettings, RetentionPolicy, SearchSettings, SourceConfig, CLI_SOURCE_ID,
+    INGEST_V2_SOURCE_ID,
+};
 use quickwit_proto::metastore::{
-    CreateIndexRequest, DeleteIndexRequest, EntityKind, IndexMetadataRequest,
-    ListIndexesMetadataRequest, MetastoreError, MetastoreService, StageSplitsRequest,
+    ClosePointInTimeRequest, CreateIndexRequest, DeleteIndexRequest, EntityKind,
+    IndexMetadataRequest, ListIndexesMetadataRequest, MetastoreError, MetastoreService,
+    OpenPointInTimeRequest, PublishSplitsRequest, StageSplitsRequest, UpdateIndexRequest,
 };
 use quickwit_proto::types::IndexUid;
+use time::OffsetDateTime;
 
 use super::DefaultForTest;
 use crate::tests::cleanup_index;
 use crate::{
     CreateIndexRequestExt, IndexMetadataResponseExt, ListIndexesMetadataResponseExt,
-    MetastoreServiceExt, SplitMetadata, StageSplitsRequestExt,
+    MetastoreServiceExt, SplitMetadata, StageSplitsRequestExt, UpdateIndexRequestExt,
 };
 
 pub async fn test_metastore_create_index<
@@ -146,6 +156,168 @@ pub async fn test_metastore_create_index_enforces_index_id_maximum_length<
     cleanup_index(&mut metastore, index_uid).await;
 }
 
+pub async fn test_metastore_update_index<
+    MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-update-index");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+
+    let new_search_settings = SearchSettings {
+        default_search_fields: vec!["body".to_string(), "owner".to_string()],
+    };
+    let new_retention_policy_opt = Some(RetentionPolicy {
+        retention_period: "1 week".to_string(),
+        evaluation_schedule: "daily".to_string(),
+    });
+    let new_indexing_settings = IndexingSettings {
+        commit_timeout_secs: 5,
+        ..index_config.indexing_settings.clone()
+    };
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.doc_mapping,
+        &new_search_settings,
+        &new_retention_policy_opt,
+        &new_indexing_settings,
+    )
+    .unwrap();
+    let updated_index_metadata = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+
+    assert_eq!(updated_index_metadata.index_uid, index_uid);
+    assert_eq!(
+        updated_index_metadata.index_config.search_settings,
+        new_search_settings
+    );
+    assert_eq!(
+        updated_index_metadata.index_config.retention_policy_opt,
+        new_retention_policy_opt
+    );
+    assert_eq!(
+        updated_index_metadata.index_config.indexing_settings,
+        new_indexing_settings
+    );
+    assert_eq!(
+        updated_index_metadata.index_config.doc_mapping,
+        index_config.doc_mapping
+    );
+    assert_eq!(updated_index_metadata.doc_mapping_version, 0);
+
+    let index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(index_metadata, updated_index_metadata);
+
+    // Updating the index with the same settings is a no-op.
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.doc_mapping,
+        &new_search_settings,
+        &new_retention_policy_opt,
+        &new_indexing_settings,
+    )
+    .unwrap();
+    let index_metadata = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(index_metadata, updated_index_metadata);
+
+    // Removing the retention policy.
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.doc_mapping,
+        &new_search_settings,
+        &None,
+        &new_indexing_settings,
+    )
+    .unwrap();
+    let index_metadata = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(index_metadata.index_config.retention_policy_opt.is_none());
+
+    // Updating the doc mapping bumps the doc mapping version.
+    let mut new_doc_mapping = index_config.doc_mapping.clone();
+    new_doc_mapping.field_mappings.push(
+        serde_json::from_str(r#"{"name": "severity", "type": "text", "tokenizer": "raw"}"#)
+            .unwrap(),
+    );
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &new_doc_mapping,
+        &new_search_settings,
+        &None,
+        &new_indexing_settings,
+    )
+    .unwrap();
+    let index_metadata = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert_eq!(index_metadata.index_config.doc_mapping, new_doc_mapping);
+    assert_eq!(index_metadata.doc_mapping_version, 1);
+
+    // Removing a field is not a backward compatible doc mapping update.
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        index_uid.clone(),
+        &index_config.doc_mapping,
+        &new_search_settings,
+        &None,
+        &new_indexing_settings,
+    )
+    .unwrap();
+    let error = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, MetastoreError::InvalidArgument { .. }));
+
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        IndexUid::new_with_random_ulid(&index_id),
+        &index_config.doc_mapping,
+        &new_search_settings,
+        &None,
+        &new_indexing_settings,
+    )
+    .unwrap();
+    let error = metastore
+        .update_index(update_index_request)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::NotFound(EntityKind::Index { .. })
+    ));
+
+    cleanup_index(&mut metastore, index_uid).await;
+}
+
 pub async fn test_metastore_index_exists<
     MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
 >() {
@@ -412,3 +584,152 @@ pub async fn test_metastore_delete_index<
 
     cleanup_index(&mut metastore, index_uid).await;
 }
+
+pub async fn test_metastore_open_close_point_in_time<
+    MetastoreToTest: MetastoreService + MetastoreServiceExt + DefaultForTest,
+>() {
+    let mut metastore = MetastoreToTest::default_for_test().await;
+
+    let index_id = append_random_suffix("test-open-close-point-in-time");
+    let index_uri = format!("ram:///indexes/{index_id}");
+    let index_config = IndexConfig::for_test(&index_id, &index_uri);
+
+    let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+    let error = metastore
+        .open_point_in_time(OpenPointInTimeRequest {
+            index_uid: Some(IndexUid::new_with_random_ulid("index-not-found")),
+            point_in_time_id: "pit-1".to_string(),
+            expiration_timestamp: now_timestamp + 60,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::NotFound(EntityKind::Index { .. })
+    ));
+
+    let create_index_request = CreateIndexRequest::try_from_index_config(&index_config).unwrap();
+    let index_uid: IndexUid = metastore
+        .create_index(create_index_request)
+        .await
+        .unwrap()
+        .index_uid()
+        .clone();
+
+    let split_id_1 = format!("{index_id}--split-1");
+    let split_metadata_1 = SplitMetadata {
+        split_id: split_id_1.clone(),
+        index_uid: index_uid.clone(),
+        ..Default::default()
+    };
+    let split_id_2 = format!("{index_id}--split-2");
+    let split_metadata_2 = SplitMetadata {
+        split_id: split_id_2.clone(),
+        index_uid: index_uid.clone(),
+        ..Default::default()
+    };
+    let stage_splits_request = StageSplitsRequest::try_from_splits_metadata(
+        index_uid.clone(),
+        [split_metadata_1, split_metadata_2],
+    )
+    .unwrap();
+    metastore.stage_splits(stage_splits_request).await.unwrap();
+
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid.clone()),
+        staged_split_ids: vec![split_id_1.clone()],
+        ..Default::default()
+    };
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    metastore
+        .open_point_in_time(OpenPointInTimeRequest {
+            index_uid: Some(index_uid.clone()),
+            point_in_time_id: "pit-1".to_string(),
+            expiration_timestamp: now_timestamp + 60,
+        })
+        .await
+        .unwrap();
+
+    // Splits published after the point in time was opened are not pinned.
+    let publish_splits_request = PublishSplitsRequest {
+        index_uid: Some(index_uid.clone()),
+        staged_split_ids: vec![split_id_2.clone()],
+        ..Default::default()
+    };
+    metastore
+        .publish_splits(publish_splits_request)
+        .await
+        .unwrap();
+
+    // Reopening an existing point in time extends its expiration timestamp.
+    metastore
+        .open_point_in_time(OpenPointInTimeRequest {
+            index_uid: Some(index_uid.clone()),
+            point_in_time_id: "pit-1".to_string(),
+            expiration_timestamp: now_timestamp + 120,
+        })
+        .await
+        .unwrap();
+
+    // Expired points in time are ignored.
+    metastore
+        .open_point_in_time(OpenPointInTimeRequest {
+            index_uid: Some(index_uid.clone()),
+            point_in_time_id: "pit-2".to_string(),
+            expiration_timestamp: now_timestamp - 1,
+        })
+        .await
+        .unwrap();
+
+    let index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+
+    let point_in_time = index_metadata.point_in_time("pit-1").unwrap();
+    assert_eq!(point_in_time.split_ids, vec![split_id_1.clone()]);
+    assert_eq!(point_in_time.expiration_timestamp, now_timestamp + 120);
+
+    assert!(index_metadata.point_in_time("pit-2").is_none());
+    assert_eq!(
+        index_metadata.point_in_time_split_ids(),
+        HashSet::from_iter([split_id_1])
+    );
+
+    metastore
+        .close_point_in_time(ClosePointInTimeRequest {
+            index_uid: Some(index_uid.clone()),
+            point_in_time_id: "pit-1".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let error = metastore
+        .close_point_in_time(ClosePointInTimeRequest {
+            index_uid: Some(index_uid.clone()),
+            point_in_time_id: "pit-1".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        MetastoreError::NotFound(EntityKind::PointInTime { .. })
+    ));
+
+    let index_metadata = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await
+        .unwrap()
+        .deserialize_index_metadata()
+        .unwrap();
+    assert!(index_metadata.point_in_time_split_ids().is_empty());
+
+    cleanup_index(&mut metastore, index_uid).await;
+}
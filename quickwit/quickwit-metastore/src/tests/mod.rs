@@ -201,6 +201,12 @@ macro_rules! metastore_test_suite {
                 .await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_update_index() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::index::test_metastore_update_index::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_index_exists() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -231,6 +237,13 @@ macro_rules! metastore_test_suite {
                 $crate::tests::index::test_metastore_delete_index::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_open_close_point_in_time() {
+                let _ = tracing_subscriber::fmt::try_init();
+                $crate::tests::index::test_metastore_open_close_point_in_time::<$metastore_type>()
+                    .await;
+            }
+
             // Split API tests
             //
             //  - stage_splits
@@ -61,6 +61,9 @@ pub async fn test_metastore_add_source<MetastoreToTest: MetastoreServiceExt + De
         source_params: SourceParams::void(),
         transform_config: None,
         input_format: SourceInputFormat::Json,
+        dead_letter_config: None,
+        multiline_config: None,
+        processors: Vec::new(),
     };
 
     assert_eq!(
@@ -161,6 +164,9 @@ pub async fn test_metastore_toggle_source<MetastoreToTest: MetastoreServiceExt +
         source_params: SourceParams::void(),
         transform_config: None,
         input_format: SourceInputFormat::Json,
+        dead_letter_config: None,
+        multiline_config: None,
+        processors: Vec::new(),
     };
     let add_source_request =
         AddSourceRequest::try_from_source_config(index_uid.clone(), &source).unwrap();
@@ -228,6 +234,9 @@ pub async fn test_metastore_delete_source<MetastoreToTest: MetastoreServiceExt +
         source_params: SourceParams::void(),
         transform_config: None,
         input_format: SourceInputFormat::Json,
+        dead_letter_config: None,
+        multiline_config: None,
+        processors: Vec::new(),
     };
 
     let index_config = IndexConfig::for_test(&index_id, index_uri.as_str());
@@ -350,6 +359,9 @@ pub async fn test_metastore_reset_checkpoint<
             source_params: SourceParams::void(),
             transform_config: None,
             input_format: SourceInputFormat::Json,
+            dead_letter_config: None,
+            multiline_config: None,
+            processors: Vec::new(),
         };
         metastore
             .add_source(
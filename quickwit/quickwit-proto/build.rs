@@ -140,7 +140,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .type_attribute(".", "#[derive(Serialize, Deserialize, utoipa::ToSchema)]")
         .type_attribute("PartialHit", "#[derive(Eq, Hash)]")
         .type_attribute("PartialHit.sort_value", "#[derive(Copy)]")
+        .field_attribute(
+            "PartialHit.additional_sort_values",
+            "#[serde(default, skip_serializing_if = \"Vec::is_empty\")]",
+        )
         .type_attribute("SearchRequest", "#[derive(Eq, Hash)]")
+        .type_attribute("SnippetOptions", "#[derive(Eq, Hash)]")
+        .type_attribute("SourceFilter", "#[derive(Eq, Hash)]")
         .type_attribute("ListFieldSerialized", "#[derive(Eq)]")
         .type_attribute("SortByValue", "#[derive(Ord, PartialOrd)]")
         .type_attribute("SortField", "#[derive(Eq, Hash)]")
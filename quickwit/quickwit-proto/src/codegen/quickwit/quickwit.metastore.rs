@@ -23,6 +23,21 @@ pub struct CreateIndexResponse {
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateIndexRequest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub search_settings_json: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "3")]
+    pub retention_policy_json: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, tag = "4")]
+    pub indexing_settings_json: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub doc_mapping_json: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListIndexesMetadataRequest {
     /// List of patterns an index should match or not match to get considered
     /// An index must match at least one positive pattern (a pattern not starting
@@ -241,6 +256,27 @@ pub struct ListStaleSplitsRequest {
 #[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenPointInTimeRequest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub point_in_time_id: ::prost::alloc::string::String,
+    /// Timestamp in seconds after which the point in time expires.
+    #[prost(int64, tag = "3")]
+    pub expiration_timestamp: i64,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClosePointInTimeRequest {
+    #[prost(message, optional, tag = "1")]
+    pub index_uid: ::core::option::Option<crate::types::IndexUid>,
+    #[prost(string, tag = "2")]
+    pub point_in_time_id: ::prost::alloc::string::String,
+}
+#[derive(serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ListDeleteTasksRequest {
     #[prost(message, optional, tag = "1")]
     pub index_uid: ::core::option::Option<crate::types::IndexUid>,
@@ -480,6 +516,10 @@ pub enum SourceType {
     Pulsar = 9,
     Vec = 10,
     Void = 11,
+    Syslog = 12,
+    StoragePrefix = 13,
+    Amqp = 14,
+    Mqtt = 15,
 }
 impl SourceType {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -500,6 +540,10 @@ impl SourceType {
             SourceType::Pulsar => "SOURCE_TYPE_PULSAR",
             SourceType::Vec => "SOURCE_TYPE_VEC",
             SourceType::Void => "SOURCE_TYPE_VOID",
+            SourceType::Syslog => "SOURCE_TYPE_SYSLOG",
+            SourceType::StoragePrefix => "SOURCE_TYPE_STORAGE_PREFIX",
+            SourceType::Amqp => "SOURCE_TYPE_AMQP",
+            SourceType::Mqtt => "SOURCE_TYPE_MQTT",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -517,6 +561,10 @@ impl SourceType {
             "SOURCE_TYPE_PULSAR" => Some(Self::Pulsar),
             "SOURCE_TYPE_VEC" => Some(Self::Vec),
             "SOURCE_TYPE_VOID" => Some(Self::Void),
+            "SOURCE_TYPE_SYSLOG" => Some(Self::Syslog),
+            "SOURCE_TYPE_STORAGE_PREFIX" => Some(Self::StoragePrefix),
+            "SOURCE_TYPE_AMQP" => Some(Self::Amqp),
+            "SOURCE_TYPE_MQTT" => Some(Self::Mqtt),
             _ => None,
         }
     }
@@ -531,6 +579,11 @@ impl PrometheusLabels<1> for CreateIndexRequest {
         OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("create_index")])
     }
 }
+impl PrometheusLabels<1> for UpdateIndexRequest {
+    fn labels(&self) -> OwnedPrometheusLabels<1usize> {
+        OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("update_index")])
+    }
+}
 impl PrometheusLabels<1> for IndexMetadataRequest {
     fn labels(&self) -> OwnedPrometheusLabels<1usize> {
         OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("index_metadata")])
@@ -622,6 +675,16 @@ impl PrometheusLabels<1> for ListStaleSplitsRequest {
         OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("list_stale_splits")])
     }
 }
+impl PrometheusLabels<1> for OpenPointInTimeRequest {
+    fn labels(&self) -> OwnedPrometheusLabels<1usize> {
+        OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("open_point_in_time")])
+    }
+}
+impl PrometheusLabels<1> for ClosePointInTimeRequest {
+    fn labels(&self) -> OwnedPrometheusLabels<1usize> {
+        OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("close_point_in_time")])
+    }
+}
 impl PrometheusLabels<1> for OpenShardsRequest {
     fn labels(&self) -> OwnedPrometheusLabels<1usize> {
         OwnedPrometheusLabels::new([std::borrow::Cow::Borrowed("open_shards")])
@@ -685,6 +748,16 @@ pub trait MetastoreService: std::fmt::Debug + dyn_clone::DynClone + Send + Sync
         &mut self,
         request: CreateIndexRequest,
     ) -> crate::metastore::MetastoreResult<CreateIndexResponse>;
+    /// Updates an index.
+    ///
+    /// This API updates the doc mapping, the search settings, the retention policy, and the indexing
+    /// settings of an existing index. The doc mapping only accepts backward compatible changes: the
+    /// metastore rejects the update with an invalid argument error otherwise. Other fields of the
+    /// index configuration cannot be modified.
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse>;
     /// Returns the `IndexMetadata` of an index identified by its IndexID or its IndexUID.
     async fn index_metadata(
         &mut self,
@@ -770,6 +843,19 @@ pub trait MetastoreService: std::fmt::Debug + dyn_clone::DynClone + Send + Sync
         &mut self,
         request: ListStaleSplitsRequest,
     ) -> crate::metastore::MetastoreResult<ListSplitsResponse>;
+    /// Opens a point in time on an index.
+    ///
+    /// This API records the splits of the index published at the time of the call. If the point in
+    /// time already exists, its expiration timestamp is extended instead.
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse>;
+    /// Closes a point in time on an index.
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse>;
     /// Shard API
     ///
     /// Note that for the file-backed metastore implementation, the requests are not processed atomically.
@@ -918,6 +1004,12 @@ impl MetastoreService for MetastoreServiceClient {
     ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
         self.inner.create_index(request).await
     }
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.inner.update_index(request).await
+    }
     async fn index_metadata(
         &mut self,
         request: IndexMetadataRequest,
@@ -1020,6 +1112,18 @@ impl MetastoreService for MetastoreServiceClient {
     ) -> crate::metastore::MetastoreResult<ListSplitsResponse> {
         self.inner.list_stale_splits(request).await
     }
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.inner.open_point_in_time(request).await
+    }
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.inner.close_point_in_time(request).await
+    }
     async fn open_shards(
         &mut self,
         request: OpenShardsRequest,
@@ -1096,6 +1200,12 @@ pub mod metastore_service_mock {
         ) -> crate::metastore::MetastoreResult<super::CreateIndexResponse> {
             self.inner.lock().await.create_index(request).await
         }
+        async fn update_index(
+            &mut self,
+            request: super::UpdateIndexRequest,
+        ) -> crate::metastore::MetastoreResult<super::IndexMetadataResponse> {
+            self.inner.lock().await.update_index(request).await
+        }
         async fn index_metadata(
             &mut self,
             request: super::IndexMetadataRequest,
@@ -1202,6 +1312,18 @@ pub mod metastore_service_mock {
         ) -> crate::metastore::MetastoreResult<super::ListSplitsResponse> {
             self.inner.lock().await.list_stale_splits(request).await
         }
+        async fn open_point_in_time(
+            &mut self,
+            request: super::OpenPointInTimeRequest,
+        ) -> crate::metastore::MetastoreResult<super::EmptyResponse> {
+            self.inner.lock().await.open_point_in_time(request).await
+        }
+        async fn close_point_in_time(
+            &mut self,
+            request: super::ClosePointInTimeRequest,
+        ) -> crate::metastore::MetastoreResult<super::EmptyResponse> {
+            self.inner.lock().await.close_point_in_time(request).await
+        }
         async fn open_shards(
             &mut self,
             request: super::OpenShardsRequest,
@@ -1291,6 +1413,22 @@ impl tower::Service<CreateIndexRequest> for Box<dyn MetastoreService> {
         Box::pin(fut)
     }
 }
+impl tower::Service<UpdateIndexRequest> for Box<dyn MetastoreService> {
+    type Response = IndexMetadataResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: UpdateIndexRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.update_index(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<IndexMetadataRequest> for Box<dyn MetastoreService> {
     type Response = IndexMetadataResponse;
     type Error = crate::metastore::MetastoreError;
@@ -1563,6 +1701,38 @@ impl tower::Service<ListStaleSplitsRequest> for Box<dyn MetastoreService> {
         Box::pin(fut)
     }
 }
+impl tower::Service<OpenPointInTimeRequest> for Box<dyn MetastoreService> {
+    type Response = EmptyResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: OpenPointInTimeRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.open_point_in_time(request).await };
+        Box::pin(fut)
+    }
+}
+impl tower::Service<ClosePointInTimeRequest> for Box<dyn MetastoreService> {
+    type Response = EmptyResponse;
+    type Error = crate::metastore::MetastoreError;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    fn poll_ready(
+        &mut self,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+    fn call(&mut self, request: ClosePointInTimeRequest) -> Self::Future {
+        let mut svc = self.clone();
+        let fut = async move { svc.close_point_in_time(request).await };
+        Box::pin(fut)
+    }
+}
 impl tower::Service<OpenShardsRequest> for Box<dyn MetastoreService> {
     type Response = OpenShardsResponse;
     type Error = crate::metastore::MetastoreError;
@@ -1716,6 +1886,11 @@ struct MetastoreServiceTowerServiceStack {
         CreateIndexResponse,
         crate::metastore::MetastoreError,
     >,
+    update_index_svc: quickwit_common::tower::BoxService<
+        UpdateIndexRequest,
+        IndexMetadataResponse,
+        crate::metastore::MetastoreError,
+    >,
     index_metadata_svc: quickwit_common::tower::BoxService<
         IndexMetadataRequest,
         IndexMetadataResponse,
@@ -1801,6 +1976,16 @@ struct MetastoreServiceTowerServiceStack {
         ListSplitsResponse,
         crate::metastore::MetastoreError,
     >,
+    open_point_in_time_svc: quickwit_common::tower::BoxService<
+        OpenPointInTimeRequest,
+        EmptyResponse,
+        crate::metastore::MetastoreError,
+    >,
+    close_point_in_time_svc: quickwit_common::tower::BoxService<
+        ClosePointInTimeRequest,
+        EmptyResponse,
+        crate::metastore::MetastoreError,
+    >,
     open_shards_svc: quickwit_common::tower::BoxService<
         OpenShardsRequest,
         OpenShardsResponse,
@@ -1852,6 +2037,7 @@ impl Clone for MetastoreServiceTowerServiceStack {
         Self {
             inner: self.inner.clone(),
             create_index_svc: self.create_index_svc.clone(),
+            update_index_svc: self.update_index_svc.clone(),
             index_metadata_svc: self.index_metadata_svc.clone(),
             list_indexes_metadata_svc: self.list_indexes_metadata_svc.clone(),
             delete_index_svc: self.delete_index_svc.clone(),
@@ -1871,6 +2057,8 @@ impl Clone for MetastoreServiceTowerServiceStack {
                 .clone(),
             list_delete_tasks_svc: self.list_delete_tasks_svc.clone(),
             list_stale_splits_svc: self.list_stale_splits_svc.clone(),
+            open_point_in_time_svc: self.open_point_in_time_svc.clone(),
+            close_point_in_time_svc: self.close_point_in_time_svc.clone(),
             open_shards_svc: self.open_shards_svc.clone(),
             acquire_shards_svc: self.acquire_shards_svc.clone(),
             delete_shards_svc: self.delete_shards_svc.clone(),
@@ -1893,6 +2081,12 @@ impl MetastoreService for MetastoreServiceTowerServiceStack {
     ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
         self.create_index_svc.ready().await?.call(request).await
     }
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.update_index_svc.ready().await?.call(request).await
+    }
     async fn index_metadata(
         &mut self,
         request: IndexMetadataRequest,
@@ -1995,6 +2189,18 @@ impl MetastoreService for MetastoreServiceTowerServiceStack {
     ) -> crate::metastore::MetastoreResult<ListSplitsResponse> {
         self.list_stale_splits_svc.ready().await?.call(request).await
     }
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.open_point_in_time_svc.ready().await?.call(request).await
+    }
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.close_point_in_time_svc.ready().await?.call(request).await
+    }
     async fn open_shards(
         &mut self,
         request: OpenShardsRequest,
@@ -2066,6 +2272,16 @@ type CreateIndexLayer = quickwit_common::tower::BoxLayer<
     CreateIndexResponse,
     crate::metastore::MetastoreError,
 >;
+type UpdateIndexLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        UpdateIndexRequest,
+        IndexMetadataResponse,
+        crate::metastore::MetastoreError,
+    >,
+    UpdateIndexRequest,
+    IndexMetadataResponse,
+    crate::metastore::MetastoreError,
+>;
 type IndexMetadataLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         IndexMetadataRequest,
@@ -2236,6 +2452,26 @@ type ListStaleSplitsLayer = quickwit_common::tower::BoxLayer<
     ListSplitsResponse,
     crate::metastore::MetastoreError,
 >;
+type OpenPointInTimeLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        OpenPointInTimeRequest,
+        EmptyResponse,
+        crate::metastore::MetastoreError,
+    >,
+    OpenPointInTimeRequest,
+    EmptyResponse,
+    crate::metastore::MetastoreError,
+>;
+type ClosePointInTimeLayer = quickwit_common::tower::BoxLayer<
+    quickwit_common::tower::BoxService<
+        ClosePointInTimeRequest,
+        EmptyResponse,
+        crate::metastore::MetastoreError,
+    >,
+    ClosePointInTimeRequest,
+    EmptyResponse,
+    crate::metastore::MetastoreError,
+>;
 type OpenShardsLayer = quickwit_common::tower::BoxLayer<
     quickwit_common::tower::BoxService<
         OpenShardsRequest,
@@ -2329,6 +2565,7 @@ type DeleteIndexTemplatesLayer = quickwit_common::tower::BoxLayer<
 #[derive(Debug, Default)]
 pub struct MetastoreServiceTowerLayerStack {
     create_index_layers: Vec<CreateIndexLayer>,
+    update_index_layers: Vec<UpdateIndexLayer>,
     index_metadata_layers: Vec<IndexMetadataLayer>,
     list_indexes_metadata_layers: Vec<ListIndexesMetadataLayer>,
     delete_index_layers: Vec<DeleteIndexLayer>,
@@ -2346,6 +2583,8 @@ pub struct MetastoreServiceTowerLayerStack {
     update_splits_delete_opstamp_layers: Vec<UpdateSplitsDeleteOpstampLayer>,
     list_delete_tasks_layers: Vec<ListDeleteTasksLayer>,
     list_stale_splits_layers: Vec<ListStaleSplitsLayer>,
+    open_point_in_time_layers: Vec<OpenPointInTimeLayer>,
+    close_point_in_time_layers: Vec<ClosePointInTimeLayer>,
     open_shards_layers: Vec<OpenShardsLayer>,
     acquire_shards_layers: Vec<AcquireShardsLayer>,
     delete_shards_layers: Vec<DeleteShardsLayer>,
@@ -2384,6 +2623,31 @@ impl MetastoreServiceTowerLayerStack {
                 crate::metastore::MetastoreError,
             >,
         >>::Service as tower::Service<CreateIndexRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    UpdateIndexRequest,
+                    IndexMetadataResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                UpdateIndexRequest,
+                IndexMetadataResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                UpdateIndexRequest,
+                Response = IndexMetadataResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                UpdateIndexRequest,
+                IndexMetadataResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<UpdateIndexRequest>>::Future: Send + 'static,
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
                     IndexMetadataRequest,
@@ -2817,6 +3081,56 @@ impl MetastoreServiceTowerLayerStack {
                 crate::metastore::MetastoreError,
             >,
         >>::Service as tower::Service<ListStaleSplitsRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    OpenPointInTimeRequest,
+                    EmptyResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                OpenPointInTimeRequest,
+                EmptyResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                OpenPointInTimeRequest,
+                Response = EmptyResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                OpenPointInTimeRequest,
+                EmptyResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<OpenPointInTimeRequest>>::Future: Send + 'static,
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ClosePointInTimeRequest,
+                    EmptyResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Clone + Send + Sync + 'static,
+        <L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ClosePointInTimeRequest,
+                EmptyResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service: tower::Service<
+                ClosePointInTimeRequest,
+                Response = EmptyResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <<L as tower::Layer<
+            quickwit_common::tower::BoxService<
+                ClosePointInTimeRequest,
+                EmptyResponse,
+                crate::metastore::MetastoreError,
+            >,
+        >>::Service as tower::Service<ClosePointInTimeRequest>>::Future: Send + 'static,
         L: tower::Layer<
                 quickwit_common::tower::BoxService<
                     OpenShardsRequest,
@@ -3053,6 +3367,8 @@ impl MetastoreServiceTowerLayerStack {
     {
         self.create_index_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.update_index_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.index_metadata_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.list_indexes_metadata_layers
@@ -3087,6 +3403,10 @@ impl MetastoreServiceTowerLayerStack {
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.list_stale_splits_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.open_point_in_time_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
+        self.close_point_in_time_layers
+            .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.open_shards_layers
             .push(quickwit_common::tower::BoxLayer::new(layer.clone()));
         self.acquire_shards_layers
@@ -3126,6 +3446,25 @@ impl MetastoreServiceTowerLayerStack {
         self.create_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_update_index_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    UpdateIndexRequest,
+                    IndexMetadataResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                UpdateIndexRequest,
+                Response = IndexMetadataResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<UpdateIndexRequest>>::Future: Send + 'static,
+    {
+        self.update_index_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_index_metadata_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -3463,6 +3802,44 @@ impl MetastoreServiceTowerLayerStack {
         self.list_stale_splits_layers.push(quickwit_common::tower::BoxLayer::new(layer));
         self
     }
+    pub fn stack_open_point_in_time_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    OpenPointInTimeRequest,
+                    EmptyResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                OpenPointInTimeRequest,
+                Response = EmptyResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<OpenPointInTimeRequest>>::Future: Send + 'static,
+    {
+        self.open_point_in_time_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
+    pub fn stack_close_point_in_time_layer<L>(mut self, layer: L) -> Self
+    where
+        L: tower::Layer<
+                quickwit_common::tower::BoxService<
+                    ClosePointInTimeRequest,
+                    EmptyResponse,
+                    crate::metastore::MetastoreError,
+                >,
+            > + Send + Sync + 'static,
+        L::Service: tower::Service<
+                ClosePointInTimeRequest,
+                Response = EmptyResponse,
+                Error = crate::metastore::MetastoreError,
+            > + Clone + Send + Sync + 'static,
+        <L::Service as tower::Service<ClosePointInTimeRequest>>::Future: Send + 'static,
+    {
+        self.close_point_in_time_layers.push(quickwit_common::tower::BoxLayer::new(layer));
+        self
+    }
     pub fn stack_open_shards_layer<L>(mut self, layer: L) -> Self
     where
         L: tower::Layer<
@@ -3701,6 +4078,14 @@ impl MetastoreServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
+        let update_index_svc = self
+            .update_index_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
         let index_metadata_svc = self
             .index_metadata_layers
             .into_iter()
@@ -3837,31 +4222,47 @@ impl MetastoreServiceTowerLayerStack {
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
-        let open_shards_svc = self
-            .open_shards_layers
+        let open_point_in_time_svc = self
+            .open_point_in_time_layers
             .into_iter()
             .rev()
             .fold(
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
-        let acquire_shards_svc = self
-            .acquire_shards_layers
+        let close_point_in_time_svc = self
+            .close_point_in_time_layers
             .into_iter()
             .rev()
             .fold(
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
-        let delete_shards_svc = self
-            .delete_shards_layers
+        let open_shards_svc = self
+            .open_shards_layers
             .into_iter()
             .rev()
             .fold(
                 quickwit_common::tower::BoxService::new(boxed_instance.clone()),
                 |svc, layer| layer.layer(svc),
             );
-        let list_shards_svc = self
+        let acquire_shards_svc = self
+            .acquire_shards_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let delete_shards_svc = self
+            .delete_shards_layers
+            .into_iter()
+            .rev()
+            .fold(
+                quickwit_common::tower::BoxService::new(boxed_instance.clone()),
+                |svc, layer| layer.layer(svc),
+            );
+        let list_shards_svc = self
             .list_shards_layers
             .into_iter()
             .rev()
@@ -3912,6 +4313,7 @@ impl MetastoreServiceTowerLayerStack {
         let tower_svc_stack = MetastoreServiceTowerServiceStack {
             inner: boxed_instance.clone(),
             create_index_svc,
+            update_index_svc,
             index_metadata_svc,
             list_indexes_metadata_svc,
             delete_index_svc,
@@ -3929,6 +4331,8 @@ impl MetastoreServiceTowerLayerStack {
             update_splits_delete_opstamp_svc,
             list_delete_tasks_svc,
             list_stale_splits_svc,
+            open_point_in_time_svc,
+            close_point_in_time_svc,
             open_shards_svc,
             acquire_shards_svc,
             delete_shards_svc,
@@ -4020,6 +4424,12 @@ where
             Error = crate::metastore::MetastoreError,
             Future = BoxFuture<CreateIndexResponse, crate::metastore::MetastoreError>,
         >
+        + tower::Service<
+            UpdateIndexRequest,
+            Response = IndexMetadataResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<IndexMetadataResponse, crate::metastore::MetastoreError>,
+        >
         + tower::Service<
             IndexMetadataRequest,
             Response = IndexMetadataResponse,
@@ -4134,6 +4544,18 @@ where
             Error = crate::metastore::MetastoreError,
             Future = BoxFuture<ListSplitsResponse, crate::metastore::MetastoreError>,
         >
+        + tower::Service<
+            OpenPointInTimeRequest,
+            Response = EmptyResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<EmptyResponse, crate::metastore::MetastoreError>,
+        >
+        + tower::Service<
+            ClosePointInTimeRequest,
+            Response = EmptyResponse,
+            Error = crate::metastore::MetastoreError,
+            Future = BoxFuture<EmptyResponse, crate::metastore::MetastoreError>,
+        >
         + tower::Service<
             OpenShardsRequest,
             Response = OpenShardsResponse,
@@ -4204,6 +4626,12 @@ where
     ) -> crate::metastore::MetastoreResult<CreateIndexResponse> {
         self.call(request).await
     }
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.call(request).await
+    }
     async fn index_metadata(
         &mut self,
         request: IndexMetadataRequest,
@@ -4306,6 +4734,18 @@ where
     ) -> crate::metastore::MetastoreResult<ListSplitsResponse> {
         self.call(request).await
     }
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.call(request).await
+    }
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.call(request).await
+    }
     async fn open_shards(
         &mut self,
         request: OpenShardsRequest,
@@ -4417,6 +4857,16 @@ where
             .map(|response| response.into_inner())
             .map_err(|error| error.into())
     }
+    async fn update_index(
+        &mut self,
+        request: UpdateIndexRequest,
+    ) -> crate::metastore::MetastoreResult<IndexMetadataResponse> {
+        self.inner
+            .update_index(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|error| error.into())
+    }
     async fn index_metadata(
         &mut self,
         request: IndexMetadataRequest,
@@ -4591,6 +5041,26 @@ where
             .map(|response| response.into_inner())
             .map_err(|error| error.into())
     }
+    async fn open_point_in_time(
+        &mut self,
+        request: OpenPointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.inner
+            .open_point_in_time(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|error| error.into())
+    }
+    async fn close_point_in_time(
+        &mut self,
+        request: ClosePointInTimeRequest,
+    ) -> crate::metastore::MetastoreResult<EmptyResponse> {
+        self.inner
+            .close_point_in_time(request)
+            .await
+            .map(|response| response.into_inner())
+            .map_err(|error| error.into())
+    }
     async fn open_shards(
         &mut self,
         request: OpenShardsRequest,
@@ -4723,6 +5193,17 @@ for MetastoreServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(|error| error.into())
     }
+    async fn update_index(
+        &self,
+        request: tonic::Request<UpdateIndexRequest>,
+    ) -> Result<tonic::Response<IndexMetadataResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .update_index(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(|error| error.into())
+    }
     async fn index_metadata(
         &self,
         request: tonic::Request<IndexMetadataRequest>,
@@ -4913,6 +5394,28 @@ for MetastoreServiceGrpcServerAdapter {
             .map(tonic::Response::new)
             .map_err(|error| error.into())
     }
+    async fn open_point_in_time(
+        &self,
+        request: tonic::Request<OpenPointInTimeRequest>,
+    ) -> Result<tonic::Response<EmptyResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .open_point_in_time(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(|error| error.into())
+    }
+    async fn close_point_in_time(
+        &self,
+        request: tonic::Request<ClosePointInTimeRequest>,
+    ) -> Result<tonic::Response<EmptyResponse>, tonic::Status> {
+        self.inner
+            .clone()
+            .close_point_in_time(request.into_inner())
+            .await
+            .map(tonic::Response::new)
+            .map_err(|error| error.into())
+    }
     async fn open_shards(
         &self,
         request: tonic::Request<OpenShardsRequest>,
@@ -5175,6 +5678,39 @@ pub mod metastore_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Updates an index.
+        ///
+        /// This API updates the doc mapping, the search settings, the retention policy, and the indexing
+        /// settings of an existing index. The doc mapping only accepts backward compatible changes: the
+        /// metastore rejects the update with an invalid argument error otherwise. Other fields of the
+        /// index configuration cannot be modified.
+        pub async fn update_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::IndexMetadataResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/UpdateIndex",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.metastore.MetastoreService", "UpdateIndex"),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Returns the `IndexMetadata` of an index identified by its IndexID or its IndexUID.
         pub async fn index_metadata(
             &mut self,
@@ -5660,6 +6196,71 @@ pub mod metastore_service_grpc_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Opens a point in time on an index.
+        ///
+        /// This API records the splits of the index published at the time of the call. If the point in
+        /// time already exists, its expiration timestamp is extended instead.
+        pub async fn open_point_in_time(
+            &mut self,
+            request: impl tonic::IntoRequest<super::OpenPointInTimeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::EmptyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/OpenPointInTime",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.metastore.MetastoreService",
+                        "OpenPointInTime",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Closes a point in time on an index.
+        pub async fn close_point_in_time(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ClosePointInTimeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::EmptyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.metastore.MetastoreService/ClosePointInTime",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "quickwit.metastore.MetastoreService",
+                        "ClosePointInTime",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Shard API
         ///
         /// Note that for the file-backed metastore implementation, the requests are not processed atomically.
@@ -5954,6 +6555,19 @@ pub mod metastore_service_grpc_server {
             tonic::Response<super::CreateIndexResponse>,
             tonic::Status,
         >;
+        /// Updates an index.
+        ///
+        /// This API updates the doc mapping, the search settings, the retention policy, and the indexing
+        /// settings of an existing index. The doc mapping only accepts backward compatible changes: the
+        /// metastore rejects the update with an invalid argument error otherwise. Other fields of the
+        /// index configuration cannot be modified.
+        async fn update_index(
+            &self,
+            request: tonic::Request<super::UpdateIndexRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::IndexMetadataResponse>,
+            tonic::Status,
+        >;
         /// Returns the `IndexMetadata` of an index identified by its IndexID or its IndexUID.
         async fn index_metadata(
             &self,
@@ -6063,6 +6677,25 @@ pub mod metastore_service_grpc_server {
             tonic::Response<super::ListSplitsResponse>,
             tonic::Status,
         >;
+        /// Opens a point in time on an index.
+        ///
+        /// This API records the splits of the index published at the time of the call. If the point in
+        /// time already exists, its expiration timestamp is extended instead.
+        async fn open_point_in_time(
+            &self,
+            request: tonic::Request<super::OpenPointInTimeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::EmptyResponse>,
+            tonic::Status,
+        >;
+        /// Closes a point in time on an index.
+        async fn close_point_in_time(
+            &self,
+            request: tonic::Request<super::ClosePointInTimeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::EmptyResponse>,
+            tonic::Status,
+        >;
         /// Shard API
         ///
         /// Note that for the file-backed metastore implementation, the requests are not processed atomically.
@@ -6309,6 +6942,52 @@ pub mod metastore_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.metastore.MetastoreService/UpdateIndex" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateIndexSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::UpdateIndexRequest>
+                    for UpdateIndexSvc<T> {
+                        type Response = super::IndexMetadataResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateIndexRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).update_index(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateIndexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.metastore.MetastoreService/IndexMetadata" => {
                     #[allow(non_camel_case_types)]
                     struct IndexMetadataSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
@@ -7093,6 +7772,98 @@ pub mod metastore_service_grpc_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.metastore.MetastoreService/OpenPointInTime" => {
+                    #[allow(non_camel_case_types)]
+                    struct OpenPointInTimeSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::OpenPointInTimeRequest>
+                    for OpenPointInTimeSvc<T> {
+                        type Response = super::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::OpenPointInTimeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).open_point_in_time(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = OpenPointInTimeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quickwit.metastore.MetastoreService/ClosePointInTime" => {
+                    #[allow(non_camel_case_types)]
+                    struct ClosePointInTimeSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
+                    impl<
+                        T: MetastoreServiceGrpc,
+                    > tonic::server::UnaryService<super::ClosePointInTimeRequest>
+                    for ClosePointInTimeSvc<T> {
+                        type Response = super::EmptyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ClosePointInTimeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).close_point_in_time(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ClosePointInTimeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.metastore.MetastoreService/OpenShards" => {
                     #[allow(non_camel_case_types)]
                     struct OpenShardsSvc<T: MetastoreServiceGrpc>(pub Arc<T>);
@@ -184,6 +184,33 @@ pub struct SearchRequest {
     pub search_after: ::core::option::Option<PartialHit>,
     #[prost(enumeration = "CountHits", tag = "17")]
     pub count_hits: i32,
+    /// Options controlling how the snippets of `snippet_fields` are rendered.
+    #[prost(message, optional, tag = "18")]
+    pub snippet_options: ::core::option::Option<SnippetOptions>,
+    /// Restricts the document fields returned in the hits.
+    /// If none, the whole documents are returned.
+    #[prost(message, optional, tag = "19")]
+    pub source_filter: ::core::option::Option<SourceFilter>,
+    /// If set, the search only targets the splits pinned by this point in time.
+    #[prost(string, optional, tag = "20")]
+    pub point_in_time_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, the expiration of the point in time is extended by this amount of seconds.
+    #[prost(uint32, optional, tag = "21")]
+    pub point_in_time_keep_alive_secs: ::core::option::Option<u32>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SourceFilter {
+    /// Paths of the fields to return, e.g. `actor.*`. Supports `*` wildcards.
+    /// If empty, all the fields are returned.
+    #[prost(string, repeated, tag = "1")]
+    pub includes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Paths of the fields to omit. Supports `*` wildcards.
+    /// Excludes take precedence over includes.
+    #[prost(string, repeated, tag = "2")]
+    pub excludes: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[derive(Eq, Hash)]
@@ -347,6 +374,10 @@ pub struct PartialHit {
     pub sort_value: ::core::option::Option<SortByValue>,
     #[prost(message, optional, tag = "11")]
     pub sort_value2: ::core::option::Option<SortByValue>,
+    /// Values of the sorting keys beyond the second one, in the order of the sort fields.
+    #[prost(message, repeated, tag = "12")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_sort_values: ::prost::alloc::vec::Vec<SortByValue>,
     #[prost(string, tag = "2")]
     pub split_id: ::prost::alloc::string::String,
     /// (segment_ord, doc) form a tantivy DocAddress, which is sufficient to identify a document
@@ -362,7 +393,7 @@ pub struct PartialHit {
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SortByValue {
-    #[prost(oneof = "sort_by_value::SortValue", tags = "1, 2, 3, 4")]
+    #[prost(oneof = "sort_by_value::SortValue", tags = "1, 2, 3, 4, 5")]
     pub sort_value: ::core::option::Option<sort_by_value::SortValue>,
 }
 /// Nested message and enum types in `SortByValue`.
@@ -380,6 +411,8 @@ pub mod sort_by_value {
         F64(f64),
         #[prost(bool, tag = "4")]
         Boolean(bool),
+        #[prost(string, tag = "5")]
+        Str(::prost::alloc::string::String),
     }
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
@@ -413,6 +446,30 @@ pub struct SnippetRequest {
     pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     #[prost(string, tag = "2")]
     pub query_ast_resolved: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "3")]
+    pub snippet_options: ::core::option::Option<SnippetOptions>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Eq, Hash)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnippetOptions {
+    /// Tag inserted before each highlighted term (`<b>` by default).
+    #[prost(string, optional, tag = "1")]
+    pub pre_tag: ::core::option::Option<::prost::alloc::string::String>,
+    /// Tag inserted after each highlighted term (`</b>` by default).
+    #[prost(string, optional, tag = "2")]
+    pub post_tag: ::core::option::Option<::prost::alloc::string::String>,
+    /// Maximum number of characters of a snippet fragment.
+    #[prost(uint32, optional, tag = "3")]
+    pub fragment_size: ::core::option::Option<u32>,
+    /// Maximum number of snippets returned per field value (1 by default).
+    /// If set to 0, the field value is not fragmented: the whole value is highlighted.
+    #[prost(uint32, optional, tag = "4")]
+    pub number_of_fragments: ::core::option::Option<u32>,
+    /// Encoding of the snippet text. The tags are never encoded.
+    #[prost(enumeration = "SnippetEncoder", tag = "5")]
+    pub encoder: i32,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -432,6 +489,9 @@ pub struct FetchDocsRequest {
     pub index_uri: ::prost::alloc::string::String,
     #[prost(message, optional, tag = "7")]
     pub snippet_request: ::core::option::Option<SnippetRequest>,
+    /// Restricts the document fields returned in the hits.
+    #[prost(message, optional, tag = "8")]
+    pub source_filter: ::core::option::Option<SourceFilter>,
     /// `DocMapper` as json serialized trait.
     #[prost(string, tag = "6")]
     pub doc_mapper: ::prost::alloc::string::String,
@@ -547,6 +607,19 @@ pub struct SearchStreamRequest {
     /// Fields to extract snippet on.
     #[prost(string, repeated, tag = "10")]
     pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Names of the fast or stored fields to extract. Only supported by the
+    /// `ARROW_IPC`, `PARQUET`, and `NDJSON` output formats, which extract
+    /// `fast_field` if it is empty.
+    #[prost(string, repeated, tag = "12")]
+    pub fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchStreamResponse {
+    /// Data serialized in the requested output format.
+    #[prost(bytes = "vec", tag = "1")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
 }
 #[derive(Serialize, Deserialize, utoipa::ToSchema)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -729,6 +802,36 @@ impl SortDatetimeFormat {
 #[serde(rename_all = "snake_case")]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum SnippetEncoder {
+    /// The snippet text is HTML-escaped.
+    Html = 0,
+    /// The snippet text is returned as is.
+    Raw = 1,
+}
+impl SnippetEncoder {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SnippetEncoder::Html => "HTML",
+            SnippetEncoder::Raw => "RAW",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "HTML" => Some(Self::Html),
+            "RAW" => Some(Self::Raw),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum OutputFormat {
     /// Comma Separated Values format (<https://datatracker.ietf.org/doc/html/rfc4180>).
     /// The delimiter is `,`.
@@ -738,6 +841,14 @@ pub enum OutputFormat {
     /// Format data by row in ClickHouse binary format.
     /// <https://clickhouse.tech/docs/en/interfaces/formats/#rowbinary>
     ClickHouseRowBinary = 1,
+    /// Apache Arrow IPC streaming format.
+    /// <https://arrow.apache.org/docs/format/Columnar.html#ipc-streaming-format>
+    ArrowIpc = 2,
+    /// Apache Parquet file format.
+    /// <https://parquet.apache.org/docs/file-format/>
+    Parquet = 3,
+    /// Newline-delimited JSON, one object per document.
+    Ndjson = 4,
 }
 impl OutputFormat {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -748,6 +859,9 @@ impl OutputFormat {
         match self {
             OutputFormat::Csv => "CSV",
             OutputFormat::ClickHouseRowBinary => "CLICK_HOUSE_ROW_BINARY",
+            OutputFormat::ArrowIpc => "ARROW_IPC",
+            OutputFormat::Parquet => "PARQUET",
+            OutputFormat::Ndjson => "NDJSON",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -755,6 +869,9 @@ impl OutputFormat {
         match value {
             "CSV" => Some(Self::Csv),
             "CLICK_HOUSE_ROW_BINARY" => Some(Self::ClickHouseRowBinary),
+            "ARROW_IPC" => Some(Self::ArrowIpc),
+            "PARQUET" => Some(Self::Parquet),
+            "NDJSON" => Some(Self::Ndjson),
             _ => None,
         }
     }
@@ -958,6 +1075,37 @@ pub mod search_service_client {
                 );
             self.inner.server_streaming(req, path, codec).await
         }
+        /// Root search stream API.
+        /// This RPC identifies the set of splits on which the query should run on,
+        /// dispatches the several calls to `LeafSearchStream`, and streams back the
+        /// data serialized in the requested output format.
+        pub async fn root_search_stream(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SearchStreamRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::SearchStreamResponse>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quickwit.search.SearchService/RootSearchStream",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new("quickwit.search.SearchService", "RootSearchStream"),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
         /// Root list terms API.
         /// This RPC identifies the set of splits on which the query should run on,
         /// and dispatches the several calls to `LeafListTerms`.
@@ -1231,6 +1379,23 @@ pub mod search_service_server {
             tonic::Response<Self::LeafSearchStreamStream>,
             tonic::Status,
         >;
+        /// Server streaming response type for the RootSearchStream method.
+        type RootSearchStreamStream: futures_core::Stream<
+                Item = std::result::Result<super::SearchStreamResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        /// Root search stream API.
+        /// This RPC identifies the set of splits on which the query should run on,
+        /// dispatches the several calls to `LeafSearchStream`, and streams back the
+        /// data serialized in the requested output format.
+        async fn root_search_stream(
+            &self,
+            request: tonic::Request<super::SearchStreamRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::RootSearchStreamStream>,
+            tonic::Status,
+        >;
         /// Root list terms API.
         /// This RPC identifies the set of splits on which the query should run on,
         /// and dispatches the several calls to `LeafListTerms`.
@@ -1554,6 +1719,54 @@ pub mod search_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/quickwit.search.SearchService/RootSearchStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct RootSearchStreamSvc<T: SearchService>(pub Arc<T>);
+                    impl<
+                        T: SearchService,
+                    > tonic::server::ServerStreamingService<
+                        super::SearchStreamRequest,
+                    > for RootSearchStreamSvc<T> {
+                        type Response = super::SearchStreamResponse;
+                        type ResponseStream = T::RootSearchStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SearchStreamRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                (*inner).root_search_stream(request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = RootSearchStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/quickwit.search.SearchService/RootListTerms" => {
                     #[allow(non_camel_case_types)]
                     struct RootListTermsSvc<T: SearchService>(pub Arc<T>);
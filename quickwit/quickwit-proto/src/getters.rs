@@ -33,10 +33,11 @@ generate_getters! {
     ReplicateSuccess, ReplicateFailure, TruncateShardsSubrequest, OpenFetchStreamRequest,
     FetchPayload, FetchEof,
 
-    CreateIndexResponse, DeleteIndexRequest, StageSplitsRequest, PublishSplitsRequest,
-    MarkSplitsForDeletionRequest, DeleteSplitsRequest, AddSourceRequest, ToggleSourceRequest,
-    DeleteSourceRequest, ResetSourceCheckpointRequest, DeleteQuery, UpdateSplitsDeleteOpstampRequest,
-    LastDeleteOpstampRequest, ListStaleSplitsRequest, ListDeleteTasksRequest, OpenShardsSubrequest,
-    OpenShardsSubresponse, AcquireShardsSubrequest, AcquireShardsSubresponse, DeleteShardsSubrequest,
-    ListShardsSubrequest, ListShardsSubresponse
+    CreateIndexResponse, UpdateIndexRequest, DeleteIndexRequest, StageSplitsRequest,
+    PublishSplitsRequest, MarkSplitsForDeletionRequest, DeleteSplitsRequest, AddSourceRequest,
+    ToggleSourceRequest, DeleteSourceRequest, ResetSourceCheckpointRequest, DeleteQuery,
+    UpdateSplitsDeleteOpstampRequest, LastDeleteOpstampRequest, ListStaleSplitsRequest,
+    OpenPointInTimeRequest, ClosePointInTimeRequest, ListDeleteTasksRequest, OpenShardsSubrequest,
+    OpenShardsSubresponse, AcquireShardsSubrequest, AcquireShardsSubresponse,
+    DeleteShardsSubrequest, ListShardsSubrequest, ListShardsSubresponse
 }
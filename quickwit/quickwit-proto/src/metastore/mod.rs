@@ -78,6 +78,11 @@ pub enum EntityKind {
         /// Index template ID.
         template_id: String,
     },
+    /// A point in time.
+    PointInTime {
+        /// Point in time ID.
+        point_in_time_id: String,
+    },
 }
 
 impl fmt::Display for EntityKind {
@@ -99,6 +104,9 @@ impl fmt::Display for EntityKind {
             EntityKind::IndexTemplate { template_id } => {
                 write!(f, "index template `{}`", template_id)
             }
+            EntityKind::PointInTime { point_in_time_id } => {
+                write!(f, "point in time `{point_in_time_id}`")
+            }
         }
     }
 }
@@ -218,6 +226,10 @@ impl SourceType {
             SourceType::Kinesis => "kinesis",
             SourceType::Nats => "nats",
             SourceType::Pulsar => "pulsar",
+            SourceType::Syslog => "syslog",
+            SourceType::StoragePrefix => "storage_prefix",
+            SourceType::Amqp => "amqp",
+            SourceType::Mqtt => "mqtt",
             SourceType::Unspecified => "unspecified",
             SourceType::Vec => "vec",
             SourceType::Void => "void",
@@ -55,7 +55,6 @@ impl fmt::Display for SplitSearchError {
 }
 
 impl Eq for SortByValue {}
-impl Copy for SortByValue {}
 impl From<SortValue> for SortByValue {
     fn from(sort_value: SortValue) -> Self {
         SortByValue {
@@ -85,6 +84,7 @@ impl SortByValue {
                 }
             }
             Some(SortValue::Boolean(b)) => Bool(b),
+            Some(SortValue::Str(s)) => String(s),
             None => Null,
         }
     }
@@ -106,18 +106,11 @@ impl SortByValue {
                     return None;
                 }
             }
-            // Strings that can be converted to a number are accepted.
-            // Some clients (like JS clients) can't easily handle large integers
-            // without losing precision, so we accept them as strings.
-            String(value) => {
-                if let Ok(number) = value.parse::<i64>() {
-                    Some(SortValue::I64(number))
-                } else if let Ok(number) = value.parse::<u64>() {
-                    Some(SortValue::U64(number))
-                } else {
-                    return None;
-                }
-            }
+            // Strings are kept as is, as they can be the values of a text sort field. When the
+            // sort field is numeric, they get converted to numbers by the leaves: some clients
+            // (like JS clients) can't easily handle large integers without losing precision, so
+            // we accept them as strings.
+            String(value) => Some(SortValue::Str(value)),
             Array(_) | Object(_) => return None,
         };
         Some(SortByValue { sort_value })
@@ -130,15 +123,14 @@ impl SortByValue {
 // This is terrible because this means Eq, PartialEq are not really in line with Ord's
 // implementation. if in presence of NaN.
 impl Eq for SortValue {}
-impl Copy for SortValue {}
 
 impl Ord for SortValue {
     fn cmp(&self, other: &Self) -> Ordering {
         // We make sure to end up with a total order.
-        match (*self, *other) {
+        match (self, other) {
             // Same types.
-            (SortValue::U64(left), SortValue::U64(right)) => left.cmp(&right),
-            (SortValue::I64(left), SortValue::I64(right)) => left.cmp(&right),
+            (SortValue::U64(left), SortValue::U64(right)) => left.cmp(right),
+            (SortValue::I64(left), SortValue::I64(right)) => left.cmp(right),
             (SortValue::F64(left), SortValue::F64(right)) => {
                 if left.is_nan() {
                     if right.is_nan() {
@@ -149,27 +141,30 @@ impl Ord for SortValue {
                 } else if right.is_nan() {
                     Ordering::Greater
                 } else {
-                    left.partial_cmp(&right).unwrap_or(Ordering::Less)
+                    left.partial_cmp(right).unwrap_or(Ordering::Less)
                 }
             }
-            (SortValue::Boolean(left), SortValue::Boolean(right)) => left.cmp(&right),
+            (SortValue::Boolean(left), SortValue::Boolean(right)) => left.cmp(right),
+            (SortValue::Str(left), SortValue::Str(right)) => left.cmp(right),
             // We half the logic by making sure we keep
             // the "stronger" type on the left.
             (SortValue::U64(left), SortValue::I64(right)) => {
-                if left > i64::MAX as u64 {
+                if *left > i64::MAX as u64 {
                     return Ordering::Greater;
                 }
-                (left as i64).cmp(&right)
+                (*left as i64).cmp(right)
             }
             (SortValue::F64(left), _) if left.is_nan() => Ordering::Less,
             (SortValue::F64(left), SortValue::U64(right)) => {
-                left.partial_cmp(&(right as f64)).unwrap_or(Ordering::Less)
+                left.partial_cmp(&(*right as f64)).unwrap_or(Ordering::Less)
             }
             (SortValue::F64(left), SortValue::I64(right)) => {
-                left.partial_cmp(&(right as f64)).unwrap_or(Ordering::Less)
+                left.partial_cmp(&(*right as f64)).unwrap_or(Ordering::Less)
             }
-            (SortValue::Boolean(left), right) => SortValue::U64(left as u64).cmp(&right),
-            (left, right) => right.cmp(&left).reverse(),
+            (SortValue::Boolean(left), right) => SortValue::U64(*left as u64).cmp(right),
+            // Strings are greater than any other type.
+            (SortValue::Str(_), _) => Ordering::Greater,
+            (left, right) => right.cmp(left).reverse(),
         }
     }
 }
@@ -200,6 +195,10 @@ impl std::hash::Hash for SortValue {
                 3u8.hash(state);
                 b.hash(state);
             }
+            SortValue::Str(s) => {
+                4u8.hash(state);
+                s.hash(state);
+            }
         }
     }
 }
@@ -210,13 +209,14 @@ impl SortValue {
     /// For number, we prefer to represent them, in order, as i64, then as u64 and finaly as f64.
     pub fn normalize(&self) -> Self {
         match self {
-            SortValue::I64(_) => *self,
-            SortValue::Boolean(_) => *self,
+            SortValue::I64(_) => self.clone(),
+            SortValue::Boolean(_) => self.clone(),
+            SortValue::Str(_) => self.clone(),
             SortValue::U64(number) => {
                 if let Ok(number) = (*number).try_into() {
                     SortValue::I64(number)
                 } else {
-                    *self
+                    self.clone()
                 }
             }
             SortValue::F64(number) => {
@@ -229,7 +229,7 @@ impl SortValue {
                         return SortValue::U64(number as u64);
                     }
                 }
-                *self
+                self.clone()
             }
         }
     }
@@ -238,12 +238,28 @@ impl SortValue {
 impl PartialHit {
     /// Helper to get access to the 1st sort value
     pub fn sort_value(&self) -> Option<SortValue> {
-        if let Some(sort_value) = self.sort_value {
-            sort_value.sort_value
+        if let Some(sort_value) = &self.sort_value {
+            sort_value.sort_value.clone()
         } else {
             None
         }
     }
+
+    /// Returns the sort values of the hit, in the order of the sort fields.
+    ///
+    /// The first two items are always returned, even if the hit was sorted by fewer fields.
+    pub fn sort_values(&self) -> impl Iterator<Item = Option<&SortByValue>> {
+        [self.sort_value.as_ref(), self.sort_value2.as_ref()]
+            .into_iter()
+            .chain(self.additional_sort_values.iter().map(Some))
+    }
+
+    /// Mutable version of [`PartialHit::sort_values`].
+    pub fn sort_values_mut(&mut self) -> impl Iterator<Item = Option<&mut SortByValue>> {
+        [self.sort_value.as_mut(), self.sort_value2.as_mut()]
+            .into_iter()
+            .chain(self.additional_sort_values.iter_mut().map(Some))
+    }
 }
 
 /// Serializes the Split fields.
@@ -0,0 +1,234 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{
+    default_max_expansions, ConvertableToQueryAst, StringOrStructForSerialization,
+};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst, MAX_FUZZY_DISTANCE};
+
+/// `FuzzyQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-fuzzy-query.html>
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "OneFieldMap<StringOrStructForSerialization<FuzzyQueryParams>>")]
+pub(crate) struct FuzzyQuery {
+    pub(crate) field: String,
+    pub(crate) params: FuzzyQueryParams,
+}
+
+/// Fuzziness as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/common-options.html#fuzziness>
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(untagged)]
+pub(crate) enum Fuzziness {
+    Distance(u8),
+    Str(String),
+}
+
+impl Default for Fuzziness {
+    fn default() -> Self {
+        Fuzziness::Str("AUTO".to_string())
+    }
+}
+
+fn auto_fuzziness_distance(value: &str, low: usize, high: usize) -> u8 {
+    let num_chars = value.chars().count();
+    if num_chars < low {
+        0
+    } else if num_chars < high {
+        1
+    } else {
+        2
+    }
+}
+
+impl Fuzziness {
+    /// Returns the maximum edit distance allowed for the given value.
+    fn distance(&self, value: &str) -> anyhow::Result<u8> {
+        let distance = match self {
+            Fuzziness::Distance(distance) => *distance,
+            Fuzziness::Str(fuzziness) if fuzziness == "AUTO" => {
+                auto_fuzziness_distance(value, 3, 6)
+            }
+            Fuzziness::Str(fuzziness) => {
+                if let Some(low_high) = fuzziness.strip_prefix("AUTO:") {
+                    let (low, high) = low_high
+                        .split_once(',')
+                        .with_context(|| format!("invalid fuzziness `{fuzziness}`"))?;
+                    let low: usize = low
+                        .parse()
+                        .with_context(|| format!("invalid fuzziness `{fuzziness}`"))?;
+                    let high: usize = high
+                        .parse()
+                        .with_context(|| format!("invalid fuzziness `{fuzziness}`"))?;
+                    auto_fuzziness_distance(value, low, high)
+                } else {
+                    fuzziness
+                        .parse()
+                        .with_context(|| format!("invalid fuzziness `{fuzziness}`"))?
+                }
+            }
+        };
+        if distance > MAX_FUZZY_DISTANCE {
+            anyhow::bail!(
+                "fuzziness must be lower or equal to {MAX_FUZZY_DISTANCE}, got {distance}"
+            );
+        }
+        Ok(distance)
+    }
+}
+
+fn default_transpositions() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FuzzyQueryParams {
+    value: String,
+    #[serde(default)]
+    fuzziness: Fuzziness,
+    // Accepted for compatibility, but the number of terms a fuzzy query expands to is not
+    // limited.
+    #[serde(default = "default_max_expansions")]
+    max_expansions: u32,
+    #[serde(default)]
+    prefix_length: u32,
+    #[serde(default = "default_transpositions")]
+    transpositions: bool,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<OneFieldMap<StringOrStructForSerialization<FuzzyQueryParams>>> for FuzzyQuery {
+    fn from(one_field_map: OneFieldMap<StringOrStructForSerialization<FuzzyQueryParams>>) -> Self {
+        FuzzyQuery {
+            field: one_field_map.field,
+            params: one_field_map.value.inner,
+        }
+    }
+}
+
+impl From<String> for FuzzyQueryParams {
+    fn from(value: String) -> FuzzyQueryParams {
+        FuzzyQueryParams {
+            value,
+            fuzziness: Fuzziness::default(),
+            max_expansions: default_max_expansions(),
+            prefix_length: 0,
+            transpositions: default_transpositions(),
+            boost: None,
+        }
+    }
+}
+
+impl ConvertableToQueryAst for FuzzyQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        if self.params.prefix_length != 0 {
+            anyhow::bail!("`prefix_length` is not supported in fuzzy queries");
+        }
+        let distance = self.params.fuzziness.distance(&self.params.value)?;
+        let fuzzy_ast: QueryAst = query_ast::FuzzyQuery {
+            field: self.field,
+            value: self.params.value,
+            distance,
+            transposition_cost_one: self.params.transpositions,
+        }
+        .into();
+        Ok(fuzzy_ast.boost(self.params.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzziness_distance() {
+        let auto = Fuzziness::default();
+        assert_eq!(auto.distance("ab").unwrap(), 0);
+        assert_eq!(auto.distance("abc").unwrap(), 1);
+        assert_eq!(auto.distance("abcde").unwrap(), 1);
+        assert_eq!(auto.distance("abcdef").unwrap(), 2);
+
+        let auto_low_high = Fuzziness::Str("AUTO:2,4".to_string());
+        assert_eq!(auto_low_high.distance("a").unwrap(), 0);
+        assert_eq!(auto_low_high.distance("ab").unwrap(), 1);
+        assert_eq!(auto_low_high.distance("abcd").unwrap(), 2);
+
+        assert_eq!(Fuzziness::Distance(1).distance("abcdef").unwrap(), 1);
+        assert_eq!(Fuzziness::Str("2".to_string()).distance("a").unwrap(), 2);
+
+        let error = Fuzziness::Distance(3).distance("abc").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "fuzziness must be lower or equal to 2, got 3"
+        );
+        let error = Fuzziness::Str("AUTO:2".to_string())
+            .distance("abc")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "invalid fuzziness `AUTO:2`");
+    }
+
+    #[test]
+    fn test_fuzzy_query_convert_to_query_ast() {
+        let fuzzy_query: FuzzyQuery = serde_json::from_str(
+            r#"{ "user.id": { "value": "kimchy", "fuzziness": 1, "transpositions": false } }"#,
+        )
+        .unwrap();
+        let query_ast = fuzzy_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Fuzzy(query_ast::FuzzyQuery {
+                field: "user.id".to_string(),
+                value: "kimchy".to_string(),
+                distance: 1,
+                transposition_cost_one: false,
+            })
+        );
+
+        let fuzzy_query: FuzzyQuery = serde_json::from_str(r#"{ "user.id": "kimchy" }"#).unwrap();
+        let query_ast = fuzzy_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Fuzzy(query_ast::FuzzyQuery {
+                field: "user.id".to_string(),
+                value: "kimchy".to_string(),
+                distance: 2,
+                transposition_cost_one: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_query_prefix_length_unsupported() {
+        let fuzzy_query: FuzzyQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "kimchy", "prefix_length": 2 } }"#)
+                .unwrap();
+        let error = fuzzy_query.convert_to_query_ast().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`prefix_length` is not supported in fuzzy queries"
+        );
+    }
+}
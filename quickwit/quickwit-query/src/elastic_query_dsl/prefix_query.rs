@@ -0,0 +1,123 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertableToQueryAst, StringOrStructForSerialization};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// `PrefixQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-prefix-query.html>
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "OneFieldMap<StringOrStructForSerialization<PrefixQueryParams>>")]
+pub(crate) struct PrefixQuery {
+    pub(crate) field: String,
+    pub(crate) params: PrefixQueryParams,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct PrefixQueryParams {
+    value: String,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<OneFieldMap<StringOrStructForSerialization<PrefixQueryParams>>> for PrefixQuery {
+    fn from(one_field_map: OneFieldMap<StringOrStructForSerialization<PrefixQueryParams>>) -> Self {
+        PrefixQuery {
+            field: one_field_map.field,
+            params: one_field_map.value.inner,
+        }
+    }
+}
+
+impl From<String> for PrefixQueryParams {
+    fn from(value: String) -> PrefixQueryParams {
+        PrefixQueryParams {
+            value,
+            case_insensitive: false,
+            boost: None,
+        }
+    }
+}
+
+impl ConvertableToQueryAst for PrefixQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        if self.params.case_insensitive {
+            anyhow::bail!("`case_insensitive` is not supported in prefix queries");
+        }
+        let prefix_ast: QueryAst = query_ast::PrefixQuery {
+            field: self.field,
+            value: self.params.value,
+        }
+        .into();
+        Ok(prefix_ast.boost(self.params.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_query_deserialization() {
+        let prefix_query: PrefixQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "ki" } }"#).unwrap();
+        assert_eq!(prefix_query.field, "user.id");
+        assert_eq!(
+            prefix_query.params,
+            PrefixQueryParams::from("ki".to_string())
+        );
+
+        let prefix_query: PrefixQuery = serde_json::from_str(r#"{ "user.id": "ki" }"#).unwrap();
+        assert_eq!(prefix_query.field, "user.id");
+        assert_eq!(
+            prefix_query.params,
+            PrefixQueryParams::from("ki".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prefix_query_convert_to_query_ast() {
+        let prefix_query: PrefixQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "ki" } }"#).unwrap();
+        let query_ast = prefix_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Prefix(query_ast::PrefixQuery {
+                field: "user.id".to_string(),
+                value: "ki".to_string(),
+            })
+        );
+
+        let prefix_query: PrefixQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "ki", "case_insensitive": true } }"#)
+                .unwrap();
+        let error = prefix_query.convert_to_query_ast().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`case_insensitive` is not supported in prefix queries"
+        );
+    }
+}
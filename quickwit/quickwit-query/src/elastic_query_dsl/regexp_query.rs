@@ -0,0 +1,125 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertableToQueryAst, StringOrStructForSerialization};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// `RegexpQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-regexp-query.html>
+///
+/// The regular expression follows the syntax of the Rust `regex` crate rather than the Lucene
+/// one. Both are similar for the most common constructs.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "OneFieldMap<StringOrStructForSerialization<RegexpQueryParams>>")]
+pub(crate) struct RegexpQuery {
+    pub(crate) field: String,
+    pub(crate) params: RegexpQueryParams,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RegexpQueryParams {
+    value: String,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<OneFieldMap<StringOrStructForSerialization<RegexpQueryParams>>> for RegexpQuery {
+    fn from(one_field_map: OneFieldMap<StringOrStructForSerialization<RegexpQueryParams>>) -> Self {
+        RegexpQuery {
+            field: one_field_map.field,
+            params: one_field_map.value.inner,
+        }
+    }
+}
+
+impl From<String> for RegexpQueryParams {
+    fn from(value: String) -> RegexpQueryParams {
+        RegexpQueryParams {
+            value,
+            case_insensitive: false,
+            boost: None,
+        }
+    }
+}
+
+impl ConvertableToQueryAst for RegexpQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        let regex = if self.params.case_insensitive {
+            format!("(?i:{})", self.params.value)
+        } else {
+            self.params.value
+        };
+        let regex_ast: QueryAst = query_ast::RegexQuery {
+            field: self.field,
+            regex,
+        }
+        .into();
+        Ok(regex_ast.boost(self.params.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regexp_query_convert_to_query_ast() {
+        let regexp_query: RegexpQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "k.*y" } }"#).unwrap();
+        let query_ast = regexp_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Regex(query_ast::RegexQuery {
+                field: "user.id".to_string(),
+                regex: "k.*y".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_regexp_query_case_insensitive() {
+        let regexp_query: RegexpQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "k.*y", "case_insensitive": true } }"#)
+                .unwrap();
+        let query_ast = regexp_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Regex(query_ast::RegexQuery {
+                field: "user.id".to_string(),
+                regex: "(?i:k.*y)".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_regexp_query_nice_errors() {
+        let deser_error = serde_json::from_str::<RegexpQuery>(
+            r#"{ "user.id": { "value": "k.*y", "flags": "ALL" } }"#,
+        )
+        .unwrap_err();
+        assert!(deser_error.to_string().contains("unknown field `flags`"));
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::Deserialize;
+
+use crate::elastic_query_dsl::one_field_map::OneFieldMap;
+use crate::elastic_query_dsl::{ConvertableToQueryAst, StringOrStructForSerialization};
+use crate::not_nan_f32::NotNaNf32;
+use crate::query_ast::{self, QueryAst};
+
+/// `WildcardQuery` as defined in
+/// <https://www.elastic.co/guide/en/elasticsearch/reference/current/query-dsl-wildcard-query.html>
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(from = "OneFieldMap<StringOrStructForSerialization<WildcardQueryParams>>")]
+pub(crate) struct WildcardQuery {
+    pub(crate) field: String,
+    pub(crate) params: WildcardQueryParams,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct WildcardQueryParams {
+    #[serde(alias = "wildcard")]
+    value: String,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    boost: Option<NotNaNf32>,
+}
+
+impl From<OneFieldMap<StringOrStructForSerialization<WildcardQueryParams>>> for WildcardQuery {
+    fn from(
+        one_field_map: OneFieldMap<StringOrStructForSerialization<WildcardQueryParams>>,
+    ) -> Self {
+        WildcardQuery {
+            field: one_field_map.field,
+            params: one_field_map.value.inner,
+        }
+    }
+}
+
+impl From<String> for WildcardQueryParams {
+    fn from(value: String) -> WildcardQueryParams {
+        WildcardQueryParams {
+            value,
+            case_insensitive: false,
+            boost: None,
+        }
+    }
+}
+
+impl ConvertableToQueryAst for WildcardQuery {
+    fn convert_to_query_ast(self) -> anyhow::Result<QueryAst> {
+        if self.params.case_insensitive {
+            anyhow::bail!("`case_insensitive` is not supported in wildcard queries");
+        }
+        let wildcard_ast: QueryAst = query_ast::WildcardQuery {
+            field: self.field,
+            value: self.params.value,
+        }
+        .into();
+        Ok(wildcard_ast.boost(self.params.boost))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_query_deserialization() {
+        let wildcard_query: WildcardQuery =
+            serde_json::from_str(r#"{ "user.id": { "value": "ki*y", "boost": 1.0 } }"#).unwrap();
+        assert_eq!(wildcard_query.field, "user.id");
+        assert_eq!(wildcard_query.params.value, "ki*y");
+        assert!(wildcard_query.params.boost.is_some());
+
+        let wildcard_query: WildcardQuery =
+            serde_json::from_str(r#"{ "user.id": { "wildcard": "ki*y" } }"#).unwrap();
+        assert_eq!(wildcard_query.params.value, "ki*y");
+
+        let wildcard_query: WildcardQuery =
+            serde_json::from_str(r#"{ "user.id": "ki*y" }"#).unwrap();
+        assert_eq!(wildcard_query.params.value, "ki*y");
+    }
+
+    #[test]
+    fn test_wildcard_query_convert_to_query_ast() {
+        let wildcard_query: WildcardQuery =
+            serde_json::from_str(r#"{ "user.id": "ki*y" }"#).unwrap();
+        let query_ast = wildcard_query.convert_to_query_ast().unwrap();
+        assert_eq!(
+            query_ast,
+            QueryAst::Wildcard(query_ast::WildcardQuery {
+                field: "user.id".to_string(),
+                value: "ki*y".to_string(),
+            })
+        );
+    }
+}
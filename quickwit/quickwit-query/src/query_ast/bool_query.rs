@@ -63,6 +63,7 @@ impl BuildTantivyAst for BoolQuery {
         tokenizer_manager: &TokenizerManager,
         search_fields: &[String],
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let mut boolean_query = super::tantivy_query_ast::TantivyBoolQuery::default();
         for must in &self.must {
@@ -71,6 +72,7 @@ impl BuildTantivyAst for BoolQuery {
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             )?;
             boolean_query.must.push(must_leaf);
         }
@@ -80,6 +82,7 @@ impl BuildTantivyAst for BoolQuery {
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             )?;
             boolean_query.must_not.push(must_not_leaf);
         }
@@ -89,6 +92,7 @@ impl BuildTantivyAst for BoolQuery {
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             )?;
             boolean_query.should.push(should_leaf);
         }
@@ -98,6 +102,7 @@ impl BuildTantivyAst for BoolQuery {
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             )?;
             boolean_query.filter.push(filter_leaf);
         }
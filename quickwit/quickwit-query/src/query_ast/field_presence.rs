@@ -76,6 +76,7 @@ impl BuildTantivyAst for FieldPresenceQuery {
         _tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let field_presence_field = schema.get_field(FIELD_PRESENCE_FIELD_NAME).map_err(|_| {
             InvalidQuery::SchemaError("field presence is not available for this split".to_string())
@@ -241,6 +241,7 @@ impl BuildTantivyAst for FullTextQuery {
         tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         full_text_query(
             &self.field,
@@ -332,6 +333,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         assert_eq!(ast.const_predicate(), Some(crate::MatchAllOrNone::MatchAll));
@@ -357,6 +359,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let leaf = ast.as_leaf().unwrap();
@@ -387,6 +390,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let leaf = ast.as_leaf().unwrap();
@@ -416,6 +420,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let bool_query = ast.as_bool_query().unwrap();
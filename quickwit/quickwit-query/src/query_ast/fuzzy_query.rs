@@ -0,0 +1,121 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::Schema as TantivySchema;
+
+use super::utils::normalize_into_single_term;
+use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::InvalidQuery;
+
+/// Maximum Levenshtein distance supported by fuzzy queries.
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// A Fuzzy query matches the documents containing a term within a given Levenshtein distance of
+/// the query value.
+///
+/// The value is not tokenized, but goes through the normalizer of the field.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct FuzzyQuery {
+    pub field: String,
+    pub value: String,
+    /// Maximum number of edits. Must be lower or equal to `MAX_FUZZY_DISTANCE`.
+    pub distance: u8,
+    /// If true, swapping two adjacent characters counts as a single edit.
+    #[serde(default = "default_transposition_cost_one")]
+    pub transposition_cost_one: bool,
+}
+
+fn default_transposition_cost_one() -> bool {
+    true
+}
+
+impl From<FuzzyQuery> for QueryAst {
+    fn from(fuzzy_query: FuzzyQuery) -> Self {
+        Self::Fuzzy(fuzzy_query)
+    }
+}
+
+impl BuildTantivyAst for FuzzyQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _split_id_opt: Option<&str>,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        if self.distance > MAX_FUZZY_DISTANCE {
+            return Err(InvalidQuery::Other(anyhow::anyhow!(
+                "fuzzy query distance must be lower or equal to {MAX_FUZZY_DISTANCE}, got {}",
+                self.distance
+            )));
+        }
+        let (_, term) = normalize_into_single_term(
+            &self.field,
+            &self.value,
+            "fuzzy",
+            schema,
+            tokenizer_manager,
+        )?;
+        let fuzzy_term_query =
+            tantivy::query::FuzzyTermQuery::new(term, self.distance, self.transposition_cost_one);
+        Ok(fuzzy_term_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema as TantivySchema, TEXT};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+
+    #[test]
+    fn test_fuzzy_query_distance_too_high() {
+        let mut schema_builder = TantivySchema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let fuzzy_query = FuzzyQuery {
+            field: "body".to_string(),
+            value: "bond".to_string(),
+            distance: 2,
+            transposition_cost_one: true,
+        };
+        fuzzy_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true, None)
+            .unwrap();
+
+        let fuzzy_query = FuzzyQuery {
+            distance: 3,
+            ..fuzzy_query
+        };
+        let error = fuzzy_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true, None)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "fuzzy query distance must be lower or equal to 2, got 3"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::query::{ConstScorer, EnableScoring, Explanation, Query, Scorer, Weight};
+use tantivy::schema::Schema as TantivySchema;
+use tantivy::{DocId, DocSet, Score, SegmentReader, TantivyError, TERMINATED};
+
+use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::InvalidQuery;
+
+/// An Ids query matches documents given their ids.
+///
+/// Quickwit documents do not have a user-defined id. The id of a document is its address:
+/// `{split_id}:{segment_ord:08x}:{doc_id:08x}`, as returned in the `_id` field of the hits of
+/// the Elasticsearch-compatible API.
+///
+/// Document addresses are only meaningful within a split: the tantivy query of a split only
+/// matches the ids of this split, and the query matches no documents when it is built without a
+/// split ID (see `QueryAst::build_tantivy_query`).
+///
+/// Ids are not stable: they change when splits are merged, so they should only be used to
+/// look up documents returned by a recent search.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct IdsQuery {
+    pub values: Vec<String>,
+}
+
+impl From<IdsQuery> for QueryAst {
+    fn from(ids_query: IdsQuery) -> Self {
+        Self::Ids(ids_query)
+    }
+}
+
+/// Parses a document id into a split id, a segment ord and a doc id.
+fn parse_doc_address(id: &str) -> Option<(&str, u32, DocId)> {
+    let mut id_parts = id.rsplitn(3, ':');
+    let doc_id = u32::from_str_radix(id_parts.next()?, 16).ok()?;
+    let segment_ord = u32::from_str_radix(id_parts.next()?, 16).ok()?;
+    let split_id = id_parts.next()?;
+    Some((split_id, segment_ord, doc_id))
+}
+
+impl IdsQuery {
+    /// Returns the sorted doc ids of the split matched by the query.
+    fn doc_ids(&self, split_id: &str) -> Vec<DocId> {
+        let mut doc_ids: Vec<DocId> = self
+            .values
+            .iter()
+            .filter_map(|value| parse_doc_address(value))
+            // Splits are made of a single segment.
+            .filter(|(value_split_id, segment_ord, _)| {
+                *value_split_id == split_id && *segment_ord == 0
+            })
+            .map(|(_, _, doc_id)| doc_id)
+            .collect();
+        doc_ids.sort_unstable();
+        doc_ids.dedup();
+        doc_ids
+    }
+}
+
+impl BuildTantivyAst for IdsQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        _schema: &TantivySchema,
+        _tokenizer_manager: &TokenizerManager,
+        _search_fields: &[String],
+        _with_validation: bool,
+        split_id_opt: Option<&str>,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let Some(split_id) = split_id_opt else {
+            return Ok(TantivyQueryAst::match_none());
+        };
+        let doc_ids = self.doc_ids(split_id);
+        if doc_ids.is_empty() {
+            return Ok(TantivyQueryAst::match_none());
+        }
+        Ok(DocIdSetQuery { doc_ids }.into())
+    }
+}
+
+/// Tantivy query matching a sorted set of doc ids.
+#[derive(Clone, Debug)]
+struct DocIdSetQuery {
+    doc_ids: Vec<DocId>,
+}
+
+impl Query for DocIdSetQuery {
+    fn weight(&self, _enable_scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        Ok(Box::new(DocIdSetWeight {
+            doc_ids: self.doc_ids.clone(),
+        }))
+    }
+}
+
+struct DocIdSetWeight {
+    doc_ids: Vec<DocId>,
+}
+
+impl Weight for DocIdSetWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let max_doc = reader.max_doc();
+        let doc_ids: Vec<DocId> = self
+            .doc_ids
+            .iter()
+            .copied()
+            .take_while(|doc_id| *doc_id < max_doc)
+            .collect();
+        let doc_set = SortedDocIdSet { doc_ids, cursor: 0 };
+        Ok(Box::new(ConstScorer::new(doc_set, boost)))
+    }
+
+    fn explain(&self, _reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        if self.doc_ids.binary_search(&doc).is_err() {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("DocIdSetQuery", 1.0))
+    }
+}
+
+struct SortedDocIdSet {
+    doc_ids: Vec<DocId>,
+    cursor: usize,
+}
+
+impl DocSet for SortedDocIdSet {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.doc_ids.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc_ids.get(self.cursor).copied().unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        (self.doc_ids.len() - self.cursor) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_doc_address() {
+        assert_eq!(
+            parse_doc_address("split_1:00000000:0000002a"),
+            Some(("split_1", 0, 42))
+        );
+        assert_eq!(parse_doc_address("split_1:0000002a"), None);
+        assert_eq!(parse_doc_address("split_1:00000000:not_hex"), None);
+        assert_eq!(parse_doc_address("1"), None);
+    }
+
+    #[test]
+    fn test_ids_query_doc_ids() {
+        let ids_query = IdsQuery {
+            values: vec![
+                "split_1:00000000:0000002a".to_string(),
+                "split_1:00000000:00000001".to_string(),
+                "split_1:00000001:00000002".to_string(),
+                "split_2:00000000:00000003".to_string(),
+                "split_1:00000000:00000001".to_string(),
+                "invalid".to_string(),
+            ],
+        };
+        assert_eq!(ids_query.doc_ids("split_1"), vec![1, 42]);
+        assert_eq!(ids_query.doc_ids("split_2"), vec![3]);
+        assert!(ids_query.doc_ids("split_3").is_empty());
+    }
+
+    #[test]
+    fn test_sorted_doc_id_set() {
+        let mut doc_set = SortedDocIdSet {
+            doc_ids: vec![1, 42],
+            cursor: 0,
+        };
+        assert_eq!(doc_set.doc(), 1);
+        assert_eq!(doc_set.advance(), 42);
+        assert_eq!(doc_set.advance(), TERMINATED);
+        assert_eq!(doc_set.advance(), TERMINATED);
+    }
+}
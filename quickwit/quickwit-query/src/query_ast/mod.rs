@@ -26,8 +26,12 @@ use crate::tokenizers::TokenizerManager;
 mod bool_query;
 mod field_presence;
 mod full_text_query;
+mod fuzzy_query;
+mod ids_query;
 mod phrase_prefix_query;
+mod prefix_query;
 mod range_query;
+mod regex_query;
 mod tantivy_query_ast;
 mod term_query;
 mod term_set_query;
@@ -39,8 +43,12 @@ mod wildcard_query;
 pub use bool_query::BoolQuery;
 pub use field_presence::FieldPresenceQuery;
 pub use full_text_query::{FullTextMode, FullTextParams, FullTextQuery};
+pub use fuzzy_query::{FuzzyQuery, MAX_FUZZY_DISTANCE};
+pub use ids_query::IdsQuery;
 pub use phrase_prefix_query::PhrasePrefixQuery;
+pub use prefix_query::PrefixQuery;
 pub use range_query::RangeQuery;
+pub use regex_query::RegexQuery;
 use tantivy_query_ast::TantivyQueryAst;
 pub use term_query::TermQuery;
 pub use term_set_query::TermSetQuery;
@@ -63,6 +71,10 @@ pub enum QueryAst {
     Range(RangeQuery),
     UserInput(UserInputQuery),
     Wildcard(WildcardQuery),
+    Prefix(PrefixQuery),
+    Regex(RegexQuery),
+    Fuzzy(FuzzyQuery),
+    Ids(IdsQuery),
     MatchAll,
     MatchNone,
     Boost {
@@ -103,7 +115,11 @@ impl QueryAst {
             | ast @ QueryAst::MatchNone
             | ast @ QueryAst::FieldPresence(_)
             | ast @ QueryAst::Range(_)
-            | ast @ QueryAst::Wildcard(_) => Ok(ast),
+            | ast @ QueryAst::Wildcard(_)
+            | ast @ QueryAst::Prefix(_)
+            | ast @ QueryAst::Regex(_)
+            | ast @ QueryAst::Fuzzy(_)
+            | ast @ QueryAst::Ids(_) => Ok(ast),
             QueryAst::UserInput(user_text_query) => {
                 user_text_query.parse_user_query(default_search_fields)
             }
@@ -117,6 +133,58 @@ impl QueryAst {
         }
     }
 
+    /// Replaces the queries targeting fields missing from the schema with
+    /// [`QueryAst::MatchNone`].
+    ///
+    /// The splits created before a doc mapping update do not have the fields added by the update,
+    /// so none of their documents can match a query on these fields.
+    pub fn match_none_on_missing_fields(&mut self, schema: &TantivySchema) {
+        let is_missing_field = match self {
+            QueryAst::Bool(BoolQuery {
+                must,
+                must_not,
+                should,
+                filter,
+            }) => {
+                for ast in must
+                    .iter_mut()
+                    .chain(must_not.iter_mut())
+                    .chain(should.iter_mut())
+                    .chain(filter.iter_mut())
+                {
+                    ast.match_none_on_missing_fields(schema);
+                }
+                false
+            }
+            QueryAst::Boost { underlying, .. } => {
+                underlying.match_none_on_missing_fields(schema);
+                false
+            }
+            QueryAst::TermSet(term_set_query) => {
+                term_set_query
+                    .terms_per_field
+                    .retain(|field, _| !utils::is_missing_field(field, schema));
+                term_set_query.terms_per_field.is_empty()
+            }
+            QueryAst::Term(TermQuery { field, .. })
+            | QueryAst::FieldPresence(FieldPresenceQuery { field })
+            | QueryAst::FullText(FullTextQuery { field, .. })
+            | QueryAst::PhrasePrefix(PhrasePrefixQuery { field, .. })
+            | QueryAst::Range(RangeQuery { field, .. })
+            | QueryAst::Wildcard(WildcardQuery { field, .. })
+            | QueryAst::Prefix(PrefixQuery { field, .. })
+            | QueryAst::Regex(RegexQuery { field, .. })
+            | QueryAst::Fuzzy(FuzzyQuery { field, .. }) => utils::is_missing_field(field, schema),
+            QueryAst::UserInput(_)
+            | QueryAst::Ids(_)
+            | QueryAst::MatchAll
+            | QueryAst::MatchNone => false,
+        };
+        if is_missing_field {
+            *self = QueryAst::MatchNone;
+        }
+    }
+
     pub fn boost(self, scale_boost_opt: Option<NotNaNf32>) -> Self {
         let Some(scale_boost) = scale_boost_opt else {
             return self;
@@ -154,6 +222,7 @@ trait BuildTantivyAst {
         tokenizer_manager: &TokenizerManager,
         search_fields: &[String],
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery>;
 
     /// This method is meant to be called, but should never be overloaded.
@@ -163,9 +232,15 @@ trait BuildTantivyAst {
         tokenizer_manager: &TokenizerManager,
         search_fields: &[String],
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
-        let tantivy_ast_res =
-            self.build_tantivy_ast_impl(schema, tokenizer_manager, search_fields, with_validation);
+        let tantivy_ast_res = self.build_tantivy_ast_impl(
+            schema,
+            tokenizer_manager,
+            search_fields,
+            with_validation,
+            split_id_opt,
+        );
         if !with_validation && tantivy_ast_res.is_err() {
             return match tantivy_ast_res {
                 res @ Ok(_) | res @ Err(InvalidQuery::UserQueryNotParsed) => res,
@@ -183,6 +258,7 @@ impl BuildTantivyAst for QueryAst {
         tokenizer_manager: &TokenizerManager,
         search_fields: &[String],
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         match self {
             QueryAst::Bool(bool_query) => bool_query.build_tantivy_ast_call(
@@ -190,18 +266,21 @@ impl BuildTantivyAst for QueryAst {
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::Term(term_query) => term_query.build_tantivy_ast_call(
                 schema,
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::Range(range_query) => range_query.build_tantivy_ast_call(
                 schema,
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::MatchAll => Ok(TantivyQueryAst::match_all()),
             QueryAst::MatchNone => Ok(TantivyQueryAst::match_none()),
@@ -211,6 +290,7 @@ impl BuildTantivyAst for QueryAst {
                     tokenizer_manager,
                     search_fields,
                     with_validation,
+                    split_id_opt,
                 )?;
                 let boost_query = TantivyBoostQuery::new(underlying.into(), (*boost).into());
                 Ok(boost_query.into())
@@ -220,47 +300,96 @@ impl BuildTantivyAst for QueryAst {
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::FullText(full_text_query) => full_text_query.build_tantivy_ast_call(
                 schema,
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::PhrasePrefix(phrase_prefix_query) => phrase_prefix_query
-                .build_tantivy_ast_call(schema, tokenizer_manager, search_fields, with_validation),
+                .build_tantivy_ast_call(
+                    schema,
+                    tokenizer_manager,
+                    search_fields,
+                    with_validation,
+                    split_id_opt,
+                ),
             QueryAst::UserInput(user_text_query) => user_text_query.build_tantivy_ast_call(
                 schema,
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::FieldPresence(field_presence) => field_presence.build_tantivy_ast_call(
                 schema,
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
             ),
             QueryAst::Wildcard(wildcard) => wildcard.build_tantivy_ast_call(
                 schema,
                 tokenizer_manager,
                 search_fields,
                 with_validation,
+                split_id_opt,
+            ),
+            QueryAst::Prefix(prefix) => prefix.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+                split_id_opt,
+            ),
+            QueryAst::Regex(regex) => regex.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+                split_id_opt,
+            ),
+            QueryAst::Fuzzy(fuzzy) => fuzzy.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+                split_id_opt,
+            ),
+            QueryAst::Ids(ids) => ids.build_tantivy_ast_call(
+                schema,
+                tokenizer_manager,
+                search_fields,
+                with_validation,
+                split_id_opt,
             ),
         }
     }
 }
 
 impl QueryAst {
+    /// Builds the tantivy query of the AST.
+    ///
+    /// `split_id_opt` is the ID of the split the query is built for. Document ids are only
+    /// meaningful within a split, so `ids` queries match no documents without it.
     pub fn build_tantivy_query(
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
         search_fields: &[String],
         with_validation: bool,
+        split_id_opt: Option<&str>,
     ) -> Result<Box<dyn crate::TantivyQuery>, InvalidQuery> {
-        let tantivy_query_ast =
-            self.build_tantivy_ast_call(schema, tokenizer_manager, search_fields, with_validation)?;
+        let tantivy_query_ast = self.build_tantivy_ast_call(
+            schema,
+            tokenizer_manager,
+            search_fields,
+            with_validation,
+            split_id_opt,
+        )?;
         Ok(tantivy_query_ast.simplify().into())
     }
 }
@@ -322,11 +451,19 @@ pub fn query_ast_from_user_text(user_text: &str, default_fields: Option<Vec<Stri
 
 #[cfg(test)]
 mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use tantivy::schema::{Schema, TEXT};
+
     use crate::query_ast::tantivy_query_ast::TantivyQueryAst;
     use crate::query_ast::{
-        query_ast_from_user_text, BoolQuery, BuildTantivyAst, QueryAst, UserInputQuery,
+        query_ast_from_user_text, BoolQuery, BuildTantivyAst, FieldPresenceQuery, IdsQuery,
+        PrefixQuery, QueryAst, TermQuery, TermSetQuery, UserInputQuery,
+    };
+    use crate::{
+        create_default_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery, MatchAllOrNone,
+        NotNaNf32,
     };
-    use crate::{create_default_quickwit_tokenizer_manager, BooleanOperand, InvalidQuery};
 
     #[test]
     fn test_user_query_not_parsed() {
@@ -343,6 +480,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap_err();
         assert!(matches!(
@@ -367,6 +505,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         assert_eq!(&tantivy_query_ast, &TantivyQueryAst::match_all(),);
@@ -394,6 +533,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let tantivy_query_ast_simplified = tantivy_query_ast.simplify();
@@ -444,4 +584,104 @@ mod tests {
         };
         assert_eq!(input_query.default_operator, BooleanOperand::And);
     }
+
+    #[test]
+    fn test_query_ast_ids_query_requires_split_id() {
+        let schema = Schema::builder().build();
+        let query_ast: QueryAst = BoolQuery {
+            must: vec![QueryAst::MatchAll],
+            filter: vec![IdsQuery {
+                values: vec!["split_1:00000000:00000001".to_string()],
+            }
+            .into()],
+            ..Default::default()
+        }
+        .into();
+        let build_tantivy_ast = |split_id_opt: Option<&str>| {
+            query_ast
+                .build_tantivy_ast_call(
+                    &schema,
+                    &create_default_quickwit_tokenizer_manager(),
+                    &[],
+                    true,
+                    split_id_opt,
+                )
+                .unwrap()
+                .simplify()
+        };
+        assert_eq!(
+            build_tantivy_ast(None).const_predicate(),
+            Some(MatchAllOrNone::MatchNone)
+        );
+        assert_eq!(
+            build_tantivy_ast(Some("split_2")).const_predicate(),
+            Some(MatchAllOrNone::MatchNone)
+        );
+        assert!(build_tantivy_ast(Some("split_1"))
+            .const_predicate()
+            .is_none());
+    }
+
+    #[test]
+    fn test_query_ast_match_none_on_missing_fields() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT);
+        let schema = schema_builder.build();
+
+        let term_query = |field: &str| -> QueryAst {
+            TermQuery {
+                field: field.to_string(),
+                value: "hello".to_string(),
+            }
+            .into()
+        };
+        let mut query_ast: QueryAst = BoolQuery {
+            must: vec![term_query("title")],
+            must_not: vec![FieldPresenceQuery {
+                field: "severity".to_string(),
+            }
+            .into()],
+            should: vec![
+                term_query("severity").boost(Some(NotNaNf32::try_from(2.0).unwrap())),
+                PrefixQuery {
+                    field: "severity".to_string(),
+                    value: "err".to_string(),
+                }
+                .into(),
+            ],
+            filter: vec![TermSetQuery {
+                terms_per_field: HashMap::from_iter([
+                    (
+                        "title".to_string(),
+                        BTreeSet::from_iter(["hello".to_string()]),
+                    ),
+                    (
+                        "severity".to_string(),
+                        BTreeSet::from_iter(["error".to_string()]),
+                    ),
+                ]),
+            }
+            .into()],
+        }
+        .into();
+        query_ast.match_none_on_missing_fields(&schema);
+
+        let expected_query_ast: QueryAst = BoolQuery {
+            must: vec![term_query("title")],
+            must_not: vec![QueryAst::MatchNone],
+            should: vec![
+                QueryAst::MatchNone.boost(Some(NotNaNf32::try_from(2.0).unwrap())),
+                QueryAst::MatchNone,
+            ],
+            filter: vec![TermSetQuery {
+                terms_per_field: HashMap::from_iter([(
+                    "title".to_string(),
+                    BTreeSet::from_iter(["hello".to_string()]),
+                )]),
+            }
+            .into()],
+        }
+        .into();
+        assert_eq!(query_ast, expected_query_ast);
+    }
 }
@@ -116,6 +116,7 @@ impl BuildTantivyAst for PhrasePrefixQuery {
         tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let (_, terms) = self.get_terms(schema, tokenizer_manager)?;
 
@@ -0,0 +1,113 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use tantivy::schema::{Field, Schema as TantivySchema};
+use tantivy::Term;
+
+use super::utils::normalize_into_single_term;
+use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::InvalidQuery;
+
+/// A Prefix query matches the documents containing a term starting with a given prefix.
+///
+/// The prefix is not tokenized, but goes through the normalizer of the field.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct PrefixQuery {
+    pub field: String,
+    pub value: String,
+}
+
+impl From<PrefixQuery> for QueryAst {
+    fn from(prefix_query: PrefixQuery) -> Self {
+        Self::Prefix(prefix_query)
+    }
+}
+
+impl PrefixQuery {
+    pub fn extract_prefix_term(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+    ) -> Result<(Field, Term), InvalidQuery> {
+        normalize_into_single_term(
+            &self.field,
+            &self.value,
+            "prefix",
+            schema,
+            tokenizer_manager,
+        )
+    }
+}
+
+impl BuildTantivyAst for PrefixQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _split_id_opt: Option<&str>,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let (_, term) = self.extract_prefix_term(schema, tokenizer_manager)?;
+        let mut phrase_prefix_query =
+            tantivy::query::PhrasePrefixQuery::new_with_offset(vec![(0, term)]);
+        phrase_prefix_query.set_max_expansions(u32::MAX);
+        Ok(phrase_prefix_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema as TantivySchema, STRING, TEXT};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+
+    #[test]
+    fn test_prefix_query_extract_prefix_term() {
+        let mut schema_builder = TantivySchema::builder();
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let prefix_query = PrefixQuery {
+            field: "body".to_string(),
+            value: "Bon".to_string(),
+        };
+        let (field, term) = prefix_query
+            .extract_prefix_term(&schema, &tokenizer_manager)
+            .unwrap();
+        assert_eq!(field, body_field);
+        assert_eq!(term, Term::from_field_text(body_field, "bon"));
+
+        let prefix_query = PrefixQuery {
+            field: "id".to_string(),
+            value: "Bon".to_string(),
+        };
+        let (field, term) = prefix_query
+            .extract_prefix_term(&schema, &tokenizer_manager)
+            .unwrap();
+        assert_eq!(field, id_field);
+        assert_eq!(term, Term::from_field_text(id_field, "Bon"));
+    }
+}
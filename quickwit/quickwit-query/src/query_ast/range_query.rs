@@ -227,6 +227,7 @@ impl BuildTantivyAst for RangeQuery {
         _tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let (_field, field_entry, _path) =
             super::utils::find_field_or_hit_dynamic(&self.field, schema)?;
@@ -399,6 +400,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap()
             .simplify();
@@ -449,6 +451,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap_err();
         assert!(
@@ -461,7 +464,8 @@ mod tests {
                     &schema,
                     &create_default_quickwit_tokenizer_manager(),
                     &[],
-                    false
+                    false,
+                    None
                 )
                 .unwrap()
                 .const_predicate(),
@@ -484,6 +488,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap_err();
         assert!(matches!(
@@ -497,7 +502,8 @@ mod tests {
                     &schema,
                     &create_default_quickwit_tokenizer_manager(),
                     &[],
-                    false
+                    false,
+                    None
                 )
                 .unwrap()
                 .const_predicate(),
@@ -519,6 +525,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let TantivyBoolQuery {
@@ -567,6 +574,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap_err();
         assert!(matches!(err, InvalidQuery::SchemaError { .. }));
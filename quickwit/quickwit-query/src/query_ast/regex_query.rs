@@ -0,0 +1,191 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::Write;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tantivy::json_utils::JsonTermWriter;
+use tantivy::schema::{Field, FieldType, Schema as TantivySchema};
+use tantivy::Term;
+
+use super::{BuildTantivyAst, QueryAst};
+use crate::query_ast::TantivyQueryAst;
+use crate::tokenizers::TokenizerManager;
+use crate::{find_field_or_hit_dynamic, InvalidQuery};
+
+/// A Regex query matches the documents containing a term matching a regular expression.
+///
+/// The regular expression has to match the entire term. The terms are not normalized, so on a
+/// field using a lowercasing tokenizer, `Hel+o` will never match anything.
+#[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
+pub struct RegexQuery {
+    pub field: String,
+    pub regex: String,
+}
+
+impl From<RegexQuery> for QueryAst {
+    fn from(regex_query: RegexQuery) -> Self {
+        Self::Regex(regex_query)
+    }
+}
+
+/// Escapes all of the ASCII characters of a text that are not alphanumeric, so that it can be used
+/// as a literal in a regular expression.
+///
+/// Contrary to `regex_syntax::escape`, this also escapes the non-printable characters used as
+/// separators in JSON terms.
+pub(crate) fn escape_regex_literal(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii() && !c.is_ascii_alphanumeric() {
+            write!(escaped, "\\x{:02x}", c as u32).expect("writing to a string should not fail");
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Returns the field targeted by a regex, and the regex to run on its term dictionary.
+///
+/// For JSON fields, the keys of the term dictionary are prefixed by the JSON path of the term and
+/// its type, so the regex is prefixed accordingly.
+pub(crate) fn field_and_term_dictionary_regex(
+    full_path: &str,
+    regex: &str,
+    query_type: &str,
+    schema: &TantivySchema,
+) -> Result<(Field, String), InvalidQuery> {
+    let (field, field_entry, json_path) = find_field_or_hit_dynamic(full_path, schema)?;
+    match field_entry.field_type() {
+        FieldType::Str(text_options) => {
+            if text_options.get_indexing_options().is_none() {
+                return Err(InvalidQuery::SchemaError(format!(
+                    "field {} is not full-text searchable",
+                    field_entry.name()
+                )));
+            }
+            Ok((field, regex.to_string()))
+        }
+        FieldType::JsonObject(json_options) => {
+            if json_options.get_text_indexing_options().is_none() {
+                return Err(InvalidQuery::SchemaError(format!(
+                    "field {} is not full-text searchable",
+                    field_entry.name()
+                )));
+            }
+            let mut term = Term::with_capacity(100);
+            let mut json_term_writer = JsonTermWriter::from_field_and_json_path(
+                field,
+                json_path,
+                json_options.is_expand_dots_enabled(),
+                &mut term,
+            );
+            json_term_writer.set_str("");
+            let term_prefix = std::str::from_utf8(json_term_writer.term().serialized_value_bytes())
+                .context("JSON term prefix is not valid UTF-8")?;
+            let term_dictionary_regex = format!("{}(?:{regex})", escape_regex_literal(term_prefix));
+            Ok((field, term_dictionary_regex))
+        }
+        _ => Err(InvalidQuery::SchemaError(format!(
+            "trying to run a {query_type} query on a non-text field"
+        ))),
+    }
+}
+
+impl BuildTantivyAst for RegexQuery {
+    fn build_tantivy_ast_impl(
+        &self,
+        schema: &TantivySchema,
+        _tokenizer_manager: &TokenizerManager,
+        _search_fields: &[String],
+        _with_validation: bool,
+        _split_id_opt: Option<&str>,
+    ) -> Result<TantivyQueryAst, InvalidQuery> {
+        let (field, term_dictionary_regex) =
+            field_and_term_dictionary_regex(&self.field, &self.regex, "regex", schema)?;
+        let regex_query = tantivy::query::RegexQuery::from_pattern(&term_dictionary_regex, field)
+            .with_context(|| format!("invalid regex `{}`", self.regex))?;
+        Ok(regex_query.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema as TantivySchema, STRING, TEXT};
+
+    use super::*;
+    use crate::create_default_quickwit_tokenizer_manager;
+
+    #[test]
+    fn test_escape_regex_literal() {
+        assert_eq!(escape_regex_literal("abc"), "abc");
+        assert_eq!(escape_regex_literal("a.b*"), "a\\x2eb\\x2a");
+        assert_eq!(escape_regex_literal("a\u{0}s"), "a\\x00s");
+        assert_eq!(escape_regex_literal("été"), "été");
+    }
+
+    #[test]
+    fn test_regex_query_on_text_field() {
+        let mut schema_builder = TantivySchema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_u64_field("count", tantivy::schema::INDEXED);
+        let schema = schema_builder.build();
+        let tokenizer_manager = create_default_quickwit_tokenizer_manager();
+
+        let regex_query = RegexQuery {
+            field: "body".to_string(),
+            regex: "hel+o".to_string(),
+        };
+        let tantivy_query_ast = regex_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true, None)
+            .unwrap();
+        assert!(tantivy_query_ast.as_leaf().is_some());
+
+        let invalid_regex_query = RegexQuery {
+            field: "body".to_string(),
+            regex: "hel(o".to_string(),
+        };
+        let error = invalid_regex_query
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true, None)
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid regex `hel(o`"));
+
+        let regex_query_on_numeric_field = RegexQuery {
+            field: "count".to_string(),
+            regex: "1.*".to_string(),
+        };
+        let error = regex_query_on_numeric_field
+            .build_tantivy_ast_call(&schema, &tokenizer_manager, &[], true, None)
+            .unwrap_err();
+        assert!(matches!(error, InvalidQuery::SchemaError(_)));
+    }
+
+    #[test]
+    fn test_field_and_term_dictionary_regex_on_json_field() {
+        let mut schema_builder = TantivySchema::builder();
+        schema_builder.add_json_field("attributes", STRING);
+        let schema = schema_builder.build();
+        let (_field, term_dictionary_regex) =
+            field_and_term_dictionary_regex("attributes.server", "web.*", "regex", &schema)
+                .unwrap();
+        assert_eq!(term_dictionary_regex, "server\\x00s(?:web.*)");
+    }
+}
@@ -58,6 +58,7 @@ impl BuildTantivyAst for TermQuery {
         tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let full_text_params = FullTextParams {
             tokenizer: Some("raw".to_string()),
@@ -145,6 +146,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
@@ -169,6 +171,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
@@ -193,6 +196,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
@@ -217,6 +221,7 @@ mod tests {
                 &create_default_quickwit_tokenizer_manager(),
                 &[],
                 true,
+                None,
             )
             .unwrap();
         let leaf = tantivy_query_ast.as_leaf().unwrap();
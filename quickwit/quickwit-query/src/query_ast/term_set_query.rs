@@ -56,8 +56,13 @@ impl TermSetQuery {
                     field: full_path.to_string(),
                     value: value.to_string(),
                 };
-                let ast =
-                    term_query.build_tantivy_ast_call(schema, tokenizer_manager, &[], false)?;
+                let ast = term_query.build_tantivy_ast_call(
+                    schema,
+                    tokenizer_manager,
+                    &[],
+                    false,
+                    None,
+                )?;
                 let tantivy_query: Box<dyn crate::TantivyQuery> = ast.simplify().into();
                 tantivy_query.query_terms(&mut |term, _| {
                     terms.insert(term.clone());
@@ -75,6 +80,7 @@ impl BuildTantivyAst for TermSetQuery {
         tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
         let terms_it = self.make_term_iterator(schema, tokenizer_manager)?;
         let term_set_query = tantivy::query::TermSetQuery::new(terms_it);
@@ -90,6 +90,7 @@ impl BuildTantivyAst for UserInputQuery {
         _tokenizer_manager: &TokenizerManager,
         _default_search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, crate::InvalidQuery> {
         Err(InvalidQuery::UserQueryNotParsed)
     }
@@ -304,6 +305,7 @@ mod tests {
                     &create_default_quickwit_tokenizer_manager(),
                     &[],
                     true,
+                    None,
                 )
                 .unwrap_err();
             assert!(matches!(invalid_query, InvalidQuery::UserQueryNotParsed));
@@ -315,6 +317,7 @@ mod tests {
                     &create_default_quickwit_tokenizer_manager(),
                     &[],
                     false,
+                    None,
                 )
                 .unwrap_err();
             assert!(matches!(invalid_query, InvalidQuery::UserQueryNotParsed));
@@ -17,12 +17,14 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use anyhow::{anyhow, Context};
 use tantivy::json_utils::{convert_to_fast_value_and_get_term, JsonTermWriter};
 use tantivy::query::TermQuery as TantivyTermQuery;
 use tantivy::schema::{
     Field, FieldEntry, FieldType, IndexRecordOption, JsonObjectOptions, Schema as TantivySchema,
     Type,
 };
+use tantivy::tokenizer::TextAnalyzer;
 use tantivy::Term;
 
 use crate::json_literal::InterpretUserInput;
@@ -68,6 +70,84 @@ pub fn find_field_or_hit_dynamic<'a>(
     Ok((field, field_entry, path))
 }
 
+/// Returns true if the field does not exist in the schema and cannot be captured by the dynamic
+/// field either.
+pub(crate) fn is_missing_field(full_path: &str, schema: &TantivySchema) -> bool {
+    matches!(
+        find_field_or_hit_dynamic(full_path, schema),
+        Err(InvalidQuery::FieldDoesNotExist { .. })
+    )
+}
+
+/// Returns the normalizer associated with the tokenizer of a text or JSON field.
+pub(crate) fn get_field_normalizer(
+    field_entry: &FieldEntry,
+    query_type: &str,
+    tokenizer_manager: &TokenizerManager,
+) -> Result<TextAnalyzer, InvalidQuery> {
+    let text_field_indexing_opt = match field_entry.field_type() {
+        FieldType::Str(text_options) => text_options.get_indexing_options(),
+        FieldType::JsonObject(json_options) => json_options.get_text_indexing_options(),
+        _ => {
+            return Err(InvalidQuery::SchemaError(format!(
+                "trying to run a {query_type} query on a non-text field"
+            )));
+        }
+    };
+    let text_field_indexing = text_field_indexing_opt.ok_or_else(|| {
+        InvalidQuery::SchemaError(format!(
+            "field {} is not full-text searchable",
+            field_entry.name()
+        ))
+    })?;
+    let tokenizer_name = text_field_indexing.tokenizer();
+    let normalizer = tokenizer_manager
+        .get_normalizer(tokenizer_name)
+        .with_context(|| format!("no tokenizer named `{}` is registered", tokenizer_name))?;
+    Ok(normalizer)
+}
+
+/// Normalizes a text and returns the single term it generates.
+///
+/// Term-level queries (prefix, wildcard, fuzzy) do not tokenize their text: it only goes through
+/// the normalizer of the field (lowercasing, for instance).
+pub(crate) fn normalize_into_single_term(
+    full_path: &str,
+    text: &str,
+    query_type: &str,
+    schema: &TantivySchema,
+    tokenizer_manager: &TokenizerManager,
+) -> Result<(Field, Term), InvalidQuery> {
+    let (field, field_entry, json_path) = find_field_or_hit_dynamic(full_path, schema)?;
+    let mut normalizer = get_field_normalizer(field_entry, query_type, tokenizer_manager)?;
+    let mut token_stream = normalizer.token_stream(text);
+    let mut terms = Vec::new();
+    if let FieldType::JsonObject(json_options) = field_entry.field_type() {
+        let mut term = Term::with_capacity(100);
+        let mut json_term_writer = JsonTermWriter::from_field_and_json_path(
+            field,
+            json_path,
+            json_options.is_expand_dots_enabled(),
+            &mut term,
+        );
+        token_stream.process(&mut |token| {
+            json_term_writer.set_str(&token.text);
+            terms.push(json_term_writer.term().clone());
+        });
+    } else {
+        token_stream.process(&mut |token| {
+            terms.push(Term::from_field_text(field, &token.text));
+        });
+    }
+    let term = terms
+        .pop()
+        .with_context(|| format!("{query_type} query generated no term"))?;
+    if !terms.is_empty() {
+        return Err(anyhow!("{query_type} query generated more than one term").into());
+    }
+    Ok((field, term))
+}
+
 /// Creates a full text query.
 ///
 /// If tokenize is set to true, the text will be tokenized.
@@ -21,8 +21,8 @@ use crate::not_nan_f32::NotNaNf32;
 use crate::query_ast::field_presence::FieldPresenceQuery;
 use crate::query_ast::user_input_query::UserInputQuery;
 use crate::query_ast::{
-    BoolQuery, FullTextQuery, PhrasePrefixQuery, QueryAst, RangeQuery, TermQuery, TermSetQuery,
-    WildcardQuery,
+    BoolQuery, FullTextQuery, FuzzyQuery, IdsQuery, PhrasePrefixQuery, PrefixQuery, QueryAst,
+    RangeQuery, RegexQuery, TermQuery, TermSetQuery, WildcardQuery,
 };
 
 /// Simple trait to implement a Visitor over the QueryAst.
@@ -45,6 +45,10 @@ pub trait QueryAstVisitor<'a> {
             QueryAst::UserInput(user_text_query) => self.visit_user_text(user_text_query),
             QueryAst::FieldPresence(exists) => self.visit_exists(exists),
             QueryAst::Wildcard(wildcard) => self.visit_wildcard(wildcard),
+            QueryAst::Prefix(prefix) => self.visit_prefix(prefix),
+            QueryAst::Regex(regex) => self.visit_regex(regex),
+            QueryAst::Fuzzy(fuzzy) => self.visit_fuzzy(fuzzy),
+            QueryAst::Ids(ids) => self.visit_ids(ids),
         }
     }
 
@@ -111,4 +115,20 @@ pub trait QueryAstVisitor<'a> {
     fn visit_wildcard(&mut self, _wildcard_query: &'a WildcardQuery) -> Result<(), Self::Err> {
         Ok(())
     }
+
+    fn visit_prefix(&mut self, _prefix_query: &'a PrefixQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_regex(&mut self, _regex_query: &'a RegexQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_fuzzy(&mut self, _fuzzy_query: &'a FuzzyQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    fn visit_ids(&mut self, _ids_query: &'a IdsQuery) -> Result<(), Self::Err> {
+        Ok(())
+    }
 }
@@ -19,10 +19,11 @@
 
 use anyhow::{anyhow, bail, Context};
 use serde::{Deserialize, Serialize};
-use tantivy::json_utils::JsonTermWriter;
-use tantivy::schema::{Field, FieldType, Schema as TantivySchema};
+use tantivy::schema::{Field, Schema as TantivySchema};
 use tantivy::Term;
 
+use super::regex_query::{escape_regex_literal, field_and_term_dictionary_regex};
+use super::utils::{get_field_normalizer, normalize_into_single_term};
 use super::{BuildTantivyAst, QueryAst};
 use crate::query_ast::TantivyQueryAst;
 use crate::tokenizers::TokenizerManager;
@@ -30,7 +31,9 @@ use crate::{find_field_or_hit_dynamic, InvalidQuery};
 
 /// A Wildcard query allows to match 'bond' with a query like 'b*d'.
 ///
-/// At the moment, only wildcard at end of term is supported.
+/// `*` matches any sequence of characters, `?` matches a single character, and `\` escapes the
+/// character that follows it. Patterns with a single `*` in final position are run as prefix
+/// queries, the others are run as regex queries over the term dictionary.
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize, Clone)]
 pub struct WildcardQuery {
     pub field: String,
@@ -53,16 +56,6 @@ impl WildcardQuery {
     }
 }
 
-fn extract_unique_token(mut tokens: Vec<Term>) -> anyhow::Result<Term> {
-    let term = tokens
-        .pop()
-        .with_context(|| "wildcard query generated no term")?;
-    if !tokens.is_empty() {
-        anyhow::bail!("wildcard query generated more than one term");
-    }
-    Ok(term)
-}
-
 fn unescape_with_final_wildcard(phrase: &str) -> anyhow::Result<String> {
     enum State {
         Normal,
@@ -111,77 +104,66 @@ fn unescape_with_final_wildcard(phrase: &str) -> anyhow::Result<String> {
     Ok(phrase)
 }
 
+/// Converts a wildcard pattern into a regex, normalizing its literal parts.
+fn wildcard_to_regex(pattern: &str, normalize: &mut impl FnMut(&str) -> String) -> String {
+    let mut regex = String::with_capacity(pattern.len());
+    let mut literal = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' | '?' => {
+                if !literal.is_empty() {
+                    regex.push_str(&escape_regex_literal(&normalize(&literal)));
+                    literal.clear();
+                }
+                regex.push_str(if c == '*' { ".*" } else { "." });
+            }
+            '\\' => {
+                if let Some(escaped_char) = chars.next() {
+                    literal.push(escaped_char);
+                }
+            }
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        regex.push_str(&escape_regex_literal(&normalize(&literal)));
+    }
+    regex
+}
+
 impl WildcardQuery {
-    // TODO this method will probably disappear once we support the full semantic of
-    // wildcard queries
+    /// Returns true if the pattern only contains a single `*` wildcard, in final position.
+    pub fn is_prefix_pattern(&self) -> bool {
+        unescape_with_final_wildcard(&self.value).is_ok()
+    }
+
     pub fn extract_prefix_term(
         &self,
         schema: &TantivySchema,
         tokenizer_manager: &TokenizerManager,
     ) -> Result<(Field, Term), InvalidQuery> {
-        let (field, field_entry, json_path) = find_field_or_hit_dynamic(&self.field, schema)?;
-        let field_type = field_entry.field_type();
-
         let prefix = unescape_with_final_wildcard(&self.value)?;
+        normalize_into_single_term(&self.field, &prefix, "wildcard", schema, tokenizer_manager)
+    }
 
-        match field_type {
-            FieldType::Str(ref text_options) => {
-                let text_field_indexing = text_options.get_indexing_options().ok_or_else(|| {
-                    InvalidQuery::SchemaError(format!(
-                        "field {} is not full-text searchable",
-                        field_entry.name()
-                    ))
-                })?;
-                let tokenizer_name = text_field_indexing.tokenizer();
-                let mut normalizer = tokenizer_manager
-                    .get_normalizer(tokenizer_name)
-                    .with_context(|| {
-                        format!("no tokenizer named `{}` is registered", tokenizer_name)
-                    })?;
-                let mut token_stream = normalizer.token_stream(&prefix);
-                let mut tokens = Vec::new();
-                token_stream.process(&mut |token| {
-                    let term: Term = Term::from_field_text(field, &token.text);
-                    tokens.push(term);
-                });
-                let term = extract_unique_token(tokens)?;
-                Ok((field, term))
-            }
-            FieldType::JsonObject(json_options) => {
-                let text_field_indexing =
-                    json_options.get_text_indexing_options().ok_or_else(|| {
-                        InvalidQuery::SchemaError(format!(
-                            "field {} is not full-text searchable",
-                            field_entry.name()
-                        ))
-                    })?;
-                let tokenizer_name = text_field_indexing.tokenizer();
-                let mut normalizer = tokenizer_manager
-                    .get_normalizer(tokenizer_name)
-                    .with_context(|| {
-                        format!("no tokenizer named `{}` is registered", tokenizer_name)
-                    })?;
-                let mut token_stream = normalizer.token_stream(&prefix);
-                let mut tokens = Vec::new();
-                let mut term = Term::with_capacity(100);
-                let mut json_term_writer = JsonTermWriter::from_field_and_json_path(
-                    field,
-                    json_path,
-                    json_options.is_expand_dots_enabled(),
-                    &mut term,
-                );
-
-                token_stream.process(&mut |token| {
-                    json_term_writer.set_str(&token.text);
-                    tokens.push(json_term_writer.term().clone());
-                });
-                let term = extract_unique_token(tokens)?;
-                Ok((field, term))
-            }
-            _ => Err(InvalidQuery::SchemaError(
-                "trying to run a Wildcard query on a non-text field".to_string(),
-            )),
-        }
+    /// Returns the field targeted by the query, and the regex to run on its term dictionary.
+    fn extract_term_dictionary_regex(
+        &self,
+        schema: &TantivySchema,
+        tokenizer_manager: &TokenizerManager,
+    ) -> Result<(Field, String), InvalidQuery> {
+        let (_, field_entry, _) = find_field_or_hit_dynamic(&self.field, schema)?;
+        let mut normalizer = get_field_normalizer(field_entry, "wildcard", tokenizer_manager)?;
+        let mut normalize = |literal: &str| {
+            let mut normalized_literal = String::with_capacity(literal.len());
+            normalizer
+                .token_stream(literal)
+                .process(&mut |token| normalized_literal.push_str(&token.text));
+            normalized_literal
+        };
+        let regex = wildcard_to_regex(&self.value, &mut normalize);
+        field_and_term_dictionary_regex(&self.field, &regex, "wildcard", schema)
     }
 }
 
@@ -192,7 +174,14 @@ impl BuildTantivyAst for WildcardQuery {
         tokenizer_manager: &TokenizerManager,
         _search_fields: &[String],
         _with_validation: bool,
+        _split_id_opt: Option<&str>,
     ) -> Result<TantivyQueryAst, InvalidQuery> {
+        if !self.is_prefix_pattern() {
+            let (field, regex) = self.extract_term_dictionary_regex(schema, tokenizer_manager)?;
+            let regex_query = tantivy::query::RegexQuery::from_pattern(&regex, field)
+                .with_context(|| format!("invalid wildcard pattern `{}`", self.value))?;
+            return Ok(regex_query.into());
+        }
         let (_, term) = self.extract_prefix_term(schema, tokenizer_manager)?;
 
         let mut phrase_prefix_query =
@@ -201,3 +190,29 @@ impl BuildTantivyAst for WildcardQuery {
         Ok(phrase_prefix_query.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_to_regex() {
+        let mut lowercase = |literal: &str| literal.to_lowercase();
+        assert_eq!(wildcard_to_regex("Bo*d", &mut lowercase), "bo.*d");
+        assert_eq!(wildcard_to_regex("*ond?", &mut lowercase), ".*ond.");
+        assert_eq!(
+            wildcard_to_regex("b.n\\*d", &mut lowercase),
+            "b\\x2en\\x2ad"
+        );
+        assert_eq!(wildcard_to_regex("bond", &mut lowercase), "bond");
+    }
+
+    #[test]
+    fn test_is_prefix_pattern() {
+        assert!(WildcardQuery::from_field_value("body", "bon*").is_prefix_pattern());
+        assert!(WildcardQuery::from_field_value("body", "b\\*n*").is_prefix_pattern());
+        assert!(!WildcardQuery::from_field_value("body", "b*d").is_prefix_pattern());
+        assert!(!WildcardQuery::from_field_value("body", "bon?").is_prefix_pattern());
+        assert!(!WildcardQuery::from_field_value("body", "bond").is_prefix_pattern());
+    }
+}
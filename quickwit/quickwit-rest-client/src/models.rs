@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
 use reqwest::StatusCode;
@@ -83,6 +84,43 @@ pub enum IngestSource {
     Stdin,
 }
 
+/// Format of the documents sent to the ingest API.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum IngestFormat {
+    /// One JSON object per line.
+    #[default]
+    Json,
+    /// Comma-separated values, starting with a header row.
+    Csv,
+    /// Tab-separated values, starting with a header row.
+    Tsv,
+}
+
+impl IngestFormat {
+    pub(crate) fn to_query_parameter(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            IngestFormat::Json => None,
+            IngestFormat::Csv => Some(("format", "csv")),
+            IngestFormat::Tsv => Some(("format", "tsv")),
+        }
+    }
+}
+
+impl FromStr for IngestFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> anyhow::Result<Self> {
+        match format {
+            "json" => Ok(IngestFormat::Json),
+            "csv" => Ok(IngestFormat::Csv),
+            "tsv" => Ok(IngestFormat::Tsv),
+            _ => anyhow::bail!(
+                "unknown ingest format `{format}`, supported formats are `json`, `csv`, and `tsv`"
+            ),
+        }
+    }
+}
+
 /// A structure that represent a timeout. Unlike Duration it can also represent an infinite or no
 /// timeout value.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
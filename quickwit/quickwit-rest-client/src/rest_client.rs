@@ -34,7 +34,7 @@ use serde_json::json;
 use tracing::warn;
 
 use crate::error::Error;
-use crate::models::{ApiResponse, IngestSource, Timeout};
+use crate::models::{ApiResponse, IngestFormat, IngestSource, Timeout};
 use crate::BatchLineReader;
 
 pub const DEFAULT_BASE_URL: &str = "http://127.0.0.1:7280";
@@ -256,6 +256,7 @@ impl QuickwitClient {
         &self,
         index_id: &str,
         ingest_source: IngestSource,
+        ingest_format: IngestFormat,
         batch_size_limit_opt: Option<usize>,
         on_ingest_event: Option<&(dyn Fn(IngestEvent) + Sync)>,
         last_block_commit: CommitType,
@@ -275,21 +276,44 @@ impl QuickwitClient {
                 BatchLineReader::from_string(ingest_payload, batch_size_limit)
             }
         };
-        while let Some(batch) = batch_reader.next_batch().await? {
+        // CSV and TSV bodies must start with the header row, so it is prepended to every batch
+        // after the first one.
+        let mut csv_header_opt: Option<Bytes> = None;
+
+        while let Some(mut batch) = batch_reader.next_batch().await? {
+            let batch_num_bytes = batch.len();
+
+            if ingest_format != IngestFormat::Json {
+                if let Some(csv_header) = &csv_header_opt {
+                    let mut buffer = Vec::with_capacity(csv_header.len() + batch.len());
+                    buffer.extend_from_slice(csv_header);
+                    buffer.extend_from_slice(&batch);
+                    batch = Bytes::from(buffer);
+                } else {
+                    let header_len = batch
+                        .iter()
+                        .position(|byte| *byte == b'\n')
+                        .map_or(batch.len(), |pos| pos + 1);
+                    csv_header_opt = Some(batch.slice(..header_len));
+                }
+            }
             loop {
-                let (query_params, timeout) =
-                    if !batch_reader.has_next() && last_block_commit != CommitType::Auto {
-                        (last_block_commit.to_query_parameter(), self.commit_timeout)
-                    } else {
-                        (None, self.ingest_timeout)
-                    };
+                let mut query_params: Vec<(&str, &str)> =
+                    ingest_format.to_query_parameter().into_iter().collect();
+                let timeout = if !batch_reader.has_next() && last_block_commit != CommitType::Auto {
+                    query_params.extend(last_block_commit.to_query_parameter().unwrap_or(&[]));
+                    self.commit_timeout
+                } else {
+                    self.ingest_timeout
+                };
+                let query_params_opt = (!query_params.is_empty()).then_some(&query_params);
                 let response = self
                     .transport
                     .send(
                         Method::POST,
                         &ingest_path,
                         None,
-                        query_params,
+                        query_params_opt,
                         Some(batch.clone()),
                         timeout,
                     )
@@ -305,7 +329,7 @@ impl QuickwitClient {
                 }
             }
             if let Some(event_fn) = on_ingest_event.as_ref() {
-                event_fn(IngestEvent::IngestedDocBatch(batch.len()))
+                event_fn(IngestEvent::IngestedDocBatch(batch_num_bytes))
             }
         }
 
@@ -352,6 +376,30 @@ impl<'a> IndexClient<'a> {
         Ok(index_metadata)
     }
 
+    pub async fn update(
+        &self,
+        index_id: &str,
+        index_config: impl ToString,
+        config_format: ConfigFormat,
+    ) -> Result<IndexMetadata, Error> {
+        let header_map = header_from_config_format(config_format);
+        let body = Bytes::from(index_config.to_string());
+        let path = format!("indexes/{index_id}");
+        let response = self
+            .transport
+            .send::<()>(
+                Method::PUT,
+                &path,
+                Some(header_map),
+                None,
+                Some(body),
+                self.timeout,
+            )
+            .await?;
+        let index_metadata = response.deserialize().await?;
+        Ok(index_metadata)
+    }
+
     pub async fn list(&self) -> Result<Vec<IndexMetadata>, Error> {
         let response = self
             .transport
@@ -672,7 +720,7 @@ mod test {
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     use crate::error::Error;
-    use crate::models::IngestSource;
+    use crate::models::{IngestFormat, IngestSource};
     use crate::rest_client::QuickwitClientBuilder;
 
     #[tokio::test]
@@ -760,7 +808,14 @@ mod test {
             .await;
         let ingest_source = IngestSource::File(PathBuf::from_str(&ndjson_filepath).unwrap());
         qw_client
-            .ingest("my-index", ingest_source, None, None, CommitType::Auto)
+            .ingest(
+                "my-index",
+                ingest_source,
+                IngestFormat::Json,
+                None,
+                None,
+                CommitType::Auto,
+            )
             .await
             .unwrap();
     }
@@ -788,7 +843,53 @@ mod test {
             .await;
         let ingest_source = IngestSource::File(PathBuf::from_str(&ndjson_filepath).unwrap());
         qw_client
-            .ingest("my-index", ingest_source, None, None, CommitType::Force)
+            .ingest(
+                "my-index",
+                ingest_source,
+                IngestFormat::Json,
+                None,
+                None,
+                CommitType::Force,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ingest_endpoint_with_csv_format() {
+        let mock_server = MockServer::start().await;
+        let server_url = Url::parse(&mock_server.uri()).unwrap();
+        let qw_client = QuickwitClientBuilder::new(server_url).build();
+        Mock::given(method("POST"))
+            .and(path("/api/v1/my-index/ingest"))
+            .and(query_param("format", "csv"))
+            .and(query_param_is_missing("commit"))
+            .and(body_bytes(b"host,status\ncdn-1,200\n".to_vec()))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/my-index/ingest"))
+            .and(query_param("format", "csv"))
+            .and(query_param("commit", "force"))
+            .and(body_bytes(b"host,status\ncdn-2,404\n".to_vec()))
+            .respond_with(ResponseTemplate::new(StatusCode::OK))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let ingest_source = IngestSource::Str("host,status\ncdn-1,200\ncdn-2,404\n".to_string());
+        qw_client
+            .ingest(
+                "my-index",
+                ingest_source,
+                IngestFormat::Csv,
+                Some(25),
+                None,
+                CommitType::Force,
+            )
             .await
             .unwrap();
     }
@@ -816,7 +917,14 @@ mod test {
             .await;
         let ingest_source = IngestSource::File(PathBuf::from_str(&ndjson_filepath).unwrap());
         qw_client
-            .ingest("my-index", ingest_source, None, None, CommitType::WaitFor)
+            .ingest(
+                "my-index",
+                ingest_source,
+                IngestFormat::Json,
+                None,
+                None,
+                CommitType::WaitFor,
+            )
             .await
             .unwrap();
     }
@@ -848,6 +956,7 @@ mod test {
             .ingest(
                 "my-index",
                 ingest_source,
+                IngestFormat::Json,
                 Some(4096),
                 None,
                 CommitType::Auto,
@@ -918,6 +1027,27 @@ mod test {
             index_metadata
         );
 
+        // PUT update index
+        let index_config_to_update = index_metadata.index_config.clone();
+        Mock::given(method("PUT"))
+            .and(path("/api/v1/indexes/test-index"))
+            .and(body_json(index_config_to_update.clone()))
+            .respond_with(
+                ResponseTemplate::new(StatusCode::OK).set_body_json(index_metadata.clone()),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        let put_body = serde_json::to_string(&index_config_to_update).unwrap();
+        assert_eq!(
+            qw_client
+                .indexes()
+                .update("test-index", put_body, ConfigFormat::Json)
+                .await
+                .unwrap(),
+            index_metadata
+        );
+
         // PUT clear index
         Mock::given(method("PUT"))
             .and(path("/api/v1/indexes/my-index/clear"))
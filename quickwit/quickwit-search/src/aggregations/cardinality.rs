@@ -0,0 +1,215 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! HyperLogLog++ sketch backing the `cardinality` aggregation.
+//!
+//! Small cardinalities are counted exactly by keeping the set of hashes, until the precision
+//! threshold of the aggregation is reached. The sketch then switches to the dense HyperLogLog
+//! representation, whose memory footprint is fixed (16KiB) and relative error about 0.8%.
+
+use std::hash::Hasher;
+
+use fnv::FnvHashSet;
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher;
+
+/// Number of bits of the hash selecting the register.
+const PRECISION: u32 = 14;
+
+const NUM_REGISTERS: usize = 1 << PRECISION;
+
+/// Default number of distinct values below which the count is exact.
+pub(crate) const DEFAULT_PRECISION_THRESHOLD: usize = 3_000;
+
+/// Maximum precision threshold, above which counting exactly uses more memory than the dense
+/// representation.
+pub(crate) const MAX_PRECISION_THRESHOLD: usize = 40_000;
+
+/// Hashes a value with a hash function that is stable across nodes and versions, so that sketches
+/// computed by different searchers can be merged.
+pub(crate) fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = SipHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+enum Registers {
+    Sparse(FnvHashSet<u64>),
+    Dense(Vec<u8>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct HyperLogLogPlusPlus {
+    precision_threshold: usize,
+    registers: Registers,
+}
+
+impl HyperLogLogPlusPlus {
+    pub fn new(precision_threshold: usize) -> Self {
+        Self {
+            precision_threshold,
+            registers: Registers::Sparse(FnvHashSet::default()),
+        }
+    }
+
+    pub fn insert_hash(&mut self, hash: u64) {
+        match &mut self.registers {
+            Registers::Sparse(hashes) => {
+                if hashes.insert(hash) && hashes.len() > self.precision_threshold {
+                    self.densify();
+                }
+            }
+            Registers::Dense(registers) => insert_into_registers(registers, hash),
+        }
+    }
+
+    pub fn merge(&mut self, other: HyperLogLogPlusPlus) {
+        match other.registers {
+            Registers::Sparse(hashes) => {
+                for hash in hashes {
+                    self.insert_hash(hash);
+                }
+            }
+            Registers::Dense(other_registers) => {
+                self.densify();
+                let Registers::Dense(registers) = &mut self.registers else {
+                    unreachable!("the registers should be dense");
+                };
+                for (register, other_register) in registers.iter_mut().zip(other_registers) {
+                    *register = (*register).max(other_register);
+                }
+            }
+        }
+    }
+
+    /// Returns the estimated number of distinct values.
+    pub fn estimate(&self) -> u64 {
+        let registers = match &self.registers {
+            Registers::Sparse(hashes) => return hashes.len() as u64,
+            Registers::Dense(registers) => registers,
+        };
+        let num_registers = NUM_REGISTERS as f64;
+        let mut sum = 0.0;
+        let mut num_zero_registers = 0;
+
+        for &register in registers {
+            sum += 1.0 / (1u64 << register) as f64;
+
+            if register == 0 {
+                num_zero_registers += 1;
+            }
+        }
+        let alpha = 0.7213 / (1.0 + 1.079 / num_registers);
+        let raw_estimate = alpha * num_registers * num_registers / sum;
+
+        // Small range correction: linear counting is more accurate than the raw estimate when
+        // many registers are still empty.
+        if raw_estimate <= 2.5 * num_registers && num_zero_registers > 0 {
+            let linear_count = num_registers * (num_registers / num_zero_registers as f64).ln();
+            return linear_count.round() as u64;
+        }
+        raw_estimate.round() as u64
+    }
+
+    fn densify(&mut self) {
+        let Registers::Sparse(hashes) = &mut self.registers else {
+            return;
+        };
+        let mut registers = vec![0u8; NUM_REGISTERS];
+
+        for &hash in hashes.iter() {
+            insert_into_registers(&mut registers, hash);
+        }
+        self.registers = Registers::Dense(registers);
+    }
+}
+
+fn insert_into_registers(registers: &mut [u8], hash: u64) {
+    let register_idx = (hash >> (64 - PRECISION)) as usize;
+    // The remaining bits are padded with a one to bound the rank.
+    let remaining_bits = (hash << PRECISION) | (1 << (PRECISION - 1));
+    let rank = remaining_bits.leading_zeros() as u8 + 1;
+    registers[register_idx] = registers[register_idx].max(rank);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch_for_range(precision_threshold: usize, start: u64, end: u64) -> HyperLogLogPlusPlus {
+        let mut sketch = HyperLogLogPlusPlus::new(precision_threshold);
+
+        for value in start..end {
+            sketch.insert_hash(hash_bytes(&value.to_le_bytes()));
+        }
+        sketch
+    }
+
+    fn assert_relative_error(estimate: u64, expected: u64, max_relative_error: f64) {
+        let relative_error = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(
+            relative_error <= max_relative_error,
+            "estimate {estimate} is too far from {expected}"
+        );
+    }
+
+    #[test]
+    fn test_hyperloglog_exact_below_precision_threshold() {
+        let mut sketch = sketch_for_range(DEFAULT_PRECISION_THRESHOLD, 0, 1_000);
+        assert_eq!(sketch.estimate(), 1_000);
+
+        sketch.merge(sketch_for_range(DEFAULT_PRECISION_THRESHOLD, 500, 2_000));
+        assert_eq!(sketch.estimate(), 2_000);
+    }
+
+    #[test]
+    fn test_hyperloglog_dense_estimate() {
+        let sketch = sketch_for_range(100, 0, 5_000);
+        assert!(matches!(sketch.registers, Registers::Dense(_)));
+        assert_relative_error(sketch.estimate(), 5_000, 0.03);
+
+        let sketch = sketch_for_range(100, 0, 1_000_000);
+        assert_relative_error(sketch.estimate(), 1_000_000, 0.03);
+    }
+
+    #[test]
+    fn test_hyperloglog_merge() {
+        let mut sparse_sketch = sketch_for_range(DEFAULT_PRECISION_THRESHOLD, 0, 1_000);
+        let dense_sketch = sketch_for_range(100, 500, 100_000);
+        sparse_sketch.merge(dense_sketch.clone());
+        assert_relative_error(sparse_sketch.estimate(), 100_000, 0.03);
+
+        let mut dense_sketch = dense_sketch;
+        dense_sketch.merge(sketch_for_range(
+            DEFAULT_PRECISION_THRESHOLD,
+            99_000,
+            101_000,
+        ));
+        assert_relative_error(dense_sketch.estimate(), 101_000, 0.03);
+    }
+
+    #[test]
+    fn test_hyperloglog_serialization() {
+        let sketch = sketch_for_range(100, 0, 10_000);
+        let serialized = postcard::to_allocvec(&sketch).unwrap();
+        let deserialized: HyperLogLogPlusPlus = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(deserialized.estimate(), sketch.estimate());
+    }
+}
@@ -0,0 +1,474 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Keys and sources of the `composite` aggregation.
+
+use std::cmp::{Ordering, Reverse};
+
+use quickwit_proto::search::SortValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tantivy::columnar::StrColumn;
+
+use super::fast_field_values::{FastFieldValueReader, SegmentValue};
+use crate::collector::term_to_segment_sort_value;
+
+/// Default number of buckets returned by a `composite` aggregation.
+pub(crate) const DEFAULT_COMPOSITE_SIZE: usize = 10;
+
+/// Maximum number of buckets returned by a `composite` aggregation.
+pub(crate) const MAX_COMPOSITE_SIZE: usize = 65_535;
+
+/// Value of a source in the key of a composite bucket.
+///
+/// Within a segment, the values of text sources are term ordinals, which follow the
+/// lexicographical order of the terms. They are converted into terms when the segment is harvested.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) enum KeyValue {
+    Null,
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Str(String),
+}
+
+impl KeyValue {
+    pub fn from_json(json_value: &JsonValue) -> Result<Self, String> {
+        let key_value = match json_value {
+            JsonValue::Null => KeyValue::Null,
+            JsonValue::Bool(value) => KeyValue::Bool(*value),
+            JsonValue::Number(number) => {
+                if let Some(value) = number.as_i64() {
+                    KeyValue::I64(value)
+                } else if let Some(value) = number.as_u64() {
+                    KeyValue::U64(value)
+                } else {
+                    KeyValue::F64(number.as_f64().unwrap_or_default())
+                }
+            }
+            JsonValue::String(value) => KeyValue::Str(value.clone()),
+            JsonValue::Array(_) | JsonValue::Object(_) => {
+                return Err(format!("invalid composite `after` value `{json_value}`"));
+            }
+        };
+        Ok(key_value)
+    }
+
+    pub fn into_json(self) -> JsonValue {
+        match self {
+            KeyValue::Null => JsonValue::Null,
+            KeyValue::Bool(value) => JsonValue::Bool(value),
+            KeyValue::U64(value) => JsonValue::from(value),
+            KeyValue::I64(value) => JsonValue::from(value),
+            KeyValue::F64(value) => JsonValue::from(value),
+            KeyValue::Str(value) => JsonValue::String(value),
+        }
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            KeyValue::Null => 0,
+            KeyValue::Bool(_) => 1,
+            KeyValue::U64(_) | KeyValue::I64(_) | KeyValue::F64(_) => 2,
+            KeyValue::Str(_) => 3,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            KeyValue::U64(value) => *value as f64,
+            KeyValue::I64(value) => *value as f64,
+            KeyValue::F64(value) => *value,
+            KeyValue::Null | KeyValue::Bool(_) | KeyValue::Str(_) => 0.0,
+        }
+    }
+}
+
+impl Ord for KeyValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (KeyValue::Bool(left), KeyValue::Bool(right)) => left.cmp(right),
+            (KeyValue::U64(left), KeyValue::U64(right)) => left.cmp(right),
+            (KeyValue::I64(left), KeyValue::I64(right)) => left.cmp(right),
+            (KeyValue::Str(left), KeyValue::Str(right)) => left.cmp(right),
+            _ => self
+                .type_rank()
+                .cmp(&other.type_rank())
+                .then_with(|| self.as_f64().total_cmp(&other.as_f64())),
+        }
+    }
+}
+
+impl PartialOrd for KeyValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for KeyValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for KeyValue {}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SourceOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum OrderedKeyValue {
+    Asc(KeyValue),
+    Desc(Reverse<KeyValue>),
+}
+
+/// Key of a composite bucket, ordered according to the orders of the sources. Missing values
+/// come first in ascending order and last in descending order.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) struct CompositeKey(Vec<OrderedKeyValue>);
+
+impl CompositeKey {
+    pub fn new(key_values: Vec<KeyValue>, sources: &[CompositeSource]) -> Self {
+        let ordered_key_values = key_values
+            .into_iter()
+            .zip(sources)
+            .map(|(key_value, source)| match source.order {
+                SourceOrder::Asc => OrderedKeyValue::Asc(key_value),
+                SourceOrder::Desc => OrderedKeyValue::Desc(Reverse(key_value)),
+            })
+            .collect();
+        Self(ordered_key_values)
+    }
+
+    pub fn into_key_values(self) -> Vec<KeyValue> {
+        self.0
+            .into_iter()
+            .map(|ordered_key_value| match ordered_key_value {
+                OrderedKeyValue::Asc(key_value) => key_value,
+                OrderedKeyValue::Desc(Reverse(key_value)) => key_value,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum CompositeSourceKind {
+    Terms,
+    Histogram {
+        interval: f64,
+    },
+    /// Fixed interval in milliseconds.
+    DateHistogram {
+        interval_millis: i64,
+    },
+}
+
+/// Source of a composite aggregation, extracting one value of the key of the buckets.
+#[derive(Clone, Debug)]
+pub(crate) struct CompositeSource {
+    pub name: String,
+    pub field: String,
+    pub kind: CompositeSourceKind,
+    pub order: SourceOrder,
+    pub missing_bucket: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TermsSourceParams {
+    field: String,
+    #[serde(default)]
+    order: SourceOrder,
+    #[serde(default)]
+    missing_bucket: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HistogramSourceParams {
+    field: String,
+    interval: f64,
+    #[serde(default)]
+    order: SourceOrder,
+    #[serde(default)]
+    missing_bucket: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DateHistogramSourceParams {
+    field: String,
+    #[serde(default)]
+    fixed_interval: Option<String>,
+    #[serde(default)]
+    calendar_interval: Option<String>,
+    #[serde(default)]
+    order: SourceOrder,
+    #[serde(default)]
+    missing_bucket: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CompositeSourceParams {
+    Terms(TermsSourceParams),
+    Histogram(HistogramSourceParams),
+    DateHistogram(DateHistogramSourceParams),
+}
+
+impl CompositeSource {
+    pub fn from_json(name: String, json_value: JsonValue) -> Result<Self, String> {
+        let source_params: CompositeSourceParams = serde_json::from_value(json_value)
+            .map_err(|error| format!("invalid composite source `{name}`: {error}"))?;
+
+        let source = match source_params {
+            CompositeSourceParams::Terms(params) => CompositeSource {
+                name,
+                field: params.field,
+                kind: CompositeSourceKind::Terms,
+                order: params.order,
+                missing_bucket: params.missing_bucket,
+            },
+            CompositeSourceParams::Histogram(params) => {
+                if params.interval.is_nan() || params.interval <= 0.0 {
+                    return Err(format!(
+                        "interval of composite source `{name}` must be strictly positive"
+                    ));
+                }
+                CompositeSource {
+                    name,
+                    field: params.field,
+                    kind: CompositeSourceKind::Histogram {
+                        interval: params.interval,
+                    },
+                    order: params.order,
+                    missing_bucket: params.missing_bucket,
+                }
+            }
+            CompositeSourceParams::DateHistogram(params) => {
+                let interval_millis = parse_date_interval(
+                    params.fixed_interval.as_deref(),
+                    params.calendar_interval.as_deref(),
+                )
+                .map_err(|error| format!("invalid composite source `{name}`: {error}"))?;
+                CompositeSource {
+                    name,
+                    field: params.field,
+                    kind: CompositeSourceKind::DateHistogram { interval_millis },
+                    order: params.order,
+                    missing_bucket: params.missing_bucket,
+                }
+            }
+        };
+        Ok(source)
+    }
+
+    /// Converts a value read from a segment into a key value, keeping term ordinals as is.
+    pub fn segment_key_value(&self, segment_value: SegmentValue) -> KeyValue {
+        match (&self.kind, segment_value) {
+            (_, SegmentValue::TermOrd(term_ord)) => KeyValue::U64(term_ord),
+            (CompositeSourceKind::Terms, SegmentValue::U64(value)) => KeyValue::U64(value),
+            (CompositeSourceKind::Terms, SegmentValue::I64(value)) => KeyValue::I64(value),
+            (CompositeSourceKind::Terms, SegmentValue::F64(value)) => KeyValue::F64(value),
+            (CompositeSourceKind::Terms, SegmentValue::Bool(value)) => KeyValue::Bool(value),
+            (CompositeSourceKind::Terms, SegmentValue::Date(date_time)) => {
+                KeyValue::I64(date_time.into_timestamp_millis())
+            }
+            (CompositeSourceKind::Histogram { interval }, segment_value) => {
+                let value = segment_value.as_f64().unwrap_or_default();
+                KeyValue::F64((value / interval).floor() * interval)
+            }
+            (CompositeSourceKind::DateHistogram { interval_millis }, segment_value) => {
+                let millis = match segment_value {
+                    SegmentValue::Date(date_time) => date_time.into_timestamp_millis(),
+                    other => other.as_f64().unwrap_or_default() as i64,
+                };
+                KeyValue::I64(millis.div_euclid(*interval_millis) * interval_millis)
+            }
+        }
+    }
+
+    /// Converts an `after` key value into a value that can be compared with the key values of a
+    /// segment.
+    pub fn after_segment_key_value(
+        &self,
+        after_key_value: &KeyValue,
+        value_reader: &FastFieldValueReader,
+    ) -> std::io::Result<KeyValue> {
+        let (Some(str_column), KeyValue::Str(term)) = (value_reader.str_column(), after_key_value)
+        else {
+            return Ok(after_key_value.clone());
+        };
+        let key_value = match term_to_segment_sort_value(str_column, term)? {
+            SortValue::U64(term_ord) => KeyValue::U64(term_ord),
+            SortValue::F64(value) => KeyValue::F64(value),
+            _ => KeyValue::Null,
+        };
+        Ok(key_value)
+    }
+
+    /// Converts a key value of a segment into a key value that can be compared with the key
+    /// values of other segments and splits.
+    pub fn split_key_value(
+        &self,
+        segment_key_value: KeyValue,
+        str_column_opt: Option<&StrColumn>,
+    ) -> std::io::Result<KeyValue> {
+        let (Some(str_column), KeyValue::U64(term_ord)) = (str_column_opt, &segment_key_value)
+        else {
+            return Ok(segment_key_value);
+        };
+        let mut term = String::new();
+        str_column.ord_to_str(*term_ord, &mut term)?;
+        Ok(KeyValue::Str(term))
+    }
+}
+
+/// Parses the interval of a date histogram, in milliseconds.
+pub(crate) fn parse_date_interval(
+    fixed_interval_opt: Option<&str>,
+    calendar_interval_opt: Option<&str>,
+) -> Result<i64, String> {
+    let interval_millis_opt = match (fixed_interval_opt, calendar_interval_opt) {
+        (Some(fixed_interval), None) => parse_fixed_interval(fixed_interval),
+        (None, Some(calendar_interval)) => parse_calendar_interval(calendar_interval),
+        _ => None,
+    };
+    interval_millis_opt.ok_or_else(|| {
+        "expected either a `fixed_interval` (e.g. `30m`) or a `calendar_interval` among `minute`, \
+         `hour`, `day`, and `week`"
+            .to_string()
+    })
+}
+
+fn parse_fixed_interval(fixed_interval: &str) -> Option<i64> {
+    let unit_start = fixed_interval.find(|character: char| !character.is_ascii_digit())?;
+    let (number, unit) = fixed_interval.split_at(unit_start);
+    let number: i64 = number.parse().ok().filter(|number| *number > 0)?;
+    let unit_millis = match unit {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return None,
+    };
+    number.checked_mul(unit_millis)
+}
+
+fn parse_calendar_interval(calendar_interval: &str) -> Option<i64> {
+    let interval_millis = match calendar_interval {
+        "minute" | "1m" => 60_000,
+        "hour" | "1h" => 3_600_000,
+        "day" | "1d" => 86_400_000,
+        "week" | "1w" => 7 * 86_400_000,
+        _ => return None,
+    };
+    Some(interval_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_intervals() {
+        assert_eq!(parse_fixed_interval("30s"), Some(30_000));
+        assert_eq!(parse_fixed_interval("1h"), Some(3_600_000));
+        assert_eq!(parse_fixed_interval("2d"), Some(172_800_000));
+        assert_eq!(parse_fixed_interval("h"), None);
+        assert_eq!(parse_fixed_interval("0m"), None);
+        assert_eq!(parse_fixed_interval("1M"), None);
+        assert_eq!(parse_calendar_interval("hour"), Some(3_600_000));
+        assert_eq!(parse_calendar_interval("month"), None);
+    }
+
+    #[test]
+    fn test_key_value_ordering() {
+        assert!(KeyValue::Null < KeyValue::I64(-1));
+        assert!(KeyValue::I64(-1) < KeyValue::U64(0));
+        assert!(KeyValue::U64(1) < KeyValue::F64(1.5));
+        assert_eq!(KeyValue::U64(1), KeyValue::I64(1));
+        assert!(KeyValue::F64(2.0) < KeyValue::Str("a".to_string()));
+        assert!(KeyValue::Str("a".to_string()) < KeyValue::Str("b".to_string()));
+    }
+
+    #[test]
+    fn test_composite_key_ordering() {
+        let asc_source = CompositeSource {
+            name: "asc".to_string(),
+            field: "asc".to_string(),
+            kind: CompositeSourceKind::Terms,
+            order: SourceOrder::Asc,
+            missing_bucket: true,
+        };
+        let desc_source = CompositeSource {
+            order: SourceOrder::Desc,
+            ..asc_source.clone()
+        };
+        let sources = [asc_source, desc_source];
+        let key =
+            |first: KeyValue, second: KeyValue| CompositeKey::new(vec![first, second], &sources);
+
+        assert!(key(KeyValue::U64(1), KeyValue::U64(2)) < key(KeyValue::U64(1), KeyValue::U64(1)));
+        assert!(key(KeyValue::U64(1), KeyValue::U64(1)) < key(KeyValue::U64(2), KeyValue::U64(3)));
+        assert!(key(KeyValue::Null, KeyValue::U64(1)) < key(KeyValue::U64(0), KeyValue::U64(1)));
+        assert!(key(KeyValue::U64(0), KeyValue::U64(1)) < key(KeyValue::U64(0), KeyValue::Null));
+        assert_eq!(
+            key(KeyValue::U64(0), KeyValue::Null).into_key_values(),
+            [KeyValue::U64(0), KeyValue::Null]
+        );
+    }
+
+    #[test]
+    fn test_segment_key_value() {
+        let source = CompositeSource {
+            name: "hour".to_string(),
+            field: "timestamp".to_string(),
+            kind: CompositeSourceKind::DateHistogram {
+                interval_millis: 3_600_000,
+            },
+            order: SourceOrder::Asc,
+            missing_bucket: false,
+        };
+        let date_time = tantivy::DateTime::from_timestamp_secs(7_300);
+        assert_eq!(
+            source.segment_key_value(SegmentValue::Date(date_time)),
+            KeyValue::I64(7_200_000)
+        );
+        let source = CompositeSource {
+            kind: CompositeSourceKind::Histogram { interval: 10.0 },
+            ..source
+        };
+        assert_eq!(
+            source.segment_key_value(SegmentValue::I64(-5)),
+            KeyValue::F64(-10.0)
+        );
+        assert_eq!(
+            source.segment_key_value(SegmentValue::TermOrd(3)),
+            KeyValue::U64(3)
+        );
+    }
+}
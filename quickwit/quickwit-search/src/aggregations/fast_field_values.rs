@@ -0,0 +1,143 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+
+use tantivy::columnar::StrColumn;
+use tantivy::fastfield::Column;
+use tantivy::{DateTime, DocId, SegmentReader};
+
+/// Value of a fast field for a document, as read from a segment.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum SegmentValue {
+    /// Ordinal of a term in the dictionary of the segment.
+    TermOrd(u64),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Date(DateTime),
+}
+
+impl SegmentValue {
+    /// Returns the value as a number, if it is numeric.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            SegmentValue::TermOrd(_) => None,
+            SegmentValue::U64(value) => Some(*value as f64),
+            SegmentValue::I64(value) => Some(*value as f64),
+            SegmentValue::F64(value) => Some(*value),
+            SegmentValue::Bool(value) => Some(*value as u64 as f64),
+            SegmentValue::Date(date_time) => Some(date_time.into_timestamp_millis() as f64),
+        }
+    }
+}
+
+/// Reads the values of a fast field in a segment. The column is looked up by type, so fields
+/// missing from the segment are read as having no values.
+pub(crate) enum FastFieldValueReader {
+    Str(StrColumn),
+    U64(Column<u64>),
+    I64(Column<i64>),
+    F64(Column<f64>),
+    Bool(Column<bool>),
+    Date(Column<DateTime>),
+    Missing,
+}
+
+impl FastFieldValueReader {
+    pub fn open(segment_reader: &SegmentReader, field_name: &str) -> tantivy::Result<Self> {
+        let fast_fields = segment_reader.fast_fields();
+
+        if let Some(str_column) = fast_fields.str(field_name)? {
+            return Ok(Self::Str(str_column));
+        }
+        if let Some(column) = fast_fields.column_opt(field_name)? {
+            return Ok(Self::I64(column));
+        }
+        if let Some(column) = fast_fields.column_opt(field_name)? {
+            return Ok(Self::U64(column));
+        }
+        if let Some(column) = fast_fields.column_opt(field_name)? {
+            return Ok(Self::F64(column));
+        }
+        if let Some(column) = fast_fields.column_opt(field_name)? {
+            return Ok(Self::Bool(column));
+        }
+        if let Some(column) = fast_fields.column_opt(field_name)? {
+            return Ok(Self::Date(column));
+        }
+        Ok(Self::Missing)
+    }
+
+    /// Calls `callback` with each value of the field for the document.
+    pub fn for_each_value(&self, doc_id: DocId, mut callback: impl FnMut(SegmentValue)) {
+        match self {
+            Self::Str(str_column) => {
+                for term_ord in str_column.term_ords(doc_id) {
+                    callback(SegmentValue::TermOrd(term_ord));
+                }
+            }
+            Self::U64(column) => {
+                for value in column.values_for_doc(doc_id) {
+                    callback(SegmentValue::U64(value));
+                }
+            }
+            Self::I64(column) => {
+                for value in column.values_for_doc(doc_id) {
+                    callback(SegmentValue::I64(value));
+                }
+            }
+            Self::F64(column) => {
+                for value in column.values_for_doc(doc_id) {
+                    callback(SegmentValue::F64(value));
+                }
+            }
+            Self::Bool(column) => {
+                for value in column.values_for_doc(doc_id) {
+                    callback(SegmentValue::Bool(value));
+                }
+            }
+            Self::Date(column) => {
+                for value in column.values_for_doc(doc_id) {
+                    callback(SegmentValue::Date(value));
+                }
+            }
+            Self::Missing => {}
+        }
+    }
+
+    /// Returns the column of the field if it is a text field.
+    pub fn str_column(&self) -> Option<&StrColumn> {
+        match self {
+            Self::Str(str_column) => Some(str_column),
+            _ => None,
+        }
+    }
+
+    /// Returns the term of a term ordinal returned by [`Self::for_each_value`].
+    pub fn term(&self, term_ord: u64) -> io::Result<String> {
+        let mut term = String::new();
+
+        if let Self::Str(str_column) = self {
+            str_column.ord_to_str(term_ord, &mut term)?;
+        }
+        Ok(term)
+    }
+}
@@ -0,0 +1,2504 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Aggregations computed by Quickwit alongside the tantivy ones: `cardinality`, `composite`,
+//! `filters`, and `top_hits`.
+//!
+//! A request containing at least one of these aggregations is split into the aggregations handled
+//! by tantivy and the ones handled here. Both are collected in the same pass over the documents
+//! and their results are merged into a single response, following the format of Elasticsearch.
+//! Since tantivy aggregations cannot hold the ones handled here, `terms` and `date_histogram`
+//! aggregations are also handled here when they do.
+//!
+//! Pipeline aggregations are removed from the request before it reaches the leaves, and applied
+//! by the root to the final results of the other aggregations.
+
+mod cardinality;
+mod composite;
+mod fast_field_values;
+mod pipeline;
+mod script;
+mod top_hits;
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem::size_of;
+use std::sync::Arc;
+
+use fnv::FnvHashSet;
+use itertools::Itertools;
+use quickwit_doc_mapper::{DocMapper, WarmupInfo};
+use quickwit_proto::search::{Hit, PartialHit};
+use quickwit_query::query_ast::QueryAst;
+use quickwit_query::ElasticQueryDsl;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
+use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
+use tantivy::aggregation::{AggregationError, AggregationLimits, AggregationSegmentCollector};
+use tantivy::collector::SegmentCollector;
+use tantivy::query::{EnableScoring, Query, Scorer};
+use tantivy::schema::Schema;
+use tantivy::{DocId, DocSet, Score, SegmentOrdinal, SegmentReader, TantivyError};
+
+use self::cardinality::{
+    hash_bytes, HyperLogLogPlusPlus, DEFAULT_PRECISION_THRESHOLD, MAX_PRECISION_THRESHOLD,
+};
+use self::composite::{
+    parse_date_interval, CompositeKey, CompositeSource, CompositeSourceKind, KeyValue, SourceOrder,
+    DEFAULT_COMPOSITE_SIZE, MAX_COMPOSITE_SIZE,
+};
+use self::fast_field_values::{FastFieldValueReader, SegmentValue};
+pub(crate) use self::pipeline::PipelineAggregations;
+use self::top_hits::{IntermediateTopHits, TopHitsAggregation};
+use crate::collector::{get_score_extractor, SegmentTopHits, SortBy, SortingFieldExtractor};
+use crate::{GlobalDocAddress, SearchError};
+
+const EXTENDED_AGGREGATION_TYPES: [&str; 4] = ["cardinality", "composite", "filters", "top_hits"];
+
+// Options of the `terms`, `date_histogram`, and metric aggregations that are supported by tantivy,
+// but not when these aggregations contain or are nested in a `cardinality`, `composite`,
+// `filters`, or `top_hits` aggregation.
+const UNSUPPORTED_TERMS_PARAMS: [&str; 5] = [
+    "exclude",
+    "include",
+    "missing",
+    "segment_size",
+    "show_term_doc_count_error",
+];
+const UNSUPPORTED_DATE_HISTOGRAM_PARAMS: [&str; 6] = [
+    "extended_bounds",
+    "format",
+    "hard_bounds",
+    "keyed",
+    "missing",
+    "offset",
+];
+const UNSUPPORTED_METRIC_PARAMS: [&str; 1] = ["missing"];
+
+const DEFAULT_OTHER_BUCKET_KEY: &str = "_other_";
+
+const DEFAULT_TERMS_SIZE: usize = 10;
+
+/// Maximum number of buckets a `date_histogram` aggregation can return, empty buckets included.
+const MAX_DATE_HISTOGRAM_BUCKETS: i64 = 65_535;
+
+/// Returns whether the aggregation request contains a `cardinality`, `composite`, `filters`, or
+/// `top_hits` aggregation.
+pub(crate) fn contains_extended_aggregations(aggregation_request: &str) -> bool {
+    let Ok(json_map) = serde_json::from_str::<JsonMap<String, JsonValue>>(aggregation_request)
+    else {
+        return false;
+    };
+    json_map.values().any(is_extended_aggregation)
+}
+
+fn is_extended_aggregation(json_value: &JsonValue) -> bool {
+    let Some(json_object) = json_value.as_object() else {
+        return false;
+    };
+    if EXTENDED_AGGREGATION_TYPES
+        .iter()
+        .any(|aggregation_type| json_object.contains_key(*aggregation_type))
+    {
+        return true;
+    }
+    ["aggs", "aggregations"]
+        .iter()
+        .filter_map(|key| json_object.get(*key)?.as_object())
+        .flat_map(|sub_aggregations_json| sub_aggregations_json.values())
+        .any(is_extended_aggregation)
+}
+
+/// Aggregation request containing at least one `cardinality`, `composite`, `filters`, or
+/// `top_hits` aggregation, along with the aggregations handled by tantivy.
+#[derive(Clone, Debug)]
+pub struct ExtendedAggregations {
+    tantivy_aggregations: Aggregations,
+    aggregations: Vec<(String, ExtendedAggregation)>,
+}
+
+#[derive(Clone, Debug)]
+enum ExtendedAggregation {
+    Cardinality(CardinalityAggregation),
+    Composite(CompositeAggregation),
+    Filters(FiltersAggregation),
+    Terms(TermsAggregation),
+    DateHistogram(DateHistogramAggregation),
+    TopHits(TopHitsAggregation),
+    /// Metric aggregations are handled here when they are nested in a bucket aggregation handled
+    /// here.
+    Metric(MetricAggregation),
+}
+
+#[derive(Clone, Debug)]
+struct CardinalityAggregation {
+    field: String,
+    precision_threshold: usize,
+}
+
+#[derive(Clone, Debug)]
+struct CompositeAggregation {
+    sources: Vec<CompositeSource>,
+    size: usize,
+    after_key_opt: Option<Vec<KeyValue>>,
+    sub_aggregations: Vec<(String, ExtendedAggregation)>,
+}
+
+#[derive(Clone, Debug)]
+struct FiltersAggregation {
+    keyed: bool,
+    filters: Vec<NamedFilter>,
+    other_bucket_key_opt: Option<String>,
+    sub_aggregations: Vec<(String, ExtendedAggregation)>,
+}
+
+#[derive(Clone, Debug)]
+struct NamedFilter {
+    name: String,
+    query_ast: QueryAst,
+    // Built against the schema of the split by `ExtendedAggregations::build_filter_queries`.
+    query_opt: Option<Arc<dyn Query>>,
+    warmup_info: WarmupInfo,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TermsOrder {
+    CountDesc,
+    CountAsc,
+    KeyAsc,
+    KeyDesc,
+}
+
+#[derive(Clone, Debug)]
+struct TermsAggregation {
+    // Terms source extracting the keys of the buckets.
+    source: CompositeSource,
+    size: usize,
+    split_size: usize,
+    order: TermsOrder,
+    min_doc_count: u64,
+    sub_aggregations: Vec<(String, ExtendedAggregation)>,
+}
+
+#[derive(Clone, Debug)]
+struct DateHistogramAggregation {
+    // Date histogram source extracting the keys of the buckets.
+    source: CompositeSource,
+    interval_millis: i64,
+    min_doc_count: u64,
+    sub_aggregations: Vec<(String, ExtendedAggregation)>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MetricKind {
+    Avg,
+    Min,
+    Max,
+    Sum,
+    ValueCount,
+}
+
+#[derive(Clone, Debug)]
+struct MetricAggregation {
+    kind: MetricKind,
+    field: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CardinalityParams {
+    field: String,
+    #[serde(default)]
+    precision_threshold: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CompositeParams {
+    sources: Vec<JsonMap<String, JsonValue>>,
+    #[serde(default)]
+    size: Option<usize>,
+    #[serde(default)]
+    after: Option<JsonMap<String, JsonValue>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FiltersParams {
+    filters: JsonValue,
+    #[serde(default)]
+    other_bucket: bool,
+    #[serde(default)]
+    other_bucket_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TermsParams {
+    field: String,
+    #[serde(default)]
+    size: Option<usize>,
+    #[serde(default, alias = "shard_size")]
+    split_size: Option<usize>,
+    #[serde(default)]
+    order: Option<JsonMap<String, JsonValue>>,
+    #[serde(default)]
+    min_doc_count: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DateHistogramParams {
+    field: String,
+    #[serde(default)]
+    fixed_interval: Option<String>,
+    #[serde(default)]
+    calendar_interval: Option<String>,
+    #[serde(default)]
+    min_doc_count: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MetricParams {
+    field: String,
+}
+
+impl<'de> Deserialize<'de> for ExtendedAggregations {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let json_map = JsonMap::<String, JsonValue>::deserialize(deserializer)?;
+        Self::from_json_map(json_map).map_err(serde::de::Error::custom)
+    }
+}
+
+impl ExtendedAggregations {
+    fn from_json_map(json_map: JsonMap<String, JsonValue>) -> Result<Self, String> {
+        let mut tantivy_aggregations_json = JsonMap::new();
+        let mut aggregations = Vec::new();
+
+        for (name, json_value) in json_map {
+            if is_extended_aggregation(&json_value) {
+                let aggregation = ExtendedAggregation::from_json(&name, json_value, true)?;
+                aggregations.push((name, aggregation));
+            } else {
+                tantivy_aggregations_json.insert(name, json_value);
+            }
+        }
+        if aggregations.is_empty() {
+            return Err(
+                "expected at least one `cardinality`, `composite`, `filters`, or `top_hits` \
+                 aggregation"
+                    .to_string(),
+            );
+        }
+        let tantivy_aggregations: Aggregations =
+            serde_json::from_value(JsonValue::Object(tantivy_aggregations_json))
+                .map_err(|error| error.to_string())?;
+        Ok(Self {
+            tantivy_aggregations,
+            aggregations,
+        })
+    }
+
+    /// Builds the queries of the `filters` aggregations against the given schema: the schema of
+    /// the split on leaves, or the schema of the doc mapping for validation on the root.
+    pub(crate) fn build_filter_queries(
+        &mut self,
+        doc_mapper: &dyn DocMapper,
+        schema: Schema,
+        with_validation: bool,
+        split_id_opt: Option<&str>,
+    ) -> crate::Result<()> {
+        for (_, aggregation) in &mut self.aggregations {
+            aggregation.build_filter_queries(doc_mapper, &schema, with_validation, split_id_opt)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the warmup info required by the queries of the `filters` aggregations.
+    pub(crate) fn warmup_info(&self) -> WarmupInfo {
+        let mut warmup_info = WarmupInfo::default();
+
+        for (_, aggregation) in &self.aggregations {
+            aggregation.add_warmup_info(&mut warmup_info);
+        }
+        warmup_info
+    }
+
+    /// Returns whether a `top_hits` aggregation sorts its hits by score.
+    pub(crate) fn requires_scoring(&self) -> bool {
+        self.aggregations
+            .iter()
+            .any(|(_, aggregation)| aggregation.requires_scoring())
+    }
+
+    /// Returns whether the request contains a `top_hits` aggregation, in which case the documents
+    /// of the top hits must be fetched before the results are finalized.
+    pub(crate) fn has_top_hits(&self) -> bool {
+        self.aggregations
+            .iter()
+            .any(|(_, aggregation)| aggregation.has_top_hits())
+    }
+
+    pub(crate) fn fast_field_names(&self) -> HashSet<String> {
+        let mut fast_field_names = get_fast_field_names(&self.tantivy_aggregations);
+
+        for (_, aggregation) in &self.aggregations {
+            aggregation.add_fast_field_names(&mut fast_field_names);
+        }
+        fast_field_names
+    }
+
+    pub(crate) fn segment_collector(
+        &self,
+        split_id: &str,
+        segment_reader: &SegmentReader,
+        segment_ord: SegmentOrdinal,
+        aggregation_limits: &AggregationLimits,
+    ) -> tantivy::Result<ExtendedAggregationsSegmentCollector> {
+        let tantivy_collector_opt = if self.tantivy_aggregations.is_empty() {
+            None
+        } else {
+            let tantivy_collector = AggregationSegmentCollector::from_agg_req_and_reader(
+                &self.tantivy_aggregations,
+                segment_reader,
+                segment_ord,
+                aggregation_limits,
+            )?;
+            Some(tantivy_collector)
+        };
+        let segment = SegmentContext {
+            split_id,
+            segment_reader,
+            segment_ord,
+        };
+        let readers = AggregationSegmentReader::open_all(&self.aggregations, &segment)?;
+        let states = AggregationSegmentState::new_all(&readers);
+
+        Ok(ExtendedAggregationsSegmentCollector {
+            tantivy_collector_opt,
+            readers,
+            states,
+            limits: SegmentAggregationLimits::new(aggregation_limits.clone()),
+        })
+    }
+}
+
+fn parse_sub_aggregations(
+    json_map_opt: Option<JsonValue>,
+) -> Result<Vec<(String, ExtendedAggregation)>, String> {
+    let Some(json_value) = json_map_opt else {
+        return Ok(Vec::new());
+    };
+    let JsonValue::Object(json_map) = json_value else {
+        return Err("expected an object of sub-aggregations".to_string());
+    };
+    json_map
+        .into_iter()
+        .map(|(name, json_value)| {
+            let aggregation = ExtendedAggregation::from_json(&name, json_value, false)?;
+            Ok((name, aggregation))
+        })
+        .collect()
+}
+
+impl ExtendedAggregation {
+    fn from_json(name: &str, json_value: JsonValue, is_top_level: bool) -> Result<Self, String> {
+        let JsonValue::Object(mut json_map) = json_value else {
+            return Err(format!("aggregation `{name}` must be an object"));
+        };
+        let sub_aggregations_json_opt =
+            match (json_map.remove("aggs"), json_map.remove("aggregations")) {
+                (Some(_), Some(_)) => {
+                    return Err(format!(
+                        "aggregation `{name}` cannot define both `aggs` and `aggregations`"
+                    ));
+                }
+                (aggs_opt, aggregations_opt) => aggs_opt.or(aggregations_opt),
+            };
+        if json_map.len() != 1 {
+            return Err(format!(
+                "aggregation `{name}` must define exactly one aggregation type"
+            ));
+        }
+        let (aggregation_type, params) = json_map.into_iter().next().expect("map has one entry");
+        let invalid_params = |error: serde_json::Error| {
+            format!("invalid `{aggregation_type}` aggregation `{name}`: {error}")
+        };
+        let unsupported_params: &[&str] = match aggregation_type.as_str() {
+            "terms" => &UNSUPPORTED_TERMS_PARAMS,
+            "date_histogram" => &UNSUPPORTED_DATE_HISTOGRAM_PARAMS,
+            "avg" | "min" | "max" | "sum" | "value_count" => &UNSUPPORTED_METRIC_PARAMS,
+            _ => &[],
+        };
+        if let Some(param) = unsupported_params
+            .iter()
+            .find(|param| params.get(**param).is_some())
+        {
+            return Err(format!(
+                "`{param}` option of `{aggregation_type}` aggregation `{name}` is not supported \
+                 when the aggregation contains or is nested in a `cardinality`, `composite`, \
+                 `filters`, or `top_hits` aggregation"
+            ));
+        }
+        let sub_aggregations = parse_sub_aggregations(sub_aggregations_json_opt)?;
+        let is_bucket_aggregation = matches!(
+            aggregation_type.as_str(),
+            "composite" | "filters" | "terms" | "date_histogram"
+        );
+
+        if !is_bucket_aggregation && !sub_aggregations.is_empty() {
+            return Err(format!(
+                "`{aggregation_type}` aggregation `{name}` does not accept sub-aggregations"
+            ));
+        }
+        let aggregation = match aggregation_type.as_str() {
+            "cardinality" => {
+                let params: CardinalityParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let precision_threshold = params
+                    .precision_threshold
+                    .unwrap_or(DEFAULT_PRECISION_THRESHOLD);
+
+                if precision_threshold > MAX_PRECISION_THRESHOLD {
+                    return Err(format!(
+                        "precision threshold of `cardinality` aggregation `{name}` must be lower \
+                         than or equal to {MAX_PRECISION_THRESHOLD}"
+                    ));
+                }
+                ExtendedAggregation::Cardinality(CardinalityAggregation {
+                    field: params.field,
+                    precision_threshold,
+                })
+            }
+            "composite" if is_top_level => {
+                let params: CompositeParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let composite_aggregation =
+                    CompositeAggregation::from_params(name, params, sub_aggregations)?;
+                ExtendedAggregation::Composite(composite_aggregation)
+            }
+            "composite" => {
+                return Err(format!(
+                    "`composite` aggregation `{name}` must be a top-level aggregation"
+                ));
+            }
+            "filters" => {
+                let params: FiltersParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let filters_aggregation =
+                    FiltersAggregation::from_params(name, params, sub_aggregations)?;
+                ExtendedAggregation::Filters(filters_aggregation)
+            }
+            "terms" => {
+                let params: TermsParams = serde_json::from_value(params).map_err(invalid_params)?;
+                let terms_aggregation =
+                    TermsAggregation::from_params(name, params, sub_aggregations)?;
+                ExtendedAggregation::Terms(terms_aggregation)
+            }
+            "date_histogram" => {
+                let params: DateHistogramParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let date_histogram_aggregation =
+                    DateHistogramAggregation::from_params(name, params, sub_aggregations)?;
+                ExtendedAggregation::DateHistogram(date_histogram_aggregation)
+            }
+            "top_hits" => {
+                let top_hits_aggregation = TopHitsAggregation::from_json(name, params)?;
+                ExtendedAggregation::TopHits(top_hits_aggregation)
+            }
+            "avg" | "min" | "max" | "sum" | "value_count" if !is_top_level => {
+                let params: MetricParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let kind = match aggregation_type.as_str() {
+                    "avg" => MetricKind::Avg,
+                    "min" => MetricKind::Min,
+                    "max" => MetricKind::Max,
+                    "sum" => MetricKind::Sum,
+                    _ => MetricKind::ValueCount,
+                };
+                ExtendedAggregation::Metric(MetricAggregation {
+                    kind,
+                    field: params.field,
+                })
+            }
+            _ => {
+                return Err(format!(
+                    "`{aggregation_type}` aggregation `{name}` cannot be combined with \
+                     `cardinality`, `composite`, `filters`, or `top_hits` aggregations. supported \
+                     aggregations are `avg`, `cardinality`, `date_histogram`, `filters`, `max`, \
+                     `min`, `sum`, `terms`, `top_hits`, and `value_count`"
+                ));
+            }
+        };
+        Ok(aggregation)
+    }
+
+    fn sub_aggregations_mut(&mut self) -> &mut [(String, ExtendedAggregation)] {
+        match self {
+            ExtendedAggregation::Composite(composite) => &mut composite.sub_aggregations,
+            ExtendedAggregation::Filters(filters) => &mut filters.sub_aggregations,
+            ExtendedAggregation::Terms(terms) => &mut terms.sub_aggregations,
+            ExtendedAggregation::DateHistogram(date_histogram) => {
+                &mut date_histogram.sub_aggregations
+            }
+            ExtendedAggregation::Cardinality(_)
+            | ExtendedAggregation::TopHits(_)
+            | ExtendedAggregation::Metric(_) => &mut [],
+        }
+    }
+
+    fn sub_aggregations(&self) -> &[(String, ExtendedAggregation)] {
+        match self {
+            ExtendedAggregation::Composite(composite) => &composite.sub_aggregations,
+            ExtendedAggregation::Filters(filters) => &filters.sub_aggregations,
+            ExtendedAggregation::Terms(terms) => &terms.sub_aggregations,
+            ExtendedAggregation::DateHistogram(date_histogram) => &date_histogram.sub_aggregations,
+            ExtendedAggregation::Cardinality(_)
+            | ExtendedAggregation::TopHits(_)
+            | ExtendedAggregation::Metric(_) => &[],
+        }
+    }
+
+    fn requires_scoring(&self) -> bool {
+        if let ExtendedAggregation::TopHits(top_hits) = self {
+            return top_hits.requires_scoring();
+        }
+        self.sub_aggregations()
+            .iter()
+            .any(|(_, sub_aggregation)| sub_aggregation.requires_scoring())
+    }
+
+    fn has_top_hits(&self) -> bool {
+        if let ExtendedAggregation::TopHits(_) = self {
+            return true;
+        }
+        self.sub_aggregations()
+            .iter()
+            .any(|(_, sub_aggregation)| sub_aggregation.has_top_hits())
+    }
+
+    fn build_filter_queries(
+        &mut self,
+        doc_mapper: &dyn DocMapper,
+        schema: &Schema,
+        with_validation: bool,
+        split_id_opt: Option<&str>,
+    ) -> crate::Result<()> {
+        if let ExtendedAggregation::Filters(filters) = self {
+            for filter in &mut filters.filters {
+                let query_ast = filter
+                    .query_ast
+                    .clone()
+                    .parse_user_query(doc_mapper.default_search_fields())
+                    .map_err(|error| SearchError::InvalidQuery(error.to_string()))?;
+                let (query, warmup_info) =
+                    doc_mapper.query(schema.clone(), &query_ast, with_validation, split_id_opt)?;
+                filter.query_opt = Some(Arc::from(query));
+                filter.warmup_info = warmup_info;
+            }
+        }
+        for (_, sub_aggregation) in self.sub_aggregations_mut() {
+            sub_aggregation.build_filter_queries(
+                doc_mapper,
+                schema,
+                with_validation,
+                split_id_opt,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn add_warmup_info(&self, warmup_info: &mut WarmupInfo) {
+        if let ExtendedAggregation::Filters(filters) = self {
+            for filter in &filters.filters {
+                warmup_info.merge(filter.warmup_info.clone());
+            }
+        }
+        for (_, sub_aggregation) in self.sub_aggregations() {
+            sub_aggregation.add_warmup_info(warmup_info);
+        }
+    }
+
+    fn add_fast_field_names(&self, fast_field_names: &mut HashSet<String>) {
+        match self {
+            ExtendedAggregation::Cardinality(cardinality) => {
+                fast_field_names.insert(cardinality.field.clone());
+            }
+            ExtendedAggregation::Composite(composite) => {
+                for source in &composite.sources {
+                    fast_field_names.insert(source.field.clone());
+                }
+            }
+            ExtendedAggregation::Filters(_) => {}
+            ExtendedAggregation::Terms(terms) => {
+                fast_field_names.insert(terms.source.field.clone());
+            }
+            ExtendedAggregation::DateHistogram(date_histogram) => {
+                fast_field_names.insert(date_histogram.source.field.clone());
+            }
+            ExtendedAggregation::TopHits(top_hits) => {
+                top_hits.add_fast_field_names(fast_field_names);
+            }
+            ExtendedAggregation::Metric(metric) => {
+                fast_field_names.insert(metric.field.clone());
+            }
+        }
+        for (_, sub_aggregation) in self.sub_aggregations() {
+            sub_aggregation.add_fast_field_names(fast_field_names);
+        }
+    }
+}
+
+impl CompositeAggregation {
+    fn from_params(
+        name: &str,
+        params: CompositeParams,
+        sub_aggregations: Vec<(String, ExtendedAggregation)>,
+    ) -> Result<Self, String> {
+        if params.sources.is_empty() {
+            return Err(format!(
+                "`composite` aggregation `{name}` must define at least one source"
+            ));
+        }
+        let mut sources: Vec<CompositeSource> = Vec::with_capacity(params.sources.len());
+
+        for source_json in params.sources {
+            if source_json.len() != 1 {
+                return Err(format!(
+                    "each source of `composite` aggregation `{name}` must be an object with a \
+                     single entry"
+                ));
+            }
+            let (source_name, source_params) =
+                source_json.into_iter().next().expect("map has one entry");
+
+            if sources.iter().any(|source| source.name == source_name) {
+                return Err(format!(
+                    "duplicate source `{source_name}` in `composite` aggregation `{name}`"
+                ));
+            }
+            sources.push(CompositeSource::from_json(source_name, source_params)?);
+        }
+        let size = params.size.unwrap_or(DEFAULT_COMPOSITE_SIZE);
+
+        if size == 0 || size > MAX_COMPOSITE_SIZE {
+            return Err(format!(
+                "size of `composite` aggregation `{name}` must be between 1 and \
+                 {MAX_COMPOSITE_SIZE}"
+            ));
+        }
+        let after_key_opt = if let Some(mut after_json) = params.after {
+            let after_key = sources
+                .iter()
+                .map(|source| {
+                    let key_value_json = after_json.remove(&source.name).ok_or_else(|| {
+                        format!(
+                            "`after` key of `composite` aggregation `{name}` is missing source \
+                             `{}`",
+                            source.name
+                        )
+                    })?;
+                    KeyValue::from_json(&key_value_json)
+                })
+                .collect::<Result<Vec<KeyValue>, String>>()?;
+
+            if let Some(unknown_source_name) = after_json.keys().next() {
+                return Err(format!(
+                    "`after` key of `composite` aggregation `{name}` contains unknown source \
+                     `{unknown_source_name}`"
+                ));
+            }
+            Some(after_key)
+        } else {
+            None
+        };
+        Ok(Self {
+            sources,
+            size,
+            after_key_opt,
+            sub_aggregations,
+        })
+    }
+}
+
+impl FiltersAggregation {
+    fn from_params(
+        name: &str,
+        params: FiltersParams,
+        sub_aggregations: Vec<(String, ExtendedAggregation)>,
+    ) -> Result<Self, String> {
+        let (keyed, filters_json): (bool, Vec<(String, JsonValue)>) = match params.filters {
+            JsonValue::Object(json_map) => (true, json_map.into_iter().collect()),
+            JsonValue::Array(json_values) => (
+                false,
+                json_values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(filter_idx, json_value)| (filter_idx.to_string(), json_value))
+                    .collect(),
+            ),
+            _ => {
+                return Err(format!(
+                    "filters of `filters` aggregation `{name}` must be an object or an array"
+                ));
+            }
+        };
+        let filters = filters_json
+            .into_iter()
+            .map(|(filter_name, filter_json)| {
+                let elastic_query_dsl: ElasticQueryDsl = serde_json::from_value(filter_json)
+                    .map_err(|error| {
+                        format!(
+                            "invalid filter `{filter_name}` of `filters` aggregation `{name}`: \
+                             {error}"
+                        )
+                    })?;
+                let query_ast = QueryAst::try_from(elastic_query_dsl).map_err(|error| {
+                    format!(
+                        "invalid filter `{filter_name}` of `filters` aggregation `{name}`: {error}"
+                    )
+                })?;
+                Ok(NamedFilter {
+                    name: filter_name,
+                    query_ast,
+                    query_opt: None,
+                    warmup_info: WarmupInfo::default(),
+                })
+            })
+            .collect::<Result<Vec<NamedFilter>, String>>()?;
+
+        let other_bucket_key_opt = match params.other_bucket_key {
+            Some(other_bucket_key) => Some(other_bucket_key),
+            None if params.other_bucket => Some(DEFAULT_OTHER_BUCKET_KEY.to_string()),
+            None => None,
+        };
+        Ok(Self {
+            keyed,
+            filters,
+            other_bucket_key_opt,
+            sub_aggregations,
+        })
+    }
+
+    fn num_buckets(&self) -> usize {
+        self.filters.len() + self.other_bucket_key_opt.is_some() as usize
+    }
+}
+
+impl TermsAggregation {
+    fn from_params(
+        name: &str,
+        params: TermsParams,
+        sub_aggregations: Vec<(String, ExtendedAggregation)>,
+    ) -> Result<Self, String> {
+        let size = params.size.unwrap_or(DEFAULT_TERMS_SIZE);
+
+        if size == 0 {
+            return Err(format!(
+                "size of `terms` aggregation `{name}` must be strictly positive"
+            ));
+        }
+        // Same default as Elasticsearch for the number of buckets returned by each shard.
+        let split_size = params.split_size.unwrap_or(size + size / 2 + 10).max(size);
+        let order = match params.order {
+            Some(order_json) => parse_terms_order(order_json)
+                .map_err(|error| format!("invalid `terms` aggregation `{name}`: {error}"))?,
+            None => TermsOrder::CountDesc,
+        };
+        let source = CompositeSource {
+            name: name.to_string(),
+            field: params.field,
+            kind: CompositeSourceKind::Terms,
+            order: SourceOrder::Asc,
+            missing_bucket: false,
+        };
+        Ok(Self {
+            source,
+            size,
+            split_size,
+            order,
+            min_doc_count: params.min_doc_count.unwrap_or(1),
+            sub_aggregations,
+        })
+    }
+}
+
+fn parse_terms_order(order_json: JsonMap<String, JsonValue>) -> Result<TermsOrder, String> {
+    if order_json.len() != 1 {
+        return Err("order must be an object with a single entry".to_string());
+    }
+    let (target, direction_json) = order_json.into_iter().next().expect("map has one entry");
+    let order = match (target.as_str(), direction_json.as_str()) {
+        ("_count", Some("desc")) => TermsOrder::CountDesc,
+        ("_count", Some("asc")) => TermsOrder::CountAsc,
+        ("_key", Some("asc")) => TermsOrder::KeyAsc,
+        ("_key", Some("desc")) => TermsOrder::KeyDesc,
+        (target, _) if !matches!(target, "_count" | "_key") => {
+            return Err(format!(
+                "ordering by the sub-aggregation `{target}` is not supported, expected `_count` \
+                 or `_key`"
+            ));
+        }
+        _ => {
+            return Err(format!(
+                "unsupported order `{target}: {direction_json}`, expected `_count` or `_key` \
+                 ordered by `asc` or `desc`"
+            ));
+        }
+    };
+    Ok(order)
+}
+
+impl DateHistogramAggregation {
+    fn from_params(
+        name: &str,
+        params: DateHistogramParams,
+        sub_aggregations: Vec<(String, ExtendedAggregation)>,
+    ) -> Result<Self, String> {
+        let interval_millis = parse_date_interval(
+            params.fixed_interval.as_deref(),
+            params.calendar_interval.as_deref(),
+        )
+        .map_err(|error| format!("invalid `date_histogram` aggregation `{name}`: {error}"))?;
+        let source = CompositeSource {
+            name: name.to_string(),
+            field: params.field,
+            kind: CompositeSourceKind::DateHistogram { interval_millis },
+            order: SourceOrder::Asc,
+            missing_bucket: false,
+        };
+        Ok(Self {
+            source,
+            interval_millis,
+            min_doc_count: params.min_doc_count,
+            sub_aggregations,
+        })
+    }
+}
+
+/// Sorts the buckets of a `terms` aggregation and only keeps the `size` first ones, adding the
+/// doc counts of the others to `sum_other_doc_count`.
+fn truncate_terms_buckets(
+    buckets: &mut Vec<(KeyValue, IntermediateBucket)>,
+    order: TermsOrder,
+    size: usize,
+    sum_other_doc_count: &mut u64,
+) {
+    buckets.sort_by(
+        |(left_key, left_bucket), (right_key, right_bucket)| match order {
+            TermsOrder::CountDesc => right_bucket
+                .doc_count
+                .cmp(&left_bucket.doc_count)
+                .then_with(|| left_key.cmp(right_key)),
+            TermsOrder::CountAsc => left_bucket
+                .doc_count
+                .cmp(&right_bucket.doc_count)
+                .then_with(|| left_key.cmp(right_key)),
+            TermsOrder::KeyAsc => left_key.cmp(right_key),
+            TermsOrder::KeyDesc => right_key.cmp(left_key),
+        },
+    );
+    if buckets.len() > size {
+        *sum_other_doc_count += buckets[size..]
+            .iter()
+            .map(|(_, bucket)| bucket.doc_count)
+            .sum::<u64>();
+        buckets.truncate(size);
+    }
+}
+
+/// Minimum, maximum, sum, and count of the values of a field.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct MetricState {
+    num_values: u64,
+    num_numeric_values: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for MetricState {
+    fn default() -> Self {
+        Self {
+            num_values: 0,
+            num_numeric_values: 0,
+            sum: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl MetricState {
+    fn collect(&mut self, segment_value: SegmentValue) {
+        self.num_values += 1;
+
+        if let Some(value) = segment_value.as_f64() {
+            self.num_numeric_values += 1;
+            self.sum += value;
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+    }
+
+    fn merge(&mut self, other: MetricState) {
+        self.num_values += other.num_values;
+        self.num_numeric_values += other.num_numeric_values;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    fn into_final_result(self, kind: MetricKind) -> JsonValue {
+        let value = match kind {
+            MetricKind::Avg if self.num_numeric_values > 0 => {
+                JsonValue::from(self.sum / self.num_numeric_values as f64)
+            }
+            MetricKind::Min if self.num_numeric_values > 0 => JsonValue::from(self.min),
+            MetricKind::Max if self.num_numeric_values > 0 => JsonValue::from(self.max),
+            MetricKind::Sum => JsonValue::from(self.sum),
+            MetricKind::ValueCount => JsonValue::from(self.num_values),
+            MetricKind::Avg | MetricKind::Min | MetricKind::Max => JsonValue::Null,
+        };
+        serde_json::json!({ "value": value })
+    }
+}
+
+/// Reads the fast fields and evaluates the filters of an aggregation in a segment.
+enum AggregationSegmentReader {
+    Cardinality {
+        value_reader: FastFieldValueReader,
+        precision_threshold: usize,
+    },
+    Composite(Box<CompositeSegmentReader>),
+    Filters {
+        scorers: Vec<Box<dyn Scorer>>,
+        has_other_bucket: bool,
+        sub_readers: Vec<AggregationSegmentReader>,
+    },
+    Buckets(Box<BucketsSegmentReader>),
+    TopHits(Box<TopHitsSegmentReader>),
+    Metric {
+        value_reader: FastFieldValueReader,
+    },
+}
+
+/// Segment being collected.
+struct SegmentContext<'a> {
+    split_id: &'a str,
+    segment_reader: &'a SegmentReader,
+    segment_ord: SegmentOrdinal,
+}
+
+struct CompositeSegmentReader {
+    sources: Vec<CompositeSource>,
+    value_readers: Vec<FastFieldValueReader>,
+    // The `after` key converted into segment key values.
+    after_key_opt: Option<CompositeKey>,
+    size: usize,
+    sub_readers: Vec<AggregationSegmentReader>,
+}
+
+/// Reads the keys of the buckets of a `terms` or `date_histogram` aggregation in a segment.
+struct BucketsSegmentReader {
+    source: CompositeSource,
+    value_reader: FastFieldValueReader,
+    // The order and split size of a `terms` aggregation, used to truncate its buckets.
+    terms_truncation_opt: Option<(TermsOrder, usize)>,
+    sub_readers: Vec<AggregationSegmentReader>,
+}
+
+struct TopHitsSegmentReader {
+    sort_by: SortBy,
+    score_extractor: SortingFieldExtractor,
+    size: usize,
+    split_id: String,
+    segment_ord: SegmentOrdinal,
+}
+
+impl AggregationSegmentReader {
+    fn open_all(
+        aggregations: &[(String, ExtendedAggregation)],
+        segment: &SegmentContext,
+    ) -> tantivy::Result<Vec<Self>> {
+        aggregations
+            .iter()
+            .map(|(_, aggregation)| Self::open(aggregation, segment))
+            .collect()
+    }
+
+    fn open(aggregation: &ExtendedAggregation, segment: &SegmentContext) -> tantivy::Result<Self> {
+        let segment_reader = segment.segment_reader;
+        let segment_aggregation_reader = match aggregation {
+            ExtendedAggregation::Cardinality(cardinality) => Self::Cardinality {
+                value_reader: FastFieldValueReader::open(segment_reader, &cardinality.field)?,
+                precision_threshold: cardinality.precision_threshold,
+            },
+            ExtendedAggregation::Composite(composite) => {
+                let value_readers = composite
+                    .sources
+                    .iter()
+                    .map(|source| FastFieldValueReader::open(segment_reader, &source.field))
+                    .collect::<tantivy::Result<Vec<_>>>()?;
+                let after_key_opt = if let Some(after_key) = &composite.after_key_opt {
+                    let segment_after_key = composite
+                        .sources
+                        .iter()
+                        .zip(&value_readers)
+                        .zip(after_key)
+                        .map(|((source, value_reader), key_value)| {
+                            source.after_segment_key_value(key_value, value_reader)
+                        })
+                        .collect::<std::io::Result<Vec<KeyValue>>>()?;
+                    Some(CompositeKey::new(segment_after_key, &composite.sources))
+                } else {
+                    None
+                };
+                Self::Composite(Box::new(CompositeSegmentReader {
+                    sources: composite.sources.clone(),
+                    value_readers,
+                    after_key_opt,
+                    size: composite.size,
+                    sub_readers: Self::open_all(&composite.sub_aggregations, segment)?,
+                }))
+            }
+            ExtendedAggregation::Filters(filters) => {
+                let enable_scoring = EnableScoring::disabled_from_schema(segment_reader.schema());
+                let scorers = filters
+                    .filters
+                    .iter()
+                    .map(|filter| {
+                        let query = filter.query_opt.as_ref().ok_or_else(|| {
+                            TantivyError::InternalError(format!(
+                                "query of filter `{}` was not built",
+                                filter.name
+                            ))
+                        })?;
+                        query.weight(enable_scoring)?.scorer(segment_reader, 1.0)
+                    })
+                    .collect::<tantivy::Result<Vec<_>>>()?;
+                Self::Filters {
+                    scorers,
+                    has_other_bucket: filters.other_bucket_key_opt.is_some(),
+                    sub_readers: Self::open_all(&filters.sub_aggregations, segment)?,
+                }
+            }
+            ExtendedAggregation::Terms(terms) => Self::Buckets(Box::new(BucketsSegmentReader {
+                source: terms.source.clone(),
+                value_reader: FastFieldValueReader::open(segment_reader, &terms.source.field)?,
+                terms_truncation_opt: Some((terms.order, terms.split_size)),
+                sub_readers: Self::open_all(&terms.sub_aggregations, segment)?,
+            })),
+            ExtendedAggregation::DateHistogram(date_histogram) => {
+                Self::Buckets(Box::new(BucketsSegmentReader {
+                    source: date_histogram.source.clone(),
+                    value_reader: FastFieldValueReader::open(
+                        segment_reader,
+                        &date_histogram.source.field,
+                    )?,
+                    terms_truncation_opt: None,
+                    sub_readers: Self::open_all(&date_histogram.sub_aggregations, segment)?,
+                }))
+            }
+            ExtendedAggregation::TopHits(top_hits) => {
+                let sort_by = top_hits.sort_by();
+                let score_extractor = get_score_extractor(&sort_by, segment_reader)?;
+                Self::TopHits(Box::new(TopHitsSegmentReader {
+                    sort_by,
+                    score_extractor,
+                    size: top_hits.size,
+                    split_id: segment.split_id.to_string(),
+                    segment_ord: segment.segment_ord,
+                }))
+            }
+            ExtendedAggregation::Metric(metric) => Self::Metric {
+                value_reader: FastFieldValueReader::open(segment_reader, &metric.field)?,
+            },
+        };
+        Ok(segment_aggregation_reader)
+    }
+}
+
+/// State of an aggregation in a segment.
+enum AggregationSegmentState {
+    Cardinality {
+        // Terms are only hashed once the segment is harvested.
+        term_ords: FnvHashSet<u64>,
+        sketch: HyperLogLogPlusPlus,
+    },
+    Composite(BTreeMap<CompositeKey, SegmentBucket>),
+    Filters(Vec<SegmentBucket>),
+    Buckets(BTreeMap<KeyValue, SegmentBucket>),
+    TopHits {
+        num_docs: u64,
+        top_hits: SegmentTopHits,
+    },
+    Metric(MetricState),
+}
+
+struct SegmentBucket {
+    doc_count: u64,
+    sub_states: Vec<AggregationSegmentState>,
+}
+
+impl SegmentBucket {
+    fn new(sub_readers: &[AggregationSegmentReader]) -> Self {
+        Self {
+            doc_count: 0,
+            sub_states: AggregationSegmentState::new_all(sub_readers),
+        }
+    }
+
+    /// Estimates the memory used by a bucket of a `terms` or `date_histogram` aggregation,
+    /// excluding the memory used by the states of its sub-aggregations once they collect values.
+    fn memory_estimate(sub_readers: &[AggregationSegmentReader]) -> usize {
+        size_of::<(KeyValue, SegmentBucket)>()
+            + sub_readers.len() * size_of::<AggregationSegmentState>()
+    }
+
+    fn collect(
+        &mut self,
+        sub_readers: &mut [AggregationSegmentReader],
+        doc_id: DocId,
+        score: Score,
+        limits: &mut SegmentAggregationLimits,
+    ) {
+        self.doc_count += 1;
+
+        for (sub_reader, sub_state) in sub_readers.iter_mut().zip(&mut self.sub_states) {
+            sub_state.collect(sub_reader, doc_id, score, limits);
+        }
+    }
+
+    fn harvest(
+        self,
+        sub_readers: &[AggregationSegmentReader],
+    ) -> tantivy::Result<IntermediateBucket> {
+        Ok(IntermediateBucket {
+            doc_count: self.doc_count,
+            sub_results: AggregationSegmentState::harvest_all(self.sub_states, sub_readers)?,
+        })
+    }
+}
+
+impl AggregationSegmentState {
+    fn new_all(readers: &[AggregationSegmentReader]) -> Vec<Self> {
+        readers.iter().map(Self::new).collect()
+    }
+
+    fn new(reader: &AggregationSegmentReader) -> Self {
+        match reader {
+            AggregationSegmentReader::Cardinality {
+                precision_threshold,
+                ..
+            } => Self::Cardinality {
+                term_ords: FnvHashSet::default(),
+                sketch: HyperLogLogPlusPlus::new(*precision_threshold),
+            },
+            AggregationSegmentReader::Composite(_) => Self::Composite(BTreeMap::new()),
+            AggregationSegmentReader::Filters {
+                scorers,
+                has_other_bucket,
+                sub_readers,
+            } => {
+                let num_buckets = scorers.len() + *has_other_bucket as usize;
+                let buckets = (0..num_buckets)
+                    .map(|_| SegmentBucket::new(sub_readers))
+                    .collect();
+                Self::Filters(buckets)
+            }
+            AggregationSegmentReader::Buckets(_) => Self::Buckets(BTreeMap::new()),
+            AggregationSegmentReader::TopHits(top_hits_reader) => Self::TopHits {
+                num_docs: 0,
+                top_hits: SegmentTopHits::new(&top_hits_reader.sort_by, top_hits_reader.size),
+            },
+            AggregationSegmentReader::Metric { .. } => Self::Metric(MetricState::default()),
+        }
+    }
+
+    fn collect(
+        &mut self,
+        reader: &mut AggregationSegmentReader,
+        doc_id: DocId,
+        score: Score,
+        limits: &mut SegmentAggregationLimits,
+    ) {
+        match (self, reader) {
+            (
+                Self::Cardinality { term_ords, sketch },
+                AggregationSegmentReader::Cardinality { value_reader, .. },
+            ) => {
+                value_reader.for_each_value(doc_id, |segment_value| match segment_value {
+                    SegmentValue::TermOrd(term_ord) => {
+                        if term_ords.insert(term_ord) {
+                            limits.add_memory_consumed(size_of::<u64>());
+                        }
+                    }
+                    segment_value => sketch.insert_hash(hash_segment_value(segment_value)),
+                });
+            }
+            (Self::Composite(buckets), AggregationSegmentReader::Composite(composite_reader)) => {
+                collect_composite(buckets, composite_reader, doc_id, score, limits);
+            }
+            (
+                Self::Filters(buckets),
+                AggregationSegmentReader::Filters {
+                    scorers,
+                    has_other_bucket,
+                    sub_readers,
+                },
+            ) => {
+                let mut matches_any_filter = false;
+
+                for (scorer, bucket) in scorers.iter_mut().zip(buckets.iter_mut()) {
+                    if scorer.doc() < doc_id {
+                        scorer.seek(doc_id);
+                    }
+                    if scorer.doc() == doc_id {
+                        matches_any_filter = true;
+                        bucket.collect(sub_readers, doc_id, score, limits);
+                    }
+                }
+                if *has_other_bucket && !matches_any_filter {
+                    if let Some(other_bucket) = buckets.last_mut() {
+                        other_bucket.collect(sub_readers, doc_id, score, limits);
+                    }
+                }
+            }
+            (Self::Buckets(buckets), AggregationSegmentReader::Buckets(buckets_reader)) => {
+                let BucketsSegmentReader {
+                    source,
+                    value_reader,
+                    sub_readers,
+                    ..
+                } = buckets_reader.as_mut();
+                let mut key_values = Vec::new();
+                value_reader.for_each_value(doc_id, |segment_value| {
+                    key_values.push(source.segment_key_value(segment_value))
+                });
+                key_values.sort();
+                key_values.dedup();
+
+                for key_value in key_values {
+                    let bucket = match buckets.entry(key_value) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => {
+                            let memory_estimate = SegmentBucket::memory_estimate(sub_readers);
+
+                            if !limits.add_bucket(memory_estimate) {
+                                return;
+                            }
+                            entry.insert(SegmentBucket::new(sub_readers))
+                        }
+                    };
+                    bucket.collect(sub_readers, doc_id, score, limits);
+                }
+            }
+            (
+                Self::TopHits { num_docs, top_hits },
+                AggregationSegmentReader::TopHits(top_hits_reader),
+            ) => {
+                *num_docs += 1;
+                top_hits.collect(&top_hits_reader.score_extractor, doc_id, score);
+            }
+            (Self::Metric(metric_state), AggregationSegmentReader::Metric { value_reader }) => {
+                value_reader
+                    .for_each_value(doc_id, |segment_value| metric_state.collect(segment_value));
+            }
+            _ => {}
+        }
+    }
+
+    fn harvest_all(
+        states: Vec<Self>,
+        readers: &[AggregationSegmentReader],
+    ) -> tantivy::Result<Vec<IntermediateAggregationResult>> {
+        states
+            .into_iter()
+            .zip(readers)
+            .map(|(state, reader)| state.harvest(reader))
+            .collect()
+    }
+
+    fn harvest(
+        self,
+        reader: &AggregationSegmentReader,
+    ) -> tantivy::Result<IntermediateAggregationResult> {
+        let intermediate_result = match (self, reader) {
+            (
+                Self::Cardinality {
+                    term_ords,
+                    mut sketch,
+                },
+                AggregationSegmentReader::Cardinality { value_reader, .. },
+            ) => {
+                for term_ord in term_ords {
+                    let term = value_reader.term(term_ord)?;
+                    sketch.insert_hash(hash_bytes(term.as_bytes()));
+                }
+                IntermediateAggregationResult::Cardinality(sketch)
+            }
+            (Self::Composite(buckets), AggregationSegmentReader::Composite(composite_reader)) => {
+                let mut intermediate_buckets = Vec::with_capacity(buckets.len());
+
+                for (key, bucket) in buckets {
+                    let key_values = key
+                        .into_key_values()
+                        .into_iter()
+                        .zip(&composite_reader.sources)
+                        .zip(&composite_reader.value_readers)
+                        .map(|((key_value, source), value_reader)| {
+                            source.split_key_value(key_value, value_reader.str_column())
+                        })
+                        .collect::<std::io::Result<Vec<KeyValue>>>()?;
+                    let intermediate_bucket = bucket.harvest(&composite_reader.sub_readers)?;
+                    intermediate_buckets.push((key_values, intermediate_bucket));
+                }
+                IntermediateAggregationResult::Composite(intermediate_buckets)
+            }
+            (Self::Filters(buckets), AggregationSegmentReader::Filters { sub_readers, .. }) => {
+                let intermediate_buckets = buckets
+                    .into_iter()
+                    .map(|bucket| bucket.harvest(sub_readers))
+                    .collect::<tantivy::Result<Vec<_>>>()?;
+                IntermediateAggregationResult::Filters(intermediate_buckets)
+            }
+            (Self::Buckets(buckets), AggregationSegmentReader::Buckets(buckets_reader)) => {
+                let mut intermediate_buckets = Vec::with_capacity(buckets.len());
+
+                for (key_value, bucket) in buckets {
+                    let key_value = buckets_reader
+                        .source
+                        .split_key_value(key_value, buckets_reader.value_reader.str_column())?;
+                    let intermediate_bucket = bucket.harvest(&buckets_reader.sub_readers)?;
+                    intermediate_buckets.push((key_value, intermediate_bucket));
+                }
+                let mut sum_other_doc_count = 0;
+
+                if let Some((order, split_size)) = buckets_reader.terms_truncation_opt {
+                    truncate_terms_buckets(
+                        &mut intermediate_buckets,
+                        order,
+                        split_size,
+                        &mut sum_other_doc_count,
+                    );
+                }
+                IntermediateAggregationResult::Buckets {
+                    buckets: intermediate_buckets,
+                    sum_other_doc_count,
+                }
+            }
+            (
+                Self::TopHits { num_docs, top_hits },
+                AggregationSegmentReader::TopHits(top_hits_reader),
+            ) => {
+                let partial_hits = top_hits.harvest(
+                    &top_hits_reader.score_extractor,
+                    &top_hits_reader.split_id,
+                    top_hits_reader.segment_ord,
+                )?;
+                IntermediateAggregationResult::TopHits(IntermediateTopHits::new(
+                    num_docs,
+                    partial_hits,
+                ))
+            }
+            (Self::Metric(metric_state), AggregationSegmentReader::Metric { .. }) => {
+                IntermediateAggregationResult::Metric(metric_state)
+            }
+            _ => {
+                return Err(TantivyError::InternalError(
+                    "aggregation segment state does not match its reader".to_string(),
+                ));
+            }
+        };
+        Ok(intermediate_result)
+    }
+}
+
+fn hash_segment_value(segment_value: SegmentValue) -> u64 {
+    let (type_tag, value_bytes): (u8, [u8; 8]) = match segment_value {
+        SegmentValue::TermOrd(term_ord) => (0, term_ord.to_le_bytes()),
+        // Unsigned values representable as signed ones are hashed as such, so that the same
+        // number is counted once across splits with different column types.
+        SegmentValue::U64(value) => match i64::try_from(value) {
+            Ok(value) => (1, value.to_le_bytes()),
+            Err(_) => (2, value.to_le_bytes()),
+        },
+        SegmentValue::I64(value) => (1, value.to_le_bytes()),
+        SegmentValue::F64(value) => (3, value.to_le_bytes()),
+        SegmentValue::Bool(value) => (4, (value as u64).to_le_bytes()),
+        SegmentValue::Date(date_time) => (5, date_time.into_timestamp_nanos().to_le_bytes()),
+    };
+    let mut bytes = [0u8; 9];
+    bytes[0] = type_tag;
+    bytes[1..].copy_from_slice(&value_bytes);
+    hash_bytes(&bytes)
+}
+
+/// Adds the document to the buckets of its keys, only keeping the `size` first buckets following
+/// the `after` key.
+///
+/// A bucket evicted because `size` smaller keys were collected can never be part of the first
+/// `size` buckets again, so the doc counts of the buckets kept are exact.
+fn collect_composite(
+    buckets: &mut BTreeMap<CompositeKey, SegmentBucket>,
+    composite_reader: &mut CompositeSegmentReader,
+    doc_id: DocId,
+    score: Score,
+    limits: &mut SegmentAggregationLimits,
+) {
+    let CompositeSegmentReader {
+        sources,
+        value_readers,
+        after_key_opt,
+        size,
+        sub_readers,
+    } = composite_reader;
+    let mut source_key_values: Vec<Vec<KeyValue>> = Vec::with_capacity(sources.len());
+
+    for (source, value_reader) in sources.iter().zip(value_readers.iter()) {
+        let mut key_values = Vec::new();
+        value_reader.for_each_value(doc_id, |segment_value| {
+            key_values.push(source.segment_key_value(segment_value))
+        });
+        if key_values.is_empty() {
+            if !source.missing_bucket {
+                return;
+            }
+            key_values.push(KeyValue::Null);
+        }
+        key_values.sort();
+        key_values.dedup();
+        source_key_values.push(key_values);
+    }
+    for key_values in source_key_values.into_iter().multi_cartesian_product() {
+        let key = CompositeKey::new(key_values, sources);
+
+        if let Some(after_key) = after_key_opt.as_ref() {
+            if key <= *after_key {
+                continue;
+            }
+        }
+        if !buckets.contains_key(&key) {
+            if buckets.len() >= *size {
+                let Some((last_key, _)) = buckets.last_key_value() else {
+                    continue;
+                };
+                if key > *last_key {
+                    continue;
+                }
+            }
+            buckets.insert(key.clone(), SegmentBucket::new(sub_readers));
+
+            if buckets.len() > *size {
+                buckets.pop_last();
+            }
+        }
+        if let Some(bucket) = buckets.get_mut(&key) {
+            bucket.collect(sub_readers, doc_id, score, limits);
+        }
+    }
+}
+
+/// Accounts for the buckets and the memory used by the aggregations of a segment, which are
+/// bounded by the aggregation limits of the request, shared with the tantivy aggregations.
+struct SegmentAggregationLimits {
+    aggregation_limits: AggregationLimits,
+    num_buckets: u32,
+    // The error raised when a limit is first exceeded, returned when the segment is harvested.
+    error_opt: Option<TantivyError>,
+}
+
+impl SegmentAggregationLimits {
+    fn new(aggregation_limits: AggregationLimits) -> Self {
+        Self {
+            aggregation_limits,
+            num_buckets: 0,
+            error_opt: None,
+        }
+    }
+
+    fn is_exceeded(&self) -> bool {
+        self.error_opt.is_some()
+    }
+
+    /// Accounts for a new bucket, returns `false` if a limit is exceeded.
+    fn add_bucket(&mut self, num_bytes: usize) -> bool {
+        if self.is_exceeded() {
+            return false;
+        }
+        self.num_buckets += 1;
+        let bucket_limit = self.aggregation_limits.get_bucket_limit();
+
+        if self.num_buckets > bucket_limit {
+            let error = AggregationError::BucketLimitExceeded {
+                limit: bucket_limit,
+                current: self.num_buckets,
+            };
+            self.error_opt = Some(TantivyError::AggregationError(error));
+            return false;
+        }
+        self.add_memory_consumed(num_bytes)
+    }
+
+    /// Accounts for memory used by a segment state, returns `false` if the limit is exceeded.
+    fn add_memory_consumed(&mut self, num_bytes: usize) -> bool {
+        if self.is_exceeded() {
+            return false;
+        }
+        if let Err(error) = self
+            .aggregation_limits
+            .add_memory_consumed(num_bytes as u64)
+        {
+            self.error_opt = Some(error);
+            return false;
+        }
+        true
+    }
+}
+
+/// Collects the aggregations of an [`ExtendedAggregations`] request in a segment.
+pub(crate) struct ExtendedAggregationsSegmentCollector {
+    tantivy_collector_opt: Option<AggregationSegmentCollector>,
+    readers: Vec<AggregationSegmentReader>,
+    states: Vec<AggregationSegmentState>,
+    limits: SegmentAggregationLimits,
+}
+
+impl ExtendedAggregationsSegmentCollector {
+    pub fn collect(&mut self, doc_id: DocId, score: Score) {
+        if let Some(tantivy_collector) = &mut self.tantivy_collector_opt {
+            tantivy_collector.collect(doc_id, score);
+        }
+        // Once a limit is exceeded, the segment fails and collecting is pointless.
+        if self.limits.is_exceeded() {
+            return;
+        }
+        for (reader, state) in self.readers.iter_mut().zip(&mut self.states) {
+            state.collect(reader, doc_id, score, &mut self.limits);
+        }
+    }
+
+    pub fn harvest(self) -> tantivy::Result<IntermediateExtendedAggregationResults> {
+        if let Some(error) = self.limits.error_opt {
+            return Err(error);
+        }
+        let tantivy_results_opt = self
+            .tantivy_collector_opt
+            .map(|tantivy_collector| tantivy_collector.harvest())
+            .transpose()?;
+        let results = AggregationSegmentState::harvest_all(self.states, &self.readers)?;
+        Ok(IntermediateExtendedAggregationResults {
+            tantivy_results_opt,
+            results,
+        })
+    }
+}
+
+/// Intermediate results of an [`ExtendedAggregations`] request, mergeable across segments,
+/// splits, and nodes.
+///
+/// The results are stored in the order in which the aggregations are defined in the request.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct IntermediateExtendedAggregationResults {
+    tantivy_results_opt: Option<IntermediateAggregationResults>,
+    results: Vec<IntermediateAggregationResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum IntermediateAggregationResult {
+    Cardinality(HyperLogLogPlusPlus),
+    /// Buckets sorted by key.
+    Composite(Vec<(Vec<KeyValue>, IntermediateBucket)>),
+    Filters(Vec<IntermediateBucket>),
+    /// Buckets of a `terms` or `date_histogram` aggregation, in no particular order.
+    Buckets {
+        buckets: Vec<(KeyValue, IntermediateBucket)>,
+        // Doc count of the `terms` buckets dropped when truncating the buckets of a split.
+        sum_other_doc_count: u64,
+    },
+    TopHits(IntermediateTopHits),
+    Metric(MetricState),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct IntermediateBucket {
+    doc_count: u64,
+    sub_results: Vec<IntermediateAggregationResult>,
+}
+
+impl IntermediateExtendedAggregationResults {
+    /// Returns the results of the request when no document was collected.
+    pub fn empty(aggregations: &ExtendedAggregations) -> Self {
+        Self {
+            tantivy_results_opt: None,
+            results: IntermediateAggregationResult::empty_all(&aggregations.aggregations),
+        }
+    }
+
+    pub fn merge(
+        &mut self,
+        other: IntermediateExtendedAggregationResults,
+        aggregations: &ExtendedAggregations,
+    ) -> tantivy::Result<()> {
+        if let Some(other_tantivy_results) = other.tantivy_results_opt {
+            if let Some(tantivy_results) = &mut self.tantivy_results_opt {
+                tantivy_results.merge_fruits(other_tantivy_results)?;
+            } else {
+                self.tantivy_results_opt = Some(other_tantivy_results);
+            }
+        }
+        IntermediateAggregationResult::merge_all(
+            &mut self.results,
+            other.results,
+            &aggregations.aggregations,
+        )
+    }
+
+    /// Returns the hits of the `top_hits` aggregations of the final buckets, whose documents must
+    /// be fetched to finalize the results.
+    pub fn top_hits_partial_hits(mut self, aggregations: &ExtendedAggregations) -> Vec<PartialHit> {
+        IntermediateAggregationResult::prune_all(&mut self.results, &aggregations.aggregations);
+
+        let mut partial_hits = Vec::new();
+        IntermediateAggregationResult::add_top_hits_all(
+            &self.results,
+            &aggregations.aggregations,
+            &mut partial_hits,
+        );
+        let mut global_doc_addresses = HashSet::new();
+        partial_hits.retain(|partial_hit| {
+            global_doc_addresses.insert(GlobalDocAddress::from_partial_hit(partial_hit))
+        });
+        partial_hits
+    }
+
+    /// Computes the final results of the request, formatted as in Elasticsearch.
+    ///
+    /// `top_hits_docs` holds the documents of the hits returned by
+    /// [`Self::top_hits_partial_hits`].
+    pub fn into_final_result(
+        mut self,
+        aggregations: ExtendedAggregations,
+        top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
+        aggregation_limits: &AggregationLimits,
+    ) -> crate::Result<JsonValue> {
+        IntermediateAggregationResult::prune_all(&mut self.results, &aggregations.aggregations);
+
+        let mut final_result = if aggregations.tantivy_aggregations.is_empty() {
+            JsonMap::new()
+        } else {
+            let tantivy_final_result = self
+                .tantivy_results_opt
+                .unwrap_or_default()
+                .into_final_result(aggregations.tantivy_aggregations, aggregation_limits)?;
+            match serde_json::to_value(tantivy_final_result)? {
+                JsonValue::Object(json_map) => json_map,
+                _ => JsonMap::new(),
+            }
+        };
+        final_result.extend(final_results(
+            self.results,
+            &aggregations.aggregations,
+            top_hits_docs,
+        )?);
+        Ok(JsonValue::Object(final_result))
+    }
+}
+
+impl IntermediateAggregationResult {
+    fn empty_all(aggregations: &[(String, ExtendedAggregation)]) -> Vec<Self> {
+        aggregations
+            .iter()
+            .map(|(_, aggregation)| Self::empty(aggregation))
+            .collect()
+    }
+
+    fn empty(aggregation: &ExtendedAggregation) -> Self {
+        match aggregation {
+            ExtendedAggregation::Cardinality(cardinality) => {
+                Self::Cardinality(HyperLogLogPlusPlus::new(cardinality.precision_threshold))
+            }
+            ExtendedAggregation::Composite(_) => Self::Composite(Vec::new()),
+            ExtendedAggregation::Filters(filters) => {
+                let buckets = (0..filters.num_buckets())
+                    .map(|_| IntermediateBucket {
+                        doc_count: 0,
+                        sub_results: Self::empty_all(&filters.sub_aggregations),
+                    })
+                    .collect();
+                Self::Filters(buckets)
+            }
+            ExtendedAggregation::Terms(_) | ExtendedAggregation::DateHistogram(_) => {
+                Self::Buckets {
+                    buckets: Vec::new(),
+                    sum_other_doc_count: 0,
+                }
+            }
+            ExtendedAggregation::TopHits(_) => Self::TopHits(IntermediateTopHits::default()),
+            ExtendedAggregation::Metric(_) => Self::Metric(MetricState::default()),
+        }
+    }
+
+    fn merge_all(
+        results: &mut [Self],
+        other_results: Vec<Self>,
+        aggregations: &[(String, ExtendedAggregation)],
+    ) -> tantivy::Result<()> {
+        if results.len() != other_results.len() || results.len() != aggregations.len() {
+            return Err(TantivyError::InternalError(
+                "cannot merge aggregation results of different requests".to_string(),
+            ));
+        }
+        for ((result, other_result), (_, aggregation)) in
+            results.iter_mut().zip(other_results).zip(aggregations)
+        {
+            result.merge(other_result, aggregation)?;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self, aggregation: &ExtendedAggregation) -> tantivy::Result<()> {
+        match (self, other, aggregation) {
+            (Self::Cardinality(sketch), Self::Cardinality(other_sketch), _) => {
+                sketch.merge(other_sketch);
+            }
+            (
+                Self::Composite(buckets),
+                Self::Composite(other_buckets),
+                ExtendedAggregation::Composite(composite),
+            ) => {
+                let mut merged_buckets: BTreeMap<CompositeKey, IntermediateBucket> =
+                    BTreeMap::new();
+
+                for (key_values, bucket) in std::mem::take(buckets).into_iter().chain(other_buckets)
+                {
+                    let key = CompositeKey::new(key_values, &composite.sources);
+
+                    if let Some(merged_bucket) = merged_buckets.get_mut(&key) {
+                        merged_bucket.merge(bucket, &composite.sub_aggregations)?;
+                    } else {
+                        merged_buckets.insert(key, bucket);
+                    }
+                }
+                *buckets = merged_buckets
+                    .into_iter()
+                    .take(composite.size)
+                    .map(|(key, bucket)| (key.into_key_values(), bucket))
+                    .collect();
+            }
+            (
+                Self::Filters(buckets),
+                Self::Filters(other_buckets),
+                ExtendedAggregation::Filters(filters),
+            ) if buckets.len() == other_buckets.len() => {
+                for (bucket, other_bucket) in buckets.iter_mut().zip(other_buckets) {
+                    bucket.merge(other_bucket, &filters.sub_aggregations)?;
+                }
+            }
+            (
+                Self::Buckets {
+                    buckets,
+                    sum_other_doc_count,
+                },
+                Self::Buckets {
+                    buckets: other_buckets,
+                    sum_other_doc_count: other_sum_other_doc_count,
+                },
+                aggregation,
+            ) => {
+                let mut merged_buckets: BTreeMap<KeyValue, IntermediateBucket> = BTreeMap::new();
+
+                for (key_value, bucket) in std::mem::take(buckets).into_iter().chain(other_buckets)
+                {
+                    if let Some(merged_bucket) = merged_buckets.get_mut(&key_value) {
+                        merged_bucket.merge(bucket, aggregation.sub_aggregations())?;
+                    } else {
+                        merged_buckets.insert(key_value, bucket);
+                    }
+                }
+                *buckets = merged_buckets.into_iter().collect();
+                *sum_other_doc_count += other_sum_other_doc_count;
+            }
+            (
+                Self::TopHits(top_hits_result),
+                Self::TopHits(other_top_hits_result),
+                ExtendedAggregation::TopHits(top_hits),
+            ) => {
+                top_hits_result.merge(other_top_hits_result, top_hits);
+            }
+            (Self::Metric(metric_state), Self::Metric(other_metric_state), _) => {
+                metric_state.merge(other_metric_state);
+            }
+            _ => {
+                return Err(TantivyError::InternalError(
+                    "cannot merge aggregation results of different types".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn buckets(&self) -> Vec<&IntermediateBucket> {
+        match self {
+            Self::Composite(buckets) => buckets.iter().map(|(_, bucket)| bucket).collect(),
+            Self::Filters(buckets) => buckets.iter().collect(),
+            Self::Buckets { buckets, .. } => buckets.iter().map(|(_, bucket)| bucket).collect(),
+            Self::Cardinality(_) | Self::TopHits(_) | Self::Metric(_) => Vec::new(),
+        }
+    }
+
+    fn buckets_mut(&mut self) -> Vec<&mut IntermediateBucket> {
+        match self {
+            Self::Composite(buckets) => buckets.iter_mut().map(|(_, bucket)| bucket).collect(),
+            Self::Filters(buckets) => buckets.iter_mut().collect(),
+            Self::Buckets { buckets, .. } => buckets.iter_mut().map(|(_, bucket)| bucket).collect(),
+            Self::Cardinality(_) | Self::TopHits(_) | Self::Metric(_) => Vec::new(),
+        }
+    }
+
+    fn prune_all(results: &mut [Self], aggregations: &[(String, ExtendedAggregation)]) {
+        for (result, (_, aggregation)) in results.iter_mut().zip(aggregations) {
+            result.prune(aggregation);
+        }
+    }
+
+    /// Drops the buckets that are not part of the final results and sorts the remaining ones.
+    fn prune(&mut self, aggregation: &ExtendedAggregation) {
+        match (&mut *self, aggregation) {
+            (
+                Self::Buckets {
+                    buckets,
+                    sum_other_doc_count,
+                },
+                ExtendedAggregation::Terms(terms),
+            ) => {
+                buckets.retain(|(_, bucket)| bucket.doc_count >= terms.min_doc_count);
+                truncate_terms_buckets(buckets, terms.order, terms.size, sum_other_doc_count);
+            }
+            (Self::Buckets { buckets, .. }, ExtendedAggregation::DateHistogram(date_histogram)) => {
+                buckets.retain(|(_, bucket)| bucket.doc_count >= date_histogram.min_doc_count);
+                buckets.sort_by(|(left_key, _), (right_key, _)| left_key.cmp(right_key));
+            }
+            _ => {}
+        }
+        for bucket in self.buckets_mut() {
+            Self::prune_all(&mut bucket.sub_results, aggregation.sub_aggregations());
+        }
+    }
+
+    fn add_top_hits_all(
+        results: &[Self],
+        aggregations: &[(String, ExtendedAggregation)],
+        partial_hits: &mut Vec<PartialHit>,
+    ) {
+        for (result, (_, aggregation)) in results.iter().zip(aggregations) {
+            if let Self::TopHits(top_hits_result) = result {
+                partial_hits.extend(top_hits_result.partial_hits());
+            }
+            for bucket in result.buckets() {
+                Self::add_top_hits_all(
+                    &bucket.sub_results,
+                    aggregation.sub_aggregations(),
+                    partial_hits,
+                );
+            }
+        }
+    }
+
+    fn into_final_result(
+        self,
+        aggregation: &ExtendedAggregation,
+        top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
+    ) -> crate::Result<JsonValue> {
+        let final_result = match (self, aggregation) {
+            (Self::Cardinality(sketch), _) => serde_json::json!({ "value": sketch.estimate() }),
+            (Self::Composite(buckets), ExtendedAggregation::Composite(composite)) => {
+                let source_names = || composite.sources.iter().map(|source| source.name.clone());
+                let key_json = |key_values: Vec<KeyValue>| -> JsonMap<String, JsonValue> {
+                    source_names()
+                        .zip(key_values.into_iter().map(KeyValue::into_json))
+                        .collect()
+                };
+                let mut final_result = JsonMap::new();
+
+                if let Some((last_key_values, _)) = buckets.last() {
+                    final_result.insert(
+                        "after_key".to_string(),
+                        JsonValue::Object(key_json(last_key_values.clone())),
+                    );
+                }
+                let final_buckets = buckets
+                    .into_iter()
+                    .map(|(key_values, bucket)| {
+                        let mut final_bucket =
+                            bucket.into_final_result(&composite.sub_aggregations, top_hits_docs)?;
+                        final_bucket
+                            .insert("key".to_string(), JsonValue::Object(key_json(key_values)));
+                        Ok(JsonValue::Object(final_bucket))
+                    })
+                    .collect::<crate::Result<_>>()?;
+                final_result.insert("buckets".to_string(), JsonValue::Array(final_buckets));
+                JsonValue::Object(final_result)
+            }
+            (Self::Filters(buckets), ExtendedAggregation::Filters(filters)) => {
+                let bucket_names = filters
+                    .filters
+                    .iter()
+                    .map(|filter| filter.name.clone())
+                    .chain(filters.other_bucket_key_opt.clone());
+                let named_final_buckets = bucket_names
+                    .zip(buckets)
+                    .map(|(name, bucket)| {
+                        let final_bucket =
+                            bucket.into_final_result(&filters.sub_aggregations, top_hits_docs)?;
+                        Ok((name, JsonValue::Object(final_bucket)))
+                    })
+                    .collect::<crate::Result<Vec<_>>>()?
+                    .into_iter();
+                let final_buckets = if filters.keyed {
+                    JsonValue::Object(named_final_buckets.collect())
+                } else {
+                    JsonValue::Array(
+                        named_final_buckets
+                            .map(|(_, final_bucket)| final_bucket)
+                            .collect(),
+                    )
+                };
+                serde_json::json!({ "buckets": final_buckets })
+            }
+            (
+                Self::Buckets {
+                    buckets,
+                    sum_other_doc_count,
+                },
+                ExtendedAggregation::Terms(terms),
+            ) => {
+                let final_buckets = buckets
+                    .into_iter()
+                    .map(|(key_value, bucket)| {
+                        let mut final_bucket =
+                            bucket.into_final_result(&terms.sub_aggregations, top_hits_docs)?;
+                        final_bucket.insert("key".to_string(), key_value.into_json());
+                        Ok(JsonValue::Object(final_bucket))
+                    })
+                    .collect::<crate::Result<Vec<_>>>()?;
+                serde_json::json!({
+                    "sum_other_doc_count": sum_other_doc_count,
+                    "buckets": final_buckets,
+                })
+            }
+            (Self::Buckets { buckets, .. }, ExtendedAggregation::DateHistogram(date_histogram)) => {
+                let final_buckets =
+                    date_histogram_final_buckets(buckets, date_histogram, top_hits_docs)?;
+                serde_json::json!({ "buckets": final_buckets })
+            }
+            (Self::TopHits(top_hits_result), ExtendedAggregation::TopHits(top_hits)) => {
+                top_hits_result.into_final_result(top_hits, top_hits_docs)
+            }
+            (Self::Metric(metric_state), ExtendedAggregation::Metric(metric)) => {
+                metric_state.into_final_result(metric.kind)
+            }
+            _ => JsonValue::Null,
+        };
+        Ok(final_result)
+    }
+}
+
+/// Builds the buckets of a `date_histogram` aggregation, sorted by key. When `min_doc_count` is
+/// 0, the empty buckets between the first and the last bucket are filled in.
+fn date_histogram_final_buckets(
+    buckets: Vec<(KeyValue, IntermediateBucket)>,
+    date_histogram: &DateHistogramAggregation,
+    top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
+) -> crate::Result<Vec<JsonValue>> {
+    let interval_millis = date_histogram.interval_millis;
+    let mut final_buckets: Vec<JsonValue> = Vec::with_capacity(buckets.len());
+    let mut previous_key_opt: Option<i64> = None;
+
+    let to_final_bucket = |key_value: KeyValue, bucket: IntermediateBucket| {
+        let mut final_bucket =
+            bucket.into_final_result(&date_histogram.sub_aggregations, top_hits_docs)?;
+        final_bucket.insert("key".to_string(), key_value.into_json());
+        crate::Result::Ok(JsonValue::Object(final_bucket))
+    };
+    for (key_value, bucket) in buckets {
+        if let (KeyValue::I64(key), Some(previous_key)) = (&key_value, previous_key_opt) {
+            let num_empty_buckets = if date_histogram.min_doc_count == 0 {
+                (key - previous_key) / interval_millis - 1
+            } else {
+                0
+            };
+            if final_buckets.len() as i64 + num_empty_buckets >= MAX_DATE_HISTOGRAM_BUCKETS {
+                return Err(SearchError::InvalidAggregationRequest(format!(
+                    "`date_histogram` aggregation `{}` would return more than \
+                     {MAX_DATE_HISTOGRAM_BUCKETS} buckets, use a larger interval",
+                    date_histogram.source.name
+                )));
+            }
+            for empty_bucket_idx in 1..=num_empty_buckets {
+                let empty_key = previous_key + empty_bucket_idx * interval_millis;
+                let empty_bucket = IntermediateBucket {
+                    doc_count: 0,
+                    sub_results: IntermediateAggregationResult::empty_all(
+                        &date_histogram.sub_aggregations,
+                    ),
+                };
+                final_buckets.push(to_final_bucket(KeyValue::I64(empty_key), empty_bucket)?);
+            }
+        }
+        if let KeyValue::I64(key) = &key_value {
+            previous_key_opt = Some(*key);
+        }
+        final_buckets.push(to_final_bucket(key_value, bucket)?);
+    }
+    Ok(final_buckets)
+}
+
+impl IntermediateBucket {
+    fn merge(
+        &mut self,
+        other: IntermediateBucket,
+        sub_aggregations: &[(String, ExtendedAggregation)],
+    ) -> tantivy::Result<()> {
+        self.doc_count += other.doc_count;
+        IntermediateAggregationResult::merge_all(
+            &mut self.sub_results,
+            other.sub_results,
+            sub_aggregations,
+        )
+    }
+
+    fn into_final_result(
+        self,
+        sub_aggregations: &[(String, ExtendedAggregation)],
+        top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
+    ) -> crate::Result<JsonMap<String, JsonValue>> {
+        let mut final_bucket = final_results(self.sub_results, sub_aggregations, top_hits_docs)?;
+        final_bucket.insert("doc_count".to_string(), JsonValue::from(self.doc_count));
+        Ok(final_bucket)
+    }
+}
+
+fn final_results(
+    results: Vec<IntermediateAggregationResult>,
+    aggregations: &[(String, ExtendedAggregation)],
+    top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
+) -> crate::Result<JsonMap<String, JsonValue>> {
+    results
+        .into_iter()
+        .zip(aggregations)
+        .map(|(result, (name, aggregation))| {
+            let final_result = result.into_final_result(aggregation, top_hits_docs)?;
+            Ok((name.clone(), final_result))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn parse_aggregations(aggregations_json: JsonValue) -> Result<ExtendedAggregations, String> {
+        serde_json::from_value(aggregations_json).map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_parse_extended_aggregations() {
+        let aggregations = parse_aggregations(json!({
+            "by_host": {
+                "composite": {
+                    "size": 100,
+                    "sources": [
+                        {"host": {"terms": {"field": "host", "order": "desc"}}},
+                        {"hour": {"date_histogram": {"field": "timestamp", "fixed_interval": "1h"}}}
+                    ],
+                    "after": {"host": "host-1", "hour": 3_600_000}
+                },
+                "aggs": {
+                    "num_users": {"cardinality": {"field": "user_id"}},
+                    "latency": {"avg": {"field": "latency"}}
+                }
+            },
+            "avg_latency": {"avg": {"field": "latency"}}
+        }))
+        .unwrap();
+        assert_eq!(aggregations.tantivy_aggregations.len(), 1);
+        assert_eq!(aggregations.aggregations.len(), 1);
+        let ExtendedAggregation::Composite(composite) = &aggregations.aggregations[0].1 else {
+            panic!("expected a composite aggregation");
+        };
+        assert_eq!(composite.size, 100);
+        assert_eq!(
+            composite.after_key_opt,
+            Some(vec![
+                KeyValue::Str("host-1".to_string()),
+                KeyValue::I64(3_600_000)
+            ])
+        );
+        assert_eq!(composite.sub_aggregations.len(), 2);
+        assert_eq!(
+            aggregations.fast_field_names(),
+            HashSet::from_iter(
+                ["host", "timestamp", "user_id", "latency"].map(ToString::to_string)
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_extended_aggregations_errors() {
+        let error = parse_aggregations(json!({
+            "avg_latency": {"avg": {"field": "latency"}}
+        }))
+        .unwrap_err();
+        assert!(error.contains("at least one"), "{error}");
+
+        let error = parse_aggregations(json!({
+            "hosts": {
+                "filters": {"filters": {"error": {"term": {"level": "error"}}}},
+                "aggs": {"by_host": {"composite": {"sources": [{"host": {"terms": {"field": "host"}}}]}}}
+            }
+        }))
+        .unwrap_err();
+        assert!(error.contains("must be a top-level aggregation"), "{error}");
+
+        let error = parse_aggregations(json!({
+            "hosts": {
+                "filters": {"filters": {"error": {"term": {"level": "error"}}}},
+                "aggs": {"latency": {"percentiles": {"field": "latency"}}}
+            }
+        }))
+        .unwrap_err();
+        assert!(error.contains("cannot be combined with"), "{error}");
+
+        let error = parse_aggregations(json!({
+            "by_host": {
+                "terms": {"field": "host", "order": {"latency": "desc"}},
+                "aggs": {"latest": {"top_hits": {}}}
+            }
+        }))
+        .unwrap_err();
+        assert!(
+            error.contains("ordering by the sub-aggregation `latency` is not supported"),
+            "{error}"
+        );
+
+        let error = parse_aggregations(json!({
+            "by_host": {
+                "terms": {"field": "host", "include": "host-.*"},
+                "aggs": {"latest": {"top_hits": {}}}
+            }
+        }))
+        .unwrap_err();
+        assert!(
+            error.contains("`include` option of `terms` aggregation `by_host` is not supported"),
+            "{error}"
+        );
+
+        let error = parse_aggregations(json!({
+            "by_hour": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h", "offset": "30m"},
+                "aggs": {
+                    "num_users": {"cardinality": {"field": "user_id"}},
+                    "avg_latency": {"avg": {"field": "latency"}}
+                }
+            }
+        }))
+        .unwrap_err();
+        assert!(
+            error.contains(
+                "`offset` option of `date_histogram` aggregation `by_hour` is not supported"
+            ),
+            "{error}"
+        );
+
+        let error = parse_aggregations(json!({
+            "by_host": {
+                "filters": {"filters": {"error": {"term": {"level": "error"}}}},
+                "aggs": {"avg_latency": {"avg": {"field": "latency", "missing": 0}}}
+            }
+        }))
+        .unwrap_err();
+        assert!(
+            error.contains("`missing` option of `avg` aggregation `avg_latency` is not supported"),
+            "{error}"
+        );
+
+        let error = parse_aggregations(json!({
+            "by_host": {
+                "composite": {
+                    "sources": [{"host": {"terms": {"field": "host"}}}],
+                    "after": {"hostname": "host-1"}
+                }
+            }
+        }))
+        .unwrap_err();
+        assert!(error.contains("is missing source `host`"), "{error}");
+
+        let error = parse_aggregations(json!({
+            "num_users": {"cardinality": {"field": "user_id", "precision_threshold": 50_000}}
+        }))
+        .unwrap_err();
+        assert!(error.contains("precision threshold"), "{error}");
+
+        assert!(contains_extended_aggregations(
+            r#"{"num_users": {"cardinality": {"field": "user_id"}}}"#
+        ));
+        assert!(!contains_extended_aggregations(
+            r#"{"avg_latency": {"avg": {"field": "latency"}}}"#
+        ));
+        assert!(contains_extended_aggregations(
+            r#"{"by_host": {"terms": {"field": "host"}, "aggs": {"latest": {"top_hits": {}}}}}"#
+        ));
+        assert!(!contains_extended_aggregations(
+            r#"{"by_host": {"terms": {"field": "host"}, "aggs": {"latency": {"avg": {"field": "latency"}}}}}"#
+        ));
+    }
+
+    #[test]
+    fn test_parse_terms_and_date_histogram_aggregations() {
+        let aggregations = parse_aggregations(json!({
+            "by_host": {
+                "terms": {"field": "host", "size": 5, "order": {"_key": "desc"}},
+                "aggs": {
+                    "latest": {"top_hits": {"size": 1, "sort": [{"timestamp": "desc"}]}}
+                }
+            },
+            "per_hour": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                "aggs": {"num_users": {"cardinality": {"field": "user_id"}}}
+            },
+            "by_level": {"terms": {"field": "level"}}
+        }))
+        .unwrap();
+        assert_eq!(aggregations.tantivy_aggregations.len(), 1);
+        assert_eq!(aggregations.aggregations.len(), 2);
+        assert!(aggregations.has_top_hits());
+        assert!(!aggregations.requires_scoring());
+
+        let ExtendedAggregation::Terms(terms) = &aggregations.aggregations[0].1 else {
+            panic!("expected a terms aggregation");
+        };
+        assert_eq!(terms.size, 5);
+        assert_eq!(terms.split_size, 17);
+        assert_eq!(terms.order, TermsOrder::KeyDesc);
+        assert_eq!(terms.min_doc_count, 1);
+
+        let ExtendedAggregation::DateHistogram(date_histogram) = &aggregations.aggregations[1].1
+        else {
+            panic!("expected a date histogram aggregation");
+        };
+        assert_eq!(date_histogram.interval_millis, 3_600_000);
+        assert_eq!(date_histogram.min_doc_count, 0);
+
+        assert_eq!(
+            aggregations.fast_field_names(),
+            HashSet::from_iter(["host", "timestamp", "user_id", "level"].map(ToString::to_string))
+        );
+        let aggregations = parse_aggregations(json!({
+            "latest": {"top_hits": {"size": 1}}
+        }))
+        .unwrap();
+        assert!(aggregations.requires_scoring());
+    }
+
+    fn composite_result(keys: &[&str], doc_count: u64) -> IntermediateAggregationResult {
+        let buckets = keys
+            .iter()
+            .map(|key| {
+                let bucket = IntermediateBucket {
+                    doc_count,
+                    sub_results: Vec::new(),
+                };
+                (vec![KeyValue::Str(key.to_string())], bucket)
+            })
+            .collect();
+        IntermediateAggregationResult::Composite(buckets)
+    }
+
+    #[test]
+    fn test_merge_composite_results() {
+        let aggregations = parse_aggregations(json!({
+            "by_host": {
+                "composite": {
+                    "size": 3,
+                    "sources": [{"host": {"terms": {"field": "host", "order": "desc"}}}]
+                }
+            }
+        }))
+        .unwrap();
+        let mut results = IntermediateExtendedAggregationResults {
+            tantivy_results_opt: None,
+            results: vec![composite_result(&["host-5", "host-3", "host-1"], 1)],
+        };
+        let other_results = IntermediateExtendedAggregationResults {
+            tantivy_results_opt: None,
+            results: vec![composite_result(&["host-4", "host-3", "host-2"], 2)],
+        };
+        results.merge(other_results, &aggregations).unwrap();
+
+        let final_result = results
+            .into_final_result(aggregations, &HashMap::new(), &AggregationLimits::default())
+            .unwrap();
+        assert_eq!(
+            final_result,
+            json!({
+                "by_host": {
+                    "after_key": {"host": "host-3"},
+                    "buckets": [
+                        {"key": {"host": "host-5"}, "doc_count": 1},
+                        {"key": {"host": "host-4"}, "doc_count": 2},
+                        {"key": {"host": "host-3"}, "doc_count": 3},
+                    ]
+                }
+            })
+        );
+    }
+
+    fn buckets_result(
+        buckets: &[(KeyValue, u64)],
+        aggregations: &ExtendedAggregations,
+    ) -> IntermediateAggregationResult {
+        let sub_aggregations = aggregations.aggregations[0].1.sub_aggregations();
+        let buckets = buckets
+            .iter()
+            .map(|(key_value, doc_count)| {
+                let bucket = IntermediateBucket {
+                    doc_count: *doc_count,
+                    sub_results: IntermediateAggregationResult::empty_all(sub_aggregations),
+                };
+                (key_value.clone(), bucket)
+            })
+            .collect();
+        IntermediateAggregationResult::Buckets {
+            buckets,
+            sum_other_doc_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_merge_terms_results() {
+        let aggregations = parse_aggregations(json!({
+            "by_host": {
+                "terms": {"field": "host", "size": 2},
+                "aggs": {"num_users": {"cardinality": {"field": "user_id"}}}
+            }
+        }))
+        .unwrap();
+        let host = |host: &str| KeyValue::Str(host.to_string());
+        let mut results = IntermediateExtendedAggregationResults {
+            tantivy_results_opt: None,
+            results: vec![buckets_result(
+                &[(host("host-1"), 3), (host("host-2"), 2)],
+                &aggregations,
+            )],
+        };
+        let other_results = IntermediateExtendedAggregationResults {
+            tantivy_results_opt: None,
+            results: vec![buckets_result(
+                &[(host("host-2"), 2), (host("host-3"), 4)],
+                &aggregations,
+            )],
+        };
+        results.merge(other_results, &aggregations).unwrap();
+
+        let final_result = results
+            .into_final_result(aggregations, &HashMap::new(), &AggregationLimits::default())
+            .unwrap();
+        assert_eq!(
+            final_result,
+            json!({
+                "by_host": {
+                    "sum_other_doc_count": 5,
+                    "buckets": [
+                        {"key": "host-2", "doc_count": 4, "num_users": {"value": 0}},
+                        {"key": "host-3", "doc_count": 4, "num_users": {"value": 0}},
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_date_histogram_fills_empty_buckets() {
+        let aggregations = parse_aggregations(json!({
+            "per_hour": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                "aggs": {"num_users": {"cardinality": {"field": "user_id"}}}
+            }
+        }))
+        .unwrap();
+        let hour = |num_hours: i64| KeyValue::I64(num_hours * 3_600_000);
+        let results = IntermediateExtendedAggregationResults {
+            tantivy_results_opt: None,
+            results: vec![buckets_result(&[(hour(3), 1), (hour(0), 2)], &aggregations)],
+        };
+        let final_result = results
+            .into_final_result(aggregations, &HashMap::new(), &AggregationLimits::default())
+            .unwrap();
+        let bucket = |num_hours: i64, doc_count: u64| json!({"key": num_hours * 3_600_000, "doc_count": doc_count, "num_users": {"value": 0}});
+        assert_eq!(
+            final_result,
+            json!({
+                "per_hour": {
+                    "buckets": [bucket(0, 2), bucket(1, 0), bucket(2, 0), bucket(3, 1)]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty_results() {
+        let aggregations = parse_aggregations(json!({
+            "levels": {
+                "filters": {
+                    "filters": [{"term": {"level": "error"}}, {"term": {"level": "warn"}}],
+                    "other_bucket": true
+                },
+                "aggs": {
+                    "num_hosts": {"cardinality": {"field": "host"}},
+                    "min_latency": {"min": {"field": "latency"}}
+                }
+            }
+        }))
+        .unwrap();
+        let results = IntermediateExtendedAggregationResults::empty(&aggregations);
+        let final_result = results
+            .into_final_result(aggregations, &HashMap::new(), &AggregationLimits::default())
+            .unwrap();
+        let empty_bucket = json!({
+            "doc_count": 0,
+            "num_hosts": {"value": 0},
+            "min_latency": {"value": null},
+        });
+        assert_eq!(
+            final_result,
+            json!({
+                "levels": {
+                    "buckets": [empty_bucket, empty_bucket, empty_bucket]
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,1344 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Pipeline aggregations, computed by the root from the final results of the other aggregations.
+//!
+//! Pipeline aggregations are declared among the sub-aggregations of a multi-bucket aggregation
+//! and reference the metrics of its buckets through a `buckets_path`. They are removed from the
+//! request before it is sent to the leaves, and applied to the buckets of the final results in
+//! the format of Elasticsearch.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use super::script::{Script, ScriptValue};
+use crate::SearchError;
+
+const PIPELINE_AGGREGATION_TYPES: [&str; 7] = [
+    "bucket_script",
+    "bucket_selector",
+    "bucket_sort",
+    "cumulative_sum",
+    "derivative",
+    "moving_avg",
+    "moving_fn",
+];
+
+/// Bucket aggregations whose buckets are ordered by key, which sequential pipeline aggregations
+/// such as `derivative` require.
+const HISTOGRAM_AGGREGATION_TYPES: [&str; 2] = ["date_histogram", "histogram"];
+
+const MULTI_BUCKET_AGGREGATION_TYPES: [&str; 7] = [
+    "composite",
+    "date_histogram",
+    "filters",
+    "histogram",
+    "range",
+    "terms",
+    "date_range",
+];
+
+const DEFAULT_MOVING_AVG_WINDOW: usize = 5;
+
+fn is_pipeline_aggregation(json_value: &JsonValue) -> bool {
+    let Some(json_object) = json_value.as_object() else {
+        return false;
+    };
+    PIPELINE_AGGREGATION_TYPES
+        .iter()
+        .any(|aggregation_type| json_object.contains_key(*aggregation_type))
+}
+
+/// Pipeline aggregations of a request, by bucket aggregation they are declared in.
+#[derive(Debug, Default)]
+pub(crate) struct PipelineAggregations {
+    bucket_aggregations: Vec<(String, BucketPipelineAggregations)>,
+}
+
+#[derive(Debug, Default)]
+struct BucketPipelineAggregations {
+    // Pipeline aggregations applied to the buckets, in the order returned by
+    // `sort_by_dependencies`.
+    pipeline_aggregations: Vec<(String, PipelineAggregation)>,
+    // Pipeline aggregations declared in the sub-aggregations of the buckets.
+    sub_pipeline_aggregations: PipelineAggregations,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum GapPolicy {
+    #[default]
+    Skip,
+    InsertZeros,
+    KeepValues,
+}
+
+impl GapPolicy {
+    fn apply(&self, value_opt: Option<f64>) -> Option<f64> {
+        let value_opt = value_opt.filter(|value| !value.is_nan());
+        match self {
+            GapPolicy::Skip | GapPolicy::KeepValues => value_opt,
+            GapPolicy::InsertZeros => value_opt.or(Some(0.0)),
+        }
+    }
+}
+
+/// Path from a bucket to one of its metrics, e.g. `_count`, `latency`, `stats.avg`,
+/// `percentiles[99.0]`, or `errors>latency` through the single-bucket aggregation `errors`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct BucketsPath {
+    // Names of the single-bucket aggregations leading to the metric.
+    bucket_names: Vec<String>,
+    target: BucketsPathTarget,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum BucketsPathTarget {
+    DocCount,
+    Key,
+    Metric {
+        aggregation_name: String,
+        metric_opt: Option<String>,
+    },
+}
+
+impl BucketsPath {
+    fn parse(buckets_path: &str) -> Result<Self, String> {
+        let mut path_elements: Vec<&str> = buckets_path.split('>').map(str::trim).collect();
+        let last_element = path_elements.pop().unwrap_or_default();
+
+        if last_element.is_empty() || path_elements.iter().any(|element| element.is_empty()) {
+            return Err(format!("invalid buckets path `{buckets_path}`"));
+        }
+        let target = match last_element {
+            "_count" => BucketsPathTarget::DocCount,
+            "_key" => BucketsPathTarget::Key,
+            _ => {
+                let (aggregation_name, metric_opt) = if let Some((aggregation_name, metric)) =
+                    last_element
+                        .strip_suffix(']')
+                        .and_then(|element| element.split_once('['))
+                {
+                    (aggregation_name, Some(metric))
+                } else if let Some((aggregation_name, metric)) = last_element.split_once('.') {
+                    (aggregation_name, Some(metric))
+                } else {
+                    (last_element, None)
+                };
+                if aggregation_name.is_empty() || metric_opt == Some("") {
+                    return Err(format!("invalid buckets path `{buckets_path}`"));
+                }
+                BucketsPathTarget::Metric {
+                    aggregation_name: aggregation_name.to_string(),
+                    metric_opt: metric_opt.map(ToString::to_string),
+                }
+            }
+        };
+        Ok(Self {
+            bucket_names: path_elements.into_iter().map(ToString::to_string).collect(),
+            target,
+        })
+    }
+
+    /// Returns the name of the sibling aggregation the path starts with, if any.
+    fn first_aggregation_name(&self) -> Option<&str> {
+        if let Some(bucket_name) = self.bucket_names.first() {
+            return Some(bucket_name.as_str());
+        }
+        match &self.target {
+            BucketsPathTarget::Metric {
+                aggregation_name, ..
+            } => Some(aggregation_name.as_str()),
+            BucketsPathTarget::DocCount | BucketsPathTarget::Key => None,
+        }
+    }
+
+    fn resolve_json<'a>(&self, bucket: &'a JsonMap<String, JsonValue>) -> Option<&'a JsonValue> {
+        let mut current_bucket = bucket;
+
+        for bucket_name in &self.bucket_names {
+            current_bucket = current_bucket.get(bucket_name)?.as_object()?;
+        }
+        match &self.target {
+            BucketsPathTarget::DocCount => current_bucket.get("doc_count"),
+            BucketsPathTarget::Key => current_bucket.get("key"),
+            BucketsPathTarget::Metric {
+                aggregation_name,
+                metric_opt: None,
+            } => current_bucket.get(aggregation_name)?.get("value"),
+            BucketsPathTarget::Metric {
+                aggregation_name,
+                metric_opt: Some(metric),
+            } => {
+                let result = current_bucket.get(aggregation_name)?;
+
+                if let Some(value) = result.get(metric) {
+                    return Some(value);
+                }
+                // Percentiles are keyed by their formatted value, e.g. `99.0`.
+                let values = result.get("values")?.as_object()?;
+                values.get(metric).or_else(|| {
+                    let percent: f64 = metric.parse().ok()?;
+                    values
+                        .iter()
+                        .find(|(key, _)| key.parse::<f64>().ok() == Some(percent))
+                        .map(|(_, value)| value)
+                })
+            }
+        }
+    }
+
+    fn resolve(&self, bucket: &JsonMap<String, JsonValue>) -> Option<f64> {
+        self.resolve_json(bucket)?.as_f64()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MovingFunction {
+    Max,
+    Min,
+    Sum,
+    UnweightedAvg,
+    LinearWeightedAvg,
+    StdDev,
+}
+
+impl MovingFunction {
+    fn parse_script(script: &str) -> Result<Self, String> {
+        let script: String = script
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != ';')
+            .collect();
+        let moving_function = match script.as_str() {
+            "MovingFunctions.max(values)" => MovingFunction::Max,
+            "MovingFunctions.min(values)" => MovingFunction::Min,
+            "MovingFunctions.sum(values)" => MovingFunction::Sum,
+            "MovingFunctions.unweightedAvg(values)" => MovingFunction::UnweightedAvg,
+            "MovingFunctions.linearWeightedAvg(values)" => MovingFunction::LinearWeightedAvg,
+            "MovingFunctions.stdDev(values,MovingFunctions.unweightedAvg(values))" => {
+                MovingFunction::StdDev
+            }
+            _ => {
+                return Err(format!(
+                    "unsupported script `{script}`, expected one of `MovingFunctions.max`, `min`, \
+                     `sum`, `unweightedAvg`, `linearWeightedAvg`, or `stdDev` applied to `values`"
+                ));
+            }
+        };
+        Ok(moving_function)
+    }
+
+    /// Applies the function to the values of the window, from the oldest to the newest.
+    fn apply(&self, values: &[f64]) -> Option<f64> {
+        if values.is_empty() {
+            return match self {
+                MovingFunction::Sum => Some(0.0),
+                _ => None,
+            };
+        }
+        let num_values = values.len() as f64;
+        let avg = values.iter().sum::<f64>() / num_values;
+
+        let result = match self {
+            MovingFunction::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            MovingFunction::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            MovingFunction::Sum => values.iter().sum(),
+            MovingFunction::UnweightedAvg => avg,
+            MovingFunction::LinearWeightedAvg => {
+                let weighted_sum: f64 = values
+                    .iter()
+                    .enumerate()
+                    .map(|(value_idx, value)| (value_idx + 1) as f64 * value)
+                    .sum();
+                weighted_sum / (num_values * (num_values + 1.0) / 2.0)
+            }
+            MovingFunction::StdDev => {
+                let variance = values
+                    .iter()
+                    .map(|value| (value - avg) * (value - avg))
+                    .sum::<f64>()
+                    / num_values;
+                variance.sqrt()
+            }
+        };
+        Some(result)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum MovingAvgModel {
+    #[default]
+    Simple,
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BucketSortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug)]
+enum PipelineAggregation {
+    Derivative {
+        buckets_path: BucketsPath,
+        gap_policy: GapPolicy,
+    },
+    CumulativeSum {
+        buckets_path: BucketsPath,
+    },
+    MovingFunction {
+        buckets_path: BucketsPath,
+        window: usize,
+        shift: usize,
+        function: MovingFunction,
+        gap_policy: GapPolicy,
+    },
+    BucketScript {
+        buckets_paths: Vec<(String, BucketsPath)>,
+        script: Script,
+        gap_policy: GapPolicy,
+    },
+    BucketSelector {
+        buckets_paths: Vec<(String, BucketsPath)>,
+        script: Script,
+        gap_policy: GapPolicy,
+    },
+    BucketSort {
+        sort: Vec<(BucketsPath, BucketSortOrder)>,
+        from: usize,
+        size_opt: Option<usize>,
+        gap_policy: GapPolicy,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DerivativeParams {
+    buckets_path: String,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CumulativeSumParams {
+    buckets_path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MovingAvgParams {
+    buckets_path: String,
+    #[serde(default)]
+    window: Option<usize>,
+    #[serde(default)]
+    model: MovingAvgModel,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MovingFnParams {
+    buckets_path: String,
+    window: usize,
+    script: ScriptParam,
+    #[serde(default)]
+    shift: usize,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BucketScriptParams {
+    buckets_path: HashMap<String, String>,
+    script: ScriptParam,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BucketSortParams {
+    #[serde(default)]
+    sort: Vec<JsonValue>,
+    #[serde(default)]
+    from: usize,
+    #[serde(default)]
+    size: Option<usize>,
+    #[serde(default)]
+    gap_policy: GapPolicy,
+}
+
+/// Script given either as a string or as an object with a `source`, as in Elasticsearch.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScriptParam {
+    Source(String),
+    Object { source: String },
+}
+
+impl ScriptParam {
+    fn source(&self) -> &str {
+        match self {
+            ScriptParam::Source(source) | ScriptParam::Object { source, .. } => source,
+        }
+    }
+}
+
+impl PipelineAggregations {
+    /// Removes the pipeline aggregations from an aggregation request.
+    ///
+    /// Returns the request without its pipeline aggregations along with the pipeline
+    /// aggregations, or `None` if the request does not contain any.
+    pub fn split_from_request(
+        aggregation_request: &str,
+    ) -> crate::Result<Option<(String, PipelineAggregations)>> {
+        // Invalid requests are reported by the validation of the request.
+        let Ok(mut aggregations_json) =
+            serde_json::from_str::<JsonMap<String, JsonValue>>(aggregation_request)
+        else {
+            return Ok(None);
+        };
+        let top_level_pipeline_aggregations = Self::extract(&mut aggregations_json, None)
+            .map_err(SearchError::InvalidAggregationRequest)?;
+        let pipeline_aggregations = top_level_pipeline_aggregations.sub_pipeline_aggregations;
+
+        if pipeline_aggregations.bucket_aggregations.is_empty() {
+            return Ok(None);
+        }
+        let aggregation_request = serde_json::to_string(&aggregations_json)?;
+        Ok(Some((aggregation_request, pipeline_aggregations)))
+    }
+
+    /// Removes the pipeline aggregations of a map of aggregations, declared in an aggregation of
+    /// type `parent_type_opt`, and of their sub-aggregations.
+    fn extract(
+        aggregations_json: &mut JsonMap<String, JsonValue>,
+        parent_type_opt: Option<&str>,
+    ) -> Result<BucketPipelineAggregations, String> {
+        let sibling_names: HashSet<String> = aggregations_json.keys().cloned().collect();
+        let pipeline_names: Vec<String> = aggregations_json
+            .iter()
+            .filter(|(_, json_value)| is_pipeline_aggregation(json_value))
+            .map(|(name, _)| name.clone())
+            .collect();
+        let mut pipeline_aggregations = Vec::with_capacity(pipeline_names.len());
+
+        for name in pipeline_names {
+            let json_value = aggregations_json.remove(&name).expect("aggregation exists");
+            let Some(parent_type) = parent_type_opt else {
+                return Err(format!(
+                    "pipeline aggregation `{name}` must be declared in the sub-aggregations of a \
+                     multi-bucket aggregation"
+                ));
+            };
+            let pipeline_aggregation =
+                PipelineAggregation::from_json(&name, json_value, parent_type, &sibling_names)?;
+            pipeline_aggregations.push((name, pipeline_aggregation));
+        }
+        let pipeline_aggregations = sort_by_dependencies(pipeline_aggregations)?;
+        let mut sub_pipeline_aggregations = PipelineAggregations::default();
+
+        for (name, json_value) in aggregations_json.iter_mut() {
+            let JsonValue::Object(aggregation_json) = json_value else {
+                continue;
+            };
+            let aggregation_type_opt = aggregation_json
+                .keys()
+                .find(|key| !matches!(key.as_str(), "aggs" | "aggregations" | "meta"))
+                .cloned();
+
+            for sub_aggregations_key in ["aggs", "aggregations"] {
+                let Some(JsonValue::Object(sub_aggregations_json)) =
+                    aggregation_json.get_mut(sub_aggregations_key)
+                else {
+                    continue;
+                };
+                let bucket_pipeline_aggregations =
+                    Self::extract(sub_aggregations_json, aggregation_type_opt.as_deref())?;
+
+                if sub_aggregations_json.is_empty() {
+                    aggregation_json.remove(sub_aggregations_key);
+                }
+                if !bucket_pipeline_aggregations.is_empty() {
+                    sub_pipeline_aggregations
+                        .bucket_aggregations
+                        .push((name.clone(), bucket_pipeline_aggregations));
+                }
+            }
+        }
+        Ok(BucketPipelineAggregations {
+            pipeline_aggregations,
+            sub_pipeline_aggregations,
+        })
+    }
+
+    /// Applies the pipeline aggregations to the final results of the aggregations, serialized in
+    /// JSON.
+    pub fn apply(&self, aggregation_results_json: &str) -> crate::Result<String> {
+        let mut aggregation_results: JsonMap<String, JsonValue> =
+            serde_json::from_str(aggregation_results_json)?;
+        self.apply_to_results(&mut aggregation_results)?;
+        let aggregation_results_json = serde_json::to_string(&aggregation_results)?;
+        Ok(aggregation_results_json)
+    }
+
+    fn apply_to_results(&self, results: &mut JsonMap<String, JsonValue>) -> crate::Result<()> {
+        for (name, bucket_pipeline_aggregations) in &self.bucket_aggregations {
+            if let Some(JsonValue::Object(result)) = results.get_mut(name) {
+                bucket_pipeline_aggregations.apply(result)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Orders the pipeline aggregations so that the ones referencing other pipeline aggregations come
+/// after them. Otherwise, `bucket_selector` and `bucket_sort` aggregations, which drop buckets,
+/// come after the aggregations computing values.
+fn sort_by_dependencies(
+    mut pipeline_aggregations: Vec<(String, PipelineAggregation)>,
+) -> Result<Vec<(String, PipelineAggregation)>, String> {
+    pipeline_aggregations.sort_by_key(|(_, aggregation)| aggregation.drops_buckets());
+
+    let pipeline_names: HashSet<String> = pipeline_aggregations
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut sorted_pipeline_aggregations = Vec::with_capacity(pipeline_aggregations.len());
+    let mut sorted_names: HashSet<String> = HashSet::new();
+
+    while !pipeline_aggregations.is_empty() {
+        let Some(ready_idx) = pipeline_aggregations.iter().position(|(_, aggregation)| {
+            aggregation
+                .referenced_names()
+                .into_iter()
+                .all(|name| !pipeline_names.contains(name) || sorted_names.contains(name))
+        }) else {
+            let names = pipeline_aggregations
+                .iter()
+                .map(|(name, _)| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(format!(
+                "pipeline aggregations {names} reference each other in a cycle"
+            ));
+        };
+        let (name, aggregation) = pipeline_aggregations.remove(ready_idx);
+        sorted_names.insert(name.clone());
+        sorted_pipeline_aggregations.push((name, aggregation));
+    }
+    Ok(sorted_pipeline_aggregations)
+}
+
+impl BucketPipelineAggregations {
+    fn is_empty(&self) -> bool {
+        self.pipeline_aggregations.is_empty()
+            && self
+                .sub_pipeline_aggregations
+                .bucket_aggregations
+                .is_empty()
+    }
+
+    fn apply(&self, result: &mut JsonMap<String, JsonValue>) -> crate::Result<()> {
+        let Some(buckets_json) = result.get_mut("buckets") else {
+            return Ok(());
+        };
+        // Buckets are returned either as an array or as an object keyed by bucket name.
+        let (keyed, mut buckets): (bool, Vec<(String, JsonMap<String, JsonValue>)>) =
+            match std::mem::take(buckets_json) {
+                JsonValue::Array(bucket_jsons) => {
+                    let buckets = bucket_jsons
+                        .into_iter()
+                        .filter_map(|bucket_json| match bucket_json {
+                            JsonValue::Object(bucket) => Some((String::new(), bucket)),
+                            _ => None,
+                        })
+                        .collect();
+                    (false, buckets)
+                }
+                JsonValue::Object(bucket_jsons) => {
+                    let buckets = bucket_jsons
+                        .into_iter()
+                        .filter_map(|(key, bucket_json)| match bucket_json {
+                            JsonValue::Object(bucket) => Some((key, bucket)),
+                            _ => None,
+                        })
+                        .collect();
+                    (true, buckets)
+                }
+                other => {
+                    *buckets_json = other;
+                    return Ok(());
+                }
+            };
+        for (_, bucket) in &mut buckets {
+            self.sub_pipeline_aggregations.apply_to_results(bucket)?;
+        }
+        for (name, pipeline_aggregation) in &self.pipeline_aggregations {
+            pipeline_aggregation
+                .apply(name, &mut buckets)
+                .map_err(SearchError::InvalidAggregationRequest)?;
+        }
+        *buckets_json = if keyed {
+            JsonValue::Object(
+                buckets
+                    .into_iter()
+                    .map(|(key, bucket)| (key, JsonValue::Object(bucket)))
+                    .collect(),
+            )
+        } else {
+            JsonValue::Array(
+                buckets
+                    .into_iter()
+                    .map(|(_, bucket)| JsonValue::Object(bucket))
+                    .collect(),
+            )
+        };
+        Ok(())
+    }
+}
+
+fn parse_buckets_path(
+    name: &str,
+    buckets_path: &str,
+    sibling_names: &HashSet<String>,
+) -> Result<BucketsPath, String> {
+    let buckets_path = BucketsPath::parse(buckets_path)
+        .map_err(|error| format!("invalid pipeline aggregation `{name}`: {error}"))?;
+
+    if let Some(aggregation_name) = buckets_path.first_aggregation_name() {
+        if aggregation_name == name || !sibling_names.contains(aggregation_name) {
+            return Err(format!(
+                "buckets path of pipeline aggregation `{name}` references unknown aggregation \
+                 `{aggregation_name}`"
+            ));
+        }
+    }
+    Ok(buckets_path)
+}
+
+fn parse_script_buckets_paths(
+    name: &str,
+    params: &BucketScriptParams,
+    sibling_names: &HashSet<String>,
+) -> Result<(Vec<(String, BucketsPath)>, Script), String> {
+    let script = Script::parse(params.script.source())
+        .map_err(|error| format!("invalid script of pipeline aggregation `{name}`: {error}"))?;
+    let mut buckets_paths = params
+        .buckets_path
+        .iter()
+        .map(|(variable, buckets_path)| {
+            let buckets_path = parse_buckets_path(name, buckets_path, sibling_names)?;
+            Ok((variable.clone(), buckets_path))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    buckets_paths.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+    for variable in script.variables() {
+        if !params.buckets_path.contains_key(variable) {
+            return Err(format!(
+                "script of pipeline aggregation `{name}` references variable `{variable}` which \
+                 is not defined in `buckets_path`"
+            ));
+        }
+    }
+    Ok((buckets_paths, script))
+}
+
+fn parse_bucket_sort(
+    name: &str,
+    sort_jsons: Vec<JsonValue>,
+    sibling_names: &HashSet<String>,
+) -> Result<Vec<(BucketsPath, BucketSortOrder)>, String> {
+    let parse_order = |order: &str| match order {
+        "asc" => Ok(BucketSortOrder::Asc),
+        "desc" => Ok(BucketSortOrder::Desc),
+        _ => Err(format!(
+            "invalid sort order `{order}` of pipeline aggregation `{name}`, expected `asc` or \
+             `desc`"
+        )),
+    };
+    sort_jsons
+        .into_iter()
+        .map(|sort_json| {
+            let (buckets_path, order) = match sort_json {
+                JsonValue::String(buckets_path) => (buckets_path, BucketSortOrder::Asc),
+                JsonValue::Object(json_map) if json_map.len() == 1 => {
+                    let (buckets_path, order_json) =
+                        json_map.into_iter().next().expect("map has one entry");
+                    let order = match &order_json {
+                        JsonValue::String(order) => parse_order(order)?,
+                        JsonValue::Object(params) => match params.get("order") {
+                            Some(JsonValue::String(order)) => parse_order(order)?,
+                            None => BucketSortOrder::Asc,
+                            Some(_) => {
+                                return Err(format!(
+                                    "invalid sort of pipeline aggregation `{name}`"
+                                ));
+                            }
+                        },
+                        _ => return Err(format!("invalid sort of pipeline aggregation `{name}`")),
+                    };
+                    (buckets_path, order)
+                }
+                _ => return Err(format!("invalid sort of pipeline aggregation `{name}`")),
+            };
+            let buckets_path = parse_buckets_path(name, &buckets_path, sibling_names)?;
+            Ok((buckets_path, order))
+        })
+        .collect()
+}
+
+impl PipelineAggregation {
+    fn from_json(
+        name: &str,
+        json_value: JsonValue,
+        parent_type: &str,
+        sibling_names: &HashSet<String>,
+    ) -> Result<Self, String> {
+        let JsonValue::Object(json_map) = json_value else {
+            return Err(format!("aggregation `{name}` must be an object"));
+        };
+        if json_map.contains_key("aggs") || json_map.contains_key("aggregations") {
+            return Err(format!(
+                "pipeline aggregation `{name}` does not accept sub-aggregations"
+            ));
+        }
+        if json_map.len() != 1 {
+            return Err(format!(
+                "aggregation `{name}` must define exactly one aggregation type"
+            ));
+        }
+        let (aggregation_type, params) = json_map.into_iter().next().expect("map has one entry");
+        let invalid_params = |error: serde_json::Error| {
+            format!("invalid `{aggregation_type}` aggregation `{name}`: {error}")
+        };
+        let is_sequential = matches!(
+            aggregation_type.as_str(),
+            "cumulative_sum" | "derivative" | "moving_avg" | "moving_fn"
+        );
+        if is_sequential && !HISTOGRAM_AGGREGATION_TYPES.contains(&parent_type) {
+            return Err(format!(
+                "`{aggregation_type}` aggregation `{name}` must be declared in a `histogram` or \
+                 `date_histogram` aggregation"
+            ));
+        }
+        if !MULTI_BUCKET_AGGREGATION_TYPES.contains(&parent_type) {
+            return Err(format!(
+                "`{aggregation_type}` aggregation `{name}` must be declared in a multi-bucket \
+                 aggregation"
+            ));
+        }
+        let pipeline_aggregation = match aggregation_type.as_str() {
+            "derivative" => {
+                let params: DerivativeParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                PipelineAggregation::Derivative {
+                    buckets_path: parse_buckets_path(name, &params.buckets_path, sibling_names)?,
+                    gap_policy: params.gap_policy,
+                }
+            }
+            "cumulative_sum" => {
+                let params: CumulativeSumParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                PipelineAggregation::CumulativeSum {
+                    buckets_path: parse_buckets_path(name, &params.buckets_path, sibling_names)?,
+                }
+            }
+            "moving_avg" => {
+                let params: MovingAvgParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let function = match params.model {
+                    MovingAvgModel::Simple => MovingFunction::UnweightedAvg,
+                    MovingAvgModel::Linear => MovingFunction::LinearWeightedAvg,
+                };
+                PipelineAggregation::MovingFunction {
+                    buckets_path: parse_buckets_path(name, &params.buckets_path, sibling_names)?,
+                    window: params.window.unwrap_or(DEFAULT_MOVING_AVG_WINDOW),
+                    shift: 0,
+                    function,
+                    gap_policy: params.gap_policy,
+                }
+            }
+            "moving_fn" => {
+                let params: MovingFnParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let function =
+                    MovingFunction::parse_script(params.script.source()).map_err(|error| {
+                        format!("invalid `moving_fn` aggregation `{name}`: {error}")
+                    })?;
+                PipelineAggregation::MovingFunction {
+                    buckets_path: parse_buckets_path(name, &params.buckets_path, sibling_names)?,
+                    window: params.window,
+                    shift: params.shift,
+                    function,
+                    gap_policy: params.gap_policy,
+                }
+            }
+            "bucket_script" => {
+                let params: BucketScriptParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let (buckets_paths, script) =
+                    parse_script_buckets_paths(name, &params, sibling_names)?;
+                PipelineAggregation::BucketScript {
+                    buckets_paths,
+                    script,
+                    gap_policy: params.gap_policy,
+                }
+            }
+            "bucket_selector" => {
+                let params: BucketScriptParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                let (buckets_paths, script) =
+                    parse_script_buckets_paths(name, &params, sibling_names)?;
+                PipelineAggregation::BucketSelector {
+                    buckets_paths,
+                    script,
+                    gap_policy: params.gap_policy,
+                }
+            }
+            "bucket_sort" => {
+                let params: BucketSortParams =
+                    serde_json::from_value(params).map_err(invalid_params)?;
+                PipelineAggregation::BucketSort {
+                    sort: parse_bucket_sort(name, params.sort, sibling_names)?,
+                    from: params.from,
+                    size_opt: params.size,
+                    gap_policy: params.gap_policy,
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "aggregation `{name}` must define exactly one aggregation type"
+                ));
+            }
+        };
+        if let PipelineAggregation::MovingFunction { window: 0, .. } = pipeline_aggregation {
+            return Err(format!(
+                "window of `{aggregation_type}` aggregation `{name}` must be strictly positive"
+            ));
+        }
+        Ok(pipeline_aggregation)
+    }
+
+    fn drops_buckets(&self) -> bool {
+        matches!(
+            self,
+            PipelineAggregation::BucketSelector { .. } | PipelineAggregation::BucketSort { .. }
+        )
+    }
+
+    /// Returns the names of the sibling aggregations referenced by the pipeline aggregation.
+    fn referenced_names(&self) -> Vec<&str> {
+        match self {
+            PipelineAggregation::Derivative { buckets_path, .. }
+            | PipelineAggregation::CumulativeSum { buckets_path }
+            | PipelineAggregation::MovingFunction { buckets_path, .. } => {
+                buckets_path.first_aggregation_name().into_iter().collect()
+            }
+            PipelineAggregation::BucketScript { buckets_paths, .. }
+            | PipelineAggregation::BucketSelector { buckets_paths, .. } => buckets_paths
+                .iter()
+                .filter_map(|(_, buckets_path)| buckets_path.first_aggregation_name())
+                .collect(),
+            PipelineAggregation::BucketSort { sort, .. } => sort
+                .iter()
+                .filter_map(|(buckets_path, _)| buckets_path.first_aggregation_name())
+                .collect(),
+        }
+    }
+
+    fn apply(
+        &self,
+        name: &str,
+        buckets: &mut Vec<(String, JsonMap<String, JsonValue>)>,
+    ) -> Result<(), String> {
+        let value_json = |value: f64| {
+            let value = if value.is_finite() {
+                JsonValue::from(value)
+            } else {
+                JsonValue::Null
+            };
+            serde_json::json!({ "value": value })
+        };
+        match self {
+            PipelineAggregation::Derivative {
+                buckets_path,
+                gap_policy,
+            } => {
+                let mut previous_value_opt: Option<f64> = None;
+
+                for (_, bucket) in buckets.iter_mut() {
+                    let Some(value) = gap_policy.apply(buckets_path.resolve(bucket)) else {
+                        continue;
+                    };
+                    if let Some(previous_value) = previous_value_opt {
+                        bucket.insert(name.to_string(), value_json(value - previous_value));
+                    }
+                    previous_value_opt = Some(value);
+                }
+            }
+            PipelineAggregation::CumulativeSum { buckets_path } => {
+                let mut sum = 0.0;
+
+                for (_, bucket) in buckets.iter_mut() {
+                    if let Some(value) = GapPolicy::Skip.apply(buckets_path.resolve(bucket)) {
+                        sum += value;
+                    }
+                    bucket.insert(name.to_string(), value_json(sum));
+                }
+            }
+            PipelineAggregation::MovingFunction {
+                buckets_path,
+                window,
+                shift,
+                function,
+                gap_policy,
+            } => {
+                let values: Vec<Option<f64>> = buckets
+                    .iter()
+                    .map(|(_, bucket)| gap_policy.apply(buckets_path.resolve(bucket)))
+                    .collect();
+
+                for (bucket_idx, (_, bucket)) in buckets.iter_mut().enumerate() {
+                    // The window precedes the bucket, and is shifted towards the next buckets by
+                    // `shift`.
+                    let window_end = (bucket_idx + shift).min(values.len());
+                    let window_start = (bucket_idx + shift).saturating_sub(*window);
+                    let window_values: Vec<f64> = values[window_start.min(window_end)..window_end]
+                        .iter()
+                        .flatten()
+                        .copied()
+                        .collect();
+                    let final_value = match function.apply(&window_values) {
+                        Some(value) => value_json(value),
+                        None => serde_json::json!({ "value": null }),
+                    };
+                    bucket.insert(name.to_string(), final_value);
+                }
+            }
+            PipelineAggregation::BucketScript {
+                buckets_paths,
+                script,
+                gap_policy,
+            } => {
+                for (_, bucket) in buckets.iter_mut() {
+                    let Some(variables) = resolve_variables(buckets_paths, *gap_policy, bucket)
+                    else {
+                        continue;
+                    };
+                    match script.eval(&variables) {
+                        Ok(ScriptValue::Number(value)) => {
+                            bucket.insert(name.to_string(), value_json(value));
+                        }
+                        Ok(ScriptValue::Bool(_)) => {
+                            return Err(format!(
+                                "script of `bucket_script` aggregation `{name}` must return a \
+                                 number"
+                            ));
+                        }
+                        Err(error) => {
+                            return Err(format!(
+                                "failed to evaluate script of `bucket_script` aggregation \
+                                 `{name}`: {error}"
+                            ));
+                        }
+                    }
+                }
+            }
+            PipelineAggregation::BucketSelector {
+                buckets_paths,
+                script,
+                gap_policy,
+            } => {
+                let mut error_opt = None;
+
+                buckets.retain(|(_, bucket)| {
+                    if error_opt.is_some() {
+                        return true;
+                    }
+                    // Buckets with missing values are kept.
+                    let Some(variables) = resolve_variables(buckets_paths, *gap_policy, bucket)
+                    else {
+                        return true;
+                    };
+                    match script.eval(&variables) {
+                        Ok(ScriptValue::Bool(keep)) => keep,
+                        Ok(ScriptValue::Number(_)) => {
+                            error_opt = Some(format!(
+                                "script of `bucket_selector` aggregation `{name}` must return a \
+                                 boolean"
+                            ));
+                            true
+                        }
+                        Err(error) => {
+                            error_opt = Some(format!(
+                                "failed to evaluate script of `bucket_selector` aggregation \
+                                 `{name}`: {error}"
+                            ));
+                            true
+                        }
+                    }
+                });
+                if let Some(error) = error_opt {
+                    return Err(error);
+                }
+            }
+            PipelineAggregation::BucketSort {
+                sort,
+                from,
+                size_opt,
+                gap_policy,
+            } => {
+                if *gap_policy != GapPolicy::InsertZeros {
+                    buckets.retain(|(_, bucket)| {
+                        sort.iter()
+                            .all(|(buckets_path, _)| buckets_path.resolve_json(bucket).is_some())
+                    });
+                }
+                buckets.sort_by(|(_, left_bucket), (_, right_bucket)| {
+                    sort.iter()
+                        .map(|(buckets_path, order)| {
+                            let ordering = compare_sort_values(
+                                buckets_path.resolve_json(left_bucket),
+                                buckets_path.resolve_json(right_bucket),
+                            );
+                            match order {
+                                BucketSortOrder::Asc => ordering,
+                                BucketSortOrder::Desc => ordering.reverse(),
+                            }
+                        })
+                        .find(|ordering| ordering.is_ne())
+                        .unwrap_or(Ordering::Equal)
+                });
+                let num_buckets = buckets.len();
+                let end = size_opt
+                    .map(|size| from.saturating_add(size).min(num_buckets))
+                    .unwrap_or(num_buckets);
+                buckets.truncate(end);
+                buckets.drain(..(*from).min(end));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn resolve_variables<'a>(
+    buckets_paths: &'a [(String, BucketsPath)],
+    gap_policy: GapPolicy,
+    bucket: &JsonMap<String, JsonValue>,
+) -> Option<HashMap<&'a str, f64>> {
+    buckets_paths
+        .iter()
+        .map(|(variable, buckets_path)| {
+            let value = gap_policy.apply(buckets_path.resolve(bucket))?;
+            Some((variable.as_str(), value))
+        })
+        .collect()
+}
+
+/// Compares the sort values of two buckets, missing values and zeros inserted in their place
+/// being sorted as zeros.
+fn compare_sort_values(left_opt: Option<&JsonValue>, right_opt: Option<&JsonValue>) -> Ordering {
+    match (left_opt, right_opt) {
+        (Some(JsonValue::String(left)), Some(JsonValue::String(right))) => left.cmp(right),
+        _ => {
+            let left = left_opt.and_then(JsonValue::as_f64).unwrap_or_default();
+            let right = right_opt.and_then(JsonValue::as_f64).unwrap_or_default();
+            left.total_cmp(&right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn split_from_request(
+        aggregation_request: JsonValue,
+    ) -> crate::Result<Option<(JsonValue, PipelineAggregations)>> {
+        let split_opt = PipelineAggregations::split_from_request(&aggregation_request.to_string())?;
+        Ok(
+            split_opt.map(|(aggregation_request, pipeline_aggregations)| {
+                let aggregation_request_json = serde_json::from_str(&aggregation_request).unwrap();
+                (aggregation_request_json, pipeline_aggregations)
+            }),
+        )
+    }
+
+    fn apply(pipeline_aggregations: &PipelineAggregations, results: JsonValue) -> JsonValue {
+        let results_json = pipeline_aggregations.apply(&results.to_string()).unwrap();
+        serde_json::from_str(&results_json).unwrap()
+    }
+
+    #[test]
+    fn test_split_pipeline_aggregations_from_request() {
+        assert!(
+            split_from_request(json!({"avg_latency": {"avg": {"field": "latency"}}}))
+                .unwrap()
+                .is_none()
+        );
+
+        let (aggregation_request, pipeline_aggregations) = split_from_request(json!({
+            "per_hour": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                "aggs": {
+                    "num_errors": {"sum": {"field": "errors"}},
+                    "error_rate": {"derivative": {"buckets_path": "num_errors"}},
+                    "by_host": {
+                        "terms": {"field": "host"},
+                        "aggs": {
+                            "ratio": {
+                                "bucket_script": {
+                                    "buckets_path": {"count": "_count"},
+                                    "script": "params.count * 2"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            aggregation_request,
+            json!({
+                "per_hour": {
+                    "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                    "aggs": {
+                        "num_errors": {"sum": {"field": "errors"}},
+                        "by_host": {"terms": {"field": "host"}}
+                    }
+                }
+            })
+        );
+        let (name, per_hour_pipeline_aggregations) = &pipeline_aggregations.bucket_aggregations[0];
+        assert_eq!(name, "per_hour");
+        assert_eq!(
+            per_hour_pipeline_aggregations.pipeline_aggregations.len(),
+            1
+        );
+        assert_eq!(
+            per_hour_pipeline_aggregations
+                .sub_pipeline_aggregations
+                .bucket_aggregations[0]
+                .0,
+            "by_host"
+        );
+    }
+
+    #[test]
+    fn test_split_pipeline_aggregations_from_request_errors() {
+        let error_message = |aggregation_request: JsonValue| {
+            let Err(SearchError::InvalidAggregationRequest(error)) =
+                split_from_request(aggregation_request)
+            else {
+                panic!("expected an invalid aggregation request error");
+            };
+            error
+        };
+        let error = error_message(json!({
+            "total_deriv": {"derivative": {"buckets_path": "_count"}}
+        }));
+        assert!(error.contains("multi-bucket aggregation"), "{error}");
+
+        let error = error_message(json!({
+            "by_host": {
+                "terms": {"field": "host"},
+                "aggs": {"deriv": {"derivative": {"buckets_path": "_count"}}}
+            }
+        }));
+        assert!(error.contains("`histogram` or `date_histogram`"), "{error}");
+
+        let error = error_message(json!({
+            "by_host": {
+                "terms": {"field": "host"},
+                "aggs": {"sorted": {"bucket_sort": {"sort": [{"latency": "desc"}]}}}
+            }
+        }));
+        assert!(error.contains("unknown aggregation `latency`"), "{error}");
+
+        let error = error_message(json!({
+            "by_host": {
+                "terms": {"field": "host"},
+                "aggs": {
+                    "ratio": {
+                        "bucket_script": {
+                            "buckets_path": {"count": "_count"},
+                            "script": "params.count / params.total"
+                        }
+                    }
+                }
+            }
+        }));
+        assert!(error.contains("variable `total`"), "{error}");
+
+        let error = error_message(json!({
+            "per_hour": {
+                "histogram": {"field": "timestamp", "interval": 3600},
+                "aggs": {
+                    "first": {"cumulative_sum": {"buckets_path": "second"}},
+                    "second": {"cumulative_sum": {"buckets_path": "first"}}
+                }
+            }
+        }));
+        assert!(error.contains("cycle"), "{error}");
+    }
+
+    #[test]
+    fn test_apply_sequential_pipeline_aggregations() {
+        let (_, pipeline_aggregations) = split_from_request(json!({
+            "per_hour": {
+                "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+                "aggs": {
+                    "total": {"sum": {"field": "price"}},
+                    "cumulative_total": {"cumulative_sum": {"buckets_path": "total"}},
+                    "total_deriv": {"derivative": {"buckets_path": "total"}},
+                    "total_deriv_2": {"derivative": {"buckets_path": "total_deriv"}},
+                    "moving_max": {
+                        "moving_fn": {
+                            "buckets_path": "total",
+                            "window": 2,
+                            "script": "MovingFunctions.max(values)"
+                        }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .unwrap();
+        let results = apply(
+            &pipeline_aggregations,
+            json!({
+                "per_hour": {
+                    "buckets": [
+                        {"key": 0, "doc_count": 1, "total": {"value": 10.0}},
+                        {"key": 1, "doc_count": 0, "total": {"value": null}},
+                        {"key": 2, "doc_count": 2, "total": {"value": 30.0}},
+                        {"key": 3, "doc_count": 1, "total": {"value": 25.0}}
+                    ]
+                }
+            }),
+        );
+        assert_eq!(
+            results,
+            json!({
+                "per_hour": {
+                    "buckets": [
+                        {
+                            "key": 0, "doc_count": 1, "total": {"value": 10.0},
+                            "cumulative_total": {"value": 10.0},
+                            "moving_max": {"value": null}
+                        },
+                        {
+                            "key": 1, "doc_count": 0, "total": {"value": null},
+                            "cumulative_total": {"value": 10.0},
+                            "moving_max": {"value": 10.0}
+                        },
+                        {
+                            "key": 2, "doc_count": 2, "total": {"value": 30.0},
+                            "cumulative_total": {"value": 40.0},
+                            "total_deriv": {"value": 20.0},
+                            "moving_max": {"value": 10.0}
+                        },
+                        {
+                            "key": 3, "doc_count": 1, "total": {"value": 25.0},
+                            "cumulative_total": {"value": 65.0},
+                            "total_deriv": {"value": -5.0},
+                            "total_deriv_2": {"value": -25.0},
+                            "moving_max": {"value": 30.0}
+                        }
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_bucket_pipeline_aggregations() {
+        let (_, pipeline_aggregations) = split_from_request(json!({
+            "by_host": {
+                "terms": {"field": "host"},
+                "aggs": {
+                    "latency": {"stats": {"field": "latency"}},
+                    "p99": {"percentiles": {"field": "latency", "percents": [99]}},
+                    "spread": {
+                        "bucket_script": {
+                            "buckets_path": {"max": "latency.max", "min": "latency[min]"},
+                            "script": {"source": "params.max - params.min", "lang": "painless"}
+                        }
+                    },
+                    "slow": {
+                        "bucket_selector": {
+                            "buckets_path": {"p99": "p99[99]", "count": "_count"},
+                            "script": "params.p99 >= 100 && params.count > 1"
+                        }
+                    },
+                    "top": {
+                        "bucket_sort": {"sort": [{"spread": {"order": "desc"}}], "size": 1}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+        .unwrap();
+        let bucket = |host: &str, doc_count: u64, min: f64, max: f64, p99: f64| {
+            json!({
+                "key": host,
+                "doc_count": doc_count,
+                "latency": {"min": min, "max": max},
+                "p99": {"values": {"99.0": p99}}
+            })
+        };
+        let results = apply(
+            &pipeline_aggregations,
+            json!({
+                "by_host": {
+                    "buckets": [
+                        bucket("host-1", 2, 10.0, 150.0, 150.0),
+                        bucket("host-2", 1, 10.0, 500.0, 500.0),
+                        bucket("host-3", 3, 50.0, 400.0, 400.0),
+                        bucket("host-4", 5, 10.0, 50.0, 50.0)
+                    ]
+                }
+            }),
+        );
+        let mut expected_bucket = bucket("host-3", 3, 50.0, 400.0, 400.0);
+        expected_bucket["spread"] = json!({"value": 350.0});
+        assert_eq!(
+            results,
+            json!({
+                "by_host": {
+                    "buckets": [expected_bucket]
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,587 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Expression language of the `bucket_script` and `bucket_selector` pipeline aggregations.
+//!
+//! Scripts are arithmetic and boolean expressions over the variables defined by the
+//! `buckets_path` of the aggregation, e.g. `params.errors / params.total * 100` or
+//! `params.max_latency > 500 && params.total > 10`. Variables are referenced as `params.<name>`,
+//! as in Painless, or by their bare name. Scripts cannot loop nor call anything beyond a few
+//! `Math` functions, so evaluating them is bounded by their length.
+
+use std::collections::{HashMap, HashSet};
+
+/// Maximum nesting depth of an expression, which bounds the recursion of the parser and of the
+/// evaluation of the script.
+const MAX_EXPRESSION_DEPTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ScriptValue {
+    Number(f64),
+    Bool(bool),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Script {
+    expression: Expression,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum UnaryOperator {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum BinaryOperator {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOperator {
+    /// Binding power of the operator: operators with a higher binding power are evaluated first.
+    fn binding_power(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Eq | BinaryOperator::Ne => 3,
+            BinaryOperator::Lt | BinaryOperator::Le | BinaryOperator::Gt | BinaryOperator::Ge => 4,
+            BinaryOperator::Add | BinaryOperator::Sub => 5,
+            BinaryOperator::Mul | BinaryOperator::Div | BinaryOperator::Rem => 6,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Function {
+    Abs,
+    Ceil,
+    Exp,
+    Floor,
+    Log,
+    Log10,
+    Max,
+    Min,
+    Pow,
+    Round,
+    Sqrt,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        let function = match name {
+            "Math.abs" => Function::Abs,
+            "Math.ceil" => Function::Ceil,
+            "Math.exp" => Function::Exp,
+            "Math.floor" => Function::Floor,
+            "Math.log" => Function::Log,
+            "Math.log10" => Function::Log10,
+            "Math.max" => Function::Max,
+            "Math.min" => Function::Min,
+            "Math.pow" => Function::Pow,
+            "Math.round" => Function::Round,
+            "Math.sqrt" => Function::Sqrt,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    fn num_args(&self) -> usize {
+        match self {
+            Function::Max | Function::Min | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(&self, args: &[f64]) -> f64 {
+        match (self, args) {
+            (Function::Abs, [value]) => value.abs(),
+            (Function::Ceil, [value]) => value.ceil(),
+            (Function::Exp, [value]) => value.exp(),
+            (Function::Floor, [value]) => value.floor(),
+            (Function::Log, [value]) => value.ln(),
+            (Function::Log10, [value]) => value.log10(),
+            (Function::Max, [left, right]) => left.max(*right),
+            (Function::Min, [left, right]) => left.min(*right),
+            (Function::Pow, [base, exponent]) => base.powf(*exponent),
+            (Function::Round, [value]) => value.round(),
+            (Function::Sqrt, [value]) => value.sqrt(),
+            _ => f64::NAN,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expression {
+    Number(f64),
+    Bool(bool),
+    Variable(String),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    Conditional(Box<Expression>, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+const OPERATORS: [&str; 20] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", ",", "?",
+    ":", ";",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut remaining = source.trim();
+
+    while let Some(first_char) = remaining.chars().next() {
+        if first_char.is_whitespace() {
+            remaining = remaining.trim_start();
+            continue;
+        }
+        if first_char.is_ascii_digit() || first_char == '.' {
+            let end = remaining
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(remaining.len());
+            let number = remaining[..end]
+                .parse::<f64>()
+                .map_err(|_| format!("invalid number `{}`", &remaining[..end]))?;
+            tokens.push(Token::Number(number));
+            remaining = &remaining[end..];
+            continue;
+        }
+        if first_char.is_alphabetic() || first_char == '_' {
+            let end = remaining
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(remaining.len());
+            tokens.push(Token::Identifier(remaining[..end].to_string()));
+            remaining = &remaining[end..];
+            continue;
+        }
+        let Some(operator) = OPERATORS
+            .iter()
+            .find(|operator| remaining.starts_with(**operator))
+        else {
+            return Err(format!("unexpected character `{first_char}`"));
+        };
+        tokens.push(Token::Operator(*operator));
+        remaining = &remaining[operator.len()..];
+    }
+    // Painless scripts commonly end with a semicolon.
+    if tokens.last() == Some(&Token::Operator(";")) {
+        tokens.pop();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token_opt = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token_opt
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), String> {
+        match self.advance() {
+            Some(Token::Operator(next_operator)) if next_operator == operator => Ok(()),
+            Some(token) => Err(format!("expected `{operator}`, found {token:?}")),
+            None => Err(format!("expected `{operator}`, found end of script")),
+        }
+    }
+
+    /// Increments the nesting depth of the expression being parsed, failing if it exceeds
+    /// [`MAX_EXPRESSION_DEPTH`].
+    fn enter_nested_expression(&mut self) -> Result<(), String> {
+        self.depth += 1;
+
+        if self.depth > MAX_EXPRESSION_DEPTH {
+            return Err(format!(
+                "script exceeds the maximum nesting depth of {MAX_EXPRESSION_DEPTH}"
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        self.enter_nested_expression()?;
+        let condition = self.parse_binary(1)?;
+
+        let expression = if self.peek() == Some(&Token::Operator("?")) {
+            self.advance();
+            let then_expression = self.parse_expression()?;
+            self.expect(":")?;
+            let else_expression = self.parse_expression()?;
+            Expression::Conditional(
+                Box::new(condition),
+                Box::new(then_expression),
+                Box::new(else_expression),
+            )
+        } else {
+            condition
+        };
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn peek_binary_operator(&self) -> Option<BinaryOperator> {
+        let Some(Token::Operator(operator)) = self.peek() else {
+            return None;
+        };
+        let binary_operator = match *operator {
+            "||" => BinaryOperator::Or,
+            "&&" => BinaryOperator::And,
+            "==" => BinaryOperator::Eq,
+            "!=" => BinaryOperator::Ne,
+            "<" => BinaryOperator::Lt,
+            "<=" => BinaryOperator::Le,
+            ">" => BinaryOperator::Gt,
+            ">=" => BinaryOperator::Ge,
+            "+" => BinaryOperator::Add,
+            "-" => BinaryOperator::Sub,
+            "*" => BinaryOperator::Mul,
+            "/" => BinaryOperator::Div,
+            "%" => BinaryOperator::Rem,
+            _ => return None,
+        };
+        Some(binary_operator)
+    }
+
+    fn parse_binary(&mut self, min_binding_power: u8) -> Result<Expression, String> {
+        let depth = self.depth;
+        let mut left = self.parse_unary()?;
+
+        while let Some(operator) = self.peek_binary_operator() {
+            let binding_power = operator.binding_power();
+
+            if binding_power < min_binding_power {
+                break;
+            }
+            self.advance();
+            // Each operator of a chain such as `a + b + c` nests the expression parsed so far one
+            // level deeper, so long chains are bounded like parentheses.
+            self.enter_nested_expression()?;
+            let right = self.parse_binary(binding_power + 1)?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        let unary_operator = match self.peek() {
+            Some(Token::Operator("-")) => UnaryOperator::Neg,
+            Some(Token::Operator("!")) => UnaryOperator::Not,
+            _ => return self.parse_primary(),
+        };
+        self.advance();
+        self.enter_nested_expression()?;
+        let operand = self.parse_unary()?;
+        self.depth -= 1;
+        Ok(Expression::Unary(unary_operator, Box::new(operand)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        match self.advance() {
+            Some(Token::Number(number)) => Ok(Expression::Number(number)),
+            Some(Token::Identifier(identifier)) => self.parse_identifier(identifier),
+            Some(Token::Operator("(")) => {
+                let expression = self.parse_expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Some(token) => Err(format!("unexpected token {token:?}")),
+            None => Err("unexpected end of script".to_string()),
+        }
+    }
+
+    fn parse_identifier(&mut self, identifier: String) -> Result<Expression, String> {
+        match identifier.as_str() {
+            "true" => return Ok(Expression::Bool(true)),
+            "false" => return Ok(Expression::Bool(false)),
+            _ => {}
+        }
+        if self.peek() != Some(&Token::Operator("(")) {
+            let variable = identifier
+                .strip_prefix("params.")
+                .unwrap_or(&identifier)
+                .to_string();
+            return Ok(Expression::Variable(variable));
+        }
+        let function = Function::from_name(&identifier)
+            .ok_or_else(|| format!("unknown function `{identifier}`"))?;
+        self.advance();
+        let mut args = Vec::with_capacity(function.num_args());
+
+        for arg_idx in 0..function.num_args() {
+            if arg_idx > 0 {
+                self.expect(",")?;
+            }
+            args.push(self.parse_expression()?);
+        }
+        self.expect(")")?;
+        Ok(Expression::Call(function, args))
+    }
+}
+
+impl Script {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let expression = parser.parse_expression()?;
+
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected token {token:?}"));
+        }
+        Ok(Self { expression })
+    }
+
+    /// Returns the names of the variables referenced by the script.
+    pub fn variables(&self) -> HashSet<&str> {
+        let mut variables = HashSet::new();
+        let mut expressions = vec![&self.expression];
+
+        while let Some(expression) = expressions.pop() {
+            match expression {
+                Expression::Number(_) | Expression::Bool(_) => {}
+                Expression::Variable(variable) => {
+                    variables.insert(variable.as_str());
+                }
+                Expression::Unary(_, operand) => expressions.push(operand),
+                Expression::Binary(_, left, right) => {
+                    expressions.push(left);
+                    expressions.push(right);
+                }
+                Expression::Conditional(condition, then_expression, else_expression) => {
+                    expressions.push(condition);
+                    expressions.push(then_expression);
+                    expressions.push(else_expression);
+                }
+                Expression::Call(_, args) => expressions.extend(args),
+            }
+        }
+        variables
+    }
+
+    pub fn eval(&self, variables: &HashMap<&str, f64>) -> Result<ScriptValue, String> {
+        eval(&self.expression, variables)
+    }
+}
+
+fn eval_number(expression: &Expression, variables: &HashMap<&str, f64>) -> Result<f64, String> {
+    match eval(expression, variables)? {
+        ScriptValue::Number(number) => Ok(number),
+        ScriptValue::Bool(_) => Err("expected a number, found a boolean".to_string()),
+    }
+}
+
+fn eval_bool(expression: &Expression, variables: &HashMap<&str, f64>) -> Result<bool, String> {
+    match eval(expression, variables)? {
+        ScriptValue::Bool(value) => Ok(value),
+        ScriptValue::Number(_) => Err("expected a boolean, found a number".to_string()),
+    }
+}
+
+fn eval(expression: &Expression, variables: &HashMap<&str, f64>) -> Result<ScriptValue, String> {
+    let value = match expression {
+        Expression::Number(number) => ScriptValue::Number(*number),
+        Expression::Bool(value) => ScriptValue::Bool(*value),
+        Expression::Variable(variable) => {
+            let value = variables
+                .get(variable.as_str())
+                .ok_or_else(|| format!("unknown variable `{variable}`"))?;
+            ScriptValue::Number(*value)
+        }
+        Expression::Unary(UnaryOperator::Neg, operand) => {
+            ScriptValue::Number(-eval_number(operand, variables)?)
+        }
+        Expression::Unary(UnaryOperator::Not, operand) => {
+            ScriptValue::Bool(!eval_bool(operand, variables)?)
+        }
+        Expression::Binary(BinaryOperator::Or, left, right) => {
+            ScriptValue::Bool(eval_bool(left, variables)? || eval_bool(right, variables)?)
+        }
+        Expression::Binary(BinaryOperator::And, left, right) => {
+            ScriptValue::Bool(eval_bool(left, variables)? && eval_bool(right, variables)?)
+        }
+        Expression::Binary(operator, left, right) => {
+            let left_value = eval(left, variables)?;
+            let right_value = eval(right, variables)?;
+
+            match (operator, left_value, right_value) {
+                (BinaryOperator::Eq, _, _) => ScriptValue::Bool(left_value == right_value),
+                (BinaryOperator::Ne, _, _) => ScriptValue::Bool(left_value != right_value),
+                (_, ScriptValue::Number(left), ScriptValue::Number(right)) => match operator {
+                    BinaryOperator::Lt => ScriptValue::Bool(left < right),
+                    BinaryOperator::Le => ScriptValue::Bool(left <= right),
+                    BinaryOperator::Gt => ScriptValue::Bool(left > right),
+                    BinaryOperator::Ge => ScriptValue::Bool(left >= right),
+                    BinaryOperator::Add => ScriptValue::Number(left + right),
+                    BinaryOperator::Sub => ScriptValue::Number(left - right),
+                    BinaryOperator::Mul => ScriptValue::Number(left * right),
+                    BinaryOperator::Div => ScriptValue::Number(left / right),
+                    BinaryOperator::Rem => ScriptValue::Number(left % right),
+                    BinaryOperator::Or
+                    | BinaryOperator::And
+                    | BinaryOperator::Eq
+                    | BinaryOperator::Ne => unreachable!("operator is handled above"),
+                },
+                _ => return Err("expected numbers, found a boolean".to_string()),
+            }
+        }
+        Expression::Conditional(condition, then_expression, else_expression) => {
+            if eval_bool(condition, variables)? {
+                eval(then_expression, variables)?
+            } else {
+                eval(else_expression, variables)?
+            }
+        }
+        Expression::Call(function, args) => {
+            let arg_values = args
+                .iter()
+                .map(|arg| eval_number(arg, variables))
+                .collect::<Result<Vec<f64>, String>>()?;
+            ScriptValue::Number(function.apply(&arg_values))
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_script(source: &str, variables: &[(&str, f64)]) -> Result<ScriptValue, String> {
+        let variables: HashMap<&str, f64> = variables.iter().copied().collect();
+        Script::parse(source)?.eval(&variables)
+    }
+
+    #[test]
+    fn test_script_arithmetic() {
+        assert_eq!(
+            eval_script("1 + 2 * 3 - 4 / 2", &[]).unwrap(),
+            ScriptValue::Number(5.0)
+        );
+        assert_eq!(
+            eval_script("(1 + 2) * -3 % 4", &[]).unwrap(),
+            ScriptValue::Number(-1.0)
+        );
+        assert_eq!(
+            eval_script(
+                "params.errors / params.total * 100",
+                &[("errors", 5.0), ("total", 20.0)]
+            )
+            .unwrap(),
+            ScriptValue::Number(25.0)
+        );
+        assert_eq!(
+            eval_script("Math.max(a, Math.pow(b, 2));", &[("a", 3.0), ("b", 2.0)]).unwrap(),
+            ScriptValue::Number(4.0)
+        );
+        assert_eq!(
+            eval_script(
+                "params.total > 0 ? params.errors / params.total : 0",
+                &[("errors", 1.0), ("total", 0.0)]
+            )
+            .unwrap(),
+            ScriptValue::Number(0.0)
+        );
+    }
+
+    #[test]
+    fn test_script_boolean() {
+        let variables = [("latency", 600.0), ("total", 3.0)];
+        assert_eq!(
+            eval_script("params.latency > 500 && params.total >= 10", &variables).unwrap(),
+            ScriptValue::Bool(false)
+        );
+        assert_eq!(
+            eval_script("latency > 500 || !(total == 3)", &variables).unwrap(),
+            ScriptValue::Bool(true)
+        );
+        let script = Script::parse("params.latency > 500 && total != 0").unwrap();
+        assert_eq!(script.variables(), HashSet::from_iter(["latency", "total"]));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        let error = eval_script("1 +", &[]).unwrap_err();
+        assert!(error.contains("unexpected end of script"), "{error}");
+
+        let error = eval_script("(1 + 2", &[]).unwrap_err();
+        assert!(error.contains("expected `)`"), "{error}");
+
+        let error = eval_script("System.exit(0)", &[]).unwrap_err();
+        assert!(error.contains("unknown function"), "{error}");
+
+        let error = eval_script("1 + true", &[]).unwrap_err();
+        assert!(error.contains("expected numbers"), "{error}");
+
+        let error = eval_script("params.missing * 2", &[]).unwrap_err();
+        assert!(error.contains("unknown variable `missing`"), "{error}");
+
+        let error = eval_script(&"(".repeat(100), &[]).unwrap_err();
+        assert!(error.contains("maximum nesting depth"), "{error}");
+    }
+
+    #[test]
+    fn test_script_long_operator_chain() {
+        let script = ["1"; 32].join(" + ");
+        assert_eq!(
+            eval_script(&script, &[]).unwrap(),
+            ScriptValue::Number(32.0)
+        );
+
+        let script = "1 + ".repeat(100_000) + "1";
+        let error = eval_script(&script, &[]).unwrap_err();
+        assert!(error.contains("maximum nesting depth"), "{error}");
+
+        let script = "params.a > 0 && ".repeat(100_000) + "true";
+        let error = eval_script(&script, &[("a", 1.0)]).unwrap_err();
+        assert!(error.contains("maximum nesting depth"), "{error}");
+    }
+}
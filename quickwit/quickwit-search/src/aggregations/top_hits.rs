@@ -0,0 +1,450 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! The `top_hits` aggregation, returning the top documents of the buckets of its parent
+//! aggregation.
+//!
+//! Leaves only collect the addresses and sort values of the top hits. The root merges them across
+//! splits and fetches the documents of the hits of the final buckets through the fetch docs phase.
+
+use std::collections::{HashMap, HashSet};
+
+use quickwit_proto::search::{
+    Hit, PartialHit, SortByValue, SortField, SortOrder, SortValue, SourceFilter,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map as JsonMap, Value as JsonValue};
+
+use crate::collector::{merge_top_hits, sort_by_from_sort_fields, SortBy};
+use crate::source_filter::filter_source;
+use crate::GlobalDocAddress;
+
+/// Default number of hits returned per bucket.
+const DEFAULT_TOP_HITS_SIZE: usize = 3;
+
+/// Maximum number of hits returned per bucket.
+const MAX_TOP_HITS_SIZE: usize = 100;
+
+#[derive(Clone, Debug)]
+pub(crate) struct TopHitsAggregation {
+    pub size: usize,
+    pub sort_fields: Vec<SortField>,
+    // The sort values of the hits are only returned when the sort is explicit.
+    has_explicit_sort: bool,
+    // `None` if `_source` is disabled.
+    source_filter_opt: Option<SourceFilter>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TopHitsParams {
+    #[serde(default)]
+    size: Option<usize>,
+    #[serde(default)]
+    sort: Option<JsonValue>,
+    #[serde(default, rename = "_source")]
+    source: Option<JsonValue>,
+}
+
+impl TopHitsAggregation {
+    pub fn from_json(name: &str, json_value: JsonValue) -> Result<Self, String> {
+        let params: TopHitsParams = serde_json::from_value(json_value)
+            .map_err(|error| format!("invalid `top_hits` aggregation `{name}`: {error}"))?;
+        let size = params.size.unwrap_or(DEFAULT_TOP_HITS_SIZE);
+
+        if size > MAX_TOP_HITS_SIZE {
+            return Err(format!(
+                "size of `top_hits` aggregation `{name}` must be lower than or equal to \
+                 {MAX_TOP_HITS_SIZE}"
+            ));
+        }
+        let has_explicit_sort = params.sort.is_some();
+        let sort_fields = match params.sort {
+            Some(sort_json) => parse_sort_fields(sort_json)
+                .map_err(|error| format!("invalid `top_hits` aggregation `{name}`: {error}"))?,
+            None => vec![sort_field("_score", SortOrder::Desc)],
+        };
+        let source_filter_opt = match params.source {
+            Some(source_json) => parse_source_filter(source_json)
+                .map_err(|error| format!("invalid `top_hits` aggregation `{name}`: {error}"))?,
+            None => Some(SourceFilter::default()),
+        };
+        Ok(Self {
+            size,
+            sort_fields,
+            has_explicit_sort,
+            source_filter_opt,
+        })
+    }
+
+    pub fn sort_by(&self) -> SortBy {
+        sort_by_from_sort_fields(&self.sort_fields)
+    }
+
+    pub fn requires_scoring(&self) -> bool {
+        self.sort_by()
+            .components()
+            .any(|sort_by_component| sort_by_component.requires_scoring())
+    }
+
+    pub fn add_fast_field_names(&self, fast_field_names: &mut HashSet<String>) {
+        for sort_by_component in self.sort_by().components() {
+            sort_by_component.add_fast_field(fast_field_names);
+        }
+    }
+}
+
+fn sort_field(field_name: &str, sort_order: SortOrder) -> SortField {
+    SortField {
+        field_name: field_name.to_string(),
+        sort_order: sort_order as i32,
+        sort_datetime_format: None,
+    }
+}
+
+/// Parses a sort in the format of Elasticsearch, e.g. `[{"timestamp": "desc"}, "_score"]`.
+fn parse_sort_fields(sort_json: JsonValue) -> Result<Vec<SortField>, String> {
+    let sort_jsons = match sort_json {
+        JsonValue::Array(sort_jsons) => sort_jsons,
+        sort_json => vec![sort_json],
+    };
+    sort_jsons.into_iter().map(parse_sort_field).collect()
+}
+
+fn parse_sort_field(sort_json: JsonValue) -> Result<SortField, String> {
+    let default_order = |field_name: &str| {
+        if field_name == "_score" {
+            SortOrder::Desc
+        } else {
+            SortOrder::Asc
+        }
+    };
+    let parse_order = |order: &str| match order {
+        "asc" => Ok(SortOrder::Asc),
+        "desc" => Ok(SortOrder::Desc),
+        _ => Err(format!(
+            "unknown sort order `{order}`, expected `asc` or `desc`"
+        )),
+    };
+    match sort_json {
+        JsonValue::String(field_name) => Ok(sort_field(&field_name, default_order(&field_name))),
+        JsonValue::Object(json_map) if json_map.len() == 1 => {
+            let (field_name, order_json) = json_map.into_iter().next().expect("map has one entry");
+            let sort_order = match &order_json {
+                JsonValue::String(order) => parse_order(order)?,
+                JsonValue::Object(params) => match params.get("order") {
+                    Some(JsonValue::String(order)) => parse_order(order)?,
+                    None => default_order(&field_name),
+                    Some(_) => return Err(format!("invalid sort `{order_json}`")),
+                },
+                _ => return Err(format!("invalid sort `{order_json}`")),
+            };
+            Ok(sort_field(&field_name, sort_order))
+        }
+        _ => Err(format!("invalid sort `{sort_json}`")),
+    }
+}
+
+/// Parses a `_source` parameter in the format of Elasticsearch.
+fn parse_source_filter(source_json: JsonValue) -> Result<Option<SourceFilter>, String> {
+    let paths = |paths_json: Option<JsonValue>| -> Result<Vec<String>, String> {
+        match paths_json {
+            None => Ok(Vec::new()),
+            Some(JsonValue::String(path)) => Ok(vec![path]),
+            Some(JsonValue::Array(path_jsons)) => path_jsons
+                .into_iter()
+                .map(|path_json| match path_json {
+                    JsonValue::String(path) => Ok(path),
+                    _ => Err(format!("invalid `_source` path `{path_json}`")),
+                })
+                .collect(),
+            Some(paths_json) => Err(format!("invalid `_source` paths `{paths_json}`")),
+        }
+    };
+    let source_filter = match source_json {
+        JsonValue::Bool(false) => return Ok(None),
+        JsonValue::Bool(true) => SourceFilter::default(),
+        JsonValue::String(_) | JsonValue::Array(_) => SourceFilter {
+            includes: paths(Some(source_json))?,
+            excludes: Vec::new(),
+        },
+        JsonValue::Object(mut json_map) => {
+            let includes_json = json_map.remove("includes").or(json_map.remove("include"));
+            let excludes_json = json_map.remove("excludes").or(json_map.remove("exclude"));
+
+            if let Some(unknown_key) = json_map.keys().next() {
+                return Err(format!("unknown `_source` parameter `{unknown_key}`"));
+            }
+            SourceFilter {
+                includes: paths(includes_json)?,
+                excludes: paths(excludes_json)?,
+            }
+        }
+        _ => return Err(format!("invalid `_source` `{source_json}`")),
+    };
+    Ok(Some(source_filter))
+}
+
+/// Top hits of a bucket, mergeable across segments, splits, and nodes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct IntermediateTopHits {
+    num_docs: u64,
+    hits: Vec<IntermediateTopHit>,
+}
+
+// `PartialHit` skips empty fields when serialized, which the postcard format does not support.
+#[derive(Debug, Serialize, Deserialize)]
+struct IntermediateTopHit {
+    sort_value: Option<SortByValue>,
+    sort_value2: Option<SortByValue>,
+    additional_sort_values: Vec<SortByValue>,
+    split_id: String,
+    segment_ord: u32,
+    doc_id: u32,
+}
+
+impl From<PartialHit> for IntermediateTopHit {
+    fn from(partial_hit: PartialHit) -> Self {
+        Self {
+            sort_value: partial_hit.sort_value,
+            sort_value2: partial_hit.sort_value2,
+            additional_sort_values: partial_hit.additional_sort_values,
+            split_id: partial_hit.split_id,
+            segment_ord: partial_hit.segment_ord,
+            doc_id: partial_hit.doc_id,
+        }
+    }
+}
+
+impl From<IntermediateTopHit> for PartialHit {
+    fn from(top_hit: IntermediateTopHit) -> Self {
+        Self {
+            sort_value: top_hit.sort_value,
+            sort_value2: top_hit.sort_value2,
+            additional_sort_values: top_hit.additional_sort_values,
+            split_id: top_hit.split_id,
+            segment_ord: top_hit.segment_ord,
+            doc_id: top_hit.doc_id,
+        }
+    }
+}
+
+impl IntermediateTopHits {
+    pub fn new(num_docs: u64, partial_hits: Vec<PartialHit>) -> Self {
+        Self {
+            num_docs,
+            hits: partial_hits
+                .into_iter()
+                .map(IntermediateTopHit::from)
+                .collect(),
+        }
+    }
+
+    pub fn merge(&mut self, other: IntermediateTopHits, top_hits: &TopHitsAggregation) {
+        self.num_docs += other.num_docs;
+        let partial_hits = std::mem::take(&mut self.hits)
+            .into_iter()
+            .chain(other.hits)
+            .map(PartialHit::from);
+        self.hits = merge_top_hits(&top_hits.sort_by(), partial_hits, top_hits.size)
+            .into_iter()
+            .map(IntermediateTopHit::from)
+            .collect();
+    }
+
+    /// Returns the hits whose documents must be fetched.
+    pub fn partial_hits(&self) -> impl Iterator<Item = PartialHit> + '_ {
+        self.hits.iter().map(|hit| PartialHit {
+            sort_value: hit.sort_value.clone(),
+            sort_value2: hit.sort_value2.clone(),
+            additional_sort_values: hit.additional_sort_values.clone(),
+            split_id: hit.split_id.clone(),
+            segment_ord: hit.segment_ord,
+            doc_id: hit.doc_id,
+        })
+    }
+
+    /// Builds the hits in the format of Elasticsearch, from the documents fetched by the root.
+    pub fn into_final_result(
+        self,
+        top_hits: &TopHitsAggregation,
+        top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
+    ) -> JsonValue {
+        let final_hits: Vec<JsonValue> = self
+            .hits
+            .into_iter()
+            .filter_map(|top_hit| {
+                let partial_hit = PartialHit::from(top_hit);
+                let hit = top_hits_docs.get(&GlobalDocAddress::from_partial_hit(&partial_hit))?;
+                let mut final_hit = JsonMap::new();
+                final_hit.insert("_index".to_string(), JsonValue::from(hit.index_id.clone()));
+
+                if let Some(source_filter) = &top_hits.source_filter_opt {
+                    let doc: JsonMap<String, JsonValue> =
+                        serde_json::from_str(&hit.json).unwrap_or_default();
+                    let source = filter_source(source_filter, doc);
+                    final_hit.insert("_source".to_string(), JsonValue::Object(source));
+                }
+                if top_hits.has_explicit_sort {
+                    let sort_values = partial_hit
+                        .sort_values()
+                        .take(top_hits.sort_fields.len())
+                        .map(sort_value_to_json)
+                        .collect();
+                    final_hit.insert("sort".to_string(), JsonValue::Array(sort_values));
+                }
+                Some(JsonValue::Object(final_hit))
+            })
+            .collect();
+        serde_json::json!({
+            "hits": {
+                "total": {"value": self.num_docs, "relation": "eq"},
+                "hits": final_hits,
+            }
+        })
+    }
+}
+
+fn sort_value_to_json(sort_by_value_opt: Option<&SortByValue>) -> JsonValue {
+    match sort_by_value_opt.and_then(|sort_by_value| sort_by_value.sort_value.as_ref()) {
+        Some(SortValue::U64(value)) => JsonValue::from(*value),
+        Some(SortValue::I64(value)) => JsonValue::from(*value),
+        Some(SortValue::F64(value)) => JsonValue::from(*value),
+        Some(SortValue::Boolean(value)) => JsonValue::from(*value),
+        Some(SortValue::Str(value)) => JsonValue::from(value.clone()),
+        None => JsonValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_top_hits_aggregation() {
+        let top_hits = TopHitsAggregation::from_json(
+            "latest",
+            json!({
+                "size": 1,
+                "sort": [{"timestamp": {"order": "desc"}}, "_score", {"latency": "asc"}],
+                "_source": {"includes": ["message", "service.*"]}
+            }),
+        )
+        .unwrap();
+        assert_eq!(top_hits.size, 1);
+        assert_eq!(
+            top_hits.sort_fields,
+            [
+                sort_field("timestamp", SortOrder::Desc),
+                sort_field("_score", SortOrder::Desc),
+                sort_field("latency", SortOrder::Asc),
+            ]
+        );
+        assert!(top_hits.requires_scoring());
+        assert_eq!(
+            top_hits.source_filter_opt,
+            Some(SourceFilter {
+                includes: vec!["message".to_string(), "service.*".to_string()],
+                excludes: Vec::new(),
+            })
+        );
+        let mut fast_field_names = HashSet::new();
+        top_hits.add_fast_field_names(&mut fast_field_names);
+        assert_eq!(
+            fast_field_names,
+            HashSet::from_iter(["timestamp".to_string(), "latency".to_string()])
+        );
+
+        let top_hits = TopHitsAggregation::from_json("latest", json!({"_source": false})).unwrap();
+        assert_eq!(top_hits.size, DEFAULT_TOP_HITS_SIZE);
+        assert_eq!(
+            top_hits.sort_fields,
+            [sort_field("_score", SortOrder::Desc)]
+        );
+        assert!(top_hits.source_filter_opt.is_none());
+
+        let error =
+            TopHitsAggregation::from_json("latest", json!({"sort": {"timestamp": "latest"}}))
+                .unwrap_err();
+        assert!(error.contains("unknown sort order `latest`"), "{error}");
+    }
+
+    fn top_hit(split_id: &str, doc_id: u32, timestamp: i64) -> PartialHit {
+        PartialHit {
+            sort_value: Some(SortByValue {
+                sort_value: Some(SortValue::I64(timestamp)),
+            }),
+            sort_value2: None,
+            additional_sort_values: Vec::new(),
+            split_id: split_id.to_string(),
+            segment_ord: 0,
+            doc_id,
+        }
+    }
+
+    #[test]
+    fn test_merge_and_finalize_top_hits() {
+        let top_hits = TopHitsAggregation::from_json(
+            "latest",
+            json!({"size": 2, "sort": {"timestamp": "desc"}, "_source": "message"}),
+        )
+        .unwrap();
+        let mut intermediate_top_hits = IntermediateTopHits::new(
+            3,
+            vec![top_hit("split-1", 1, 30), top_hit("split-1", 2, 10)],
+        );
+        let other_intermediate_top_hits =
+            IntermediateTopHits::new(2, vec![top_hit("split-2", 3, 20), top_hit("split-2", 4, 5)]);
+        intermediate_top_hits.merge(other_intermediate_top_hits, &top_hits);
+
+        let serialized = postcard::to_allocvec(&intermediate_top_hits).unwrap();
+        let intermediate_top_hits: IntermediateTopHits = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(
+            intermediate_top_hits.partial_hits().collect::<Vec<_>>(),
+            [top_hit("split-1", 1, 30), top_hit("split-2", 3, 20)]
+        );
+        let top_hits_docs: HashMap<GlobalDocAddress, Hit> = intermediate_top_hits
+            .partial_hits()
+            .map(|partial_hit| {
+                let hit = Hit {
+                    json: json!({"message": "hello", "level": "info"}).to_string(),
+                    index_id: "logs".to_string(),
+                    partial_hit: Some(partial_hit.clone()),
+                    snippet: None,
+                };
+                (GlobalDocAddress::from_partial_hit(&partial_hit), hit)
+            })
+            .collect();
+        let final_result = intermediate_top_hits.into_final_result(&top_hits, &top_hits_docs);
+        assert_eq!(
+            final_result,
+            json!({
+                "hits": {
+                    "total": {"value": 5, "relation": "eq"},
+                    "hits": [
+                        {"_index": "logs", "_source": {"message": "hello"}, "sort": [30]},
+                        {"_index": "logs", "_source": {"message": "hello"}, "sort": [20]},
+                    ]
+                }
+            })
+        );
+    }
+}
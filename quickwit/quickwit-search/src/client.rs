@@ -32,11 +32,12 @@ use quickwit_proto::tonic::codegen::InterceptedService;
 use quickwit_proto::tonic::transport::{Channel, Endpoint};
 use quickwit_proto::tonic::Request;
 use quickwit_proto::{tonic, SpanContextInterceptor};
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::wrappers::ReceiverStream;
 use tower::timeout::Timeout;
 use tracing::{info_span, warn, Instrument};
 
 use crate::error::parse_grpc_error;
+use crate::search_stream::LEAF_SEARCH_STREAM_CHANNEL_CAPACITY;
 use crate::SearchService;
 
 /// Impl is an enumeration that meant to manage Quickwit's search service client types.
@@ -163,7 +164,7 @@ impl SearchServiceClient {
     pub async fn leaf_search_stream(
         &mut self,
         request: quickwit_proto::search::LeafSearchStreamRequest,
-    ) -> UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>> {
+    ) -> ReceiverStream<crate::Result<LeafSearchStreamResponse>> {
         match &mut self.client_impl {
             SearchServiceClientImpl::Grpc(grpc_client) => {
                 let mut grpc_client_clone = grpc_client.clone();
@@ -172,7 +173,8 @@ impl SearchServiceClient {
                     grpc_addr=?self.grpc_addr()
                 );
                 let tonic_request = Request::new(request);
-                let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
+                let (result_sender, result_receiver) =
+                    tokio::sync::mpsc::channel(LEAF_SEARCH_STREAM_CHANNEL_CAPACITY);
                 tokio::spawn(
                     async move {
                         let tonic_result = grpc_client_clone
@@ -182,7 +184,7 @@ impl SearchServiceClient {
                         // If the grpc client fails, send the error in the channel and stop.
                         if let Err(error) = tonic_result {
                             // It is ok to ignore error sending error.
-                            let _ = result_sender.send(Err(error));
+                            let _ = result_sender.send(Err(error)).await;
                             return;
                         }
                         let mut results_stream = tonic_result
@@ -190,7 +192,7 @@ impl SearchServiceClient {
                             .into_inner()
                             .map_err(|tonic_error| parse_grpc_error(&tonic_error));
                         while let Some(search_result) = results_stream.next().await {
-                            let send_result = result_sender.send(search_result);
+                            let send_result = result_sender.send(search_result).await;
                             // If we get a sending error, stop consuming the stream.
                             if send_result.is_err() {
                                 break;
@@ -199,15 +201,15 @@ impl SearchServiceClient {
                     }
                     .instrument(span),
                 );
-                UnboundedReceiverStream::new(result_receiver)
+                ReceiverStream::new(result_receiver)
             }
             SearchServiceClientImpl::Local(service) => {
                 let stream_result = service.leaf_search_stream(request).await;
                 stream_result.unwrap_or_else(|error| {
-                    let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
-                    // Receiver cannot be closed here, ignore error.
-                    let _ = result_sender.send(Err(error));
-                    UnboundedReceiverStream::new(result_receiver)
+                    let (result_sender, result_receiver) = tokio::sync::mpsc::channel(1);
+                    // Receiver cannot be closed and the channel is empty here, ignore error.
+                    let _ = result_sender.try_send(Err(error));
+                    ReceiverStream::new(result_receiver)
                 })
             }
         }
@@ -29,13 +29,14 @@ use quickwit_proto::search::{
 };
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tokio::sync::mpsc::error::SendError;
-use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio::sync::mpsc::{channel, Sender};
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::{debug, error, info, warn};
 
 use crate::retry::search::LeafSearchRetryPolicy;
 use crate::retry::search_stream::{LeafSearchStreamRetryPolicy, SuccessfulSplitIds};
 use crate::retry::{retry_client, DefaultRetryPolicy, RetryPolicy};
+use crate::search_stream::LEAF_SEARCH_STREAM_CHANNEL_CAPACITY;
 use crate::{SearchError, SearchJobPlacer, SearchServiceClient};
 
 /// Maximum number of put requests emitted to perform a replicated given PUT KV.
@@ -126,11 +127,11 @@ impl ClusterClient {
         &self,
         request: LeafSearchStreamRequest,
         mut client: SearchServiceClient,
-    ) -> UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>> {
+    ) -> ReceiverStream<crate::Result<LeafSearchStreamResponse>> {
         // We need a dedicated channel to send results with retry. First we send only the successful
         // responses and and ignore errors. If there are some errors, we make one retry and
         // in this case we send all results.
-        let (result_sender, result_receiver) = unbounded_channel();
+        let (result_sender, result_receiver) = channel(LEAF_SEARCH_STREAM_CHANNEL_CAPACITY);
         let client_pool = self.search_job_placer.clone();
         let retry_policy = LeafSearchStreamRetryPolicy {};
         tokio::spawn(async move {
@@ -151,7 +152,7 @@ impl ClusterClient {
                     Ok(retry_client) => retry_client,
                     Err(error) => {
                         // Propagates the error if we cannot get a new client and stops the task.
-                        let _ = result_sender.send(Err(SearchError::from(error)));
+                        let _ = result_sender.send(Err(SearchError::from(error))).await;
                         return;
                     }
                 };
@@ -168,7 +169,7 @@ impl ClusterClient {
             }
         });
 
-        UnboundedReceiverStream::new(result_receiver)
+        ReceiverStream::new(result_receiver)
     }
 
     /// Leaf search with retry on another node client.
@@ -324,8 +325,8 @@ fn merge_leaf_search_results(
 // If `send_error` is false, errors are ignored and not forwarded. This is
 // useful if you want to make a retry before propagating errors.
 async fn forward_leaf_search_stream(
-    mut stream: UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>>,
-    sender: UnboundedSender<crate::Result<LeafSearchStreamResponse>>,
+    mut stream: ReceiverStream<crate::Result<LeafSearchStreamResponse>>,
+    sender: Sender<crate::Result<LeafSearchStreamResponse>>,
     send_error: bool,
 ) -> Result<SuccessfulSplitIds, SendError<crate::Result<LeafSearchStreamResponse>>> {
     let mut successful_split_ids: Vec<String> = Vec::new();
@@ -333,11 +334,11 @@ async fn forward_leaf_search_stream(
         match result {
             Ok(response) => {
                 successful_split_ids.push(response.split_id.clone());
-                sender.send(Ok(response))?;
+                sender.send(Ok(response)).await?;
             }
             Err(error) => {
                 if send_error {
-                    sender.send(Err(error))?;
+                    sender.send(Err(error)).await?;
                 }
             }
         }
@@ -364,6 +365,7 @@ mod tests {
         PartialHit {
             sort_value: Some(SortValue::U64(sort_value).into()),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
@@ -425,6 +427,7 @@ mod tests {
             fast_field: "fast".to_string(),
             output_format: 0,
             partition_by_field: None,
+            fields: Vec::new(),
         };
         LeafSearchStreamRequest {
             request: Some(search_request),
@@ -673,10 +676,10 @@ mod tests {
             .return_once(|_| Err(SearchError::Internal("error".to_string())));
 
         let mut mock_search_service_2 = MockSearchService::new();
-        let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
+        let (result_sender, result_receiver) = tokio::sync::mpsc::channel(2);
         mock_search_service_2
             .expect_leaf_search_stream()
-            .return_once(|_| Ok(UnboundedReceiverStream::new(result_receiver)));
+            .return_once(|_| Ok(ReceiverStream::new(result_receiver)));
 
         let searcher_pool = searcher_pool_for_test([
             ("127.0.0.1:1001", mock_search_service_1),
@@ -690,9 +693,11 @@ mod tests {
                 data: Vec::new(),
                 split_id: "split_1".to_string(),
             }))
+            .await
             .unwrap();
         result_sender
             .send(Err(SearchError::Internal("last split error".to_string())))
+            .await
             .unwrap();
         drop(result_sender);
 
@@ -20,12 +20,13 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashSet;
+use std::io;
 
 use itertools::Itertools;
 use quickwit_common::binary_heap::{SortKeyMapper, TopK};
 use quickwit_doc_mapper::{DocMapper, WarmupInfo};
 use quickwit_proto::search::{
-    LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortOrder, SortValue,
+    LeafSearchResponse, PartialHit, SearchRequest, SortByValue, SortField, SortOrder, SortValue,
     SplitSearchError,
 };
 use serde::Deserialize;
@@ -33,10 +34,15 @@ use tantivy::aggregation::agg_req::{get_fast_field_names, Aggregations};
 use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::aggregation::{AggregationLimits, AggregationSegmentCollector};
 use tantivy::collector::{Collector, SegmentCollector};
-use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64};
+use tantivy::columnar::{ColumnType, MonotonicallyMappableToU64, StrColumn};
 use tantivy::fastfield::Column;
+use tantivy::schema::Schema;
 use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader, TantivyError};
 
+use crate::aggregations::{
+    ExtendedAggregations, ExtendedAggregationsSegmentCollector,
+    IntermediateExtendedAggregationResults,
+};
 use crate::filters::{create_timestamp_filter_builder, TimestampFilter, TimestampFilterBuilder};
 use crate::find_trace_ids_collector::{FindTraceIdsCollector, FindTraceIdsSegmentCollector, Span};
 use crate::GlobalDocAddress;
@@ -54,28 +60,44 @@ pub(crate) enum SortByComponent {
         order: SortOrder,
     },
 }
-impl From<SortByComponent> for SortByPair {
+impl From<SortByComponent> for SortBy {
     fn from(value: SortByComponent) -> Self {
         Self {
             first: value,
             second: None,
+            additional: Vec::new(),
         }
     }
 }
 #[derive(Clone)]
-pub(crate) struct SortByPair {
+pub(crate) struct SortBy {
     first: SortByComponent,
     second: Option<SortByComponent>,
+    // Sort components beyond the second one. This is empty in the common case, which keeps
+    // sorting by one or two fields allocation free.
+    additional: Vec<SortByComponent>,
 }
-impl SortByPair {
-    pub fn sort_orders(&self) -> (SortOrder, SortOrder) {
-        (
-            self.first.sort_order(),
-            self.second
+impl SortBy {
+    fn sort_key_mapper(&self) -> HitSortingMapper {
+        HitSortingMapper {
+            order1: self.first.sort_order(),
+            order2: self
+                .second
                 .as_ref()
                 .map(|sort_by| sort_by.sort_order())
                 .unwrap_or(SortOrder::Desc),
-        )
+            additional_orders: self
+                .additional
+                .iter()
+                .map(SortByComponent::sort_order)
+                .collect(),
+        }
+    }
+
+    pub(crate) fn components(&self) -> impl Iterator<Item = &SortByComponent> {
+        std::iter::once(&self.first)
+            .chain(self.second.as_ref())
+            .chain(self.additional.iter())
     }
 }
 impl SortByComponent {
@@ -88,6 +110,11 @@ impl SortByComponent {
             SortByComponent::FastField { field_name, .. } => {
                 let sort_column_opt: Option<(Column<u64>, ColumnType)> =
                     segment_reader.fast_fields().u64_lenient(field_name)?;
+                if sort_column_opt.is_none() {
+                    if let Some(str_column) = segment_reader.fast_fields().str(field_name)? {
+                        return Ok(SortingFieldExtractorComponent::StrFastField { str_column });
+                    }
+                }
                 let (sort_column, column_type) = sort_column_opt.unwrap_or_else(|| {
                     (
                         Column::build_empty_column(segment_reader.max_doc()),
@@ -146,6 +173,12 @@ enum SortingFieldExtractorComponent {
         sort_column: Column<u64>,
         sort_field_type: SortFieldType,
     },
+    /// Text fast fields are sorted on their term ordinals within a segment, as ordinals follow
+    /// the lexicographical order of the terms. Ordinals are converted back into terms when the
+    /// top hits of the segment are harvested.
+    StrFastField {
+        str_column: StrColumn,
+    },
     Score,
 }
 
@@ -172,26 +205,120 @@ impl SortingFieldExtractorComponent {
             } => sort_column
                 .first(doc_id)
                 .map(|field_val| map_fast_field_to_value(field_val, *sort_field_type)),
+            SortingFieldExtractorComponent::StrFastField { str_column } => {
+                str_column.ords().first(doc_id).map(SortValue::U64)
+            }
             SortingFieldExtractorComponent::Score { .. } => Some(SortValue::F64(score as f64)),
         }
     }
+
+    /// Converts a sort value extracted by [`Self::extract_typed_sort_value_opt`] into a value
+    /// that can be compared with the sort values of other segments and splits.
+    fn convert_to_split_sort_value(
+        &self,
+        sort_value_opt: &mut Option<SortValue>,
+    ) -> io::Result<()> {
+        let SortingFieldExtractorComponent::StrFastField { str_column } = self else {
+            return Ok(());
+        };
+        if let Some(SortValue::U64(term_ord)) = sort_value_opt {
+            let mut term = String::new();
+            str_column.ord_to_str(*term_ord, &mut term)?;
+            *sort_value_opt = Some(SortValue::Str(term));
+        }
+        Ok(())
+    }
+
+    /// Converts a `search_after` sort value into a value that can be compared with the values
+    /// returned by [`Self::extract_typed_sort_value_opt`].
+    fn convert_to_segment_sort_value(
+        &self,
+        sort_value_opt: &mut Option<SortValue>,
+    ) -> tantivy::Result<()> {
+        let Some(sort_value) = sort_value_opt.as_mut() else {
+            return Ok(());
+        };
+        match self {
+            SortingFieldExtractorComponent::DocId => {}
+            SortingFieldExtractorComponent::FastField { .. }
+            | SortingFieldExtractorComponent::Score => {
+                // Numbers can be passed as strings by clients that can't handle large integers.
+                if let SortValue::Str(value) = sort_value {
+                    *sort_value = parse_numeric_sort_value(value).ok_or_else(|| {
+                        TantivyError::InvalidArgument(format!(
+                            "search after value `{value}` of a numeric sort field must be a number"
+                        ))
+                    })?;
+                }
+            }
+            SortingFieldExtractorComponent::StrFastField { str_column } => {
+                let term = match sort_value {
+                    SortValue::Str(term) => term.clone(),
+                    SortValue::U64(number) => number.to_string(),
+                    SortValue::I64(number) => number.to_string(),
+                    SortValue::F64(number) => number.to_string(),
+                    SortValue::Boolean(boolean) => boolean.to_string(),
+                };
+                *sort_value = term_to_segment_sort_value(str_column, &term)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_numeric_sort_value(value: &str) -> Option<SortValue> {
+    if let Ok(number) = value.parse::<i64>() {
+        Some(SortValue::I64(number))
+    } else if let Ok(number) = value.parse::<u64>() {
+        Some(SortValue::U64(number))
+    } else if let Ok(number) = value.parse::<f64>() {
+        Some(SortValue::F64(number))
+    } else {
+        None
+    }
+}
+
+/// Returns the segment sort value of a term, used for `search_after` comparisons.
+///
+/// If the term is present in the segment dictionary, this is its term ordinal. Otherwise, this is
+/// an `F64` value that sits in between the ordinals of the terms surrounding it.
+pub(crate) fn term_to_segment_sort_value(
+    str_column: &StrColumn,
+    term: &str,
+) -> io::Result<SortValue> {
+    let mut lower_ord = 0u64;
+    let mut upper_ord = str_column.num_terms() as u64;
+    let mut buffer = String::new();
+
+    while lower_ord < upper_ord {
+        let mid_ord = lower_ord + (upper_ord - lower_ord) / 2;
+        str_column.ord_to_str(mid_ord, &mut buffer)?;
+        match buffer.as_str().cmp(term) {
+            Ordering::Less => lower_ord = mid_ord + 1,
+            Ordering::Equal => return Ok(SortValue::U64(mid_ord)),
+            Ordering::Greater => upper_ord = mid_ord,
+        }
+    }
+    Ok(SortValue::F64(lower_ord as f64 - 0.5))
 }
 
-impl From<SortingFieldExtractorComponent> for SortingFieldExtractorPair {
+impl From<SortingFieldExtractorComponent> for SortingFieldExtractor {
     fn from(value: SortingFieldExtractorComponent) -> Self {
         Self {
             first: value,
             second: None,
+            additional: Vec::new(),
         }
     }
 }
 
-pub(crate) struct SortingFieldExtractorPair {
+pub(crate) struct SortingFieldExtractor {
     first: SortingFieldExtractorComponent,
     second: Option<SortingFieldExtractorComponent>,
+    additional: Vec<SortingFieldExtractorComponent>,
 }
 
-impl SortingFieldExtractorPair {
+impl SortingFieldExtractor {
     /// Returns the list of sort values for the given element
     ///
     /// See also [`SortingFieldExtractorComponent::extract_typed_sort_value_opt`] for more
@@ -208,6 +335,61 @@ impl SortingFieldExtractorPair {
             .and_then(|second| second.extract_typed_sort_value_opt(doc_id, score));
         (first, second)
     }
+
+    /// Converts the sort values of a hit collected in this segment into values that can be
+    /// compared across segments and splits.
+    fn convert_to_split_sort_values(
+        &self,
+        segment_partial_hit: &mut SegmentPartialHit,
+    ) -> io::Result<()> {
+        self.first
+            .convert_to_split_sort_value(&mut segment_partial_hit.sort_value)?;
+        if let Some(second) = &self.second {
+            second.convert_to_split_sort_value(&mut segment_partial_hit.sort_value2)?;
+        }
+        for (component, sort_value_opt) in self
+            .additional
+            .iter()
+            .zip(&mut segment_partial_hit.additional_sort_values)
+        {
+            component.convert_to_split_sort_value(sort_value_opt)?;
+        }
+        Ok(())
+    }
+
+    /// Converts the sort values of a `search_after` hit into values that can be compared with the
+    /// sort values extracted from this segment.
+    fn convert_to_segment_search_after(
+        &self,
+        search_after: &PartialHit,
+    ) -> tantivy::Result<PartialHit> {
+        let mut segment_search_after = search_after.clone();
+        let components = std::iter::once(&self.first)
+            .chain(self.second.as_ref())
+            .chain(self.additional.iter());
+        for (component, sort_by_value_opt) in components.zip(segment_search_after.sort_values_mut())
+        {
+            if let Some(sort_by_value) = sort_by_value_opt {
+                component.convert_to_segment_sort_value(&mut sort_by_value.sort_value)?;
+            }
+        }
+        Ok(segment_search_after)
+    }
+
+    /// Returns the sort values beyond the second one for the given element.
+    ///
+    /// The returned vector is empty, and does not allocate, if the hits are sorted by up to two
+    /// fields.
+    fn extract_additional_typed_sort_values(
+        &self,
+        doc_id: DocId,
+        score: Score,
+    ) -> Vec<Option<SortValue>> {
+        self.additional
+            .iter()
+            .map(|component| component.extract_typed_sort_value_opt(doc_id, score))
+            .collect()
+    }
 }
 
 impl TryFrom<ColumnType> for SortFieldType {
@@ -229,12 +411,12 @@ impl TryFrom<ColumnType> for SortFieldType {
 }
 
 /// Takes a user-defined sorting criteria and resolves it to a
-/// segment specific `SortingFieldExtractorPair`.
-fn get_score_extractor(
-    sort_by: &SortByPair,
+/// segment specific `SortingFieldExtractor`.
+pub(crate) fn get_score_extractor(
+    sort_by: &SortBy,
     segment_reader: &SegmentReader,
-) -> tantivy::Result<SortingFieldExtractorPair> {
-    Ok(SortingFieldExtractorPair {
+) -> tantivy::Result<SortingFieldExtractor> {
+    Ok(SortingFieldExtractor {
         first: sort_by
             .first
             .to_sorting_field_extractor_component(segment_reader)?,
@@ -243,6 +425,11 @@ fn get_score_extractor(
             .as_ref()
             .map(|first| first.to_sorting_field_extractor_component(segment_reader))
             .transpose()?,
+        additional: sort_by
+            .additional
+            .iter()
+            .map(|component| component.to_sorting_field_extractor_component(segment_reader))
+            .collect::<tantivy::Result<_>>()?,
     })
 }
 
@@ -291,18 +478,20 @@ impl Eq for PartialHitHeapItem {}
 enum AggregationSegmentCollectors {
     FindTraceIdsSegmentCollector(Box<FindTraceIdsSegmentCollector>),
     TantivyAggregationSegmentCollector(AggregationSegmentCollector),
+    ExtendedAggregationsSegmentCollector(Box<ExtendedAggregationsSegmentCollector>),
 }
 
 /// Quickwit collector working at the scale of the segment.
 pub struct QuickwitSegmentCollector {
     num_hits: u64,
     split_id: String,
-    score_extractor: SortingFieldExtractorPair,
+    score_extractor: SortingFieldExtractor,
     // PartialHits in this heap don't contain a split_id yet.
     top_k_hits: TopK<SegmentPartialHit, SegmentPartialHitSortingKey, HitSortingMapper>,
     segment_ord: u32,
     timestamp_filter_opt: Option<TimestampFilter>,
     aggregation: Option<AggregationSegmentCollectors>,
+    // The sort values of `search_after` are converted into segment-level sort values.
     search_after: Option<PartialHit>,
     split_search_after_order: Ordering,
 }
@@ -312,18 +501,41 @@ impl QuickwitSegmentCollector {
     fn collect_top_k(&mut self, doc_id: DocId, score: Score) {
         let (sort_value, sort_value2) =
             self.score_extractor.extract_typed_sort_value(doc_id, score);
+        let additional_sort_values = self
+            .score_extractor
+            .extract_additional_typed_sort_values(doc_id, score);
 
         if let Some(search_after) = &self.search_after {
-            let search_after_value1 = search_after.sort_value.and_then(|v| v.sort_value);
-            let search_after_value2 = search_after.sort_value2.and_then(|v| v.sort_value);
+            let search_after_value1 = search_after
+                .sort_value
+                .as_ref()
+                .and_then(|v| v.sort_value.as_ref());
+            let search_after_value2 = search_after
+                .sort_value2
+                .as_ref()
+                .and_then(|v| v.sort_value.as_ref());
             let orders = &self.top_k_hits.sort_key_mapper;
             let mut cmp_result = orders
                 .order1
-                .compare_opt(&sort_value, &search_after_value1)
+                .compare_opt(&sort_value.as_ref(), &search_after_value1)
                 .then_with(|| {
                     orders
                         .order2
-                        .compare_opt(&sort_value2, &search_after_value2)
+                        .compare_opt(&sort_value2.as_ref(), &search_after_value2)
+                })
+                .then_with(|| {
+                    let additional_sort_key = AdditionalSortKey::new(
+                        &orders.additional_orders,
+                        additional_sort_values.iter().cloned(),
+                    );
+                    let search_after_additional_sort_key = AdditionalSortKey::new(
+                        &orders.additional_orders,
+                        search_after
+                            .additional_sort_values
+                            .iter()
+                            .map(|sort_by_value| sort_by_value.sort_value.clone()),
+                    );
+                    additional_sort_key.cmp(&search_after_additional_sort_key)
                 });
             if !search_after.split_id.is_empty() {
                 // TODO actually it's not first, it should be what's in _shard_doc then first then
@@ -341,8 +553,9 @@ impl QuickwitSegmentCollector {
         }
 
         let hit = SegmentPartialHit {
-            sort_value: sort_value.map(Into::into),
-            sort_value2: sort_value2.map(Into::into),
+            sort_value,
+            sort_value2,
+            additional_sort_values,
             doc_id,
         };
         self.top_k_hits.add_entry(hit);
@@ -357,15 +570,21 @@ impl QuickwitSegmentCollector {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 struct SegmentPartialHit {
     sort_value: Option<SortValue>,
     sort_value2: Option<SortValue>,
+    additional_sort_values: Vec<Option<SortValue>>,
     doc_id: DocId,
 }
 
 impl SegmentPartialHit {
-    fn into_partial_hit(self, split_id: String, segment_ord: SegmentOrdinal) -> PartialHit {
+    fn into_partial_hit(mut self, split_id: String, segment_ord: SegmentOrdinal) -> PartialHit {
+        // Like for the first two sort values, trailing missing values (e.g. the ones of a `_doc`
+        // sort field) are not materialized.
+        while let Some(None) = self.additional_sort_values.last() {
+            self.additional_sort_values.pop();
+        }
         PartialHit {
             sort_value: self.sort_value.map(|sort_value| SortByValue {
                 sort_value: Some(sort_value),
@@ -373,6 +592,11 @@ impl SegmentPartialHit {
             sort_value2: self.sort_value2.map(|sort_value| SortByValue {
                 sort_value: Some(sort_value),
             }),
+            additional_sort_values: self
+                .additional_sort_values
+                .into_iter()
+                .map(|sort_value| SortByValue { sort_value })
+                .collect(),
             doc_id: self.doc_id,
             split_id,
             segment_ord,
@@ -399,6 +623,9 @@ impl SegmentCollector for QuickwitSegmentCollector {
             Some(AggregationSegmentCollectors::TantivyAggregationSegmentCollector(collector)) => {
                 collector.collect(doc_id, score)
             }
+            Some(AggregationSegmentCollectors::ExtendedAggregationsSegmentCollector(collector)) => {
+                collector.collect(doc_id, score)
+            }
             None => (),
         }
     }
@@ -408,10 +635,12 @@ impl SegmentCollector for QuickwitSegmentCollector {
             .top_k_hits
             .finalize()
             .into_iter()
-            .map(|segment_partial_hit: SegmentPartialHit| {
-                segment_partial_hit.into_partial_hit(self.split_id.clone(), self.segment_ord)
+            .map(|mut segment_partial_hit: SegmentPartialHit| {
+                self.score_extractor
+                    .convert_to_split_sort_values(&mut segment_partial_hit)?;
+                Ok(segment_partial_hit.into_partial_hit(self.split_id.clone(), self.segment_ord))
             })
-            .collect();
+            .collect::<io::Result<_>>()?;
 
         let intermediate_aggregation_result = match self.aggregation {
             Some(AggregationSegmentCollectors::FindTraceIdsSegmentCollector(collector)) => {
@@ -425,6 +654,11 @@ impl SegmentCollector for QuickwitSegmentCollector {
                     .expect("Collector fruit should be serializable.");
                 Some(serialized)
             }
+            Some(AggregationSegmentCollectors::ExtendedAggregationsSegmentCollector(collector)) => {
+                let serialized = postcard::to_allocvec(&collector.harvest()?)
+                    .expect("Collector fruit should be serializable.");
+                Some(serialized)
+            }
             None => None,
         };
         Ok(LeafSearchResponse {
@@ -444,6 +678,9 @@ pub enum QuickwitAggregations {
     /// Aggregation used by the Jaeger service to find trace IDs that match a
     /// [`quickwit_proto::jaeger::storage::v1::FindTraceIDsRequest`].
     FindTraceIdsAggregation(FindTraceIdsCollector),
+    /// Tantivy aggregations along with at least one `cardinality`, `composite`, or `filters`
+    /// aggregation. This variant is tried before the tantivy one, which would reject them.
+    ExtendedAggregations(ExtendedAggregations),
     /// Your classic Tantivy aggregation.
     TantivyAggregations(Aggregations),
 }
@@ -454,6 +691,9 @@ impl QuickwitAggregations {
             QuickwitAggregations::FindTraceIdsAggregation(collector) => {
                 collector.fast_field_names()
             }
+            QuickwitAggregations::ExtendedAggregations(aggregations) => {
+                aggregations.fast_field_names()
+            }
             QuickwitAggregations::TantivyAggregations(aggregations) => {
                 get_fast_field_names(aggregations)
             }
@@ -465,6 +705,9 @@ impl QuickwitAggregations {
             QuickwitAggregations::FindTraceIdsAggregation(aggreg) => {
                 QuickwitIncrementalAggregations::FindTraceIdsAggregation(aggreg.clone(), Vec::new())
             }
+            QuickwitAggregations::ExtendedAggregations(aggreg) => {
+                QuickwitIncrementalAggregations::ExtendedAggregations(aggreg.clone(), Vec::new())
+            }
             QuickwitAggregations::TantivyAggregations(aggreg) => {
                 QuickwitIncrementalAggregations::TantivyAggregations(aggreg.clone(), Vec::new())
             }
@@ -475,6 +718,7 @@ impl QuickwitAggregations {
 #[derive(Clone)]
 enum QuickwitIncrementalAggregations {
     FindTraceIdsAggregation(FindTraceIdsCollector, Vec<Vec<Span>>),
+    ExtendedAggregations(ExtendedAggregations, Vec<Vec<u8>>),
     TantivyAggregations(Aggregations, Vec<Vec<u8>>),
     NoAggregation,
 }
@@ -491,7 +735,8 @@ impl QuickwitIncrementalAggregations {
                     state.push(new_state);
                 }
             }
-            QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
+            QuickwitIncrementalAggregations::ExtendedAggregations(_, state)
+            | QuickwitIncrementalAggregations::TantivyAggregations(_, state) => {
                 state.push(intermediate_result);
             }
             QuickwitIncrementalAggregations::NoAggregation => (),
@@ -511,6 +756,7 @@ impl QuickwitIncrementalAggregations {
                                     sort_value: Some(SortValue::I64(timestamp)),
                                 }),
                                 sort_value2: None,
+                                additional_sort_values: Vec::new(),
                                 split_id: String::new(),
                                 segment_ord: 0,
                                 doc_id: 0,
@@ -520,6 +766,7 @@ impl QuickwitIncrementalAggregations {
                 }
                 None
             }
+            QuickwitIncrementalAggregations::ExtendedAggregations(_, _) => None,
             QuickwitIncrementalAggregations::TantivyAggregations(_, _) => None,
             QuickwitIncrementalAggregations::NoAggregation => None,
         }
@@ -536,6 +783,12 @@ impl QuickwitIncrementalAggregations {
                 let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
                 Ok(Some(serialized))
             }
+            QuickwitIncrementalAggregations::ExtendedAggregations(aggregation, state) => {
+                merge_intermediate_aggregation_result(
+                    &Some(QuickwitAggregations::ExtendedAggregations(aggregation)),
+                    state.iter().map(|vec| vec.as_slice()),
+                )
+            }
             QuickwitIncrementalAggregations::TantivyAggregations(aggregation, state) => {
                 merge_intermediate_aggregation_result(
                     &Some(QuickwitAggregations::TantivyAggregations(aggregation)),
@@ -556,7 +809,7 @@ pub(crate) struct QuickwitCollector {
     pub split_id: String,
     pub start_offset: usize,
     pub max_hits: usize,
-    pub sort_by: SortByPair,
+    pub sort_by: SortBy,
     timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
     pub aggregation: Option<QuickwitAggregations>,
     pub aggregation_limits: AggregationLimits,
@@ -566,9 +819,8 @@ pub(crate) struct QuickwitCollector {
 impl QuickwitCollector {
     pub fn fast_field_names(&self) -> HashSet<String> {
         let mut fast_field_names = HashSet::default();
-        self.sort_by.first.add_fast_field(&mut fast_field_names);
-        if let Some(sort_by_second) = &self.sort_by.second {
-            sort_by_second.add_fast_field(&mut fast_field_names);
+        for sort_by_component in self.sort_by.components() {
+            sort_by_component.add_fast_field(&mut fast_field_names);
         }
         if let Some(aggregations) = &self.aggregation {
             fast_field_names.extend(aggregations.fast_field_names());
@@ -580,11 +832,16 @@ impl QuickwitCollector {
     }
 
     pub fn warmup_info(&self) -> WarmupInfo {
-        WarmupInfo {
+        let mut warmup_info = WarmupInfo {
             fast_field_names: self.fast_field_names(),
             field_norms: self.requires_scoring(),
             ..WarmupInfo::default()
+        };
+        // The queries of `filters` aggregations have their own warmup requirements.
+        if let Some(QuickwitAggregations::ExtendedAggregations(aggregations)) = &self.aggregation {
+            warmup_info.merge(aggregations.warmup_info());
         }
+        warmup_info
     }
 }
 
@@ -611,6 +868,16 @@ impl Collector for QuickwitCollector {
                     Box::new(collector.for_segment(0, segment_reader)?),
                 ))
             }
+            Some(QuickwitAggregations::ExtendedAggregations(aggs)) => Some(
+                AggregationSegmentCollectors::ExtendedAggregationsSegmentCollector(Box::new(
+                    aggs.segment_collector(
+                        &self.split_id,
+                        segment_reader,
+                        segment_ord,
+                        &self.aggregation_limits,
+                    )?,
+                )),
+            ),
             Some(QuickwitAggregations::TantivyAggregations(aggs)) => Some(
                 AggregationSegmentCollectors::TantivyAggregationSegmentCollector(
                     AggregationSegmentCollector::from_agg_req_and_reader(
@@ -624,11 +891,17 @@ impl Collector for QuickwitCollector {
             None => None,
         };
         let score_extractor = get_score_extractor(&self.sort_by, segment_reader)?;
-        let (order1, order2) = self.sort_by.sort_orders();
-        let sort_key_mapper = HitSortingMapper { order1, order2 };
+        let segment_search_after = self
+            .search_after
+            .as_ref()
+            .map(|search_after| score_extractor.convert_to_segment_search_after(search_after))
+            .transpose()?;
+        let sort_key_mapper = self.sort_by.sort_key_mapper();
         let split_search_after_order = if let Some(search_after) = &self.search_after {
             if !search_after.split_id.is_empty() {
-                order1.compare(&self.split_id, &search_after.split_id)
+                sort_key_mapper
+                    .order1
+                    .compare(&self.split_id, &search_after.split_id)
             } else {
                 // so we don't reject document based on their split_id if we don't have one in
                 // search_after
@@ -646,7 +919,7 @@ impl Collector for QuickwitCollector {
             segment_ord,
             timestamp_filter_opt,
             aggregation,
-            search_after: self.search_after.clone(),
+            search_after: segment_search_after,
             split_search_after_order,
         })
     }
@@ -655,13 +928,14 @@ impl Collector for QuickwitCollector {
         // We do not need BM25 scoring in Quickwit if it is not opted-in.
         // By returning false, we inform tantivy that it does not need to decompress
         // term frequencies.
-        self.sort_by.first.requires_scoring()
-            || self
-                .sort_by
-                .second
-                .as_ref()
-                .map(|sort_by| sort_by.requires_scoring())
-                .unwrap_or(false)
+        if let Some(QuickwitAggregations::ExtendedAggregations(aggregations)) = &self.aggregation {
+            if aggregations.requires_scoring() {
+                return true;
+            }
+        }
+        self.sort_by
+            .components()
+            .any(|sort_by_component| sort_by_component.requires_scoring())
     }
 
     fn merge_fruits(
@@ -674,12 +948,10 @@ impl Collector for QuickwitCollector {
         // All leaves will return their top [0..start_offset + max_hits) documents.
         // We compute the overall [0..start_offset + max_hits) documents ...
         let num_hits = self.start_offset + self.max_hits;
-        let (sort_order1, sort_order2) = self.sort_by.sort_orders();
         let mut merged_leaf_response = merge_leaf_responses(
             &self.aggregation,
             segment_fruits?,
-            sort_order1,
-            sort_order2,
+            self.sort_by.sort_key_mapper(),
             num_hits,
         )?;
         // ... and drop the first [..start_offsets) hits.
@@ -719,6 +991,27 @@ fn merge_intermediate_aggregation_result<'a>(
             let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
             Some(serialized)
         }
+        Some(QuickwitAggregations::ExtendedAggregations(aggregations)) => {
+            let fruits: Vec<IntermediateExtendedAggregationResults> =
+                intermediate_aggregation_results
+                    .map(|intermediate_aggregation_result| {
+                        postcard::from_bytes(intermediate_aggregation_result).map_err(map_error)
+                    })
+                    .collect::<Result<_, _>>()?;
+
+            let mut fruit_iter = fruits.into_iter();
+            if let Some(first_fruit) = fruit_iter.next() {
+                let mut merged_fruit = first_fruit;
+                for fruit in fruit_iter {
+                    merged_fruit.merge(fruit, aggregations)?;
+                }
+                let serialized = postcard::to_allocvec(&merged_fruit).map_err(map_error)?;
+
+                Some(serialized)
+            } else {
+                None
+            }
+        }
         Some(QuickwitAggregations::TantivyAggregations(_)) => {
             let fruits: Vec<IntermediateAggregationResults> = intermediate_aggregation_results
                 .map(|intermediate_aggregation_result| {
@@ -749,8 +1042,7 @@ fn merge_intermediate_aggregation_result<'a>(
 fn merge_leaf_responses(
     aggregations_opt: &Option<QuickwitAggregations>,
     mut leaf_responses: Vec<LeafSearchResponse>,
-    sort_order1: SortOrder,
-    sort_order2: SortOrder,
+    sort_key_mapper: HitSortingMapper,
     max_hits: usize,
 ) -> tantivy::Result<LeafSearchResponse> {
     // Optimization: No merging needed if there is only one result.
@@ -782,12 +1074,8 @@ fn merge_leaf_responses(
         .into_iter()
         .flat_map(|leaf_response| leaf_response.partial_hits)
         .collect();
-    let top_k_partial_hits: Vec<PartialHit> = top_k_partial_hits(
-        all_partial_hits.into_iter(),
-        sort_order1,
-        sort_order2,
-        max_hits,
-    );
+    let top_k_partial_hits: Vec<PartialHit> =
+        top_k_partial_hits(all_partial_hits.into_iter(), sort_key_mapper, max_hits);
     Ok(LeafSearchResponse {
         intermediate_aggregation_result: merged_intermediate_aggregation_result,
         num_hits,
@@ -797,17 +1085,71 @@ fn merge_leaf_responses(
     })
 }
 
+/// Top hits of a bucket of a `top_hits` aggregation within a segment.
+pub(crate) struct SegmentTopHits {
+    top_k_hits: TopK<SegmentPartialHit, SegmentPartialHitSortingKey, HitSortingMapper>,
+}
+
+impl SegmentTopHits {
+    pub fn new(sort_by: &SortBy, num_hits: usize) -> Self {
+        Self {
+            top_k_hits: TopK::new(num_hits, sort_by.sort_key_mapper()),
+        }
+    }
+
+    pub fn collect(
+        &mut self,
+        score_extractor: &SortingFieldExtractor,
+        doc_id: DocId,
+        score: Score,
+    ) {
+        let (sort_value, sort_value2) = score_extractor.extract_typed_sort_value(doc_id, score);
+        let additional_sort_values =
+            score_extractor.extract_additional_typed_sort_values(doc_id, score);
+        let hit = SegmentPartialHit {
+            sort_value,
+            sort_value2,
+            additional_sort_values,
+            doc_id,
+        };
+        self.top_k_hits.add_entry(hit);
+    }
+
+    pub fn harvest(
+        self,
+        score_extractor: &SortingFieldExtractor,
+        split_id: &str,
+        segment_ord: SegmentOrdinal,
+    ) -> io::Result<Vec<PartialHit>> {
+        self.top_k_hits
+            .finalize()
+            .into_iter()
+            .map(|mut segment_partial_hit| {
+                score_extractor.convert_to_split_sort_values(&mut segment_partial_hit)?;
+                Ok(segment_partial_hit.into_partial_hit(split_id.to_string(), segment_ord))
+            })
+            .collect()
+    }
+}
+
+/// Merges the top hits of a `top_hits` aggregation collected in different segments or splits.
+pub(crate) fn merge_top_hits(
+    sort_by: &SortBy,
+    partial_hits: impl Iterator<Item = PartialHit>,
+    num_hits: usize,
+) -> Vec<PartialHit> {
+    top_k_partial_hits(partial_hits, sort_by.sort_key_mapper(), num_hits)
+}
+
 /// Mutates partial_hits so that it contains the top-num_hitso hits,
 /// and so that these elements are sorted.
 ///
 /// TODO we could possibly optimize the sort away (but I doubt it matters).
 fn top_k_partial_hits(
     partial_hits: impl Iterator<Item = PartialHit>,
-    order1: SortOrder,
-    order2: SortOrder,
+    sort_key_mapper: HitSortingMapper,
     num_hits: usize,
 ) -> Vec<PartialHit> {
-    let sort_key_mapper = HitSortingMapper { order1, order2 };
     let mut top_k_hits = TopK::new(num_hits, sort_key_mapper);
 
     partial_hits.for_each(|hit| top_k_hits.add_entry(hit));
@@ -815,8 +1157,14 @@ fn top_k_partial_hits(
     top_k_hits.finalize()
 }
 
-pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair {
-    let to_sort_by_component = |field_name: &str, order| {
+pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortBy {
+    sort_by_from_sort_fields(&search_request.sort_fields)
+}
+
+pub(crate) fn sort_by_from_sort_fields(sort_fields: &[SortField]) -> SortBy {
+    let to_sort_by_component = |sort_field: &SortField| {
+        let field_name = sort_field.field_name.as_str();
+        let order = SortOrder::from_i32(sort_field.sort_order).unwrap_or(SortOrder::Desc);
         if field_name == "_score" {
             SortByComponent::Score { order }
         } else if field_name == "_shard_doc" || field_name == "_doc" {
@@ -828,42 +1176,38 @@ pub(crate) fn sort_by_from_request(search_request: &SearchRequest) -> SortByPair
             }
         }
     };
+    let mut sort_by_components = sort_fields.iter().map(to_sort_by_component);
 
-    let num_sort_fields = search_request.sort_fields.len();
-    if num_sort_fields == 0 {
-        SortByComponent::DocId {
+    let Some(first) = sort_by_components.next() else {
+        return SortByComponent::DocId {
             order: SortOrder::Desc,
         }
-        .into()
-    } else if num_sort_fields == 1 {
-        let sort_field = &search_request.sort_fields[0];
-        let order = SortOrder::from_i32(sort_field.sort_order).unwrap_or(SortOrder::Desc);
-        to_sort_by_component(&sort_field.field_name, order).into()
-    } else if num_sort_fields == 2 {
-        let sort_field1 = &search_request.sort_fields[0];
-        let order1 = SortOrder::from_i32(sort_field1.sort_order).unwrap_or(SortOrder::Desc);
-        let sort_field2 = &search_request.sort_fields[1];
-        let order2 = SortOrder::from_i32(sort_field2.sort_order).unwrap_or(SortOrder::Desc);
-        SortByPair {
-            first: to_sort_by_component(&sort_field1.field_name, order1),
-            second: Some(to_sort_by_component(&sort_field2.field_name, order2)),
-        }
-    } else {
-        panic!("Sort by more than 2 fields is not supported yet.")
+        .into();
+    };
+    SortBy {
+        first,
+        second: sort_by_components.next(),
+        additional: sort_by_components.collect(),
     }
 }
 
 /// Builds the QuickwitCollector, in function of the information that was requested by the user.
+///
+/// The queries of the `filters` aggregations are built against the schema of the split.
 pub(crate) fn make_collector_for_split(
     split_id: String,
     doc_mapper: &dyn DocMapper,
+    split_schema: Schema,
     search_request: &SearchRequest,
     aggregation_limits: AggregationLimits,
 ) -> crate::Result<QuickwitCollector> {
-    let aggregation = match &search_request.aggregation_request {
+    let mut aggregation = match &search_request.aggregation_request {
         Some(aggregation) => Some(serde_json::from_str(aggregation)?),
         None => None,
     };
+    if let Some(QuickwitAggregations::ExtendedAggregations(aggregations)) = &mut aggregation {
+        aggregations.build_filter_queries(doc_mapper, split_schema, false, Some(&split_id))?;
+    }
     let timestamp_filter_builder_opt = create_timestamp_filter_builder(
         doc_mapper.timestamp_field_name(),
         search_request.start_timestamp,
@@ -904,10 +1248,57 @@ pub(crate) fn make_merge_collector(
     })
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Sort values beyond the second one, along with their sort order.
+///
+/// Missing trailing sort values are stored as `None`, so that two keys built from the same sort
+/// orders always have the same length. The key is empty, and does not allocate, when sorting by at
+/// most two fields.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct AdditionalSortKey(Vec<(SortOrder, Option<SortValue>)>);
+
+impl AdditionalSortKey {
+    fn new(
+        sort_orders: &[SortOrder],
+        sort_values: impl Iterator<Item = Option<SortValue>>,
+    ) -> Self {
+        let sort_key = sort_orders
+            .iter()
+            .copied()
+            .zip(sort_values.chain(std::iter::repeat(None)))
+            .collect();
+        AdditionalSortKey(sort_key)
+    }
+}
+
+impl Ord for AdditionalSortKey {
+    fn cmp(&self, other: &AdditionalSortKey) -> Ordering {
+        for ((sort_order, sort_value), (other_sort_order, other_sort_value)) in
+            self.0.iter().zip(other.0.iter())
+        {
+            debug_assert_eq!(
+                sort_order, other_sort_order,
+                "comparing two AdditionalSortKey of different ordering"
+            );
+            let order = sort_order.compare_opt(sort_value, other_sort_value);
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl PartialOrd for AdditionalSortKey {
+    fn partial_cmp(&self, other: &AdditionalSortKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 struct SegmentPartialHitSortingKey {
     sort_value: Option<SortValue>,
     sort_value2: Option<SortValue>,
+    additional_sort_key: AdditionalSortKey,
     doc_id: DocId,
     // TODO This should not be there.
     sort_order: SortOrder,
@@ -932,7 +1323,10 @@ impl Ord for SegmentPartialHitSortingKey {
             .sort_order2
             .compare_opt(&self.sort_value2, &other.sort_value2);
         let order_addr = self.sort_order.compare(&self.doc_id, &other.doc_id);
-        order.then(order2).then(order_addr)
+        order
+            .then(order2)
+            .then_with(|| self.additional_sort_key.cmp(&other.additional_sort_key))
+            .then(order_addr)
     }
 }
 
@@ -946,6 +1340,7 @@ impl PartialOrd for SegmentPartialHitSortingKey {
 struct PartialHitSortingKey {
     sort_value: Option<SortValue>,
     sort_value2: Option<SortValue>,
+    additional_sort_key: AdditionalSortKey,
     address: GlobalDocAddress,
     // TODO remove this
     sort_order: SortOrder,
@@ -973,7 +1368,10 @@ impl Ord for PartialHitSortingKey {
 
         let order_addr = self.sort_order.compare(&self.address, &other.address);
 
-        order.then(order2).then(order_addr)
+        order
+            .then(order2)
+            .then_with(|| self.additional_sort_key.cmp(&other.additional_sort_key))
+            .then(order_addr)
     }
 }
 
@@ -987,14 +1385,28 @@ impl PartialOrd for PartialHitSortingKey {
 struct HitSortingMapper {
     order1: SortOrder,
     order2: SortOrder,
+    additional_orders: Vec<SortOrder>,
 }
 
 impl SortKeyMapper<PartialHit> for HitSortingMapper {
     type Key = PartialHitSortingKey;
     fn get_sort_key(&self, partial_hit: &PartialHit) -> PartialHitSortingKey {
         PartialHitSortingKey {
-            sort_value: partial_hit.sort_value.and_then(|v| v.sort_value),
-            sort_value2: partial_hit.sort_value2.and_then(|v| v.sort_value),
+            sort_value: partial_hit
+                .sort_value
+                .as_ref()
+                .and_then(|v| v.sort_value.clone()),
+            sort_value2: partial_hit
+                .sort_value2
+                .as_ref()
+                .and_then(|v| v.sort_value.clone()),
+            additional_sort_key: AdditionalSortKey::new(
+                &self.additional_orders,
+                partial_hit
+                    .additional_sort_values
+                    .iter()
+                    .map(|sort_by_value| sort_by_value.sort_value.clone()),
+            ),
             address: GlobalDocAddress::from_partial_hit(partial_hit),
             sort_order: self.order1,
             sort_order2: self.order2,
@@ -1006,8 +1418,12 @@ impl SortKeyMapper<SegmentPartialHit> for HitSortingMapper {
     type Key = SegmentPartialHitSortingKey;
     fn get_sort_key(&self, partial_hit: &SegmentPartialHit) -> SegmentPartialHitSortingKey {
         SegmentPartialHitSortingKey {
-            sort_value: partial_hit.sort_value,
-            sort_value2: partial_hit.sort_value2,
+            sort_value: partial_hit.sort_value.clone(),
+            sort_value2: partial_hit.sort_value2.clone(),
+            additional_sort_key: AdditionalSortKey::new(
+                &self.additional_orders,
+                partial_hit.additional_sort_values.iter().cloned(),
+            ),
             doc_id: partial_hit.doc_id,
             sort_order: self.order1,
             sort_order2: self.order2,
@@ -1034,8 +1450,7 @@ impl IncrementalCollector {
             .as_ref()
             .map(QuickwitAggregations::maybe_incremental_aggregator)
             .unwrap_or(QuickwitIncrementalAggregations::NoAggregation);
-        let (order1, order2) = inner.sort_by.sort_orders();
-        let sort_key_mapper = HitSortingMapper { order1, order2 };
+        let sort_key_mapper = inner.sort_by.sort_key_mapper();
         IncrementalCollector {
             top_k_hits: TopK::new(inner.max_hits + inner.start_offset, sort_key_mapper),
             inner,
@@ -1116,6 +1531,7 @@ mod tests {
         SortValue, SplitSearchError,
     };
     use tantivy::collector::Collector;
+    use tantivy::schema::Schema;
     use tantivy::TantivyDocument;
 
     use super::{make_merge_collector, IncrementalCollector, PartialHitHeapItem};
@@ -1169,6 +1585,7 @@ mod tests {
         let make_doc = |sort_value: u64| PartialHit {
             sort_value: Some(SortValue::U64(sort_value).into()),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: "split1".to_string(),
             segment_ord: 0u32,
             doc_id: 0u32,
@@ -1176,8 +1593,11 @@ mod tests {
         assert_eq!(
             top_k_partial_hits(
                 vec![make_doc(1u64), make_doc(3u64), make_doc(2u64),].into_iter(),
-                SortOrder::Asc,
-                SortOrder::Asc,
+                HitSortingMapper {
+                    order1: SortOrder::Asc,
+                    order2: SortOrder::Asc,
+                    additional_orders: Vec::new(),
+                },
                 2
             ),
             vec![make_doc(1), make_doc(2)]
@@ -1189,6 +1609,7 @@ mod tests {
         let make_hit_given_split_id = |split_id: u64| PartialHit {
             sort_value: Some(SortValue::U64(0u64).into()),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: format!("split_{split_id}"),
             segment_ord: 0u32,
             doc_id: 0u32,
@@ -1201,8 +1622,11 @@ mod tests {
                     make_hit_given_split_id(2u64),
                 ]
                 .into_iter(),
-                SortOrder::Desc,
-                SortOrder::Desc,
+                HitSortingMapper {
+                    order1: SortOrder::Desc,
+                    order2: SortOrder::Desc,
+                    additional_orders: Vec::new(),
+                },
                 2
             ),
             &[make_hit_given_split_id(3), make_hit_given_split_id(2)]
@@ -1215,8 +1639,11 @@ mod tests {
                     make_hit_given_split_id(2u64),
                 ]
                 .into_iter(),
-                SortOrder::Asc,
-                SortOrder::Asc,
+                HitSortingMapper {
+                    order1: SortOrder::Asc,
+                    order2: SortOrder::Asc,
+                    additional_orders: Vec::new(),
+                },
                 2
             ),
             &[make_hit_given_split_id(1), make_hit_given_split_id(2)]
@@ -1250,6 +1677,7 @@ mod tests {
             _split_schema: tantivy::schema::Schema,
             _query_ast: &quickwit_query::query_ast::QueryAst,
             _with_validation: bool,
+            _split_id_opt: Option<&str>,
         ) -> Result<
             (
                 Box<dyn tantivy::query::Query>,
@@ -1481,6 +1909,7 @@ mod tests {
                 let collector = super::make_collector_for_split(
                     "fake_split_id".to_string(),
                     &MockDocMapper,
+                    Schema::builder().build(),
                     &make_request(len as u64, sort_str),
                     Default::default(),
                 )
@@ -1531,6 +1960,7 @@ mod tests {
                 sort_value2: Some(SortByValue {
                     sort_value: val2.map(SortValue::U64),
                 }),
+                additional_sort_values: Vec::new(),
             })
             .collect::<Vec<_>>();
         // we eliminte based on sort value
@@ -1555,6 +1985,7 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id".to_string(),
                 &MockDocMapper,
+                Schema::builder().build(),
                 &request,
                 Default::default(),
             )
@@ -1579,6 +2010,7 @@ mod tests {
                 doc_id: 5,
                 sort_value: None,
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             };
             let request = SearchRequest {
                 max_hits: 1000,
@@ -1594,6 +2026,7 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id1".to_string(),
                 &MockDocMapper,
+                Schema::builder().build(),
                 &request,
                 Default::default(),
             )
@@ -1609,6 +2042,7 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id2".to_string(),
                 &MockDocMapper,
+                Schema::builder().build(),
                 &request,
                 Default::default(),
             )
@@ -1623,6 +2057,7 @@ mod tests {
             let collector = super::make_collector_for_split(
                 "fake_split_id3".to_string(),
                 &MockDocMapper,
+                Schema::builder().build(),
                 &request,
                 Default::default(),
             )
@@ -1637,6 +2072,206 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sort_by_more_than_two_fields() {
+        use tantivy::indexer::UserOperation;
+        use tantivy::schema::{NumericOptions, Schema};
+        use tantivy::Index;
+
+        // Values of the fields `sort_u64`, `sort_i64` and `sort_f64`. The first two fields have
+        // many ties, so that the ordering of the hits depends on the third one.
+        let dataset: Vec<(u64, i64, Option<f64>)> = vec![
+            (1, -1, Some(0.5)),
+            (1, -1, Some(1.5)),
+            (2, 3, None),
+            (1, 2, Some(-0.5)),
+            (2, 3, Some(2.5)),
+            (1, -1, None),
+            (2, -4, Some(0.5)),
+            (2, 3, Some(-2.5)),
+        ];
+        let mut schema_builder = Schema::builder();
+        let opts = NumericOptions::default().set_fast();
+        let u64_field = schema_builder.add_u64_field("sort_u64", opts.clone());
+        let i64_field = schema_builder.add_i64_field("sort_i64", opts.clone());
+        let f64_field = schema_builder.add_f64_field("sort_f64", opts);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer(50_000_000).unwrap();
+        index_writer
+            .run(dataset.iter().map(|(u64_val, i64_val, f64_val_opt)| {
+                let mut doc = TantivyDocument::new();
+                doc.add_u64(u64_field, *u64_val);
+                doc.add_i64(i64_field, *i64_val);
+                if let Some(f64_val) = f64_val_opt {
+                    doc.add_f64(f64_field, *f64_val);
+                }
+                UserOperation::Add(doc)
+            }))
+            .unwrap();
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let sort_str = "sort_u64,-sort_i64,sort_f64";
+        let expected_doc_ids: Vec<u32> = vec![6, 4, 7, 2, 1, 0, 5, 3];
+
+        for len in 1..=dataset.len() {
+            let collector = super::make_collector_for_split(
+                "fake_split_id".to_string(),
+                &MockDocMapper,
+                Schema::builder().build(),
+                &make_request(len as u64, sort_str),
+                Default::default(),
+            )
+            .unwrap();
+            let res = searcher
+                .search(&tantivy::query::AllQuery, &collector)
+                .unwrap();
+            let doc_ids: Vec<u32> = res.partial_hits.iter().map(|hit| hit.doc_id).collect();
+            assert_eq!(doc_ids, &expected_doc_ids[..len]);
+        }
+
+        let collector = super::make_collector_for_split(
+            "fake_split_id".to_string(),
+            &MockDocMapper,
+            Schema::builder().build(),
+            &make_request(dataset.len() as u64, sort_str),
+            Default::default(),
+        )
+        .unwrap();
+        let partial_hits = searcher
+            .search(&tantivy::query::AllQuery, &collector)
+            .unwrap()
+            .partial_hits;
+        assert_eq!(
+            partial_hits[0].additional_sort_values,
+            vec![SortByValue {
+                sort_value: Some(SortValue::F64(0.5))
+            }]
+        );
+        // Trailing missing sort values are omitted.
+        assert!(partial_hits[3].additional_sort_values.is_empty());
+
+        // The search after values of each hit exclude this hit and all the ones before it.
+        for (i, partial_hit) in partial_hits.iter().enumerate() {
+            let search_after = PartialHit {
+                split_id: String::new(),
+                segment_ord: 0,
+                doc_id: 0,
+                ..partial_hit.clone()
+            };
+            let request = SearchRequest {
+                search_after: Some(search_after),
+                ..make_request(dataset.len() as u64, sort_str)
+            };
+            let collector = super::make_collector_for_split(
+                "fake_split_id".to_string(),
+                &MockDocMapper,
+                Schema::builder().build(),
+                &request,
+                Default::default(),
+            )
+            .unwrap();
+            let res = searcher
+                .search(&tantivy::query::AllQuery, &collector)
+                .unwrap();
+            let doc_ids: Vec<u32> = res.partial_hits.iter().map(|hit| hit.doc_id).collect();
+            assert_eq!(doc_ids, &expected_doc_ids[i + 1..]);
+        }
+
+        // Merging hits coming from different splits also takes the third sort value into
+        // account.
+        let split_hits = partial_hits
+            .iter()
+            .enumerate()
+            .map(|(i, partial_hit)| PartialHit {
+                split_id: format!("split_{}", i % 3),
+                ..partial_hit.clone()
+            })
+            .rev();
+        let merged_hits = top_k_partial_hits(
+            split_hits,
+            HitSortingMapper {
+                order1: SortOrder::Desc,
+                order2: SortOrder::Asc,
+                additional_orders: vec![SortOrder::Desc],
+            },
+            4,
+        );
+        let doc_ids: Vec<u32> = merged_hits.iter().map(|hit| hit.doc_id).collect();
+        assert_eq!(doc_ids, &expected_doc_ids[..4]);
+    }
+
+    #[test]
+    fn test_sort_by_str_fast_field() {
+        use tantivy::schema::{Schema, FAST};
+        use tantivy::Index;
+
+        let mut schema_builder = Schema::builder();
+        let host_field = schema_builder.add_text_field("host", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer(50_000_000).unwrap();
+        for host_opt in [
+            Some("host-3"),
+            Some("host-1"),
+            None,
+            Some("host-2"),
+            Some("host-1"),
+        ] {
+            let mut doc = TantivyDocument::new();
+            if let Some(host) = host_opt {
+                doc.add_text(host_field, host);
+            }
+            index_writer.add_document(doc).unwrap();
+        }
+        index_writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        let search = |sort_str: &str, search_after_opt: Option<&str>| -> Vec<PartialHit> {
+            let mut request = make_request(10, sort_str);
+            request.search_after = search_after_opt.map(|search_after| PartialHit {
+                sort_value: Some(SortValue::Str(search_after.to_string()).into()),
+                ..Default::default()
+            });
+            let collector = super::make_collector_for_split(
+                "fake_split_id".to_string(),
+                &MockDocMapper,
+                Schema::builder().build(),
+                &request,
+                Default::default(),
+            )
+            .unwrap();
+            searcher
+                .search(&tantivy::query::AllQuery, &collector)
+                .unwrap()
+                .partial_hits
+        };
+        let doc_ids = |partial_hits: Vec<PartialHit>| -> Vec<u32> {
+            partial_hits.iter().map(|hit| hit.doc_id).collect()
+        };
+
+        let partial_hits = search("-host", None);
+        assert_eq!(
+            partial_hits[0].sort_value(),
+            Some(SortValue::Str("host-1".to_string()))
+        );
+        assert_eq!(
+            partial_hits[3].sort_value(),
+            Some(SortValue::Str("host-3".to_string()))
+        );
+        assert_eq!(partial_hits[4].sort_value(), None);
+        assert_eq!(doc_ids(partial_hits), vec![1, 4, 3, 0, 2]);
+        assert_eq!(doc_ids(search("host", None)), vec![0, 3, 4, 1, 2]);
+
+        // `search_after` values present in the segment dictionary.
+        assert_eq!(doc_ids(search("-host", Some("host-2"))), vec![0, 2]);
+        assert_eq!(doc_ids(search("host", Some("host-2"))), vec![4, 1, 2]);
+        // `search_after` values absent from the segment dictionary.
+        assert_eq!(doc_ids(search("-host", Some("host-15"))), vec![3, 0, 2]);
+        assert_eq!(doc_ids(search("host", Some("host-15"))), vec![4, 1, 2]);
+        assert_eq!(doc_ids(search("-host", Some("a"))), vec![1, 4, 3, 0, 2]);
+        assert_eq!(doc_ids(search("-host", Some("z"))), vec![2]);
+    }
+
     fn merge_collector_equal_results(
         request: &SearchRequest,
         results: Vec<LeafSearchResponse>,
@@ -1679,6 +2314,7 @@ mod tests {
                     doc_id: 123,
                     sort_value: Some(SortValue::I64(1234).into()),
                     sort_value2: None,
+                    additional_sort_values: Vec::new(),
                 }],
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
@@ -1696,6 +2332,7 @@ mod tests {
                     doc_id: 123,
                     sort_value: Some(SortValue::I64(1234).into()),
                     sort_value2: None,
+                    additional_sort_values: Vec::new(),
                 }],
                 failed_splits: Vec::new(),
                 num_attempted_splits: 3,
@@ -1725,6 +2362,7 @@ mod tests {
                             doc_id: 123,
                             sort_value: Some(SortValue::I64(1234).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                         },
                         PartialHit {
                             split_id: "1".to_string(),
@@ -1732,6 +2370,7 @@ mod tests {
                             doc_id: 125,
                             sort_value: Some(SortValue::I64(1236).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                         },
                     ],
                     failed_splits: Vec::new(),
@@ -1746,6 +2385,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        additional_sort_values: Vec::new(),
                     }],
                     failed_splits: vec![SplitSearchError {
                         error: "fake error".to_string(),
@@ -1769,6 +2409,7 @@ mod tests {
                         doc_id: 125,
                         sort_value: Some(SortValue::I64(1236).into()),
                         sort_value2: None,
+                        additional_sort_values: Vec::new(),
                     },
                     PartialHit {
                         split_id: "2".to_string(),
@@ -1776,6 +2417,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        additional_sort_values: Vec::new(),
                     },
                 ],
                 failed_splits: vec![SplitSearchError {
@@ -1811,6 +2453,7 @@ mod tests {
                             doc_id: 123,
                             sort_value: Some(SortValue::I64(1234).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                         },
                         PartialHit {
                             split_id: "1".to_string(),
@@ -1818,6 +2461,7 @@ mod tests {
                             doc_id: 125,
                             sort_value: Some(SortValue::I64(1236).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                         },
                     ],
                     failed_splits: Vec::new(),
@@ -1832,6 +2476,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        additional_sort_values: Vec::new(),
                     }],
                     failed_splits: vec![SplitSearchError {
                         error: "fake error".to_string(),
@@ -1855,6 +2500,7 @@ mod tests {
                         doc_id: 123,
                         sort_value: Some(SortValue::I64(1234).into()),
                         sort_value2: None,
+                        additional_sort_values: Vec::new(),
                     },
                     PartialHit {
                         split_id: "2".to_string(),
@@ -1862,6 +2508,7 @@ mod tests {
                         doc_id: 3,
                         sort_value: Some(SortValue::I64(1235).into()),
                         sort_value2: None,
+                        additional_sort_values: Vec::new(),
                     },
                 ],
                 failed_splits: vec![SplitSearchError {
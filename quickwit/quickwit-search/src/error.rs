@@ -17,6 +17,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use arrow::error::ArrowError;
+use parquet::errors::ParquetError;
 use quickwit_doc_mapper::QueryParserError;
 use quickwit_proto::metastore::{EntityKind, MetastoreError};
 use quickwit_proto::{tonic, ServiceError, ServiceErrorCode};
@@ -40,6 +42,8 @@ pub enum SearchError {
     InvalidArgument(String),
     #[error("{0}")]
     InvalidQuery(String),
+    #[error("could not find point in time `{point_in_time_id}`")]
+    PointInTimeNotFound { point_in_time_id: String },
     #[error("storage not found: `{0}`)")]
     StorageResolver(#[from] StorageResolverError),
 }
@@ -52,6 +56,7 @@ impl ServiceError for SearchError {
             SearchError::InvalidAggregationRequest(_) => ServiceErrorCode::BadRequest,
             SearchError::InvalidArgument(_) => ServiceErrorCode::BadRequest,
             SearchError::InvalidQuery(_) => ServiceErrorCode::BadRequest,
+            SearchError::PointInTimeNotFound { .. } => ServiceErrorCode::NotFound,
             SearchError::StorageResolver(_) => ServiceErrorCode::BadRequest,
         }
     }
@@ -76,6 +81,18 @@ impl From<TantivyError> for SearchError {
     }
 }
 
+impl From<ArrowError> for SearchError {
+    fn from(error: ArrowError) -> Self {
+        SearchError::Internal(format!("Arrow error: {error}"))
+    }
+}
+
+impl From<ParquetError> for SearchError {
+    fn from(error: ParquetError) -> Self {
+        SearchError::Internal(format!("Parquet error: {error}"))
+    }
+}
+
 impl From<postcard::Error> for SearchError {
     fn from(error: postcard::Error) -> Self {
         SearchError::Internal(format!("Postcard error: {error}"))
@@ -111,6 +128,9 @@ impl From<MetastoreError> for SearchError {
             MetastoreError::NotFound(EntityKind::Indexes { index_ids }) => {
                 SearchError::IndexesNotFound { index_ids }
             }
+            MetastoreError::NotFound(EntityKind::PointInTime { point_in_time_id }) => {
+                SearchError::PointInTimeNotFound { point_in_time_id }
+            }
             _ => SearchError::Internal(metastore_error.to_string()),
         }
     }
@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::cmp::Reverse;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
@@ -25,12 +26,13 @@ use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::search::{
-    FetchDocsResponse, PartialHit, SnippetRequest, SplitIdAndFooterOffsets,
+    FetchDocsResponse, PartialHit, SnippetEncoder, SnippetOptions, SnippetRequest, SourceFilter,
+    SplitIdAndFooterOffsets,
 };
 use quickwit_storage::Storage;
 use tantivy::query::Query;
 use tantivy::schema::{Document as DocumentTrait, Field, OwnedValue, TantivyDocument, Value};
-use tantivy::{ReloadPolicy, Score, Searcher, SnippetGenerator, Term};
+use tantivy::{ReloadPolicy, Score, Searcher, Snippet, SnippetGenerator, Term};
 use tracing::{error, Instrument};
 
 use crate::leaf::open_index_with_caches;
@@ -48,6 +50,7 @@ async fn fetch_docs_to_map(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    source_filter_opt: Option<&SourceFilter>,
 ) -> anyhow::Result<HashMap<GlobalDocAddress, Document>> {
     let mut split_fetch_docs_futures = Vec::new();
 
@@ -75,6 +78,7 @@ async fn fetch_docs_to_map(
             split_and_offset,
             doc_mapper.clone(),
             snippet_request_opt,
+            source_filter_opt,
         ));
     }
 
@@ -115,6 +119,7 @@ pub async fn fetch_docs(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    source_filter_opt: Option<&SourceFilter>,
 ) -> anyhow::Result<FetchDocsResponse> {
     let global_doc_addrs: Vec<GlobalDocAddress> = partial_hits
         .iter()
@@ -128,6 +133,7 @@ pub async fn fetch_docs(
         splits,
         doc_mapper,
         snippet_request_opt,
+        source_filter_opt,
     )
     .await?;
 
@@ -168,6 +174,7 @@ async fn fetch_docs_in_split(
     split: &SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request_opt: Option<&SnippetRequest>,
+    source_filter_opt: Option<&SourceFilter>,
 ) -> anyhow::Result<Vec<(GlobalDocAddress, Document)>> {
     global_doc_addrs.sort_by_key(|doc| doc.doc_addr);
     // Opens the index without the ephemeral unbounded cache, this cache is indeed not useful
@@ -189,7 +196,15 @@ async fn fetch_docs_in_split(
         .try_into()?;
     let searcher = Arc::new(index_reader.searcher());
     let fields_snippet_generator_opt = if let Some(snippet_request) = snippet_request_opt {
-        Some(create_fields_snippet_generator(&searcher, doc_mapper.clone(), snippet_request).await?)
+        Some(
+            create_fields_snippet_generator(
+                &searcher,
+                &split.split_id,
+                doc_mapper.clone(),
+                snippet_request,
+            )
+            .await?,
+        )
     } else {
         None
     };
@@ -205,8 +220,11 @@ async fn fetch_docs_in_split(
                 .context("searcher-doc-async")?;
 
             let named_field_doc = doc.to_named_doc(moved_searcher.schema());
-            let content_json =
-                convert_document_to_json_string(named_field_doc, &*moved_doc_mapper)?;
+            let content_json = convert_document_to_json_string(
+                named_field_doc,
+                &*moved_doc_mapper,
+                source_filter_opt,
+            )?;
             if fields_snippet_generator_opt_clone.is_none() {
                 return Ok((
                     global_doc_addr,
@@ -260,6 +278,8 @@ async fn fetch_docs_in_split(
 #[derive(Clone)]
 struct FieldsSnippetGenerator {
     field_generators: Arc<HashMap<String, SnippetGenerator>>,
+    snippet_options: Arc<SnippetOptions>,
+    max_num_chars: usize,
 }
 
 impl FieldsSnippetGenerator {
@@ -269,23 +289,44 @@ impl FieldsSnippetGenerator {
         field_name: &str,
         field_values: Vec<&OwnedValue>,
     ) -> Option<Vec<String>> {
-        if let Some(snippet_generator) = self.field_generators.get(field_name) {
-            let values = field_values
-                .into_iter()
-                .filter_map(|value| {
-                    value.as_str().and_then(|text| {
-                        let snippet = snippet_generator.snippet(text);
-                        match snippet.is_empty() {
-                            false => Some(snippet.to_html()),
-                            _ => None,
-                        }
-                    })
-                })
-                .collect();
-            Some(values)
-        } else {
-            None
-        }
+        let snippet_generator = self.field_generators.get(field_name)?;
+        let values = field_values
+            .into_iter()
+            .filter_map(|value| value.as_str())
+            .flat_map(|text| self.snippets_from_text(snippet_generator, text))
+            .collect();
+        Some(values)
+    }
+
+    // Returns the snippets of a single field value: up to `number_of_fragments` fragments (one by
+    // default), or the whole highlighted value if `number_of_fragments` is 0.
+    fn snippets_from_text(&self, snippet_generator: &SnippetGenerator, text: &str) -> Vec<String> {
+        let max_num_fragments = match self.snippet_options.number_of_fragments {
+            Some(0) => {
+                let snippet = snippet_generator.snippet(text);
+                if snippet.is_empty() {
+                    return Vec::new();
+                }
+                return vec![render_whole_value(&snippet, text, &self.snippet_options)];
+            }
+            Some(number_of_fragments) => number_of_fragments as usize,
+            None => 1,
+        };
+        let mut snippets: Vec<(usize, Snippet)> = split_into_fragments(text, self.max_num_chars)
+            .map(|fragment| snippet_generator.snippet(fragment))
+            .enumerate()
+            .filter(|(_, snippet)| !snippet.is_empty())
+            .collect();
+        // Keep the fragments highlighting the most terms, in the order they appear in the text.
+        snippets.sort_by_key(|(fragment_ord, snippet)| {
+            (Reverse(snippet.highlighted().len()), *fragment_ord)
+        });
+        snippets.truncate(max_num_fragments);
+        snippets.sort_by_key(|(fragment_ord, _)| *fragment_ord);
+        snippets
+            .iter()
+            .map(|(_, snippet)| render_snippet(snippet, &self.snippet_options))
+            .collect()
     }
 
     fn is_empty(&self) -> bool {
@@ -296,25 +337,136 @@ impl FieldsSnippetGenerator {
 // Creates FieldsSnippetGenerator.
 async fn create_fields_snippet_generator(
     searcher: &Searcher,
+    split_id: &str,
     doc_mapper: Arc<dyn DocMapper>,
     snippet_request: &SnippetRequest,
 ) -> anyhow::Result<FieldsSnippetGenerator> {
     let schema = searcher.schema();
     let query_ast_resolved = serde_json::from_str(&snippet_request.query_ast_resolved)
         .context("failed to deserialize QueryAst")?;
-    let (query, _) = doc_mapper.query(schema.clone(), &query_ast_resolved, false)?;
+    let (query, _) =
+        doc_mapper.query(schema.clone(), &query_ast_resolved, false, Some(split_id))?;
+    let snippet_options = snippet_request.snippet_options.clone().unwrap_or_default();
+    let max_num_chars = if snippet_options.number_of_fragments == Some(0) {
+        // Highlighting the whole field value boils down to using a single unbounded fragment.
+        usize::MAX
+    } else {
+        snippet_options
+            .fragment_size
+            .map(|fragment_size| fragment_size as usize)
+            .unwrap_or(SNIPPET_MAX_NUM_CHARS)
+    };
     let mut snippet_generators = HashMap::new();
     for field_name in &snippet_request.snippet_fields {
-        let field = schema.get_field(field_name)?;
-        let snippet_generator = create_snippet_generator(searcher, &query, field).await?;
+        // Splits built with an older doc mapping may not contain the field.
+        let Ok(field) = schema.get_field(field_name) else {
+            continue;
+        };
+        let mut snippet_generator = create_snippet_generator(searcher, &query, field).await?;
+        snippet_generator.set_max_num_chars(max_num_chars);
         snippet_generators.insert(field_name.clone(), snippet_generator);
     }
 
     Ok(FieldsSnippetGenerator {
         field_generators: Arc::new(snippet_generators),
+        snippet_options: Arc::new(snippet_options),
+        max_num_chars,
+    })
+}
+
+/// Splits a text into consecutive fragments of at most `max_num_chars` bytes. Fragments are cut
+/// after a whitespace whenever possible, so that words are not split across fragments.
+fn split_into_fragments(text: &str, max_num_chars: usize) -> impl Iterator<Item = &str> {
+    let mut remaining_text = text;
+    std::iter::from_fn(move || {
+        if remaining_text.is_empty() {
+            return None;
+        }
+        if remaining_text.len() <= max_num_chars {
+            return Some(std::mem::take(&mut remaining_text));
+        }
+        let mut fragment_len = max_num_chars;
+        while !remaining_text.is_char_boundary(fragment_len) {
+            fragment_len -= 1;
+        }
+        if let Some((whitespace_pos, whitespace)) = remaining_text[..fragment_len]
+            .char_indices()
+            .rev()
+            .find(|(_, character)| character.is_whitespace())
+        {
+            fragment_len = whitespace_pos + whitespace.len_utf8();
+        }
+        if fragment_len == 0 {
+            fragment_len = remaining_text
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or_default();
+        }
+        let (fragment, rest) = remaining_text.split_at(fragment_len);
+        remaining_text = rest;
+        Some(fragment)
     })
 }
 
+const DEFAULT_PRE_TAG: &str = "<b>";
+const DEFAULT_POST_TAG: &str = "</b>";
+
+/// Renders a snippet generated without any limit on its number of characters, followed by the
+/// rest of the field value.
+///
+/// Such a snippet starts at the beginning of the value and ends with its last token.
+fn render_whole_value(snippet: &Snippet, text: &str, snippet_options: &SnippetOptions) -> String {
+    let trailing_text = text.get(snippet.fragment().len()..).unwrap_or_default();
+    let mut rendered = render_snippet(snippet, snippet_options);
+    push_encoded_text(&mut rendered, trailing_text, snippet_options.encoder());
+    rendered
+}
+
+/// Renders a snippet, wrapping the highlighted terms with the requested tags (`<b>` and `</b>` by
+/// default).
+///
+/// The text of the snippet is HTML-escaped with the `html` encoder and returned as is with the
+/// `raw` encoder. The tags are never escaped.
+fn render_snippet(snippet: &Snippet, snippet_options: &SnippetOptions) -> String {
+    let pre_tag = snippet_options
+        .pre_tag
+        .as_deref()
+        .unwrap_or(DEFAULT_PRE_TAG);
+    let post_tag = snippet_options
+        .post_tag
+        .as_deref()
+        .unwrap_or(DEFAULT_POST_TAG);
+    let encoder = snippet_options.encoder();
+    let fragment = snippet.fragment();
+    let mut rendered = String::with_capacity(fragment.len());
+    let mut start_from = 0;
+    for highlighted_range in snippet.highlighted() {
+        // Skip the ranges overlapping an already highlighted one.
+        if highlighted_range.start < start_from {
+            continue;
+        }
+        push_encoded_text(
+            &mut rendered,
+            &fragment[start_from..highlighted_range.start],
+            encoder,
+        );
+        rendered.push_str(pre_tag);
+        push_encoded_text(&mut rendered, &fragment[highlighted_range.clone()], encoder);
+        rendered.push_str(post_tag);
+        start_from = highlighted_range.end;
+    }
+    push_encoded_text(&mut rendered, &fragment[start_from..], encoder);
+    rendered
+}
+
+fn push_encoded_text(rendered: &mut String, text: &str, encoder: SnippetEncoder) {
+    match encoder {
+        SnippetEncoder::Html => rendered.push_str(&htmlescape::encode_minimal(text)),
+        SnippetEncoder::Raw => rendered.push_str(text),
+    }
+}
+
 // Creates a snippet generator associated to a field.
 async fn create_snippet_generator(
     searcher: &Searcher,
@@ -356,12 +356,14 @@ async fn leaf_search_single_split(
     let quickwit_collector = make_collector_for_split(
         split_id.clone(),
         doc_mapper.as_ref(),
+        split_schema.clone(),
         &search_request,
         searcher_context.get_aggregation_limits(),
     )?;
     let query_ast: QueryAst = serde_json::from_str(search_request.query_ast.as_str())
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-    let (query, mut warmup_info) = doc_mapper.query(split_schema, &query_ast, false)?;
+    let (query, mut warmup_info) =
+        doc_mapper.query(split_schema, &query_ast, false, Some(&split_id))?;
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
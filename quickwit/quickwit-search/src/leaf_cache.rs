@@ -246,6 +246,7 @@ mod tests {
                 segment_ord: 0,
                 sort_value: Some(SortValue::U64(0u64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
                 split_id: "split_1".to_string(),
             }],
         };
@@ -332,6 +333,7 @@ mod tests {
                 segment_ord: 0,
                 sort_value: Some(SortValue::U64(0).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
                 split_id: "split_1".to_string(),
             }],
         };
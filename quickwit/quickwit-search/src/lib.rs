@@ -22,6 +22,7 @@
 #![allow(clippy::bool_assert_comparison)]
 #![deny(clippy::disallowed_methods)]
 
+mod aggregations;
 mod client;
 mod cluster_client;
 mod collector;
@@ -34,6 +35,7 @@ mod leaf_cache;
 mod list_fields;
 mod list_fields_cache;
 mod list_terms;
+mod point_in_time;
 mod retry;
 mod root;
 mod scroll_context;
@@ -41,6 +43,7 @@ mod search_job_placer;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod source_filter;
 mod thread_pool;
 
 mod metrics;
@@ -48,6 +51,7 @@ mod metrics;
 #[cfg(test)]
 mod tests;
 
+pub use aggregations::ExtendedAggregations;
 pub use collector::QuickwitAggregations;
 use metrics::SEARCH_METRICS;
 use quickwit_common::tower::Pool;
@@ -70,7 +74,9 @@ use quickwit_metastore::{
     IndexMetadata, ListIndexesMetadataResponseExt, ListSplitsQuery, ListSplitsRequestExt,
     MetastoreServiceStreamSplitsExt, SplitMetadata, SplitState,
 };
-use quickwit_proto::search::{PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets};
+use quickwit_proto::search::{
+    PartialHit, SearchRequest, SearchResponse, SourceFilter, SplitIdAndFooterOffsets,
+};
 use quickwit_proto::types::IndexUid;
 use quickwit_storage::StorageResolver;
 pub use service::SearcherContext;
@@ -83,6 +89,7 @@ pub use crate::cluster_client::ClusterClient;
 pub use crate::error::{parse_grpc_error, SearchError};
 use crate::fetch_docs::fetch_docs;
 use crate::leaf::leaf_search;
+pub use crate::point_in_time::{close_point_in_time, open_point_in_time};
 pub use crate::root::{
     check_all_index_metadata_found, jobs_to_leaf_requests, root_search, IndexMetasForLeafSearch,
     SearchJob,
@@ -91,6 +98,7 @@ pub use crate::search_job_placer::{Job, SearchJobPlacer};
 pub use crate::search_response_rest::SearchResponseRest;
 pub use crate::search_stream::root_search_stream;
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+use crate::source_filter::filter_source;
 use crate::thread_pool::run_cpu_intensive;
 
 /// A pool of searcher clients identified by their gRPC socket address.
@@ -188,8 +196,20 @@ pub async fn list_relevant_splits(
     tags_filter_opt: Option<TagFilterAst>,
     metastore: &mut MetastoreServiceClient,
 ) -> crate::Result<Vec<SplitMetadata>> {
-    let mut query =
-        ListSplitsQuery::try_from_index_uids(index_uids)?.with_split_state(SplitState::Published);
+    let query = relevant_splits_query(index_uids, start_timestamp, end_timestamp, tags_filter_opt)?
+        .with_split_state(SplitState::Published);
+    list_splits(query, metastore).await
+}
+
+/// Builds the query listing the splits of the given indexes that match the time range and tags
+/// of a request. The split states are left to the caller.
+fn relevant_splits_query(
+    index_uids: Vec<IndexUid>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+) -> crate::Result<ListSplitsQuery> {
+    let mut query = ListSplitsQuery::try_from_index_uids(index_uids)?;
 
     if let Some(start_ts) = start_timestamp {
         query = query.with_time_range_start_gte(start_ts);
@@ -200,6 +220,13 @@ pub async fn list_relevant_splits(
     if let Some(tags_filter) = tags_filter_opt {
         query = query.with_tags_filter(tags_filter);
     }
+    Ok(query)
+}
+
+async fn list_splits(
+    query: ListSplitsQuery,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<Vec<SplitMetadata>> {
     let list_splits_request = ListSplitsRequest::try_from_list_splits_query(&query)?;
     let splits_metadata: Vec<SplitMetadata> = metastore
         .list_splits(list_splits_request)
@@ -241,9 +268,13 @@ pub async fn resolve_index_patterns(
 fn convert_document_to_json_string(
     named_field_doc: NamedFieldDocument,
     doc_mapper: &dyn DocMapper,
+    source_filter_opt: Option<&SourceFilter>,
 ) -> anyhow::Result<String> {
     let NamedFieldDocument(named_field_doc_map) = named_field_doc;
-    let doc_json_map = doc_mapper.doc_to_json(named_field_doc_map)?;
+    let mut doc_json_map = doc_mapper.doc_to_json(named_field_doc_map)?;
+    if let Some(source_filter) = source_filter_opt {
+        doc_json_map = filter_source(source_filter, doc_json_map);
+    }
     let content_json =
         serde_json::to_string(&doc_json_map).expect("Json serialization should never fail.");
     Ok(content_json)
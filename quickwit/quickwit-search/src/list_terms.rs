@@ -217,14 +217,16 @@ async fn leaf_list_terms_single_split(
         .try_into()?;
     let searcher = reader.searcher();
 
-    let field = split_schema
-        .get_field(&search_request.field)
-        .with_context(|| {
-            format!(
-                "couldn't get field named {:?} from schema to list terms",
-                search_request.field
-            )
-        })?;
+    // The field was validated against the index current doc mapping, so a split built with an
+    // older doc mapping may not contain it. In that case, the split has no terms to list.
+    let Ok(field) = split_schema.get_field(&search_request.field) else {
+        return Ok(LeafListTermsResponse {
+            num_hits: 0,
+            terms: Vec::new(),
+            num_attempted_splits: 1,
+            failed_splits: Vec::new(),
+        });
+    };
 
     let field_type = split_schema.get_field_entry(field).field_type();
     let start_term: Option<Term> = search_request
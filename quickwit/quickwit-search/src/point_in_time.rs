@@ -0,0 +1,436 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use quickwit_metastore::{IndexMetadata, SplitMetadata, SplitState};
+use quickwit_proto::metastore::{
+    ClosePointInTimeRequest, EntityKind, MetastoreError, MetastoreService, MetastoreServiceClient,
+    OpenPointInTimeRequest,
+};
+use quickwit_proto::types::{IndexUid, SplitId};
+use serde::{Deserialize, Serialize};
+use tantivy::time::OffsetDateTime;
+use ulid::Ulid;
+
+use crate::{list_splits, relevant_splits_query, resolve_index_patterns, SearchError};
+
+/// Maximum amount of time a point in time can be kept alive for in a single request.
+const MAX_POINT_IN_TIME_KEEP_ALIVE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Identifies a point in time and the indexes it was opened on. It is handed out to clients as
+/// an opaque base64-encoded string.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PointInTimeId {
+    pub(crate) id: String,
+    pub(crate) index_uids: Vec<IndexUid>,
+}
+
+impl PointInTimeId {
+    fn new(index_uids: Vec<IndexUid>) -> Self {
+        Self {
+            id: Ulid::new().to_string(),
+            index_uids,
+        }
+    }
+
+    pub(crate) fn index_ids(&self) -> Vec<String> {
+        self.index_uids
+            .iter()
+            .map(|index_uid| index_uid.index_id.clone())
+            .collect()
+    }
+
+    /// Restricts `indexes_metadata`, resolved from the index ID patterns of a search request, to
+    /// the indexes of the point in time. The request must target all of them since it is
+    /// authorized against its index ID patterns, not against the point in time.
+    pub(crate) fn retain_indexes(
+        &self,
+        indexes_metadata: &mut Vec<IndexMetadata>,
+    ) -> crate::Result<()> {
+        for index_uid in &self.index_uids {
+            let is_targeted = indexes_metadata
+                .iter()
+                .any(|index_metadata| index_metadata.index_id() == index_uid.index_id);
+
+            if !is_targeted {
+                return Err(SearchError::InvalidArgument(format!(
+                    "index `{}` of the point in time is not targeted by the search request",
+                    index_uid.index_id
+                )));
+            }
+        }
+        // Indexes recreated since the point in time was opened are retained, so that the point
+        // in time is reported as not found.
+        indexes_metadata.retain(|index_metadata| {
+            self.index_uids
+                .iter()
+                .any(|index_uid| index_uid.index_id == index_metadata.index_id())
+        });
+        Ok(())
+    }
+
+    /// Returns the IDs of the splits pinned by the point in time, provided it still exists on all
+    /// of its indexes.
+    pub(crate) fn pinned_split_ids(
+        &self,
+        indexes_metadata: &[IndexMetadata],
+    ) -> crate::Result<HashSet<SplitId>> {
+        let mut pinned_split_ids = HashSet::new();
+
+        for index_uid in &self.index_uids {
+            let point_in_time_opt = indexes_metadata
+                .iter()
+                .find(|index_metadata| index_metadata.index_uid == *index_uid)
+                .and_then(|index_metadata| index_metadata.point_in_time(&self.id));
+            let Some(point_in_time) = point_in_time_opt else {
+                return Err(SearchError::PointInTimeNotFound {
+                    point_in_time_id: self.to_string(),
+                });
+            };
+            pinned_split_ids.extend(point_in_time.split_ids.iter().cloned());
+        }
+        Ok(pinned_split_ids)
+    }
+}
+
+impl fmt::Display for PointInTimeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let payload = serde_json::to_vec(self).expect("point in time id should be serializable");
+        write!(f, "{}", BASE64_STANDARD.encode(payload))
+    }
+}
+
+impl FromStr for PointInTimeId {
+    type Err = &'static str;
+
+    fn from_str(point_in_time_id_str: &str) -> Result<Self, Self::Err> {
+        let base64_decoded: Vec<u8> = BASE64_STANDARD
+            .decode(point_in_time_id_str)
+            .map_err(|_| "point in time id is invalid base64")?;
+        serde_json::from_slice(&base64_decoded).map_err(|_| "point in time id is malformed")
+    }
+}
+
+/// Opens a point in time on the indexes matching `index_id_patterns`, pinning the splits
+/// currently published. Returns the point in time id.
+pub async fn open_point_in_time(
+    index_id_patterns: &[String],
+    keep_alive: Duration,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<String> {
+    validate_keep_alive(keep_alive)?;
+    let indexes_metadata = resolve_index_patterns(index_id_patterns, metastore).await?;
+
+    if indexes_metadata.is_empty() {
+        return Err(SearchError::IndexesNotFound {
+            index_ids: index_id_patterns.to_vec(),
+        });
+    }
+    let index_uids: Vec<IndexUid> = indexes_metadata
+        .into_iter()
+        .map(|index_metadata| index_metadata.index_uid)
+        .collect();
+    let point_in_time_id = PointInTimeId::new(index_uids);
+    let expiration_timestamp =
+        OffsetDateTime::now_utc().unix_timestamp() + keep_alive.as_secs() as i64;
+    open_point_in_time_on_indexes(&point_in_time_id, expiration_timestamp, metastore).await?;
+    Ok(point_in_time_id.to_string())
+}
+
+/// Closes a point in time, releasing the splits it pins. Returns the number of indexes on which
+/// the point in time was still open.
+pub async fn close_point_in_time(
+    point_in_time_id_str: &str,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<u32> {
+    let point_in_time_id = PointInTimeId::from_str(point_in_time_id_str)
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))?;
+    let mut num_freed = 0;
+
+    for index_uid in point_in_time_id.index_uids {
+        let close_point_in_time_request = ClosePointInTimeRequest {
+            index_uid: Some(index_uid),
+            point_in_time_id: point_in_time_id.id.clone(),
+        };
+        match metastore
+            .close_point_in_time(close_point_in_time_request)
+            .await
+        {
+            Ok(_) => num_freed += 1,
+            Err(MetastoreError::NotFound(
+                EntityKind::Index { .. } | EntityKind::PointInTime { .. },
+            )) => {}
+            Err(metastore_error) => return Err(metastore_error.into()),
+        }
+    }
+    Ok(num_freed)
+}
+
+fn validate_keep_alive(keep_alive: Duration) -> crate::Result<()> {
+    if keep_alive.is_zero() || keep_alive > MAX_POINT_IN_TIME_KEEP_ALIVE {
+        return Err(SearchError::InvalidArgument(format!(
+            "point in time keep alive must be between 1s and {}s",
+            MAX_POINT_IN_TIME_KEEP_ALIVE.as_secs()
+        )));
+    }
+    Ok(())
+}
+
+/// Extends the expiration of a point in time searched with a keep alive, unless it already
+/// expires after the keep alive.
+pub(crate) async fn extend_point_in_time(
+    point_in_time_id: &PointInTimeId,
+    keep_alive: Duration,
+    indexes_metadata: &[IndexMetadata],
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<()> {
+    validate_keep_alive(keep_alive)?;
+    let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+
+    if let Some(expiration_timestamp) = extended_expiration_timestamp(
+        point_in_time_id,
+        keep_alive,
+        indexes_metadata,
+        now_timestamp,
+    ) {
+        open_point_in_time_on_indexes(point_in_time_id, expiration_timestamp, metastore).await?;
+    }
+    Ok(())
+}
+
+/// Returns the expiration timestamp to which a point in time must be extended for it to outlive
+/// `keep_alive`, or `None` if it already does on all of its indexes.
+///
+/// The point in time is extended by half a keep alive on top of it, so that the following pages
+/// of a pagination do not need to extend it again, which would write to the metastore for every
+/// page.
+fn extended_expiration_timestamp(
+    point_in_time_id: &PointInTimeId,
+    keep_alive: Duration,
+    indexes_metadata: &[IndexMetadata],
+    now_timestamp: i64,
+) -> Option<i64> {
+    let keep_alive_secs = keep_alive.as_secs() as i64;
+    let min_expiration_timestamp = now_timestamp + keep_alive_secs;
+
+    let is_kept_alive = point_in_time_id.index_uids.iter().all(|index_uid| {
+        indexes_metadata
+            .iter()
+            .find(|index_metadata| index_metadata.index_uid == *index_uid)
+            .and_then(|index_metadata| index_metadata.point_in_time(&point_in_time_id.id))
+            .map_or(false, |point_in_time| {
+                point_in_time.expiration_timestamp >= min_expiration_timestamp
+            })
+    });
+    if is_kept_alive {
+        return None;
+    }
+    Some(min_expiration_timestamp + keep_alive_secs / 2)
+}
+
+/// Opens the point in time on each of its indexes, or extends its expiration if it is already
+/// open.
+async fn open_point_in_time_on_indexes(
+    point_in_time_id: &PointInTimeId,
+    expiration_timestamp: i64,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<()> {
+    for index_uid in &point_in_time_id.index_uids {
+        let open_point_in_time_request = OpenPointInTimeRequest {
+            index_uid: Some(index_uid.clone()),
+            point_in_time_id: point_in_time_id.id.clone(),
+            expiration_timestamp,
+        };
+        metastore
+            .open_point_in_time(open_point_in_time_request)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Lists the splits pinned by a point in time that are relevant for a given request. Pinned
+/// splits may have been marked for deletion since the point in time was opened, for instance
+/// after a merge, so they are listed as well.
+pub(crate) async fn list_point_in_time_splits(
+    index_uids: Vec<IndexUid>,
+    start_timestamp: Option<i64>,
+    end_timestamp: Option<i64>,
+    tags_filter_opt: Option<TagFilterAst>,
+    pinned_split_ids: &HashSet<SplitId>,
+    metastore: &mut MetastoreServiceClient,
+) -> crate::Result<Vec<SplitMetadata>> {
+    let query = relevant_splits_query(index_uids, start_timestamp, end_timestamp, tags_filter_opt)?
+        .with_split_states([SplitState::Published, SplitState::MarkedForDeletion]);
+    let mut splits_metadata = list_splits(query, metastore).await?;
+    splits_metadata.retain(|split_metadata| pinned_split_ids.contains(&split_metadata.split_id));
+    Ok(splits_metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use quickwit_metastore::{IndexMetadata, PointInTime};
+    use quickwit_proto::types::IndexUid;
+    use tantivy::time::OffsetDateTime;
+
+    use super::{extended_expiration_timestamp, PointInTimeId};
+    use crate::SearchError;
+
+    #[test]
+    fn test_point_in_time_id_serialization() {
+        let point_in_time_id = PointInTimeId::new(vec![
+            IndexUid::for_test("test-index-1", 0),
+            IndexUid::for_test("test-index-2", 0),
+        ]);
+        let point_in_time_id_str = point_in_time_id.to_string();
+        assert_eq!(
+            PointInTimeId::from_str(&point_in_time_id_str).unwrap(),
+            point_in_time_id
+        );
+        assert_eq!(
+            point_in_time_id.index_ids(),
+            vec!["test-index-1".to_string(), "test-index-2".to_string()]
+        );
+        assert!(PointInTimeId::from_str("not base64!").is_err());
+        assert!(PointInTimeId::from_str("bm90IGpzb24=").is_err());
+    }
+
+    #[test]
+    fn test_point_in_time_id_pinned_split_ids() {
+        let mut index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+
+        let point_in_time_id = PointInTimeId::new(vec![
+            index_metadata_1.index_uid.clone(),
+            index_metadata_2.index_uid.clone(),
+        ]);
+        let error = point_in_time_id
+            .pinned_split_ids(&[index_metadata_1.clone(), index_metadata_2.clone()])
+            .unwrap_err();
+        assert!(matches!(error, SearchError::PointInTimeNotFound { .. }));
+
+        let point_in_time = PointInTime {
+            split_ids: vec!["split-1".to_string()],
+            expiration_timestamp: i64::MAX,
+        };
+        index_metadata_1
+            .points_in_time
+            .insert(point_in_time_id.id.clone(), point_in_time);
+
+        let point_in_time_id = PointInTimeId::from_str(&point_in_time_id.to_string()).unwrap();
+        let error = point_in_time_id
+            .pinned_split_ids(&[index_metadata_1.clone(), index_metadata_2.clone()])
+            .unwrap_err();
+        assert!(matches!(error, SearchError::PointInTimeNotFound { .. }));
+
+        let point_in_time_id = PointInTimeId {
+            id: point_in_time_id.id,
+            index_uids: vec![index_metadata_1.index_uid.clone()],
+        };
+        let pinned_split_ids = point_in_time_id
+            .pinned_split_ids(&[index_metadata_1, index_metadata_2])
+            .unwrap();
+        assert_eq!(pinned_split_ids.len(), 1);
+        assert!(pinned_split_ids.contains("split-1"));
+    }
+
+    #[test]
+    fn test_point_in_time_id_retain_indexes() {
+        let index_metadata_1 = IndexMetadata::for_test("test-index-1", "ram:///test-index-1");
+        let index_metadata_2 = IndexMetadata::for_test("test-index-2", "ram:///test-index-2");
+        let index_metadata_3 = IndexMetadata::for_test("test-index-3", "ram:///test-index-3");
+
+        let point_in_time_id = PointInTimeId::new(vec![
+            index_metadata_1.index_uid.clone(),
+            index_metadata_2.index_uid.clone(),
+        ]);
+        let mut indexes_metadata = vec![index_metadata_1.clone(), index_metadata_3.clone()];
+        let error = point_in_time_id
+            .retain_indexes(&mut indexes_metadata)
+            .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let mut indexes_metadata = vec![index_metadata_1, index_metadata_2, index_metadata_3];
+        point_in_time_id
+            .retain_indexes(&mut indexes_metadata)
+            .unwrap();
+        let index_ids: Vec<&str> = indexes_metadata
+            .iter()
+            .map(|index_metadata| index_metadata.index_id())
+            .collect();
+        assert_eq!(index_ids, ["test-index-1", "test-index-2"]);
+    }
+
+    #[test]
+    fn test_extended_expiration_timestamp() {
+        let now_timestamp = OffsetDateTime::now_utc().unix_timestamp();
+        let keep_alive = Duration::from_secs(60);
+        let mut index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        let point_in_time_id = PointInTimeId::new(vec![index_metadata.index_uid.clone()]);
+
+        let point_in_time = PointInTime {
+            split_ids: Vec::new(),
+            expiration_timestamp: now_timestamp + 30,
+        };
+        index_metadata
+            .points_in_time
+            .insert(point_in_time_id.id.clone(), point_in_time);
+        let expiration_timestamp = extended_expiration_timestamp(
+            &point_in_time_id,
+            keep_alive,
+            &[index_metadata.clone()],
+            now_timestamp,
+        );
+        assert_eq!(expiration_timestamp, Some(now_timestamp + 90));
+
+        // The following pages do not extend the point in time again until half of the keep alive
+        // has elapsed.
+        index_metadata
+            .points_in_time
+            .get_mut(&point_in_time_id.id)
+            .unwrap()
+            .expiration_timestamp = now_timestamp + 90;
+
+        for elapsed_secs in [0, 10, 30] {
+            let expiration_timestamp = extended_expiration_timestamp(
+                &point_in_time_id,
+                keep_alive,
+                &[index_metadata.clone()],
+                now_timestamp + elapsed_secs,
+            );
+            assert_eq!(expiration_timestamp, None);
+        }
+        let expiration_timestamp = extended_expiration_timestamp(
+            &point_in_time_id,
+            keep_alive,
+            &[index_metadata],
+            now_timestamp + 31,
+        );
+        assert_eq!(expiration_timestamp, Some(now_timestamp + 121));
+    }
+}
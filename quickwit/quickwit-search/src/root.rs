@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use std::time::Duration;
 
 use anyhow::Context;
@@ -36,7 +37,7 @@ use quickwit_proto::metastore::{
 use quickwit_proto::search::{
     FetchDocsRequest, FetchDocsResponse, Hit, LeafHit, LeafSearchRequest, LeafSearchResponse,
     PartialHit, SearchRequest, SearchResponse, SnippetRequest, SortDatetimeFormat, SortField,
-    SortValue, SplitIdAndFooterOffsets,
+    SortValue, SourceFilter, SplitIdAndFooterOffsets,
 };
 use quickwit_proto::types::{IndexUid, SplitId};
 use quickwit_query::query_ast::{
@@ -50,15 +51,20 @@ use tantivy::schema::{FieldEntry, FieldType, Schema};
 use tantivy::TantivyError;
 use tracing::{debug, error, info, info_span, instrument};
 
+use crate::aggregations::{
+    contains_extended_aggregations, ExtendedAggregations, IntermediateExtendedAggregationResults,
+    PipelineAggregations,
+};
 use crate::cluster_client::ClusterClient;
 use crate::collector::{make_merge_collector, QuickwitAggregations};
 use crate::find_trace_ids_collector::Span;
+use crate::point_in_time::{extend_point_in_time, list_point_in_time_splits, PointInTimeId};
 use crate::scroll_context::{ScrollContext, ScrollKeyAndStartOffset};
 use crate::search_job_placer::Job;
 use crate::service::SearcherContext;
 use crate::{
-    extract_split_and_footer_offsets, list_relevant_splits, SearchError, SearchJobPlacer,
-    SearchServiceClient,
+    extract_split_and_footer_offsets, list_relevant_splits, GlobalDocAddress, SearchError,
+    SearchJobPlacer, SearchServiceClient,
 };
 
 /// Maximum accepted scroll TTL.
@@ -79,7 +85,6 @@ pub struct SearchJob {
 impl SearchJob {
     #[cfg(test)]
     pub fn for_test(split_id: &str, cost: usize) -> SearchJob {
-        use std::str::FromStr;
         SearchJob {
             index_uid: IndexUid::from_str("test-index:00000000000000000000000000").unwrap(),
             cost,
@@ -237,7 +242,26 @@ fn validate_request_and_build_metadata(
         )?;
 
         // Validates the query by effectively building it against the current schema.
-        doc_mapper.query(doc_mapper.schema(), &query_ast_resolved_for_index, true)?;
+        doc_mapper.query(
+            doc_mapper.schema(),
+            &query_ast_resolved_for_index,
+            true,
+            None,
+        )?;
+
+        // Validates the queries of the `filters` aggregations, if any.
+        if let Some(aggregation_request) = &search_request.aggregation_request {
+            if let Ok(QuickwitAggregations::ExtendedAggregations(mut aggregations)) =
+                serde_json::from_str(aggregation_request)
+            {
+                aggregations.build_filter_queries(
+                    doc_mapper.as_ref(),
+                    doc_mapper.schema(),
+                    true,
+                    None,
+                )?;
+            }
+        }
 
         let index_metadata_for_leaf_search = IndexMetasForLeafSearch {
             index_uri: index_metadata.index_uri().clone(),
@@ -346,20 +370,24 @@ fn simplify_search_request_for_scroll_api(req: &SearchRequest) -> crate::Result<
         // We remove all aggregation request.
         // The aggregation will not be computed for each scroll request.
         aggregation_request: None,
-        // We remove the snippet fields. This feature is not supported for scroll requests.
-        snippet_fields: Vec::new(),
+        // Snippets are generated when fetching the docs of each scroll page.
+        snippet_fields: req.snippet_fields.clone(),
+        snippet_options: req.snippet_options.clone(),
+        source_filter: req.source_filter.clone(),
         // We remove the scroll ttl parameter. It is irrelevant to process later request
         scroll_ttl_secs: None,
         search_after: None,
         // request is simplified after initial query, and we cache the hit count, so we don't need
         // to recompute it afterward.
         count_hits: quickwit_proto::search::CountHits::Underestimate as i32,
+        point_in_time_id: None,
+        point_in_time_keep_alive_secs: None,
     })
 }
 
 /// Validates sort fields and search after values.
-/// - validate sort fields length.
-/// - search after values must be set for all sort fields.
+/// - search after values must be provided for all sort fields. A search after value without a sort
+///   value stands for a document missing the sort field.
 fn validate_sort_by_fields_and_search_after(
     sort_fields: &[SortField],
     search_after: &Option<PartialHit>,
@@ -367,12 +395,6 @@ fn validate_sort_by_fields_and_search_after(
     if sort_fields.is_empty() {
         return Ok(());
     }
-    if sort_fields.len() > 2 {
-        return Err(SearchError::InvalidArgument(format!(
-            "sort by field must be up to 2 fields, got {}",
-            sort_fields.len()
-        )));
-    }
     let Some(search_after_partial_hit) = search_after.as_ref() else {
         return Ok(());
     };
@@ -390,19 +412,9 @@ fn validate_sort_by_fields_and_search_after(
         ));
     }
 
-    let mut search_after_sort_value_count = 0;
     // TODO: we could validate if the search after sort value types of consistent with the sort
     // field types.
-    if let Some(sort_by_value) = search_after_partial_hit.sort_value.as_ref() {
-        sort_by_value.sort_value.context("sort value must be set")?;
-        search_after_sort_value_count += 1;
-    }
-    if let Some(sort_by_value_2) = search_after_partial_hit.sort_value2.as_ref() {
-        sort_by_value_2
-            .sort_value
-            .context("sort value must be set")?;
-        search_after_sort_value_count += 1;
-    }
+    let search_after_sort_value_count = search_after_partial_hit.sort_values().flatten().count();
     if search_after_sort_value_count != sort_fields_without_doc_count {
         return Err(SearchError::InvalidArgument(format!(
             "`search_after` must have the same number of sort values as sort by fields {:?}",
@@ -438,11 +450,6 @@ fn validate_sort_by_field_type(
     has_timestamp_format: bool,
 ) -> crate::Result<()> {
     let field_name = sort_by_field_entry.name();
-    if matches!(sort_by_field_entry.field_type(), FieldType::Str(_)) {
-        return Err(SearchError::InvalidArgument(format!(
-            "sort by field on type text is currently not supported `{field_name}`"
-        )));
-    }
     if !sort_by_field_entry.is_fast() {
         return Err(SearchError::InvalidArgument(format!(
             "sort by field must be a fast field, please add the fast property to your field \
@@ -477,8 +484,12 @@ fn validate_request(
 
     if let Some(agg) = search_request.aggregation_request.as_ref() {
         let _aggs: QuickwitAggregations = serde_json::from_str(agg).map_err(|_err| {
-            let err = serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(agg)
-                .unwrap_err();
+            let err = if contains_extended_aggregations(agg) {
+                serde_json::from_str::<ExtendedAggregations>(agg).unwrap_err()
+            } else {
+                serde_json::from_str::<tantivy::aggregation::agg_req::Aggregations>(agg)
+                    .unwrap_err()
+            };
             SearchError::InvalidAggregationRequest(err.to_string())
         })?;
     };
@@ -699,6 +710,7 @@ pub(crate) fn get_snippet_request(search_request: &SearchRequest) -> Option<Snip
     Some(SnippetRequest {
         snippet_fields: search_request.snippet_fields.clone(),
         query_ast_resolved: search_request.query_ast.clone(),
+        snippet_options: search_request.snippet_options.clone(),
     })
 }
 
@@ -735,6 +747,7 @@ pub(crate) async fn fetch_docs_phase(
     for (client, client_jobs) in assigned_fetch_docs_jobs {
         let fetch_jobs_requests = jobs_to_fetch_docs_requests(
             snippet_request.clone(),
+            search_request.source_filter.clone(),
             indexes_metas_for_leaf_search,
             client_jobs,
         )?;
@@ -760,19 +773,18 @@ pub(crate) async fn fetch_docs_phase(
             )
         })
         .collect();
-    let mut sort_field_iter = search_request.sort_fields.iter();
-    let sort_field_1_datetime_format_opt: Option<SortDatetimeFormat> =
-        get_sort_field_datetime_format(sort_field_iter.next())?;
-    let sort_field_2_datetime_format_opt: Option<SortDatetimeFormat> =
-        get_sort_field_datetime_format(sort_field_iter.next())?;
+    let sort_fields_datetime_format_opt: Vec<Option<SortDatetimeFormat>> = search_request
+        .sort_fields
+        .iter()
+        .map(get_sort_field_datetime_format)
+        .try_collect()?;
     let mut hits_with_position: Vec<(usize, Hit)> = leaf_hits
         .map(|leaf_hit| {
             build_hit_with_position(
                 leaf_hit,
                 &split_id_to_index_id_map,
                 &hit_order,
-                &sort_field_1_datetime_format_opt,
-                &sort_field_2_datetime_format_opt,
+                &sort_fields_datetime_format_opt,
             )
         })
         .try_collect()?;
@@ -790,8 +802,7 @@ fn build_hit_with_position(
     mut leaf_hit: LeafHit,
     split_id_to_index_id_map: &HashMap<&SplitId, &str>,
     hit_order: &HashMap<(String, u32, u32), usize>,
-    sort_field_1_datetime_format_opt: &Option<SortDatetimeFormat>,
-    sort_field_2_datetime_format_opt: &Option<SortDatetimeFormat>,
+    sort_fields_datetime_format_opt: &[Option<SortDatetimeFormat>],
 ) -> crate::Result<(usize, Hit)> {
     let partial_hit_ref = leaf_hit
         .partial_hit
@@ -802,21 +813,16 @@ fn build_hit_with_position(
         partial_hit_ref.segment_ord,
         partial_hit_ref.doc_id,
     );
-    let sort_value_opt = partial_hit_ref
-        .sort_value
-        .as_mut()
-        .and_then(|sort_field| sort_field.sort_value.as_mut());
-    if let Some(sort_by_value) = sort_value_opt {
-        if let Some(output_datetime_format) = &sort_field_1_datetime_format_opt {
-            convert_sort_datetime_value(sort_by_value, *output_datetime_format)?;
-        }
-    }
-    let sort_value_2_opt = partial_hit_ref
-        .sort_value2
-        .as_mut()
-        .and_then(|sort_field| sort_field.sort_value.as_mut());
-    if let Some(sort_by_value) = sort_value_2_opt {
-        if let Some(output_datetime_format) = &sort_field_2_datetime_format_opt {
+    for (sort_value_opt, sort_field_datetime_format_opt) in partial_hit_ref
+        .sort_values_mut()
+        .zip(sort_fields_datetime_format_opt)
+    {
+        let Some(sort_by_value) =
+            sort_value_opt.and_then(|sort_field| sort_field.sort_value.as_mut())
+        else {
+            continue;
+        };
+        if let Some(output_datetime_format) = sort_field_datetime_format_opt {
             convert_sort_datetime_value(sort_by_value, *output_datetime_format)?;
         }
     }
@@ -838,15 +844,13 @@ fn build_hit_with_position(
 }
 
 fn get_sort_field_datetime_format(
-    sort_field: Option<&SortField>,
+    sort_field: &SortField,
 ) -> crate::Result<Option<SortDatetimeFormat>> {
-    if let Some(sort_field) = sort_field {
-        if let Some(sort_field_datetime_format_int) = &sort_field.sort_datetime_format {
-            let sort_field_datetime_format =
-                SortDatetimeFormat::from_i32(*sort_field_datetime_format_int)
-                    .context("invalid sort datetime format")?;
-            return Ok(Some(sort_field_datetime_format));
-        }
+    if let Some(sort_field_datetime_format_int) = &sort_field.sort_datetime_format {
+        let sort_field_datetime_format =
+            SortDatetimeFormat::from_i32(*sort_field_datetime_format_int)
+                .context("invalid sort datetime format")?;
+        return Ok(Some(sort_field_datetime_format));
     }
     Ok(None)
 }
@@ -886,9 +890,21 @@ async fn root_search_aux(
     )
     .await?;
 
+    let top_hits_docs = fetch_top_hits_docs_phase(
+        indexes_metas_for_leaf_search,
+        first_phase_result
+            .intermediate_aggregation_result
+            .as_deref(),
+        &split_metadatas[..],
+        &search_request,
+        cluster_client,
+    )
+    .await?;
+
     let mut aggregation_result_json_opt = finalize_aggregation_if_any(
         &search_request,
         first_phase_result.intermediate_aggregation_result,
+        &top_hits_docs,
         searcher_context,
     )?;
     // In case there is no index, we don't want the response to contain any aggregation structure
@@ -908,9 +924,64 @@ async fn root_search_aux(
     })
 }
 
+/// Fetches the documents of the hits of the `top_hits` aggregations, if any.
+async fn fetch_top_hits_docs_phase(
+    indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
+    intermediate_aggregation_result_bytes_opt: Option<&[u8]>,
+    split_metadatas: &[SplitMetadata],
+    search_request: &SearchRequest,
+    cluster_client: &ClusterClient,
+) -> crate::Result<HashMap<GlobalDocAddress, Hit>> {
+    let (Some(aggregations_json), Some(intermediate_aggregation_result_bytes)) = (
+        search_request.aggregation_request.as_ref(),
+        intermediate_aggregation_result_bytes_opt,
+    ) else {
+        return Ok(HashMap::new());
+    };
+    let Ok(QuickwitAggregations::ExtendedAggregations(aggregations)) =
+        serde_json::from_str::<QuickwitAggregations>(aggregations_json)
+    else {
+        return Ok(HashMap::new());
+    };
+    if !aggregations.has_top_hits() {
+        return Ok(HashMap::new());
+    }
+    let intermediate_aggregation_results: IntermediateExtendedAggregationResults =
+        postcard::from_bytes(intermediate_aggregation_result_bytes)?;
+    let partial_hits = intermediate_aggregation_results.top_hits_partial_hits(&aggregations);
+
+    if partial_hits.is_empty() {
+        return Ok(HashMap::new());
+    }
+    // The documents are returned whole and filtered by each `top_hits` aggregation.
+    let top_hits_request = SearchRequest {
+        snippet_fields: Vec::new(),
+        source_filter: None,
+        sort_fields: Vec::new(),
+        ..search_request.clone()
+    };
+    let hits = fetch_docs_phase(
+        indexes_metas_for_leaf_search,
+        &partial_hits,
+        split_metadatas,
+        &top_hits_request,
+        cluster_client,
+    )
+    .await?;
+    let top_hits_docs = hits
+        .into_iter()
+        .filter_map(|hit| {
+            let global_doc_address = GlobalDocAddress::from_partial_hit(hit.partial_hit.as_ref()?);
+            Some((global_doc_address, hit))
+        })
+        .collect();
+    Ok(top_hits_docs)
+}
+
 fn finalize_aggregation(
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
     aggregations: QuickwitAggregations,
+    top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<String>> {
     let merge_aggregation_result = match aggregations {
@@ -924,6 +995,24 @@ fn finalize_aggregation(
             let aggs: Vec<Span> = postcard::from_bytes(&intermediate_aggregation_result_bytes)?;
             serde_json::to_string(&aggs)?
         }
+        QuickwitAggregations::ExtendedAggregations(aggregations) => {
+            let intermediate_aggregation_results =
+                if let Some(intermediate_aggregation_result_bytes) =
+                    intermediate_aggregation_result_bytes_opt
+                {
+                    let intermediate_aggregation_results: IntermediateExtendedAggregationResults =
+                        postcard::from_bytes(&intermediate_aggregation_result_bytes)?;
+                    intermediate_aggregation_results
+                } else {
+                    IntermediateExtendedAggregationResults::empty(&aggregations)
+                };
+            let final_aggregation_results = intermediate_aggregation_results.into_final_result(
+                aggregations,
+                top_hits_docs,
+                &searcher_context.get_aggregation_limits(),
+            )?;
+            serde_json::to_string(&final_aggregation_results)?
+        }
         QuickwitAggregations::TantivyAggregations(aggregations) => {
             let intermediate_aggregation_results =
                 if let Some(intermediate_aggregation_result_bytes) =
@@ -947,6 +1036,7 @@ fn finalize_aggregation(
 fn finalize_aggregation_if_any(
     search_request: &SearchRequest,
     intermediate_aggregation_result_bytes_opt: Option<Vec<u8>>,
+    top_hits_docs: &HashMap<GlobalDocAddress, Hit>,
     searcher_context: &SearcherContext,
 ) -> crate::Result<Option<String>> {
     let Some(aggregations_json) = search_request.aggregation_request.as_ref() else {
@@ -956,6 +1046,7 @@ fn finalize_aggregation_if_any(
     let aggregation_result_json = finalize_aggregation(
         intermediate_aggregation_result_bytes_opt,
         aggregations,
+        top_hits_docs,
         searcher_context,
     )?;
     Ok(aggregation_result_json)
@@ -1001,6 +1092,24 @@ pub fn check_all_index_metadata_found(
     Ok(())
 }
 
+/// Removes the pipeline aggregations from the aggregation request, if any, so that the leaves only
+/// receive the aggregations they compute. The pipeline aggregations are applied once the final
+/// results of the other aggregations are computed.
+fn split_pipeline_aggregations(
+    search_request: &mut SearchRequest,
+) -> crate::Result<Option<PipelineAggregations>> {
+    let Some(aggregation_request) = &search_request.aggregation_request else {
+        return Ok(None);
+    };
+    let Some((aggregation_request, pipeline_aggregations)) =
+        PipelineAggregations::split_from_request(aggregation_request)?
+    else {
+        return Ok(None);
+    };
+    search_request.aggregation_request = Some(aggregation_request);
+    Ok(Some(pipeline_aggregations))
+}
+
 /// Performs a distributed search.
 /// 1. Sends leaf request over gRPC to multiple leaf nodes.
 /// 2. Merges the search results.
@@ -1015,16 +1124,39 @@ pub async fn root_search(
 ) -> crate::Result<SearchResponse> {
     info!(searcher_context = ?searcher_context, search_request = ?search_request);
     let start_instant = tokio::time::Instant::now();
+    let pipeline_aggregations_opt = split_pipeline_aggregations(&mut search_request)?;
+    let point_in_time_id_opt: Option<PointInTimeId> = search_request
+        .point_in_time_id
+        .as_deref()
+        .map(PointInTimeId::from_str)
+        .transpose()
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))?;
+
+    if let Some(point_in_time_id) = &point_in_time_id_opt {
+        if search_request.scroll_ttl_secs.is_some() {
+            return Err(SearchError::InvalidArgument(
+                "scroll cannot be used with a point in time".to_string(),
+            ));
+        }
+        if search_request.index_id_patterns.is_empty() {
+            search_request.index_id_patterns = point_in_time_id.index_ids();
+        }
+    }
     let list_indexes_metadatas_request = ListIndexesMetadataRequest {
         index_id_patterns: search_request.index_id_patterns.clone(),
     };
-    let indexes_metadata: Vec<IndexMetadata> = metastore
+    let mut indexes_metadata: Vec<IndexMetadata> = metastore
         .list_indexes_metadata(list_indexes_metadatas_request)
         .await?
         .deserialize_indexes_metadata()?;
 
     check_all_index_metadata_found(&indexes_metadata[..], &search_request.index_id_patterns[..])?;
 
+    if let Some(point_in_time_id) = &point_in_time_id_opt {
+        point_in_time_id.retain_indexes(&mut indexes_metadata)?;
+        search_request.index_id_patterns = point_in_time_id.index_ids();
+    }
+
     if indexes_metadata.is_empty() {
         // We go through root_search_aux instead of directly
         // returning an empty response to make sure we generate
@@ -1041,6 +1173,24 @@ pub async fn root_search(
         return Ok(search_response);
     }
 
+    let pinned_split_ids_opt: Option<HashSet<SplitId>> = point_in_time_id_opt
+        .as_ref()
+        .map(|point_in_time_id| point_in_time_id.pinned_split_ids(&indexes_metadata))
+        .transpose()?;
+
+    if let (Some(point_in_time_id), Some(keep_alive_secs)) = (
+        &point_in_time_id_opt,
+        search_request.point_in_time_keep_alive_secs,
+    ) {
+        let keep_alive = Duration::from_secs(keep_alive_secs as u64);
+        extend_point_in_time(
+            point_in_time_id,
+            keep_alive,
+            &indexes_metadata,
+            &mut metastore,
+        )
+        .await?;
+    }
     let index_uids = indexes_metadata
         .iter()
         .map(|index_metadata| index_metadata.index_uid.clone())
@@ -1067,14 +1217,27 @@ pub async fn root_search(
 
     // TODO if search after is set, we sort by timestamp and we don't want to count all results,
     // we can refine more here. Same if we sort by _shard_doc
-    let split_metadatas: Vec<SplitMetadata> = list_relevant_splits(
-        index_uids,
-        search_request.start_timestamp,
-        search_request.end_timestamp,
-        tag_filter_ast,
-        &mut metastore,
-    )
-    .await?;
+    let split_metadatas: Vec<SplitMetadata> = if let Some(pinned_split_ids) = &pinned_split_ids_opt
+    {
+        list_point_in_time_splits(
+            index_uids,
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+            tag_filter_ast,
+            pinned_split_ids,
+            &mut metastore,
+        )
+        .await?
+    } else {
+        list_relevant_splits(
+            index_uids,
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+            tag_filter_ast,
+            &mut metastore,
+        )
+        .await?
+    };
 
     let mut search_response = root_search_aux(
         searcher_context,
@@ -1085,6 +1248,11 @@ pub async fn root_search(
     )
     .await?;
 
+    if let (Some(pipeline_aggregations), Some(aggregation_results_json)) =
+        (&pipeline_aggregations_opt, &search_response.aggregation)
+    {
+        search_response.aggregation = Some(pipeline_aggregations.apply(aggregation_results_json)?);
+    }
     search_response.elapsed_time_micros = start_instant.elapsed().as_micros() as u64;
     Ok(search_response)
 }
@@ -1107,12 +1275,10 @@ fn convert_search_after_datetime_values(
         }
     }
     if let Some(partial_hit) = search_request.search_after.as_mut() {
-        let search_after_values = [
-            partial_hit.sort_value.as_mut(),
-            partial_hit.sort_value2.as_mut(),
-        ];
-        for (sort_field, search_after_value_opt) in
-            search_request.sort_fields.iter().zip(search_after_values)
+        for (sort_field, search_after_value_opt) in search_request
+            .sort_fields
+            .iter()
+            .zip(partial_hit.sort_values_mut())
         {
             let Some(search_after_sort_by_value) = search_after_value_opt else {
                 continue;
@@ -1138,6 +1304,16 @@ fn convert_sort_datetime_value_into_nanos(
     sort_value: &mut SortValue,
     input_format: SortDatetimeFormat,
 ) -> crate::Result<()> {
+    // Datetime values passed as strings, for instance by clients that can't handle large
+    // integers, are parsed first.
+    if let SortValue::Str(value) = sort_value {
+        let timestamp = value.parse::<i64>().map_err(|_| {
+            SearchError::InvalidArgument(format!(
+                "sort value `{value}` of a datetime sort field must be an integer"
+            ))
+        })?;
+        *sort_value = SortValue::I64(timestamp);
+    }
     match sort_value {
         SortValue::U64(value) => match input_format {
             SortDatetimeFormat::UnixTimestampMillis => {
@@ -1428,6 +1604,7 @@ pub fn jobs_to_leaf_requests(
 /// Builds a list of [`FetchDocsRequest`], one per index, from a list of [`FetchDocsJob`].
 pub fn jobs_to_fetch_docs_requests(
     snippet_request_opt: Option<SnippetRequest>,
+    source_filter_opt: Option<SourceFilter>,
     indexes_metas_for_leaf_search: &IndexesMetasForLeafSearch,
     jobs: Vec<FetchDocsJob>,
 ) -> crate::Result<Vec<FetchDocsRequest>> {
@@ -1455,6 +1632,7 @@ pub fn jobs_to_fetch_docs_requests(
             split_offsets,
             index_uri: index_meta.index_uri.to_string(),
             snippet_request: snippet_request_opt.clone(),
+            source_filter: source_filter_opt.clone(),
             doc_mapper: index_meta.doc_mapper_str.clone(),
         };
         fetch_docs_requests.push(fetch_docs_req);
@@ -1475,7 +1653,8 @@ mod tests {
     use quickwit_metastore::{IndexMetadata, ListSplitsRequestExt, ListSplitsResponseExt};
     use quickwit_proto::metastore::{ListIndexesMetadataResponse, ListSplitsResponse};
     use quickwit_proto::search::{
-        ScrollRequest, SortByValue, SortOrder, SortValue, SplitSearchError,
+        ScrollRequest, SnippetEncoder, SnippetOptions, SortByValue, SortOrder, SortValue,
+        SplitSearchError,
     };
     use quickwit_query::query_ast::{qast_helper, qast_json_helper, query_ast_from_user_text};
     use tantivy::schema::{FAST, STORED, TEXT};
@@ -1516,6 +1695,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_simplify_search_request_for_scroll_api_keeps_snippets() {
+        let snippet_options = SnippetOptions {
+            pre_tag: Some("<em>".to_string()),
+            post_tag: Some("</em>".to_string()),
+            fragment_size: Some(100),
+            number_of_fragments: Some(5),
+            encoder: SnippetEncoder::Raw as i32,
+        };
+        let search_request = SearchRequest {
+            index_id_patterns: vec!["test-index".to_string()],
+            query_ast: qast_json_helper("test", &["body"]),
+            max_hits: 10,
+            aggregation_request: Some("{}".to_string()),
+            snippet_fields: vec!["body".to_string()],
+            snippet_options: Some(snippet_options.clone()),
+            scroll_ttl_secs: Some(60),
+            ..Default::default()
+        };
+        let scroll_search_request =
+            simplify_search_request_for_scroll_api(&search_request).unwrap();
+        assert!(scroll_search_request.aggregation_request.is_none());
+        assert!(scroll_search_request.scroll_ttl_secs.is_none());
+        assert_eq!(
+            scroll_search_request.snippet_fields,
+            vec!["body".to_string()]
+        );
+        assert_eq!(scroll_search_request.snippet_options, Some(snippet_options));
+    }
+
     #[test]
     fn test_get_sort_by_field_entry() {
         let mut schema_builder = Schema::builder();
@@ -1815,6 +2024,24 @@ mod tests {
         )
         .unwrap();
         assert_eq!(sort_value, SortValue::I64(1617000000000000000));
+        // values passed as strings are parsed.
+        let mut sort_value = SortValue::Str("1617000000000".to_string());
+        convert_sort_datetime_value_into_nanos(
+            &mut sort_value,
+            SortDatetimeFormat::UnixTimestampMillis,
+        )
+        .unwrap();
+        assert_eq!(sort_value, SortValue::I64(1617000000000000000));
+        let mut sort_value = SortValue::Str("yesterday".to_string());
+        let error = convert_sort_datetime_value_into_nanos(
+            &mut sort_value,
+            SortDatetimeFormat::UnixTimestampMillis,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid argument: sort value `yesterday` of a datetime sort field must be an integer"
+        );
 
         // conversion with a too large millisecond value should fail.
         let mut sort_value = SortValue::I64(1617000000000000);
@@ -1971,6 +2198,34 @@ mod tests {
             sort_value2: Some(SortByValue {
                 sort_value: Some(SortValue::U64(2)),
             }),
+            additional_sort_values: Vec::new(),
+            split_id: "".to_string(),
+            segment_ord: 0,
+            doc_id: 0,
+        };
+        validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap();
+    }
+
+    #[test]
+    fn test_validate_sort_by_fields_and_search_after_ok_with_missing_sort_value() {
+        let sort_fields = vec![
+            SortField {
+                field_name: "timestamp".to_string(),
+                sort_order: 0,
+                sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+            },
+            SortField {
+                field_name: "id".to_string(),
+                sort_order: 0,
+                sort_datetime_format: None,
+            },
+        ];
+        let partial_hit = PartialHit {
+            sort_value: Some(SortByValue { sort_value: None }),
+            sort_value2: Some(SortByValue {
+                sort_value: Some(SortValue::U64(2)),
+            }),
+            additional_sort_values: Vec::new(),
             split_id: "".to_string(),
             segment_ord: 0,
             doc_id: 0,
@@ -1997,6 +2252,7 @@ mod tests {
                 sort_value: Some(SortValue::U64(1)),
             }),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: "split1".to_string(),
             segment_ord: 1,
             doc_id: 1,
@@ -2011,6 +2267,7 @@ mod tests {
         let id_field = schema_builder.add_u64_field("id", FAST);
         let no_fast_field = schema_builder.add_u64_field("no_fast", STORED);
         let text_field = schema_builder.add_text_field("text", STORED);
+        let fast_text_field = schema_builder.add_text_field("fast_text", FAST);
         let schema = schema_builder.build();
         {
             let sort_by_field_entry = schema.get_field_entry(timestamp_field);
@@ -2038,10 +2295,21 @@ mod tests {
         }
         {
             let sort_by_field_entry = schema.get_field_entry(text_field);
+            let error = validate_sort_by_field_type(sort_by_field_entry, false).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "Invalid argument: sort by field must be a fast field, please add the fast \
+                 property to your field `text`"
+            );
+        }
+        {
+            let sort_by_field_entry = schema.get_field_entry(fast_text_field);
+            validate_sort_by_field_type(sort_by_field_entry, false).unwrap();
             let error = validate_sort_by_field_type(sort_by_field_entry, true).unwrap_err();
             assert_eq!(
                 error.to_string(),
-                "Invalid argument: sort by field on type text is currently not supported `text`"
+                "Invalid argument: sort by field with a timestamp format must be a datetime field \
+                 and the field `fast_text` is not"
             );
         }
     }
@@ -2066,6 +2334,7 @@ mod tests {
                 sort_value: Some(SortValue::U64(1)),
             }),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: "split1".to_string(),
             segment_ord: 1,
             doc_id: 1,
@@ -2099,6 +2368,7 @@ mod tests {
                 sort_value: Some(SortValue::U64(1)),
             }),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: "".to_string(),
             segment_ord: 1,
             doc_id: 1,
@@ -2132,6 +2402,7 @@ mod tests {
                 sort_value: Some(SortValue::U64(1)),
             }),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: "split1".to_string(),
             segment_ord: 1,
             doc_id: 1,
@@ -2161,8 +2432,7 @@ mod tests {
     }
 
     #[test]
-    fn test_validate_sort_by_fields_and_search_after_invalid_3() {
-        // 3 sort fields is not possible.
+    fn test_validate_sort_by_fields_and_search_after_with_3_sort_fields() {
         let sort_fields = vec![
             SortField {
                 field_name: "timestamp".to_string(),
@@ -2170,20 +2440,41 @@ mod tests {
                 sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
             },
             SortField {
-                field_name: "timestamp".to_string(),
-                sort_order: 0,
-                sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                field_name: "id".to_string(),
+                sort_order: 1,
+                sort_datetime_format: None,
             },
             SortField {
-                field_name: "timestamp".to_string(),
+                field_name: "score".to_string(),
                 sort_order: 0,
-                sort_datetime_format: Some(SortDatetimeFormat::UnixTimestampMillis as i32),
+                sort_datetime_format: None,
             },
         ];
-        let error = validate_sort_by_fields_and_search_after(&sort_fields, &None).unwrap_err();
+        validate_sort_by_fields_and_search_after(&sort_fields, &None).unwrap();
+
+        let mut partial_hit = PartialHit {
+            sort_value: Some(SortByValue {
+                sort_value: Some(SortValue::U64(1)),
+            }),
+            sort_value2: Some(SortByValue {
+                sort_value: Some(SortValue::U64(2)),
+            }),
+            additional_sort_values: vec![SortByValue {
+                sort_value: Some(SortValue::F64(0.5)),
+            }],
+            split_id: "".to_string(),
+            segment_ord: 0,
+            doc_id: 0,
+        };
+        validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit.clone())).unwrap();
+
+        partial_hit.additional_sort_values.clear();
+        let error =
+            validate_sort_by_fields_and_search_after(&sort_fields, &Some(partial_hit)).unwrap_err();
         assert_eq!(
             error.to_string(),
-            "Invalid argument: sort by field must be up to 2 fields, got 3"
+            "Invalid argument: `search_after` must have the same number of sort values as sort by \
+             fields [\"timestamp\", \"id\", \"score\"]"
         );
     }
 
@@ -2195,6 +2486,7 @@ mod tests {
         quickwit_proto::search::PartialHit {
             sort_value: Some(SortValue::U64(sort_value).into()),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
@@ -2209,6 +2501,7 @@ mod tests {
         quickwit_proto::search::PartialHit {
             sort_value: sort_value.map(|sort_value| SortValue::U64(sort_value).into()),
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: split_id.to_string(),
             segment_ord: 1,
             doc_id,
@@ -2539,6 +2832,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::U64(2u64).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
@@ -2546,6 +2840,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
@@ -2573,6 +2868,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::I64(-1i64).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
@@ -2580,6 +2876,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::I64(1i64).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
@@ -2587,6 +2884,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 2,
@@ -2629,6 +2927,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: Some(SortValue::I64(-1i64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2639,6 +2938,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::I64(1i64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2649,6 +2949,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::U64(2u64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2659,6 +2960,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: None,
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2669,6 +2971,7 @@ mod tests {
                 doc_id: 2,
                 sort_value: None,
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         Ok(())
@@ -2720,6 +3023,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::U64(2u64).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
@@ -2727,6 +3031,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split1".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
@@ -2754,6 +3059,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::I64(1i64).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 0,
@@ -2761,6 +3067,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: Some(SortValue::I64(-1i64).into()),
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 1,
@@ -2768,6 +3075,7 @@ mod tests {
                         quickwit_proto::search::PartialHit {
                             sort_value: None,
                             sort_value2: None,
+                            additional_sort_values: Vec::new(),
                             split_id: "split2".to_string(),
                             segment_ord: 0,
                             doc_id: 2,
@@ -2810,6 +3118,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::U64(2u64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2820,6 +3129,7 @@ mod tests {
                 doc_id: 0,
                 sort_value: Some(SortValue::I64(1i64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2830,6 +3140,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: Some(SortValue::I64(-1i64).into()),
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2840,6 +3151,7 @@ mod tests {
                 doc_id: 2,
                 sort_value: None,
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         assert_eq!(
@@ -2850,6 +3162,7 @@ mod tests {
                 doc_id: 1,
                 sort_value: None,
                 sort_value2: None,
+                additional_sort_values: Vec::new(),
             }
         );
         Ok(())
@@ -246,6 +246,7 @@ mod tests {
         let partial_hit = PartialHit {
             sort_value: None,
             sort_value2: None,
+            additional_sort_values: Vec::new(),
             split_id: "split".to_string(),
             segment_ord: 1,
             doc_id: 2,
@@ -24,7 +24,7 @@ use std::marker::PhantomData;
 use tantivy::collector::{Collector, SegmentCollector};
 use tantivy::columnar::{DynamicColumn, HasAssociatedColumnType};
 use tantivy::fastfield::Column;
-use tantivy::{DocId, Score, SegmentOrdinal, SegmentReader};
+use tantivy::{DocAddress, DocId, Score, SegmentOrdinal, SegmentReader};
 
 use crate::filters::{TimestampFilter, TimestampFilterBuilder};
 
@@ -244,3 +244,69 @@ impl<Item: HasAssociatedColumnType, PartitionItem: HasAssociatedColumnType + Has
         self.fast_field_values
     }
 }
+
+/// Collects the addresses of the matching documents, in doc address order.
+#[derive(Clone)]
+pub struct DocAddressCollector {
+    pub timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
+}
+
+impl Collector for DocAddressCollector {
+    type Child = DocAddressSegmentCollector;
+    type Fruit = Vec<DocAddress>;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let timestamp_filter_opt =
+            if let Some(timestamp_filter_builder) = &self.timestamp_filter_builder_opt {
+                timestamp_filter_builder.build(segment_reader)?
+            } else {
+                None
+            };
+        Ok(DocAddressSegmentCollector {
+            segment_ord,
+            doc_ids: Vec::new(),
+            timestamp_filter_opt,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        // We do not need BM25 scoring in Quickwit.
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Vec<DocAddress>>) -> tantivy::Result<Self::Fruit> {
+        let mut doc_addresses: Vec<DocAddress> = segment_fruits.into_iter().flatten().collect();
+        doc_addresses.sort_unstable();
+        Ok(doc_addresses)
+    }
+}
+
+pub struct DocAddressSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    doc_ids: Vec<DocId>,
+    timestamp_filter_opt: Option<TimestampFilter>,
+}
+
+impl SegmentCollector for DocAddressSegmentCollector {
+    type Fruit = Vec<DocAddress>;
+
+    fn collect(&mut self, doc_id: DocId, _score: Score) {
+        if let Some(timestamp_filter) = &self.timestamp_filter_opt {
+            if !timestamp_filter.is_within_range(doc_id) {
+                return;
+            }
+        }
+        self.doc_ids.push(doc_id);
+    }
+
+    fn harvest(self) -> Vec<DocAddress> {
+        self.doc_ids
+            .into_iter()
+            .map(|doc_id| DocAddress::new(self.segment_ord, doc_id))
+            .collect()
+    }
+}
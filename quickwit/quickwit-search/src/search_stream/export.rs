@@ -0,0 +1,937 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+//! Multi-column export of the documents matching a search stream request, in the Arrow IPC,
+//! Parquet, and NDJSON output formats.
+//!
+//! Leaves serialize the documents of each split as an Arrow IPC stream, or as NDJSON lines. The
+//! root then merges the Arrow IPC streams of the splits into a single Arrow IPC stream or Parquet
+//! file, so that clients receive one well-formed payload.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{self, Cursor, Write};
+use std::net::Ipv6Addr;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::{
+    ArrayBuilder, ArrayRef, BinaryBuilder, BooleanBuilder, Float64Builder, Int64Builder,
+    StringBuilder, TimestampMicrosecondBuilder, UInt64Builder,
+};
+use arrow::datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef, TimeUnit};
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow::json::LineDelimitedWriter;
+use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use quickwit_doc_mapper::DocMapper;
+use quickwit_proto::search::{
+    OutputFormat, SearchRequest, SearchStreamRequest, SplitIdAndFooterOffsets,
+};
+use quickwit_query::query_ast::QueryAst;
+use quickwit_storage::Storage;
+use tantivy::columnar::{BytesColumn, StrColumn};
+use tantivy::fastfield::Column;
+use tantivy::schema::{OwnedValue, Schema, TantivyDocument, Type};
+use tantivy::{DateTime, DocAddress, ReloadPolicy, Searcher, SegmentReader, TantivyError};
+
+use super::collector::DocAddressCollector;
+use crate::filters::create_timestamp_filter_builder;
+use crate::leaf::{open_index_with_caches, warmup};
+use crate::service::SearcherContext;
+use crate::{run_cpu_intensive, SearchError};
+
+/// Maximum number of rows of the record batches.
+pub(super) const RECORD_BATCH_NUM_ROWS: usize = 8_192;
+
+/// Number of concurrent doc store requests when exporting stored fields from a split.
+const NUM_CONCURRENT_DOC_REQUESTS: usize = 30;
+
+/// Returns whether the output format supports exporting several fields.
+pub(crate) fn is_multi_column_output_format(output_format: OutputFormat) -> bool {
+    match output_format {
+        OutputFormat::Csv | OutputFormat::ClickHouseRowBinary => false,
+        OutputFormat::ArrowIpc | OutputFormat::Parquet | OutputFormat::Ndjson => true,
+    }
+}
+
+/// Returns the names of the fields to export: `fields`, or `fast_field` if `fields` is empty.
+pub(crate) fn export_field_names(search_stream_request: &SearchStreamRequest) -> Vec<String> {
+    if !search_stream_request.fields.is_empty() {
+        return search_stream_request.fields.clone();
+    }
+    if search_stream_request.fast_field.is_empty() {
+        return Vec::new();
+    }
+    vec![search_stream_request.fast_field.clone()]
+}
+
+/// Where the values of an exported field are read from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ColumnSource {
+    Fast,
+    Stored,
+}
+
+#[derive(Debug)]
+struct ExportColumn {
+    field_name: String,
+    field_type: Type,
+    source: ColumnSource,
+}
+
+/// Columns of an export and the corresponding Arrow schema.
+///
+/// The export schema is built from the schema of the doc mapper rather than the schemas of the
+/// splits, so that all the splits produce record batches with the same schema. Fields missing
+/// from a split are exported as null values.
+#[derive(Debug)]
+pub(crate) struct ExportSchema {
+    columns: Vec<ExportColumn>,
+    arrow_schema: SchemaRef,
+}
+
+impl ExportSchema {
+    pub fn try_new(schema: &Schema, field_names: &[String]) -> crate::Result<Self> {
+        if field_names.is_empty() {
+            return Err(SearchError::InvalidArgument(
+                "either `fast_field` or `fields` must be set".to_string(),
+            ));
+        }
+        let mut columns = Vec::with_capacity(field_names.len());
+        let mut arrow_fields = Vec::with_capacity(field_names.len());
+
+        for field_name in field_names {
+            let field = schema.get_field(field_name).map_err(|_| {
+                SearchError::InvalidArgument(format!("unknown field `{field_name}`"))
+            })?;
+            let field_entry = schema.get_field_entry(field);
+            let field_type = field_entry.field_type().value_type();
+
+            // JSON fast fields are not exported as a single column: they are read from the doc
+            // store instead.
+            let source = if field_entry.is_fast() && field_type != Type::Json {
+                ColumnSource::Fast
+            } else if field_entry.is_stored() {
+                ColumnSource::Stored
+            } else {
+                return Err(SearchError::InvalidArgument(format!(
+                    "field `{field_name}` must be a fast field or a stored field to be exported"
+                )));
+            };
+            let data_type = match field_type {
+                Type::Str | Type::IpAddr | Type::Json => DataType::Utf8,
+                Type::U64 => DataType::UInt64,
+                Type::I64 => DataType::Int64,
+                Type::F64 => DataType::Float64,
+                Type::Bool => DataType::Boolean,
+                Type::Date => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                Type::Bytes => DataType::Binary,
+                Type::Facet => {
+                    return Err(SearchError::InvalidArgument(format!(
+                        "field `{field_name}` of type `facet` cannot be exported"
+                    )));
+                }
+            };
+            columns.push(ExportColumn {
+                field_name: field_name.clone(),
+                field_type,
+                source,
+            });
+            arrow_fields.push(ArrowField::new(field_name, data_type, true));
+        }
+        Ok(Self {
+            columns,
+            arrow_schema: Arc::new(ArrowSchema::new(arrow_fields)),
+        })
+    }
+
+    pub fn arrow_schema(&self) -> SchemaRef {
+        self.arrow_schema.clone()
+    }
+
+    fn fast_field_names(&self) -> impl Iterator<Item = &str> {
+        self.columns
+            .iter()
+            .filter(|column| column.source == ColumnSource::Fast)
+            .map(|column| column.field_name.as_str())
+    }
+
+    fn has_stored_columns(&self) -> bool {
+        self.columns
+            .iter()
+            .any(|column| column.source == ColumnSource::Stored)
+    }
+}
+
+/// Documents of a split matching an export request.
+///
+/// The documents are exported in chunks of [`RECORD_BATCH_NUM_ROWS`] docs, so that the stored
+/// values of at most one chunk are held in memory at a time.
+pub(super) struct SplitExport {
+    split_id: String,
+    searcher: Arc<Searcher>,
+    export_schema: ExportSchema,
+    doc_addresses: Vec<DocAddress>,
+    output_format: OutputFormat,
+}
+
+impl SplitExport {
+    /// Opens the split and collects the addresses of the documents matching the request.
+    pub async fn open(
+        searcher_context: &SearcherContext,
+        split: &SplitIdAndFooterOffsets,
+        doc_mapper: Arc<dyn DocMapper>,
+        stream_request: &SearchStreamRequest,
+        storage: Arc<dyn Storage>,
+        output_format: OutputFormat,
+    ) -> crate::Result<Self> {
+        let export_schema =
+            ExportSchema::try_new(&doc_mapper.schema(), &export_field_names(stream_request))?;
+        let index = open_index_with_caches(
+            searcher_context,
+            storage,
+            split,
+            Some(doc_mapper.tokenizer_manager()),
+            false,
+        )
+        .await?;
+        let split_schema = index.schema();
+
+        let search_request = SearchRequest::try_from(stream_request.clone())?;
+        let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
+            .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
+        let (query, mut warmup_info) = doc_mapper.query(
+            split_schema.clone(),
+            &query_ast,
+            false,
+            Some(&split.split_id),
+        )?;
+        let reader = index
+            .reader_builder()
+            // The docs are fetched in doc address order.
+            .doc_store_cache_num_blocks(NUM_CONCURRENT_DOC_REQUESTS)
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = Arc::new(reader.searcher());
+
+        let timestamp_filter_builder_opt = create_timestamp_filter_builder(
+            doc_mapper.timestamp_field_name(),
+            search_request.start_timestamp,
+            search_request.end_timestamp,
+        );
+        if let Some(timestamp_filter_builder) = &timestamp_filter_builder_opt {
+            warmup_info
+                .fast_field_names
+                .insert(timestamp_filter_builder.timestamp_field_name.clone());
+        }
+        warmup_info.fast_field_names.extend(
+            export_schema
+                .fast_field_names()
+                .filter(|field_name| split_schema.get_field(field_name).is_ok())
+                .map(ToString::to_string),
+        );
+        warmup_info.simplify();
+        warmup(&searcher, &warmup_info).await?;
+
+        let collector = DocAddressCollector {
+            timestamp_filter_builder_opt,
+        };
+        let searcher_clone = searcher.clone();
+        let doc_addresses = run_cpu_intensive(move || searcher_clone.search(&*query, &collector))
+            .await
+            .map_err(|_| {
+                SearchError::Internal(format!(
+                    "error when collecting docs for split {}",
+                    split.split_id
+                ))
+            })??;
+
+        Ok(Self {
+            split_id: split.split_id.clone(),
+            searcher,
+            export_schema,
+            doc_addresses,
+            output_format,
+        })
+    }
+
+    /// Returns the stream of the exported chunks, each serialized as an Arrow IPC stream for the
+    /// Arrow IPC and Parquet output formats, or as NDJSON lines.
+    ///
+    /// The chunks are fetched and serialized lazily, as the stream is polled.
+    pub fn into_data_stream(self) -> impl futures::Stream<Item = crate::Result<Vec<u8>>> {
+        let num_chunks = self.doc_addresses.chunks(RECORD_BATCH_NUM_ROWS).len();
+        let split_export = Arc::new(self);
+
+        futures::stream::iter(0..num_chunks)
+            .then(move |chunk_ord| split_export.clone().export_chunk(chunk_ord))
+    }
+
+    async fn export_chunk(self: Arc<Self>, chunk_ord: usize) -> crate::Result<Vec<u8>> {
+        let start = chunk_ord * RECORD_BATCH_NUM_ROWS;
+        let end = self.doc_addresses.len().min(start + RECORD_BATCH_NUM_ROWS);
+
+        let stored_values = if self.export_schema.has_stored_columns() {
+            fetch_stored_values(
+                &self.searcher,
+                &self.export_schema,
+                &self.doc_addresses[start..end],
+            )
+            .await?
+        } else {
+            Vec::new()
+        };
+        let split_export = self.clone();
+        run_cpu_intensive(move || {
+            serialize_record_batch(
+                &split_export.searcher,
+                &split_export.export_schema,
+                &split_export.doc_addresses[start..end],
+                &stored_values,
+                split_export.output_format,
+            )
+        })
+        .await
+        .map_err(|_| {
+            SearchError::Internal(format!(
+                "error when exporting docs for split {}",
+                self.split_id
+            ))
+        })?
+    }
+}
+
+/// Fetches the first value of each stored column for each doc, in doc address order.
+async fn fetch_stored_values(
+    searcher: &Searcher,
+    export_schema: &ExportSchema,
+    doc_addresses: &[DocAddress],
+) -> crate::Result<Vec<Vec<Option<OwnedValue>>>> {
+    let split_schema = searcher.schema();
+    let stored_fields: Vec<_> = export_schema
+        .columns
+        .iter()
+        .filter(|column| column.source == ColumnSource::Stored)
+        .map(|column| split_schema.get_field(&column.field_name).ok())
+        .collect();
+
+    futures::stream::iter(doc_addresses.iter().copied())
+        .map(|doc_address| {
+            let stored_fields = &stored_fields;
+            async move {
+                let doc: TantivyDocument = searcher.doc_async(doc_address).await?;
+                let values = stored_fields
+                    .iter()
+                    .map(|field_opt| field_opt.and_then(|field| doc.get_first(field).cloned()))
+                    .collect::<Vec<_>>();
+                crate::Result::Ok(values)
+            }
+        })
+        .buffered(NUM_CONCURRENT_DOC_REQUESTS)
+        .try_collect()
+        .await
+}
+
+/// Serializes a chunk of at most [`RECORD_BATCH_NUM_ROWS`] docs as a single record batch.
+fn serialize_record_batch(
+    searcher: &Searcher,
+    export_schema: &ExportSchema,
+    doc_addresses: &[DocAddress],
+    stored_values: &[Vec<Option<OwnedValue>>],
+    output_format: OutputFormat,
+) -> crate::Result<Vec<u8>> {
+    let mut segment_columns: HashMap<u32, Vec<Option<FastColumnReader>>> = HashMap::new();
+    let mut buffer = Vec::new();
+
+    let mut record_batch_writer = match output_format {
+        OutputFormat::ArrowIpc | OutputFormat::Parquet => RecordBatchWriter::ArrowIpc(
+            StreamWriter::try_new(&mut buffer, &export_schema.arrow_schema)?,
+        ),
+        OutputFormat::Ndjson => RecordBatchWriter::Ndjson(LineDelimitedWriter::new(&mut buffer)),
+        OutputFormat::Csv | OutputFormat::ClickHouseRowBinary => {
+            return Err(SearchError::Internal(format!(
+                "output format `{}` does not support multi-column exports",
+                output_format.as_str_name()
+            )));
+        }
+    };
+    let mut column_builders: Vec<ColumnBuilder> = export_schema
+        .columns
+        .iter()
+        .map(|column| ColumnBuilder::new(column.field_type, doc_addresses.len()))
+        .collect();
+
+    for (doc_idx, doc_address) in doc_addresses.iter().enumerate() {
+        let segment_column_readers = match segment_columns.entry(doc_address.segment_ord) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+                let column_readers = export_schema
+                    .columns
+                    .iter()
+                    .map(|column| FastColumnReader::open(segment_reader, column))
+                    .collect::<crate::Result<Vec<_>>>()?;
+                entry.insert(column_readers)
+            }
+        };
+        let mut stored_values_iter = stored_values.get(doc_idx).into_iter().flatten();
+
+        for ((column, column_reader_opt), column_builder) in export_schema
+            .columns
+            .iter()
+            .zip(segment_column_readers.iter())
+            .zip(column_builders.iter_mut())
+        {
+            match column.source {
+                ColumnSource::Fast => match column_reader_opt {
+                    Some(column_reader) => column_reader
+                        .append_value(doc_address.doc_id, column_builder)
+                        .map_err(TantivyError::from)?,
+                    None => column_builder.append_null(),
+                },
+                ColumnSource::Stored => {
+                    let value_opt = stored_values_iter.next().and_then(Option::as_ref);
+                    column_builder.append_owned_value(value_opt);
+                }
+            }
+        }
+    }
+    let columns: Vec<ArrayRef> = column_builders
+        .into_iter()
+        .map(ColumnBuilder::finish)
+        .collect();
+    let record_batch = RecordBatch::try_new(export_schema.arrow_schema(), columns)?;
+    record_batch_writer.write(&record_batch)?;
+    record_batch_writer.finish()?;
+    Ok(buffer)
+}
+
+enum RecordBatchWriter<W: Write> {
+    ArrowIpc(StreamWriter<W>),
+    Ndjson(LineDelimitedWriter<W>),
+}
+
+impl<W: Write> RecordBatchWriter<W> {
+    fn write(&mut self, record_batch: &RecordBatch) -> crate::Result<()> {
+        match self {
+            Self::ArrowIpc(writer) => writer.write(record_batch)?,
+            Self::Ndjson(writer) => writer.write(record_batch)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> crate::Result<()> {
+        match self {
+            Self::ArrowIpc(mut writer) => writer.finish()?,
+            Self::Ndjson(mut writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+}
+
+/// Reads the first value of a fast field for a segment.
+enum FastColumnReader {
+    U64(Column<u64>),
+    I64(Column<i64>),
+    F64(Column<f64>),
+    Bool(Column<bool>),
+    Date(Column<DateTime>),
+    IpAddr(Column<Ipv6Addr>),
+    Str(StrColumn),
+    Bytes(BytesColumn),
+}
+
+impl FastColumnReader {
+    fn open(segment_reader: &SegmentReader, column: &ExportColumn) -> crate::Result<Option<Self>> {
+        if column.source != ColumnSource::Fast {
+            return Ok(None);
+        }
+        let fast_fields = segment_reader.fast_fields();
+        let field_name = column.field_name.as_str();
+
+        let column_reader_opt = match column.field_type {
+            Type::U64 => fast_fields.column_opt(field_name)?.map(Self::U64),
+            Type::I64 => fast_fields.column_opt(field_name)?.map(Self::I64),
+            Type::F64 => fast_fields.column_opt(field_name)?.map(Self::F64),
+            Type::Bool => fast_fields.column_opt(field_name)?.map(Self::Bool),
+            Type::Date => fast_fields.column_opt(field_name)?.map(Self::Date),
+            Type::IpAddr => fast_fields.column_opt(field_name)?.map(Self::IpAddr),
+            Type::Str => fast_fields.str(field_name)?.map(Self::Str),
+            Type::Bytes => fast_fields.bytes(field_name)?.map(Self::Bytes),
+            Type::Facet | Type::Json => None,
+        };
+        Ok(column_reader_opt)
+    }
+
+    fn append_value(&self, doc_id: u32, column_builder: &mut ColumnBuilder) -> io::Result<()> {
+        match (self, column_builder) {
+            (Self::U64(column), ColumnBuilder::U64(builder)) => {
+                builder.append_option(column.first(doc_id))
+            }
+            (Self::I64(column), ColumnBuilder::I64(builder)) => {
+                builder.append_option(column.first(doc_id))
+            }
+            (Self::F64(column), ColumnBuilder::F64(builder)) => {
+                builder.append_option(column.first(doc_id))
+            }
+            (Self::Bool(column), ColumnBuilder::Bool(builder)) => {
+                builder.append_option(column.first(doc_id))
+            }
+            (Self::Date(column), ColumnBuilder::Date(builder)) => builder.append_option(
+                column
+                    .first(doc_id)
+                    .map(|date_time| date_time.into_timestamp_micros()),
+            ),
+            (Self::IpAddr(column), ColumnBuilder::Str(builder)) => {
+                builder.append_option(column.first(doc_id).map(format_ip_addr))
+            }
+            (Self::Str(column), ColumnBuilder::Str(builder)) => {
+                let mut value = String::new();
+                match column.ords().first(doc_id) {
+                    Some(term_ord) if column.ord_to_str(term_ord, &mut value)? => {
+                        builder.append_value(value)
+                    }
+                    _ => builder.append_null(),
+                }
+            }
+            (Self::Bytes(column), ColumnBuilder::Bytes(builder)) => {
+                let mut value = Vec::new();
+                match column.term_ords(doc_id).next() {
+                    Some(term_ord) if column.ord_to_bytes(term_ord, &mut value)? => {
+                        builder.append_value(value)
+                    }
+                    _ => builder.append_null(),
+                }
+            }
+            (_, column_builder) => column_builder.append_null(),
+        }
+        Ok(())
+    }
+}
+
+fn format_ip_addr(ip_addr: Ipv6Addr) -> String {
+    match ip_addr.to_ipv4_mapped() {
+        Some(ipv4_addr) => ipv4_addr.to_string(),
+        None => ip_addr.to_string(),
+    }
+}
+
+/// Builds the Arrow array of a column. Multivalued fields are exported as their first value.
+enum ColumnBuilder {
+    U64(UInt64Builder),
+    I64(Int64Builder),
+    F64(Float64Builder),
+    Bool(BooleanBuilder),
+    Date(TimestampMicrosecondBuilder),
+    Str(StringBuilder),
+    Bytes(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(field_type: Type, capacity: usize) -> Self {
+        match field_type {
+            Type::U64 => Self::U64(UInt64Builder::with_capacity(capacity)),
+            Type::I64 => Self::I64(Int64Builder::with_capacity(capacity)),
+            Type::F64 => Self::F64(Float64Builder::with_capacity(capacity)),
+            Type::Bool => Self::Bool(BooleanBuilder::with_capacity(capacity)),
+            Type::Date => Self::Date(
+                TimestampMicrosecondBuilder::with_capacity(capacity).with_timezone("UTC"),
+            ),
+            Type::Bytes => Self::Bytes(BinaryBuilder::new()),
+            Type::Str | Type::IpAddr | Type::Json | Type::Facet => Self::Str(StringBuilder::new()),
+        }
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            Self::U64(builder) => builder.append_null(),
+            Self::I64(builder) => builder.append_null(),
+            Self::F64(builder) => builder.append_null(),
+            Self::Bool(builder) => builder.append_null(),
+            Self::Date(builder) => builder.append_null(),
+            Self::Str(builder) => builder.append_null(),
+            Self::Bytes(builder) => builder.append_null(),
+        }
+    }
+
+    fn append_owned_value(&mut self, value_opt: Option<&OwnedValue>) {
+        let Some(value) = value_opt else {
+            self.append_null();
+            return;
+        };
+        match (self, value) {
+            (Self::U64(builder), OwnedValue::U64(value)) => builder.append_value(*value),
+            (Self::I64(builder), OwnedValue::I64(value)) => builder.append_value(*value),
+            (Self::F64(builder), OwnedValue::F64(value)) => builder.append_value(*value),
+            (Self::Bool(builder), OwnedValue::Bool(value)) => builder.append_value(*value),
+            (Self::Date(builder), OwnedValue::Date(date_time)) => {
+                builder.append_value(date_time.into_timestamp_micros())
+            }
+            (Self::Str(builder), OwnedValue::Str(value)) => builder.append_value(value),
+            (Self::Str(builder), OwnedValue::PreTokStr(pre_tokenized_str)) => {
+                builder.append_value(&pre_tokenized_str.text)
+            }
+            (Self::Str(builder), OwnedValue::IpAddr(ip_addr)) => {
+                builder.append_value(format_ip_addr(*ip_addr))
+            }
+            (Self::Str(builder), OwnedValue::Object(_) | OwnedValue::Array(_)) => {
+                match serde_json::to_string(value) {
+                    Ok(json) => builder.append_value(json),
+                    Err(_) => builder.append_null(),
+                }
+            }
+            (Self::Bytes(builder), OwnedValue::Bytes(value)) => builder.append_value(value),
+            (column_builder, _) => column_builder.append_null(),
+        }
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            Self::U64(mut builder) => Arc::new(builder.finish()),
+            Self::I64(mut builder) => Arc::new(builder.finish()),
+            Self::F64(mut builder) => Arc::new(builder.finish()),
+            Self::Bool(mut builder) => Arc::new(builder.finish()),
+            Self::Date(mut builder) => Arc::new(builder.finish()),
+            Self::Str(mut builder) => Arc::new(builder.finish()),
+            Self::Bytes(mut builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+/// In-memory writer whose content can be drained while an Arrow or Parquet writer holds it.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Bytes {
+        let mut buffer = self.0.lock().unwrap();
+        Bytes::from(std::mem::take(&mut *buffer))
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum ExportWriterKind {
+    ArrowIpc(StreamWriter<SharedBuffer>),
+    Parquet(ArrowWriter<SharedBuffer>),
+    Ndjson,
+}
+
+/// Merges the data exported from each split into a single payload, emitted as the split data
+/// comes in.
+pub(crate) struct ExportWriter {
+    kind: ExportWriterKind,
+    buffer: SharedBuffer,
+}
+
+impl ExportWriter {
+    pub fn try_new(output_format: OutputFormat, arrow_schema: SchemaRef) -> crate::Result<Self> {
+        let buffer = SharedBuffer::default();
+        let kind = match output_format {
+            OutputFormat::ArrowIpc => {
+                ExportWriterKind::ArrowIpc(StreamWriter::try_new(buffer.clone(), &arrow_schema)?)
+            }
+            OutputFormat::Parquet => {
+                let writer_properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .build();
+                ExportWriterKind::Parquet(ArrowWriter::try_new(
+                    buffer.clone(),
+                    arrow_schema,
+                    Some(writer_properties),
+                )?)
+            }
+            OutputFormat::Ndjson => ExportWriterKind::Ndjson,
+            OutputFormat::Csv | OutputFormat::ClickHouseRowBinary => {
+                return Err(SearchError::Internal(format!(
+                    "output format `{}` does not support multi-column exports",
+                    output_format.as_str_name()
+                )));
+            }
+        };
+        Ok(Self { kind, buffer })
+    }
+
+    /// Appends the data exported from a split and returns the bytes ready to be sent.
+    pub fn write_split_data(&mut self, split_data: Bytes) -> crate::Result<Bytes> {
+        if let ExportWriterKind::Ndjson = self.kind {
+            return Ok(split_data);
+        }
+        let stream_reader = StreamReader::try_new(Cursor::new(split_data), None)?;
+
+        for record_batch_res in stream_reader {
+            let record_batch = record_batch_res?;
+            match &mut self.kind {
+                ExportWriterKind::ArrowIpc(writer) => writer.write(&record_batch)?,
+                ExportWriterKind::Parquet(writer) => writer.write(&record_batch)?,
+                ExportWriterKind::Ndjson => {}
+            }
+        }
+        if let ExportWriterKind::Parquet(writer) = &mut self.kind {
+            // Closes the current row group so that its bytes can be sent.
+            writer.flush()?;
+        }
+        Ok(self.buffer.take())
+    }
+
+    /// Terminates the payload and returns the remaining bytes.
+    pub fn finish(self) -> crate::Result<Bytes> {
+        match self.kind {
+            ExportWriterKind::ArrowIpc(mut writer) => writer.finish()?,
+            ExportWriterKind::Parquet(writer) => {
+                writer.close()?;
+            }
+            ExportWriterKind::Ndjson => {}
+        }
+        Ok(self.buffer.take())
+    }
+}
+
+/// Merges the data exported from each split by the leaves into a single payload.
+pub(crate) fn merge_split_exports<S>(
+    split_data_stream: S,
+    export_writer: ExportWriter,
+) -> impl futures::Stream<Item = crate::Result<Bytes>>
+where
+    S: futures::Stream<Item = crate::Result<Bytes>> + Unpin,
+{
+    futures::stream::unfold(
+        (split_data_stream, Some(export_writer)),
+        |(mut split_data_stream, mut export_writer_opt)| async move {
+            let export_writer = export_writer_opt.as_mut()?;
+            let bytes_res = match split_data_stream.next().await {
+                Some(Ok(split_data)) => export_writer.write_split_data(split_data),
+                Some(Err(error)) => Err(error),
+                None => export_writer_opt.take()?.finish(),
+            };
+            Some((bytes_res, (split_data_stream, export_writer_opt)))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{AsArray, Int64Array};
+    use arrow::datatypes::UInt64Type;
+    use tantivy::schema::{FacetOptions, FAST, STORED, TEXT};
+
+    use super::*;
+
+    fn test_schema() -> Schema {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("count", FAST);
+        schema_builder.add_text_field("body", TEXT | STORED);
+        schema_builder.add_text_field("title", TEXT);
+        schema_builder.add_facet_field("category", FacetOptions::default().set_stored());
+        schema_builder.add_json_field("attributes", STORED);
+        schema_builder.build()
+    }
+
+    fn split_data(arrow_schema: &SchemaRef, counts: &[Option<u64>]) -> Bytes {
+        let record_batch = RecordBatch::try_new(
+            arrow_schema.clone(),
+            vec![Arc::new(arrow::array::UInt64Array::from(counts.to_vec()))],
+        )
+        .unwrap();
+        let mut buffer = Vec::new();
+        let mut stream_writer = StreamWriter::try_new(&mut buffer, arrow_schema).unwrap();
+        stream_writer.write(&record_batch).unwrap();
+        stream_writer.finish().unwrap();
+        drop(stream_writer);
+        Bytes::from(buffer)
+    }
+
+    #[test]
+    fn test_export_field_names() {
+        let mut search_stream_request = SearchStreamRequest {
+            fast_field: "count".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(export_field_names(&search_stream_request), ["count"]);
+
+        search_stream_request.fields = vec!["body".to_string(), "count".to_string()];
+        assert_eq!(
+            export_field_names(&search_stream_request),
+            ["body", "count"]
+        );
+
+        search_stream_request.fast_field.clear();
+        search_stream_request.fields.clear();
+        assert!(export_field_names(&search_stream_request).is_empty());
+    }
+
+    #[test]
+    fn test_export_schema() {
+        let schema = test_schema();
+        let export_schema = ExportSchema::try_new(
+            &schema,
+            &[
+                "count".to_string(),
+                "body".to_string(),
+                "attributes".to_string(),
+            ],
+        )
+        .unwrap();
+        let arrow_schema = export_schema.arrow_schema();
+        assert_eq!(arrow_schema.fields().len(), 3);
+        assert_eq!(arrow_schema.field(0).data_type(), &DataType::UInt64);
+        assert_eq!(arrow_schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(arrow_schema.field(2).data_type(), &DataType::Utf8);
+        assert!(arrow_schema
+            .fields()
+            .iter()
+            .all(|field| field.is_nullable()));
+        assert_eq!(
+            export_schema.fast_field_names().collect::<Vec<_>>(),
+            ["count"]
+        );
+        assert!(export_schema.has_stored_columns());
+
+        let error = ExportSchema::try_new(&schema, &[]).unwrap_err();
+        assert!(matches!(error, SearchError::InvalidArgument(_)));
+
+        let error = ExportSchema::try_new(&schema, &["unknown".to_string()]).unwrap_err();
+        assert!(
+            matches!(error, SearchError::InvalidArgument(message) if message == "unknown field `unknown`")
+        );
+        let error = ExportSchema::try_new(&schema, &["title".to_string()]).unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::InvalidArgument(message)
+                if message == "field `title` must be a fast field or a stored field to be exported"
+        ));
+        let error = ExportSchema::try_new(&schema, &["category".to_string()]).unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::InvalidArgument(message)
+                if message == "field `category` of type `facet` cannot be exported"
+        ));
+    }
+
+    #[test]
+    fn test_column_builder_append_owned_value() {
+        let mut column_builder = ColumnBuilder::new(Type::I64, 3);
+        column_builder.append_owned_value(Some(&OwnedValue::I64(-1)));
+        column_builder.append_owned_value(None);
+        column_builder.append_owned_value(Some(&OwnedValue::Str("-2".to_string())));
+        let array = column_builder.finish();
+        assert_eq!(
+            array.as_any().downcast_ref::<Int64Array>().unwrap(),
+            &Int64Array::from(vec![Some(-1), None, None])
+        );
+
+        let mut column_builder = ColumnBuilder::new(Type::IpAddr, 1);
+        column_builder.append_owned_value(Some(&OwnedValue::IpAddr(
+            "127.0.0.1"
+                .parse::<std::net::Ipv4Addr>()
+                .unwrap()
+                .to_ipv6_mapped(),
+        )));
+        let array = column_builder.finish();
+        assert_eq!(array.as_string::<i32>().value(0), "127.0.0.1");
+    }
+
+    #[test]
+    fn test_export_writer_arrow_ipc() {
+        let export_schema = ExportSchema::try_new(&test_schema(), &["count".to_string()]).unwrap();
+        let arrow_schema = export_schema.arrow_schema();
+        let mut export_writer =
+            ExportWriter::try_new(OutputFormat::ArrowIpc, arrow_schema.clone()).unwrap();
+
+        let mut payload = Vec::new();
+        payload.extend_from_slice(
+            &export_writer
+                .write_split_data(split_data(&arrow_schema, &[Some(1), None]))
+                .unwrap(),
+        );
+        payload.extend_from_slice(
+            &export_writer
+                .write_split_data(split_data(&arrow_schema, &[Some(3)]))
+                .unwrap(),
+        );
+        payload.extend_from_slice(&export_writer.finish().unwrap());
+
+        let stream_reader = StreamReader::try_new(Cursor::new(payload), None).unwrap();
+        assert_eq!(stream_reader.schema(), arrow_schema);
+        let counts: Vec<Option<u64>> = stream_reader
+            .flat_map(|record_batch_res| {
+                let record_batch = record_batch_res.unwrap();
+                record_batch
+                    .column(0)
+                    .as_primitive::<UInt64Type>()
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(counts, [Some(1), None, Some(3)]);
+    }
+
+    #[test]
+    fn test_export_writer_parquet() {
+        let export_schema = ExportSchema::try_new(&test_schema(), &["count".to_string()]).unwrap();
+        let arrow_schema = export_schema.arrow_schema();
+        let mut export_writer =
+            ExportWriter::try_new(OutputFormat::Parquet, arrow_schema.clone()).unwrap();
+
+        let mut payload = Vec::new();
+        let split_bytes = export_writer
+            .write_split_data(split_data(&arrow_schema, &[Some(1), Some(2)]))
+            .unwrap();
+        // The row group of the split is flushed right away.
+        assert!(!split_bytes.is_empty());
+        payload.extend_from_slice(&split_bytes);
+        payload.extend_from_slice(&export_writer.finish().unwrap());
+
+        assert_eq!(&payload[..4], b"PAR1");
+        assert_eq!(&payload[payload.len() - 4..], b"PAR1");
+    }
+
+    #[test]
+    fn test_export_writer_ndjson() {
+        let export_schema = ExportSchema::try_new(&test_schema(), &["count".to_string()]).unwrap();
+        let mut export_writer =
+            ExportWriter::try_new(OutputFormat::Ndjson, export_schema.arrow_schema()).unwrap();
+        let split_data = Bytes::from_static(b"{\"count\":1}\n");
+        assert_eq!(
+            export_writer.write_split_data(split_data.clone()).unwrap(),
+            split_data
+        );
+        assert!(export_writer.finish().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_export_writer_unsupported_output_format() {
+        let export_schema = ExportSchema::try_new(&test_schema(), &["count".to_string()]).unwrap();
+        assert!(ExportWriter::try_new(OutputFormat::Csv, export_schema.arrow_schema()).is_err());
+    }
+}
@@ -22,24 +22,26 @@ use std::hash::Hash;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use futures::{FutureExt, StreamExt};
+use futures::{StreamExt, TryStreamExt};
 use quickwit_common::PrettySample;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::search::{
     LeafSearchStreamResponse, OutputFormat, SearchRequest, SearchStreamRequest,
     SplitIdAndFooterOffsets,
 };
+use quickwit_query::query_ast::QueryAst;
 use quickwit_storage::Storage;
 use tantivy::columnar::{DynamicColumn, HasAssociatedColumnType};
 use tantivy::fastfield::Column;
 use tantivy::query::Query;
 use tantivy::schema::{Field, Schema, Type};
 use tantivy::{DateTime, ReloadPolicy, Searcher};
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::*;
 
 use super::collector::{PartionnedFastFieldCollector, PartitionValues};
-use super::FastFieldCollector;
+use super::export::{is_multi_column_output_format, SplitExport};
+use super::{FastFieldCollector, LEAF_SEARCH_STREAM_CHANNEL_CAPACITY};
 use crate::filters::{create_timestamp_filter_builder, TimestampFilterBuilder};
 use crate::leaf::{open_index_with_caches, rewrite_start_end_time_bounds, warmup};
 use crate::service::SearcherContext;
@@ -59,9 +61,10 @@ pub async fn leaf_search_stream(
     storage: Arc<dyn Storage>,
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
-) -> UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>> {
+) -> ReceiverStream<crate::Result<LeafSearchStreamResponse>> {
     info!(split_offsets = ?PrettySample::new(&splits, 5));
-    let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (result_sender, result_receiver) =
+        tokio::sync::mpsc::channel(LEAF_SEARCH_STREAM_CHANNEL_CAPACITY);
     let span = info_span!("leaf_search_stream",);
     tokio::spawn(
         async move {
@@ -69,7 +72,7 @@ pub async fn leaf_search_stream(
                 leaf_search_results_stream(searcher_context, request, storage, splits, doc_mapper)
                     .await;
             while let Some(item) = stream.next().await {
-                if let Err(error) = result_sender.send(item) {
+                if let Err(error) = result_sender.send(item).await {
                     error!(
                         "Failed to send leaf search stream result. Stop sending. Cause: {}",
                         error
@@ -80,7 +83,7 @@ pub async fn leaf_search_stream(
         }
         .instrument(span),
     );
-    UnboundedReceiverStream::new(result_receiver)
+    ReceiverStream::new(result_receiver)
 }
 
 async fn leaf_search_results_stream(
@@ -89,22 +92,84 @@ async fn leaf_search_results_stream(
     storage: Arc<dyn Storage>,
     splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
-) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Sync + Send + 'static {
+) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Send + 'static {
     let max_num_concurrent_split_streams = searcher_context
         .searcher_config
         .max_num_concurrent_split_streams;
+    let export_output_format_opt = OutputFormat::from_i32(request.output_format)
+        .filter(|output_format| is_multi_column_output_format(*output_format));
     futures::stream::iter(splits)
         .map(move |split| {
-            leaf_search_stream_single_split(
+            if let Some(output_format) = export_output_format_opt {
+                return leaf_export_stream_single_split(
+                    searcher_context.clone(),
+                    split,
+                    doc_mapper.clone(),
+                    request.clone(),
+                    storage.clone(),
+                    output_format,
+                )
+                .boxed();
+            }
+            futures::stream::once(leaf_search_stream_single_split(
                 searcher_context.clone(),
                 split,
                 doc_mapper.clone(),
                 request.clone(),
                 storage.clone(),
-            )
-            .shared()
+            ))
+            .boxed()
+        })
+        .flatten_unordered(max_num_concurrent_split_streams)
+}
+
+/// Exports the documents of a single split, sending one response per record batch so that the
+/// documents of the split are never all held in memory at once.
+fn leaf_export_stream_single_split(
+    searcher_context: Arc<SearcherContext>,
+    split: SplitIdAndFooterOffsets,
+    doc_mapper: Arc<dyn DocMapper>,
+    mut stream_request: SearchStreamRequest,
+    storage: Arc<dyn Storage>,
+    output_format: OutputFormat,
+) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Send + 'static {
+    let split_id = split.split_id.clone();
+    let span = info_span!("leaf_export_stream_single_split", split_id = %split.split_id);
+    let data_stream_fut = async move {
+        let leaf_split_stream_permit = searcher_context
+            .split_stream_semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.");
+        rewrite_start_end_time_bounds(
+            &mut stream_request.start_timestamp,
+            &mut stream_request.end_timestamp,
+            &split,
+        );
+        let split_export = SplitExport::open(
+            &searcher_context,
+            &split,
+            doc_mapper,
+            &stream_request,
+            storage,
+            output_format,
+        )
+        .await?;
+        let data_stream = split_export.into_data_stream().inspect(move |_| {
+            // The permit is released once the whole split is exported.
+            let _leaf_split_stream_permit = &leaf_split_stream_permit;
+        });
+        crate::Result::Ok(data_stream)
+    }
+    .instrument(span);
+
+    futures::stream::once(data_stream_fut)
+        .try_flatten()
+        .map_ok(move |data| LeafSearchStreamResponse {
+            data,
+            split_id: split_id.clone(),
         })
-        .buffer_unordered(max_num_concurrent_split_streams)
 }
 
 /// Apply a leaf search on a single split.
@@ -127,6 +192,9 @@ async fn leaf_search_stream_single_split(
         &split,
     );
 
+    let output_format = OutputFormat::from_i32(stream_request.output_format)
+        .ok_or_else(|| SearchError::Internal("invalid output format specified".to_string()))?;
+
     let index = open_index_with_caches(
         &searcher_context,
         storage,
@@ -143,9 +211,6 @@ async fn leaf_search_stream_single_split(
         doc_mapper.as_ref(),
     )?);
 
-    let output_format = OutputFormat::from_i32(stream_request.output_format)
-        .ok_or_else(|| SearchError::Internal("invalid output format specified".to_string()))?;
-
     if request_fields.partition_by_fast_field.is_some()
         && output_format != OutputFormat::ClickHouseRowBinary
     {
@@ -157,9 +222,14 @@ async fn leaf_search_stream_single_split(
     }
 
     let search_request = Arc::new(SearchRequest::try_from(stream_request.clone())?);
-    let query_ast = serde_json::from_str(&search_request.query_ast)
+    let query_ast: QueryAst = serde_json::from_str(&search_request.query_ast)
         .map_err(|err| SearchError::InvalidQuery(err.to_string()))?;
-    let (query, mut warmup_info) = doc_mapper.query(split_schema.clone(), &query_ast, false)?;
+    let (query, mut warmup_info) = doc_mapper.query(
+        split_schema.clone(),
+        &query_ast,
+        false,
+        Some(&split.split_id),
+    )?;
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
@@ -460,6 +530,7 @@ mod tests {
 
     use super::*;
     use crate::extract_split_and_footer_offsets;
+    use crate::search_stream::export::RECORD_BATCH_NUM_ROWS;
 
     #[tokio::test]
     async fn test_leaf_search_stream_to_csv_output_with_filtering() -> anyhow::Result<()> {
@@ -498,6 +569,7 @@ mod tests {
             fast_field: "ts".to_string(),
             output_format: 0,
             partition_by_field: None,
+            fields: Vec::new(),
         };
         let splits = test_sandbox
             .metastore()
@@ -577,6 +649,7 @@ mod tests {
             fast_field: "ts".to_string(),
             output_format: 0,
             partition_by_field: None,
+            fields: Vec::new(),
         };
         let splits = test_sandbox
             .metastore()
@@ -634,6 +707,7 @@ mod tests {
             fast_field: "app".to_string(),
             output_format: 0,
             partition_by_field: None,
+            fields: Vec::new(),
         };
         let splits = test_sandbox
             .metastore()
@@ -724,6 +798,7 @@ mod tests {
             fast_field: "fast_field".to_string(),
             output_format: 1,
             partition_by_field: Some(String::from("partition_by_fast_field")),
+            fields: Vec::new(),
         };
         let splits = test_sandbox
             .metastore()
@@ -782,4 +857,124 @@ mod tests {
         }
         partitions_values
     }
+
+    #[tokio::test]
+    async fn test_leaf_search_stream_to_ndjson_output_with_multiple_fields() -> anyhow::Result<()> {
+        let index_id = "single-node-ndjson";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: count
+                type: u64
+                fast: true
+              - name: ts
+                type: datetime
+                fast: true
+            timestamp_field: ts
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"]).await?;
+        let mut docs = Vec::new();
+        for i in 0..3 {
+            docs.push(json!({"body": format!("info {i}"), "count": i, "ts": 72057595 + i}));
+        }
+        docs.push(json!({"body": "info without count", "ts": 72057595}));
+        test_sandbox.add_documents(docs).await?;
+
+        let request = SearchStreamRequest {
+            index_id: index_id.to_string(),
+            query_ast: qast_json_helper("info", &["body"]),
+            output_format: OutputFormat::Ndjson as i32,
+            fields: vec!["count".to_string(), "body".to_string()],
+            ..Default::default()
+        };
+        let splits = test_sandbox
+            .metastore()
+            .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+            .await?
+            .collect_splits()
+            .await
+            .unwrap();
+        let splits_offsets = splits
+            .into_iter()
+            .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+            .collect();
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        let mut single_node_stream = leaf_search_stream(
+            searcher_context,
+            request,
+            test_sandbox.storage(),
+            splits_offsets,
+            test_sandbox.doc_mapper(),
+        )
+        .await;
+        let res = single_node_stream.next().await.expect("no leaf result")?;
+        let expected_lines = [
+            r#"{"count":0,"body":"info 0"}"#,
+            r#"{"count":1,"body":"info 1"}"#,
+            r#"{"count":2,"body":"info 2"}"#,
+            r#"{"body":"info without count"}"#,
+        ];
+        assert_eq!(
+            from_utf8(&res.data)?,
+            format!("{}\n", expected_lines.join("\n"))
+        );
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_leaf_search_stream_to_ndjson_output_sends_one_response_per_record_batch(
+    ) -> anyhow::Result<()> {
+        let index_id = "single-node-ndjson-record-batches";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+                stored: true
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "", &["body"]).await?;
+        let num_docs = RECORD_BATCH_NUM_ROWS + 1;
+        let docs = (0..num_docs)
+            .map(|i| json!({"body": format!("info {i}")}))
+            .collect();
+        test_sandbox.add_documents(docs).await?;
+
+        let request = SearchStreamRequest {
+            index_id: index_id.to_string(),
+            query_ast: qast_json_helper("info", &["body"]),
+            output_format: OutputFormat::Ndjson as i32,
+            fields: vec!["body".to_string()],
+            ..Default::default()
+        };
+        let splits = test_sandbox
+            .metastore()
+            .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+            .await?
+            .collect_splits()
+            .await
+            .unwrap();
+        let splits_offsets = splits
+            .into_iter()
+            .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+            .collect();
+        let searcher_context = Arc::new(SearcherContext::for_test());
+        let responses: Vec<LeafSearchStreamResponse> = leaf_search_stream(
+            searcher_context,
+            request,
+            test_sandbox.storage(),
+            splits_offsets,
+            test_sandbox.doc_mapper(),
+        )
+        .await
+        .try_collect()
+        .await?;
+        let num_lines_per_response = responses
+            .iter()
+            .map(|response| from_utf8(&response.data).unwrap().lines().count())
+            .collect::<Vec<_>>();
+        assert_eq!(num_lines_per_response, [RECORD_BATCH_NUM_ROWS, 1]);
+        test_sandbox.assert_quit().await;
+        Ok(())
+    }
 }
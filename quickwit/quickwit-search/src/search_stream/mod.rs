@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod collector;
+mod export;
 mod leaf;
 mod root;
 
@@ -33,6 +34,11 @@ use tantivy::columnar::MonotonicallyMappableToU64;
 
 use self::collector::PartitionValues;
 
+/// Capacity of the channels carrying leaf search stream responses. Each response holds the data
+/// of a whole split, so a small capacity is enough to apply backpressure on the leaves when the
+/// client consumes the stream slowly.
+pub(crate) const LEAF_SEARCH_STREAM_CHANNEL_CAPACITY: usize = 2;
+
 pub trait ToLittleEndian {
     fn to_le_bytes(&self) -> [u8; 8];
 }
@@ -66,6 +72,15 @@ pub fn serialize<T: ToLittleEndian + Display>(
     match format {
         OutputFormat::Csv => serialize_csv(values, buffer),
         OutputFormat::ClickHouseRowBinary => serialize_click_house_row_binary(values, buffer),
+        OutputFormat::ArrowIpc | OutputFormat::Parquet | OutputFormat::Ndjson => {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "output format `{}` is only supported for multi-column exports",
+                    format.as_str_name()
+                ),
+            ))
+        }
     }
 }
 
@@ -26,11 +26,17 @@ use quickwit_config::build_doc_mapper;
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_metastore::IndexMetadataResponseExt;
 use quickwit_proto::metastore::{IndexMetadataRequest, MetastoreService, MetastoreServiceClient};
-use quickwit_proto::search::{LeafSearchStreamRequest, SearchRequest, SearchStreamRequest};
+use quickwit_proto::search::{
+    LeafSearchStreamRequest, OutputFormat, SearchRequest, SearchStreamRequest,
+};
 use quickwit_query::query_ast::QueryAst;
 use tokio_stream::StreamMap;
 use tracing::*;
 
+use super::export::{
+    export_field_names, is_multi_column_output_format, merge_split_exports, ExportSchema,
+    ExportWriter,
+};
 use crate::cluster_client::ClusterClient;
 use crate::root::{refine_start_end_timestamp_from_ast, SearchJob};
 use crate::{list_relevant_splits, SearchError};
@@ -73,9 +79,36 @@ pub async fn root_search_stream(
     }
 
     // Validates the query by effectively building it against the current schema.
-    doc_mapper.query(doc_mapper.schema(), &query_ast_resolved, true)?;
+    doc_mapper.query(doc_mapper.schema(), &query_ast_resolved, true, None)?;
     search_stream_request.query_ast = serde_json::to_string(&query_ast_resolved)?;
 
+    let output_format = OutputFormat::from_i32(search_stream_request.output_format)
+        .ok_or_else(|| SearchError::InvalidArgument("invalid output format".to_string()))?;
+    let export_writer_opt = if is_multi_column_output_format(output_format) {
+        if search_stream_request.partition_by_field.is_some() {
+            return Err(SearchError::InvalidArgument(format!(
+                "output format `{}` does not support `partition_by_field`",
+                output_format.as_str_name()
+            )));
+        }
+        let export_schema = ExportSchema::try_new(
+            &doc_mapper.schema(),
+            &export_field_names(&search_stream_request),
+        )?;
+        Some(ExportWriter::try_new(
+            output_format,
+            export_schema.arrow_schema(),
+        )?)
+    } else {
+        if !search_stream_request.fields.is_empty() {
+            return Err(SearchError::InvalidArgument(format!(
+                "output format `{}` does not support `fields`, use `fast_field` instead",
+                output_format.as_str_name()
+            )));
+        }
+        None
+    };
+
     let search_request = SearchRequest::try_from(search_stream_request.clone())?;
     let split_metadatas = list_relevant_splits(
         vec![index_uid],
@@ -110,9 +143,15 @@ pub async fn root_search_stream(
             .await;
         stream_map.insert(leaf_ord, leaf_stream);
     }
-    Ok(stream_map
+    let split_data_stream = stream_map
         .map(|(_leaf_ord, result)| result)
-        .map_ok(|leaf_response| Bytes::from(leaf_response.data)))
+        .map_ok(|leaf_response| Bytes::from(leaf_response.data));
+
+    if let Some(export_writer) = export_writer_opt {
+        let export_stream = merge_split_exports(split_data_stream, export_writer);
+        return Ok(export_stream.left_stream());
+    }
+    Ok(split_data_stream.right_stream())
 }
 
 fn jobs_to_leaf_request(
@@ -138,7 +177,7 @@ mod tests {
     use quickwit_proto::metastore::{IndexMetadataResponse, ListSplitsResponse};
     use quickwit_proto::search::OutputFormat;
     use quickwit_query::query_ast::qast_json_helper;
-    use tokio_stream::wrappers::UnboundedReceiverStream;
+    use tokio_stream::wrappers::ReceiverStream;
 
     use super::*;
     use crate::{searcher_pool_for_test, MockSearchService, SearchJobPlacer};
@@ -166,18 +205,22 @@ mod tests {
             Ok(ServiceStream::from(vec![Ok(splits)]))
         });
         let mut mock_search_service = MockSearchService::new();
-        let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
-        result_sender.send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
-            data: b"123".to_vec(),
-            split_id: "split_1".to_string(),
-        }))?;
-        result_sender.send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
-            data: b"456".to_vec(),
-            split_id: "split_1".to_string(),
-        }))?;
+        let (result_sender, result_receiver) = tokio::sync::mpsc::channel(2);
+        result_sender
+            .send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
+                data: b"123".to_vec(),
+                split_id: "split_1".to_string(),
+            }))
+            .await?;
+        result_sender
+            .send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
+                data: b"456".to_vec(),
+                split_id: "split_1".to_string(),
+            }))
+            .await?;
         mock_search_service.expect_leaf_search_stream().return_once(
             |_leaf_search_req: quickwit_proto::search::LeafSearchStreamRequest| {
-                Ok(UnboundedReceiverStream::new(result_receiver))
+                Ok(ReceiverStream::new(result_receiver))
             },
         );
         // The test will hang on indefinitely if we don't drop the receiver.
@@ -224,18 +267,22 @@ mod tests {
             Ok(ServiceStream::from(vec![Ok(splits)]))
         });
         let mut mock_search_service = MockSearchService::new();
-        let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
-        result_sender.send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
-            data: b"123".to_vec(),
-            split_id: "1".to_string(),
-        }))?;
-        result_sender.send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
-            data: b"456".to_vec(),
-            split_id: "2".to_string(),
-        }))?;
+        let (result_sender, result_receiver) = tokio::sync::mpsc::channel(2);
+        result_sender
+            .send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
+                data: b"123".to_vec(),
+                split_id: "1".to_string(),
+            }))
+            .await?;
+        result_sender
+            .send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
+                data: b"456".to_vec(),
+                split_id: "2".to_string(),
+            }))
+            .await?;
         mock_search_service.expect_leaf_search_stream().return_once(
             |_leaf_search_req: quickwit_proto::search::LeafSearchStreamRequest| {
-                Ok(UnboundedReceiverStream::new(result_receiver))
+                Ok(ReceiverStream::new(result_receiver))
             },
         );
         // The test will hang on indefinitely if we don't drop the sender.
@@ -285,18 +332,22 @@ mod tests {
             Ok(ServiceStream::from(vec![Ok(splits)]))
         });
         let mut mock_search_service = MockSearchService::new();
-        let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
-        result_sender.send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
-            data: b"123".to_vec(),
-            split_id: "split1".to_string(),
-        }))?;
-        result_sender.send(Err(SearchError::Internal("error".to_string())))?;
+        let (result_sender, result_receiver) = tokio::sync::mpsc::channel(2);
+        result_sender
+            .send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
+                data: b"123".to_vec(),
+                split_id: "split1".to_string(),
+            }))
+            .await?;
+        result_sender
+            .send(Err(SearchError::Internal("error".to_string())))
+            .await?;
         mock_search_service
             .expect_leaf_search_stream()
             .withf(|request| request.split_offsets.len() == 2) // First request.
             .return_once(
                 |_leaf_search_req: quickwit_proto::search::LeafSearchStreamRequest| {
-                    Ok(UnboundedReceiverStream::new(result_receiver))
+                    Ok(ReceiverStream::new(result_receiver))
                 },
             );
         mock_search_service
@@ -376,4 +427,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_root_search_stream_with_invalid_export_fields() -> anyhow::Result<()> {
+        let mut mock_metastore = MetastoreServiceClient::mock();
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///test-index");
+        mock_metastore.expect_index_metadata().returning(move |_| {
+            Ok(IndexMetadataResponse::try_from_index_metadata(&index_metadata).unwrap())
+        });
+        let searcher_pool = searcher_pool_for_test([("127.0.0.1:1001", MockSearchService::new())]);
+        let search_job_placer = SearchJobPlacer::new(searcher_pool);
+        let metastore = MetastoreServiceClient::from(mock_metastore);
+
+        let invalid_requests = [
+            // `fields` is not supported by the CSV output format.
+            quickwit_proto::search::SearchStreamRequest {
+                index_id: "test-index".to_string(),
+                query_ast: qast_json_helper("test", &["body"]),
+                output_format: OutputFormat::Csv as i32,
+                fields: vec!["timestamp".to_string()],
+                ..Default::default()
+            },
+            // `partition_by_field` is not supported by the Parquet output format.
+            quickwit_proto::search::SearchStreamRequest {
+                index_id: "test-index".to_string(),
+                query_ast: qast_json_helper("test", &["body"]),
+                output_format: OutputFormat::Parquet as i32,
+                fields: vec!["timestamp".to_string()],
+                partition_by_field: Some("timestamp".to_string()),
+                ..Default::default()
+            },
+            // `owner` is neither a fast field nor a stored field.
+            quickwit_proto::search::SearchStreamRequest {
+                index_id: "test-index".to_string(),
+                query_ast: qast_json_helper("test", &["body"]),
+                output_format: OutputFormat::Ndjson as i32,
+                fields: vec!["timestamp".to_string(), "owner".to_string()],
+                ..Default::default()
+            },
+            // No field to export.
+            quickwit_proto::search::SearchStreamRequest {
+                index_id: "test-index".to_string(),
+                query_ast: qast_json_helper("test", &["body"]),
+                output_format: OutputFormat::ArrowIpc as i32,
+                ..Default::default()
+            },
+        ];
+        for invalid_request in invalid_requests {
+            let error = root_search_stream(
+                invalid_request,
+                metastore.clone(),
+                ClusterClient::new(search_job_placer.clone()),
+            )
+            .await
+            .err()
+            .unwrap();
+            assert!(matches!(error, SearchError::InvalidArgument(_)));
+        }
+        Ok(())
+    }
 }
@@ -34,13 +34,14 @@ use quickwit_proto::search::{
     LeafSearchStreamRequest, LeafSearchStreamResponse, ListFieldsRequest, ListFieldsResponse,
     ListTermsRequest, ListTermsResponse, PutKvRequest, ReportSplitsRequest, ReportSplitsResponse,
     ScrollRequest, SearchRequest, SearchResponse, SearchStreamRequest, SnippetRequest,
+    SourceFilter,
 };
 use quickwit_storage::{
     MemorySizedCache, QuickwitCache, SplitCache, StorageCache, StorageResolver,
 };
 use tantivy::aggregation::AggregationLimits;
 use tokio::sync::Semaphore;
-use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::wrappers::ReceiverStream;
 
 use crate::leaf_cache::LeafSearchCache;
 use crate::list_fields::{leaf_list_fields, root_list_fields};
@@ -100,7 +101,7 @@ pub trait SearchService: 'static + Send + Sync {
     async fn leaf_search_stream(
         &self,
         request: LeafSearchStreamRequest,
-    ) -> crate::Result<UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>>>;
+    ) -> crate::Result<ReceiverStream<crate::Result<LeafSearchStreamResponse>>>;
 
     /// Root search API.
     /// This RPC identifies the set of splits on which the query should run on,
@@ -220,6 +221,7 @@ impl SearchService for SearchServiceImpl {
         let storage = self.storage_resolver.resolve(&index_uri).await?;
         let snippet_request_opt: Option<&SnippetRequest> =
             fetch_docs_request.snippet_request.as_ref();
+        let source_filter_opt: Option<&SourceFilter> = fetch_docs_request.source_filter.as_ref();
         let doc_mapper = deserialize_doc_mapper(&fetch_docs_request.doc_mapper)?;
         let fetch_docs_response = fetch_docs(
             self.searcher_context.clone(),
@@ -228,6 +230,7 @@ impl SearchService for SearchServiceImpl {
             &fetch_docs_request.split_offsets,
             doc_mapper,
             snippet_request_opt,
+            source_filter_opt,
         )
         .await?;
 
@@ -250,7 +253,7 @@ impl SearchService for SearchServiceImpl {
     async fn leaf_search_stream(
         &self,
         leaf_stream_request: LeafSearchStreamRequest,
-    ) -> crate::Result<UnboundedReceiverStream<crate::Result<LeafSearchStreamResponse>>> {
+    ) -> crate::Result<ReceiverStream<crate::Result<LeafSearchStreamResponse>>> {
         let stream_request = leaf_stream_request
             .request
             .ok_or_else(|| SearchError::Internal("no search request".to_string()))?;
@@ -443,7 +446,7 @@ pub struct SearcherContext {
     /// Split footer cache.
     pub split_footer_cache: MemorySizedCache<String>,
     /// Counting semaphore to limit concurrent split stream requests.
-    pub split_stream_semaphore: Semaphore,
+    pub split_stream_semaphore: Arc<Semaphore>,
     /// Recent sub-query cache.
     pub leaf_search_cache: LeafSearchCache,
     /// Search split cache. `None` if no split cache is configured.
@@ -482,8 +485,9 @@ impl SearcherContext {
         let leaf_search_split_semaphore = Arc::new(Semaphore::new(
             searcher_config.max_num_concurrent_split_searches,
         ));
-        let split_stream_semaphore =
-            Semaphore::new(searcher_config.max_num_concurrent_split_streams);
+        let split_stream_semaphore = Arc::new(Semaphore::new(
+            searcher_config.max_num_concurrent_split_streams,
+        ));
         let fast_field_cache_capacity = searcher_config.fast_field_cache_capacity.as_u64() as usize;
         let storage_long_term_cache = Arc::new(QuickwitCache::new(fast_field_cache_capacity));
         let leaf_search_cache =
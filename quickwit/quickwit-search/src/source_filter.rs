@@ -0,0 +1,209 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_proto::search::SourceFilter;
+use serde_json::{Map as JsonObject, Value as JsonValue};
+
+/// Filters the fields of a document according to the includes and excludes
+/// path patterns of a [`SourceFilter`].
+///
+/// The semantics are those of Elasticsearch's `_source` filtering:
+/// - field paths are dot-separated and `*` matches any sequence of characters, dots included;
+/// - a pattern matching an object path matches the whole object;
+/// - excludes take precedence over includes;
+/// - objects left empty by the filtering are removed.
+pub(crate) fn filter_source(
+    source_filter: &SourceFilter,
+    doc: JsonObject<String, JsonValue>,
+) -> JsonObject<String, JsonValue> {
+    let included = source_filter.includes.is_empty();
+    let mut path = String::new();
+    filter_object(source_filter, doc, &mut path, included)
+}
+
+fn filter_object(
+    source_filter: &SourceFilter,
+    object: JsonObject<String, JsonValue>,
+    path: &mut String,
+    included: bool,
+) -> JsonObject<String, JsonValue> {
+    let mut filtered_object = JsonObject::new();
+    for (key, value) in object {
+        let parent_path_len = path.len();
+        if parent_path_len > 0 {
+            path.push('.');
+        }
+        path.push_str(&key);
+        if let Some(filtered_value) = filter_value(source_filter, value, path, included) {
+            filtered_object.insert(key, filtered_value);
+        }
+        path.truncate(parent_path_len);
+    }
+    filtered_object
+}
+
+fn filter_value(
+    source_filter: &SourceFilter,
+    value: JsonValue,
+    path: &mut String,
+    included: bool,
+) -> Option<JsonValue> {
+    if matches_any_pattern(&source_filter.excludes, path) {
+        return None;
+    }
+    let included = included || matches_any_pattern(&source_filter.includes, path);
+    match value {
+        JsonValue::Object(object) => {
+            let was_empty = object.is_empty();
+            let filtered_object = filter_object(source_filter, object, path, included);
+            if filtered_object.is_empty() && !(included && was_empty) {
+                return None;
+            }
+            Some(JsonValue::Object(filtered_object))
+        }
+        JsonValue::Array(values) => {
+            // The elements of an array share the path of the array.
+            let was_empty = values.is_empty();
+            let filtered_values: Vec<JsonValue> = values
+                .into_iter()
+                .filter_map(|value| filter_value(source_filter, value, path, included))
+                .collect();
+            if filtered_values.is_empty() && !(included && was_empty) {
+                return None;
+            }
+            Some(JsonValue::Array(filtered_values))
+        }
+        leaf_value => included.then_some(leaf_value),
+    }
+}
+
+fn matches_any_pattern(patterns: &[String], path: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| matches_pattern(pattern.as_bytes(), path.as_bytes()))
+}
+
+/// Returns true if the path matches the pattern, `*` matching any sequence of characters.
+fn matches_pattern(pattern: &[u8], path: &[u8]) -> bool {
+    let mut pattern_pos = 0;
+    let mut path_pos = 0;
+    // Position of the last star in the pattern and of the path char it is matched against.
+    let mut last_star_opt: Option<(usize, usize)> = None;
+
+    while path_pos < path.len() {
+        if pattern.get(pattern_pos) == Some(&b'*') {
+            last_star_opt = Some((pattern_pos, path_pos));
+            pattern_pos += 1;
+        } else if pattern.get(pattern_pos) == Some(&path[path_pos]) {
+            pattern_pos += 1;
+            path_pos += 1;
+        } else if let Some((star_pattern_pos, star_path_pos)) = last_star_opt {
+            // Backtrack: the last star absorbs one more char.
+            last_star_opt = Some((star_pattern_pos, star_path_pos + 1));
+            pattern_pos = star_pattern_pos + 1;
+            path_pos = star_path_pos + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[pattern_pos..].iter().all(|byte| *byte == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn test_filter_source_aux(
+        includes: &[&str],
+        excludes: &[&str],
+        doc: JsonValue,
+        expected_doc: JsonValue,
+    ) {
+        let source_filter = SourceFilter {
+            includes: includes.iter().map(|path| path.to_string()).collect(),
+            excludes: excludes.iter().map(|path| path.to_string()).collect(),
+        };
+        let JsonValue::Object(doc) = doc else {
+            panic!("doc must be a JSON object");
+        };
+        let filtered_doc = filter_source(&source_filter, doc);
+        assert_eq!(JsonValue::Object(filtered_doc), expected_doc);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern(b"actor", b"actor"));
+        assert!(!matches_pattern(b"actor", b"actor.id"));
+        assert!(!matches_pattern(b"actor.id", b"actor"));
+        assert!(matches_pattern(b"*", b"actor.id"));
+        assert!(matches_pattern(b"actor.*", b"actor.id"));
+        assert!(!matches_pattern(b"actor.*", b"actor"));
+        assert!(matches_pattern(b"*.id", b"actor.id"));
+        assert!(matches_pattern(b"*.id", b"payload.commits.id"));
+        assert!(!matches_pattern(b"*.id", b"id"));
+        assert!(matches_pattern(b"a*b*c", b"aXbYbZc"));
+        assert!(!matches_pattern(b"a*b*c", b"aXbYbZ"));
+        assert!(matches_pattern(b"**", b""));
+    }
+
+    #[test]
+    fn test_filter_source() {
+        let doc = json!({
+            "id": 1,
+            "actor": {"id": 2, "login": "fulmicoton"},
+            "payload": {"commits": [{"id": 3, "message": "fix"}, {"id": 4}]},
+            "tags": ["a", "b"],
+            "empty": {}
+        });
+        test_filter_source_aux(&[], &[], doc.clone(), doc.clone());
+        test_filter_source_aux(&[], &["*"], doc.clone(), json!({}));
+        test_filter_source_aux(
+            &["actor", "tags"],
+            &[],
+            doc.clone(),
+            json!({"actor": {"id": 2, "login": "fulmicoton"}, "tags": ["a", "b"]}),
+        );
+        test_filter_source_aux(
+            &["actor.login", "empty"],
+            &[],
+            doc.clone(),
+            json!({"actor": {"login": "fulmicoton"}, "empty": {}}),
+        );
+        test_filter_source_aux(
+            &["payload.commits.message"],
+            &[],
+            doc.clone(),
+            json!({"payload": {"commits": [{"message": "fix"}]}}),
+        );
+        test_filter_source_aux(
+            &[],
+            &["*.id", "payload"],
+            doc.clone(),
+            json!({"id": 1, "actor": {"login": "fulmicoton"}, "tags": ["a", "b"], "empty": {}}),
+        );
+        test_filter_source_aux(
+            &["act*", "*.message"],
+            &["actor.id"],
+            doc,
+            json!({"actor": {"login": "fulmicoton"}, "payload": {"commits": [{"message": "fix"}]}}),
+        );
+    }
+}
@@ -18,16 +18,20 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
+use bytesize::ByteSize;
 use quickwit_config::SearcherConfig;
 use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::TestSandbox;
+use quickwit_metastore::{IndexMetadataResponseExt, UpdateIndexRequestExt};
 use quickwit_opentelemetry::otlp::TraceId;
+use quickwit_proto::metastore::{IndexMetadataRequest, UpdateIndexRequest};
 use quickwit_proto::search::{
-    LeafListTermsResponse, ListTermsRequest, SearchRequest, SortByValue, SortField, SortOrder,
-    SortValue,
+    LeafListTermsResponse, ListTermsRequest, SearchRequest, SnippetEncoder, SnippetOptions,
+    SortByValue, SortField, SortOrder, SortValue, SourceFilter,
 };
 use quickwit_query::query_ast::{
     qast_helper, qast_json_helper, query_ast_from_user_text, QueryAst,
@@ -182,6 +186,267 @@ async fn test_single_search_with_snippet() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_search_with_snippet_options() -> anyhow::Result<()> {
+    let index_id = "single-node-with-snippet-options";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: array<text>
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "beagle", "body": ["The beagle is a breed of small scent hound.", "A beagle & a hound."]}),
+        json!({"title": "lisa", "body": ["Lisa is a character in `The Simpsons` animated tv series."]}),
+    ];
+    test_sandbox.add_documents(docs.clone()).await?;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("beagle", &["title", "body"]),
+        snippet_fields: vec!["title".to_string(), "body".to_string()],
+        snippet_options: Some(SnippetOptions {
+            pre_tag: Some("<em>".to_string()),
+            post_tag: Some("</em>".to_string()),
+            fragment_size: None,
+            number_of_fragments: Some(1),
+            encoder: SnippetEncoder::Html as i32,
+        }),
+        max_hits: 2,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+    assert_eq!(single_node_result.hits.len(), 1);
+
+    let highlight_json: JsonValue =
+        serde_json::from_str(single_node_result.hits[0].snippet.as_ref().unwrap())?;
+    let expected_json: JsonValue = json!({
+        "title": ["<em>beagle</em>"],
+        "body": ["The <em>beagle</em> is a breed of small scent hound", "A <em>beagle</em> &amp; a hound"]
+    });
+    assert_json_eq!(highlight_json, expected_json);
+
+    // The number of fragments is limited per value.
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("beagle OR hound", &["body"]),
+        snippet_fields: vec!["body".to_string()],
+        snippet_options: Some(SnippetOptions {
+            pre_tag: Some("[".to_string()),
+            post_tag: Some("]".to_string()),
+            fragment_size: Some(20),
+            number_of_fragments: Some(2),
+            encoder: SnippetEncoder::Raw as i32,
+        }),
+        max_hits: 2,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let highlight_json: JsonValue =
+        serde_json::from_str(single_node_result.hits[0].snippet.as_ref().unwrap())?;
+    let expected_json: JsonValue = json!({
+        "body": ["The [beagle] is a", "scent [hound]", "A [beagle] & a [hound]"]
+    });
+    assert_json_eq!(highlight_json, expected_json);
+
+    // With the raw encoder, the text is not HTML-escaped. With 0 fragments, the whole value is
+    // returned.
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("hound", &["body"]),
+        snippet_fields: vec!["body".to_string()],
+        snippet_options: Some(SnippetOptions {
+            pre_tag: Some("[".to_string()),
+            post_tag: Some("]".to_string()),
+            fragment_size: None,
+            number_of_fragments: Some(0),
+            encoder: SnippetEncoder::Raw as i32,
+        }),
+        max_hits: 2,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let highlight_json: JsonValue =
+        serde_json::from_str(single_node_result.hits[0].snippet.as_ref().unwrap())?;
+    let expected_json: JsonValue = json!({
+        "body": ["The beagle is a breed of small scent [hound].", "A beagle & a [hound]."]
+    });
+    assert_json_eq!(highlight_json, expected_json);
+
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_search_with_source_filter() -> anyhow::Result<()> {
+    let index_id = "single-node-with-source-filter";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: actor
+                type: object
+                field_mappings:
+                  - name: id
+                    type: u64
+                  - name: login
+                    type: text
+            mode: dynamic
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["title"]).await?;
+    let docs = vec![json!({
+        "title": "beagle",
+        "actor": {"id": 1, "login": "snoopy"},
+        "payload": {"size": 3, "ref": "main"}
+    })];
+    test_sandbox.add_documents(docs).await?;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("beagle", &["title"]),
+        max_hits: 10,
+        source_filter: Some(SourceFilter {
+            includes: vec!["actor.*".to_string(), "payload".to_string()],
+            excludes: vec!["actor.id".to_string(), "*.ref".to_string()],
+        }),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let hit_json: JsonValue = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_eq!(
+        hit_json,
+        json!({"actor": {"login": "snoopy"}, "payload": {"size": 3}})
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_search_with_point_in_time() -> anyhow::Result<()> {
+    let index_id = "single-node-with-point-in-time";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["title"]).await?;
+    test_sandbox
+        .add_documents(vec![json!({"title": "beagle"})])
+        .await?;
+
+    let mut metastore = test_sandbox.metastore();
+    let point_in_time_id = open_point_in_time(
+        &[index_id.to_string()],
+        Duration::from_secs(60),
+        &mut metastore,
+    )
+    .await?;
+
+    // Documents indexed after the point in time was opened are not visible through it.
+    test_sandbox
+        .add_documents(vec![json!({"title": "beagle"})])
+        .await?;
+
+    let search_request = SearchRequest {
+        query_ast: qast_json_helper("beagle", &["title"]),
+        max_hits: 10,
+        point_in_time_id: Some(point_in_time_id.clone()),
+        point_in_time_keep_alive_secs: Some(60),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        search_request.clone(),
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+
+    // The search request must target the indexes of the point in time.
+    let search_request_with_index_pattern = SearchRequest {
+        index_id_patterns: vec!["single-node-with-point-*".to_string()],
+        ..search_request.clone()
+    };
+    let single_node_result = single_node_search(
+        search_request_with_index_pattern,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 1);
+
+    let search_request_with_other_index = SearchRequest {
+        index_id_patterns: vec!["other-index-*".to_string()],
+        ..search_request.clone()
+    };
+    let search_error = single_node_search(
+        search_request_with_other_index,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(search_error, SearchError::InvalidArgument(_)));
+
+    let search_request_without_point_in_time = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        point_in_time_id: None,
+        point_in_time_keep_alive_secs: None,
+        ..search_request.clone()
+    };
+    let single_node_result = single_node_search(
+        search_request_without_point_in_time,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 2);
+
+    let num_freed = close_point_in_time(&point_in_time_id, &mut metastore).await?;
+    assert_eq!(num_freed, 1);
+
+    let num_freed = close_point_in_time(&point_in_time_id, &mut metastore).await?;
+    assert_eq!(num_freed, 0);
+
+    let search_error = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        search_error,
+        SearchError::PointInTimeNotFound { .. }
+    ));
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 async fn slop_search_and_check(
     test_sandbox: &TestSandbox,
     index_id: &str,
@@ -328,7 +593,7 @@ async fn test_single_node_several_splits() -> anyhow::Result<()> {
     assert!(is_sorted(single_node_result.hits.iter().flat_map(|hit| {
         hit.partial_hit.as_ref().map(|partial_hit| {
             (
-                partial_hit.sort_value,
+                partial_hit.sort_value.clone(),
                 partial_hit.split_id.as_str(),
                 partial_hit.doc_id,
             )
@@ -340,6 +605,77 @@ async fn test_single_node_several_splits() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_search_after_doc_mapping_update() -> anyhow::Result<()> {
+    let index_id = "single-node-doc-mapping-update";
+    let doc_mapping_yaml = r#"
+            mode: lenient
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"body": "the beagle is a breed of small scent hound"}),
+            json!({"body": "snoopy is an anthropomorphic beagle"}),
+        ])
+        .await?;
+
+    // Add a field to the doc mapping: the split indexed above does not have it.
+    let mut metastore = test_sandbox.metastore();
+    let index_config = metastore
+        .index_metadata(IndexMetadataRequest::for_index_id(index_id.to_string()))
+        .await?
+        .deserialize_index_metadata()?
+        .into_index_config();
+    let mut new_doc_mapping = index_config.doc_mapping.clone();
+    new_doc_mapping.field_mappings.push(serde_json::from_str(
+        r#"{"name": "severity", "type": "text", "tokenizer": "raw"}"#,
+    )?);
+    let update_index_request = UpdateIndexRequest::try_from_updates(
+        test_sandbox.index_uid(),
+        &new_doc_mapping,
+        &index_config.search_settings,
+        &index_config.retention_policy_opt,
+        &index_config.indexing_settings,
+    )?;
+    metastore.update_index(update_index_request).await?;
+
+    test_sandbox
+        .add_documents(vec![
+            json!({"body": "the foxhound is a larger hound", "severity": "error"}),
+            json!({"body": "the beagle has a great sense of smell", "severity": "info"}),
+        ])
+        .await?;
+
+    for (user_query, expected_num_hits) in [
+        ("severity:error", 1),
+        ("severity:err*", 1),
+        ("severity: IN [error info]", 2),
+        ("severity:*", 2),
+        ("body:beagle OR severity:error", 4),
+        ("body:beagle AND NOT severity:info", 2),
+    ] {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper(user_query, &[]),
+            max_hits: 10,
+            ..Default::default()
+        };
+        let search_response = single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+        .await?;
+        assert!(search_response.errors.is_empty(), "{user_query}");
+        assert_eq!(search_response.num_hits, expected_num_hits, "{user_query}");
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_filtering() -> anyhow::Result<()> {
     let index_id = "single-node-filtering";
@@ -1204,7 +1540,7 @@ fn test_convert_leaf_hit_aux(
         serde_json::from_value(default_doc_mapper_json).unwrap();
     let named_field_doc = json_to_named_field_doc(document_json);
     let hit_json_str =
-        convert_document_to_json_string(named_field_doc, &default_doc_mapper).unwrap();
+        convert_document_to_json_string(named_field_doc, &default_doc_mapper, None).unwrap();
     let hit_json: JsonValue = serde_json::from_str(&hit_json_str).unwrap();
     assert_eq!(hit_json, expected_hit_json);
 }
@@ -1466,6 +1802,475 @@ async fn test_single_node_aggregation_missing_fast_field() {
     test_sandbox.assert_quit().await;
 }
 
+#[tokio::test]
+async fn test_single_node_extended_aggregations() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-3";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "blue", "price": 10.0}),
+            json!({"color": "blue", "price": 15.0}),
+            json!({"color": "green", "price": 10.0}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "white", "price": 100.0}),
+            json!({"color": "white", "price": 1.0}),
+            json!({"color": "blue", "price": 3.0}),
+        ])
+        .await?;
+    let search = |aggregations: JsonValue| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(aggregations.to_string()),
+            ..Default::default()
+        };
+        single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+    };
+    let aggregations = json!({
+        "num_colors": {
+            "cardinality": {"field": "color"}
+        },
+        "by_color": {
+            "composite": {
+                "size": 2,
+                "sources": [{"color": {"terms": {"field": "color"}}}]
+            },
+            "aggs": {
+                "max_price": {"max": {"field": "price"}}
+            }
+        },
+        "cheap": {
+            "filters": {
+                "filters": {"cheap": {"range": {"price": {"lt": 12}}}},
+                "other_bucket": true
+            }
+        },
+        "avg_price": {
+            "avg": {"field": "price"}
+        }
+    });
+    let search_response = search(aggregations).await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+    assert_eq!(agg_res_json["num_colors"]["value"], 3);
+    assert_eq!(
+        agg_res_json["by_color"],
+        json!({
+            "after_key": {"color": "green"},
+            "buckets": [
+                {"key": {"color": "blue"}, "doc_count": 3, "max_price": {"value": 15.0}},
+                {"key": {"color": "green"}, "doc_count": 1, "max_price": {"value": 10.0}},
+            ]
+        })
+    );
+    assert_eq!(
+        agg_res_json["cheap"],
+        json!({
+            "buckets": {
+                "cheap": {"doc_count": 4},
+                "_other_": {"doc_count": 2},
+            }
+        })
+    );
+    assert_eq!(agg_res_json["avg_price"]["value"], 139.0 / 6.0);
+
+    let aggregations = json!({
+        "by_color": {
+            "composite": {
+                "size": 2,
+                "sources": [{"color": {"terms": {"field": "color"}}}],
+                "after": {"color": "green"}
+            }
+        }
+    });
+    let search_response = search(aggregations).await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+    assert_eq!(
+        agg_res_json["by_color"],
+        json!({
+            "after_key": {"color": "white"},
+            "buckets": [{"key": {"color": "white"}, "doc_count": 2}]
+        })
+    );
+
+    let aggregations = json!({
+        "by_color": {
+            "terms": {"field": "color"},
+            "aggs": {
+                "by_price": {
+                    "composite": {
+                        "sources": [{"price": {"histogram": {"field": "price", "interval": 10}}}]
+                    }
+                }
+            }
+        }
+    });
+    let search_error = search(aggregations).await.unwrap_err();
+    assert!(matches!(
+        search_error,
+        SearchError::InvalidAggregationRequest(_)
+    ));
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_filters_aggregation_with_ids_query() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-filters-ids";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    test_sandbox
+        .add_documents(vec![json!({"color": "blue"}), json!({"color": "green"})])
+        .await?;
+    test_sandbox
+        .add_documents(vec![json!({"color": "white"})])
+        .await?;
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    assert_eq!(search_response.hits.len(), 3);
+    let partial_hit = search_response.hits[0].partial_hit.as_ref().unwrap();
+    let doc_id = format!(
+        "{}:{:08x}:{:08x}",
+        partial_hit.split_id, partial_hit.segment_ord, partial_hit.doc_id
+    );
+
+    // The ids of the filters are matched against the documents of each split.
+    let aggregations = json!({
+        "by_id": {
+            "filters": {
+                "filters": {"doc": {"ids": {"values": [doc_id]}}},
+                "other_bucket": true
+            }
+        }
+    });
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(aggregations.to_string()),
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+    assert_eq!(
+        agg_res_json["by_id"],
+        json!({
+            "buckets": {
+                "doc": {"doc_count": 1},
+                "_other_": {"doc_count": 2},
+            }
+        })
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_top_hits_aggregation() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-top-hits";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+              - name: name
+                type: text
+              - name: price
+                type: f64
+                fast: true
+              - name: timestamp
+                type: datetime
+                fast: true
+                input_formats: [unix_timestamp]
+                fast_precision: seconds
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["name"]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "blue", "name": "sky", "price": 10.0, "timestamp": 0}),
+            json!({"color": "blue", "name": "sea", "price": 15.0, "timestamp": 3_600}),
+            json!({"color": "green", "name": "grass", "price": 10.0, "timestamp": 3_600}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "white", "name": "snow", "price": 100.0, "timestamp": 0}),
+            json!({"color": "white", "name": "cloud", "price": 1.0, "timestamp": 10_800}),
+            json!({"color": "blue", "name": "jeans", "price": 3.0, "timestamp": 10_800}),
+        ])
+        .await?;
+    let search = |aggregations: JsonValue| {
+        let search_request = SearchRequest {
+            index_id_patterns: vec![index_id.to_string()],
+            query_ast: qast_json_helper("*", &[]),
+            max_hits: 0,
+            aggregation_request: Some(aggregations.to_string()),
+            ..Default::default()
+        };
+        single_node_search(
+            search_request,
+            test_sandbox.metastore(),
+            test_sandbox.storage_resolver(),
+        )
+    };
+    let aggregations = json!({
+        "by_color": {
+            "terms": {"field": "color", "size": 2},
+            "aggs": {
+                "most_expensive": {
+                    "top_hits": {
+                        "size": 1,
+                        "sort": [{"price": "desc"}],
+                        "_source": {"includes": ["name"]}
+                    }
+                }
+            }
+        },
+        "per_hour": {
+            "date_histogram": {"field": "timestamp", "fixed_interval": "1h"},
+            "aggs": {
+                "num_colors": {"cardinality": {"field": "color"}}
+            }
+        }
+    });
+    let search_response = search(aggregations).await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+    let top_hit = |num_docs: u64, name: &str, price: f64| {
+        json!({
+            "hits": {
+                "total": {"value": num_docs, "relation": "eq"},
+                "hits": [{"_index": index_id, "_source": {"name": name}, "sort": [price]}]
+            }
+        })
+    };
+    assert_eq!(
+        agg_res_json["by_color"],
+        json!({
+            "sum_other_doc_count": 1,
+            "buckets": [
+                {"key": "blue", "doc_count": 3, "most_expensive": top_hit(3, "sea", 15.0)},
+                {"key": "white", "doc_count": 2, "most_expensive": top_hit(2, "snow", 100.0)},
+            ]
+        })
+    );
+    assert_eq!(
+        agg_res_json["per_hour"],
+        json!({
+            "buckets": [
+                {"key": 0, "doc_count": 2, "num_colors": {"value": 2}},
+                {"key": 3_600_000, "doc_count": 2, "num_colors": {"value": 2}},
+                {"key": 7_200_000, "doc_count": 0, "num_colors": {"value": 0}},
+                {"key": 10_800_000, "doc_count": 2, "num_colors": {"value": 2}},
+            ]
+        })
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_pipeline_aggregations() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-pipeline";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                fast: true
+              - name: price
+                type: f64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "blue", "price": 10.0}),
+            json!({"color": "blue", "price": 15.0}),
+            json!({"color": "green", "price": 10.0}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "white", "price": 100.0}),
+            json!({"color": "white", "price": 1.0}),
+            json!({"color": "blue", "price": 3.0}),
+        ])
+        .await?;
+    let aggregations = json!({
+        "by_color": {
+            "terms": {"field": "color"},
+            "aggs": {
+                "total": {"sum": {"field": "price"}},
+                "double_total": {
+                    "bucket_script": {
+                        "buckets_path": {"total": "total"},
+                        "script": "params.total * 2"
+                    }
+                },
+                "expensive": {
+                    "bucket_selector": {
+                        "buckets_path": {"total": "total"},
+                        "script": "params.total > 20"
+                    }
+                }
+            }
+        },
+        "by_price": {
+            "histogram": {"field": "price", "interval": 50},
+            "aggs": {
+                "count_deriv": {"derivative": {"buckets_path": "_count"}},
+                "count_cumsum": {"cumulative_sum": {"buckets_path": "_count"}}
+            }
+        }
+    });
+    let search_request = SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        max_hits: 0,
+        aggregation_request: Some(aggregations.to_string()),
+        ..Default::default()
+    };
+    let search_response = single_node_search(
+        search_request,
+        test_sandbox.metastore(),
+        test_sandbox.storage_resolver(),
+    )
+    .await?;
+    let agg_res_json: JsonValue = serde_json::from_str(&search_response.aggregation.unwrap())?;
+    assert_eq!(
+        agg_res_json["by_color"]["buckets"],
+        json!([
+            {"key": "blue", "doc_count": 3, "total": {"value": 28.0}, "double_total": {"value": 56.0}},
+            {"key": "white", "doc_count": 2, "total": {"value": 101.0}, "double_total": {"value": 202.0}},
+        ])
+    );
+    assert_eq!(
+        agg_res_json["by_price"]["buckets"],
+        json!([
+            {"key": 0.0, "doc_count": 5, "count_cumsum": {"value": 5.0}},
+            {"key": 50.0, "doc_count": 0, "count_deriv": {"value": -5.0}, "count_cumsum": {"value": 5.0}},
+            {"key": 100.0, "doc_count": 1, "count_deriv": {"value": 1.0}, "count_cumsum": {"value": 6.0}},
+        ])
+    );
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_extended_aggregations_limits() -> anyhow::Result<()> {
+    let index_id = "leaf-search-extended-aggregations-limits";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                tokenizer: raw
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"color": "blue"}),
+            json!({"color": "green"}),
+            json!({"color": "white"}),
+        ])
+        .await?;
+    let splits = test_sandbox
+        .metastore()
+        .list_splits(ListSplitsRequest::try_from_index_uid(test_sandbox.index_uid()).unwrap())
+        .await?
+        .collect_splits()
+        .await?;
+    let splits_offsets: Vec<_> = splits
+        .into_iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    let aggregations = json!({
+        "by_color": {
+            "terms": {"field": "color"},
+            "aggs": {
+                "num_colors": {"cardinality": {"field": "color"}}
+            }
+        }
+    });
+    let request = Arc::new(SearchRequest {
+        index_id_patterns: vec![index_id.to_string()],
+        query_ast: qast_json_helper("*", &[]),
+        aggregation_request: Some(aggregations.to_string()),
+        ..Default::default()
+    });
+    let leaf_search_with_config = |searcher_config: SearcherConfig| {
+        let searcher_context = Arc::new(SearcherContext::new(searcher_config, None));
+        leaf_search(
+            searcher_context,
+            request.clone(),
+            test_sandbox.storage(),
+            splits_offsets.clone(),
+            test_sandbox.doc_mapper(),
+        )
+    };
+    let leaf_search_response = leaf_search_with_config(SearcherConfig::default()).await?;
+    assert!(leaf_search_response.failed_splits.is_empty());
+    {
+        let searcher_config = SearcherConfig {
+            aggregation_bucket_limit: 2,
+            ..Default::default()
+        };
+        let leaf_search_response = leaf_search_with_config(searcher_config).await?;
+        assert_eq!(leaf_search_response.failed_splits.len(), 1);
+        assert!(leaf_search_response.failed_splits[0]
+            .error
+            .contains("bucket limit was exceeded"));
+    }
+    {
+        let searcher_config = SearcherConfig {
+            aggregation_memory_limit: ByteSize::b(16),
+            ..Default::default()
+        };
+        let leaf_search_response = leaf_search_with_config(searcher_config).await?;
+        assert_eq!(leaf_search_response.failed_splits.len(), 1);
+        assert!(leaf_search_response.failed_splits[0]
+            .error
+            .contains("memory limit was exceeded"));
+    }
+    test_sandbox.assert_quit().await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_with_ip_field() -> anyhow::Result<()> {
     let index_id = "single-node-with-ip-field";
@@ -17,12 +17,10 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::io::Read;
-
 use bytes::Bytes;
-use flate2::read::GzDecoder;
+use bytesize::ByteSize;
+use quickwit_common::compression::{decompress_bytes, CompressionCodec, DecompressionError};
 use thiserror::Error;
-use tokio::task;
 use warp::reject::Reject;
 use warp::Filter;
 
@@ -33,36 +31,29 @@ use warp::Filter;
 /// The first approach lowers the latency, while the second approach is more CPU efficient.
 /// Ingesting data is usually CPU bound and there is considerable latency until the data is
 /// searchable, so the second approach is more suitable for this use case.
-async fn decompress_body(encoding: Option<String>, body: Bytes) -> Result<Bytes, warp::Rejection> {
-    match encoding.as_deref() {
-        Some("gzip" | "x-gzip") => {
-            let decompressed = task::spawn_blocking(move || {
-                let mut decompressed = Vec::new();
-                let mut decoder = GzDecoder::new(body.as_ref());
-                decoder
-                    .read_to_end(&mut decompressed)
-                    .map_err(|_| warp::reject::custom(CorruptedData))?;
-                Result::<_, warp::Rejection>::Ok(Bytes::from(decompressed))
-            })
-            .await
-            .map_err(|_| warp::reject::custom(CorruptedData))??;
-            Ok(decompressed)
-        }
-        Some("zstd") => {
-            let decompressed = task::spawn_blocking(move || {
-                zstd::decode_all(body.as_ref())
-                    .map(Bytes::from)
-                    .map_err(|_| warp::reject::custom(CorruptedData))
-            })
-            .await
-            .map_err(|_| warp::reject::custom(CorruptedData))??;
-            Ok(decompressed)
-        }
-        Some(encoding) => Err(warp::reject::custom(UnsupportedEncoding(
-            encoding.to_string(),
-        ))),
-        _ => Ok(body),
-    }
+///
+/// The decompressed body is subject to the same length limit as the request body.
+async fn decompress_body(
+    encoding: Option<String>,
+    body: Bytes,
+    content_length_limit: ByteSize,
+) -> Result<Bytes, warp::Rejection> {
+    let Some(encoding) = encoding else {
+        return Ok(body);
+    };
+    let Some(codec) = CompressionCodec::from_content_encoding(&encoding)
+        .map_err(|error| warp::reject::custom(UnsupportedEncoding(error.0)))?
+    else {
+        return Ok(body);
+    };
+    decompress_bytes(codec, body, content_length_limit.as_u64() as usize)
+        .await
+        .map_err(|error| match error {
+            DecompressionError::TooLarge { .. } => {
+                warp::reject::custom(DecompressedPayloadTooLarge(content_length_limit))
+            }
+            DecompressionError::Io(_) => warp::reject::custom(CorruptedData),
+        })
 }
 
 #[derive(Debug, Error)]
@@ -72,16 +63,79 @@ pub(crate) struct CorruptedData;
 impl Reject for CorruptedData {}
 
 #[derive(Debug, Error)]
-#[error("Unsupported Content-Encoding {}. Supported encodings are 'gzip' and 'zstd'", self.0)]
+#[error("The decompressed payload exceeds the limit of {0}")]
+pub(crate) struct DecompressedPayloadTooLarge(ByteSize);
+
+impl Reject for DecompressedPayloadTooLarge {}
+
+#[derive(Debug, Error)]
+#[error(
+    "Unsupported Content-Encoding {}. Supported encodings are 'gzip', 'zstd', 'bzip2', 'lz4' and \
+     'snappy'",
+    self.0
+)]
 pub(crate) struct UnsupportedEncoding(String);
 
 impl Reject for UnsupportedEncoding {}
 
-/// Custom filter for optional decompression
-pub(crate) fn get_body_bytes() -> impl Filter<Extract = (Bytes,), Error = warp::Rejection> + Clone {
-    warp::header::optional("content-encoding")
+/// Custom filter for optional decompression. Both the request body and the decompressed body are
+/// limited to `content_length_limit`.
+pub(crate) fn get_body_bytes(
+    content_length_limit: ByteSize,
+) -> impl Filter<Extract = (Bytes,), Error = warp::Rejection> + Clone {
+    warp::body::content_length_limit(content_length_limit.as_u64())
+        .and(warp::header::optional("content-encoding"))
         .and(warp::body::bytes())
-        .and_then(|encoding: Option<String>, body: Bytes| async move {
-            decompress_body(encoding, body).await
+        .and_then(move |encoding: Option<String>, body: Bytes| async move {
+            decompress_body(encoding, body, content_length_limit).await
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_body_bytes() {
+        let payload = b"{\"body\": \"foo\"}\n";
+
+        let body = warp::test::request()
+            .body(&payload[..])
+            .filter(&get_body_bytes(ByteSize::kib(1)))
+            .await
+            .unwrap();
+        assert_eq!(body, &payload[..]);
+
+        let body = warp::test::request()
+            .header("content-encoding", "zstd")
+            .body(zstd::encode_all(&payload[..], 0).unwrap())
+            .filter(&get_body_bytes(ByteSize::kib(1)))
+            .await
+            .unwrap();
+        assert_eq!(body, &payload[..]);
+
+        let rejection = warp::test::request()
+            .header("content-encoding", "zstd")
+            .body(&payload[..])
+            .filter(&get_body_bytes(ByteSize::kib(1)))
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<CorruptedData>().is_some());
+
+        let rejection = warp::test::request()
+            .header("content-encoding", "br")
+            .body(&payload[..])
+            .filter(&get_body_bytes(ByteSize::kib(1)))
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<UnsupportedEncoding>().is_some());
+
+        let rejection = warp::test::request()
+            .header("content-encoding", "zstd")
+            .body(zstd::encode_all(&payload.repeat(100)[..], 0).unwrap())
+            .filter(&get_body_bytes(ByteSize::kib(1)))
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<DecompressedPayloadTooLarge>().is_some());
+    }
+}
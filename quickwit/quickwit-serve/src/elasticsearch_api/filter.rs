@@ -24,8 +24,9 @@ use warp::reject::LengthRequired;
 use warp::{Filter, Rejection};
 
 use super::model::{
-    CatIndexQueryParams, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
-    MultiSearchQueryParams, SearchQueryParamsCount,
+    CatIndexQueryParams, ClosePointInTimeRequestBody, FieldCapabilityQueryParams,
+    FieldCapabilityRequestBody, MultiSearchQueryParams, OpenPointInTimeQueryParams,
+    SearchQueryParamsCount,
 };
 use crate::decompression::get_body_bytes;
 use crate::elasticsearch_api::model::{
@@ -50,12 +51,35 @@ pub(crate) fn elastic_cluster_info_filter() -> impl Filter<Extract = (), Error =
         .and(warp::path::end())
 }
 
+/// Header that can be used to specify the index ID patterns targeted by the `_elastic/_search`
+/// endpoint. The `index` query parameter takes precedence over this header.
+pub(crate) const ELASTIC_INDEX_HEADER: &str = "x-elastic-index";
+
 #[utoipa::path(get, tag = "Search", path = "/_search")]
 pub(crate) fn elasticsearch_filter(
-) -> impl Filter<Extract = (SearchQueryParams,), Error = Rejection> + Clone {
+) -> impl Filter<Extract = (Vec<String>, SearchQueryParams, SearchBody), Error = Rejection> + Clone
+{
     warp::path!("_elastic" / "_search")
         .and(warp::get().or(warp::post()).unify())
+        .and(warp::header::optional::<String>(ELASTIC_INDEX_HEADER))
         .and(serde_qs::warp::query(serde_qs::Config::default()))
+        .and_then(extract_search_index_id_patterns)
+        .untuple_one()
+        .and(json_or_empty())
+}
+
+/// Extracts the index ID patterns targeted by a `_elastic/_search` request from the `index` query
+/// parameter or the `x-elastic-index` header. Defaults to all the indexes.
+async fn extract_search_index_id_patterns(
+    index_header_opt: Option<String>,
+    mut search_params: SearchQueryParams,
+) -> Result<(Vec<String>, SearchQueryParams), Rejection> {
+    let Some(comma_separated_index_id_patterns) = search_params.index.take().or(index_header_opt)
+    else {
+        return Ok((vec!["*".to_string()], search_params));
+    };
+    let index_id_patterns = extract_index_id_patterns(comma_separated_index_id_patterns).await?;
+    Ok((index_id_patterns, search_params))
 }
 
 #[utoipa::path(
@@ -74,10 +98,7 @@ pub(crate) fn elastic_bulk_filter(
 ) -> impl Filter<Extract = (Bytes, ElasticBulkOptions), Error = Rejection> + Clone {
     warp::path!("_elastic" / "_bulk")
         .and(warp::post())
-        .and(warp::body::content_length_limit(
-            CONTENT_LENGTH_LIMIT.as_u64(),
-        ))
-        .and(get_body_bytes())
+        .and(get_body_bytes(CONTENT_LENGTH_LIMIT))
         .and(serde_qs::warp::query(serde_qs::Config::default()))
 }
 
@@ -97,10 +118,7 @@ pub(crate) fn elastic_index_bulk_filter(
 ) -> impl Filter<Extract = (String, Bytes, ElasticBulkOptions), Error = Rejection> + Clone {
     warp::path!("_elastic" / String / "_bulk")
         .and(warp::post())
-        .and(warp::body::content_length_limit(
-            CONTENT_LENGTH_LIMIT.as_u64(),
-        ))
-        .and(get_body_bytes())
+        .and(get_body_bytes(CONTENT_LENGTH_LIMIT))
         .and(serde_qs::warp::query::<ElasticBulkOptions>(
             serde_qs::Config::default(),
         ))
@@ -248,3 +266,21 @@ pub(crate) fn elastic_scroll_filter(
             },
         )
 }
+
+#[utoipa::path(post, tag = "Search", path = "/{index}/_pit")]
+pub(crate) fn elastic_open_point_in_time_filter(
+) -> impl Filter<Extract = (Vec<String>, OpenPointInTimeQueryParams), Error = Rejection> + Clone {
+    warp::path!("_elastic" / String / "_pit")
+        .and_then(extract_index_id_patterns)
+        .and(warp::post())
+        .and(serde_qs::warp::query(serde_qs::Config::default()))
+}
+
+#[utoipa::path(delete, tag = "Search", path = "/_pit")]
+pub(crate) fn elastic_close_point_in_time_filter(
+) -> impl Filter<Extract = (ClosePointInTimeRequestBody,), Error = Rejection> + Clone {
+    warp::path!("_elastic" / "_pit")
+        .and(warp::delete())
+        .and(warp::body::content_length_limit(BODY_LENGTH_LIMIT.as_u64()))
+        .and(warp::body::json())
+}
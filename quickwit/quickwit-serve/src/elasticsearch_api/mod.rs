@@ -34,8 +34,9 @@ use quickwit_proto::ingest::router::IngestRouterServiceClient;
 use quickwit_proto::metastore::MetastoreServiceClient;
 use quickwit_search::SearchService;
 use rest_handler::{
-    es_compat_cluster_info_handler, es_compat_index_multi_search_handler,
-    es_compat_index_search_handler, es_compat_scroll_handler, es_compat_search_handler,
+    es_compat_close_point_in_time_handler, es_compat_cluster_info_handler,
+    es_compat_index_multi_search_handler, es_compat_index_search_handler,
+    es_compat_open_point_in_time_handler, es_compat_scroll_handler, es_compat_search_handler,
 };
 use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
@@ -78,6 +79,8 @@ pub fn elastic_api_handlers(
         .or(es_compat_stats_handler(metastore.clone()))
         .or(es_compat_index_cat_indices_handler(metastore.clone()))
         .or(es_compat_cat_indices_handler(metastore.clone()))
+        .or(es_compat_open_point_in_time_handler(metastore.clone()))
+        .or(es_compat_close_point_in_time_handler(metastore.clone()))
     // Register newly created handlers here.
 }
 
@@ -414,6 +417,115 @@ mod tests {
         assert_eq!(resp.status(), 200);
     }
 
+    #[tokio::test]
+    async fn test_search_api_without_index_in_path() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns == vec!["*".to_string()]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns
+                        == vec!["index-1".to_string(), "index-2*".to_string()]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.index_id_patterns == vec!["index-3".to_string()]
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let ingest_router = IngestRouterServiceClient::from(IngestRouterServiceClient::mock());
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mock().into(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search?q=test")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search?index=index-1,index-2*")
+            .method("POST")
+            .body(r#"{"query": {"match_all": {}}}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search")
+            .header("x-elastic-index", "index-3")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search?index=index-1,")
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_search_api_with_point_in_time() {
+        let config = Arc::new(NodeConfig::for_test());
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.point_in_time_id.as_deref() == Some("pit-id")
+                        && search_request.point_in_time_keep_alive_secs == Some(60)
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let ingest_router = IngestRouterServiceClient::from(IngestRouterServiceClient::mock());
+        let es_search_api_handler = super::elastic_api_handlers(
+            config,
+            Arc::new(mock_search_service),
+            ingest_service_client(),
+            ingest_router,
+            MetastoreServiceClient::mock().into(),
+        )
+        .recover(recover_fn);
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search")
+            .method("POST")
+            .body(r#"{"pit": {"id": "pit-id", "keep_alive": "1m"}}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: JsonValue = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(resp_json["pit_id"], "pit-id");
+
+        let resp = warp::test::request()
+            .path("/_elastic/_search")
+            .method("POST")
+            .body(r#"{"pit": {"id": "pit-id", "keep_alive": "forever"}}"#)
+            .reply(&es_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+    }
+
     #[tokio::test]
     async fn test_es_compat_cluster_info_handler() {
         let build_info = BuildInfo::get();
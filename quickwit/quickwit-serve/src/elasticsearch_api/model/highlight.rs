@@ -0,0 +1,246 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use quickwit_proto::search::{SnippetEncoder, SnippetOptions};
+use serde::{Deserialize, Deserializer};
+
+type HighlightFieldParams = serde_json::Map<String, serde_json::Value>;
+
+const DEFAULT_PRE_TAG: &str = "<em>";
+const DEFAULT_POST_TAG: &str = "</em>";
+const DEFAULT_FRAGMENT_SIZE: u32 = 100;
+const DEFAULT_NUMBER_OF_FRAGMENTS: u32 = 5;
+
+/// Highlight clause of an Elasticsearch search request.
+///
+/// Highlights are computed using Quickwit's snippets. The highlight options
+/// can only be set globally, not on a per-field basis.
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticHighlight {
+    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_highlight_fields")]
+    pub fields: BTreeMap<String, HighlightFieldParams>,
+    #[serde(default)]
+    pub pre_tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub post_tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub fragment_size: Option<u32>,
+    #[serde(default)]
+    pub number_of_fragments: Option<u32>,
+    #[serde(default)]
+    pub encoder: HighlightEncoder,
+}
+
+/// Encoding of the highlighted text. As in ES, the text is returned as is by
+/// default, and HTML-escaped with `html`. The tags are never encoded.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightEncoder {
+    #[default]
+    Default,
+    Html,
+}
+
+impl From<HighlightEncoder> for SnippetEncoder {
+    fn from(encoder: HighlightEncoder) -> Self {
+        match encoder {
+            HighlightEncoder::Default => SnippetEncoder::Raw,
+            HighlightEncoder::Html => SnippetEncoder::Html,
+        }
+    }
+}
+
+impl ElasticHighlight {
+    /// Converts the highlight clause into the list of snippet fields and the
+    /// options used to render their snippets.
+    pub fn into_snippet_fields_and_options(self) -> anyhow::Result<(Vec<String>, SnippetOptions)> {
+        let mut snippet_fields = Vec::with_capacity(self.fields.len());
+        for (field_name, field_params) in self.fields {
+            if !field_params.is_empty() {
+                let param_names = field_params.keys().map(String::as_str).collect::<Vec<_>>();
+                bail!(
+                    "per-field highlight options are not supported, got `{}` for field \
+                     `{field_name}`",
+                    param_names.join(", ")
+                );
+            }
+            snippet_fields.push(field_name);
+        }
+        let snippet_options = SnippetOptions {
+            pre_tag: Some(single_tag(self.pre_tags, "pre_tags", DEFAULT_PRE_TAG)?),
+            post_tag: Some(single_tag(self.post_tags, "post_tags", DEFAULT_POST_TAG)?),
+            fragment_size: Some(self.fragment_size.unwrap_or(DEFAULT_FRAGMENT_SIZE)),
+            number_of_fragments: Some(
+                self.number_of_fragments
+                    .unwrap_or(DEFAULT_NUMBER_OF_FRAGMENTS),
+            ),
+            encoder: SnippetEncoder::from(self.encoder) as i32,
+        };
+        Ok((snippet_fields, snippet_options))
+    }
+}
+
+fn single_tag(
+    tags_opt: Option<Vec<String>>,
+    param_name: &str,
+    default_tag: &str,
+) -> anyhow::Result<String> {
+    let Some(mut tags) = tags_opt else {
+        return Ok(default_tag.to_string());
+    };
+    if tags.len() != 1 {
+        bail!(
+            "`{param_name}` must contain exactly one tag, got {}",
+            tags.len()
+        );
+    }
+    Ok(tags.pop().unwrap())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrMapHighlightField {
+    FieldNameOnly(String),
+    Map(BTreeMap<String, HighlightFieldParams>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HighlightFieldsForDeser {
+    Map(BTreeMap<String, HighlightFieldParams>),
+    List(Vec<StringOrMapHighlightField>),
+}
+
+/// ES accepts the highlight fields either as an object, or as an array of
+/// field names or single-field objects.
+fn deserialize_highlight_fields<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, HighlightFieldParams>, D::Error>
+where D: Deserializer<'de> {
+    let highlight_fields = match HighlightFieldsForDeser::deserialize(deserializer)? {
+        HighlightFieldsForDeser::Map(highlight_fields) => highlight_fields,
+        HighlightFieldsForDeser::List(highlight_field_list) => {
+            let mut highlight_fields = BTreeMap::new();
+            for highlight_field in highlight_field_list {
+                match highlight_field {
+                    StringOrMapHighlightField::FieldNameOnly(field_name) => {
+                        highlight_fields.insert(field_name, HighlightFieldParams::default());
+                    }
+                    StringOrMapHighlightField::Map(field_map) => {
+                        highlight_fields.extend(field_map);
+                    }
+                }
+            }
+            highlight_fields
+        }
+    };
+    Ok(highlight_fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_fields_formats() {
+        let highlight: ElasticHighlight =
+            serde_json::from_str(r#"{ "fields": { "title": {}, "body": {} } }"#).unwrap();
+        assert_eq!(
+            highlight.fields.keys().collect::<Vec<_>>(),
+            ["body", "title"]
+        );
+        let highlight: ElasticHighlight =
+            serde_json::from_str(r#"{ "fields": ["title", { "body": {} }] }"#).unwrap();
+        assert_eq!(
+            highlight.fields.keys().collect::<Vec<_>>(),
+            ["body", "title"]
+        );
+    }
+
+    #[test]
+    fn test_highlight_into_snippet_options() {
+        let highlight: ElasticHighlight =
+            serde_json::from_str(r#"{ "fields": { "title": {} } }"#).unwrap();
+        let (snippet_fields, snippet_options) =
+            highlight.into_snippet_fields_and_options().unwrap();
+        assert_eq!(snippet_fields, ["title"]);
+        assert_eq!(
+            snippet_options,
+            SnippetOptions {
+                pre_tag: Some("<em>".to_string()),
+                post_tag: Some("</em>".to_string()),
+                fragment_size: Some(100),
+                number_of_fragments: Some(5),
+                encoder: SnippetEncoder::Raw as i32,
+            }
+        );
+
+        let highlight: ElasticHighlight = serde_json::from_str(
+            r#"{
+                "fields": ["title"],
+                "pre_tags": ["<mark>"],
+                "post_tags": ["</mark>"],
+                "fragment_size": 50,
+                "number_of_fragments": 0,
+                "encoder": "html"
+            }"#,
+        )
+        .unwrap();
+        let (_, snippet_options) = highlight.into_snippet_fields_and_options().unwrap();
+        assert_eq!(
+            snippet_options,
+            SnippetOptions {
+                pre_tag: Some("<mark>".to_string()),
+                post_tag: Some("</mark>".to_string()),
+                fragment_size: Some(50),
+                number_of_fragments: Some(0),
+                encoder: SnippetEncoder::Html as i32,
+            }
+        );
+    }
+
+    #[test]
+    fn test_highlight_unsupported_options() {
+        let highlight: ElasticHighlight =
+            serde_json::from_str(r#"{ "fields": { "title": { "fragment_size": 10 } } }"#).unwrap();
+        let error = highlight.into_snippet_fields_and_options().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "per-field highlight options are not supported, got `fragment_size` for field `title`"
+        );
+        let highlight: ElasticHighlight =
+            serde_json::from_str(r#"{ "fields": ["title"], "pre_tags": ["<em>", "<strong>"] }"#)
+                .unwrap();
+        let error = highlight.into_snippet_fields_and_options().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`pre_tags` must contain exactly one tag, got 2"
+        );
+        let error = serde_json::from_str::<ElasticHighlight>(
+            r#"{ "fields": ["title"], "type": "unified" }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown field `type`"));
+    }
+}
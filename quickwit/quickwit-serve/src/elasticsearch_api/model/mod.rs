@@ -22,10 +22,13 @@ mod bulk_query_params;
 mod cat_indices;
 mod error;
 mod field_capability;
+mod highlight;
 mod multi_search;
+mod point_in_time;
 mod scroll;
 mod search_body;
 mod search_query_params;
+mod source_filter;
 mod stats;
 
 pub use bulk_body::BulkAction;
@@ -36,14 +39,20 @@ pub use field_capability::{
     build_list_field_request_for_es_api, convert_to_es_field_capabilities_response,
     FieldCapabilityQueryParams, FieldCapabilityRequestBody, FieldCapabilityResponse,
 };
+pub use highlight::ElasticHighlight;
 pub use multi_search::{
     MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse, MultiSearchSingleResponse,
 };
+pub use point_in_time::{
+    ClosePointInTimeRequestBody, ClosePointInTimeResponse, ElasticPointInTime,
+    OpenPointInTimeQueryParams, OpenPointInTimeResponse,
+};
 use quickwit_proto::search::{SortDatetimeFormat, SortOrder};
 pub use scroll::ScrollQueryParams;
 pub use search_body::SearchBody;
 pub use search_query_params::{SearchQueryParams, SearchQueryParamsCount};
 use serde::{Deserialize, Serialize};
+pub use source_filter::ElasticSourceFilter;
 pub use stats::{ElasticsearchStatsResponse, StatsResponseEntry};
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -0,0 +1,107 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::time::Duration;
+
+use quickwit_search::SearchError;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+pub struct OpenPointInTimeQueryParams {
+    pub keep_alive: Option<String>,
+}
+
+impl OpenPointInTimeQueryParams {
+    pub fn parse_keep_alive(&self) -> Result<Duration, SearchError> {
+        let Some(keep_alive_str) = self.keep_alive.as_ref() else {
+            return Err(SearchError::InvalidArgument(
+                "missing required parameter `keep_alive`".to_string(),
+            ));
+        };
+        parse_keep_alive(keep_alive_str)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OpenPointInTimeResponse {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ClosePointInTimeRequestBody {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ClosePointInTimeResponse {
+    pub succeeded: bool,
+    pub num_freed: u32,
+}
+
+/// The `pit` parameter of a search request body.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElasticPointInTime {
+    pub id: String,
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+}
+
+impl ElasticPointInTime {
+    pub fn parse_keep_alive(&self) -> Result<Option<Duration>, SearchError> {
+        self.keep_alive.as_deref().map(parse_keep_alive).transpose()
+    }
+}
+
+fn parse_keep_alive(keep_alive_str: &str) -> Result<Duration, SearchError> {
+    humantime::parse_duration(keep_alive_str).map_err(|_err| {
+        SearchError::InvalidArgument(format!("invalid keep alive duration: `{keep_alive_str}`"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{ElasticPointInTime, OpenPointInTimeQueryParams};
+
+    #[test]
+    fn test_point_in_time_parse_keep_alive() {
+        let query_params = OpenPointInTimeQueryParams {
+            keep_alive: Some("1m".to_string()),
+        };
+        assert_eq!(
+            query_params.parse_keep_alive().unwrap(),
+            Duration::from_secs(60)
+        );
+        let query_params = OpenPointInTimeQueryParams { keep_alive: None };
+        query_params.parse_keep_alive().unwrap_err();
+
+        let point_in_time: ElasticPointInTime =
+            serde_json::from_str(r#"{"id": "pit-id", "keep_alive": "2h"}"#).unwrap();
+        assert_eq!(
+            point_in_time.parse_keep_alive().unwrap(),
+            Some(Duration::from_secs(2 * 60 * 60))
+        );
+        let point_in_time: ElasticPointInTime =
+            serde_json::from_str(r#"{"id": "pit-id", "keep_alive": "forever"}"#).unwrap();
+        point_in_time.parse_keep_alive().unwrap_err();
+    }
+}
@@ -25,7 +25,7 @@ use quickwit_query::{ElasticQueryDsl, OneFieldMap};
 use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer, Serialize};
 
-use super::ElasticDateFormat;
+use super::{ElasticDateFormat, ElasticHighlight, ElasticPointInTime, ElasticSourceFilter};
 use crate::elasticsearch_api::model::{default_elasticsearch_sort_order, SortField};
 use crate::elasticsearch_api::TrackTotalHits;
 
@@ -86,6 +86,13 @@ pub struct SearchBody {
     pub stored_fields: Option<BTreeSet<String>>,
     #[serde(default)]
     pub search_after: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub highlight: Option<ElasticHighlight>,
+    #[serde(default)]
+    #[serde(rename = "_source")]
+    pub source: Option<ElasticSourceFilter>,
+    #[serde(default)]
+    pub pit: Option<ElasticPointInTime>,
 }
 
 struct FieldSortVecVisitor;
@@ -266,7 +273,7 @@ mod tests {
         assert!(error_msg.contains("unknown field `term`"));
         assert!(error_msg.contains(
             "expected one of `from`, `size`, `query`, `sort`, `aggs`, `track_total_hits`, \
-             `stored_fields`, `search_after`"
+             `stored_fields`, `search_after`, `highlight`, `_source`"
         ));
     }
 }
@@ -27,7 +27,9 @@ use serde::{Deserialize, Serialize};
 
 use super::super::TrackTotalHits;
 use super::MultiSearchHeader;
-use crate::elasticsearch_api::model::{default_elasticsearch_sort_order, SortField};
+use crate::elasticsearch_api::model::{
+    default_elasticsearch_sort_order, ElasticSourceFilter, SortField,
+};
 use crate::simple_list::{from_simple_list, to_simple_list};
 
 #[serde_with::skip_serializing_none]
@@ -88,6 +90,11 @@ pub struct SearchQueryParams {
     pub ignore_throttled: Option<bool>,
     #[serde(default)]
     pub ignore_unavailable: Option<bool>,
+    /// Comma-separated list of index ID patterns targeted by the `_elastic/_search` endpoint.
+    /// This parameter is specific to Quickwit and is ignored when the indexes are specified in
+    /// the path.
+    #[serde(default)]
+    pub index: Option<String>,
     #[serde(default)]
     pub lenient: Option<bool>,
     #[serde(default)]
@@ -252,6 +259,25 @@ impl SearchQueryParams {
         Ok(Some(sort_fields))
     }
 
+    /// Returns the `_source` filter supplied in the query string parameters.
+    ///
+    /// `_source_includes` and `_source_excludes` take precedence over `_source`.
+    pub(crate) fn source_filter(&self) -> Option<ElasticSourceFilter> {
+        if self._source_includes.is_some() || self._source_excludes.is_some() {
+            return Some(ElasticSourceFilter::IncludesExcludes {
+                includes: self._source_includes.clone().unwrap_or_default(),
+                excludes: self._source_excludes.clone().unwrap_or_default(),
+            });
+        }
+        let source = self._source.as_ref()?;
+        let source_filter = match source.as_slice() {
+            [enabled] if enabled == "true" => ElasticSourceFilter::Enabled(true),
+            [enabled] if enabled == "false" => ElasticSourceFilter::Enabled(false),
+            _ => ElasticSourceFilter::Includes(source.clone()),
+        };
+        Some(source_filter)
+    }
+
     /// Returns the scroll duration supplied by the user.
     ///
     /// This function returns an error if the scroll duration is not in the expected format. (`40s`
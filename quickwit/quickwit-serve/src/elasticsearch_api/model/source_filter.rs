@@ -0,0 +1,112 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_proto::search::SourceFilter;
+use serde::Deserialize;
+use serde_with::formats::PreferMany;
+use serde_with::{serde_as, OneOrMany};
+
+/// `_source` parameter of an Elasticsearch search request, restricting the document fields
+/// returned in the hits. Field paths support `*` wildcards.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ElasticSourceFilter {
+    /// `true` returns the whole documents, `false` returns none of their fields.
+    Enabled(bool),
+    /// Paths of the fields to return.
+    Includes(#[serde_as(deserialize_as = "OneOrMany<_, PreferMany>")] Vec<String>),
+    /// Paths of the fields to return and of the fields to omit.
+    IncludesExcludes {
+        #[serde_as(deserialize_as = "OneOrMany<_, PreferMany>")]
+        #[serde(default)]
+        #[serde(alias = "include")]
+        includes: Vec<String>,
+        #[serde_as(deserialize_as = "OneOrMany<_, PreferMany>")]
+        #[serde(default)]
+        #[serde(alias = "exclude")]
+        excludes: Vec<String>,
+    },
+}
+
+impl ElasticSourceFilter {
+    /// Converts the `_source` parameter into a [`SourceFilter`].
+    ///
+    /// Returns `None` if the whole documents should be returned.
+    pub fn into_source_filter(self) -> Option<SourceFilter> {
+        let (includes, excludes) = match self {
+            ElasticSourceFilter::Enabled(true) => return None,
+            ElasticSourceFilter::Enabled(false) => (Vec::new(), vec!["*".to_string()]),
+            ElasticSourceFilter::Includes(includes) => (includes, Vec::new()),
+            ElasticSourceFilter::IncludesExcludes { includes, excludes } => (includes, excludes),
+        };
+        if includes.is_empty() && excludes.is_empty() {
+            return None;
+        }
+        Some(SourceFilter { includes, excludes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn test_source_filter_aux(source_json: &str, expected_source_filter: Option<SourceFilter>) {
+        let elastic_source_filter: ElasticSourceFilter = serde_json::from_str(source_json).unwrap();
+        assert_eq!(
+            elastic_source_filter.into_source_filter(),
+            expected_source_filter
+        );
+    }
+
+    #[test]
+    fn test_elastic_source_filter() {
+        test_source_filter_aux("true", None);
+        test_source_filter_aux(
+            "false",
+            Some(SourceFilter {
+                includes: Vec::new(),
+                excludes: vec!["*".to_string()],
+            }),
+        );
+        test_source_filter_aux(
+            r#""actor.*""#,
+            Some(SourceFilter {
+                includes: vec!["actor.*".to_string()],
+                excludes: Vec::new(),
+            }),
+        );
+        test_source_filter_aux(
+            r#"["actor.*", "id"]"#,
+            Some(SourceFilter {
+                includes: vec!["actor.*".to_string(), "id".to_string()],
+                excludes: Vec::new(),
+            }),
+        );
+        test_source_filter_aux(
+            r#"{"includes": ["actor.*"], "excludes": "actor.id"}"#,
+            Some(SourceFilter {
+                includes: vec!["actor.*".to_string()],
+                excludes: vec!["actor.id".to_string()],
+            }),
+        );
+        test_source_filter_aux(r#"{"includes": []}"#, None);
+    }
+}
@@ -37,32 +37,36 @@ use quickwit_proto::search::{
     SortDatetimeFormat,
 };
 use quickwit_proto::types::IndexUid;
-use quickwit_proto::ServiceErrorCode;
 use quickwit_query::query_ast::{QueryAst, UserInputQuery};
 use quickwit_query::BooleanOperand;
-use quickwit_search::{list_all_splits, resolve_index_patterns, SearchError, SearchService};
+use quickwit_search::{
+    close_point_in_time, list_all_splits, open_point_in_time, resolve_index_patterns, SearchError,
+    SearchService,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use warp::{Filter, Rejection};
 
 use super::filter::{
-    elastic_cat_indices_filter, elastic_cluster_info_filter, elastic_field_capabilities_filter,
-    elastic_index_cat_indices_filter, elastic_index_count_filter,
-    elastic_index_field_capabilities_filter, elastic_index_search_filter,
-    elastic_index_stats_filter, elastic_multi_search_filter, elastic_scroll_filter,
-    elastic_stats_filter, elasticsearch_filter,
+    elastic_cat_indices_filter, elastic_close_point_in_time_filter, elastic_cluster_info_filter,
+    elastic_field_capabilities_filter, elastic_index_cat_indices_filter,
+    elastic_index_count_filter, elastic_index_field_capabilities_filter,
+    elastic_index_search_filter, elastic_index_stats_filter, elastic_multi_search_filter,
+    elastic_open_point_in_time_filter, elastic_scroll_filter, elastic_stats_filter,
+    elasticsearch_filter,
 };
 use super::model::{
     build_list_field_request_for_es_api, convert_to_es_field_capabilities_response,
-    CatIndexQueryParams, ElasticsearchCatIndexResponse, ElasticsearchError,
+    CatIndexQueryParams, ClosePointInTimeRequestBody, ClosePointInTimeResponse,
+    ElasticSourceFilter, ElasticsearchCatIndexResponse, ElasticsearchError,
     ElasticsearchStatsResponse, FieldCapabilityQueryParams, FieldCapabilityRequestBody,
     FieldCapabilityResponse, MultiSearchHeader, MultiSearchQueryParams, MultiSearchResponse,
-    MultiSearchSingleResponse, ScrollQueryParams, SearchBody, SearchQueryParams,
-    SearchQueryParamsCount, StatsResponseEntry,
+    MultiSearchSingleResponse, OpenPointInTimeQueryParams, OpenPointInTimeResponse,
+    ScrollQueryParams, SearchBody, SearchQueryParams, SearchQueryParamsCount, StatsResponseEntry,
 };
 use super::{make_elastic_api_response, TrackTotalHits};
 use crate::format::BodyFormat;
-use crate::rest_api_response::{into_rest_api_response, RestApiError, RestApiResponse};
+use crate::rest_api_response::RestApiResponse;
 use crate::{with_arg, BuildInfo};
 
 /// Elastic compatible cluster info handler.
@@ -91,18 +95,12 @@ pub fn es_compat_cluster_info_handler(
 
 /// GET or POST _elastic/_search
 pub fn es_compat_search_handler(
-    _search_service: Arc<dyn SearchService>,
+    search_service: Arc<dyn SearchService>,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
-    elasticsearch_filter().then(|_params: SearchQueryParams| async move {
-        // TODO
-        let api_error = RestApiError {
-            service_code: ServiceErrorCode::NotSupportedYet,
-            message: "_elastic/_search is not supported yet. Please try the index search endpoint \
-                      (_elastic/{index}/search)"
-                .to_string(),
-        };
-        into_rest_api_response::<(), _>(Err(api_error), BodyFormat::default())
-    })
+    elasticsearch_filter()
+        .and(with_arg(search_service))
+        .then(es_compat_index_search)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
 }
 
 /// GET or POST _elastic/{index}/_field_caps
@@ -156,6 +154,26 @@ pub fn es_compat_index_cat_indices_handler(
         .map(|result| make_elastic_api_response(result, BodyFormat::default()))
 }
 
+/// POST _elastic/{index}/_pit
+pub fn es_compat_open_point_in_time_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_open_point_in_time_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_open_point_in_time)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+}
+
+/// DELETE _elastic/_pit
+pub fn es_compat_close_point_in_time_handler(
+    metastore: MetastoreServiceClient,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = Rejection> + Clone {
+    elastic_close_point_in_time_filter()
+        .and(with_arg(metastore))
+        .then(es_compat_close_point_in_time)
+        .map(|result| make_elastic_api_response(result, BodyFormat::default()))
+}
+
 /// GET or POST _elastic/{index}/_search
 pub fn es_compat_index_search_handler(
     search_service: Arc<dyn SearchService>,
@@ -255,11 +273,6 @@ fn build_request_for_es_api(
         })
         .take_while_inclusive(|sort_field| !is_doc_field(sort_field))
         .collect();
-    if sort_fields.len() >= 3 {
-        return Err(ElasticsearchError::from(SearchError::InvalidArgument(
-            format!("only up to two sort fields supported at the moment. got {sort_fields:?}"),
-        )));
-    }
 
     let scroll_duration: Option<Duration> = search_params.parse_scroll_ttl()?;
     let scroll_ttl_secs: Option<u32> = scroll_duration.map(|duration| duration.as_secs() as u32);
@@ -267,6 +280,28 @@ fn build_request_for_es_api(
     let has_doc_id_field = sort_fields.iter().any(is_doc_field);
     let search_after = partial_hit_from_search_after_param(search_body.search_after, &sort_fields)?;
 
+    let (snippet_fields, snippet_options) = if let Some(highlight) = search_body.highlight {
+        let (snippet_fields, snippet_options) = highlight
+            .into_snippet_fields_and_options()
+            .map_err(|err| SearchError::InvalidArgument(err.to_string()))?;
+        (snippet_fields, Some(snippet_options))
+    } else {
+        (Vec::new(), None)
+    };
+    let source_filter = search_params
+        .source_filter()
+        .or(search_body.source)
+        .and_then(ElasticSourceFilter::into_source_filter);
+
+    let (point_in_time_id, point_in_time_keep_alive_secs) = match search_body.pit {
+        Some(point_in_time) => {
+            let keep_alive_opt: Option<Duration> = point_in_time.parse_keep_alive()?;
+            let keep_alive_secs_opt = keep_alive_opt.map(|keep_alive| keep_alive.as_secs() as u32);
+            (Some(point_in_time.id), keep_alive_secs_opt)
+        }
+        None => (None, None),
+    };
+
     Ok((
         quickwit_proto::search::SearchRequest {
             index_id_patterns,
@@ -277,10 +312,14 @@ fn build_request_for_es_api(
             sort_fields,
             start_timestamp: None,
             end_timestamp: None,
-            snippet_fields: Vec::new(),
+            snippet_fields,
             scroll_ttl_secs,
             search_after,
             count_hits,
+            snippet_options,
+            source_filter,
+            point_in_time_id,
+            point_in_time_keep_alive_secs,
         },
         has_doc_id_field,
     ))
@@ -333,11 +372,12 @@ fn partial_hit_from_search_after_param(
                     "invalid search_after field value, expect bool, number or string".to_string(),
                 )
             })?;
-            // TODO make cleaner once we support Vec
             if parsed_search_after.sort_value.is_none() {
                 parsed_search_after.sort_value = Some(value);
-            } else {
+            } else if parsed_search_after.sort_value2.is_none() {
                 parsed_search_after.sort_value2 = Some(value);
+            } else {
+                parsed_search_after.additional_sort_values.push(value);
             }
         }
     }
@@ -374,14 +414,41 @@ async fn es_compat_index_search(
     let start_instant = Instant::now();
     let (search_request, append_shard_doc) =
         build_request_for_es_api(index_id_patterns, search_params, search_body)?;
+    let point_in_time_id_opt = search_request.point_in_time_id.clone();
+    let num_sort_values = num_sort_values(&search_request);
     let search_response: SearchResponse = search_service.root_search(search_request).await?;
     let elapsed = start_instant.elapsed();
     let mut search_response_rest: ElasticsearchResponse =
-        convert_to_es_search_response(search_response, append_shard_doc);
+        convert_to_es_search_response(search_response, Some(num_sort_values), append_shard_doc);
     search_response_rest.took = elapsed.as_millis() as u32;
+    search_response_rest.pit_id = point_in_time_id_opt;
     Ok(search_response_rest)
 }
 
+async fn es_compat_open_point_in_time(
+    index_id_patterns: Vec<String>,
+    query_params: OpenPointInTimeQueryParams,
+    mut metastore: MetastoreServiceClient,
+) -> Result<OpenPointInTimeResponse, ElasticsearchError> {
+    let keep_alive = query_params.parse_keep_alive()?;
+    let point_in_time_id =
+        open_point_in_time(&index_id_patterns, keep_alive, &mut metastore).await?;
+    Ok(OpenPointInTimeResponse {
+        id: point_in_time_id,
+    })
+}
+
+async fn es_compat_close_point_in_time(
+    request_body: ClosePointInTimeRequestBody,
+    mut metastore: MetastoreServiceClient,
+) -> Result<ClosePointInTimeResponse, ElasticsearchError> {
+    let num_freed = close_point_in_time(&request_body.id, &mut metastore).await?;
+    Ok(ClosePointInTimeResponse {
+        succeeded: true,
+        num_freed,
+    })
+}
+
 async fn es_compat_stats(
     metastore: MetastoreServiceClient,
 ) -> Result<ElasticsearchStatsResponse, ElasticsearchError> {
@@ -481,34 +548,68 @@ async fn es_compat_index_field_capabilities(
     Ok(search_response_rest)
 }
 
-fn convert_hit(hit: quickwit_proto::search::Hit, append_shard_doc: bool) -> ElasticHit {
+/// Returns the number of sort values of the hits returned for a search request, i.e. the number
+/// of sort fields other than `_doc` or `_shard_doc`.
+fn num_sort_values(search_request: &quickwit_proto::search::SearchRequest) -> usize {
+    search_request
+        .sort_fields
+        .iter()
+        .filter(|sort_field| !is_doc_field(sort_field))
+        .count()
+}
+
+fn convert_hit(
+    hit: quickwit_proto::search::Hit,
+    num_sort_values_opt: Option<usize>,
+    append_shard_doc: bool,
+) -> ElasticHit {
     let fields: BTreeMap<String, serde_json::Value> =
         serde_json::from_str(&hit.json).unwrap_or_default();
     let mut sort = Vec::new();
+    // Documents have no user-defined id: their address is used instead, which is what `ids`
+    // queries expect.
+    let mut id = String::new();
     if let Some(partial_hit) = hit.partial_hit {
-        if let Some(sort_value) = partial_hit.sort_value {
-            sort.push(sort_value.into_json());
-        }
-        if let Some(sort_value2) = partial_hit.sort_value2 {
-            sort.push(sort_value2.into_json());
+        id = quickwit_search::GlobalDocAddress::from_partial_hit(&partial_hit).to_string();
+        if let Some(num_sort_values) = num_sort_values_opt {
+            // Documents missing a sort field get a `null` sort value, so that the sort values
+            // remain aligned with the sort fields.
+            for sort_value_opt in partial_hit
+                .sort_values()
+                .chain(std::iter::repeat(None))
+                .take(num_sort_values)
+            {
+                let sort_value_json = sort_value_opt
+                    .map(|sort_value| sort_value.clone().into_json())
+                    .unwrap_or(serde_json::Value::Null);
+                sort.push(sort_value_json);
+            }
+        } else {
+            for sort_value in partial_hit.sort_values().flatten() {
+                sort.push(sort_value.clone().into_json());
+            }
         }
         if append_shard_doc {
-            sort.push(serde_json::Value::String(
-                quickwit_search::GlobalDocAddress::from_partial_hit(&partial_hit).to_string(),
-            ));
+            sort.push(serde_json::Value::String(id.clone()));
         }
     }
+    let mut highlight: BTreeMap<String, Vec<String>> = hit
+        .snippet
+        .and_then(|snippet_json| serde_json::from_str(&snippet_json).ok())
+        .unwrap_or_default();
+    // Like Elasticsearch, fields without any highlighted term are omitted.
+    highlight.retain(|_, snippets| !snippets.is_empty());
 
     ElasticHit {
         fields,
         explanation: None,
         index: hit.index_id,
-        id: "".to_string(),
+        id,
         score: None,
         nested: None,
         source: Source::from_string(hit.json)
             .unwrap_or_else(|_| Source::from_string("{}".to_string()).unwrap()),
-        highlight: Default::default(),
+        highlight,
         inner_hits: Default::default(),
         matched_queries: Vec::default(),
         sort,
@@ -574,11 +675,15 @@ async fn es_compat_index_multi_search(
             let search_service = &search_service;
             async move {
                 let start_instant = Instant::now();
+                let num_sort_values = num_sort_values(&search_request);
                 let search_response: SearchResponse =
                     search_service.clone().root_search(search_request).await?;
                 let elapsed = start_instant.elapsed();
-                let mut search_response_rest: ElasticsearchResponse =
-                    convert_to_es_search_response(search_response, append_shard_doc);
+                let mut search_response_rest: ElasticsearchResponse = convert_to_es_search_response(
+                    search_response,
+                    Some(num_sort_values),
+                    append_shard_doc,
+                );
                 search_response_rest.took = elapsed.as_millis() as u32;
                 Ok::<_, ElasticsearchError>(search_response_rest)
             }
@@ -620,9 +725,10 @@ async fn es_scroll(
         scroll_ttl_secs,
     };
     let search_response: SearchResponse = search_service.scroll(scroll_request).await?;
-    // TODO append_shard_doc depends on the initial request, but we don't have access to it
+    // TODO the sort fields and append_shard_doc depend on the initial request, but we don't have
+    // access to it. Only the sort values present on the hits are returned.
     let mut search_response_rest: ElasticsearchResponse =
-        convert_to_es_search_response(search_response, false);
+        convert_to_es_search_response(search_response, None, false);
     search_response_rest.took = start_instant.elapsed().as_millis() as u32;
     Ok(search_response_rest)
 }
@@ -684,12 +790,13 @@ fn convert_to_es_stats_response(
 
 fn convert_to_es_search_response(
     resp: SearchResponse,
+    num_sort_values_opt: Option<usize>,
     append_shard_doc: bool,
 ) -> ElasticsearchResponse {
     let hits: Vec<ElasticHit> = resp
         .hits
         .into_iter()
-        .map(|hit| convert_hit(hit, append_shard_doc))
+        .map(|hit| convert_hit(hit, num_sort_values_opt, append_shard_doc))
         .collect();
     let aggregations: Option<serde_json::Value> = if let Some(aggregation_json) = resp.aggregation {
         serde_json::from_str(&aggregation_json).ok()
@@ -721,8 +828,88 @@ pub(crate) fn str_lines(body: &str) -> impl Iterator<Item = &str> {
 #[cfg(test)]
 mod tests {
     use hyper::StatusCode;
+    use quickwit_proto::search::{Hit, PartialHit, SortByValue, SortValue};
 
-    use super::partial_hit_from_search_after_param;
+    use super::{convert_hit, partial_hit_from_search_after_param};
+
+    #[test]
+    fn test_convert_hit_with_missing_sort_values() {
+        let hit = Hit {
+            json: r#"{"name": "Bob"}"#.to_string(),
+            partial_hit: Some(PartialHit {
+                sort_value: Some(SortByValue {
+                    sort_value: Some(SortValue::Str("Bob".to_string())),
+                }),
+                sort_value2: None,
+                additional_sort_values: vec![SortByValue {
+                    sort_value: Some(SortValue::U64(3)),
+                }],
+                split_id: "split1".to_string(),
+                segment_ord: 0,
+                doc_id: 1,
+            }),
+            index_id: "index1".to_string(),
+            ..Default::default()
+        };
+        let elastic_hit = convert_hit(hit.clone(), Some(4), false);
+        assert_eq!(
+            elastic_hit.sort,
+            vec![
+                serde_json::json!("Bob"),
+                serde_json::Value::Null,
+                serde_json::json!(3),
+                serde_json::Value::Null,
+            ]
+        );
+        let elastic_hit = convert_hit(hit, None, false);
+        assert_eq!(
+            elastic_hit.sort,
+            vec![serde_json::json!("Bob"), serde_json::json!(3)]
+        );
+    }
+
+    #[test]
+    fn test_partial_hit_from_search_after_param_with_missing_sort_values() {
+        use quickwit_proto::search::SortField;
+
+        let hit = Hit {
+            json: r#"{"id": 3}"#.to_string(),
+            partial_hit: Some(PartialHit {
+                sort_value: None,
+                sort_value2: Some(SortByValue {
+                    sort_value: Some(SortValue::U64(3)),
+                }),
+                additional_sort_values: Vec::new(),
+                split_id: "split1".to_string(),
+                segment_ord: 0,
+                doc_id: 1,
+            }),
+            index_id: "index1".to_string(),
+            ..Default::default()
+        };
+        // The sort values of a hit can be sent back as `search_after`.
+        let search_after = convert_hit(hit, Some(2), false).sort;
+        assert_eq!(
+            search_after,
+            vec![serde_json::Value::Null, serde_json::json!(3)]
+        );
+        let sort_order: Vec<SortField> = ["count", "id"]
+            .into_iter()
+            .map(|field_name| SortField {
+                field_name: field_name.to_string(),
+                sort_order: 1,
+                sort_datetime_format: None,
+            })
+            .collect();
+        let partial_hit = partial_hit_from_search_after_param(search_after, &sort_order)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            partial_hit.sort_value,
+            Some(SortByValue { sort_value: None })
+        );
+        assert_eq!(partial_hit.sort_value2, Some(SortValue::U64(3).into()));
+    }
 
     #[test]
     fn test_partial_hit_from_search_after_param_invalid_length() {
@@ -752,6 +939,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_partial_hit_from_search_after_param_with_string_values() {
+        use quickwit_proto::search::{SortByValue, SortField, SortValue};
+
+        let search_after = vec![
+            serde_json::json!("host-1"),
+            serde_json::json!(12),
+            serde_json::json!("1700000000000"),
+        ];
+        let sort_order: Vec<SortField> = ["host", "status", "timestamp"]
+            .into_iter()
+            .map(|field_name| SortField {
+                field_name: field_name.to_string(),
+                sort_order: 1,
+                sort_datetime_format: None,
+            })
+            .collect();
+        let partial_hit = partial_hit_from_search_after_param(search_after, &sort_order)
+            .unwrap()
+            .unwrap();
+        let sort_values: Vec<Option<SortByValue>> = partial_hit
+            .sort_values()
+            .map(|sort_value| sort_value.cloned())
+            .collect();
+        assert_eq!(
+            sort_values,
+            vec![
+                Some(SortValue::Str("host-1".to_string()).into()),
+                Some(SortValue::U64(12).into()),
+                Some(SortValue::Str("1700000000000".to_string()).into()),
+            ]
+        );
+    }
+
     #[test]
     fn test_partial_hit_from_search_after_param_invalid_search_after_doc_id() {
         let search_after = vec![serde_json::json!("split_id:1112")];
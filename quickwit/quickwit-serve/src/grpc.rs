@@ -21,6 +21,7 @@ use std::collections::BTreeSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use anyhow::Context;
 use bytesize::ByteSize;
 use quickwit_common::tower::BoxFutureInfaillible;
 use quickwit_config::service::QuickwitService;
@@ -34,6 +35,7 @@ use quickwit_proto::tonic::transport::Server;
 use tracing::*;
 
 use crate::search_api::GrpcSearchAdapter;
+use crate::tls::make_grpc_server_tls_config;
 use crate::QuickwitServices;
 
 /// Starts and binds gRPC services to `grpc_listen_addr`.
@@ -47,6 +49,13 @@ pub(crate) async fn start_grpc_server(
     let mut enabled_grpc_services = BTreeSet::new();
     let mut server = Server::builder();
 
+    if let Some(tls_config) = &services.node_config.grpc_config.tls {
+        let server_tls_config = make_grpc_server_tls_config(tls_config)?;
+        server = server
+            .tls_config(server_tls_config)
+            .context("failed to build gRPC server TLS config")?;
+    }
+
     // Mount gRPC metastore service if `QuickwitService::Metastore` is enabled on node.
     let metastore_grpc_service = if let Some(metastore_server) = &services.metastore_server_opt {
         enabled_grpc_services.insert("metastore");
@@ -56,11 +56,26 @@ struct InvalidUtf8;
 
 impl warp::reject::Reject for InvalidUtf8 {}
 
+/// Format of the documents in the body of an ingest request.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum IngestFormat {
+    /// One JSON object per line.
+    #[default]
+    Json,
+    /// Comma-separated values, starting with a header row.
+    Csv,
+    /// Tab-separated values, starting with a header row.
+    Tsv,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
 struct IngestOptions {
     #[serde(alias = "commit")]
     #[serde(default)]
     commit_type: CommitType,
+    #[serde(default)]
+    format: IngestFormat,
 }
 
 pub(crate) fn ingest_api_handlers(
@@ -78,10 +93,7 @@ fn ingest_filter(
 ) -> impl Filter<Extract = (String, Bytes, IngestOptions), Error = Rejection> + Clone {
     warp::path!(String / "ingest")
         .and(warp::post())
-        .and(warp::body::content_length_limit(
-            config.content_length_limit.as_u64(),
-        ))
-        .and(get_body_bytes())
+        .and(get_body_bytes(config.content_length_limit))
         .and(serde_qs::warp::query::<IngestOptions>(
             serde_qs::Config::default(),
         ))
@@ -102,10 +114,7 @@ fn ingest_v2_filter(
 ) -> impl Filter<Extract = (String, Bytes, IngestOptions), Error = Rejection> + Clone {
     warp::path!(String / "ingest-v2")
         .and(warp::post())
-        .and(warp::body::content_length_limit(
-            config.content_length_limit.as_u64(),
-        ))
-        .and(get_body_bytes())
+        .and(get_body_bytes(config.content_length_limit))
         .and(serde_qs::warp::query::<IngestOptions>(
             serde_qs::Config::default(),
         ))
@@ -130,8 +139,8 @@ async fn ingest_v2(
 ) -> Result<IngestResponse, IngestServiceError> {
     let mut doc_batch_builder = DocBatchV2Builder::default();
 
-    for doc in lines(&body) {
-        doc_batch_builder.add_doc(doc);
+    for doc in docs(&body, ingest_options.format)? {
+        doc_batch_builder.add_doc(&doc);
     }
     let doc_batch_opt = doc_batch_builder.build();
 
@@ -194,13 +203,14 @@ fn convert_ingest_response_v2(
     post,
     tag = "Ingest",
     path = "/{index_id}/ingest",
-    request_body(content = String, description = "Documents to ingest in NDJSON format and limited to 10MB", content_type = "application/json"),
+    request_body(content = String, description = "Documents to ingest in NDJSON, CSV or TSV format and limited to 10MB", content_type = "application/json"),
     responses(
         (status = 200, description = "Successfully ingested documents.", body = IngestResponse)
     ),
     params(
         ("index_id" = String, Path, description = "The index ID to add docs to."),
         ("commit" = Option<CommitType>, Query, description = "Force or wait for commit at the end of the indexing operation."),
+        ("format" = Option<String>, Query, description = "Format of the documents: `json` (default), `csv` or `tsv`. CSV and TSV bodies must start with a header row."),
     )
 )]
 /// Ingest documents
@@ -213,8 +223,8 @@ async fn ingest(
     // The size of the body should be an upper bound of the size of the batch. The removal of the
     // end of line character for each doc compensates the addition of the `DocCommand` header.
     let mut doc_batch_builder = DocBatchBuilder::with_capacity(index_id, body.remaining());
-    for line in lines(&body) {
-        doc_batch_builder.ingest_doc(line);
+    for doc in docs(&body, ingest_options.format)? {
+        doc_batch_builder.ingest_doc(doc);
     }
     let ingest_req = IngestRequest {
         doc_batches: vec![doc_batch_builder.build()],
@@ -258,6 +268,49 @@ async fn tail_endpoint(
     Ok(fetch_response)
 }
 
+/// Returns the documents of an ingest request body. CSV and TSV records are converted into JSON
+/// objects keyed by the column names of the header row that starts the body.
+fn docs(body: &Bytes, format: IngestFormat) -> Result<Vec<Bytes>, IngestServiceError> {
+    let delimiter = match format {
+        IngestFormat::Json => {
+            let docs = lines(body).map(|line| body.slice_ref(line)).collect();
+            return Ok(docs);
+        }
+        IngestFormat::Csv => b',',
+        IngestFormat::Tsv => b'\t',
+    };
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(body.as_ref());
+    let columns = csv_reader
+        .headers()
+        .map_err(|error| IngestServiceError::BadRequest(format!("invalid CSV header: {error}")))?
+        .clone();
+    let mut docs = Vec::new();
+
+    for record_res in csv_reader.records() {
+        let record = record_res.map_err(|error| {
+            IngestServiceError::BadRequest(format!("invalid CSV record: {error}"))
+        })?;
+        if record.len() > columns.len() {
+            return Err(IngestServiceError::BadRequest(format!(
+                "CSV record has {} fields but the header row defines {} columns",
+                record.len(),
+                columns.len()
+            )));
+        }
+        let json_obj: serde_json::Map<String, serde_json::Value> = columns
+            .iter()
+            .zip(record.iter())
+            .map(|(column, field)| (column.to_string(), field.into()))
+            .collect();
+        let doc = serde_json::to_vec(&json_obj).expect("JSON object should serialize");
+        docs.push(Bytes::from(doc));
+    }
+    Ok(docs)
+}
+
 pub(crate) fn lines(body: &Bytes) -> impl Iterator<Item = &[u8]> {
     body.split(|byte| byte == &b'\n')
         .filter(|line| !is_empty_or_blank_line(line))
@@ -284,7 +337,7 @@ pub(crate) mod tests {
     };
     use quickwit_proto::ingest::router::IngestRouterServiceClient;
 
-    use super::ingest_api_handlers;
+    use super::{docs, ingest_api_handlers, IngestFormat};
     use crate::ingest_api::lines;
 
     #[test]
@@ -305,6 +358,38 @@ pub(crate) mod tests {
         }
     }
 
+    #[test]
+    fn test_docs_from_csv_body() {
+        let json_docs = |body: &'static [u8], format: IngestFormat| -> Vec<String> {
+            docs(&Bytes::from_static(body), format)
+                .unwrap()
+                .iter()
+                .map(|doc| str::from_utf8(doc).unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            json_docs(
+                b"host,status\ncdn-1,200\n\ncdn-2\n\"cdn,3\",503\n",
+                IngestFormat::Csv
+            ),
+            [
+                r#"{"host":"cdn-1","status":"200"}"#,
+                r#"{"host":"cdn-2"}"#,
+                r#"{"host":"cdn,3","status":"503"}"#,
+            ]
+        );
+        assert_eq!(
+            json_docs(b"host\tstatus\ncdn-1\t200\n", IngestFormat::Tsv),
+            [r#"{"host":"cdn-1","status":"200"}"#]
+        );
+        let body = Bytes::from_static(b"host,status\ncdn-1,200,extra\n");
+        let error = docs(&body, IngestFormat::Csv).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "bad request: CSV record has 3 fields but the header row defines 2 columns"
+        );
+    }
+
     pub(crate) async fn setup_ingest_service(
         queues: &[&str],
         config: &IngestApiConfig,
@@ -393,6 +478,44 @@ pub(crate) mod tests {
         universe.assert_quit().await;
     }
 
+    #[tokio::test]
+    async fn test_ingest_api_returns_200_when_ingest_csv_and_fetch() {
+        let (universe, _temp_dir, ingest_service, _) =
+            setup_ingest_service(&["my-index"], &IngestApiConfig::default()).await;
+        let ingest_router = IngestRouterServiceClient::mock().into();
+        let ingest_api_handlers =
+            ingest_api_handlers(ingest_router, ingest_service, IngestApiConfig::default());
+        let resp = warp::test::request()
+            .path("/my-index/ingest?format=csv")
+            .method("POST")
+            .body("id,message\n1,push\n2,push\n")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let ingest_response: IngestResponse = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(ingest_response.num_docs_for_processing, 2);
+
+        let resp = warp::test::request()
+            .path("/my-index/tail")
+            .method("GET")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let fetch_response: FetchResponse = serde_json::from_slice(resp.body()).unwrap();
+        let doc_batch = fetch_response.doc_batch.unwrap();
+        assert_eq!(doc_batch.num_docs(), 2);
+
+        let resp = warp::test::request()
+            .path("/my-index/ingest?format=csv")
+            .method("POST")
+            .body("id,message\n1,push,extra\n")
+            .reply(&ingest_api_handlers)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        universe.assert_quit().await;
+    }
+
     #[tokio::test]
     async fn test_ingest_api_return_429_if_above_limits() {
         let config = IngestApiConfig {
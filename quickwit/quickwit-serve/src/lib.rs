@@ -38,9 +38,11 @@ mod otlp_api;
 mod rate_modulator;
 mod rest;
 mod rest_api_response;
+mod rest_auth;
 mod search_api;
 pub(crate) mod simple_list;
 mod template_api;
+mod tls;
 mod ui_handler;
 
 use std::collections::{HashMap, HashSet};
@@ -18,6 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use bytes::Bytes;
+use bytesize::ByteSize;
 use quickwit_opentelemetry::otlp::{
     OtlpGrpcLogsService, OtlpGrpcTracesService, OTEL_LOGS_INDEX_ID, OTEL_TRACES_INDEX_ID,
 };
@@ -34,9 +35,12 @@ use serde::{self, Serialize};
 use tracing::error;
 use warp::{Filter, Rejection};
 
+use crate::decompression::get_body_bytes;
 use crate::rest_api_response::into_rest_api_response;
 use crate::{require, with_arg, BodyFormat};
 
+const CONTENT_LENGTH_LIMIT: ByteSize = ByteSize::mib(10);
+
 #[derive(utoipa::OpenApi)]
 #[openapi(paths())]
 pub(crate) struct OtlpApi;
@@ -62,7 +66,7 @@ pub(crate) fn otlp_default_logs_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(warp::body::bytes())
+        .and(get_body_bytes(CONTENT_LENGTH_LIMIT))
         .then(|otlp_logs_service, body| async move {
             otlp_ingest_logs(otlp_logs_service, OTEL_LOGS_INDEX_ID.to_string(), body).await
         })
@@ -80,7 +84,7 @@ pub(crate) fn otlp_logs_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(warp::body::bytes())
+        .and(get_body_bytes(CONTENT_LENGTH_LIMIT))
         .then(otlp_ingest_logs)
         .and(with_arg(BodyFormat::default()))
         .map(into_rest_api_response)
@@ -96,7 +100,7 @@ pub(crate) fn otlp_default_traces_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(warp::body::bytes())
+        .and(get_body_bytes(CONTENT_LENGTH_LIMIT))
         .then(|otlp_traces_service, body| async move {
             otlp_ingest_traces(otlp_traces_service, OTEL_TRACES_INDEX_ID.to_string(), body).await
         })
@@ -114,7 +118,7 @@ pub(crate) fn otlp_ingest_traces_handler(
             "application/x-protobuf",
         ))
         .and(warp::post())
-        .and(warp::body::bytes())
+        .and(get_body_bytes(CONTENT_LENGTH_LIMIT))
         .then(otlp_ingest_traces)
         .and(with_arg(BodyFormat::default()))
         .map(into_rest_api_response)
@@ -20,10 +20,13 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use futures::future::Either;
 use hyper::http::HeaderValue;
 use hyper::{http, Method};
 use quickwit_common::tower::BoxFutureInfaillible;
 use quickwit_proto::ServiceErrorCode;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
 use tower::make::Shared;
 use tower::ServiceBuilder;
 use tower_http::compression::predicate::{DefaultPredicate, Predicate, SizeAbove};
@@ -34,7 +37,7 @@ use warp::{redirect, Filter, Rejection, Reply};
 
 use crate::cluster_api::cluster_handler;
 use crate::debugging_api::debugging_handler;
-use crate::decompression::{CorruptedData, UnsupportedEncoding};
+use crate::decompression::{CorruptedData, DecompressedPayloadTooLarge, UnsupportedEncoding};
 use crate::delete_task_api::delete_task_api_handlers;
 use crate::elasticsearch_api::elastic_api_handlers;
 use crate::health_check_api::health_check_handlers;
@@ -46,8 +49,10 @@ use crate::metrics_api::metrics_handler;
 use crate::node_info_handler::node_info_handler;
 use crate::otlp_api::otlp_ingest_api_handlers;
 use crate::rest_api_response::{RestApiError, RestApiResponse};
+use crate::rest_auth::{rest_auth_filter, Forbidden, Unauthenticated};
 use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
 use crate::template_api::index_template_api_handlers;
+use crate::tls::{make_rustls_server_config, tls_incoming};
 use crate::ui_handler::ui_handler;
 use crate::{BodyFormat, BuildInfo, QuickwitServices, RuntimeInfo};
 
@@ -111,14 +116,19 @@ pub(crate) async fn start_rest_server(
             .clone(),
     );
 
+    let auth_filter = rest_auth_filter(quickwit_services.node_config.rest_config.auth.clone());
+
     // Combine all the routes together.
-    let rest_routes = api_v1_root_route
-        .or(api_doc)
-        .or(redirect_root_to_ui_route)
-        .or(ui_handler())
-        .or(health_check_routes)
-        .or(metrics_routes)
-        .or(debugging_routes)
+    let rest_routes = auth_filter
+        .and(
+            api_v1_root_route
+                .or(api_doc)
+                .or(redirect_root_to_ui_route)
+                .or(ui_handler())
+                .or(health_check_routes)
+                .or(metrics_routes)
+                .or(debugging_routes),
+        )
         .with(request_counter)
         .recover(recover_fn)
         .with(extra_headers)
@@ -138,8 +148,18 @@ pub(crate) async fn start_rest_server(
         .layer(cors)
         .service(warp_service);
 
+    let server = if let Some(tls_config) = &quickwit_services.node_config.rest_config.tls {
+        let server_config = make_rustls_server_config(tls_config)?;
+        let tls_acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let tcp_listener = TcpListener::bind(rest_listen_addr).await?;
+        let incoming = tls_incoming(tcp_listener, tls_acceptor);
+        Either::Left(hyper::Server::builder(incoming).serve(Shared::new(service)))
+    } else {
+        Either::Right(hyper::Server::bind(&rest_listen_addr).serve(Shared::new(service)))
+    };
     info!(
         rest_listen_addr=?rest_listen_addr,
+        tls_enabled=quickwit_services.node_config.rest_config.tls.is_some(),
         "Starting REST server listening on {rest_listen_addr}."
     );
 
@@ -151,7 +171,7 @@ pub(crate) async fn start_rest_server(
     // https://github.com/hyperium/hyper/issues/2386
     let serve_fut = async move {
         tokio::select! {
-             res = hyper::Server::bind(&rest_listen_addr).serve(Shared::new(service)) => { res }
+             res = server => { res }
              _ = shutdown_signal => { Ok(()) }
         }
     };
@@ -226,13 +246,20 @@ fn api_v1_routes(
 // More on this here: https://github.com/seanmonstar/warp/issues/388.
 // We may use this work on the PR is merged: https://github.com/seanmonstar/warp/pull/909.
 pub async fn recover_fn(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    let is_unauthenticated = rejection.find::<Unauthenticated>().is_some();
     let err = get_status_with_error(rejection);
     let status_code = err.service_code.to_http_status_code();
-    Ok(RestApiResponse::new::<(), _>(
-        &Err(err),
-        status_code,
-        &BodyFormat::default(),
-    ))
+    let mut response =
+        RestApiResponse::new::<(), _>(&Err(err), status_code, &BodyFormat::default())
+            .into_response();
+    if is_unauthenticated {
+        // Lets browsers prompt for credentials when basic auth is enabled.
+        response.headers_mut().insert(
+            http::header::WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"quickwit\""),
+        );
+    }
+    Ok(response)
 }
 
 fn get_status_with_error(rejection: Rejection) -> RestApiError {
@@ -241,6 +268,16 @@ fn get_status_with_error(rejection: Rejection) -> RestApiError {
             service_code: ServiceErrorCode::UnsupportedMediaType,
             message: error.to_string(),
         }
+    } else if let Some(error) = rejection.find::<Unauthenticated>() {
+        RestApiError {
+            service_code: ServiceErrorCode::Unauthenticated,
+            message: error.to_string(),
+        }
+    } else if let Some(error) = rejection.find::<Forbidden>() {
+        RestApiError {
+            service_code: ServiceErrorCode::Forbidden,
+            message: error.to_string(),
+        }
     } else if rejection.is_not_found() {
         RestApiError {
             service_code: ServiceErrorCode::NotFound,
@@ -314,6 +351,11 @@ fn get_status_with_error(rejection: Rejection) -> RestApiError {
             service_code: ServiceErrorCode::BadRequest,
             message: error.to_string(),
         }
+    } else if let Some(error) = rejection.find::<DecompressedPayloadTooLarge>() {
+        RestApiError {
+            service_code: ServiceErrorCode::BadRequest,
+            message: error.to_string(),
+        }
     } else {
         error!("REST server error: {:?}", rejection);
         RestApiError {
@@ -359,7 +401,7 @@ mod tests {
     use http::HeaderName;
     use hyper::{Request, Response, StatusCode};
     use quickwit_cluster::{create_cluster_for_test, ChannelTransport};
-    use quickwit_config::NodeConfig;
+    use quickwit_config::{ApiKeyConfig, NodeConfig, PermissionConfig, RestAuthConfig, Role};
     use quickwit_index_management::IndexService;
     use quickwit_ingest::{IngestApiService, IngestServiceClient};
     use quickwit_proto::control_plane::ControlPlaneServiceClient;
@@ -667,4 +709,82 @@ mod tests {
             "custom-value-2"
         );
     }
+
+    #[tokio::test]
+    async fn test_rest_auth() {
+        let mut node_config = NodeConfig::for_test();
+        node_config.rest_config.auth = Some(RestAuthConfig {
+            api_keys: vec![ApiKeyConfig {
+                name: "reader".to_string(),
+                key: "my-secret-key".to_string(),
+                permissions: vec![PermissionConfig {
+                    index_patterns: vec!["*".to_string()],
+                    roles: vec![Role::Read],
+                }],
+            }],
+            users: Vec::new(),
+        });
+        let metastore_client = MetastoreServiceClient::from(MetastoreServiceClient::mock());
+        let index_service =
+            IndexService::new(metastore_client.clone(), StorageResolver::unconfigured());
+        let control_plane_service =
+            ControlPlaneServiceClient::from(ControlPlaneServiceClient::mock());
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &[], &transport, false)
+            .await
+            .unwrap();
+        let quickwit_services = QuickwitServices {
+            _report_splits_subscription_handle_opt: None,
+            _local_shards_update_listener_handle_opt: None,
+            cluster,
+            control_plane_service,
+            indexing_service_opt: None,
+            index_manager: index_service,
+            ingest_service: ingest_service_client(),
+            ingester_service_opt: None,
+            ingest_router_service: IngestRouterServiceClient::from(
+                IngestRouterServiceClient::mock(),
+            ),
+            janitor_service_opt: None,
+            otlp_logs_service_opt: None,
+            otlp_traces_service_opt: None,
+            metastore_client,
+            metastore_server_opt: None,
+            node_config: Arc::new(node_config.clone()),
+            search_service: Arc::new(MockSearchService::new()),
+            jaeger_service_opt: None,
+        };
+        let handler = rest_auth_filter(node_config.rest_config.auth.clone())
+            .and(api_v1_routes(Arc::new(quickwit_services)))
+            .recover(recover_fn);
+
+        let resp_401 = warp::test::request()
+            .path("/api/v1/version")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp_401.status(), 401);
+        assert_eq!(
+            resp_401.headers().get("www-authenticate").unwrap(),
+            "Basic realm=\"quickwit\""
+        );
+
+        let resp = warp::test::request()
+            .path("/api/v1/version")
+            .header("authorization", "Bearer my-secret-key")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+
+        let resp_403 = warp::test::request()
+            .path("/api/v1/cluster")
+            .header("authorization", "Bearer my-secret-key")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp_403.status(), 403);
+        let body: serde_json::Value = serde_json::from_slice(resp_403.body()).unwrap();
+        assert_eq!(
+            body["message"],
+            "`reader` is not allowed to perform this request"
+        );
+    }
 }
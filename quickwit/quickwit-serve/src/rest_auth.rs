@@ -0,0 +1,589 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use hyper::Method;
+use percent_encoding::percent_decode_str;
+use quickwit_config::{PermissionConfig, RestAuthConfig, Role};
+use quickwit_opentelemetry::otlp::{OTEL_LOGS_INDEX_ID, OTEL_TRACES_INDEX_ID};
+use thiserror::Error;
+use tracing::warn;
+use warp::path::FullPath;
+use warp::reject::Reject;
+use warp::{Filter, Rejection};
+
+#[derive(Debug, Error)]
+#[error("missing or invalid credentials")]
+pub(crate) struct Unauthenticated;
+
+impl Reject for Unauthenticated {}
+
+#[derive(Debug, Error)]
+#[error("`{principal}` is not allowed to perform this request")]
+pub(crate) struct Forbidden {
+    principal: String,
+}
+
+impl Reject for Forbidden {}
+
+/// Permission required to perform a REST request.
+#[derive(Debug, Eq, PartialEq)]
+enum RequiredPermission {
+    /// Any authenticated principal is allowed.
+    Authenticated,
+    /// The principal must have the role on all the indexes targeted by the comma-separated index
+    /// ID patterns.
+    Role {
+        role: Role,
+        index_id_patterns: String,
+    },
+}
+
+fn role_on(role: Role, index_id_patterns: &str) -> RequiredPermission {
+    RequiredPermission::Role {
+        role,
+        index_id_patterns: index_id_patterns.to_string(),
+    }
+}
+
+enum Credentials {
+    ApiKey(String),
+    Basic { username: String, password: String },
+}
+
+struct Principal {
+    name: String,
+    credentials: Credentials,
+    permissions: Vec<PermissionConfig>,
+}
+
+impl Principal {
+    fn is_allowed(&self, required_permission: &RequiredPermission) -> bool {
+        let RequiredPermission::Role {
+            role,
+            index_id_patterns,
+        } = required_permission
+        else {
+            return true;
+        };
+        // Negative patterns only exclude indexes, so they never require any permission.
+        let mut positive_index_id_patterns = index_id_patterns
+            .split(',')
+            .map(str::trim)
+            .filter(|index_id_pattern| {
+                !index_id_pattern.is_empty() && !index_id_pattern.starts_with('-')
+            })
+            .peekable();
+
+        if positive_index_id_patterns.peek().is_none() {
+            return self.has_role_on(*role, "*");
+        }
+        positive_index_id_patterns.all(|index_id_pattern| {
+            if index_id_pattern == "_all" {
+                self.has_role_on(*role, "*")
+            } else {
+                self.has_role_on(*role, index_id_pattern)
+            }
+        })
+    }
+
+    fn has_role_on(&self, role: Role, index_id_pattern: &str) -> bool {
+        self.permissions.iter().any(|permission| {
+            permission
+                .roles
+                .iter()
+                .any(|granted_role| granted_role.includes(role))
+                && permission
+                    .index_patterns
+                    .iter()
+                    .any(|granted_pattern| matches_pattern(granted_pattern, index_id_pattern))
+        })
+    }
+}
+
+fn principals_from_config(auth_config: RestAuthConfig) -> Vec<Principal> {
+    let api_key_principals = auth_config
+        .api_keys
+        .into_iter()
+        .map(|api_key_config| Principal {
+            name: api_key_config.name,
+            credentials: Credentials::ApiKey(api_key_config.key),
+            permissions: api_key_config.permissions,
+        });
+    let user_principals = auth_config.users.into_iter().map(|user_config| Principal {
+        name: user_config.username.clone(),
+        credentials: Credentials::Basic {
+            username: user_config.username,
+            password: user_config.password,
+        },
+        permissions: user_config.permissions,
+    });
+    api_key_principals.chain(user_principals).collect()
+}
+
+/// Authenticates and authorizes REST requests. When no auth config is provided, all the requests
+/// are allowed.
+pub(crate) fn rest_auth_filter(
+    auth_config_opt: Option<RestAuthConfig>,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let principals_opt: Option<Arc<Vec<Principal>>> =
+        auth_config_opt.map(|auth_config| Arc::new(principals_from_config(auth_config)));
+    warp::method()
+        .and(warp::path::full())
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(
+            move |method: Method, full_path: FullPath, authorization_opt: Option<String>| {
+                let principals_opt = principals_opt.clone();
+                async move {
+                    if let Some(principals) = principals_opt {
+                        authorize(
+                            &principals,
+                            &method,
+                            full_path.as_str(),
+                            authorization_opt.as_deref(),
+                        )?;
+                    }
+                    Ok::<_, Rejection>(())
+                }
+            },
+        )
+        .untuple_one()
+}
+
+fn authorize(
+    principals: &[Principal],
+    method: &Method,
+    path: &str,
+    authorization_opt: Option<&str>,
+) -> Result<(), Rejection> {
+    let Some(required_permission) = required_permission(method, path) else {
+        return Ok(());
+    };
+    let principal = authorization_opt
+        .and_then(|authorization| authenticate(principals, authorization))
+        .ok_or_else(|| warp::reject::custom(Unauthenticated))?;
+
+    if !principal.is_allowed(&required_permission) {
+        warn!(
+            principal=%principal.name,
+            method=%method,
+            path=%path,
+            "request denied"
+        );
+        return Err(warp::reject::custom(Forbidden {
+            principal: principal.name.clone(),
+        }));
+    }
+    Ok(())
+}
+
+/// Finds the principal matching the credentials of the `Authorization` header, which must use
+/// either the `Bearer` or the `Basic` scheme.
+fn authenticate<'a>(principals: &'a [Principal], authorization: &str) -> Option<&'a Principal> {
+    let (scheme, credentials) = authorization.trim().split_once(' ')?;
+    let credentials = credentials.trim();
+
+    if scheme.eq_ignore_ascii_case("bearer") {
+        principals
+            .iter()
+            .find(|principal| match &principal.credentials {
+                Credentials::ApiKey(key) => {
+                    constant_time_eq(key.as_bytes(), credentials.as_bytes())
+                }
+                Credentials::Basic { .. } => false,
+            })
+    } else if scheme.eq_ignore_ascii_case("basic") {
+        let decoded_credentials = BASE64_STANDARD.decode(credentials).ok()?;
+        let decoded_credentials = String::from_utf8(decoded_credentials).ok()?;
+        let (username, password) = decoded_credentials.split_once(':')?;
+
+        principals
+            .iter()
+            .find(|principal| match &principal.credentials {
+                Credentials::Basic {
+                    username: expected_username,
+                    password: expected_password,
+                } => {
+                    expected_username == username
+                        && constant_time_eq(expected_password.as_bytes(), password.as_bytes())
+                }
+                Credentials::ApiKey(_) => false,
+            })
+    } else {
+        None
+    }
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.iter()
+        .zip(right)
+        .fold(0u8, |acc, (left_byte, right_byte)| {
+            acc | (left_byte ^ right_byte)
+        })
+        == 0
+}
+
+/// Returns whether `pattern`, in which `*` matches any sequence of characters, matches `value`.
+/// `value` is matched literally, so a granted pattern matching a requested index ID pattern also
+/// matches all the index IDs the requested pattern can resolve to.
+fn matches_pattern(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let value = value.as_bytes();
+    let mut pattern_pos = 0;
+    let mut value_pos = 0;
+    let mut backtrack_opt: Option<(usize, usize)> = None;
+
+    while value_pos < value.len() {
+        if pattern_pos < pattern.len() && pattern[pattern_pos] == b'*' {
+            backtrack_opt = Some((pattern_pos, value_pos));
+            pattern_pos += 1;
+        } else if pattern_pos < pattern.len() && pattern[pattern_pos] == value[value_pos] {
+            pattern_pos += 1;
+            value_pos += 1;
+        } else if let Some((star_pos, star_value_pos)) = backtrack_opt {
+            backtrack_opt = Some((star_pos, star_value_pos + 1));
+            pattern_pos = star_pos + 1;
+            value_pos = star_value_pos + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[pattern_pos..].iter().all(|byte| *byte == b'*')
+}
+
+/// Returns the permission required to perform a REST request, or `None` if the route is public.
+///
+/// Routes whose target indexes are only known from the request body (bulk ingestion, multi-search,
+/// scroll) require the role on all the indexes. For instance, searching a point in time through
+/// `_elastic/_search` requires the read role on all the indexes, whereas searching it through
+/// `_elastic/{index}/_search` only requires it on the indexes of the point in time. Unknown routes
+/// require the admin role on all the indexes.
+fn required_permission(method: &Method, path: &str) -> Option<RequiredPermission> {
+    let decoded_segments: Vec<Cow<str>> = path
+        .trim_matches('/')
+        .split('/')
+        .map(|segment| percent_decode_str(segment).decode_utf8_lossy())
+        .collect();
+    let segments: Vec<&str> = decoded_segments
+        .iter()
+        .map(|segment| segment.as_ref())
+        .collect();
+
+    match segments.as_slice() {
+        ["health", ..] => None,
+        ["debugging", ..] => Some(role_on(Role::Admin, "*")),
+        ["api", "v1", api_v1_segments @ ..] => {
+            Some(api_v1_required_permission(method, api_v1_segments))
+        }
+        // UI, OpenAPI docs and metrics.
+        _ => Some(RequiredPermission::Authenticated),
+    }
+}
+
+fn api_v1_required_permission(method: &Method, segments: &[&str]) -> RequiredPermission {
+    let is_read_only = matches!(*method, Method::GET | Method::HEAD);
+    let read_or_admin_on = |index_id_patterns: &str| {
+        if is_read_only {
+            role_on(Role::Read, index_id_patterns)
+        } else {
+            role_on(Role::Admin, index_id_patterns)
+        }
+    };
+    // The order of the arms follows the order in which the routes are matched by the REST server.
+    match segments {
+        ["version"] | ["analyze"] | ["_elastic"] => RequiredPermission::Authenticated,
+        [index_id_patterns, "search"] | [index_id_patterns, "search", "stream"] => {
+            role_on(Role::Read, index_id_patterns)
+        }
+        [index_id, "ingest" | "ingest-v2"] => role_on(Role::Ingest, index_id),
+        [index_id, "tail"] => role_on(Role::Read, index_id),
+        ["otlp", "v1", "logs"] => role_on(Role::Ingest, OTEL_LOGS_INDEX_ID),
+        ["otlp", "v1", "traces"] => role_on(Role::Ingest, OTEL_TRACES_INDEX_ID),
+        [index_id, "otlp", "v1", "logs" | "traces"] => role_on(Role::Ingest, index_id),
+        ["indexes"] if is_read_only => role_on(Role::Read, "*"),
+        ["indexes", index_id, ..] => read_or_admin_on(index_id),
+        [index_id, "delete-tasks"] => read_or_admin_on(index_id),
+        [index_id_patterns, "jaeger", "api", ..] => role_on(Role::Read, index_id_patterns),
+        ["_elastic", "_bulk"] | ["_elastic", _, "_bulk"] => role_on(Role::Ingest, "*"),
+        ["_elastic", "_cat", "indices", index_id_patterns] => {
+            role_on(Role::Read, index_id_patterns)
+        }
+        // Point in time IDs cannot be guessed and are only handed out to principals allowed to
+        // read their indexes. Closing a point in time merely releases the splits it pins.
+        ["_elastic", "_pit"] => RequiredPermission::Authenticated,
+        ["_elastic", endpoint, ..] if endpoint.starts_with('_') => role_on(Role::Read, "*"),
+        ["_elastic", index_id_patterns, "_search" | "_count" | "_field_caps" | "_stats"] => {
+            role_on(Role::Read, index_id_patterns)
+        }
+        ["_elastic", index_id_patterns, "_pit"] => role_on(Role::Read, index_id_patterns),
+        // Index creation, cluster state, indexing pipelines, index templates, node config, and
+        // unknown routes.
+        _ => role_on(Role::Admin, "*"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_config::{ApiKeyConfig, UserConfig};
+
+    use super::*;
+
+    fn auth_config_for_test() -> RestAuthConfig {
+        RestAuthConfig {
+            api_keys: vec![ApiKeyConfig {
+                name: "log-shipper".to_string(),
+                key: "my-secret-key".to_string(),
+                permissions: vec![PermissionConfig {
+                    index_patterns: vec!["logs-*".to_string()],
+                    roles: vec![Role::Ingest],
+                }],
+            }],
+            users: vec![
+                UserConfig {
+                    username: "alice".to_string(),
+                    password: "my-password".to_string(),
+                    permissions: vec![PermissionConfig {
+                        index_patterns: vec!["logs-*".to_string(), "traces".to_string()],
+                        roles: vec![Role::Read],
+                    }],
+                },
+                UserConfig {
+                    username: "admin".to_string(),
+                    password: "my-admin-password".to_string(),
+                    permissions: vec![PermissionConfig {
+                        index_patterns: vec!["*".to_string()],
+                        roles: vec![Role::Admin],
+                    }],
+                },
+            ],
+        }
+    }
+
+    fn basic_auth(username: &str, password: &str) -> String {
+        format!(
+            "Basic {}",
+            BASE64_STANDARD.encode(format!("{username}:{password}"))
+        )
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*", "logs"));
+        assert!(matches_pattern("*", "*"));
+        assert!(matches_pattern("logs-*", "logs-"));
+        assert!(matches_pattern("logs-*", "logs-app"));
+        assert!(matches_pattern("logs-*", "logs-app-*"));
+        assert!(matches_pattern("logs-*-prod", "logs-app-prod"));
+        assert!(matches_pattern("logs-*-prod", "logs-*-prod"));
+        assert!(matches_pattern("logs", "logs"));
+        assert!(!matches_pattern("logs", "logs-app"));
+        assert!(!matches_pattern("logs-*", "logs"));
+        assert!(!matches_pattern("logs-*", "*"));
+        assert!(!matches_pattern("logs-*", "log*"));
+        assert!(!matches_pattern("logs-*-prod", "logs-*"));
+    }
+
+    #[test]
+    fn test_required_permission() {
+        assert_eq!(required_permission(&Method::GET, "/health/livez"), None);
+        assert_eq!(
+            required_permission(&Method::GET, "/ui/search"),
+            Some(RequiredPermission::Authenticated)
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/version"),
+            Some(RequiredPermission::Authenticated)
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/logs-app,logs-db/search"),
+            Some(role_on(Role::Read, "logs-app,logs-db"))
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/logs-%2A/search"),
+            Some(role_on(Role::Read, "logs-*"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/logs-app/ingest"),
+            Some(role_on(Role::Ingest, "logs-app"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/otlp/v1/logs"),
+            Some(role_on(Role::Ingest, OTEL_LOGS_INDEX_ID))
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/indexes"),
+            Some(role_on(Role::Read, "*"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/indexes"),
+            Some(role_on(Role::Admin, "*"))
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/indexes/logs-app/describe"),
+            Some(role_on(Role::Read, "logs-app"))
+        );
+        assert_eq!(
+            required_permission(&Method::DELETE, "/api/v1/indexes/logs-app"),
+            Some(role_on(Role::Admin, "logs-app"))
+        );
+        assert_eq!(
+            required_permission(
+                &Method::PUT,
+                "/api/v1/indexes/logs-app/splits/mark-for-deletion"
+            ),
+            Some(role_on(Role::Admin, "logs-app"))
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/otel-traces-v0_7/jaeger/api/services"),
+            Some(role_on(Role::Read, "otel-traces-v0_7"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/_elastic/logs-app/_search"),
+            Some(role_on(Role::Read, "logs-app"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/_elastic/_msearch"),
+            Some(role_on(Role::Read, "*"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/_elastic/_search"),
+            Some(role_on(Role::Read, "*"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/_elastic/logs-app,logs-db/_pit"),
+            Some(role_on(Role::Read, "logs-app,logs-db"))
+        );
+        assert_eq!(
+            required_permission(&Method::DELETE, "/api/v1/_elastic/_pit"),
+            Some(RequiredPermission::Authenticated)
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/_elastic/logs-app/_bulk"),
+            Some(role_on(Role::Ingest, "*"))
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/_elastic/_cat/indices/logs-*"),
+            Some(role_on(Role::Read, "logs-*"))
+        );
+        assert_eq!(
+            required_permission(&Method::GET, "/api/v1/cluster"),
+            Some(role_on(Role::Admin, "*"))
+        );
+        assert_eq!(
+            required_permission(&Method::POST, "/api/v1/templates"),
+            Some(role_on(Role::Admin, "*"))
+        );
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let principals = principals_from_config(auth_config_for_test());
+
+        let principal = authenticate(&principals, "Bearer my-secret-key").unwrap();
+        assert_eq!(principal.name, "log-shipper");
+
+        let principal = authenticate(&principals, "bearer  my-secret-key ").unwrap();
+        assert_eq!(principal.name, "log-shipper");
+
+        let principal = authenticate(&principals, &basic_auth("alice", "my-password")).unwrap();
+        assert_eq!(principal.name, "alice");
+
+        assert!(authenticate(&principals, "Bearer my-other-key").is_none());
+        assert!(authenticate(&principals, "my-secret-key").is_none());
+        assert!(authenticate(&principals, &basic_auth("alice", "my-secret-key")).is_none());
+        assert!(authenticate(&principals, &basic_auth("bob", "my-password")).is_none());
+        assert!(authenticate(&principals, "Basic not-base64").is_none());
+    }
+
+    #[test]
+    fn test_principal_is_allowed() {
+        let principals = principals_from_config(auth_config_for_test());
+        let log_shipper = &principals[0];
+        let alice = &principals[1];
+        let admin = &principals[2];
+
+        assert!(log_shipper.is_allowed(&RequiredPermission::Authenticated));
+        assert!(log_shipper.is_allowed(&role_on(Role::Ingest, "logs-app")));
+        assert!(!log_shipper.is_allowed(&role_on(Role::Read, "logs-app")));
+        assert!(!log_shipper.is_allowed(&role_on(Role::Ingest, "traces")));
+
+        assert!(alice.is_allowed(&role_on(Role::Read, "logs-app,traces")));
+        assert!(alice.is_allowed(&role_on(Role::Read, "logs-app-*")));
+        assert!(alice.is_allowed(&role_on(Role::Read, "logs-*,-logs-secret")));
+        assert!(!alice.is_allowed(&role_on(Role::Read, "logs-app,metrics")));
+        assert!(!alice.is_allowed(&role_on(Role::Read, "*")));
+        assert!(!alice.is_allowed(&role_on(Role::Read, "_all")));
+        assert!(!alice.is_allowed(&role_on(Role::Admin, "logs-app")));
+
+        assert!(admin.is_allowed(&role_on(Role::Read, "*")));
+        assert!(admin.is_allowed(&role_on(Role::Ingest, "logs-app")));
+        assert!(admin.is_allowed(&role_on(Role::Admin, "_all")));
+    }
+
+    #[tokio::test]
+    async fn test_rest_auth_filter() {
+        let auth_filter = rest_auth_filter(Some(auth_config_for_test()));
+
+        warp::test::request()
+            .path("/health/livez")
+            .filter(&auth_filter)
+            .await
+            .unwrap();
+
+        let rejection = warp::test::request()
+            .path("/api/v1/logs-app/search")
+            .filter(&auth_filter)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<Unauthenticated>().is_some());
+
+        let rejection = warp::test::request()
+            .path("/api/v1/logs-app/search")
+            .header("authorization", "Bearer my-secret-key")
+            .filter(&auth_filter)
+            .await
+            .unwrap_err();
+        let forbidden = rejection.find::<Forbidden>().unwrap();
+        assert_eq!(
+            forbidden.to_string(),
+            "`log-shipper` is not allowed to perform this request"
+        );
+
+        warp::test::request()
+            .path("/api/v1/logs-app/search")
+            .header("authorization", basic_auth("alice", "my-password"))
+            .filter(&auth_filter)
+            .await
+            .unwrap();
+
+        let auth_filter = rest_auth_filter(None);
+        warp::test::request()
+            .method("DELETE")
+            .path("/api/v1/indexes/logs-app")
+            .filter(&auth_filter)
+            .await
+            .unwrap();
+    }
+}
@@ -25,7 +25,7 @@ use quickwit_proto::error::convert_to_grpc_result;
 use quickwit_proto::search::{
     search_service_server as grpc, GetKvRequest, GetKvResponse, LeafListFieldsRequest,
     LeafSearchStreamRequest, LeafSearchStreamResponse, ListFieldsRequest, ListFieldsResponse,
-    ReportSplitsRequest, ReportSplitsResponse,
+    ReportSplitsRequest, ReportSplitsResponse, SearchStreamRequest, SearchStreamResponse,
 };
 use quickwit_proto::{set_parent_span_from_request_metadata, tonic, ServiceError};
 use quickwit_search::SearchService;
@@ -98,6 +98,28 @@ impl grpc::SearchService for GrpcSearchAdapter {
         Ok(tonic::Response::new(Box::pin(leaf_search_result)))
     }
 
+    type RootSearchStreamStream = std::pin::Pin<
+        Box<dyn futures::Stream<Item = Result<SearchStreamResponse, tonic::Status>> + Send>,
+    >;
+    #[instrument(name = "search_adapter:root_search_stream", skip(self, request))]
+    async fn root_search_stream(
+        &self,
+        request: tonic::Request<SearchStreamRequest>,
+    ) -> Result<tonic::Response<Self::RootSearchStreamStream>, tonic::Status> {
+        set_parent_span_from_request_metadata(request.metadata());
+        let search_stream_request = request.into_inner();
+        let search_stream_result = self
+            .0
+            .root_search_stream(search_stream_request)
+            .await
+            .map_err(|err| err.grpc_error())?
+            .map_ok(|data| SearchStreamResponse {
+                data: data.to_vec(),
+            })
+            .map_err(|err| err.grpc_error());
+        Ok(tonic::Response::new(Box::pin(search_stream_result)))
+    }
+
     #[instrument(skip(self, request))]
     async fn root_list_terms(
         &self,
@@ -48,7 +48,7 @@ mod tests {
         create_search_client_from_grpc_addr, root_search_stream, ClusterClient, MockSearchService,
         SearchError, SearchJobPlacer, SearchService, SearcherPool,
     };
-    use tokio_stream::wrappers::UnboundedReceiverStream;
+    use tokio_stream::wrappers::ReceiverStream;
     use tonic::transport::Server;
 
     use crate::search_api::GrpcSearchAdapter;
@@ -80,6 +80,7 @@ mod tests {
             fast_field: "timestamp".to_string(),
             output_format: OutputFormat::Csv as i32,
             partition_by_field: None,
+            fields: Vec::new(),
         };
         let mut metastore = MetastoreServiceClient::mock();
         let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
@@ -100,18 +101,22 @@ mod tests {
             Ok(ServiceStream::from(vec![Ok(splits)]))
         });
         let mut mock_search_service = MockSearchService::new();
-        let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
-        result_sender.send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
-            data: b"123".to_vec(),
-            split_id: "split_1".to_string(),
-        }))?;
-        result_sender.send(Err(SearchError::Internal("Error on `split2`".to_string())))?;
+        let (result_sender, result_receiver) = tokio::sync::mpsc::channel(2);
+        result_sender
+            .send(Ok(quickwit_proto::search::LeafSearchStreamResponse {
+                data: b"123".to_vec(),
+                split_id: "split_1".to_string(),
+            }))
+            .await?;
+        result_sender
+            .send(Err(SearchError::Internal("Error on `split2`".to_string())))
+            .await?;
         mock_search_service
             .expect_leaf_search_stream()
             .withf(|request| request.split_offsets.len() == 2) // First request.
             .return_once(
                 |_leaf_search_req: quickwit_proto::search::LeafSearchStreamRequest| {
-                    Ok(UnboundedReceiverStream::new(result_receiver))
+                    Ok(ReceiverStream::new(result_receiver))
                 },
             );
         mock_search_service
@@ -25,7 +25,7 @@ use hyper::header::HeaderValue;
 use hyper::HeaderMap;
 use percent_encoding::percent_decode_str;
 use quickwit_config::validate_index_id_pattern;
-use quickwit_proto::search::{CountHits, OutputFormat, SortField, SortOrder};
+use quickwit_proto::search::{CountHits, OutputFormat, SortField, SortOrder, SourceFilter};
 use quickwit_proto::ServiceError;
 use quickwit_query::query_ast::query_ast_from_user_text;
 use quickwit_search::{SearchError, SearchResponseRest, SearchService};
@@ -198,6 +198,18 @@ pub struct SearchRequestQueryString {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(serialize_with = "to_simple_list")]
     pub snippet_fields: Option<Vec<String>>,
+    /// Paths of the document fields to return in the hits. Supports `*` wildcards.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub source_includes: Option<Vec<String>>,
+    /// Paths of the document fields to omit in the hits. Supports `*` wildcards.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(serialize_with = "to_simple_list")]
+    pub source_excludes: Option<Vec<String>>,
     /// If set, restrict search to documents with a `timestamp >= start_timestamp`.
     /// This timestamp is expressed in seconds.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -270,6 +282,16 @@ pub fn search_request_from_api_request(
     // the user of the docmapper default fields (which we do not have at this point).
     let query_ast = query_ast_from_user_text(&search_request.query, search_request.search_fields);
     let query_ast_json = serde_json::to_string(&query_ast)?;
+    let source_includes = search_request.source_includes.unwrap_or_default();
+    let source_excludes = search_request.source_excludes.unwrap_or_default();
+    let source_filter = if source_includes.is_empty() && source_excludes.is_empty() {
+        None
+    } else {
+        Some(SourceFilter {
+            includes: source_includes,
+            excludes: source_excludes,
+        })
+    };
     let search_request = quickwit_proto::search::SearchRequest {
         index_id_patterns,
         query_ast: query_ast_json,
@@ -285,6 +307,10 @@ pub fn search_request_from_api_request(
         scroll_ttl_secs: None,
         search_after: None,
         count_hits: search_request.count_all.into(),
+        snippet_options: None,
+        source_filter,
+        point_in_time_id: None,
+        point_in_time_keep_alive_secs: None,
     };
     Ok(search_request)
 }
@@ -421,8 +447,14 @@ struct SearchStreamRequestQueryString {
     /// If set, restricts search to documents with a `timestamp < end_timestamp``.
     pub end_timestamp: Option<i64>,
     /// The fast field to extract.
+    #[serde(default)]
     #[serde(deserialize_with = "deserialize_non_empty_string")]
     pub fast_field: String,
+    /// The fast or stored fields to extract, supported by the `arrow_ipc`, `parquet`, and
+    /// `ndjson` output formats only.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub fields: Option<Vec<String>>,
     /// The requested output format.
     #[serde(default)]
     pub output_format: OutputFormat,
@@ -446,6 +478,7 @@ async fn search_stream_endpoint(
         fast_field: search_request.fast_field,
         output_format: search_request.output_format as i32,
         partition_by_field: search_request.partition_by_field,
+        fields: search_request.fields.unwrap_or_default(),
     };
     let mut data = search_service.root_search_stream(request).await?;
     let (mut sender, body) = hyper::Body::channel();
@@ -506,6 +539,9 @@ async fn search_stream(
     let content_type = match request.output_format {
         OutputFormat::ClickHouseRowBinary => "application/octet-stream",
         OutputFormat::Csv => "text/csv",
+        OutputFormat::ArrowIpc => "application/vnd.apache.arrow.stream",
+        OutputFormat::Parquet => "application/vnd.apache.parquet",
+        OutputFormat::Ndjson => "application/x-ndjson",
     };
     let reply =
         make_streaming_reply(search_stream_endpoint(index_id, request, &*search_service).await);
@@ -1102,6 +1138,7 @@ mod tests {
                 start_timestamp: None,
                 end_timestamp: None,
                 fast_field: "external_id".to_string(),
+                fields: None,
                 output_format: OutputFormat::Csv,
                 partition_by_field: None,
             }
@@ -1128,12 +1165,37 @@ mod tests {
                 start_timestamp: None,
                 end_timestamp: None,
                 fast_field: "external_id".to_string(),
+                fields: None,
                 output_format: OutputFormat::ClickHouseRowBinary,
                 partition_by_field: None,
             }
         );
     }
 
+    #[tokio::test]
+    async fn test_rest_search_stream_api_parquet_with_fields() {
+        let (index, req) = warp::test::request()
+            .path("/my-index/search/stream?query=obama&fields=timestamp,body&output_format=parquet")
+            .filter(&super::search_stream_filter())
+            .await
+            .unwrap();
+        assert_eq!(&index, "my-index");
+        assert_eq!(
+            &req,
+            &super::SearchStreamRequestQueryString {
+                query: "obama".to_string(),
+                search_fields: None,
+                snippet_fields: None,
+                start_timestamp: None,
+                end_timestamp: None,
+                fast_field: String::new(),
+                fields: Some(vec!["timestamp".to_string(), "body".to_string()]),
+                output_format: OutputFormat::Parquet,
+                partition_by_field: None,
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_rest_search_stream_api_error() {
         let rejection = warp::test::request()
@@ -1147,7 +1209,8 @@ mod tests {
         let parse_error = rejection.find::<serde_qs::Error>().unwrap();
         assert_eq!(
             parse_error.to_string(),
-            "unknown variant `ClickHouseRowBinary`, expected `csv` or `click_house_row_binary`"
+            "unknown variant `ClickHouseRowBinary`, expected one of `csv`, \
+             `click_house_row_binary`, `arrow_ipc`, `parquet`, `ndjson`"
         );
     }
 
@@ -1204,6 +1267,32 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_rest_search_api_route_source_filter() {
+        let mut mock_search_service = MockSearchService::new();
+        mock_search_service
+            .expect_root_search()
+            .with(predicate::function(
+                |search_request: &quickwit_proto::search::SearchRequest| {
+                    search_request.source_filter
+                        == Some(SourceFilter {
+                            includes: vec!["actor.*".to_string(), "id".to_string()],
+                            excludes: vec!["actor.id".to_string()],
+                        })
+                },
+            ))
+            .returning(|_| Ok(Default::default()));
+        let rest_search_api_handler = search_handler(mock_search_service);
+        let resp = warp::test::request()
+            .path(
+                "/quickwit-demo-index/search?query=*&source_includes=actor.*,id&\
+                 source_excludes=actor.id",
+            )
+            .reply(&rest_search_api_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
     #[tokio::test]
     async fn test_rest_search_api_multi_indexes() {
         {
@@ -0,0 +1,230 @@
+// Copyright (C) 2024 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use futures::{future, stream, StreamExt};
+use hyper::server::accept::{self, Accept};
+use quickwit_config::TlsConfig;
+use quickwit_proto::tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::server::AllowAnyAuthenticatedClient;
+use tokio_rustls::rustls::{self, RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, warn};
+
+/// Maximum number of TLS handshakes performed concurrently by the REST server.
+const MAX_CONCURRENT_TLS_HANDSHAKES: usize = 64;
+
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Builds the rustls config of the REST server. Clients must present a certificate signed by the
+/// CA if `validate_client` is set.
+pub(crate) fn make_rustls_server_config(tls_config: &TlsConfig) -> anyhow::Result<ServerConfig> {
+    let certs = load_certs(&tls_config.cert_path)?;
+    let key = load_private_key(&tls_config.key_path)?;
+    let config_builder = ServerConfig::builder().with_safe_defaults();
+
+    let config_builder = if tls_config.validate_client {
+        let ca_path = tls_config
+            .ca_path
+            .as_ref()
+            .context("client certificate validation requires a CA certificate")?;
+        let mut root_cert_store = RootCertStore::empty();
+
+        for ca_cert in load_certs(ca_path)? {
+            root_cert_store
+                .add(&ca_cert)
+                .with_context(|| format!("invalid CA certificate in `{}`", ca_path.display()))?;
+        }
+        let client_cert_verifier = AllowAnyAuthenticatedClient::new(root_cert_store);
+        config_builder.with_client_cert_verifier(Arc::new(client_cert_verifier))
+    } else {
+        config_builder.with_no_client_auth()
+    };
+    let mut server_config = config_builder
+        .with_single_cert(certs, key)
+        .context("invalid TLS certificate or private key")?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+/// Builds the TLS config of the gRPC server. Clients must present a certificate signed by the CA
+/// if `validate_client` is set.
+pub(crate) fn make_grpc_server_tls_config(
+    tls_config: &TlsConfig,
+) -> anyhow::Result<ServerTlsConfig> {
+    let cert_pem = read_file(&tls_config.cert_path)?;
+    let key_pem = read_file(&tls_config.key_path)?;
+    let mut server_tls_config =
+        ServerTlsConfig::new().identity(Identity::from_pem(cert_pem, key_pem));
+
+    if tls_config.validate_client {
+        let ca_path = tls_config
+            .ca_path
+            .as_ref()
+            .context("client certificate validation requires a CA certificate")?;
+        let ca_pem = read_file(ca_path)?;
+        server_tls_config = server_tls_config.client_ca_root(Certificate::from_pem(ca_pem));
+    }
+    Ok(server_tls_config)
+}
+
+/// Accepts TCP connections on the listener and performs the TLS handshakes concurrently.
+/// Connections failing the handshake are dropped so that a misbehaving client cannot bring the
+/// server down.
+pub(crate) fn tls_incoming(
+    tcp_listener: TcpListener,
+    tls_acceptor: TlsAcceptor,
+) -> impl Accept<Conn = TlsStream<TcpStream>, Error = io::Error> {
+    let tcp_streams = stream::unfold(tcp_listener, |tcp_listener| async move {
+        loop {
+            match tcp_listener.accept().await {
+                Ok((tcp_stream, _peer_addr)) => return Some((tcp_stream, tcp_listener)),
+                Err(error) => {
+                    warn!(error=%error, "failed to accept TCP connection");
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                }
+            }
+        }
+    });
+    let tls_streams = tcp_streams
+        .map(move |tcp_stream| {
+            let tls_acceptor = tls_acceptor.clone();
+            async move {
+                let peer_addr_opt = tcp_stream.peer_addr().ok();
+                let handshake = tls_acceptor.accept(tcp_stream);
+
+                match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, handshake).await {
+                    Ok(Ok(tls_stream)) => Some(tls_stream),
+                    Ok(Err(error)) => {
+                        debug!(peer_addr=?peer_addr_opt, error=%error, "TLS handshake failed");
+                        None
+                    }
+                    Err(_) => {
+                        debug!(peer_addr=?peer_addr_opt, "TLS handshake timed out");
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_TLS_HANDSHAKES)
+        .filter_map(|tls_stream_opt| future::ready(tls_stream_opt.map(Ok::<_, io::Error>)));
+    accept::from_stream(tls_streams)
+}
+
+fn load_certs(path: &Path) -> anyhow::Result<Vec<rustls::Certificate>> {
+    let mut reader = open_file(path)?;
+    let certs = rustls_pemfile::certs(&mut reader)
+        .with_context(|| format!("failed to parse certificates in `{}`", path.display()))?;
+    if certs.is_empty() {
+        bail!("no certificate found in `{}`", path.display());
+    }
+    Ok(certs.into_iter().map(rustls::Certificate).collect())
+}
+
+fn load_private_key(path: &Path) -> anyhow::Result<rustls::PrivateKey> {
+    let mut reader = open_file(path)?;
+    let items = rustls_pemfile::read_all(&mut reader)
+        .with_context(|| format!("failed to parse private key in `{}`", path.display()))?;
+
+    for item in items {
+        match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(rustls::PrivateKey(key)),
+            _ => {}
+        }
+    }
+    bail!("no private key found in `{}`", path.display());
+}
+
+fn open_file(path: &Path) -> anyhow::Result<BufReader<File>> {
+    let file =
+        File::open(path).with_context(|| format!("failed to open file `{}`", path.display()))?;
+    Ok(BufReader::new(file))
+}
+
+fn read_file(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("failed to read file `{}`", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn tls_config_for_test(cert_path: PathBuf, key_path: PathBuf) -> TlsConfig {
+        TlsConfig {
+            cert_path,
+            key_path,
+            ca_path: None,
+            validate_client: false,
+            expected_server_name: None,
+        }
+    }
+
+    #[test]
+    fn test_make_rustls_server_config_missing_file() {
+        let tls_config = tls_config_for_test(
+            PathBuf::from("/does/not/exist.crt"),
+            PathBuf::from("/does/not/exist.key"),
+        );
+        let error = make_rustls_server_config(&tls_config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "failed to open file `/does/not/exist.crt`"
+        );
+    }
+
+    #[test]
+    fn test_make_rustls_server_config_invalid_pem() {
+        let mut cert_file = tempfile::NamedTempFile::new().unwrap();
+        cert_file.write_all(b"not a certificate").unwrap();
+        let tls_config = tls_config_for_test(
+            cert_file.path().to_path_buf(),
+            cert_file.path().to_path_buf(),
+        );
+        let error = make_rustls_server_config(&tls_config).unwrap_err();
+        assert!(error.to_string().starts_with("no certificate found in"));
+    }
+
+    #[test]
+    fn test_make_grpc_server_tls_config_requires_ca() {
+        let tls_config = TlsConfig {
+            validate_client: true,
+            ..tls_config_for_test(PathBuf::from("/dev/null"), PathBuf::from("/dev/null"))
+        };
+        let error = make_grpc_server_tls_config(&tls_config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "client certificate validation requires a CA certificate"
+        );
+    }
+}
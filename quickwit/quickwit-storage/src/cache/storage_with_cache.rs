@@ -28,7 +28,7 @@ use tokio::io::AsyncRead;
 
 use crate::cache::StorageCache;
 use crate::storage::SendableAsync;
-use crate::{BulkDeleteError, OwnedBytes, Storage, StorageResult};
+use crate::{BulkDeleteError, FileEntry, OwnedBytes, Storage, StorageResult};
 
 /// Use with care, StorageWithCache is read-only.
 pub struct StorageWithCache {
@@ -109,6 +109,16 @@ impl Storage for StorageWithCache {
         self.storage.file_num_bytes(path).await
     }
 
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        self.storage
+            .list_files_with_prefix(prefix, start_after_opt)
+            .await
+    }
+
     fn uri(&self) -> &Uri {
         self.storage.uri()
     }
@@ -32,7 +32,7 @@ use tantivy::directory::OwnedBytes;
 use tokio::io::AsyncRead;
 
 use crate::storage::SendableAsync;
-use crate::{BulkDeleteError, Storage, StorageResult};
+use crate::{BulkDeleteError, FileEntry, Storage, StorageResult};
 
 /// The AsyncDebouncer debounces inflight Futures, so that concurrent async request to the same data
 /// source can be deduplicated.
@@ -199,6 +199,16 @@ impl<T: Storage> Storage for DebouncedStorage<T> {
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
         self.underlying.file_num_bytes(path).await
     }
+
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        self.underlying
+            .list_files_with_prefix(prefix, start_after_opt)
+            .await
+    }
 }
 
 #[cfg(test)]
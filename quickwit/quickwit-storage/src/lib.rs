@@ -40,7 +40,7 @@ pub(crate) use debouncer::DebouncedStorage;
 
 pub use self::metrics::STORAGE_METRICS;
 pub use self::payload::PutPayload;
-pub use self::storage::Storage;
+pub use self::storage::{FileEntry, Storage};
 
 mod bundle_storage;
 mod error;
@@ -159,7 +159,7 @@ pub(crate) mod test_suite {
     use anyhow::Context;
     use tokio::io::AsyncReadExt;
 
-    use crate::{Storage, StorageErrorKind};
+    use crate::{FileEntry, Storage, StorageErrorKind};
 
     async fn test_get_inexistent_file(storage: &mut dyn Storage) -> anyhow::Result<()> {
         let err = storage
@@ -310,6 +310,52 @@ pub(crate) mod test_suite {
         Ok(())
     }
 
+    async fn test_list_files_with_prefix(storage: &mut dyn Storage) -> anyhow::Result<()> {
+        let test_paths = [
+            Path::new("list_files/foo/1.json"),
+            Path::new("list_files/foo/2.json"),
+            Path::new("list_files/foobar.json"),
+            Path::new("list_files_bar.json"),
+        ];
+        for test_path in test_paths {
+            storage.put(test_path, Box::new(b"123".to_vec())).await?;
+        }
+        let mut file_entries = storage
+            .list_files_with_prefix(Path::new("list_files/foo"), None)
+            .await?;
+        file_entries.sort_by(|left, right| left.path.cmp(&right.path));
+        let expected_file_entries: Vec<FileEntry> = test_paths[0..3]
+            .iter()
+            .map(|test_path| FileEntry {
+                path: test_path.to_path_buf(),
+                num_bytes: 3,
+            })
+            .collect();
+        assert_eq!(file_entries, expected_file_entries);
+
+        let file_entries = storage
+            .list_files_with_prefix(Path::new("list_files/foo/"), None)
+            .await?;
+        assert_eq!(file_entries.len(), 2);
+
+        let mut file_entries = storage
+            .list_files_with_prefix(
+                Path::new("list_files/foo"),
+                Some(Path::new("list_files/foo/1.json")),
+            )
+            .await?;
+        file_entries.sort_by(|left, right| left.path.cmp(&right.path));
+        assert_eq!(file_entries, expected_file_entries[1..]);
+
+        let file_entries = storage
+            .list_files_with_prefix(Path::new("list_files/missing"), None)
+            .await?;
+        assert!(file_entries.is_empty());
+
+        storage.bulk_delete(&test_paths).await?;
+        Ok(())
+    }
+
     /// Generic test suite for a storage.
     pub async fn storage_test_suite(storage: &mut dyn Storage) -> anyhow::Result<()> {
         test_get_inexistent_file(storage)
@@ -339,6 +385,9 @@ pub(crate) mod test_suite {
         test_delete_missing_file(storage)
             .await
             .context("delete_missing_file")?;
+        test_list_files_with_prefix(storage)
+            .await
+            .context("list_files_with_prefix")?;
         Ok(())
     }
 
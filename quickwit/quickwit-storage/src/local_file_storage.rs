@@ -33,9 +33,9 @@ use quickwit_config::StorageBackend;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tracing::warn;
 
-use crate::storage::SendableAsync;
+use crate::storage::{is_listed_after, SendableAsync};
 use crate::{
-    BulkDeleteError, DebouncedStorage, DeleteFailure, OwnedBytes, Storage, StorageError,
+    BulkDeleteError, DebouncedStorage, DeleteFailure, FileEntry, OwnedBytes, Storage, StorageError,
     StorageErrorKind, StorageFactory, StorageResolverError, StorageResult,
 };
 
@@ -344,6 +344,54 @@ impl Storage for LocalFileStorage {
             }
         }
     }
+
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        ensure_valid_relative_path(prefix)?;
+        let prefix_str = prefix.to_string_lossy();
+        // The prefix is matched as a string, so we start walking from the deepest directory that
+        // is fully spelled out in the prefix.
+        let start_dir = if prefix_str.is_empty() || prefix_str.ends_with('/') {
+            prefix.to_path_buf()
+        } else {
+            prefix.parent().map(Path::to_path_buf).unwrap_or_default()
+        };
+        let mut file_entries = Vec::new();
+        let mut dirs_to_visit = vec![start_dir];
+
+        while let Some(dir) = dirs_to_visit.pop() {
+            let mut read_dir = match tokio::fs::read_dir(self.root.join(&dir)).await {
+                Ok(read_dir) => read_dir,
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            while let Some(dir_entry) = read_dir.next_entry().await? {
+                let path = dir.join(dir_entry.file_name());
+                let path_str = path.to_string_lossy();
+                let file_type = dir_entry.file_type().await?;
+
+                if file_type.is_dir() {
+                    if format!("{path_str}/").starts_with(prefix_str.as_ref()) {
+                        dirs_to_visit.push(path);
+                    }
+                } else if file_type.is_file()
+                    && path_str.starts_with(prefix_str.as_ref())
+                    && is_listed_after(&path, start_after_opt)
+                {
+                    // Skip the temporary files created by in-flight `put` calls.
+                    if dir_entry.file_name().to_string_lossy().starts_with(".tmp") {
+                        continue;
+                    }
+                    let num_bytes = dir_entry.metadata().await?.len();
+                    file_entries.push(FileEntry { path, num_bytes });
+                }
+            }
+        }
+        Ok(file_entries)
+    }
 }
 
 /// A File storage resolver
@@ -49,9 +49,9 @@ use tokio_util::io::StreamReader;
 use tracing::{instrument, warn};
 
 use crate::debouncer::DebouncedStorage;
-use crate::storage::SendableAsync;
+use crate::storage::{is_listed_after, SendableAsync};
 use crate::{
-    BulkDeleteError, DeleteFailure, MultiPartPolicy, PutPayload, Storage, StorageError,
+    BulkDeleteError, DeleteFailure, FileEntry, MultiPartPolicy, PutPayload, Storage, StorageError,
     StorageErrorKind, StorageFactory, StorageResolverError, StorageResult, STORAGE_METRICS,
 };
 
@@ -499,6 +499,40 @@ impl Storage for AzureBlobStorage {
         }
     }
 
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let name_prefix = self.blob_name(prefix);
+        // The Blob Storage API cannot start the listing after a given name, so the whole prefix is
+        // listed and the blobs sorting before `start_after_opt` are filtered out.
+        let mut file_entries = Vec::new();
+        let mut response_stream = self
+            .container_client
+            .list_blobs()
+            .prefix(name_prefix)
+            .into_stream();
+
+        while let Some(response_result) = response_stream.next().await {
+            let response = response_result.map_err(AzureErrorWrapper::from)?;
+
+            for blob in response.blobs.blobs() {
+                let Ok(path) = Path::new(&blob.name).strip_prefix(&self.prefix) else {
+                    continue;
+                };
+                if !is_listed_after(path, start_after_opt) {
+                    continue;
+                }
+                file_entries.push(FileEntry {
+                    path: path.to_path_buf(),
+                    num_bytes: blob.properties.content_length,
+                });
+            }
+        }
+        Ok(file_entries)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
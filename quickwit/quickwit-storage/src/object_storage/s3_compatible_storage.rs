@@ -50,7 +50,7 @@ use tracing::{info, instrument, warn};
 use crate::object_storage::MultiPartPolicy;
 use crate::storage::SendableAsync;
 use crate::{
-    BulkDeleteError, DeleteFailure, OwnedBytes, Storage, StorageError, StorageErrorKind,
+    BulkDeleteError, DeleteFailure, FileEntry, OwnedBytes, Storage, StorageError, StorageErrorKind,
     StorageResolverError, StorageResult, STORAGE_METRICS,
 };
 
@@ -820,6 +820,50 @@ impl Storage for S3CompatibleObjectStorage {
         Ok(head_object_output.content_length() as u64)
     }
 
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let bucket = self.bucket.clone();
+        let key_prefix = self.key(prefix);
+        let start_after_key_opt = start_after_opt.map(|start_after| self.key(start_after));
+        let mut file_entries = Vec::new();
+        let mut continuation_token_opt: Option<String> = None;
+
+        loop {
+            let _permit = REQUEST_SEMAPHORE.acquire().await;
+            let list_objects_output = aws_retry(&self.retry_params, || async {
+                self.s3_client
+                    .list_objects_v2()
+                    .bucket(&bucket)
+                    .prefix(&key_prefix)
+                    .set_start_after(start_after_key_opt.clone())
+                    .set_continuation_token(continuation_token_opt.clone())
+                    .send()
+                    .await
+            })
+            .await?;
+
+            for object in list_objects_output.contents().unwrap_or_default() {
+                if let Some(key) = object.key() {
+                    file_entries.push(FileEntry {
+                        path: self.relative_path(key),
+                        num_bytes: object.size() as u64,
+                    });
+                }
+            }
+            continuation_token_opt = list_objects_output
+                .next_continuation_token()
+                .map(ToString::to_string);
+
+            if continuation_token_opt.is_none() {
+                break;
+            }
+        }
+        Ok(file_entries)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
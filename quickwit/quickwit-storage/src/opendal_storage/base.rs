@@ -19,17 +19,17 @@
 
 use std::fmt;
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 use bytesize::ByteSize;
-use opendal::Operator;
+use opendal::{Metakey, Operator};
 use quickwit_common::uri::Uri;
 use tokio::io::{AsyncRead, AsyncWriteExt};
 
-use crate::storage::SendableAsync;
+use crate::storage::{is_listed_after, SendableAsync};
 use crate::{
-    BulkDeleteError, OwnedBytes, PutPayload, Storage, StorageError, StorageErrorKind,
+    BulkDeleteError, FileEntry, OwnedBytes, PutPayload, Storage, StorageError, StorageErrorKind,
     StorageResolverError, StorageResult,
 };
 
@@ -194,6 +194,47 @@ impl Storage for OpendalStorage {
         Ok(meta.content_length())
     }
 
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let prefix = prefix.as_os_str().to_string_lossy();
+        // OpenDAL lists directories, so we list the parent directory of the prefix and filter
+        // the entries ourselves.
+        let list_dir = match prefix.rfind('/') {
+            Some(separator_pos) => &prefix[..=separator_pos],
+            None => "",
+        };
+        let mut list = self
+            .op
+            .list_with(list_dir)
+            .recursive(true)
+            .metakey(Metakey::Mode | Metakey::ContentLength);
+
+        // Not all the services can start the listing after a given path, so the entries are
+        // filtered below in any case.
+        if let Some(start_after) = start_after_opt {
+            if self.op.info().full_capability().list_with_start_after {
+                list = list.start_after(&start_after.to_string_lossy());
+            }
+        }
+        let entries = list.await?;
+        let file_entries = entries
+            .into_iter()
+            .filter(|entry| {
+                entry.metadata().is_file()
+                    && entry.path().starts_with(prefix.as_ref())
+                    && is_listed_after(Path::new(entry.path()), start_after_opt)
+            })
+            .map(|entry| FileEntry {
+                path: PathBuf::from(entry.path()),
+                num_bytes: entry.metadata().content_length(),
+            })
+            .collect();
+        Ok(file_entries)
+    }
+
     fn uri(&self) -> &Uri {
         &self.uri
     }
@@ -27,7 +27,7 @@ use quickwit_common::uri::Uri;
 use tokio::io::AsyncRead;
 
 use crate::storage::SendableAsync;
-use crate::{BulkDeleteError, OwnedBytes, Storage};
+use crate::{BulkDeleteError, FileEntry, OwnedBytes, Storage};
 
 /// This storage acts as a proxy to another storage that simply modifies each API call
 /// by preceding each path with a given a prefix.
@@ -119,6 +119,32 @@ impl Storage for PrefixStorage {
     async fn file_num_bytes(&self, path: &Path) -> crate::StorageResult<u64> {
         self.storage.file_num_bytes(&self.prefix.join(path)).await
     }
+
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> crate::StorageResult<Vec<FileEntry>> {
+        let start_after_opt = start_after_opt.map(|start_after| self.prefix.join(start_after));
+        let file_entries = self
+            .storage
+            .list_files_with_prefix(&self.prefix.join(prefix), start_after_opt.as_deref())
+            .await?
+            .into_iter()
+            .filter_map(|file_entry| {
+                let path = file_entry
+                    .path
+                    .strip_prefix(&self.prefix)
+                    .ok()?
+                    .to_path_buf();
+                Some(FileEntry {
+                    path,
+                    num_bytes: file_entry.num_bytes,
+                })
+            })
+            .collect();
+        Ok(file_entries)
+    }
 }
 
 /// Creates a [`PrefixStorage`] using an underlying storage and a prefix.
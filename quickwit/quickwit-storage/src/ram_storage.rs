@@ -31,10 +31,10 @@ use tokio::io::{AsyncRead, AsyncWriteExt};
 use tokio::sync::RwLock;
 
 use crate::prefix_storage::add_prefix_to_storage;
-use crate::storage::SendableAsync;
+use crate::storage::{is_listed_after, SendableAsync};
 use crate::{
-    BulkDeleteError, OwnedBytes, Storage, StorageErrorKind, StorageFactory, StorageResolverError,
-    StorageResult,
+    BulkDeleteError, FileEntry, OwnedBytes, Storage, StorageErrorKind, StorageFactory,
+    StorageResolverError, StorageResult,
 };
 
 /// In Ram implementation of quickwit's storage.
@@ -160,6 +160,29 @@ impl Storage for RamStorage {
             Err(StorageErrorKind::NotFound.with_error(err))
         }
     }
+
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        let prefix = prefix.to_string_lossy();
+        let file_entries = self
+            .files
+            .read()
+            .await
+            .iter()
+            .filter(|(path, _)| {
+                path.to_string_lossy().starts_with(prefix.as_ref())
+                    && is_listed_after(path, start_after_opt)
+            })
+            .map(|(path, file_bytes)| FileEntry {
+                path: path.clone(),
+                num_bytes: file_bytes.len() as u64,
+            })
+            .collect();
+        Ok(file_entries)
+    }
 }
 
 /// Builder to create a prepopulated [`RamStorage`]. This is mostly useful for tests.
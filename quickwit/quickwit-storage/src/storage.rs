@@ -31,6 +31,25 @@ use tracing::error;
 
 use crate::{BulkDeleteError, OwnedBytes, PutPayload, StorageErrorKind, StorageResult};
 
+/// A file returned by [`Storage::list_files_with_prefix`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileEntry {
+    /// Path of the file, relative to the storage root.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub num_bytes: u64,
+}
+
+/// Returns whether `path` is listed by [`Storage::list_files_with_prefix`] when it is called
+/// with `start_after_opt`, for the storage implementations that filter the listed files
+/// themselves.
+pub(crate) fn is_listed_after(path: &Path, start_after_opt: Option<&Path>) -> bool {
+    let Some(start_after) = start_after_opt else {
+        return true;
+    };
+    path.to_string_lossy() > start_after.to_string_lossy()
+}
+
 /// This trait is only used to make it build trait object with `AsyncWrite + Send + Unpin`.
 pub trait SendableAsync: AsyncWrite + Send + Unpin {}
 impl<W: AsyncWrite + Send + Unpin> SendableAsync for W {}
@@ -138,6 +157,26 @@ pub trait Storage: fmt::Debug + Send + Sync + 'static {
     /// Returns a file size.
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64>;
 
+    /// Lists the files whose path starts with `prefix` and, if `start_after_opt` is set, is
+    /// greater than `start_after_opt`.
+    ///
+    /// As with object storage, `prefix` is matched as a plain string prefix and not as a
+    /// directory: `logs/2024` matches both `logs/2024/a.json` and `logs/2024-01.json`. Paths
+    /// are compared to `start_after_opt` as strings, byte by byte.
+    /// The order of the returned entries is unspecified.
+    async fn list_files_with_prefix(
+        &self,
+        prefix: &Path,
+        start_after_opt: Option<&Path>,
+    ) -> StorageResult<Vec<FileEntry>> {
+        Err(StorageErrorKind::Service.with_error(anyhow::anyhow!(
+            "listing files is not supported by storage `{}` (prefix `{}`, start after `{:?}`)",
+            self.uri(),
+            prefix.display(),
+            start_after_opt
+        )))
+    }
+
     /// Returns an URI identifying the storage
     fn uri(&self) -> &Uri;
 }